@@ -0,0 +1,647 @@
+//! A generic bloom post-process, which blurs the bright pixels of a HDR scene and adds them back before tone mapping.
+//!
+//! The bloom is rendered into a mip chain starting from half resolution by fullscreen passes:
+//! the bright-pass writes the scene to level 0, and each following level is downsampled from the previous level.
+//! Each level is blurred horizontally(ping -> pong) and vertically(pong -> ping), so that the lower levels give wider blur.
+//! Then all the levels are added to the scene in the output render pass.
+
+use ash::vk;
+
+use crate::ci::vma::{VmaImage, VmaAllocationCI};
+use crate::ci::image::{ImageCI, ImageViewCI, SamplerCI};
+use crate::ci::pipeline::{RenderPassCI, RenderPassBI, AttachmentDescCI, SubpassDescCI, SubpassDependencyCI, FramebufferCI, PipelineLayout, RenderPass};
+use crate::ci::VkObjectBuildableCI;
+
+use crate::command::{VkCmdRecorder, IGraphics, CmdGraphicsApi};
+use crate::context::VkDevice;
+
+use crate::{VkResult, VkErrorKind};
+use crate::{vkuint, vkfloat};
+
+
+/// The maximum radius of blur kernel. Keep it the same as `MAX_BLUR_RADIUS` in blur.frag.glsl.
+const MAX_BLUR_RADIUS: usize = 8;
+/// The format of intermediate bloom images, which keeps the HDR range of bright pixels.
+const BLOOM_FORMAT: vk::Format = vk::Format::R16G16B16A16_SFLOAT;
+
+/// The configuration of a `BloomPass`.
+#[derive(Debug, Clone)]
+pub struct BloomConfig {
+
+    /// `threshold` is the brightness(the maximum of RGB components) above which the pixels contribute to bloom.
+    pub threshold: vkfloat,
+    /// `intensity` is the factor multiplied to the blurred bright pixels when adding them back to scene.
+    pub intensity: vkfloat,
+    /// `blur_radius` is the count of texels sampled on each side of Gaussian kernel in each mip level. It's clamped to 8.
+    pub blur_radius: usize,
+    /// `mip_levels` is the count of bloom mip levels, which is clamped to the full mip chain of half resolution.
+    ///
+    /// It's fixed when the `BloomPass` is created.
+    pub mip_levels: usize,
+    /// `exposure` is used in the tone mapping after composition.
+    pub exposure: vkfloat,
+}
+
+impl Default for BloomConfig {
+
+    fn default() -> BloomConfig {
+        BloomConfig {
+            threshold: 1.0,
+            intensity: 0.6,
+            blur_radius: 4,
+            mip_levels: 5,
+            exposure: 1.0,
+        }
+    }
+}
+
+/// The push constants of blur.frag.glsl, which is also used by bright.frag.glsl(only `texel_step[0]` as threshold).
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct BlurParams {
+    texel_step: [vkfloat; 2],
+    radius: i32,
+    weights: [vkfloat; MAX_BLUR_RADIUS + 1],
+}
+
+impl BlurParams {
+
+    fn new(texel_step: [vkfloat; 2], weights: &[vkfloat]) -> BlurParams {
+
+        let mut params = BlurParams {
+            texel_step,
+            radius: (weights.len() - 1) as i32,
+            weights: [0.0; MAX_BLUR_RADIUS + 1],
+        };
+        params.weights[..weights.len()].copy_from_slice(weights);
+        params
+    }
+
+    /// The push constants of bright.frag.glsl, whose `threshold` is the first float of `BlurParams`.
+    fn bright(threshold: vkfloat) -> BlurParams {
+        BlurParams::new([threshold, 0.0], &[1.0])
+    }
+}
+
+/// The push constants of composite.frag.glsl.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct CompositeParams {
+    intensity: vkfloat,
+    exposure : vkfloat,
+    level_count: i32,
+}
+
+/// Generate the one-sided weights of a normalized Gaussian kernel with `radius`.
+///
+/// The length of result is `radius + 1`, where `weights[0]` is the center texel.
+/// Since the other weights are applied to both sides, `weights[0] + 2 * sum(weights[1..])` equals to one.
+pub fn gaussian_weights(radius: usize) -> Vec<vkfloat> {
+
+    let radius = radius.min(MAX_BLUR_RADIUS);
+    let sigma = (radius as vkfloat * 0.5).max(0.5);
+
+    let mut weights: Vec<vkfloat> = (0..=radius)
+        .map(|i| (-((i * i) as vkfloat) / (2.0 * sigma * sigma)).exp())
+        .collect();
+
+    let total = weights[0] + weights[1..].iter().sum::<vkfloat>() * 2.0;
+    weights.iter_mut().for_each(|w| *w /= total);
+
+    weights
+}
+
+/// Get the count of bloom mip levels for the level 0 of `dimension`, which is `requested` clamped to the full mip chain.
+fn level_count(dimension: vk::Extent2D, requested: usize) -> usize {
+
+    let full_chain = 32 - dimension.width.max(dimension.height).max(1).leading_zeros();
+    requested.min(full_chain as usize).max(1)
+}
+
+/// Get the dimension of mip `level`, where level 0 is `dimension`.
+fn level_dimension(dimension: vk::Extent2D, level: usize) -> vk::Extent2D {
+    vk::Extent2D {
+        width : (dimension.width  >> level).max(1),
+        height: (dimension.height >> level).max(1),
+    }
+}
+
+/// A mip level of bloom image, which is rendered separately.
+struct BloomLevel {
+    view: vk::ImageView,
+    framebuffer: vk::Framebuffer,
+    dimension: vk::Extent2D,
+}
+
+/// The color target used by bloom, which contains a mip chain starting from half resolution.
+struct BloomTarget {
+    image: VmaImage,
+    /// the view of all levels, which is sampled in composition.
+    view : vk::ImageView,
+    levels: Vec<BloomLevel>,
+}
+
+/// Render the bloom of a HDR scene image.
+///
+/// The scene image must be in `vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL` when the commands of this pass are executed.
+pub struct BloomPass {
+
+    config: BloomConfig,
+
+    ping: BloomTarget,
+    pong: BloomTarget,
+    sampler: vk::Sampler,
+    render_pass: RenderPass,
+
+    descriptor_pool: vk::DescriptorPool,
+    /// the descriptor set sampling scene in bright-pass.
+    bright_set: vk::DescriptorSet,
+    /// the descriptor sets sampling ping and pong image of each mip level respectively.
+    level_sets: Vec<[vk::DescriptorSet; 2]>,
+    blur_set_layout: vk::DescriptorSetLayout,
+    /// the descriptor set sampling scene and all the levels of final bloom image.
+    composite_set: vk::DescriptorSet,
+    composite_set_layout: vk::DescriptorSetLayout,
+
+    bright_pipeline: vk::Pipeline,
+    blur_pipeline: vk::Pipeline,
+    blur_layout: PipelineLayout,
+    composite_pipeline: vk::Pipeline,
+    composite_layout: PipelineLayout,
+}
+
+impl BloomPass {
+
+    /// Create the bloom pass for `scene` image, whose dimension is `scene_dimension`.
+    ///
+    /// `output_render_pass` is the render pass in which `BloomPass::record_composite` is recorded(usually the swapchain pass).
+    pub fn new(device: &mut VkDevice, scene: vk::DescriptorImageInfo, scene_dimension: vk::Extent2D, output_render_pass: vk::RenderPass, config: BloomConfig) -> VkResult<BloomPass> {
+
+        let dimension = level_dimension(scene_dimension, 1);
+        let level_count = level_count(dimension, config.mip_levels);
+        let config = BloomConfig { mip_levels: level_count, ..config };
+
+        let render_pass = setup_renderpass(device)?;
+        let ping = setup_target(device, dimension, level_count, render_pass.handle)?;
+        let pong = setup_target(device, dimension, level_count, render_pass.handle)?;
+
+        // the levels are selected exactly by integer lod in composition.
+        let sampler = SamplerCI::new()
+            .filter(vk::Filter::LINEAR, vk::Filter::LINEAR)
+            .mipmap(vk::SamplerMipmapMode::NEAREST)
+            .address(vk::SamplerAddressMode::CLAMP_TO_EDGE, vk::SamplerAddressMode::CLAMP_TO_EDGE, vk::SamplerAddressMode::CLAMP_TO_EDGE)
+            .lod(0.0, 0.0, level_count as vkfloat)
+            .build(device)?;
+
+        let descriptors = setup_descriptor(device, scene, sampler, &ping, &pong)?;
+        let pipelines = prepare_pipelines(device, render_pass.handle, output_render_pass, &descriptors)?;
+
+        let result = BloomPass {
+            config, ping, pong, sampler, render_pass,
+            descriptor_pool: descriptors.pool,
+            bright_set: descriptors.bright_set,
+            level_sets: descriptors.level_sets,
+            blur_set_layout: descriptors.blur_set_layout,
+            composite_set: descriptors.composite_set,
+            composite_set_layout: descriptors.composite_set_layout,
+            bright_pipeline: pipelines.bright,
+            blur_pipeline: pipelines.blur,
+            blur_layout: pipelines.blur_layout,
+            composite_pipeline: pipelines.composite,
+            composite_layout: pipelines.composite_layout,
+        };
+        Ok(result)
+    }
+
+    /// Record the bright-pass, downsampling and blur passes.
+    ///
+    /// This must be recorded outside any render pass, after the scene has been rendered and before `BloomPass::record_composite`.
+    pub fn record_command(&self, recorder: &VkCmdRecorder<IGraphics>) {
+
+        let weights = gaussian_weights(self.config.blur_radius);
+
+        // bright-pass: scene -> level 0 of ping.
+        let bright_params = BlurParams::bright(self.config.threshold);
+        self.record_fullscreen(recorder, &self.ping.levels[0], self.bright_pipeline, self.bright_set, &bright_params);
+
+        for (level, level_sets) in self.level_sets.iter().enumerate() {
+
+            let dimension = self.ping.levels[level].dimension;
+
+            if level > 0 {
+                // downsample: the previous level of ping -> this level of ping, averaging 2x2 texels by linear filter.
+                let downsample_params = BlurParams::new([0.0, 0.0], &[1.0]);
+                self.record_fullscreen(recorder, &self.ping.levels[level], self.blur_pipeline, self.level_sets[level - 1][0], &downsample_params);
+            }
+
+            // horizontal blur: ping -> pong.
+            let horizontal_params = BlurParams::new([1.0 / dimension.width as vkfloat, 0.0], &weights);
+            self.record_fullscreen(recorder, &self.pong.levels[level], self.blur_pipeline, level_sets[0], &horizontal_params);
+
+            // vertical blur: pong -> ping.
+            let vertical_params = BlurParams::new([0.0, 1.0 / dimension.height as vkfloat], &weights);
+            self.record_fullscreen(recorder, &self.ping.levels[level], self.blur_pipeline, level_sets[1], &vertical_params);
+        }
+    }
+
+    /// Record the fullscreen draw adding the bloom to scene, followed by tone mapping.
+    ///
+    /// This must be recorded inside `output_render_pass`, and viewport and scissor must be set before it.
+    pub fn record_composite(&self, recorder: &VkCmdRecorder<IGraphics>) {
+
+        let composite_params = CompositeParams {
+            intensity: self.config.intensity,
+            exposure : self.config.exposure,
+            level_count: self.level_sets.len() as i32,
+        };
+
+        recorder
+            .bind_pipeline(self.composite_pipeline)
+            .bind_descriptor_sets(self.composite_layout.handle, 0, &[self.composite_set], &[])
+            .push_constant_data(&self.composite_layout, vk::ShaderStageFlags::FRAGMENT, 0, &composite_params)
+            .draw(3, 1, 0, 0);
+    }
+
+    fn record_fullscreen(&self, recorder: &VkCmdRecorder<IGraphics>, target: &BloomLevel, pipeline: vk::Pipeline, set: vk::DescriptorSet, params: &BlurParams) {
+
+        let viewport = vk::Viewport {
+            x: 0.0, y: 0.0,
+            width: target.dimension.width as f32, height: target.dimension.height as f32,
+            min_depth: 0.0, max_depth: 1.0,
+        };
+        let scissor = vk::Rect2D {
+            extent: target.dimension,
+            offset: vk::Offset2D { x: 0, y: 0 },
+        };
+
+        let render_pass_bi = RenderPassBI::with_pass(self.render_pass, target.framebuffer)
+            .render_extent(target.dimension);
+
+        recorder
+            .begin_render_pass(render_pass_bi)
+            .set_viewport(0, &[viewport])
+            .set_scissor(0, &[scissor])
+            .bind_pipeline(pipeline)
+            .bind_descriptor_sets(self.blur_layout.handle, 0, &[set], &[])
+            .push_constant_data(&self.blur_layout, vk::ShaderStageFlags::FRAGMENT, 0, params)
+            .draw(3, 1, 0, 0)
+            .end_render_pass();
+    }
+
+    /// Change the configuration, which takes effect after the command buffers are recorded again.
+    ///
+    /// `BloomConfig::mip_levels` is ignored, since the mip levels are fixed when the pass is created.
+    #[inline]
+    pub fn set_config(&mut self, config: BloomConfig) {
+        self.config = BloomConfig { mip_levels: self.config.mip_levels, ..config };
+    }
+
+    #[inline]
+    pub fn config(&self) -> &BloomConfig {
+        &self.config
+    }
+
+    pub fn discard_by(self, device: &mut VkDevice) -> VkResult<()> {
+
+        device.discard(self.bright_pipeline);
+        device.discard(self.blur_pipeline);
+        self.blur_layout.discard_by(device);
+        device.discard(self.composite_pipeline);
+        self.composite_layout.discard_by(device);
+
+        device.discard(self.blur_set_layout);
+        device.discard(self.composite_set_layout);
+        device.discard(self.descriptor_pool);
+
+        device.discard(self.sampler);
+        device.discard(self.render_pass);
+
+        for target in vec![self.ping, self.pong] {
+            for level in target.levels {
+                device.discard(level.framebuffer);
+                device.discard(level.view);
+            }
+            device.discard(target.view);
+            device.vma_discard(target.image)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn setup_renderpass(device: &VkDevice) -> VkResult<RenderPass> {
+
+    // each pass overwrites the whole image, and the result will be sampled by the next pass.
+    let color_attachment = AttachmentDescCI::new(BLOOM_FORMAT)
+        .op(vk::AttachmentLoadOp::DONT_CARE, vk::AttachmentStoreOp::STORE)
+        .layout(vk::ImageLayout::UNDEFINED, vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL);
+
+    let subpass_description = SubpassDescCI::new(vk::PipelineBindPoint::GRAPHICS)
+        .add_color_attachment(0, vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL); // Attachment 0 is color.
+
+    // make sure the previous pass has finished sampling before writing the image.
+    let dependency0 = SubpassDependencyCI::new(vk::SUBPASS_EXTERNAL, 0)
+        .stage_mask(vk::PipelineStageFlags::FRAGMENT_SHADER, vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT)
+        .access_mask(vk::AccessFlags::SHADER_READ, vk::AccessFlags::COLOR_ATTACHMENT_WRITE)
+        .flags(vk::DependencyFlags::BY_REGION);
+
+    // make sure the color writes have finished before sampling the image.
+    let dependency1 = SubpassDependencyCI::new(0, vk::SUBPASS_EXTERNAL)
+        .stage_mask(vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT, vk::PipelineStageFlags::FRAGMENT_SHADER)
+        .access_mask(vk::AccessFlags::COLOR_ATTACHMENT_WRITE, vk::AccessFlags::SHADER_READ)
+        .flags(vk::DependencyFlags::BY_REGION);
+
+    let render_pass = RenderPassCI::new()
+        .add_attachment(color_attachment)
+        .add_subpass(subpass_description)
+        .add_dependency(dependency0)
+        .add_dependency(dependency1)
+        .build_with_attachments(device)?;
+
+    Ok(render_pass)
+}
+
+fn setup_target(device: &mut VkDevice, dimension: vk::Extent2D, level_count: usize, render_pass: vk::RenderPass) -> VkResult<BloomTarget> {
+
+    let image = {
+        let color_ci = ImageCI::new_2d(BLOOM_FORMAT, dimension)
+            .mip_levels(level_count as vkuint)
+            .usages(vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::SAMPLED);
+        let allocation_ci = VmaAllocationCI::new(vma::MemoryUsage::GpuOnly, vk::MemoryPropertyFlags::DEVICE_LOCAL);
+        let color_allocation = device.vma.create_image(color_ci.as_ref(), allocation_ci.as_ref())
+            .map_err(VkErrorKind::Vma)?;
+        VmaImage::from(color_allocation)
+    };
+
+    let sub_range = |base_mip_level: usize, level_count: usize| vk::ImageSubresourceRange {
+        aspect_mask: vk::ImageAspectFlags::COLOR,
+        base_mip_level: base_mip_level as vkuint,
+        level_count   : level_count as vkuint,
+        base_array_layer: 0,
+        layer_count     : 1,
+    };
+
+    let view = ImageViewCI::new(image.handle, vk::ImageViewType::TYPE_2D, BLOOM_FORMAT)
+        .sub_range(sub_range(0, level_count))
+        .build(device)?;
+
+    let mut levels = Vec::with_capacity(level_count);
+    for level in 0..level_count {
+
+        let level_view = ImageViewCI::new(image.handle, vk::ImageViewType::TYPE_2D, BLOOM_FORMAT)
+            .sub_range(sub_range(level, 1))
+            .build(device)?;
+
+        let dimension = level_dimension(dimension, level);
+        let framebuffer = FramebufferCI::new_2d(render_pass, dimension)
+            .add_attachment(level_view)
+            .build(device)?;
+
+        levels.push(BloomLevel { view: level_view, framebuffer, dimension });
+    }
+
+    let result = BloomTarget { image, view, levels };
+    Ok(result)
+}
+
+struct BloomDescriptors {
+    pool: vk::DescriptorPool,
+    bright_set: vk::DescriptorSet,
+    level_sets: Vec<[vk::DescriptorSet; 2]>,
+    blur_set_layout: vk::DescriptorSetLayout,
+    composite_set: vk::DescriptorSet,
+    composite_set_layout: vk::DescriptorSetLayout,
+}
+
+fn setup_descriptor(device: &VkDevice, scene: vk::DescriptorImageInfo, sampler: vk::Sampler, ping: &BloomTarget, pong: &BloomTarget) -> VkResult<BloomDescriptors> {
+
+    use crate::ci::descriptor::{DescriptorPoolCI, DescriptorSetLayoutCI};
+    use crate::ci::descriptor::{DescriptorSetAI, DescriptorImageSetWI, DescriptorSetsUpdateCI};
+
+    let level_count = ping.levels.len();
+
+    // Descriptor Pool.
+    // the sets are: bright-pass, ping and pong of each level, and composition.
+    let pool = DescriptorPoolCI::new((level_count * 2 + 2) as vkuint)
+        .add_descriptor(vk::DescriptorType::COMBINED_IMAGE_SAMPLER, (level_count * 2 + 3) as vkuint)
+        .build(device)?;
+
+    let sampler_binding = |binding: vkuint| vk::DescriptorSetLayoutBinding {
+        binding,
+        descriptor_type: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+        descriptor_count: 1,
+        stage_flags: vk::ShaderStageFlags::FRAGMENT,
+        p_immutable_samplers: ::std::ptr::null(),
+    };
+
+    // layout (binding = 0) uniform sampler2D inputColor;
+    let blur_set_layout = DescriptorSetLayoutCI::new()
+        .add_binding(sampler_binding(0))
+        .build(device)?;
+
+    // layout (binding = 0) uniform sampler2D sceneColor;
+    // layout (binding = 1) uniform sampler2D bloomColor;
+    let composite_set_layout = DescriptorSetLayoutCI::new()
+        .add_binding(sampler_binding(0))
+        .add_binding(sampler_binding(1))
+        .build(device)?;
+
+    // Descriptor sets.
+    let mut set_ai = DescriptorSetAI::new(pool)
+        .add_set_layout(composite_set_layout)
+        .add_set_layout(blur_set_layout);
+    for _ in 0..(level_count * 2) {
+        set_ai = set_ai.add_set_layout(blur_set_layout);
+    }
+    let descriptor_sets = set_ai.build(device)?;
+
+    let composite_set = descriptor_sets[0];
+    let bright_set = descriptor_sets[1];
+    let level_sets: Vec<[vk::DescriptorSet; 2]> = descriptor_sets[2..].chunks(2)
+        .map(|sets| [sets[0], sets[1]])
+        .collect();
+
+    let bloom_image = |view: vk::ImageView| vk::DescriptorImageInfo {
+        sampler,
+        image_view: view,
+        image_layout: vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+    };
+
+    // update descriptor sets.
+    let mut writes = vec![
+        DescriptorImageSetWI::new(bright_set, 0, vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+            .add_image(scene),
+        DescriptorImageSetWI::new(composite_set, 0, vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+            .add_image(scene),
+        // the final blur result of each level is stored in ping image.
+        DescriptorImageSetWI::new(composite_set, 1, vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+            .add_image(bloom_image(ping.view)),
+    ];
+
+    for (level, sets) in level_sets.iter().enumerate() {
+        writes.push(DescriptorImageSetWI::new(sets[0], 0, vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+            .add_image(bloom_image(ping.levels[level].view)));
+        writes.push(DescriptorImageSetWI::new(sets[1], 0, vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+            .add_image(bloom_image(pong.levels[level].view)));
+    }
+
+    let mut update_ci = DescriptorSetsUpdateCI::new();
+    for write in writes.iter() {
+        update_ci = update_ci.add_write(write);
+    }
+    update_ci.update(device);
+
+    let result = BloomDescriptors { pool, bright_set, level_sets, blur_set_layout, composite_set, composite_set_layout };
+    Ok(result)
+}
+
+struct BloomPipelines {
+    bright: vk::Pipeline,
+    blur: vk::Pipeline,
+    blur_layout: PipelineLayout,
+    composite: vk::Pipeline,
+    composite_layout: PipelineLayout,
+}
+
+fn prepare_pipelines(device: &VkDevice, bloom_render_pass: vk::RenderPass, output_render_pass: vk::RenderPass, descriptors: &BloomDescriptors) -> VkResult<BloomPipelines> {
+
+    use crate::ci::pipeline::*;
+    use crate::ci::shader::{ShaderModuleCI, ShaderStageCI};
+
+    // Pipeline Layouts.
+    let blur_ranges = PushConstantRanges::new()
+        .push::<BlurParams>(vk::ShaderStageFlags::FRAGMENT)
+        .build(device)?;
+    let blur_layout = PipelineLayoutCI::new()
+        .add_set_layout(descriptors.blur_set_layout)
+        .add_push_constant_ranges(&blur_ranges)
+        .build_with_ranges(device)?;
+
+    let composite_ranges = PushConstantRanges::new()
+        .push::<CompositeParams>(vk::ShaderStageFlags::FRAGMENT)
+        .build(device)?;
+    let composite_layout = PipelineLayoutCI::new()
+        .add_set_layout(descriptors.composite_set_layout)
+        .add_push_constant_ranges(&composite_ranges)
+        .build_with_ranges(device)?;
+
+    let mut shader_compiler = crate::utils::shaderc::VkShaderCompiler::new()?;
+    let vert_codes = shader_compiler.compile_from_str(
+        include_str!("bloom/fullscreen.vert.glsl"),
+        shaderc::ShaderKind::Vertex,
+        "[Vertex Shader]",
+        "main")?;
+    let bright_codes = shader_compiler.compile_from_str(
+        include_str!("bloom/bright.frag.glsl"),
+        shaderc::ShaderKind::Fragment,
+        "[Fragment Shader]",
+        "main")?;
+    let blur_codes = shader_compiler.compile_from_str(
+        include_str!("bloom/blur.frag.glsl"),
+        shaderc::ShaderKind::Fragment,
+        "[Fragment Shader]",
+        "main")?;
+    let composite_codes = shader_compiler.compile_from_str(
+        include_str!("bloom/composite.frag.glsl"),
+        shaderc::ShaderKind::Fragment,
+        "[Fragment Shader]",
+        "main")?;
+
+    let vert_module      = ShaderModuleCI::new(vert_codes).build(device)?;
+    let bright_module    = ShaderModuleCI::new(bright_codes).build(device)?;
+    let blur_module      = ShaderModuleCI::new(blur_codes).build(device)?;
+    let composite_module = ShaderModuleCI::new(composite_codes).build(device)?;
+
+    // all the passes draw a fullscreen triangle without vertex input.
+    let build_fullscreen = |render_pass: vk::RenderPass, layout: vk::PipelineLayout, frag_module: vk::ShaderModule| {
+
+        let viewport_state = ViewportSCI::new()
+            .add_viewport(vk::Viewport::default())
+            .add_scissor(vk::Rect2D::default());
+
+        let blend_state = ColorBlendSCI::new()
+            .add_attachment(BlendAttachmentSCI::new());
+
+        let dynamic_state = DynamicSCI::new()
+            .add_dynamic(vk::DynamicState::VIEWPORT)
+            .add_dynamic(vk::DynamicState::SCISSOR);
+
+        let mut pipeline_ci = GraphicsPipelineCI::new(render_pass, layout);
+        pipeline_ci.set_viewport(viewport_state);
+        pipeline_ci.set_color_blend(blend_state);
+        pipeline_ci.set_dynamic(dynamic_state);
+
+        let shaders = [
+            ShaderStageCI::new(vk::ShaderStageFlags::VERTEX, vert_module),
+            ShaderStageCI::new(vk::ShaderStageFlags::FRAGMENT, frag_module),
+        ];
+        pipeline_ci.set_shaders(&shaders);
+
+        device.build(&pipeline_ci)
+    };
+
+    let bright    = build_fullscreen(bloom_render_pass, blur_layout.handle, bright_module)?;
+    let blur      = build_fullscreen(bloom_render_pass, blur_layout.handle, blur_module)?;
+    let composite = build_fullscreen(output_render_pass, composite_layout.handle, composite_module)?;
+
+    device.discard(vert_module);
+    device.discard(bright_module);
+    device.discard(blur_module);
+    device.discard(composite_module);
+
+    let result = BloomPipelines { bright, blur, blur_layout, composite, composite_layout };
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn gaussian_weights_sum_to_one() {
+
+        for radius in 0..=(MAX_BLUR_RADIUS + 2) {
+
+            let weights = gaussian_weights(radius);
+            assert_eq!(weights.len(), radius.min(MAX_BLUR_RADIUS) + 1);
+
+            let total = weights[0] + weights[1..].iter().sum::<vkfloat>() * 2.0;
+            assert!((total - 1.0).abs() < 1e-5, "weights of radius {} sum to {}", radius, total);
+
+            // the weights decrease from the center.
+            assert!(weights.windows(2).all(|pair| pair[0] >= pair[1]));
+        }
+    }
+
+    #[test]
+    fn bright_params_push_threshold_first() {
+
+        let config = BloomConfig { threshold: 2.5, ..Default::default() };
+        let params = BlurParams::bright(config.threshold);
+
+        // bright.frag.glsl declares `float threshold` at offset 0 of push constants.
+        let pushed_threshold = unsafe { *(&params as *const BlurParams as *const vkfloat) };
+        assert_eq!(pushed_threshold, config.threshold);
+        assert_eq!(params.radius, 0);
+    }
+
+    #[test]
+    fn mip_levels_are_clamped_to_chain() {
+
+        let dimension = vk::Extent2D { width: 640, height: 360 };
+        assert_eq!(level_count(dimension, 5), 5);
+        // 640 has 10 levels down to 1 texel.
+        assert_eq!(level_count(dimension, 20), 10);
+        assert_eq!(level_count(dimension, 0), 1);
+        assert_eq!(level_count(vk::Extent2D { width: 1, height: 1 }, 5), 1);
+
+        let size = |level: usize| {
+            let level_dimension = level_dimension(dimension, level);
+            (level_dimension.width, level_dimension.height)
+        };
+        assert_eq!(size(0), (640, 360));
+        assert_eq!(size(3), (80, 45));
+        assert_eq!(size(9), (1, 1));
+    }
+}
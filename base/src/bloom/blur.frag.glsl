@@ -0,0 +1,33 @@
+
+#version 450 core
+#extension GL_ARB_separate_shader_objects : enable
+
+// keep the same as `MAX_BLUR_RADIUS` in bloom.rs.
+#define MAX_BLUR_RADIUS 8
+
+layout (binding = 0) uniform sampler2D inputColor;
+
+layout (push_constant) uniform BlurParams {
+    // the texel size along blur direction.
+    vec2 texelStep;
+    int radius;
+    float weights[MAX_BLUR_RADIUS + 1];
+} params;
+
+layout (location = 0) in vec2 inUV;
+
+layout (location = 0) out vec4 outColor;
+
+void main() {
+
+    vec3 result = texture(inputColor, inUV).rgb * params.weights[0];
+
+    for (int i = 1; i <= params.radius; i++) {
+
+        vec2 offset = params.texelStep * float(i);
+        result += texture(inputColor, inUV + offset).rgb * params.weights[i];
+        result += texture(inputColor, inUV - offset).rgb * params.weights[i];
+    }
+
+    outColor = vec4(result, 1.0);
+}
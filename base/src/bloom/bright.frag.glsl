@@ -0,0 +1,22 @@
+
+#version 450 core
+#extension GL_ARB_separate_shader_objects : enable
+
+layout (binding = 0) uniform sampler2D sceneColor;
+
+layout (push_constant) uniform BrightParams {
+    float threshold;
+} params;
+
+layout (location = 0) in vec2 inUV;
+
+layout (location = 0) out vec4 outColor;
+
+void main() {
+
+    vec3 color = texture(sceneColor, inUV).rgb;
+    float brightness = max(color.r, max(color.g, color.b));
+
+    // only keep the pixels brighter than threshold.
+    outColor = (brightness > params.threshold) ? vec4(color, 1.0) : vec4(0.0, 0.0, 0.0, 1.0);
+}
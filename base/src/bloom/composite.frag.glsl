@@ -0,0 +1,34 @@
+
+#version 450 core
+#extension GL_ARB_separate_shader_objects : enable
+
+layout (binding = 0) uniform sampler2D sceneColor;
+layout (binding = 1) uniform sampler2D bloomColor;
+
+layout (push_constant) uniform CompositeParams {
+    float intensity;
+    float exposure;
+    // the count of mip levels of bloomColor.
+    int levelCount;
+} params;
+
+layout (location = 0) in vec2 inUV;
+
+layout (location = 0) out vec4 outColor;
+
+void main() {
+
+    // each level is blurred at its own resolution, so that the average of levels mixes narrow and wide blur.
+    vec3 bloom = vec3(0.0);
+    for (int i = 0; i < params.levelCount; i++) {
+        bloom += textureLod(bloomColor, inUV, float(i)).rgb;
+    }
+    bloom /= float(params.levelCount);
+
+    vec3 hdrColor = texture(sceneColor, inUV).rgb + bloom * params.intensity;
+
+    // exposure tone mapping.
+    vec3 mapped = vec3(1.0) - exp(-hdrColor * params.exposure);
+
+    outColor = vec4(mapped, 1.0);
+}
@@ -5,6 +5,35 @@ use crate::input::EventController;
 use crate::{Vec3F, Mat4F};
 
 
+/// The position and orientation of a `FlightCamera`, which can be stored to restore a viewpoint later.
+///
+/// Enable the `serialize` feature to derive `serde::Serialize` and `serde::Deserialize` for it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde_derive::Serialize, serde_derive::Deserialize))]
+pub struct CameraState {
+    /// the position of camera in world space.
+    pub position: [f32; 3],
+    /// the yaw angle in degrees.
+    pub yaw: f32,
+    /// the pitch angle in degrees.
+    pub pitch: f32,
+    /// the vertical field of view in radians.
+    pub zoom: f32,
+}
+
+/// The mapping from view distance to depth value in the projection matrix of `FlightCamera`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum DepthProjection {
+    /// the near plane is mapped to depth 0.0 and the far plane to depth 1.0(the default), which is tested by `vk::CompareOp::LESS_OR_EQUAL`.
+    Standard,
+    /// the near plane is mapped to depth 1.0 and the far plane to depth 0.0, which is tested by `vk::CompareOp::GREATER_OR_EQUAL`.
+    ///
+    /// It spreads the precision of floating point depth evenly over distance, so clear depth to 0.0 and use a `D32_SFLOAT` depth format.
+    Reversed,
+    /// the same as `Reversed`, but the far plane is at infinity and the `far` distance of camera is ignored.
+    ReversedInfinite,
+}
+
 /// A simple flight through camera.
 ///
 /// This camera is mainly modified from https://learnopengl.com.
@@ -26,17 +55,22 @@ pub struct FlightCamera {
 
     // camera options
     move_speed: f32,
-    _mouse_sensitivity: f32,
+    /// the multiplier applied to mouse motion when rotating the camera.
+    mouse_sensitivity: f32,
     _wheel_sensitivity: f32,
+    /// whether moving mouse upward makes the camera look down.
+    invert_y: bool,
 
     zoom: f32,
     near: f32,
     far : f32,
     screen_aspect: f32,
+    depth_projection: DepthProjection,
 
     /// Vulkan assumes a viewport origin at the top-left by default.
     /// This leads to the clip space having its +Y axis pointing downwards, contrary to OpenGL's behaviour.
-    /// Set `flip_vertically` to true to adapt this change for vulkan(default is true).
+    /// Set `flip_vertically` to true to adapt this change for vulkan(default is true),
+    /// or keep it false and flip the viewport instead(see `utils::viewport::viewport_flip_y`).
     ///
     /// see http://forum.lwjgl.org/index.php?topic=6167.0 for detail.
     flip_vertically: bool,
@@ -52,6 +86,16 @@ impl FlightCamera {
         self.move_speed = speed;
     }
 
+    /// Set the multiplier applied to mouse motion(default is 1.0).
+    pub fn set_mouse_sensitivity(&mut self, sensitivity: f32) {
+        self.mouse_sensitivity = sensitivity;
+    }
+
+    /// Set whether to invert the pitch controlled by vertical mouse motion(default is false).
+    pub fn set_invert_y(&mut self, is_invert: bool) {
+        self.invert_y = is_invert;
+    }
+
     pub fn current_position(&self) -> Vec3F {
         self.pos.clone()
     }
@@ -63,17 +107,55 @@ impl FlightCamera {
     }
 
     /// Generate a new projection matrix based on camera status.
+    ///
+    /// The depth values follow the `DepthProjection` of camera, which must match the depth test of pipelines.
     pub fn proj_matrix(&self) -> Mat4F {
+        perspective_matrix(self.zoom, self.screen_aspect, self.near, self.far, self.depth_projection)
+    }
 
-        Mat4F::perspective_rh_zo(self.zoom, self.screen_aspect, self.near, self.far)
+    /// Set the way to map view distance to depth value, which takes effect in the next `proj_matrix` call.
+    pub fn set_depth_projection(&mut self, projection: DepthProjection) {
+        self.depth_projection = projection;
     }
 
+    #[inline]
+    pub fn depth_projection(&self) -> DepthProjection {
+        self.depth_projection
+    }
+
+    /// Update the aspect ratio of projection to match a viewport of `width` x `height`.
+    ///
+    /// The camera does not track the window, so call this in `RenderWorkflow::on_resize` with the size of viewport
+    /// (the swapchain dimension, or a part of it for split views), and regenerate the projection matrix by `proj_matrix`.
+    /// Otherwise the rendering is stretched after window resizing.
     pub fn reset_screen_dimension(&mut self, width: u32, height: u32) {
         self.screen_aspect = (width as f32) / (height as f32);
     }
 
+    /// Get the current position and orientation of camera.
+    pub fn state(&self) -> CameraState {
+        CameraState {
+            position: [self.pos.x, self.pos.y, self.pos.z],
+            yaw  : self.yaw,
+            pitch: self.pitch,
+            zoom : self.zoom,
+        }
+    }
+
+    /// Move and rotate the camera to `state`, which is usually returned by `state` method before.
+    pub fn restore(&mut self, state: &CameraState) {
+
+        self.pos = Vec3F::new(state.position[0], state.position[1], state.position[2]);
+        self.yaw = state.yaw;
+        self.pitch = num::clamp(state.pitch, -89.0, 89.0);
+        self.zoom = state.zoom;
+
+        self.update_vectors();
+    }
+
     pub fn flip_vertically(&mut self) {
         self.flip_vertically = !self.flip_vertically;
+        self.update_vectors();
     }
 
     pub fn receive_input(&mut self, inputer: &EventController, delta_time: f32) {
@@ -97,9 +179,12 @@ impl FlightCamera {
         if inputer.is_cursor_active() {
 
             let mouse_motion = inputer.cursor.get_cursor_motion();
+            let motion_x = mouse_motion.0 * self.mouse_sensitivity;
+            let motion_y = mouse_motion.1 * self.mouse_sensitivity;
+            let pitch_offset = if self.invert_y { motion_y } else { -motion_y };
 
-            self.yaw += mouse_motion.0;
-            self.pitch = num::clamp(self.pitch - mouse_motion.1, -89.0, 89.0);
+            self.yaw += motion_x;
+            self.pitch = num::clamp(self.pitch + pitch_offset, -89.0, 89.0);
 
             // recalculate front, right or up vector only when mouse move.
             self.update_vectors();
@@ -137,6 +222,14 @@ pub struct FlightCameraBuilder {
     near: f32,
     far : f32,
     screen_aspect: f32,
+    depth_projection: DepthProjection,
+    flip_vertically: bool,
+
+    mouse_sensitivity: f32,
+    invert_y: bool,
+
+    /// the point to face, which overrides `yaw` and `pitch` if it is set.
+    look_target: Option<Vec3F>,
 }
 
 impl Default for FlightCameraBuilder {
@@ -150,6 +243,11 @@ impl Default for FlightCameraBuilder {
             near     : 0.1,
             far      : 100.0,
             screen_aspect: 1.0,
+            depth_projection: DepthProjection::Standard,
+            flip_vertically: true,
+            mouse_sensitivity: 1.0,
+            invert_y: false,
+            look_target: None,
         }
     }
 }
@@ -158,24 +256,30 @@ impl FlightCameraBuilder {
 
     pub fn build(self) -> FlightCamera {
 
+        let (yaw, pitch) = match self.look_target {
+            | Some(target) => self.face_angles(target),
+            | None => (self.yaw, self.pitch),
+        };
+
         let mut camera = FlightCamera {
             pos      : self.pos,
             front    : Vec3F::new(0.0, 0.0, -1.0),
             up       : Vec3F::zero(),
             right    : Vec3F::zero(),
             world_up : self.world_up,
-            yaw      : self.yaw,
-            pitch    : self.pitch,
+            yaw, pitch,
             near     : self.near,
             far      : self.far,
             screen_aspect: self.screen_aspect,
+            depth_projection: self.depth_projection,
 
             move_speed: 2.5,
-            _mouse_sensitivity: 1.0,
+            mouse_sensitivity: self.mouse_sensitivity,
             _wheel_sensitivity: 1.0,
+            invert_y: self.invert_y,
             zoom: 45.0_f32.to_radians(),
 
-            flip_vertically: true,
+            flip_vertically: self.flip_vertically,
         };
         camera.update_vectors();
 
@@ -198,6 +302,14 @@ impl FlightCameraBuilder {
         self.pitch = pitch; self
     }
 
+    /// Make the camera face `target` by computing its yaw and pitch from the direction `target - position`.
+    ///
+    /// The direction is computed when building the camera, so it works with `place_at` called in any order.
+    /// It overrides the value set by `yaw` and `pitch`, and keeps the current angles if `target` is the camera position.
+    pub fn look_at(mut self, target: Vec3F) -> FlightCameraBuilder {
+        self.look_target = Some(target); self
+    }
+
     pub fn view_distance(mut self, near: f32, far: f32) -> FlightCameraBuilder {
         self.near = near;
         self.far = far; self
@@ -206,5 +318,103 @@ impl FlightCameraBuilder {
     pub fn screen_aspect_ratio(mut self, ratio: f32) -> FlightCameraBuilder {
         self.screen_aspect = ratio; self
     }
+
+    /// Set the way to map view distance to depth value(default is `DepthProjection::Standard`).
+    pub fn depth_projection(mut self, projection: DepthProjection) -> FlightCameraBuilder {
+        self.depth_projection = projection; self
+    }
+
+    /// Compute the yaw and pitch in degrees, which make the camera face `target`.
+    fn face_angles(&self, target: Vec3F) -> (f32, f32) {
+
+        let direction = target - self.pos;
+        if direction.magnitude_squared() <= ::std::f32::EPSILON {
+            return (self.yaw, self.pitch)
+        }
+        let direction = direction.normalized();
+
+        let pitch = num::clamp(direction.y.asin().to_degrees(), -89.0, 89.0);
+        // this is the inverse of `FlightCamera::update_vectors`, where x component of front vector is negated when flipping vertically.
+        let yaw = if self.flip_vertically {
+            direction.z.atan2(-direction.x).to_degrees()
+        } else {
+            direction.z.atan2(direction.x).to_degrees()
+        };
+
+        (yaw, pitch)
+    }
+
+    /// Set whether to flip the camera for vulkan's downward +Y axis(default is true).
+    ///
+    /// Set it to false if the viewport is already flipped by `utils::viewport::viewport_flip_y`.
+    pub fn flip_vertically(mut self, is_flip: bool) -> FlightCameraBuilder {
+        self.flip_vertically = is_flip; self
+    }
+
+    /// Set the multiplier applied to mouse motion when rotating the camera(default is 1.0).
+    pub fn mouse_sensitivity(mut self, sensitivity: f32) -> FlightCameraBuilder {
+        self.mouse_sensitivity = sensitivity; self
+    }
+
+    /// Set whether moving mouse upward makes the camera look down(default is false).
+    pub fn invert_y(mut self, is_invert: bool) -> FlightCameraBuilder {
+        self.invert_y = is_invert; self
+    }
+}
+
+/// Generate a right-handed perspective projection, whose depth range is [0.0, 1.0] as Vulkan requires.
+fn perspective_matrix(fov_y: f32, aspect: f32, near: f32, far: f32, projection: DepthProjection) -> Mat4F {
+
+    let mut matrix = Mat4F::perspective_rh_zo(fov_y, aspect, near, far);
+
+    // only the mapping of z is changed, while w is still the negative view distance.
+    match projection {
+        | DepthProjection::Standard => {},
+        | DepthProjection::Reversed => {
+            matrix.cols[2].z = near / (far - near);
+            matrix.cols[3].z = far * near / (far - near);
+        },
+        | DepthProjection::ReversedInfinite => {
+            matrix.cols[2].z = 0.0;
+            matrix.cols[3].z = near;
+        },
+    }
+
+    matrix
 }
 
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::Vec4F;
+
+    /// Get the depth value of a point at `distance` in front of camera.
+    fn depth_at(matrix: &Mat4F, distance: f32) -> f32 {
+        let clip = *matrix * Vec4F::new(0.0, 0.0, -distance, 1.0);
+        clip.z / clip.w
+    }
+
+    #[test]
+    fn standard_projection_maps_near_to_zero() {
+        let matrix = perspective_matrix(1.0, 1.0, 0.1, 100.0, DepthProjection::Standard);
+        assert!(depth_at(&matrix, 0.1).abs() < 1e-5);
+        assert!((depth_at(&matrix, 100.0) - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn reversed_projection_maps_near_to_one() {
+        let matrix = perspective_matrix(1.0, 1.0, 0.1, 100.0, DepthProjection::Reversed);
+        assert!((depth_at(&matrix, 0.1) - 1.0).abs() < 1e-5);
+        assert!(depth_at(&matrix, 100.0).abs() < 1e-5);
+        assert!(depth_at(&matrix, 1.0) > depth_at(&matrix, 10.0));
+    }
+
+    #[test]
+    fn infinite_projection_approaches_zero() {
+        let matrix = perspective_matrix(1.0, 1.0, 0.1, 100.0, DepthProjection::ReversedInfinite);
+        assert!((depth_at(&matrix, 0.1) - 1.0).abs() < 1e-5);
+        assert!(depth_at(&matrix, 1.0e6) < 1e-6);
+        assert!(depth_at(&matrix, 1.0e6) > 0.0);
+    }
+}
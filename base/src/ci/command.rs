@@ -61,7 +61,7 @@ impl VkObjectBuildableCI for CommandBufferAI {
 
         let commands = unsafe {
             device.logic.handle.allocate_command_buffers(self.as_ref())
-                .map_err(|_| VkError::create("Command Buffers"))?
+                .map_err(|e| VkError::create_with("Command Buffers", e))?
         };
         Ok(commands)
     }
@@ -165,7 +165,7 @@ impl VkObjectBuildableCI for CommandPoolCI {
 
         let pool = unsafe {
             device.logic.handle.create_command_pool(self.as_ref(), None)
-                .map_err(|_| VkError::create("Command Pool"))?
+                .map_err(|e| VkError::create_with("Command Pool", e))?
         };
         Ok(pool)
     }
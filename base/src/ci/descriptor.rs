@@ -7,6 +7,7 @@ use crate::context::VkDevice;
 use crate::context::{VkObjectDiscardable, VkObjectAllocatable};
 use crate::ci::{VulkanCI, VkObjectBuildableCI};
 use crate::error::{VkResult, VkError};
+use crate::utils::reflect::ShaderReflection;
 use crate::vkuint;
 
 use std::ptr;
@@ -67,7 +68,7 @@ impl VkObjectBuildableCI for DescriptorPoolCI {
 
         let descriptor_pool = unsafe {
             device.logic.handle.create_descriptor_pool(self.as_ref(), None)
-                .map_err(|_| VkError::create("Descriptor Pool"))?
+                .map_err(|e| VkError::create_with("Descriptor Pool", e))?
         };
         Ok(descriptor_pool)
     }
@@ -99,6 +100,14 @@ impl DescriptorPoolCI {
         self.inner.flags = flags; self
     }
 
+    /// Allow the descriptor sets allocated from this pool to use layouts with `vk::DescriptorBindingFlagsEXT::UPDATE_AFTER_BIND`.
+    ///
+    /// This requires `DeviceExtensionType::DescriptorIndexing`.
+    #[inline(always)]
+    pub fn update_after_bind(mut self) -> DescriptorPoolCI {
+        self.inner.flags |= vk::DescriptorPoolCreateFlags::UPDATE_AFTER_BIND_EXT; self
+    }
+
     /// Add a new descriptor type that can be allocated by this pool.
     ///
     /// `type_` is the type of descriptor.
@@ -149,6 +158,10 @@ impl VkObjectDiscardable for vk::DescriptorPool {
 pub struct DescriptorSetLayoutCI {
     inner: vk::DescriptorSetLayoutCreateInfo,
     bindings: Vec<vk::DescriptorSetLayoutBinding>,
+    /// the immutable samplers with the position of their binding in `bindings`.
+    immutable_samplers: Vec<(usize, Vec<vk::Sampler>)>,
+    /// the descriptor indexing flags of each binding in `bindings`.
+    binding_flags: Vec<vk::DescriptorBindingFlagsEXT>,
 }
 
 impl VulkanCI<vk::DescriptorSetLayoutCreateInfo> for DescriptorSetLayoutCI {
@@ -178,9 +191,38 @@ impl VkObjectBuildableCI for DescriptorSetLayoutCI {
     /// Create `vk::DescriptorSetLayout` object, and return its handle.
     fn build(&self, device: &VkDevice) -> VkResult<Self::ObjectType> {
 
+        // refresh the pointers to immutable samplers, since they may dangle in a cloned CI.
+        let mut bindings = self.bindings.clone();
+        for (position, samplers) in self.immutable_samplers.iter() {
+            bindings[*position].p_immutable_samplers = samplers.as_ptr();
+        }
+
+        let mut layout_ci = vk::DescriptorSetLayoutCreateInfo {
+            binding_count: bindings.len() as _,
+            p_bindings   : bindings.as_ptr(),
+            ..self.inner
+        };
+
+        let is_indexing_used = self.binding_flags.iter().any(|flags| !flags.is_empty());
+        let binding_flags_ci = vk::DescriptorSetLayoutBindingFlagsCreateInfoEXT {
+            s_type: vk::StructureType::DESCRIPTOR_SET_LAYOUT_BINDING_FLAGS_CREATE_INFO_EXT,
+            p_next: ptr::null(),
+            binding_count  : self.binding_flags.len() as _,
+            p_binding_flags: self.binding_flags.as_ptr(),
+        };
+
+        if is_indexing_used {
+            self.check_binding_flags(device)?;
+            layout_ci.p_next = &binding_flags_ci as *const vk::DescriptorSetLayoutBindingFlagsCreateInfoEXT as _;
+
+            if self.binding_flags.iter().any(|flags| flags.contains(vk::DescriptorBindingFlagsEXT::UPDATE_AFTER_BIND)) {
+                layout_ci.flags |= vk::DescriptorSetLayoutCreateFlags::UPDATE_AFTER_BIND_POOL_EXT;
+            }
+        }
+
         let descriptor_set_layout = unsafe {
-            device.logic.handle.create_descriptor_set_layout(self.as_ref(), None)
-                .map_err(|_| VkError::create("Descriptor Set Layout"))?
+            device.logic.handle.create_descriptor_set_layout(&layout_ci, None)
+                .map_err(|e| VkError::create_with("Descriptor Set Layout", e))?
         };
         Ok(descriptor_set_layout)
     }
@@ -195,18 +237,117 @@ impl DescriptorSetLayoutCI {
         DescriptorSetLayoutCI {
             inner: DescriptorSetLayoutCI::default_ci(),
             bindings: Vec::new(),
+            immutable_samplers: Vec::new(),
+            binding_flags: Vec::new(),
         }
     }
 
     /// Add set layout bindings to this descriptor set.
     #[inline(always)]
-    pub fn add_binding(mut self, binding: vk::DescriptorSetLayoutBinding) -> DescriptorSetLayoutCI {
+    pub fn add_binding(self, binding: vk::DescriptorSetLayoutBinding) -> DescriptorSetLayoutCI {
+        self.add_binding_with_flags(binding, vk::DescriptorBindingFlagsEXT::empty())
+    }
 
+    /// Add a set layout binding with descriptor indexing `flags`(etc. a large texture array for bindless rendering).
+    ///
+    /// `PARTIALLY_BOUND` allows the descriptors not used by shader to stay unwritten,
+    /// `UPDATE_AFTER_BIND` allows the descriptors to be written after the set is bound(the pool must be created with `DescriptorPoolCI::update_after_bind`),
+    /// and `VARIABLE_DESCRIPTOR_COUNT` makes `descriptor_count` an upper bound, whose actual count is given by `DescriptorSetAI::variable_descriptor_counts`.
+    ///
+    /// Non-empty `flags` requires `DeviceExtensionType::DescriptorIndexing`, and each flag is checked against the features of device when building the layout.
+    #[inline(always)]
+    pub fn add_binding_with_flags(mut self, binding: vk::DescriptorSetLayoutBinding, flags: vk::DescriptorBindingFlagsEXT) -> DescriptorSetLayoutCI {
+
+        self.binding_flags.push(flags);
         self.bindings.push(binding);
         self.inner.binding_count = self.bindings.len() as _;
         self.inner.p_bindings    = self.bindings.as_ptr(); self
     }
 
+    /// Add a sampler binding whose samplers are fixed in the layout, so they do not need to be written to descriptor sets.
+    ///
+    /// `binding.descriptor_type` must be `SAMPLER` or `COMBINED_IMAGE_SAMPLER`, and its `descriptor_count` is set to the count of `samplers`.
+    /// The samplers are copied into this CI, so `p_immutable_samplers` stays valid as long as the CI lives.
+    /// The samplers must not be destroyed before the layout.
+    pub fn add_binding_with_immutable_samplers(mut self, mut binding: vk::DescriptorSetLayoutBinding, samplers: &[vk::Sampler]) -> DescriptorSetLayoutCI {
+
+        debug_assert!(binding.descriptor_type == vk::DescriptorType::SAMPLER || binding.descriptor_type == vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+            "Immutable samplers can only be used by SAMPLER or COMBINED_IMAGE_SAMPLER binding.");
+
+        let samplers = samplers.to_vec();
+        binding.descriptor_count = samplers.len() as _;
+        binding.p_immutable_samplers = samplers.as_ptr();
+
+        self.immutable_samplers.push((self.bindings.len(), samplers));
+        self.add_binding(binding)
+    }
+
+    /// Initialize `vk::DescriptorSetLayoutCreateInfo` with the bindings of descriptor set `set` declared in shaders.
+    ///
+    /// The bindings are generated by `ShaderReflection::layout_bindings`, so they always match the shaders.
+    pub fn from_reflection(reflection: &ShaderReflection, set: vkuint) -> DescriptorSetLayoutCI {
+
+        reflection.layout_bindings(set).into_iter()
+            .fold(DescriptorSetLayoutCI::new(), |ci, binding| ci.add_binding(binding))
+    }
+
+    fn check_binding_flags(&self, device: &VkDevice) -> VkResult<()> {
+
+        let features = device.phy.descriptor_indexing_features()
+            .ok_or(VkError::unsupported("Descriptor Indexing(VK_EXT_descriptor_indexing is not requested or Vulkan 1.1 is unavailable)"))?;
+        let is_support = |feature: vk::Bool32| feature == vk::TRUE;
+
+        let max_binding = self.bindings.iter().map(|binding| binding.binding).max().unwrap_or(0);
+
+        for (binding, flags) in self.bindings.iter().zip(self.binding_flags.iter()) {
+
+            if flags.contains(vk::DescriptorBindingFlagsEXT::PARTIALLY_BOUND) && !is_support(features.descriptor_binding_partially_bound) {
+                return Err(VkError::unsupported("Partially Bound Descriptor"))
+            }
+
+            if flags.contains(vk::DescriptorBindingFlagsEXT::VARIABLE_DESCRIPTOR_COUNT) {
+                if !is_support(features.descriptor_binding_variable_descriptor_count) {
+                    return Err(VkError::unsupported("Variable Descriptor Count"))
+                }
+                if binding.binding != max_binding {
+                    return Err(VkError::custom(format!("Only the binding with the largest number({}) can use variable descriptor count, but binding {} uses it.", max_binding, binding.binding)))
+                }
+            }
+
+            if flags.contains(vk::DescriptorBindingFlagsEXT::UPDATE_UNUSED_WHILE_PENDING) && !is_support(features.descriptor_binding_update_unused_while_pending) {
+                return Err(VkError::unsupported("Update Unused Descriptor While Pending"))
+            }
+
+            if flags.contains(vk::DescriptorBindingFlagsEXT::UPDATE_AFTER_BIND) {
+
+                let is_update_after_bind_support = match binding.descriptor_type {
+                    | vk::DescriptorType::UNIFORM_BUFFER => features.descriptor_binding_uniform_buffer_update_after_bind,
+                    | vk::DescriptorType::STORAGE_BUFFER => features.descriptor_binding_storage_buffer_update_after_bind,
+                    | vk::DescriptorType::SAMPLER
+                    | vk::DescriptorType::COMBINED_IMAGE_SAMPLER
+                    | vk::DescriptorType::SAMPLED_IMAGE  => features.descriptor_binding_sampled_image_update_after_bind,
+                    | vk::DescriptorType::STORAGE_IMAGE  => features.descriptor_binding_storage_image_update_after_bind,
+                    | vk::DescriptorType::UNIFORM_TEXEL_BUFFER => features.descriptor_binding_uniform_texel_buffer_update_after_bind,
+                    | vk::DescriptorType::STORAGE_TEXEL_BUFFER => features.descriptor_binding_storage_texel_buffer_update_after_bind,
+                    // dynamic buffers and input attachments can never be updated after bind.
+                    | _ => vk::FALSE,
+                };
+
+                if !is_support(is_update_after_bind_support) {
+                    return Err(VkError::unsupported("Update After Bind Descriptor"))
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Get the bindings added to this descriptor set layout.
+    #[inline]
+    pub fn bindings(&self) -> &[vk::DescriptorSetLayoutBinding] {
+        &self.bindings
+    }
+
     /// Set the `flags` member for `vk::DescriptorSetLayoutCreateInfo`.
     ///
     /// It specifies options for descriptor set layout creation.
@@ -246,6 +387,8 @@ impl VkObjectDiscardable for vk::DescriptorSetLayout {
 pub struct DescriptorSetAI {
     inner: vk::DescriptorSetAllocateInfo,
     set_layouts: Vec<vk::DescriptorSetLayout>,
+    /// the actual descriptor count of variable-sized binding in each set.
+    variable_counts: Vec<vkuint>,
 }
 
 impl VulkanCI<vk::DescriptorSetAllocateInfo> for DescriptorSetAI {
@@ -277,9 +420,22 @@ impl VkObjectBuildableCI for DescriptorSetAI {
 
         debug_assert!(!self.set_layouts.is_empty(), "Descriptor sets count must be greater than 0!");
 
+        let variable_count_ai = vk::DescriptorSetVariableDescriptorCountAllocateInfoEXT {
+            s_type: vk::StructureType::DESCRIPTOR_SET_VARIABLE_DESCRIPTOR_COUNT_ALLOCATE_INFO_EXT,
+            p_next: ptr::null(),
+            descriptor_set_count: self.variable_counts.len() as _,
+            p_descriptor_counts : self.variable_counts.as_ptr(),
+        };
+
+        let mut set_ai = self.inner.clone();
+        if !self.variable_counts.is_empty() {
+            debug_assert_eq!(self.variable_counts.len(), self.set_layouts.len(), "The count of variable descriptor counts must be equal to the count of descriptor sets!");
+            set_ai.p_next = &variable_count_ai as *const vk::DescriptorSetVariableDescriptorCountAllocateInfoEXT as _;
+        }
+
         let descriptor_sets = unsafe {
-            device.logic.handle.allocate_descriptor_sets(self.as_ref())
-                .map_err(|_| VkError::create("Allocate Descriptor Set"))?
+            device.logic.handle.allocate_descriptor_sets(&set_ai)
+                .map_err(|e| VkError::create_with("Allocate Descriptor Set", e))?
         };
         Ok(descriptor_sets)
     }
@@ -298,6 +454,7 @@ impl DescriptorSetAI {
                 ..DescriptorSetAI::default_ci()
             },
             set_layouts: Vec::new(),
+            variable_counts: Vec::new(),
         }
     }
 
@@ -309,6 +466,14 @@ impl DescriptorSetAI {
         self.inner.descriptor_set_count = self.set_layouts.len() as _;
         self.inner.p_set_layouts        = self.set_layouts.as_ptr(); self
     }
+
+    /// Set the actual descriptor count of the binding with `VARIABLE_DESCRIPTOR_COUNT` flag, for each set to allocate in order.
+    ///
+    /// Each count must not exceed the `descriptor_count` declared in layout.
+    #[inline(always)]
+    pub fn variable_descriptor_counts(mut self, counts: Vec<vkuint>) -> DescriptorSetAI {
+        self.variable_counts = counts; self
+    }
 }
 
 impl VkObjectAllocatable for vk::DescriptorSet {
@@ -587,3 +752,170 @@ impl<'a, 'b: 'a> DescriptorSetsUpdateCI<'a> {
     }
 }
 // ----------------------------------------------------------------------------------------------
+
+// ----------------------------------------------------------------------------------------------
+/// Write the descriptors of a descriptor set, whose descriptor types are inferred from the bindings of its layout.
+///
+/// Each binding is checked to exist in layout, to have a type matching the kind of descriptor(buffer or image),
+/// and to have enough descriptor count. The first mismatch is reported by `DescriptorSetWriter::build`.
+///
+/// ``` ignore
+/// let writer = DescriptorSetWriter::new(descriptor_set, &set_layout_ci)
+///     .buffer(0, uniform_info)
+///     .image(1, texture_info);
+/// writer.build()?.update(device);
+/// ```
+pub struct DescriptorSetWriter<'a> {
+
+    set: vk::DescriptorSet,
+    layout_bindings: &'a [vk::DescriptorSetLayoutBinding],
+
+    buffer_writes: Vec<DescriptorBufferSetWI>,
+    image_writes : Vec<DescriptorImageSetWI>,
+    /// the written ranges of descriptors, as `(binding, first element, count)`.
+    written: Vec<(vkuint, usize, usize)>,
+    /// the first error found when writing descriptors.
+    error: Option<String>,
+}
+
+impl<'a> DescriptorSetWriter<'a> {
+
+    /// Start writing the descriptors of `set`, which is allocated with the layout created by `layout`.
+    pub fn new(set: vk::DescriptorSet, layout: &'a DescriptorSetLayoutCI) -> DescriptorSetWriter<'a> {
+
+        DescriptorSetWriter {
+            set,
+            layout_bindings: layout.bindings(),
+            buffer_writes: Vec::new(),
+            image_writes : Vec::new(),
+            written: Vec::new(),
+            error: None,
+        }
+    }
+
+    /// Write a buffer descriptor to `binding`.
+    #[inline(always)]
+    pub fn buffer(self, binding: vkuint, info: vk::DescriptorBufferInfo) -> DescriptorSetWriter<'a> {
+        self.buffers(binding, vec![info])
+    }
+
+    /// Write the buffer descriptors to the elements of array `binding`, starting from the first element.
+    #[inline(always)]
+    pub fn buffers(self, binding: vkuint, infos: Vec<vk::DescriptorBufferInfo>) -> DescriptorSetWriter<'a> {
+        self.buffers_at(binding, 0, infos)
+    }
+
+    /// Write the buffer descriptors to the elements of array `binding`, starting from `first_element`.
+    pub fn buffers_at(mut self, binding: vkuint, first_element: vkuint, infos: Vec<vk::DescriptorBufferInfo>) -> DescriptorSetWriter<'a> {
+
+        if let Some(descriptor_type) = self.check_write(binding, first_element as usize, infos.len(), is_buffer_descriptor, "buffer") {
+            let mut write = DescriptorBufferSetWI::new(self.set, binding, descriptor_type)
+                .dst_array_element(first_element);
+            write.set_buffer(infos);
+            self.buffer_writes.push(write);
+        }
+        self
+    }
+
+    /// Write an image descriptor to `binding`.
+    #[inline(always)]
+    pub fn image(self, binding: vkuint, info: vk::DescriptorImageInfo) -> DescriptorSetWriter<'a> {
+        self.images(binding, vec![info])
+    }
+
+    /// Write the image descriptors to the elements of array `binding`, starting from the first element.
+    #[inline(always)]
+    pub fn images(self, binding: vkuint, infos: Vec<vk::DescriptorImageInfo>) -> DescriptorSetWriter<'a> {
+        self.images_at(binding, 0, infos)
+    }
+
+    /// Write the image descriptors to the elements of array `binding`, starting from `first_element`.
+    ///
+    /// It's used to write a range of a large texture array(see `DescriptorSetLayoutCI::add_binding_with_flags`).
+    pub fn images_at(mut self, binding: vkuint, first_element: vkuint, infos: Vec<vk::DescriptorImageInfo>) -> DescriptorSetWriter<'a> {
+
+        if let Some(descriptor_type) = self.check_write(binding, first_element as usize, infos.len(), is_image_descriptor, "image") {
+            let mut write = DescriptorImageSetWI::new(self.set, binding, descriptor_type)
+                .dst_array_element(first_element);
+            write.set_images(infos);
+            self.image_writes.push(write);
+        }
+        self
+    }
+
+    /// Collect all the writes, or return the first mismatch between the writes and layout bindings.
+    pub fn build(&self) -> VkResult<DescriptorSetsUpdateCI> {
+
+        if let Some(ref error) = self.error {
+            return Err(VkError::custom(error.clone()))
+        }
+
+        let update_ci = self.buffer_writes.iter()
+            .fold(DescriptorSetsUpdateCI::new(), |ci, write| ci.add_write(write));
+        let update_ci = self.image_writes.iter()
+            .fold(update_ci, |ci, write| ci.add_write(write));
+
+        Ok(update_ci)
+    }
+
+    /// Validate a write to `binding` and return the descriptor type declared in layout, or None if it is invalid.
+    fn check_write(&mut self, binding: vkuint, first: usize, count: usize, is_type_match: fn(vk::DescriptorType) -> bool, kind: &str) -> Option<vk::DescriptorType> {
+
+        if self.error.is_some() {
+            return None
+        }
+
+        let layout_bindings = self.layout_bindings;
+        let layout_binding = layout_bindings.iter().find(|layout_binding| layout_binding.binding == binding);
+
+        let error = match layout_binding {
+            | None => {
+                Some(format!("Binding {} is not declared in the descriptor set layout.", binding))
+            },
+            | Some(layout_binding) if !is_type_match(layout_binding.descriptor_type) => {
+                Some(format!("Binding {} is declared as {:?} in layout, which can not be written with {} descriptors.", binding, layout_binding.descriptor_type, kind))
+            },
+            | Some(layout_binding) if first + count > layout_binding.descriptor_count as usize => {
+                Some(format!("Descriptors from {} to {} are written to binding {}, but only {} are declared in layout.", first, first + count, binding, layout_binding.descriptor_count))
+            },
+            | Some(_) if self.written.iter().any(|&(written_binding, written_first, written_count)| {
+                written_binding == binding && first < written_first + written_count && written_first < first + count
+            }) => {
+                Some(format!("Some descriptors of binding {} are written more than once.", binding))
+            },
+            | Some(_) => None,
+        };
+
+        if let Some(error) = error {
+            self.error = Some(error);
+            None
+        } else {
+            self.written.push((binding, first, count));
+            layout_binding.map(|layout_binding| layout_binding.descriptor_type)
+        }
+    }
+}
+
+fn is_buffer_descriptor(descriptor_type: vk::DescriptorType) -> bool {
+
+    match descriptor_type {
+        | vk::DescriptorType::UNIFORM_BUFFER
+        | vk::DescriptorType::UNIFORM_BUFFER_DYNAMIC
+        | vk::DescriptorType::STORAGE_BUFFER
+        | vk::DescriptorType::STORAGE_BUFFER_DYNAMIC => true,
+        | _ => false,
+    }
+}
+
+fn is_image_descriptor(descriptor_type: vk::DescriptorType) -> bool {
+
+    match descriptor_type {
+        | vk::DescriptorType::SAMPLER
+        | vk::DescriptorType::COMBINED_IMAGE_SAMPLER
+        | vk::DescriptorType::SAMPLED_IMAGE
+        | vk::DescriptorType::STORAGE_IMAGE
+        | vk::DescriptorType::INPUT_ATTACHMENT => true,
+        | _ => false,
+    }
+}
+// ----------------------------------------------------------------------------------------------
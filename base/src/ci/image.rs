@@ -84,7 +84,7 @@ impl VkObjectBuildableCI for ImageCI {
 
         let image = unsafe {
             device.logic.handle.create_image(self.as_ref(), None)
-                .map_err(|_| VkError::create("Image"))?
+                .map_err(|e| VkError::create_with("Image", e))?
         };
 
         let requirement = unsafe {
@@ -137,6 +137,22 @@ impl ImageCI {
         ImageCI::new(vk::ImageType::TYPE_2D, format, extent)
     }
 
+    /// Convenient method to create a cube-compatible `ImageCI`, which contains 6 array layers(faces).
+    ///
+    /// `format` specifies the texel format of this image.
+    ///
+    /// `dimension` specifies dimension of each face, whose width and height must be equal.
+    pub fn new_cube(format: vk::Format, dimension: vk::Extent2D) -> ImageCI {
+
+        debug_assert_eq!(dimension.width, dimension.height, "The faces of cube map must be square!");
+
+        ImageCI::new_2d(format, dimension)
+            // This flag is required to create cube views from this image.
+            .flags(vk::ImageCreateFlags::CUBE_COMPATIBLE)
+            // Cube faces count as array layers in Vulkan.
+            .array_layers(6)
+    }
+
     /// Set the `flags` member for `vk::ImageCreateInfo`.
     ///
     /// It describes additional parameters of the image.
@@ -310,7 +326,7 @@ impl VkObjectBuildableCI for ImageViewCI {
 
         let view = unsafe {
             device.logic.handle.create_image_view(self.as_ref(), None)
-                .map_err(|_| VkError::create("Image View"))?
+                .map_err(|e| VkError::create_with("Image View", e))?
         };
         Ok(view)
     }
@@ -543,7 +559,7 @@ impl VkObjectBuildableCI for SamplerCI {
 
         let sampler = unsafe {
             device.logic.handle.create_sampler(self.as_ref(), None)
-                .map_err(|_| VkError::create("Sampler"))?
+                .map_err(|e| VkError::create_with("Sampler", e))?
         };
         Ok(sampler)
     }
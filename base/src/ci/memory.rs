@@ -58,7 +58,7 @@ impl VkObjectBuildableCI for MemoryAI {
 
         let memory = unsafe {
             device.logic.handle.allocate_memory(self.as_ref(), None)
-                .map_err(|_| VkError::create("Memory Allocate"))?
+                .map_err(|e| VkError::create_with("Memory Allocate", e))?
         };
         Ok(memory)
     }
@@ -80,6 +80,22 @@ impl MemoryAI {
             },
         }
     }
+
+    /// Initialize `vk::MemoryAllocateInfo` to allocate host visible memory satisfying `requirement`.
+    ///
+    /// If `is_coherent` is false, `vk::MemoryPropertyFlags::HOST_COHERENT` is not requested,
+    /// and the host writes to this memory must be flushed by `VkDevice::flush_mapped_ranges` before device reads it.
+    pub fn host_visible(device: &VkDevice, requirement: &vk::MemoryRequirements, is_coherent: bool) -> MemoryAI {
+
+        let request_flags = if is_coherent {
+            vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT
+        } else {
+            vk::MemoryPropertyFlags::HOST_VISIBLE
+        };
+
+        let memory_type_index = device.get_memory_type(requirement.memory_type_bits, request_flags);
+        MemoryAI::new(requirement.size, memory_type_index)
+    }
 }
 
 impl crate::context::VkObjectDiscardable for vk::DeviceMemory {
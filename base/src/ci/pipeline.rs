@@ -1,5 +1,5 @@
 
-pub use self::renderpass::{RenderPassCI, RenderPassBI};
+pub use self::renderpass::{RenderPassCI, RenderPassBI, RenderPass, ClearValues};
 pub use self::renderpass::{AttachmentDescCI, SubpassDescCI, SubpassDependencyCI};
 
 pub use self::state::VertexInputSCI;
@@ -11,8 +11,17 @@ pub use self::state::DepthStencilSCI;
 pub use self::state::MultisampleSCI;
 pub use self::state::DynamicSCI;
 
+pub use self::variant::{PipelineVariantCache, PipelineVariantKey, VariantAlphaMode};
+
+pub use self::graph::{RenderGraph, GraphPass, GraphAttachment};
+
+pub use self::rendering::{RenderingBI, RenderingAttachmentCI, PipelineRenderingCI};
+
 mod state;
 mod renderpass;
+mod variant;
+mod graph;
+pub(crate) mod rendering;
 
 
 
@@ -69,8 +78,9 @@ impl VkObjectBuildableCI for PipelineLayoutCI {
 
         let pipeline_layout = unsafe {
             device.logic.handle.create_pipeline_layout(self.as_ref(), None)
-                .map_err(|_| VkError::create("Pipeline Layout"))?
+                .map_err(|e| VkError::create_with("Pipeline Layout", e))?
         };
+
         Ok(pipeline_layout)
     }
 }
@@ -106,15 +116,36 @@ impl PipelineLayoutCI {
         self.inner.p_push_constant_ranges    = push_constants.as_ptr(); self
     }
 
+    /// Add all the ranges validated by `PushConstantRanges::build`.
+    #[inline(always)]
+    pub fn add_push_constant_ranges(mut self, ranges: &[vk::PushConstantRange]) -> PipelineLayoutCI {
+
+        let push_constants = self.push_constants.get_or_insert(Vec::new());
+        push_constants.extend_from_slice(ranges);
+
+        self.inner.push_constant_range_count = push_constants.len() as _;
+        self.inner.p_push_constant_ranges    = push_constants.as_ptr(); self
+    }
+
     #[inline(always)]
     pub fn flags(mut self, flags: vk::PipelineLayoutCreateFlags) -> PipelineLayoutCI {
         self.inner.flags = flags; self
     }
+
+    /// Create the pipeline layout, and keep its push constant ranges for the validation of `push_constant_data`.
+    pub fn build_with_ranges(&self, device: &VkDevice) -> VkResult<PipelineLayout> {
+
+        let handle = self.build(device)?;
+        let push_ranges = self.push_constants.clone().unwrap_or_default();
+
+        Ok(PipelineLayout { handle, push_ranges })
+    }
 }
 
 impl VkObjectDiscardable for vk::PipelineLayout {
 
     fn discard_by(self, device: &VkDevice) {
+
         unsafe {
             device.logic.handle.destroy_pipeline_layout(self, None);
         }
@@ -122,6 +153,126 @@ impl VkObjectDiscardable for vk::PipelineLayout {
 }
 // ----------------------------------------------------------------------------------------------
 
+/// A pipeline layout together with the push constant ranges declared in it.
+#[derive(Debug, Clone)]
+pub struct PipelineLayout {
+
+    pub handle: vk::PipelineLayout,
+    push_ranges: Vec<vk::PushConstantRange>,
+}
+
+impl PipelineLayout {
+
+    #[inline]
+    pub fn push_ranges(&self) -> &[vk::PushConstantRange] {
+        &self.push_ranges
+    }
+
+    pub fn discard_by(self, device: &VkDevice) {
+        device.discard(self.handle);
+    }
+}
+
+/// Check if the push constant data with `size` bytes at `offset` for `stage` is declared in `layout`.
+///
+/// This check only takes effect in debug build.
+pub(crate) fn debug_check_push_constants(layout: &PipelineLayout, stage: vk::ShaderStageFlags, offset: vkuint, size: vkuint) {
+
+    debug_assert!(is_push_constants_declared(&layout.push_ranges, stage, offset, size),
+        "Push constants(stage: {:?}, offset: {}, size: {}) are out of the ranges declared in pipeline layout: {:?}.", stage, offset, size, layout.push_ranges);
+}
+
+/// Vulkan requires each stage in `stage` to be included by a range covering the whole update.
+fn is_push_constants_declared(ranges: &[vk::PushConstantRange], stage: vk::ShaderStageFlags, offset: vkuint, size: vkuint) -> bool {
+
+    ranges.iter()
+        .filter(|range| range.offset <= offset && offset + size <= range.offset + range.size)
+        .fold(vk::ShaderStageFlags::empty(), |covered, range| covered | range.stage_flags)
+        .contains(stage)
+}
+// ----------------------------------------------------------------------------------------------
+
+// ----------------------------------------------------------------------------------------------
+/// Builder of the push constant ranges of a pipeline layout.
+///
+/// The ranges are validated against the Vulkan requirements and device limits in `PushConstantRanges::build`:
+/// the offset and size of each range must be multiple of 4, the end of all ranges must not exceed `maxPushConstantsSize`,
+/// and each shader stage can only be included by one range.
+#[derive(Debug, Clone, Default)]
+pub struct PushConstantRanges {
+
+    ranges: Vec<vk::PushConstantRange>,
+}
+
+/// The alignment required by the offset and size of push constant range.
+const PUSH_CONSTANT_ALIGNMENT: vkuint = 4;
+
+impl PushConstantRanges {
+
+    pub fn new() -> PushConstantRanges {
+        PushConstantRanges::default()
+    }
+
+    /// Add a range holding `T` for `stage`, which is placed after the end of all previous ranges.
+    #[inline(always)]
+    pub fn push<T>(self, stage: vk::ShaderStageFlags) -> PushConstantRanges {
+        self.push_sized(stage, ::std::mem::size_of::<T>() as vkuint)
+    }
+
+    /// Add a range of `size` bytes for `stage`, which is placed after the end of all previous ranges(aligned to 4 bytes).
+    #[inline(always)]
+    pub fn push_sized(self, stage: vk::ShaderStageFlags, size: vkuint) -> PushConstantRanges {
+
+        let offset = self.end_offset();
+        let offset = (offset + PUSH_CONSTANT_ALIGNMENT - 1) / PUSH_CONSTANT_ALIGNMENT * PUSH_CONSTANT_ALIGNMENT;
+        self.add_range(vk::PushConstantRange { stage_flags: stage, offset, size })
+    }
+
+    /// Add a range with explicit offset.
+    #[inline(always)]
+    pub fn add_range(mut self, range: vk::PushConstantRange) -> PushConstantRanges {
+        self.ranges.push(range); self
+    }
+
+    /// Get the end offset of all ranges in bytes.
+    pub fn end_offset(&self) -> vkuint {
+        self.ranges.iter()
+            .map(|range| range.offset + range.size)
+            .max().unwrap_or(0)
+    }
+
+    /// Validate all the ranges and return them, which can be passed to `PipelineLayoutCI::add_push_constant_ranges`.
+    pub fn build(&self, device: &VkDevice) -> VkResult<Vec<vk::PushConstantRange>> {
+
+        let max_size = device.phy.limits.max_push_constants_size;
+
+        for (i, range) in self.ranges.iter().enumerate() {
+
+            if range.size == 0 || range.offset % PUSH_CONSTANT_ALIGNMENT != 0 || range.size % PUSH_CONSTANT_ALIGNMENT != 0 {
+                return Err(VkError::custom(format!(
+                    "Push constant range(offset: {}, size: {}) must have non-zero size and be aligned to {} bytes.", range.offset, range.size, PUSH_CONSTANT_ALIGNMENT)))
+            }
+
+            if range.offset + range.size > max_size {
+                return Err(VkError::custom(format!(
+                    "Push constant range(offset: {}, size: {}) exceeds the device limit of {} bytes.", range.offset, range.size, max_size)))
+            }
+
+            if range.stage_flags.is_empty() {
+                return Err(VkError::custom(format!("Push constant range(offset: {}) does not specify any shader stage.", range.offset)))
+            }
+
+            if let Some(other) = self.ranges[..i].iter().find(|other| other.stage_flags.intersects(range.stage_flags)) {
+                return Err(VkError::custom(format!(
+                    "Push constant ranges at offset {} and {} include the same shader stage {:?}.", other.offset, range.offset, other.stage_flags & range.stage_flags)))
+            }
+        }
+
+        Ok(self.ranges.clone())
+    }
+}
+// ----------------------------------------------------------------------------------------------
+
 // ----------------------------------------------------------------------------------------------
 /// Wrapper class for vk::FramebufferCreateInfo.
 #[derive(Debug, Clone)]
@@ -163,7 +314,7 @@ impl VkObjectBuildableCI for FramebufferCI {
 
         let framebuffer = unsafe {
             device.logic.handle.create_framebuffer(self.as_ref(), None)
-                .map_err(|_| VkError::create("Framebuffer"))?
+                .map_err(|e| VkError::create_with("Framebuffer", e))?
         };
         Ok(framebuffer)
     }
@@ -247,6 +398,12 @@ pub struct GraphicsPipelineCI<'a> {
 
     cache: Option<vk::PipelineCache>,
     shader_stages: Vec<vk::PipelineShaderStageCreateInfo>,
+    /// the stages whose entry point is not declared in their shader module, and the error messages.
+    entry_point_errors: Vec<(vk::ShaderStageFlags, String)>,
+    /// whether the last stage before rasterization declares an invariant position, or None if the stage is not reflected.
+    position_invariance: Option<bool>,
+    /// the attachment formats of dynamic rendering, or None if the pipeline is used in a render pass.
+    rendering: Option<PipelineRenderingCI>,
 
     phantom_type: ::std::marker::PhantomData<&'a ()>,
 }
@@ -256,24 +413,13 @@ impl<'a> VkObjectBuildableCI for GraphicsPipelineCI<'a> {
 
     fn build(&self, device: &VkDevice) -> VkResult<Self::ObjectType> {
 
-        let pipeline_ci = vk::GraphicsPipelineCreateInfo {
-            stage_count            : self.shader_stages.len() as _,
-            p_stages               : self.shader_stages.as_ptr(),
-            p_vertex_input_state   : self.vertex_input.as_ref(),
-            p_input_assembly_state : self.input_assembly.as_ref(),
-            p_tessellation_state   : ptr::null(), // this field is not cover yet.
-            p_viewport_state       : self.viewport.as_ref(),
-            p_rasterization_state  : self.rasterization.as_ref(),
-            p_multisample_state    : self.multisample.as_ref(),
-            p_depth_stencil_state  : self.depth_stencil.as_ref(),
-            p_color_blend_state    : self.color_blend.as_ref(),
-            p_dynamic_state        : self.dynamics.as_ref(),
-            ..self.inner
-        };
+        self.check_stage_features(device)?;
+
+        let pipeline_ci = self.inner_pipeline_ci();
 
         let pipeline = unsafe {
-            device.logic.handle.create_graphics_pipelines(self.cache.unwrap_or(device.pipeline_cache), &[pipeline_ci], None)
-                .map_err(|_| VkError::create("Graphics Pipeline"))?
+            device.logic.handle.create_graphics_pipelines(self.inner_pipeline_cache(device), &[pipeline_ci], None)
+                .map_err(|(_, e)| VkError::create_with("Graphics Pipeline", e))?
         }.remove(0);
 
         Ok(pipeline)
@@ -292,6 +438,8 @@ impl<'b, 'a: 'b> GraphicsPipelineCI<'a> {
                 ..Default::default()
             },
             shader_stages  : Vec::new(),
+            entry_point_errors: Vec::new(),
+            position_invariance: None,
             vertex_input   : VertexInputSCI::new(),
             input_assembly : InputAssemblySCI::new(),
             rasterization  : RasterizationSCI::new(),
@@ -301,10 +449,21 @@ impl<'b, 'a: 'b> GraphicsPipelineCI<'a> {
             multisample    : MultisampleSCI::new(),
             dynamics       : DynamicSCI::new(),
             cache: None,
+            rendering: None,
             phantom_type: ::std::marker::PhantomData,
         }
     }
 
+    /// Create a pipeline used in dynamic rendering(see `CmdGraphicsApi::begin_rendering`), whose attachment formats are given by `rendering` instead of a render pass.
+    ///
+    /// Building the pipeline returns an `UnSupport` error if `VK_KHR_dynamic_rendering` is not enabled(see `VkPhysicalDevice::is_dynamic_rendering_support`).
+    pub fn new_dynamic_rendering(pipeline_layout: vk::PipelineLayout, rendering: PipelineRenderingCI) -> GraphicsPipelineCI<'a> {
+
+        let mut pipeline_ci = GraphicsPipelineCI::new(vk::RenderPass::null(), pipeline_layout);
+        pipeline_ci.rendering = Some(rendering);
+        pipeline_ci
+    }
+
     #[inline(always)]
     pub fn set_use_subpass(&mut self, subpass: vkuint) {
         self.inner.subpass = subpass
@@ -320,12 +479,28 @@ impl<'b, 'a: 'b> GraphicsPipelineCI<'a> {
         self.inner.flags = flags;
     }
 
+    /// Set the shader stages of pipeline(etc. `vk::ShaderStageFlags::VERTEX`, `GEOMETRY`, `FRAGMENT`).
+    ///
+    /// A geometry stage requires the `geometry_shader` feature of device, which can be requested by `PhysicalDevConfig::request_features`.
+    /// Otherwise the pipeline building will return an `UnSupport` error instead of creating an invalid pipeline.
+    ///
+    /// The entry point of each stage is also checked before building(see `ShaderStageCI::check_entry_point`).
     #[inline(always)]
     pub fn set_shaders(&mut self, cis: &'b [ShaderStageCI]) {
 
         self.shader_stages = cis.iter()
-            .map(|s| s.as_ref().clone())
+            .map(|s| s.inner_stage_ci())
+            .collect();
+
+        self.entry_point_errors = cis.iter()
+            .filter_map(|s| s.check_entry_point().err().map(|e| (s.as_ref().stage, e.to_string())))
             .collect();
+
+        // the position is output by geometry stage if it exists, otherwise by vertex stage.
+        self.position_invariance = [vk::ShaderStageFlags::GEOMETRY, vk::ShaderStageFlags::VERTEX].iter()
+            .filter_map(|&stage| cis.iter().find(|s| s.as_ref().stage == stage))
+            .next()
+            .and_then(|s| s.is_position_invariant());
     }
 
     #[inline(always)]
@@ -333,6 +508,12 @@ impl<'b, 'a: 'b> GraphicsPipelineCI<'a> {
         self.vertex_input = sci;
     }
 
+    /// Get the vertex input state, which can be validated against the vertex buffers before building(etc. `MeshResource::check_pipeline`).
+    #[inline(always)]
+    pub fn vertex_input(&self) -> &VertexInputSCI {
+        &self.vertex_input
+    }
+
     #[inline(always)]
     pub fn set_input_assembly(&mut self, sci: InputAssemblySCI) {
         self.input_assembly = sci;
@@ -372,6 +553,98 @@ impl<'b, 'a: 'b> GraphicsPipelineCI<'a> {
     pub fn set_pipeline_cache(&mut self, cache: vk::PipelineCache) {
         self.cache = Some(cache);
     }
+
+    /// Turn this CI into the depth-only pipeline of a depth pre-pass, whose depth-stencil state is replaced by `depth_stencil`.
+    ///
+    /// The fragment stage is removed and the color writes of all attachments are disabled.
+    /// All the other states and shader stages are kept, so the pre-pass transforms vertices exactly as the pipeline built before this call,
+    /// and their depth values are equal if the vertex shader declares `invariant gl_Position`(see `GraphicsPipelineCI::check_invariant_position`).
+    /// The fragment shader of color pass should not discard fragments or write `gl_FragDepth`, since the pre-pass does not run it.
+    pub fn set_depth_prepass(&mut self, depth_stencil: DepthStencilSCI) {
+
+        self.shader_stages.retain(|stage| stage.stage != vk::ShaderStageFlags::FRAGMENT);
+        self.entry_point_errors.retain(|(stage, _)| *stage != vk::ShaderStageFlags::FRAGMENT);
+
+        let attachment_count = self.color_blend.as_ref().attachment_count;
+        self.color_blend = (0..attachment_count)
+            .fold(ColorBlendSCI::new(), |color_blend, _| {
+                color_blend.add_attachment(BlendAttachmentSCI::new().color_write_mask(vk::ColorComponentFlags::empty()))
+            });
+        self.depth_stencil = depth_stencil;
+    }
+
+    /// Check that the pipelines built from this CI transform vertices to the same depth values, which is required by a depth pre-pass.
+    ///
+    /// Different pipelines may compile the same vertex shader differently, so the last stage before rasterization
+    /// must declare `invariant gl_Position`. This returns an error if the stage is reflected(see `ShaderStageCI::reflection`)
+    /// and its position is not invariant, and prints a warning if the stage is not reflected.
+    pub fn check_invariant_position(&self) -> VkResult<()> {
+
+        match self.position_invariance {
+            | Some(true) => Ok(()),
+            | Some(false) => {
+                Err(VkError::custom("The vertex transform does not declare `invariant gl_Position`, so the depth values may differ between pipelines."))
+            },
+            | None => {
+                println!("[Warning] The invariance of position is not checked, since the vertex shader is not reflected(see ShaderStageCI::reflection).");
+                Ok(())
+            },
+        }
+    }
+
+    // For crate inner use.
+    #[doc(hidden)]
+    pub(crate) fn inner_pipeline_ci(&self) -> vk::GraphicsPipelineCreateInfo {
+
+        vk::GraphicsPipelineCreateInfo {
+            stage_count            : self.shader_stages.len() as _,
+            p_stages               : self.shader_stages.as_ptr(),
+            p_vertex_input_state   : self.vertex_input.as_ref(),
+            p_input_assembly_state : self.input_assembly.as_ref(),
+            p_tessellation_state   : ptr::null(), // this field is not cover yet.
+            p_viewport_state       : self.viewport.as_ref(),
+            p_rasterization_state  : self.rasterization.as_ref(),
+            p_multisample_state    : self.multisample.as_ref(),
+            p_depth_stencil_state  : self.depth_stencil.as_ref(),
+            p_color_blend_state    : self.color_blend.as_ref(),
+            p_dynamic_state        : self.dynamics.as_ref(),
+            p_next: self.rendering.as_ref()
+                .map_or(ptr::null(), |rendering| rendering.as_ref() as *const rendering::PipelineRenderingCreateInfoKHR as _),
+            ..self.inner
+        }
+    }
+
+    // For crate inner use.
+    #[doc(hidden)]
+    pub(crate) fn check_stage_features(&self, device: &VkDevice) -> VkResult<()> {
+
+        let is_geometry_required = self.shader_stages.iter()
+            .any(|stage| stage.stage.contains(vk::ShaderStageFlags::GEOMETRY));
+
+        if is_geometry_required && device.phy.features_enabled().geometry_shader != vk::TRUE {
+            return Err(VkError::unsupported("Geometry Shader"))
+        }
+
+        if self.rendering.is_some() && device.logic.dynamic_rendering().is_none() {
+            return Err(VkError::unsupported("Dynamic Rendering"))
+        }
+
+        if let Some((_, message)) = self.entry_point_errors.first() {
+            return Err(VkError::custom(message.clone()))
+        }
+
+        self.input_assembly.check_primitive_restart()?;
+        self.rasterization.check_features(device)?;
+        self.multisample.check_features(device)?;
+
+        Ok(())
+    }
+
+    // For crate inner use.
+    #[doc(hidden)]
+    pub(crate) fn inner_pipeline_cache(&self, device: &VkDevice) -> vk::PipelineCache {
+        self.cache.unwrap_or(device.pipeline_cache)
+    }
 }
 
 impl VkObjectDiscardable for vk::Pipeline {
@@ -384,6 +657,82 @@ impl VkObjectDiscardable for vk::Pipeline {
 }
 // ----------------------------------------------------------------------------------------------
 
+// ----------------------------------------------------------------------------------------------
+// Wrapper class for vk::ComputePipelineCreateInfo.
+#[derive(Debug)]
+pub struct ComputePipelineCI<'a> {
+
+    inner: vk::ComputePipelineCreateInfo,
+
+    cache: Option<vk::PipelineCache>,
+    /// the error message if the entry point is not declared in the shader module.
+    entry_point_error: Option<String>,
+
+    phantom_type: ::std::marker::PhantomData<&'a ()>,
+}
+
+impl<'a> VkObjectBuildableCI for ComputePipelineCI<'a> {
+    type ObjectType = vk::Pipeline;
+
+    fn build(&self, device: &VkDevice) -> VkResult<Self::ObjectType> {
+
+        if self.inner.stage.stage != vk::ShaderStageFlags::COMPUTE {
+            return Err(VkError::custom(format!("The stage of compute pipeline must be COMPUTE, but {:?} is given.", self.inner.stage.stage)))
+        }
+
+        if let Some(message) = self.entry_point_error.as_ref() {
+            return Err(VkError::custom(message.clone()))
+        }
+
+        let cache = self.cache.unwrap_or(device.pipeline_cache);
+
+        let pipeline = unsafe {
+            device.logic.handle.create_compute_pipelines(cache, &[self.inner], None)
+                .map_err(|(_, e)| VkError::create_with("Compute Pipeline", e))?
+        }.remove(0);
+
+        Ok(pipeline)
+    }
+}
+
+impl<'a> ComputePipelineCI<'a> {
+
+    /// Create a compute pipeline from the shader of `stage`, which must be a `vk::ShaderStageFlags::COMPUTE` stage.
+    ///
+    /// The entry point of stage is checked before building(see `ShaderStageCI::check_entry_point`).
+    pub fn new(stage: &'a ShaderStageCI, pipeline_layout: vk::PipelineLayout) -> ComputePipelineCI<'a> {
+
+        ComputePipelineCI {
+            inner: vk::ComputePipelineCreateInfo {
+                stage: stage.inner_stage_ci(),
+                layout: pipeline_layout,
+                base_pipeline_index: -1,
+                ..Default::default()
+            },
+            cache: None,
+            entry_point_error: stage.check_entry_point().err().map(|e| e.to_string()),
+            phantom_type: ::std::marker::PhantomData,
+        }
+    }
+
+    #[inline(always)]
+    pub fn flags(mut self, flags: vk::PipelineCreateFlags) -> ComputePipelineCI<'a> {
+        self.inner.flags = flags; self
+    }
+
+    #[inline(always)]
+    pub fn base_pipeline(mut self, pipeline: vk::Pipeline) -> ComputePipelineCI<'a> {
+        self.inner.base_pipeline_handle = pipeline; self
+    }
+
+    /// Create the pipeline with `cache` instead of the pipeline cache of device.
+    #[inline(always)]
+    pub fn pipeline_cache(mut self, cache: vk::PipelineCache) -> ComputePipelineCI<'a> {
+        self.cache = Some(cache); self
+    }
+}
+// ----------------------------------------------------------------------------------------------
+
 // ----------------------------------------------------------------------------------------------
 // Wrapper class for vk::PipelineCacheCreateInfo.
 #[derive(Debug, Clone)]
@@ -429,7 +778,7 @@ impl PipelineCacheCI {
     pub fn build(&self, device: &VkDevice) -> VkResult<vk::PipelineCache> {
         unsafe {
             device.logic.handle.create_pipeline_cache(self.as_ref(), None)
-                .map_err(|_| VkError::create("Graphics Cache"))
+                .map_err(|e| VkError::create_with("Graphics Cache", e))
         }
     }
 }
@@ -443,3 +792,27 @@ impl VkObjectDiscardable for vk::PipelineCache {
     }
 }
 // ----------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn push_constants_must_be_covered_by_declared_ranges() {
+
+        let ranges = [
+            vk::PushConstantRange { stage_flags: vk::ShaderStageFlags::VERTEX,   offset: 0,  size: 64 },
+            vk::PushConstantRange { stage_flags: vk::ShaderStageFlags::FRAGMENT, offset: 64, size: 16 },
+        ];
+
+        assert!(is_push_constants_declared(&ranges, vk::ShaderStageFlags::VERTEX, 0, 64));
+        assert!(is_push_constants_declared(&ranges, vk::ShaderStageFlags::FRAGMENT, 68, 8));
+
+        // the data exceeds the end of range, or the range is declared for another stage.
+        assert!(!is_push_constants_declared(&ranges, vk::ShaderStageFlags::VERTEX, 16, 64));
+        assert!(!is_push_constants_declared(&ranges, vk::ShaderStageFlags::FRAGMENT, 0, 16));
+        // the layout without push constant ranges accepts nothing.
+        assert!(!is_push_constants_declared(&[], vk::ShaderStageFlags::VERTEX, 0, 4));
+    }
+}
@@ -0,0 +1,375 @@
+
+use ash::vk;
+
+use crate::ci::VkObjectBuildableCI;
+use crate::ci::pipeline::{RenderPassCI, AttachmentDescCI, SubpassDescCI, SubpassDependencyCI};
+use crate::context::VkDevice;
+use crate::error::VkResult;
+use crate::vkuint;
+
+// ----------------------------------------------------------------------------------------------
+/// The handle of an attachment declared in `RenderGraph`, which is also its attachment index in render pass.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct GraphAttachment(vkuint);
+
+impl GraphAttachment {
+
+    /// Get the attachment index in render pass, which is also the index of its clear value and framebuffer view.
+    #[inline(always)]
+    pub fn index(&self) -> vkuint {
+        self.0
+    }
+}
+
+#[derive(Debug, Clone)]
+struct AttachmentNode {
+    format: vk::Format,
+    /// the layout after render pass, or None if the content is discarded after render pass.
+    output_layout: Option<vk::ImageLayout>,
+}
+
+/// How a pass uses an attachment.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum AttachmentAccess {
+    ColorWrite,
+    DepthWrite,
+    DepthRead,
+    InputRead,
+}
+
+impl AttachmentAccess {
+
+    fn is_write(&self) -> bool {
+        match self {
+            | AttachmentAccess::ColorWrite
+            | AttachmentAccess::DepthWrite => true,
+            | AttachmentAccess::DepthRead
+            | AttachmentAccess::InputRead => false,
+        }
+    }
+
+    fn stage(&self) -> vk::PipelineStageFlags {
+        match self {
+            | AttachmentAccess::ColorWrite => vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+            | AttachmentAccess::DepthWrite
+            | AttachmentAccess::DepthRead  => vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS | vk::PipelineStageFlags::LATE_FRAGMENT_TESTS,
+            | AttachmentAccess::InputRead  => vk::PipelineStageFlags::FRAGMENT_SHADER,
+        }
+    }
+
+    fn access(&self) -> vk::AccessFlags {
+        match self {
+            | AttachmentAccess::ColorWrite => vk::AccessFlags::COLOR_ATTACHMENT_READ | vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+            | AttachmentAccess::DepthWrite => vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_READ | vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE,
+            | AttachmentAccess::DepthRead  => vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_READ,
+            | AttachmentAccess::InputRead  => vk::AccessFlags::INPUT_ATTACHMENT_READ,
+        }
+    }
+}
+// ----------------------------------------------------------------------------------------------
+
+// ----------------------------------------------------------------------------------------------
+/// The attachments read and written by one pass of `RenderGraph`, which becomes a subpass of render pass.
+#[derive(Debug, Clone, Default)]
+pub struct GraphPass {
+    uses: Vec<(GraphAttachment, AttachmentAccess)>,
+}
+
+impl GraphPass {
+
+    #[inline(always)]
+    pub fn new() -> GraphPass {
+        Default::default()
+    }
+
+    /// Render to `attachment` as the next color attachment of this pass(the location of fragment shader output follows the calling order).
+    #[inline]
+    pub fn write_color(mut self, attachment: GraphAttachment) -> GraphPass {
+        self.uses.push((attachment, AttachmentAccess::ColorWrite)); self
+    }
+
+    /// Use `attachment` as depth stencil attachment with depth writes.
+    #[inline]
+    pub fn write_depth(mut self, attachment: GraphAttachment) -> GraphPass {
+        self.uses.push((attachment, AttachmentAccess::DepthWrite)); self
+    }
+
+    /// Use `attachment` as read-only depth stencil attachment(depth test without depth writes).
+    #[inline]
+    pub fn read_depth(mut self, attachment: GraphAttachment) -> GraphPass {
+        self.uses.push((attachment, AttachmentAccess::DepthRead)); self
+    }
+
+    /// Read `attachment` written by previous passes as the next input attachment(`subpassInput` in shader).
+    #[inline]
+    pub fn read_input(mut self, attachment: GraphAttachment) -> GraphPass {
+        self.uses.push((attachment, AttachmentAccess::InputRead)); self
+    }
+
+    fn access_of(&self, attachment: GraphAttachment) -> Option<AttachmentAccess> {
+        self.uses.iter()
+            .find(|(a, _)| *a == attachment)
+            .map(|(_, access)| *access)
+    }
+}
+// ----------------------------------------------------------------------------------------------
+
+// ----------------------------------------------------------------------------------------------
+/// A linear render graph, which declares the passes with the attachments they read and write,
+/// and derives the attachment descriptions, subpasses and subpass dependencies of a single render pass.
+///
+/// The passes are executed in the order of `RenderGraph::add_pass` on a single queue.
+/// Each attachment is cleared at its first use. Output attachments are stored after render pass,
+/// while transient attachments are discarded, so they can use `RenderGraph::image_usage` to create lazily allocated images.
+///
+/// ```ignore
+/// let mut graph = RenderGraph::new();
+/// let color = graph.add_output(swapchain.backend_format, vk::ImageLayout::PRESENT_SRC_KHR);
+/// let depth = graph.add_transient(device.phy.depth_format);
+/// graph.add_pass(GraphPass::new().write_color(color).write_depth(depth));
+/// let render_pass = device.build(&graph)?;
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct RenderGraph {
+    attachments: Vec<AttachmentNode>,
+    passes: Vec<GraphPass>,
+}
+
+impl RenderGraph {
+
+    #[inline(always)]
+    pub fn new() -> RenderGraph {
+        Default::default()
+    }
+
+    /// Declare an attachment whose content is kept in `final_layout` after render pass(etc. swapchain image in `vk::ImageLayout::PRESENT_SRC_KHR`).
+    pub fn add_output(&mut self, format: vk::Format, final_layout: vk::ImageLayout) -> GraphAttachment {
+        self.add_attachment(format, Some(final_layout))
+    }
+
+    /// Declare an attachment that is only used inside render pass(etc. depth buffer, G-buffer), whose content is discarded after render pass.
+    pub fn add_transient(&mut self, format: vk::Format) -> GraphAttachment {
+        self.add_attachment(format, None)
+    }
+
+    fn add_attachment(&mut self, format: vk::Format, output_layout: Option<vk::ImageLayout>) -> GraphAttachment {
+        self.attachments.push(AttachmentNode { format, output_layout });
+        GraphAttachment((self.attachments.len() - 1) as _)
+    }
+
+    /// Append a pass to graph, and return its subpass index.
+    pub fn add_pass(&mut self, pass: GraphPass) -> vkuint {
+        self.passes.push(pass);
+        (self.passes.len() - 1) as _
+    }
+
+    /// Get the count of attachments declared, which is also the count of clear values and framebuffer views.
+    #[inline]
+    pub fn attachment_count(&self) -> usize {
+        self.attachments.len()
+    }
+
+    /// Check if the content of `attachment` is discarded after render pass.
+    #[inline]
+    pub fn is_transient(&self, attachment: GraphAttachment) -> bool {
+        self.attachments[attachment.0 as usize].output_layout.is_none()
+    }
+
+    /// Get the image usage required to create the image of `attachment`.
+    ///
+    /// Transient attachments contain `vk::ImageUsageFlags::TRANSIENT_ATTACHMENT`, so that their memory can be lazily allocated.
+    pub fn image_usage(&self, attachment: GraphAttachment) -> vk::ImageUsageFlags {
+
+        let mut usage = vk::ImageUsageFlags::empty();
+
+        for pass in self.passes.iter() {
+            usage |= match pass.access_of(attachment) {
+                | Some(AttachmentAccess::ColorWrite) => vk::ImageUsageFlags::COLOR_ATTACHMENT,
+                | Some(AttachmentAccess::DepthWrite)
+                | Some(AttachmentAccess::DepthRead)  => vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT,
+                | Some(AttachmentAccess::InputRead)  => vk::ImageUsageFlags::INPUT_ATTACHMENT,
+                | None => vk::ImageUsageFlags::empty(),
+            };
+        }
+
+        if self.is_transient(attachment) {
+            usage |= vk::ImageUsageFlags::TRANSIENT_ATTACHMENT;
+        }
+
+        usage
+    }
+
+    /// Generate the `RenderPassCI` of this graph.
+    pub fn render_pass_ci(&self) -> RenderPassCI {
+
+        let mut render_pass_ci = RenderPassCI::new();
+
+        for (i, attachment) in self.attachments.iter().enumerate() {
+            render_pass_ci = render_pass_ci.add_attachment(self.attachment_desc(GraphAttachment(i as _), attachment));
+        }
+
+        for (pass_index, pass) in self.passes.iter().enumerate() {
+            render_pass_ci = render_pass_ci.add_subpass(self.subpass_desc(pass_index, pass));
+        }
+
+        for dependency in self.dependencies() {
+            render_pass_ci = render_pass_ci.add_dependency(dependency);
+        }
+
+        render_pass_ci
+    }
+
+    fn attachment_desc(&self, attachment: GraphAttachment, node: &AttachmentNode) -> AttachmentDescCI {
+
+        let last_access = self.passes.iter().rev()
+            .filter_map(|pass| pass.access_of(attachment))
+            .next();
+        debug_assert!(last_access.is_some(), "Attachment {} is declared but not used by any pass of render graph.", attachment.0);
+
+        let store_op = if node.output_layout.is_some() { vk::AttachmentStoreOp::STORE } else { vk::AttachmentStoreOp::DONT_CARE };
+        // transient attachments stay in the layout of their last use.
+        let final_layout = node.output_layout
+            .unwrap_or_else(|| last_access.map_or(vk::ImageLayout::GENERAL, access_layout));
+
+        // the stencil operations are ignored for formats without stencil component.
+        AttachmentDescCI::new(node.format)
+            .op(vk::AttachmentLoadOp::CLEAR, store_op)
+            .stencil_op(vk::AttachmentLoadOp::CLEAR, store_op)
+            .layout(vk::ImageLayout::UNDEFINED, final_layout)
+    }
+
+    fn subpass_desc(&self, pass_index: usize, pass: &GraphPass) -> SubpassDescCI {
+
+        let mut subpass = SubpassDescCI::new(vk::PipelineBindPoint::GRAPHICS);
+
+        for &(attachment, access) in pass.uses.iter() {
+            subpass = match access {
+                | AttachmentAccess::ColorWrite => subpass.add_color_attachment(attachment.0, access_layout(access)),
+                | AttachmentAccess::DepthWrite
+                | AttachmentAccess::DepthRead  => subpass.set_depth_stencil_attachment(attachment.0, access_layout(access)),
+                | AttachmentAccess::InputRead  => {
+                    // a depth attachment read as input attachment stays in read-only depth layout.
+                    let is_depth = self.passes.iter().any(|p| match p.access_of(attachment) {
+                        | Some(AttachmentAccess::DepthWrite) | Some(AttachmentAccess::DepthRead) => true,
+                        | _ => false,
+                    });
+                    let layout = if is_depth { vk::ImageLayout::DEPTH_STENCIL_READ_ONLY_OPTIMAL } else { access_layout(access) };
+                    subpass.add_input_attachment(attachment.0, layout)
+                },
+            };
+        }
+
+        // the attachments not used by this pass must be preserved if they are used both before and after this pass.
+        for i in 0..self.attachments.len() {
+
+            let attachment = GraphAttachment(i as _);
+            if pass.access_of(attachment).is_some() {
+                continue
+            }
+
+            let is_used_before = self.passes[..pass_index].iter().any(|p| p.access_of(attachment).is_some());
+            let is_used_after  = self.passes[(pass_index + 1)..].iter().any(|p| p.access_of(attachment).is_some());
+            if is_used_before && is_used_after {
+                subpass = subpass.add_preserve_attachment(attachment.0);
+            }
+        }
+
+        subpass
+    }
+
+    fn dependencies(&self) -> Vec<SubpassDependencyCI> {
+
+        let mut dependencies = Vec::new();
+
+        if self.passes.is_empty() {
+            return dependencies
+        }
+
+        // wait for the previous use of the attachments(etc. presentation engine reading the swapchain image).
+        let (first_stage, first_access) = self.passes.iter()
+            .flat_map(|pass| pass.uses.iter())
+            .fold((vk::PipelineStageFlags::empty(), vk::AccessFlags::empty()), |(stage, access), (_, a)| {
+                (stage | a.stage(), access | a.access())
+            });
+        dependencies.push(SubpassDependencyCI::new(vk::SUBPASS_EXTERNAL, 0)
+            .stage_mask(vk::PipelineStageFlags::BOTTOM_OF_PIPE, first_stage)
+            .access_mask(vk::AccessFlags::MEMORY_READ, first_access)
+            .flags(vk::DependencyFlags::BY_REGION));
+
+        // the dependencies between passes, derived from the last writer of each attachment they use.
+        for (dst_index, dst_pass) in self.passes.iter().enumerate().skip(1) {
+
+            let mut src_masks: Vec<Option<(vk::PipelineStageFlags, vk::PipelineStageFlags, vk::AccessFlags, vk::AccessFlags)>> = vec![None; dst_index];
+
+            for &(attachment, dst_access) in dst_pass.uses.iter() {
+
+                let last_writer = self.passes[..dst_index].iter().enumerate().rev()
+                    .filter_map(|(i, p)| p.access_of(attachment).filter(|a| a.is_write()).map(|a| (i, a)))
+                    .next();
+
+                if let Some((src_index, src_access)) = last_writer {
+                    let masks = src_masks[src_index].get_or_insert((vk::PipelineStageFlags::empty(), vk::PipelineStageFlags::empty(), vk::AccessFlags::empty(), vk::AccessFlags::empty()));
+                    masks.0 |= src_access.stage();
+                    masks.1 |= dst_access.stage();
+                    masks.2 |= src_access.access();
+                    masks.3 |= dst_access.access();
+                }
+            }
+
+            for (src_index, masks) in src_masks.into_iter().enumerate() {
+                if let Some((src_stage, dst_stage, src_access, dst_access)) = masks {
+                    dependencies.push(SubpassDependencyCI::new(src_index as _, dst_index as _)
+                        .stage_mask(src_stage, dst_stage)
+                        .access_mask(src_access, dst_access)
+                        .flags(vk::DependencyFlags::BY_REGION));
+                }
+            }
+        }
+
+        // make the outputs available to the commands after render pass.
+        for (i, attachment) in self.attachments.iter().enumerate() {
+
+            if let Some(output_layout) = attachment.output_layout {
+
+                let last_writer = self.passes.iter().enumerate().rev()
+                    .filter_map(|(pass_index, p)| p.access_of(GraphAttachment(i as _)).filter(|a| a.is_write()).map(|a| (pass_index, a)))
+                    .next();
+
+                if let Some((src_index, src_access)) = last_writer {
+
+                    let (dst_stage, dst_access) = if output_layout == vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL {
+                        (vk::PipelineStageFlags::FRAGMENT_SHADER, vk::AccessFlags::SHADER_READ)
+                    } else {
+                        (vk::PipelineStageFlags::BOTTOM_OF_PIPE, vk::AccessFlags::MEMORY_READ)
+                    };
+
+                    dependencies.push(SubpassDependencyCI::new(src_index as _, vk::SUBPASS_EXTERNAL)
+                        .stage_mask(src_access.stage(), dst_stage)
+                        .access_mask(src_access.access(), dst_access)
+                        .flags(vk::DependencyFlags::BY_REGION));
+                }
+            }
+        }
+
+        dependencies
+    }
+}
+
+impl VkObjectBuildableCI for RenderGraph {
+    type ObjectType = vk::RenderPass;
+
+    fn build(&self, device: &VkDevice) -> VkResult<Self::ObjectType> {
+        self.render_pass_ci().build(device)
+    }
+}
+
+/// The image layout of attachment during a pass.
+fn access_layout(access: AttachmentAccess) -> vk::ImageLayout {
+    match access {
+        | AttachmentAccess::ColorWrite => vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+        | AttachmentAccess::DepthWrite => vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
+        | AttachmentAccess::DepthRead  => vk::ImageLayout::DEPTH_STENCIL_READ_ONLY_OPTIMAL,
+        | AttachmentAccess::InputRead  => vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+    }
+}
+// ----------------------------------------------------------------------------------------------
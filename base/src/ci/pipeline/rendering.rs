@@ -0,0 +1,385 @@
+//! Dynamic rendering(`VK_KHR_dynamic_rendering`), which renders into image views directly without `vk::RenderPass` and `vk::Framebuffer`.
+//!
+//! The pinned ash predates this extension, so its structures are declared here by the layout of Vulkan headers,
+//! and its commands are loaded when the logical device enables `DeviceExtensionType::DynamicRendering`.
+
+use ash::vk;
+use ash::version::{InstanceV1_0, DeviceV1_0};
+
+use crate::ci::VulkanCI;
+use crate::error::{VkResult, VkError};
+use crate::vkuint;
+
+use std::ffi::CString;
+use std::os::raw::c_void;
+use std::mem;
+use std::ptr;
+
+// ----------------------------------------------------------------------------------------------
+const STRUCTURE_TYPE_RENDERING_INFO_KHR: i32 = 1000044000;
+const STRUCTURE_TYPE_RENDERING_ATTACHMENT_INFO_KHR: i32 = 1000044001;
+const STRUCTURE_TYPE_PIPELINE_RENDERING_CREATE_INFO_KHR: i32 = 1000044002;
+const STRUCTURE_TYPE_PHYSICAL_DEVICE_DYNAMIC_RENDERING_FEATURES_KHR: i32 = 1000044003;
+
+/// `VkRenderingAttachmentInfoKHR`.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct RenderingAttachmentInfoKHR {
+    pub s_type: vk::StructureType,
+    pub p_next: *const c_void,
+    pub image_view: vk::ImageView,
+    pub image_layout: vk::ImageLayout,
+    /// `VkResolveModeFlagBits` of `VK_KHR_depth_stencil_resolve`, where 0 means no resolve.
+    pub resolve_mode: vkuint,
+    pub resolve_image_view: vk::ImageView,
+    pub resolve_image_layout: vk::ImageLayout,
+    pub load_op: vk::AttachmentLoadOp,
+    pub store_op: vk::AttachmentStoreOp,
+    pub clear_value: vk::ClearValue,
+}
+
+/// `VkRenderingInfoKHR`.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct RenderingInfoKHR {
+    pub s_type: vk::StructureType,
+    pub p_next: *const c_void,
+    /// `VkRenderingFlagsKHR`.
+    pub flags: vkuint,
+    pub render_area: vk::Rect2D,
+    pub layer_count: vkuint,
+    pub view_mask: vkuint,
+    pub color_attachment_count: vkuint,
+    pub p_color_attachments: *const RenderingAttachmentInfoKHR,
+    pub p_depth_attachment: *const RenderingAttachmentInfoKHR,
+    pub p_stencil_attachment: *const RenderingAttachmentInfoKHR,
+}
+
+/// `VkPipelineRenderingCreateInfoKHR`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct PipelineRenderingCreateInfoKHR {
+    pub s_type: vk::StructureType,
+    pub p_next: *const c_void,
+    pub view_mask: vkuint,
+    pub color_attachment_count: vkuint,
+    pub p_color_attachment_formats: *const vk::Format,
+    pub depth_attachment_format: vk::Format,
+    pub stencil_attachment_format: vk::Format,
+}
+
+/// `VkPhysicalDeviceDynamicRenderingFeaturesKHR`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct PhysicalDeviceDynamicRenderingFeaturesKHR {
+    pub s_type: vk::StructureType,
+    pub p_next: *mut c_void,
+    pub dynamic_rendering: vk::Bool32,
+}
+
+impl Default for PhysicalDeviceDynamicRenderingFeaturesKHR {
+
+    fn default() -> PhysicalDeviceDynamicRenderingFeaturesKHR {
+        PhysicalDeviceDynamicRenderingFeaturesKHR {
+            s_type: vk::StructureType::from_raw(STRUCTURE_TYPE_PHYSICAL_DEVICE_DYNAMIC_RENDERING_FEATURES_KHR),
+            p_next: ptr::null_mut(),
+            dynamic_rendering: vk::FALSE,
+        }
+    }
+}
+
+#[allow(non_camel_case_types)]
+type PFN_vkCmdBeginRenderingKHR = unsafe extern "system" fn(command: vk::CommandBuffer, rendering_info: *const RenderingInfoKHR);
+#[allow(non_camel_case_types)]
+type PFN_vkCmdEndRenderingKHR   = unsafe extern "system" fn(command: vk::CommandBuffer);
+
+/// The commands of `VK_KHR_dynamic_rendering` loaded from logical device.
+#[derive(Clone, Copy)]
+pub struct DynamicRenderingFn {
+    cmd_begin_rendering: PFN_vkCmdBeginRenderingKHR,
+    cmd_end_rendering  : PFN_vkCmdEndRenderingKHR,
+}
+
+impl DynamicRenderingFn {
+
+    pub(crate) fn load(instance: &ash::Instance, device: &ash::Device) -> VkResult<DynamicRenderingFn> {
+
+        let load_command = |name: &str| {
+            let name = CString::new(name).unwrap();
+            unsafe {
+                instance.fp_v1_0().get_device_proc_addr(device.handle(), name.as_ptr())
+            }.ok_or(VkError::unsupported("Dynamic Rendering"))
+        };
+
+        let begin_rendering = load_command("vkCmdBeginRenderingKHR")?;
+        let end_rendering   = load_command("vkCmdEndRenderingKHR")?;
+
+        let result = unsafe {
+            DynamicRenderingFn {
+                cmd_begin_rendering: mem::transmute(begin_rendering),
+                cmd_end_rendering  : mem::transmute(end_rendering),
+            }
+        };
+        Ok(result)
+    }
+
+    #[inline]
+    pub(crate) unsafe fn cmd_begin_rendering(&self, command: vk::CommandBuffer, bi: &RenderingBI) {
+        let rendering_info = bi.inner_rendering_info();
+        (self.cmd_begin_rendering)(command, &rendering_info);
+    }
+
+    #[inline]
+    pub(crate) unsafe fn cmd_end_rendering(&self, command: vk::CommandBuffer) {
+        (self.cmd_end_rendering)(command);
+    }
+}
+// ----------------------------------------------------------------------------------------------
+
+// ----------------------------------------------------------------------------------------------
+/// Wrapper class for `VkRenderingAttachmentInfoKHR`.
+#[derive(Clone, Copy)]
+pub struct RenderingAttachmentCI {
+    inner: RenderingAttachmentInfoKHR,
+}
+
+impl VulkanCI<RenderingAttachmentInfoKHR> for RenderingAttachmentCI {
+
+    fn default_ci() -> RenderingAttachmentInfoKHR {
+
+        RenderingAttachmentInfoKHR {
+            s_type: vk::StructureType::from_raw(STRUCTURE_TYPE_RENDERING_ATTACHMENT_INFO_KHR),
+            p_next: ptr::null(),
+            image_view: vk::ImageView::null(),
+            image_layout: vk::ImageLayout::UNDEFINED,
+            resolve_mode: 0,
+            resolve_image_view: vk::ImageView::null(),
+            resolve_image_layout: vk::ImageLayout::UNDEFINED,
+            load_op: vk::AttachmentLoadOp::DONT_CARE,
+            store_op: vk::AttachmentStoreOp::STORE,
+            clear_value: vk::ClearValue { color: vk::ClearColorValue { float32: [0.0; 4] } },
+        }
+    }
+}
+
+impl AsRef<RenderingAttachmentInfoKHR> for RenderingAttachmentCI {
+
+    fn as_ref(&self) -> &RenderingAttachmentInfoKHR {
+        &self.inner
+    }
+}
+
+impl RenderingAttachmentCI {
+
+    /// Initialize an attachment rendering into `view`, which must be in `layout` during rendering.
+    ///
+    /// Its content is loaded by `vk::AttachmentLoadOp::DONT_CARE` and stored by `vk::AttachmentStoreOp::STORE` by default.
+    pub fn new(view: vk::ImageView, layout: vk::ImageLayout) -> RenderingAttachmentCI {
+
+        RenderingAttachmentCI {
+            inner: RenderingAttachmentInfoKHR {
+                image_view: view,
+                image_layout: layout,
+                ..RenderingAttachmentCI::default_ci()
+            },
+        }
+    }
+
+    #[inline(always)]
+    pub fn op(mut self, load: vk::AttachmentLoadOp, store: vk::AttachmentStoreOp) -> RenderingAttachmentCI {
+        self.inner.load_op = load;
+        self.inner.store_op = store; self
+    }
+
+    /// Clear the attachment to `value` at the beginning of rendering, which sets the load operation to `vk::AttachmentLoadOp::CLEAR`.
+    #[inline(always)]
+    pub fn clear(mut self, value: vk::ClearValue) -> RenderingAttachmentCI {
+        self.inner.load_op = vk::AttachmentLoadOp::CLEAR;
+        self.inner.clear_value = value; self
+    }
+
+    /// Resolve the multisample attachment into `view` at the end of rendering.
+    ///
+    /// `mode` is a `VkResolveModeFlagBits` value(etc. 0x1 for `VK_RESOLVE_MODE_SAMPLE_ZERO_BIT`, 0x2 for `VK_RESOLVE_MODE_AVERAGE_BIT`).
+    #[inline(always)]
+    pub fn resolve(mut self, view: vk::ImageView, layout: vk::ImageLayout, mode: vkuint) -> RenderingAttachmentCI {
+        self.inner.resolve_image_view = view;
+        self.inner.resolve_image_layout = layout;
+        self.inner.resolve_mode = mode; self
+    }
+}
+// ----------------------------------------------------------------------------------------------
+
+// ----------------------------------------------------------------------------------------------
+/// Wrapper class for `VkRenderingInfoKHR`, which is used to begin dynamic rendering(see `CmdGraphicsApi::begin_rendering`).
+#[derive(Clone)]
+pub struct RenderingBI {
+
+    inner: RenderingInfoKHR,
+    colors : Vec<RenderingAttachmentInfoKHR>,
+    depth  : Option<RenderingAttachmentInfoKHR>,
+    stencil: Option<RenderingAttachmentInfoKHR>,
+}
+
+impl VulkanCI<RenderingInfoKHR> for RenderingBI {
+
+    fn default_ci() -> RenderingInfoKHR {
+
+        RenderingInfoKHR {
+            s_type: vk::StructureType::from_raw(STRUCTURE_TYPE_RENDERING_INFO_KHR),
+            p_next: ptr::null(),
+            flags: 0,
+            render_area: vk::Rect2D {
+                extent: vk::Extent2D { width: 0, height: 0 },
+                offset: vk::Offset2D { x: 0, y: 0 },
+            },
+            layer_count: 1,
+            view_mask: 0,
+            color_attachment_count: 0,
+            p_color_attachments : ptr::null(),
+            p_depth_attachment  : ptr::null(),
+            p_stencil_attachment: ptr::null(),
+        }
+    }
+}
+
+impl AsRef<RenderingInfoKHR> for RenderingBI {
+
+    fn as_ref(&self) -> &RenderingInfoKHR {
+        &self.inner
+    }
+}
+
+impl RenderingBI {
+
+    /// Initialize `VkRenderingInfoKHR` with the dimension of render area.
+    pub fn new(area: vk::Extent2D) -> RenderingBI {
+
+        let mut inner = RenderingBI::default_ci();
+        inner.render_area.extent = area;
+
+        RenderingBI { inner, colors: Vec::new(), depth: None, stencil: None }
+    }
+
+    /// Set the offset of render area. Default is 0 for both x, y coordinates.
+    #[inline(always)]
+    pub fn render_area_offset(mut self, offset: vk::Offset2D) -> RenderingBI {
+        self.inner.render_area.offset = offset; self
+    }
+
+    /// Set the count of layers rendered to. Default is 1.
+    #[inline(always)]
+    pub fn layer_count(mut self, count: vkuint) -> RenderingBI {
+        self.inner.layer_count = count; self
+    }
+
+    /// Add the next color attachment, whose index is the location of fragment shader output.
+    #[inline]
+    pub fn add_color(mut self, attachment: RenderingAttachmentCI) -> RenderingBI {
+        self.colors.push(attachment.inner); self
+    }
+
+    /// Set the depth attachment.
+    ///
+    /// For a depth-stencil format, set the same view by `stencil` as well, unless the stencil aspect is unused by pipeline.
+    #[inline(always)]
+    pub fn depth(mut self, attachment: RenderingAttachmentCI) -> RenderingBI {
+        self.depth = Some(attachment.inner); self
+    }
+
+    /// Set the stencil attachment.
+    #[inline(always)]
+    pub fn stencil(mut self, attachment: RenderingAttachmentCI) -> RenderingBI {
+        self.stencil = Some(attachment.inner); self
+    }
+
+    /// Get the count of color attachments, which must match `PipelineRenderingCI` of bound pipelines.
+    #[inline]
+    pub fn color_count(&self) -> usize {
+        self.colors.len()
+    }
+
+    /// Generate the `VkRenderingInfoKHR` pointing to the attachments of `self`, which is valid as long as `self` is not moved.
+    fn inner_rendering_info(&self) -> RenderingInfoKHR {
+
+        RenderingInfoKHR {
+            color_attachment_count: self.colors.len() as _,
+            p_color_attachments : self.colors.as_ptr(),
+            p_depth_attachment  : self.depth.as_ref().map_or(ptr::null(), |depth| depth as *const _),
+            p_stencil_attachment: self.stencil.as_ref().map_or(ptr::null(), |stencil| stencil as *const _),
+            ..self.inner
+        }
+    }
+}
+// ----------------------------------------------------------------------------------------------
+
+// ----------------------------------------------------------------------------------------------
+/// Wrapper class for `VkPipelineRenderingCreateInfoKHR`, which describes the attachment formats of a pipeline used in dynamic rendering.
+///
+/// See `GraphicsPipelineCI::new_dynamic_rendering`.
+#[derive(Debug, Clone)]
+pub struct PipelineRenderingCI {
+
+    inner: PipelineRenderingCreateInfoKHR,
+    color_formats: Vec<vk::Format>,
+}
+
+impl VulkanCI<PipelineRenderingCreateInfoKHR> for PipelineRenderingCI {
+
+    fn default_ci() -> PipelineRenderingCreateInfoKHR {
+
+        PipelineRenderingCreateInfoKHR {
+            s_type: vk::StructureType::from_raw(STRUCTURE_TYPE_PIPELINE_RENDERING_CREATE_INFO_KHR),
+            p_next: ptr::null(),
+            view_mask: 0,
+            color_attachment_count: 0,
+            p_color_attachment_formats: ptr::null(),
+            depth_attachment_format  : vk::Format::UNDEFINED,
+            stencil_attachment_format: vk::Format::UNDEFINED,
+        }
+    }
+}
+
+impl AsRef<PipelineRenderingCreateInfoKHR> for PipelineRenderingCI {
+
+    fn as_ref(&self) -> &PipelineRenderingCreateInfoKHR {
+        &self.inner
+    }
+}
+
+impl PipelineRenderingCI {
+
+    pub fn new() -> PipelineRenderingCI {
+
+        PipelineRenderingCI {
+            inner: PipelineRenderingCI::default_ci(),
+            color_formats: Vec::new(),
+        }
+    }
+
+    /// Add the format of next color attachment, in the same order as `RenderingBI::add_color`.
+    #[inline]
+    pub fn add_color_format(mut self, format: vk::Format) -> PipelineRenderingCI {
+
+        self.color_formats.push(format);
+        self.inner.color_attachment_count = self.color_formats.len() as _;
+        self.inner.p_color_attachment_formats = self.color_formats.as_ptr(); self
+    }
+
+    /// Set the format of depth attachment, and the format of stencil attachment if `format` contains a stencil component.
+    #[inline]
+    pub fn depth_format(mut self, format: vk::Format) -> PipelineRenderingCI {
+
+        self.inner.depth_attachment_format = format;
+        if crate::context::is_stencil_format(format) {
+            self.inner.stencil_attachment_format = format;
+        } self
+    }
+
+    /// Get the count of color attachments, which must match the color blend attachments of pipeline.
+    #[inline]
+    pub fn color_count(&self) -> usize {
+        self.color_formats.len()
+    }
+}
+// ----------------------------------------------------------------------------------------------
@@ -8,7 +8,8 @@ use crate::context::VkObjectDiscardable;
 use crate::ci::{VulkanCI, VkObjectBuildableCI};
 
 use crate::error::{VkResult, VkError};
-use crate::vkuint;
+use crate::utils::color::VkColor;
+use crate::{vkuint, vkfloat};
 
 use std::ptr;
 
@@ -38,6 +39,8 @@ pub struct RenderPassBI {
 
     inner: vk::RenderPassBeginInfo,
     clears: Option<Vec<vk::ClearValue>>,
+    /// the render pass whose attachments are used to validate the clear values, or None if it is unknown.
+    pass: Option<RenderPass>,
 }
 
 impl VulkanCI<vk::RenderPassBeginInfo> for RenderPassBI {
@@ -73,6 +76,8 @@ impl RenderPassBI {
     /// `render_pass`is the handle of render pass for this operations.
     ///
     /// `framebuffer` is the framebuffer containing the attachments that are used with the render pass.
+    ///
+    /// The clear values are not validated for a raw render pass handle, use `RenderPassBI::with_pass` instead if possible.
     pub fn new(render_pass: vk::RenderPass, framebuffer: vk::Framebuffer) -> RenderPassBI {
 
         RenderPassBI {
@@ -81,6 +86,18 @@ impl RenderPassBI {
                 ..RenderPassBI::default_ci()
             },
             clears: None,
+            pass: None,
+        }
+    }
+
+    /// Initialize `vk::RenderPassBeginInfo` with the render pass created by `RenderPassCI::build_with_attachments`.
+    ///
+    /// In debug build, the count of clear values is checked against the attachments of `render_pass`, when the render pass begins.
+    pub fn with_pass(render_pass: RenderPass, framebuffer: vk::Framebuffer) -> RenderPassBI {
+
+        RenderPassBI {
+            pass: Some(render_pass),
+            ..RenderPassBI::new(render_pass.handle, framebuffer)
         }
     }
 
@@ -118,6 +135,83 @@ impl RenderPassBI {
 
         self.clears.replace(values); self
     }
+
+    /// Set all the clear values for attachments used in this render pass, which is created by `ClearValues`.
+    #[inline]
+    pub fn clear_values(self, values: ClearValues) -> RenderPassBI {
+        self.set_clear_values(values.values)
+    }
+
+    // For crate inner use.
+    /// Check the count of clear values against the attachments of render pass when it begins.
+    ///
+    /// This check only takes effect in debug build, for the `RenderPassBI` created by `RenderPassBI::with_pass`.
+    #[doc(hidden)]
+    pub(crate) fn debug_check_clear_values(&self) {
+
+        if let Some(ref pass) = self.pass {
+            let error = pass.clear_count_error(self.inner.clear_value_count as usize);
+            debug_assert!(error.is_none(), "{}", error.unwrap_or_default());
+        }
+    }
+}
+
+/// A builder of clear values, whose order is the order of calling `color` and `depth_stencil`.
+///
+/// Call them in the order of attachment index, so that the i-th clear value belongs to the i-th attachment of render pass.
+#[derive(Clone, Default)]
+pub struct ClearValues {
+    values: Vec<vk::ClearValue>,
+}
+
+impl ClearValues {
+
+    #[inline(always)]
+    pub fn new() -> ClearValues {
+        Default::default()
+    }
+
+    /// Add the clear value of next color attachment.
+    #[inline]
+    pub fn color(mut self, color: VkColor) -> ClearValues {
+        self.values.push(vk::ClearValue {
+            color: vk::ClearColorValue { float32: color.into() },
+        }); self
+    }
+
+    /// Add the clear value of next color attachment with `format`, where `color` is in sRGB space.
+    ///
+    /// `color` is converted to linear space if `format` is sRGB, otherwise it is used directly.
+    #[inline]
+    pub fn color_for_format(self, color: VkColor, format: vk::Format) -> ClearValues {
+        self.color(color.for_format(format))
+    }
+
+    /// Add the clear value of next depth/stencil attachment.
+    #[inline]
+    pub fn depth_stencil(mut self, depth: vkfloat, stencil: vkuint) -> ClearValues {
+        self.values.push(vk::ClearValue {
+            depth_stencil: vk::ClearDepthStencilValue { depth, stencil },
+        }); self
+    }
+
+    /// Get the count of clear values added.
+    #[inline(always)]
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+}
+
+impl From<ClearValues> for Vec<vk::ClearValue> {
+
+    fn from(v: ClearValues) -> Vec<vk::ClearValue> {
+        v.values
+    }
 }
 // ----------------------------------------------------------------------------------------------
 
@@ -186,8 +280,9 @@ impl VkObjectBuildableCI for RenderPassCI {
 
         let render_pass = unsafe {
             device.logic.handle.create_render_pass(self.as_ref(), None)
-                .map_err(|_| VkError::create("Render Pass"))?
+                .map_err(|e| VkError::create_with("Render Pass", e))?
         };
+
         Ok(render_pass)
     }
 }
@@ -246,11 +341,24 @@ impl RenderPassCI {
     pub fn flags(mut self, flags: vk::RenderPassCreateFlags) -> RenderPassCI {
         self.inner.flags = flags; self
     }
+
+    /// Create `vk::RenderPass` object, and keep the count of its attachments for the validation of clear values.
+    pub fn build_with_attachments(&self, device: &VkDevice) -> VkResult<RenderPass> {
+
+        let handle = self.build(device)?;
+        let result = RenderPass {
+            handle,
+            attachment_count: self.attachments.len(),
+            required_clear_count: required_clear_count(&self.attachments),
+        };
+        Ok(result)
+    }
 }
 
 impl VkObjectDiscardable for vk::RenderPass {
 
     fn discard_by(self, device: &VkDevice) {
+
         unsafe {
             device.logic.handle.destroy_render_pass(self, None);
         }
@@ -258,6 +366,47 @@ impl VkObjectDiscardable for vk::RenderPass {
 }
 // ----------------------------------------------------------------------------------------------
 
+/// A render pass together with the count of its attachments, created by `RenderPassCI::build_with_attachments`.
+#[derive(Debug, Clone, Copy)]
+pub struct RenderPass {
+
+    pub handle: vk::RenderPass,
+    attachment_count: usize,
+    /// the count of clear values required by the attachments cleared on load.
+    required_clear_count: usize,
+}
+
+impl RenderPass {
+
+    /// Get the error message if `clear_value_count` does not match the attachments of this render pass.
+    fn clear_count_error(&self, clear_value_count: usize) -> Option<String> {
+
+        if clear_value_count < self.required_clear_count {
+            Some(format!("{} clear values are given, but the render pass clears attachments up to index {}.", clear_value_count, self.required_clear_count - 1))
+        } else if clear_value_count > self.attachment_count {
+            // the extra clear values are ignored by Vulkan, but they indicate that the clear values drift from the attachments.
+            Some(format!("{} clear values are given, but the render pass only has {} attachments.", clear_value_count, self.attachment_count))
+        } else {
+            None
+        }
+    }
+}
+
+impl VkObjectDiscardable for RenderPass {
+
+    fn discard_by(self, device: &VkDevice) {
+        device.discard(self.handle);
+    }
+}
+
+/// Get the count of clear values required by `attachments`, which is one more than the index of the last attachment cleared on load.
+fn required_clear_count(attachments: &[vk::AttachmentDescription]) -> usize {
+
+    attachments.iter()
+        .rposition(|attachment| attachment.load_op == vk::AttachmentLoadOp::CLEAR || attachment.stencil_load_op == vk::AttachmentLoadOp::CLEAR)
+        .map_or(0, |last_cleared| last_cleared + 1)
+}
+
 // ----------------------------------------------------------------------------------------------
 /// Wrapper class for `vk::AttachmentDescription`.
 ///
@@ -516,8 +665,8 @@ impl SubpassDescCI {
             layout: image_layout,
         });
 
-        self.inner.preserve_attachment_count = resolves.len() as _;
-        self.inner.p_resolve_attachments     = resolves.as_ptr(); self
+        // the count of resolve attachments is the same as color attachments.
+        self.inner.p_resolve_attachments = resolves.as_ptr(); self
     }
 
     /// Add preserve attachment to this subpass.
@@ -529,7 +678,8 @@ impl SubpassDescCI {
         let preserves = self.preserves.get_or_insert(Vec::new());
         preserves.push(attachment_index);
 
-        self.inner.p_preserve_attachments = preserves.as_ptr(); self
+        self.inner.preserve_attachment_count = preserves.len() as _;
+        self.inner.p_preserve_attachments    = preserves.as_ptr(); self
     }
 
     /// Set depth stencil attachment of this subpass.
@@ -668,3 +818,49 @@ impl From<vk::SubpassDependency> for SubpassDependencyCI {
     }
 }
 // ----------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn clear_count_covers_last_cleared_attachment() {
+
+        let attachment = |load_op: vk::AttachmentLoadOp, stencil_load_op: vk::AttachmentLoadOp| -> vk::AttachmentDescription {
+            AttachmentDescCI::new(vk::Format::UNDEFINED)
+                .op(load_op, vk::AttachmentStoreOp::STORE)
+                .stencil_op(stencil_load_op, vk::AttachmentStoreOp::DONT_CARE)
+                .into()
+        };
+
+        let clear = attachment(vk::AttachmentLoadOp::CLEAR, vk::AttachmentLoadOp::DONT_CARE);
+        let load = attachment(vk::AttachmentLoadOp::LOAD, vk::AttachmentLoadOp::DONT_CARE);
+        let stencil_clear = attachment(vk::AttachmentLoadOp::DONT_CARE, vk::AttachmentLoadOp::CLEAR);
+
+        assert_eq!(required_clear_count(&[]), 0);
+        assert_eq!(required_clear_count(&[load]), 0);
+        assert_eq!(required_clear_count(&[clear, load]), 1);
+        // the clear values of attachments before the last cleared one must be given, even if they are not cleared.
+        assert_eq!(required_clear_count(&[load, clear]), 2);
+        assert_eq!(required_clear_count(&[clear, load, stencil_clear]), 3);
+    }
+
+    #[test]
+    fn clear_values_are_checked_against_render_pass() {
+
+        // a color attachment cleared on load, followed by a depth attachment loaded.
+        let pass = RenderPass { handle: vk::RenderPass::null(), attachment_count: 2, required_clear_count: 1 };
+        let clear_value = vk::ClearValue { color: vk::ClearColorValue { float32: [0.0; 4] } };
+
+        assert!(pass.clear_count_error(0).is_some());
+        assert!(pass.clear_count_error(1).is_none());
+        assert!(pass.clear_count_error(2).is_none());
+        assert!(pass.clear_count_error(3).is_some());
+
+        let render_pass_bi = RenderPassBI::with_pass(pass, vk::Framebuffer::null())
+            .set_clear_values(vec![clear_value; 2]);
+        assert_eq!(render_pass_bi.as_ref().render_pass, pass.handle);
+        render_pass_bi.debug_check_clear_values();
+    }
+}
@@ -2,6 +2,8 @@
 use ash::vk;
 
 use crate::ci::VulkanCI;
+use crate::context::VkDevice;
+use crate::error::{VkResult, VkError};
 use crate::{vkfloat, vkuint};
 
 use std::ptr;
@@ -96,6 +98,20 @@ impl VertexInputSCI {
         self.inner.p_vertex_attribute_descriptions    = self.attributes.as_ptr(); self
     }
 
+    /// Get the vertex bindings added to this state.
+    #[inline]
+    pub fn bindings(&self) -> &[vk::VertexInputBindingDescription] {
+        &self.bindings
+    }
+
+    /// Get the location following the last attribute, which can be used to append more attributes(etc. per-instance attributes).
+    pub fn next_location(&self) -> vkuint {
+        self.attributes.iter()
+            .map(|attribute| attribute.location + 1)
+            .max()
+            .unwrap_or(0)
+    }
+
     /// Set the `flags` member for `vk::PipelineVertexInputStateCreateFlags`.
     #[inline(always)]
     pub fn flags(mut self, flags: vk::PipelineVertexInputStateCreateFlags) -> VertexInputSCI {
@@ -171,6 +187,12 @@ impl InputAssemblySCI {
         Default::default()
     }
 
+    /// Initialize `vk::PipelineInputAssemblyStateCreateInfo` with `topology`(etc. `LINE_LIST` for debug lines, `TRIANGLE_STRIP` for strip meshes).
+    #[inline(always)]
+    pub fn with_topology(topology: vk::PrimitiveTopology) -> InputAssemblySCI {
+        InputAssemblySCI::new().topology(topology)
+    }
+
     /// Set the `topology` member for `vk::PipelineInputAssemblyStateCreateInfo`.
     ///
     /// `topology` specifies the primitive topology.
@@ -182,11 +204,34 @@ impl InputAssemblySCI {
     /// Set the `primitive_restart_enable` member for `vk::PipelineInputAssemblyStateCreateInfo`.
     ///
     /// `is_enable` controls whether a special vertex index value is treated as restarting the assembly of primitives. Disable by default.
+    ///
+    /// The restart index is `0xFFFF` for `vk::IndexType::UINT16` indices and `0xFFFFFFFF` for `vk::IndexType::UINT32` indices.
+    /// Primitive restart is only valid for the strip and fan topologies, and the pipeline building returns error otherwise.
     #[inline(always)]
     pub fn primitive_restart(mut self, is_enable: bool) -> InputAssemblySCI {
         self.inner.primitive_restart_enable = if is_enable { vk::TRUE } else { vk::FALSE }; self
     }
 
+    // For crate inner use.
+    #[doc(hidden)]
+    pub(crate) fn check_primitive_restart(&self) -> VkResult<()> {
+
+        if self.inner.primitive_restart_enable == vk::TRUE {
+            match self.inner.topology {
+                | vk::PrimitiveTopology::LINE_STRIP
+                | vk::PrimitiveTopology::TRIANGLE_STRIP
+                | vk::PrimitiveTopology::TRIANGLE_FAN
+                | vk::PrimitiveTopology::LINE_STRIP_WITH_ADJACENCY
+                | vk::PrimitiveTopology::TRIANGLE_STRIP_WITH_ADJACENCY => {},
+                | _ => {
+                    return Err(VkError::custom(format!("Primitive restart can not be enabled for {:?} topology.", self.inner.topology)))
+                },
+            }
+        }
+
+        Ok(())
+    }
+
     /// Set the `flags` member for `vk::PipelineInputAssemblyStateCreateInfo`.
     #[inline(always)]
     pub fn flags(mut self, flags: vk::PipelineInputAssemblyStateCreateFlags) -> InputAssemblySCI {
@@ -270,15 +315,15 @@ impl RasterizationSCI {
         Default::default()
     }
 
-    /// Set the `depth_clamp_enable` and `depth_bias_clamp` members for `vk::PipelineRasterizationStateCreateInfo`.
+    /// Set the `depth_clamp_enable` member for `vk::PipelineRasterizationStateCreateInfo`.
     ///
-    /// `depth_clamp_enable` controls whether to clamp the fragment’s depth values in Depth Test.
+    /// `is_enable` controls whether to clamp the fragment’s depth values instead of clipping primitives against the near and far planes
+    /// (etc. for shadow casters behind the near plane of light). Default is false.
     ///
-    /// `bias` is the maximum (or minimum) depth bias of a fragment.
+    /// Depth clamp requires `depth_clamp` feature, and the pipeline building returns `UnSupport` error otherwise.
     #[inline(always)]
-    pub fn depth_clamp(mut self, is_enable: bool, bias: vkfloat) -> RasterizationSCI {
-        self.inner.depth_clamp_enable = if is_enable { vk::TRUE } else { vk::FALSE };
-        self.inner.depth_bias_clamp = bias; self
+    pub fn depth_clamp(mut self, is_enable: bool) -> RasterizationSCI {
+        self.inner.depth_clamp_enable = if is_enable { vk::TRUE } else { vk::FALSE }; self
     }
 
     /// Set the `rasterizer_discard_enable` member for `vk::PipelineRasterizationStateCreateInfo`.
@@ -308,17 +353,20 @@ impl RasterizationSCI {
         self.inner.polygon_mode = mode; self
     }
 
-    /// Set the `depth_bias_enable`, `depth_bias_constant_factor` and `depth_bias_slope_factor` members for `vk::PipelineRasterizationStateCreateInfo`.
-    ///
-    /// `is_enable` controls whether to bias fragment depth values.
+    /// Enable depth bias, and set the `depth_bias_constant_factor`, `depth_bias_clamp` and `depth_bias_slope_factor` members for `vk::PipelineRasterizationStateCreateInfo`.
     ///
     /// `constant_factor` is a scalar factor controlling the constant depth value added to each fragment.
     ///
+    /// `clamp` is the maximum (or minimum) depth bias of a fragment. A non-zero value requires `depth_bias_clamp` feature.
+    ///
     /// `slope_factor` is a scalar factor applied to a fragment’s slope in depth bias calculations.
+    ///
+    /// Depth bias is disabled by default. If `vk::DynamicState::DEPTH_BIAS` is used, the factors here are ignored, and they are set by `set_depth_bias` in command buffer instead.
     #[inline(always)]
-    pub fn depth_bias(mut self, is_enable: bool, constant_factor: vkfloat, slope_factor: vkfloat) -> RasterizationSCI {
-        self.inner.depth_bias_enable = if is_enable { vk::TRUE } else { vk::FALSE };
+    pub fn depth_bias(mut self, constant_factor: vkfloat, clamp: vkfloat, slope_factor: vkfloat) -> RasterizationSCI {
+        self.inner.depth_bias_enable = vk::TRUE;
         self.inner.depth_bias_constant_factor = constant_factor;
+        self.inner.depth_bias_clamp = clamp;
         self.inner.depth_bias_slope_factor = slope_factor; self
     }
 
@@ -335,6 +383,22 @@ impl RasterizationSCI {
     pub fn flags(mut self, flags: vk::PipelineRasterizationStateCreateFlags) -> RasterizationSCI {
         self.inner.flags = flags; self
     }
+
+    // For crate inner use.
+    #[doc(hidden)]
+    pub(crate) fn check_features(&self, device: &VkDevice) -> VkResult<()> {
+
+        let features = device.phy.features_enabled();
+
+        if self.inner.depth_clamp_enable == vk::TRUE && features.depth_clamp != vk::TRUE {
+            return Err(VkError::unsupported("Depth Clamp"))
+        }
+        if self.inner.depth_bias_enable == vk::TRUE && self.inner.depth_bias_clamp != 0.0 && features.depth_bias_clamp != vk::TRUE {
+            return Err(VkError::unsupported("Depth Bias Clamp"))
+        }
+
+        Ok(())
+    }
 }
 // ----------------------------------------------------------------------------------------------
 
@@ -518,6 +582,39 @@ impl BlendAttachmentSCI {
         Default::default()
     }
 
+    /// Initialize `vk::PipelineColorBlendAttachmentState` for the conventional alpha blending(etc. transparent UI).
+    ///
+    /// The result color is `src.rgb * src.a + dst.rgb * (1 - src.a)`, and the result alpha is `src.a + dst.a * (1 - src.a)`.
+    #[inline(always)]
+    pub fn alpha_blend() -> BlendAttachmentSCI {
+        BlendAttachmentSCI::new()
+            .blend_enable(true)
+            .color(vk::BlendOp::ADD, vk::BlendFactor::SRC_ALPHA, vk::BlendFactor::ONE_MINUS_SRC_ALPHA)
+            .alpha(vk::BlendOp::ADD, vk::BlendFactor::ONE, vk::BlendFactor::ONE_MINUS_SRC_ALPHA)
+    }
+
+    /// Initialize `vk::PipelineColorBlendAttachmentState` for additive blending(etc. particles, glow).
+    ///
+    /// The result color is `src.rgb * src.a + dst.rgb`, and the alpha of destination is kept.
+    #[inline(always)]
+    pub fn additive() -> BlendAttachmentSCI {
+        BlendAttachmentSCI::new()
+            .blend_enable(true)
+            .color(vk::BlendOp::ADD, vk::BlendFactor::SRC_ALPHA, vk::BlendFactor::ONE)
+            .alpha(vk::BlendOp::ADD, vk::BlendFactor::ZERO, vk::BlendFactor::ONE)
+    }
+
+    /// Initialize `vk::PipelineColorBlendAttachmentState` for the blending of premultiplied alpha colors.
+    ///
+    /// The source color must have been multiplied by its alpha, and the result is `src + dst * (1 - src.a)` for all components.
+    #[inline(always)]
+    pub fn premultiplied() -> BlendAttachmentSCI {
+        BlendAttachmentSCI::new()
+            .blend_enable(true)
+            .color(vk::BlendOp::ADD, vk::BlendFactor::ONE, vk::BlendFactor::ONE_MINUS_SRC_ALPHA)
+            .alpha(vk::BlendOp::ADD, vk::BlendFactor::ONE, vk::BlendFactor::ONE_MINUS_SRC_ALPHA)
+    }
+
     /// Set the `blend_enable` member for `vk::PipelineColorBlendAttachmentState`.
     ///
     /// `is_enable` controls whether blending is enabled for the corresponding color attachment.
@@ -796,6 +893,15 @@ impl DepthStencilSCI {
         Default::default()
     }
 
+    /// Initialize `vk::PipelineDepthStencilStateCreateInfo` for the color pass following a depth pre-pass.
+    ///
+    /// Depth test uses `vk::CompareOp::EQUAL` with depth writes disabled, so only the fragments kept by the pre-pass are shaded.
+    #[inline(always)]
+    pub fn depth_equal() -> DepthStencilSCI {
+        DepthStencilSCI::new()
+            .depth_test(true, false, vk::CompareOp::EQUAL)
+    }
+
     /// Set the `depth_test_enable`, `depth_write_enable` and `depth_compare_op` members for `vk::PipelineDepthStencilStateCreateInfo`.
     ///
     /// `is_enable_test` controls whether depth testing is enabled.
@@ -810,6 +916,20 @@ impl DepthStencilSCI {
         self.inner.depth_compare_op   = compare_op; self
     }
 
+    /// Set the `depth_compare_op` member for `vk::PipelineDepthStencilStateCreateInfo`.
+    ///
+    /// It only changes the comparison operator, leaving depth test and depth write unchanged.
+    #[inline(always)]
+    pub fn compare_op(mut self, op: vk::CompareOp) -> DepthStencilSCI {
+        self.inner.depth_compare_op = op; self
+    }
+
+    /// Set the `depth_write_enable` member for `vk::PipelineDepthStencilStateCreateInfo`.
+    #[inline(always)]
+    pub fn depth_write(mut self, is_enable: bool) -> DepthStencilSCI {
+        self.inner.depth_write_enable = if is_enable { vk::TRUE } else { vk::FALSE }; self
+    }
+
     /// Set the `depth_bounds_test_enable`, `min_depth_bounds` and `max_depth_bounds` members for `vk::PipelineDepthStencilStateCreateInfo`.
     ///
     /// `is_enable` controls whether depth testing is enabled.
@@ -868,7 +988,8 @@ impl DepthStencilSCI {
 pub struct MultisampleSCI {
 
     inner: vk::PipelineMultisampleStateCreateInfo,
-    sample_mask: Option<vk::SampleMask>,
+    /// kept on heap, so that `p_sample_mask` stays valid when this struct is moved.
+    sample_masks: Vec<vk::SampleMask>,
 }
 
 impl VulkanCI<vk::PipelineMultisampleStateCreateInfo> for MultisampleSCI {
@@ -901,7 +1022,7 @@ impl Default for MultisampleSCI {
     fn default() -> MultisampleSCI {
         MultisampleSCI {
             inner: MultisampleSCI::default_ci(),
-            sample_mask: None,
+            sample_masks: Vec::new(),
         }
     }
 }
@@ -914,6 +1035,12 @@ impl MultisampleSCI {
         Default::default()
     }
 
+    /// Initialize `vk::PipelineMultisampleStateCreateInfo` with `count` samples per pixel, which must match the sample count of attachments.
+    #[inline(always)]
+    pub fn with_sample_count(count: vk::SampleCountFlags) -> MultisampleSCI {
+        MultisampleSCI::new().sample_count(count)
+    }
+
     /// Set the `rasterization_samples` member for `vk::PipelineMultisampleStateCreateInfo`.
     ///
     /// `count` specifies the number of samples used in rasterization. Default is `vk::SampleCountFlags::TYPE_1`.
@@ -926,7 +1053,9 @@ impl MultisampleSCI {
     ///
     /// `is_enable` controls whether sample shading is enabled. Default is false.
     ///
-    /// `min` specifies a minimum fraction of sample shading.
+    /// `min` specifies a minimum fraction of sample shading(in [0.0, 1.0], 1.0 shades every sample).
+    ///
+    /// Sample shading requires `sample_rate_shading` feature, and the pipeline building returns `UnSupport` error otherwise.
     #[inline(always)]
     pub fn sample_shading(mut self, is_enable: bool, min: vkfloat) -> MultisampleSCI {
         self.inner.sample_shading_enable = if is_enable { vk::TRUE } else { vk::FALSE };
@@ -936,17 +1065,43 @@ impl MultisampleSCI {
     /// Set the `sample_mask` member for `vk::PipelineMultisampleStateCreateInfo`.
     #[inline(always)]
     pub fn sample_mask(mut self, mask: vk::SampleMask) -> MultisampleSCI {
-        self.inner.p_sample_mask = &mask;
-        self.sample_mask = Some(mask); self
+        self.sample_masks = vec![mask];
+        self.inner.p_sample_mask = self.sample_masks.as_ptr(); self
     }
 
     /// Set the `alpha_to_coverage_enable` and `alpha_to_one_enable` member for `vk::PipelineMultisampleStateCreateInfo`.
+    ///
+    /// Alpha to one requires `alpha_to_one` feature, and the pipeline building returns `UnSupport` error otherwise.
     #[inline(always)]
     pub fn alpha(mut self, is_enable_alpha2coverage: bool, is_enable_alpha2one: bool) -> MultisampleSCI {
         self.inner.alpha_to_coverage_enable = if is_enable_alpha2coverage { vk::TRUE } else { vk::FALSE };
         self.inner.alpha_to_one_enable = if is_enable_alpha2one { vk::TRUE } else { vk::FALSE }; self
     }
 
+    /// Set the `alpha_to_coverage_enable` member for `vk::PipelineMultisampleStateCreateInfo`.
+    ///
+    /// `is_enable` controls whether the alpha of the first color output decides the sample coverage(etc. for the edges of foliage). Default is false.
+    #[inline(always)]
+    pub fn alpha_to_coverage(mut self, is_enable: bool) -> MultisampleSCI {
+        self.inner.alpha_to_coverage_enable = if is_enable { vk::TRUE } else { vk::FALSE }; self
+    }
+
+    // For crate inner use.
+    #[doc(hidden)]
+    pub(crate) fn check_features(&self, device: &VkDevice) -> VkResult<()> {
+
+        let features = device.phy.features_enabled();
+
+        if self.inner.sample_shading_enable == vk::TRUE && features.sample_rate_shading != vk::TRUE {
+            return Err(VkError::unsupported("Sample Rate Shading"))
+        }
+        if self.inner.alpha_to_one_enable == vk::TRUE && features.alpha_to_one != vk::TRUE {
+            return Err(VkError::unsupported("Alpha To One"))
+        }
+
+        Ok(())
+    }
+
     /// Set the `flags` member for `vk::PipelineViewportStateCreateInfo`.
     #[inline(always)]
     pub fn flags(mut self, flags: vk::PipelineMultisampleStateCreateFlags) -> MultisampleSCI {
@@ -1024,3 +1179,34 @@ impl DynamicSCI {
     }
 }
 // ----------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn compare_op_keeps_depth_test_and_write() {
+
+        let sci = DepthStencilSCI::new()
+            .depth_test(true, true, vk::CompareOp::LESS_OR_EQUAL)
+            .compare_op(vk::CompareOp::GREATER_OR_EQUAL);
+        let inner = sci.as_ref();
+
+        assert_eq!(inner.depth_compare_op, vk::CompareOp::GREATER_OR_EQUAL);
+        assert_eq!(inner.depth_test_enable, vk::TRUE);
+        assert_eq!(inner.depth_write_enable, vk::TRUE);
+    }
+
+    #[test]
+    fn depth_write_keeps_compare_op() {
+
+        let sci = DepthStencilSCI::new()
+            .depth_test(true, true, vk::CompareOp::GREATER_OR_EQUAL)
+            .depth_write(false);
+        let inner = sci.as_ref();
+
+        assert_eq!(inner.depth_compare_op, vk::CompareOp::GREATER_OR_EQUAL);
+        assert_eq!(inner.depth_write_enable, vk::FALSE);
+    }
+}
@@ -0,0 +1,260 @@
+
+use ash::vk;
+use ash::version::DeviceV1_0;
+
+use crate::ci::pipeline::GraphicsPipelineCI;
+use crate::context::VkDevice;
+use crate::error::{VkResult, VkError};
+
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::cmp::Ordering;
+
+// ----------------------------------------------------------------------------------------------
+/// The alpha mode of material, declared in the order that their primitives should be drawn.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub enum VariantAlphaMode {
+    Opaque,
+    Mask,
+    /// alpha blending is enabled for color attachments.
+    Blend,
+}
+
+/// The pipeline states that may be different between materials.
+///
+/// The keys are ordered by their alpha modes first, so that opaque variants are drawn before masked and blended ones.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct PipelineVariantKey {
+
+    /// the triangle facing direction used in primitive culling(`vk::CullModeFlags::NONE` for double-sided material).
+    pub cull_mode: vk::CullModeFlags,
+    /// the alpha mode of material, which decides whether to enable alpha blending for color attachments.
+    pub alpha_mode: VariantAlphaMode,
+    /// whether to enable depth writes.
+    pub is_depth_write: bool,
+}
+
+impl Default for PipelineVariantKey {
+
+    fn default() -> PipelineVariantKey {
+        PipelineVariantKey {
+            cull_mode: vk::CullModeFlags::BACK,
+            alpha_mode: VariantAlphaMode::Opaque,
+            is_depth_write : true,
+        }
+    }
+}
+
+impl Hash for PipelineVariantKey {
+
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.cull_mode.as_raw().hash(state);
+        self.alpha_mode.hash(state);
+        self.is_depth_write.hash(state);
+    }
+}
+
+impl Ord for PipelineVariantKey {
+
+    fn cmp(&self, other: &PipelineVariantKey) -> Ordering {
+        self.alpha_mode.cmp(&other.alpha_mode)
+            .then(self.cull_mode.as_raw().cmp(&other.cull_mode.as_raw()))
+            // the variants writing depth are drawn first.
+            .then(other.is_depth_write.cmp(&self.is_depth_write))
+    }
+}
+
+impl PartialOrd for PipelineVariantKey {
+
+    fn partial_cmp(&self, other: &PipelineVariantKey) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+// ----------------------------------------------------------------------------------------------
+
+// ----------------------------------------------------------------------------------------------
+/// A cache of graphics pipelines, which builds each unique `PipelineVariantKey` only once.
+///
+/// All variants are derived from a base `GraphicsPipelineCI`, of which only the cull mode, color blending and depth writes are overridden.
+#[derive(Debug, Default)]
+pub struct PipelineVariantCache {
+
+    variants: HashMap<PipelineVariantKey, vk::Pipeline>,
+}
+
+impl PipelineVariantCache {
+
+    pub fn new() -> PipelineVariantCache {
+        Default::default()
+    }
+
+    /// Build the pipelines for all variants in `keys` that have not been cached yet.
+    ///
+    /// All the new pipelines are created in a single `vkCreateGraphicsPipelines` call.
+    pub fn build_variants(&mut self, device: &VkDevice, base: &GraphicsPipelineCI, keys: &[PipelineVariantKey]) -> VkResult<()> {
+
+        let request_keys = missing_variants(&self.variants, keys);
+        if request_keys.is_empty() {
+            return Ok(())
+        }
+
+        base.check_stage_features(device)?;
+
+        let base_ci = base.inner_pipeline_ci();
+
+        // the state objects of each variant must be kept alive until pipeline creation finished.
+        let rasterizations: Vec<vk::PipelineRasterizationStateCreateInfo> = request_keys.iter().map(|key| {
+            vk::PipelineRasterizationStateCreateInfo {
+                cull_mode: key.cull_mode,
+                ..unsafe { *base_ci.p_rasterization_state }
+            }
+        }).collect();
+
+        let depth_stencils: Vec<vk::PipelineDepthStencilStateCreateInfo> = request_keys.iter().map(|key| {
+            vk::PipelineDepthStencilStateCreateInfo {
+                depth_write_enable: if key.is_depth_write { vk::TRUE } else { vk::FALSE },
+                ..unsafe { *base_ci.p_depth_stencil_state }
+            }
+        }).collect();
+
+        let blend_attachments: Vec<Vec<vk::PipelineColorBlendAttachmentState>> = request_keys.iter().map(|key| {
+
+            let base_blend = unsafe { &*base_ci.p_color_blend_state };
+            let base_attachments = if base_blend.attachment_count > 0 {
+                unsafe { ::std::slice::from_raw_parts(base_blend.p_attachments, base_blend.attachment_count as _) }
+            } else {
+                &[]
+            };
+
+            base_attachments.iter().map(|attachment| {
+                if key.alpha_mode == VariantAlphaMode::Blend {
+                    vk::PipelineColorBlendAttachmentState {
+                        blend_enable: vk::TRUE,
+                        src_color_blend_factor: vk::BlendFactor::SRC_ALPHA,
+                        dst_color_blend_factor: vk::BlendFactor::ONE_MINUS_SRC_ALPHA,
+                        color_blend_op: vk::BlendOp::ADD,
+                        src_alpha_blend_factor: vk::BlendFactor::ONE,
+                        dst_alpha_blend_factor: vk::BlendFactor::ONE_MINUS_SRC_ALPHA,
+                        alpha_blend_op: vk::BlendOp::ADD,
+                        ..attachment.clone()
+                    }
+                } else {
+                    vk::PipelineColorBlendAttachmentState {
+                        blend_enable: vk::FALSE,
+                        ..attachment.clone()
+                    }
+                }
+            }).collect()
+        }).collect();
+
+        let color_blends: Vec<vk::PipelineColorBlendStateCreateInfo> = blend_attachments.iter().map(|attachments| {
+            vk::PipelineColorBlendStateCreateInfo {
+                attachment_count: attachments.len() as _,
+                p_attachments   : attachments.as_ptr(),
+                ..unsafe { *base_ci.p_color_blend_state }
+            }
+        }).collect();
+
+        let pipeline_cis: Vec<vk::GraphicsPipelineCreateInfo> = (0..request_keys.len()).map(|i| {
+            vk::GraphicsPipelineCreateInfo {
+                p_rasterization_state: &rasterizations[i],
+                p_depth_stencil_state: &depth_stencils[i],
+                p_color_blend_state  : &color_blends[i],
+                ..base_ci
+            }
+        }).collect();
+
+        let pipelines = unsafe {
+            device.logic.handle.create_graphics_pipelines(base.inner_pipeline_cache(device), &pipeline_cis, None)
+                .map_err(|(_, e)| VkError::create_with("Graphics Pipeline Variants", e))?
+        };
+
+        for (key, pipeline) in request_keys.into_iter().zip(pipelines.into_iter()) {
+            self.variants.insert(key, pipeline);
+        }
+
+        Ok(())
+    }
+
+    /// Get the pipeline matching `key`, or None if this variant has not been built.
+    #[inline]
+    pub fn get(&self, key: &PipelineVariantKey) -> Option<vk::Pipeline> {
+        self.variants.get(key).cloned()
+    }
+
+    /// Iterate through all the cached variants.
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = (&PipelineVariantKey, &vk::Pipeline)> {
+        self.variants.iter()
+    }
+
+    /// Get the count of pipelines in cache.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.variants.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.variants.is_empty()
+    }
+
+    pub fn discard_by(self, device: &VkDevice) {
+
+        for (_, pipeline) in self.variants.into_iter() {
+            device.discard(pipeline);
+        }
+    }
+}
+
+/// Collect the unique keys of `keys` which have not been built in `cached`, keeping their order in `keys`.
+fn missing_variants(cached: &HashMap<PipelineVariantKey, vk::Pipeline>, keys: &[PipelineVariantKey]) -> Vec<PipelineVariantKey> {
+
+    let mut request_keys: Vec<PipelineVariantKey> = Vec::with_capacity(keys.len());
+    for key in keys.iter() {
+        if !cached.contains_key(key) && !request_keys.contains(key) {
+            request_keys.push(key.clone());
+        }
+    }
+    request_keys
+}
+// ----------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn identical_materials_share_one_variant() {
+
+        let opaque = PipelineVariantKey::default();
+        let blend = PipelineVariantKey { alpha_mode: VariantAlphaMode::Blend, is_depth_write: false, ..opaque };
+
+        // two materials with the same states request only one pipeline.
+        let mut cached = HashMap::new();
+        let request_keys = missing_variants(&cached, &[opaque, opaque]);
+        assert_eq!(request_keys, vec![opaque]);
+
+        // the cached variant is not built again, while a differing material requests a second pipeline.
+        cached.insert(opaque, vk::Pipeline::null());
+        let request_keys = missing_variants(&cached, &[opaque, blend, opaque, blend]);
+        assert_eq!(request_keys, vec![blend]);
+
+        cached.insert(blend, vk::Pipeline::null());
+        assert!(missing_variants(&cached, &[blend, opaque]).is_empty());
+        assert_eq!(cached.len(), 2);
+    }
+
+    #[test]
+    fn variants_are_ordered_by_alpha_mode() {
+
+        let blend = PipelineVariantKey { alpha_mode: VariantAlphaMode::Blend, is_depth_write: false, ..Default::default() };
+        let mask = PipelineVariantKey { alpha_mode: VariantAlphaMode::Mask, cull_mode: vk::CullModeFlags::NONE, ..Default::default() };
+        let opaque = PipelineVariantKey { cull_mode: vk::CullModeFlags::NONE, ..Default::default() };
+
+        let mut keys = vec![blend, mask, opaque];
+        keys.sort();
+        assert_eq!(keys, vec![opaque, mask, blend]);
+    }
+}
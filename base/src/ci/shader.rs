@@ -5,9 +5,14 @@ use ash::version::DeviceV1_0;
 
 use crate::context::VkDevice;
 use crate::ci::{VulkanCI, VkObjectBuildableCI};
+use crate::utils::shaderc::VkShaderCompiler;
+use crate::utils::reflect::{ShaderReflection, ReflectedEntryPoint};
+use crate::utils::spirv::{spirv_words, load_spirv};
 use crate::error::{VkResult, VkError};
 
 use std::ffi::CString;
+use std::path::Path;
+use std::mem;
 use std::ptr;
 
 // ---------------------------------------------------------------------------------------------------
@@ -30,7 +35,10 @@ use std::ptr;
 pub struct ShaderModuleCI {
 
     inner: vk::ShaderModuleCreateInfo,
-    codes: Vec<u8>,
+    /// SPIR-V codes in words, so that `p_code` is always aligned to 4 bytes.
+    codes: Vec<u32>,
+    /// the reason why the codes passed to `ShaderModuleCI::new` are not valid SPIR-V, which is returned when building.
+    code_error: Option<String>,
 }
 
 impl VulkanCI<vk::ShaderModuleCreateInfo> for ShaderModuleCI {
@@ -60,9 +68,19 @@ impl VkObjectBuildableCI for ShaderModuleCI {
     /// Create `vk::ShaderModule` object, and return its handle.
     fn build(&self, device: &VkDevice) -> VkResult<Self::ObjectType> {
 
+        if let Some(ref code_error) = self.code_error {
+            return Err(VkError::custom(code_error.clone()))
+        }
+
+        // refresh the pointer of codes, in case this CI has been cloned.
+        let module_ci = vk::ShaderModuleCreateInfo {
+            p_code: self.codes.as_ptr(),
+            ..self.inner
+        };
+
         let module = unsafe {
-            device.logic.handle.create_shader_module(self.as_ref(), None)
-                .or(Err(VkError::create("Shader Module")))?
+            device.logic.handle.create_shader_module(&module_ci, None)
+                .map_err(|e| VkError::create_with("Shader Module", e))?
         };
 
         Ok(module)
@@ -73,24 +91,70 @@ impl ShaderModuleCI {
 
     /// Initialize `vk::ShaderModuleCreateInfo` with default value.
     ///
-    /// `codes` must be valid SPIR-V code.
+    /// `codes` must be valid SPIR-V code in bytes(etc. the output of `VkShaderCompiler`).
+    /// They are copied into words, and the building returns error if they are not valid SPIR-V.
     pub fn new(codes: Vec<u8>) -> ShaderModuleCI {
 
+        match spirv_words(&codes) {
+            | Ok(words) => ShaderModuleCI::from_words(words),
+            | Err(e) => ShaderModuleCI {
+                inner: ShaderModuleCI::default_ci(),
+                codes: Vec::new(),
+                code_error: Some(e.to_string()),
+            },
+        }
+    }
+
+    /// Initialize `vk::ShaderModuleCreateInfo` with SPIR-V codes in words(etc. the output of `utils::spirv::load_spirv`).
+    pub fn from_words(codes: Vec<u32>) -> ShaderModuleCI {
+
         ShaderModuleCI {
             inner: vk::ShaderModuleCreateInfo {
-                code_size: codes.len(),
-                p_code   : codes.as_ptr() as _,
+                code_size: codes.len() * mem::size_of::<u32>(),
+                p_code   : codes.as_ptr(),
                 ..ShaderModuleCI::default_ci()
             },
             codes,
+            code_error: None,
         }
     }
 
+    /// Load the SPIR-V file at `path`, and initialize `vk::ShaderModuleCreateInfo` with its codes.
+    pub fn from_path(path: impl AsRef<Path>) -> VkResult<ShaderModuleCI> {
+
+        let codes = load_spirv(path)?;
+        Ok(ShaderModuleCI::from_words(codes))
+    }
+
+    /// Compile the HLSL shader at `path` into SPIR-V codes, and initialize `vk::ShaderModuleCreateInfo` with them.
+    ///
+    /// `entry_name` is the entry point function in HLSL source(etc. `VSMain`),
+    /// which must also be set to the `ShaderStageCI` using this module by `ShaderStageCI::main`.
+    pub fn from_hlsl(compiler: &mut VkShaderCompiler, path: impl AsRef<Path>, stage: shaderc::ShaderKind, entry_name: &str) -> VkResult<ShaderModuleCI> {
+
+        let input_name = path.as_ref().to_string_lossy().into_owned();
+        let codes = compiler.compile_hlsl_from_path(path, stage, &input_name, entry_name)?;
+        Ok(ShaderModuleCI::new(codes))
+    }
+
     /// Set the `flags` member for `vk::ShaderModuleCreateInfo`.
     #[inline(always)]
     pub fn flags(mut self, flags: vk::ShaderModuleCreateFlags) -> ShaderModuleCI {
         self.inner.flags = flags; self
     }
+
+    /// Read the entry points, descriptor bindings, push constant ranges and vertex inputs declared in the SPIR-V codes.
+    ///
+    /// Use `ShaderReflection::merge` to combine the reflections of all shaders in a pipeline,
+    /// and generate the layouts by `DescriptorSetLayoutCI::from_reflection` and `PipelineLayoutCI::add_push_constant_ranges`.
+    /// Pass it to `ShaderStageCI::reflection` to validate the entry point of stage before pipeline creation.
+    pub fn reflect(&self) -> VkResult<ShaderReflection> {
+
+        if let Some(ref code_error) = self.code_error {
+            return Err(VkError::custom(code_error.clone()))
+        }
+        ShaderReflection::from_words(&self.codes)
+    }
 }
 
 impl crate::context::VkObjectDiscardable for vk::ShaderModule {
@@ -101,6 +165,7 @@ impl crate::context::VkObjectDiscardable for vk::ShaderModule {
         }
     }
 }
+
 // ---------------------------------------------------------------------------------------------------
 
 // ---------------------------------------------------------------------------------------------------
@@ -128,6 +193,8 @@ pub struct ShaderStageCI {
 
     main: CString,
     specialization: Option<vk::SpecializationInfo>,
+    /// the entry points declared in `module`, or None if the module is not reflected.
+    entry_points: Option<Vec<ReflectedEntryPoint>>,
 }
 
 impl VulkanCI<vk::PipelineShaderStageCreateInfo> for ShaderStageCI {
@@ -171,6 +238,7 @@ impl ShaderStageCI {
                 ..ShaderStageCI::default_ci()
             },
             specialization: None,
+            entry_points: None,
             main,
         }
     }
@@ -185,6 +253,53 @@ impl ShaderStageCI {
         self.inner.p_name = self.main.as_ptr(); self
     }
 
+    /// Set the reflection of the shader module, whose entry points are used to validate this stage.
+    ///
+    /// `reflection` should be generated from the codes of `module` by `ShaderModuleCI::reflect`.
+    #[inline(always)]
+    pub fn reflection(mut self, reflection: &ShaderReflection) -> ShaderStageCI {
+        self.entry_points = Some(reflection.entry_points.clone()); self
+    }
+
+    /// Check if the shader module declares the entry point set by `ShaderStageCI::main` for the stage of this shader.
+    ///
+    /// Only the stages with reflection(see `ShaderStageCI::reflection`) can be checked, and the others always pass.
+    /// This is called by `GraphicsPipelineCI` before pipeline creation,
+    /// so that a wrong entry point name returns a descriptive error instead of the failure of pipeline creation.
+    pub fn check_entry_point(&self) -> VkResult<()> {
+
+        let entry_points = match self.entry_points {
+            | Some(ref entry_points) => entry_points,
+            | None => return Ok(()),
+        };
+
+        let name = self.main.to_string_lossy();
+        let stage = self.inner.stage;
+
+        if entry_points.iter().any(|entry| entry.name == name && entry.stage == stage) == false {
+
+            let declared: Vec<String> = entry_points.iter()
+                .map(|entry| format!("`{}`({:?})", entry.name, entry.stage))
+                .collect();
+            return Err(VkError::custom(format!("Shader module has no entry point `{}` for {:?} stage(declared entry points: {}).", name, stage, declared.join(", "))))
+        }
+
+        Ok(())
+    }
+
+    /// Check if the entry point of this stage declares its position output as `invariant`.
+    ///
+    /// Return None if the stage has no reflection(see `ShaderStageCI::reflection`) or its entry point is not declared.
+    pub fn is_position_invariant(&self) -> Option<bool> {
+
+        let name = self.main.to_string_lossy();
+        let stage = self.inner.stage;
+
+        self.entry_points.as_ref()?.iter()
+            .find(|entry| entry.name == name && entry.stage == stage)
+            .map(|entry| entry.is_position_invariant)
+    }
+
     /// Set the `flags` member for `vk::PipelineShaderStageCreateInfo`.
     #[inline(always)]
     pub fn flags(mut self, flags: vk::PipelineShaderStageCreateFlags) -> ShaderStageCI {
@@ -196,8 +311,108 @@ impl ShaderStageCI {
     /// It describes the specialization constants used in this shader stage.
     #[inline(always)]
     pub fn specialization(mut self, info: vk::SpecializationInfo) -> ShaderStageCI {
-        self.specialization = Some(info);
-        self.inner.p_specialization_info = &info; self
+        self.specialization = Some(info); self
+    }
+
+    // For crate inner use.
+    /// Generate the create info of stage, whose pointers refer to the entry name and specialization of this object.
+    ///
+    /// This object must outlive the pipeline creation, since moving the builder would invalidate pointers stored in advance.
+    #[doc(hidden)]
+    pub(crate) fn inner_stage_ci(&self) -> vk::PipelineShaderStageCreateInfo {
+
+        vk::PipelineShaderStageCreateInfo {
+            p_name: self.main.as_ptr(),
+            p_specialization_info: self.specialization.as_ref()
+                .map_or(ptr::null(), |info| info as *const vk::SpecializationInfo),
+            ..self.inner
+        }
     }
 }
 // ---------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::utils::spirv::SPIRV_MAGIC;
+
+    /// A SPIR-V 1.0 module declaring only the vertex entry point `VSMain`.
+    fn vertex_module_reflection() -> ShaderReflection {
+
+        // `VSMain` packed into words with the lowest-order bytes first, and nul-terminated.
+        let name = [0x614D_5356, 0x0000_6E69];
+        let words = [
+            SPIRV_MAGIC, 0x0001_0000, 0, 2, 0,
+            // OpEntryPoint Vertex %1 "VSMain"
+            (5 << 16) | 15, 0, 1, name[0], name[1],
+        ];
+        ShaderReflection::from_words(&words).unwrap()
+    }
+
+    #[test]
+    fn declared_entry_point_passes() {
+
+        let stage = ShaderStageCI::new(vk::ShaderStageFlags::VERTEX, vk::ShaderModule::null())
+            .main("VSMain")
+            .reflection(&vertex_module_reflection());
+        assert!(stage.check_entry_point().is_ok());
+    }
+
+    #[test]
+    fn missing_entry_point_is_reported() {
+
+        let reflection = vertex_module_reflection();
+
+        // the default `main` is not declared.
+        let wrong_name = ShaderStageCI::new(vk::ShaderStageFlags::VERTEX, vk::ShaderModule::null())
+            .reflection(&reflection);
+        assert!(wrong_name.check_entry_point().is_err());
+
+        // `VSMain` is not declared for fragment stage.
+        let wrong_stage = ShaderStageCI::new(vk::ShaderStageFlags::FRAGMENT, vk::ShaderModule::null())
+            .main("VSMain")
+            .reflection(&reflection);
+        assert!(wrong_stage.check_entry_point().is_err());
+
+        // stages without reflection are not checked.
+        let unchecked = ShaderStageCI::new(vk::ShaderStageFlags::VERTEX, vk::ShaderModule::null());
+        assert!(unchecked.check_entry_point().is_ok());
+    }
+
+    #[test]
+    fn position_invariance_follows_reflection() {
+
+        // `VSMain`, the same as `vertex_module_reflection`.
+        let name = [0x614D_5356, 0x0000_6E69];
+        let words = [
+            SPIRV_MAGIC, 0x0001_0000, 0, 100, 0,
+            // OpEntryPoint Vertex %1 "VSMain" %10
+            (6 << 16) | 15, 0, 1, name[0], name[1], 10,
+            // OpDecorate %10 BuiltIn Position, OpDecorate %10 Invariant
+            (4 << 16) | 71, 10, 11, 0,
+            (3 << 16) | 71, 10, 18,
+            // %30 = float, %34 = vec4, %50 = pointer to output vec4, %10 = output variable.
+            (3 << 16) | 22, 30, 32,
+            (4 << 16) | 23, 34, 30, 4,
+            (4 << 16) | 32, 50, 3, 34,
+            (4 << 16) | 59, 50, 10, 3,
+        ];
+        let reflection = ShaderReflection::from_words(&words).unwrap();
+
+        let stage = ShaderStageCI::new(vk::ShaderStageFlags::VERTEX, vk::ShaderModule::null())
+            .main("VSMain")
+            .reflection(&reflection);
+        assert_eq!(stage.is_position_invariant(), Some(true));
+
+        // the module declaring no position output.
+        let reflection = vertex_module_reflection();
+        let stage = ShaderStageCI::new(vk::ShaderStageFlags::VERTEX, vk::ShaderModule::null())
+            .main("VSMain")
+            .reflection(&reflection);
+        assert_eq!(stage.is_position_invariant(), Some(false));
+
+        let unchecked = ShaderStageCI::new(vk::ShaderStageFlags::VERTEX, vk::ShaderModule::null());
+        assert_eq!(unchecked.is_position_invariant(), None);
+    }
+}
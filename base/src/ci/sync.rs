@@ -58,7 +58,7 @@ impl VkObjectBuildableCI for SemaphoreCI {
 
         let semaphore = unsafe {
             device.logic.handle.create_semaphore(self.as_ref(), None)
-                .map_err(|_| VkError::create("Semaphore"))?
+                .map_err(|e| VkError::create_with("Semaphore", e))?
         };
         Ok(semaphore)
     }
@@ -140,7 +140,7 @@ impl VkObjectBuildableCI for FenceCI {
 
         let fence = unsafe {
             device.logic.handle.create_fence(self.as_ref(), None)
-                .or(Err(VkError::create("Fence")))?
+                .map_err(|e| VkError::create_with("Fence", e))?
         };
         Ok(fence)
     }
@@ -188,12 +188,20 @@ impl VkObjectDiscardable for &Vec<vk::Fence> {
     }
 }
 
+/// Map the result of `vkWaitForFences`, where `vk::Result::TIMEOUT` is reported as a recoverable `VkError::timeout`.
+pub(crate) fn fence_wait_error(result: vk::Result) -> VkError {
+    match result {
+        | vk::Result::TIMEOUT => VkError::timeout("Wait for fences"),
+        | _ => VkError::device("Wait for fences"),
+    }
+}
+
 impl VkObjectWaitable for vk::Fence {
 
     fn wait(self, device: &VkDevice, time: VkTimeDuration) -> VkResult<()> {
         unsafe {
             device.logic.handle.wait_for_fences(&[self], true, time.into())
-                .map_err(|_| VkError::device("Wait for fences"))
+                .map_err(fence_wait_error)
         }
     }
 }
@@ -1,8 +1,8 @@
 
-pub use self::recorder::VkCmdRecorder;
+pub use self::recorder::{VkCmdRecorder, RecordingGuard};
 pub use self::graphics::{IGraphics, CmdGraphicsApi};
 pub use self::compute::{ICompute, CmdComputeApi};
-pub use self::transfer::{ITransfer, CmdTransferApi};
+pub use self::transfer::{ITransfer, CmdTransferApi, TransferToken, PendingCommand};
 
 mod recorder;
 mod graphics;
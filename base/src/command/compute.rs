@@ -1,8 +1,13 @@
 
 use ash::vk;
+use ash::version::DeviceV1_0;
 
 use crate::command::VkCommandType;
 use crate::command::recorder::VkCmdRecorder;
+use crate::vkuint;
+
+use crate::ci::pipeline::{PipelineLayout, debug_check_push_constants};
+use crate::utils::memory::any_as_u8_slice;
 
 pub struct ICompute;
 
@@ -11,9 +16,64 @@ impl VkCommandType for ICompute {
 }
 
 impl<'a> CmdComputeApi for VkCmdRecorder<'a, ICompute> {
-    // Not implement yet...
+
+    fn bind_pipeline(&self, pipeline: vk::Pipeline) -> &VkCmdRecorder<'a, ICompute> {
+        unsafe {
+            self.device.handle.cmd_bind_pipeline(self.command, ICompute::BIND_POINT, pipeline);
+        } self
+    }
+
+    fn bind_descriptor_sets(&self, layout: vk::PipelineLayout, first_set: vkuint, descriptor_sets: &[vk::DescriptorSet], dynamic_offsets: &[vkuint]) -> &VkCmdRecorder<'a, ICompute> {
+
+        unsafe {
+            self.device.handle.cmd_bind_descriptor_sets(self.command, ICompute::BIND_POINT, layout, first_set, descriptor_sets, dynamic_offsets);
+        } self
+    }
+
+    fn push_constants(&self, layout: vk::PipelineLayout, stage: vk::ShaderStageFlags, offset: vkuint, data: &[u8]) -> &VkCmdRecorder<'a, ICompute> {
+        unsafe {
+            self.device.handle.cmd_push_constants(self.command, layout, stage, offset, data);
+        } self
+    }
+
+    /// Push `data` as push constants, whose byte range is computed from the size of `T`.
+    ///
+    /// In debug build, the range is checked against the push constant ranges declared in `layout`.
+    fn push_constant_data<T: Copy>(&self, layout: &PipelineLayout, stage: vk::ShaderStageFlags, offset: vkuint, data: &T) -> &VkCmdRecorder<'a, ICompute> {
+
+        let bytes = unsafe { any_as_u8_slice(data) };
+        debug_check_push_constants(layout, stage, offset, bytes.len() as vkuint);
+
+        self.push_constants(layout.handle, stage, offset, bytes)
+    }
+
+    /// Dispatch the workgroups of the bound compute pipeline.
+    ///
+    /// See `utils::compute::group_count` to compute the count of workgroups within `maxComputeWorkGroupCount`.
+    fn dispatch(&self, group_count_x: vkuint, group_count_y: vkuint, group_count_z: vkuint) -> &VkCmdRecorder<'a, ICompute> {
+        unsafe {
+            self.device.handle.cmd_dispatch(self.command, group_count_x, group_count_y, group_count_z);
+        } self
+    }
+
+    fn image_pipeline_barrier(&self, src_stage: vk::PipelineStageFlags, dst_stage: vk::PipelineStageFlags, dependencies: vk::DependencyFlags, image_barriers: &[vk::ImageMemoryBarrier]) -> &VkCmdRecorder<'a, ICompute> {
+        unsafe {
+            self.device.handle.cmd_pipeline_barrier(self.command, src_stage, dst_stage, dependencies, &[], &[], image_barriers);
+        } self
+    }
 }
 
 pub trait CmdComputeApi {
-    // Not implement yet...
+
+    fn bind_pipeline(&self, pipeline: vk::Pipeline) -> &Self;
+
+    fn bind_descriptor_sets(&self, layout: vk::PipelineLayout, first_set: vkuint, descriptor_sets: &[vk::DescriptorSet], dynamic_offsets: &[vkuint]) -> &Self;
+
+    fn push_constants(&self, layout: vk::PipelineLayout, stage: vk::ShaderStageFlags, offset: vkuint, data: &[u8]) -> &Self;
+
+    fn push_constant_data<T: Copy>(&self, layout: &PipelineLayout, stage: vk::ShaderStageFlags, offset: vkuint, data: &T) -> &Self;
+
+    fn dispatch(&self, group_count_x: vkuint, group_count_y: vkuint, group_count_z: vkuint) -> &Self;
+
+    fn image_pipeline_barrier(&self, src_stage: vk::PipelineStageFlags, dst_stage: vk::PipelineStageFlags, dependencies: vk::DependencyFlags, image_barriers: &[vk::ImageMemoryBarrier]) -> &Self;
 }
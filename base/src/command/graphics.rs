@@ -3,10 +3,12 @@ use ash::vk;
 use ash::version::DeviceV1_0;
 
 use crate::command::VkCommandType;
-use crate::command::recorder::VkCmdRecorder;
+use crate::command::recorder::{VkCmdRecorder, RenderScope};
 use crate::{vkuint, vkfloat, vksint, vkbytes};
 
-use crate::ci::pipeline::RenderPassBI;
+use crate::ci::pipeline::{RenderPassBI, RenderingBI};
+use crate::ci::pipeline::{PipelineLayout, debug_check_push_constants};
+use crate::utils::memory::any_as_u8_slice;
 
 
 pub struct IGraphics;
@@ -19,10 +21,38 @@ impl<'a> CmdGraphicsApi for VkCmdRecorder<'a, IGraphics> {
 
     fn begin_render_pass(&self, bi: RenderPassBI) -> &VkCmdRecorder<'a, IGraphics> {
 
+        bi.debug_check_clear_values();
+
         // Currently only use primary command buffer, so always set vk::SubpassContents::INLINE here.
         unsafe {
             self.device.handle.cmd_begin_render_pass(self.command, &(bi.as_ref()), vk::SubpassContents::INLINE);
-        } self
+        }
+        self.scope.set(RenderScope::RenderPass); self
+    }
+
+    /// Begin dynamic rendering into the attachments of `bi`, without any render pass or framebuffer object.
+    ///
+    /// It requires `VK_KHR_dynamic_rendering` to be enabled(see `VkPhysicalDevice::is_dynamic_rendering_support`), and panics otherwise.
+    /// Unlike render pass, the layout transitions of attachments are not done implicitly, so record the image barriers before and after rendering.
+    fn begin_rendering(&self, bi: &RenderingBI) -> &VkCmdRecorder<'a, IGraphics> {
+
+        let commands = self.device.dynamic_rendering()
+            .expect("VK_KHR_dynamic_rendering must be enabled before recording dynamic rendering.");
+        unsafe {
+            commands.cmd_begin_rendering(self.command, bi);
+        }
+        self.scope.set(RenderScope::DynamicRendering); self
+    }
+
+    /// End the dynamic rendering begun by `begin_rendering`.
+    fn end_rendering(&self) -> &VkCmdRecorder<'a, IGraphics> {
+
+        let commands = self.device.dynamic_rendering()
+            .expect("VK_KHR_dynamic_rendering must be enabled before recording dynamic rendering.");
+        unsafe {
+            commands.cmd_end_rendering(self.command);
+        }
+        self.scope.set(RenderScope::Outside); self
     }
 
     /// Set the viewport dynamically.
@@ -39,6 +69,27 @@ impl<'a> CmdGraphicsApi for VkCmdRecorder<'a, IGraphics> {
         } self
     }
 
+    /// Set the first viewport dynamically to `viewport` with its depth range replaced by [`min_depth`, `max_depth`].
+    ///
+    /// See `utils::viewport::viewport_depth_range` for the interaction with depth test and clearing.
+    fn set_viewport_depth_range(&self, viewport: vk::Viewport, min_depth: vkfloat, max_depth: vkfloat) -> &VkCmdRecorder<'a, IGraphics> {
+        self.set_viewport(0, &[crate::utils::viewport::viewport_depth_range(viewport, min_depth, max_depth)])
+    }
+
+    /// Set all the viewports dynamically, starting from the first viewport.
+    ///
+    /// More than one viewport requires `multi_viewport` feature, and the count must match `ViewportSCI::with_dynamic_viewport_count`.
+    fn set_viewports(&self, viewports: &[vk::Viewport]) -> &VkCmdRecorder<'a, IGraphics> {
+        self.set_viewport(0, viewports)
+    }
+
+    /// Set all the scissor rectangles dynamically, starting from the first scissor.
+    ///
+    /// More than one scissor requires `multi_viewport` feature, and the count must match `ViewportSCI::with_dynamic_scissor_count`.
+    fn set_scissors(&self, scissors: &[vk::Rect2D]) -> &VkCmdRecorder<'a, IGraphics> {
+        self.set_scissor(0, scissors)
+    }
+
     /// Set the line width dynamically.
     fn set_line_width(&self, width: vkfloat) -> &VkCmdRecorder<'a, IGraphics> {
         unsafe {
@@ -47,6 +98,8 @@ impl<'a> CmdGraphicsApi for VkCmdRecorder<'a, IGraphics> {
     }
 
     /// Set the depth bias dynamically.
+    ///
+    /// The pipeline must enable depth bias by `RasterizationSCI::depth_bias` and declare `vk::DynamicState::DEPTH_BIAS`.
     fn set_depth_bias(&self, constant_factor: vkfloat, clamp: vkfloat, slope_factor: vkfloat) -> &VkCmdRecorder<'a, IGraphics> {
         unsafe {
             self.device.handle.cmd_set_depth_bias(self.command, constant_factor, clamp, slope_factor)
@@ -77,7 +130,7 @@ impl<'a> CmdGraphicsApi for VkCmdRecorder<'a, IGraphics> {
     /// Set the stencil write mask dynamically.
     fn set_stencil_write_mask(&self, face: vk::StencilFaceFlags, mask: vkuint) -> &VkCmdRecorder<'a, IGraphics> {
         unsafe {
-            self.device.handle.cmd_set_stencil_compare_mask(self.command, face, mask);
+            self.device.handle.cmd_set_stencil_write_mask(self.command, face, mask);
         } self
     }
 
@@ -94,6 +147,17 @@ impl<'a> CmdGraphicsApi for VkCmdRecorder<'a, IGraphics> {
         } self
     }
 
+    /// Push `data` as push constants, whose byte range is computed from the size of `T`.
+    ///
+    /// In debug build, the range is checked against the push constant ranges declared in `layout`.
+    fn push_constant_data<T: Copy>(&self, layout: &PipelineLayout, stage: vk::ShaderStageFlags, offset: vkuint, data: &T) -> &VkCmdRecorder<'a, IGraphics> {
+
+        let bytes = unsafe { any_as_u8_slice(data) };
+        debug_check_push_constants(layout, stage, offset, bytes.len() as vkuint);
+
+        self.push_constants(layout.handle, stage, offset, bytes)
+    }
+
     fn bind_pipeline(&self, pipeline: vk::Pipeline) -> &VkCmdRecorder<'a, IGraphics> {
         unsafe {
             self.device.handle.cmd_bind_pipeline(self.command, IGraphics::BIND_POINT, pipeline);
@@ -132,11 +196,25 @@ impl<'a> CmdGraphicsApi for VkCmdRecorder<'a, IGraphics> {
         } self
     }
 
+    /// Clear regions of the attachments of current subpass. This must be recorded inside a render pass.
+    fn clear_attachments(&self, attachments: &[vk::ClearAttachment], rects: &[vk::ClearRect]) -> &VkCmdRecorder<'a, IGraphics> {
+        unsafe {
+            self.device.handle.cmd_clear_attachments(self.command, attachments, rects);
+        } self
+    }
+
+    fn image_pipeline_barrier(&self, src_stage: vk::PipelineStageFlags, dst_stage: vk::PipelineStageFlags, dependencies: vk::DependencyFlags, image_barriers: &[vk::ImageMemoryBarrier]) -> &VkCmdRecorder<'a, IGraphics> {
+        unsafe {
+            self.device.handle.cmd_pipeline_barrier(self.command, src_stage, dst_stage, dependencies, &[], &[], image_barriers);
+        } self
+    }
+
     fn end_render_pass(&self) -> &VkCmdRecorder<'a, IGraphics> {
         // Ending the render pass will add an implicit barrier transitioning the frame buffer color attachment vk::IMAGE_LAYOUT_PRESENT_SRC_KHR for presenting it to the windowing system.
         unsafe {
             self.device.handle.cmd_end_render_pass(self.command);
-        } self
+        }
+        self.scope.set(RenderScope::Outside); self
     }
 }
 
@@ -144,10 +222,20 @@ pub trait CmdGraphicsApi {
 
     fn begin_render_pass(&self, bi: RenderPassBI) -> &Self;
 
+    fn begin_rendering(&self, bi: &RenderingBI) -> &Self;
+
+    fn end_rendering(&self) -> &Self;
+
     fn set_viewport(&self, first_viewport: vkuint, viewports: &[vk::Viewport]) -> &Self;
 
     fn set_scissor(&self, first_scissor: vkuint, scissors: &[vk::Rect2D]) -> &Self;
 
+    fn set_viewport_depth_range(&self, viewport: vk::Viewport, min_depth: vkfloat, max_depth: vkfloat) -> &Self;
+
+    fn set_viewports(&self, viewports: &[vk::Viewport]) -> &Self;
+
+    fn set_scissors(&self, scissors: &[vk::Rect2D]) -> &Self;
+
     fn set_line_width(&self, width: vkfloat) -> &Self;
 
     fn set_depth_bias(&self, constant_factor: vkfloat, clamp: vkfloat, slope_factor: vkfloat) -> &Self;
@@ -164,6 +252,8 @@ pub trait CmdGraphicsApi {
 
     fn push_constants(&self, layout: vk::PipelineLayout, stage: vk::ShaderStageFlags, offset: vkuint, data: &[u8]) -> &Self;
 
+    fn push_constant_data<T: Copy>(&self, layout: &PipelineLayout, stage: vk::ShaderStageFlags, offset: vkuint, data: &T) -> &Self;
+
     fn bind_pipeline(&self, pipeline: vk::Pipeline) -> &Self;
 
     fn bind_vertex_buffers(&self, first_binding: vkuint, buffers: &[vk::Buffer], offsets: &[vkbytes]) -> &Self;
@@ -176,5 +266,9 @@ pub trait CmdGraphicsApi {
 
     fn draw_indexed(&self, index_count: vkuint, instance_count: vkuint, first_index: vkuint, vertex_offset: vksint, first_instance: vkuint) -> &Self;
 
+    fn clear_attachments(&self, attachments: &[vk::ClearAttachment], rects: &[vk::ClearRect]) -> &Self;
+
+    fn image_pipeline_barrier(&self, src_stage: vk::PipelineStageFlags, dst_stage: vk::PipelineStageFlags, dependencies: vk::DependencyFlags, image_barriers: &[vk::ImageMemoryBarrier]) -> &Self;
+
     fn end_render_pass(&self) -> &Self;
 }
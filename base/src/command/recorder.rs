@@ -6,8 +6,18 @@ use crate::context::VkLogicalDevice;
 use crate::error::{VkResult, VkError};
 
 use std::marker::PhantomData;
+use std::cell::Cell;
+use std::ops::Deref;
 use std::ptr;
 
+/// The render pass instance or dynamic rendering which has begun but not ended in the recording command buffer.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub(super) enum RenderScope {
+    Outside,
+    RenderPass,
+    DynamicRendering,
+}
+
 pub struct VkCmdRecorder<'a, T> {
 
     phantom_marker: PhantomData<T>,
@@ -15,6 +25,8 @@ pub struct VkCmdRecorder<'a, T> {
     pub(super) device: &'a VkLogicalDevice,
     pub(super) command: vk::CommandBuffer,
     usage  : vk::CommandBufferUsageFlags,
+    /// updated by the begin and end commands of render pass and dynamic rendering.
+    pub(super) scope: Cell<RenderScope>,
 }
 
 impl<'a, 'd: 'a, T> VkCmdRecorder<'a, T> {
@@ -24,6 +36,7 @@ impl<'a, 'd: 'a, T> VkCmdRecorder<'a, T> {
         VkCmdRecorder {
             device, command,
             usage: vk::CommandBufferUsageFlags::empty(),
+            scope: Cell::new(RenderScope::Outside),
             phantom_marker: PhantomData,
         }
     }
@@ -45,6 +58,8 @@ impl<'a, 'd: 'a, T> VkCmdRecorder<'a, T> {
             self.device.handle.begin_command_buffer(self.command, &begin_ci)
                 .or(Err(VkError::device("Begin Command Buffer.")))?;
         }
+
+        self.scope.set(RenderScope::Outside);
         Ok(self)
     }
 
@@ -58,6 +73,77 @@ impl<'a, 'd: 'a, T> VkCmdRecorder<'a, T> {
         Ok(())
     }
 
+    /// Begin recording, and return a guard which ends the recording when it is dropped.
+    ///
+    /// Prefer `RecordingGuard::end` to finish recording, so that the error of `vkEndCommandBuffer` is not ignored.
+    /// The guard only makes sure that the command buffer leaves the recording state on early return(ending the render pass or dynamic rendering left open first),
+    /// so a command buffer whose recording is interrupted by error should not be submitted.
+    pub fn begin_scoped(&self) -> VkResult<RecordingGuard<'_, 'a, T>> {
+
+        self.begin_record()?;
+        let guard = RecordingGuard { recorder: self, is_ended: false };
+        Ok(guard)
+    }
+
+    /// Record the commands in `record_func` between `begin_record` and `end_record`.
+    ///
+    /// The recording is ended even if `record_func` returns error, in which case the error is returned.
+    pub fn record(&self, record_func: impl FnOnce(&VkCmdRecorder<'a, T>) -> VkResult<()>) -> VkResult<()> {
+
+        let guard = self.begin_scoped()?;
+        record_func(&guard)?;
+        guard.end()
+    }
+
+    /// Record the release operation of a queue family ownership transfer.
+    ///
+    /// This must be recorded in a command buffer submitted to the source queue family.
+    ///
+    /// `src_stage` is the pipeline stage which last accessed the resources on source queue.
+    ///
+    /// The `dst_access_mask` of each barrier is ignored,
+    /// and the same barriers must be recorded by `acquire_ownership` on the destination queue family.
+    pub fn release_ownership(&self, src_stage: vk::PipelineStageFlags, buffer_barriers: &[vk::BufferMemoryBarrier], image_barriers: &[vk::ImageMemoryBarrier]) -> &Self {
+
+        unsafe {
+            self.device.handle.cmd_pipeline_barrier(self.command, src_stage, vk::PipelineStageFlags::BOTTOM_OF_PIPE, vk::DependencyFlags::empty(), &[], buffer_barriers, image_barriers);
+        } self
+    }
+
+    /// Record the acquire operation of a queue family ownership transfer.
+    ///
+    /// This must be recorded in a command buffer submitted to the destination queue family,
+    /// and executed after the release operation recorded by `release_ownership` has completed(usually by a semaphore or fence).
+    ///
+    /// `dst_stage` is the pipeline stage which will first access the resources on destination queue.
+    ///
+    /// The `src_access_mask` of each barrier is ignored.
+    pub fn acquire_ownership(&self, dst_stage: vk::PipelineStageFlags, buffer_barriers: &[vk::BufferMemoryBarrier], image_barriers: &[vk::ImageMemoryBarrier]) -> &Self {
+
+        unsafe {
+            self.device.handle.cmd_pipeline_barrier(self.command, vk::PipelineStageFlags::TOP_OF_PIPE, dst_stage, vk::DependencyFlags::empty(), &[], buffer_barriers, image_barriers);
+        } self
+    }
+
+    /// End the render pass instance or dynamic rendering left open, so that the recording can be ended validly.
+    fn close_render_scope(&self) {
+
+        match self.scope.replace(RenderScope::Outside) {
+            | RenderScope::Outside => {},
+            | RenderScope::RenderPass => unsafe {
+                self.device.handle.cmd_end_render_pass(self.command);
+            },
+            | RenderScope::DynamicRendering => {
+                // dynamic rendering must have been enabled to begin it.
+                if let Some(commands) = self.device.dynamic_rendering() {
+                    unsafe {
+                        commands.cmd_end_rendering(self.command);
+                    }
+                }
+            },
+        }
+    }
+
     pub fn reset_command(&self, flags: vk::CommandBufferResetFlags) -> VkResult<()> {
 
         unsafe {
@@ -67,3 +153,41 @@ impl<'a, 'd: 'a, T> VkCmdRecorder<'a, T> {
         Ok(())
     }
 }
+
+/// A recording command buffer, whose recording is ended when this object is dropped.
+///
+/// This object can be used as `VkCmdRecorder` to record commands. See `VkCmdRecorder::begin_scoped` for detail.
+pub struct RecordingGuard<'r, 'a, T> {
+
+    recorder: &'r VkCmdRecorder<'a, T>,
+    is_ended: bool,
+}
+
+impl<'r, 'a, T> RecordingGuard<'r, 'a, T> {
+
+    /// End the recording explicitly, and return the error of `vkEndCommandBuffer` if any.
+    pub fn end(mut self) -> VkResult<()> {
+        self.is_ended = true;
+        self.recorder.end_record()
+    }
+}
+
+impl<'r, 'a, T> Deref for RecordingGuard<'r, 'a, T> {
+    type Target = VkCmdRecorder<'a, T>;
+
+    fn deref(&self) -> &VkCmdRecorder<'a, T> {
+        self.recorder
+    }
+}
+
+impl<'r, 'a, T> Drop for RecordingGuard<'r, 'a, T> {
+
+    fn drop(&mut self) {
+        if self.is_ended == false {
+            // the early return may leave a render pass or dynamic rendering open, which must be ended before the command buffer.
+            self.recorder.close_render_scope();
+            // there is no way to report error in drop, and the recording has failed anyway.
+            let _ = self.recorder.end_record();
+        }
+    }
+}
@@ -2,8 +2,11 @@
 use ash::vk;
 use ash::version::DeviceV1_0;
 
-use crate::ci::sync::FenceCI;
+use crate::ci::sync::{FenceCI, fence_wait_error};
 use crate::ci::device::SubmitCI;
+use crate::ci::vma::VmaBuffer;
+use crate::ci::image::ImageBarrierCI;
+use crate::context::VkDevice;
 
 use crate::command::VkCommandType;
 use crate::command::recorder::VkCmdRecorder;
@@ -21,19 +24,178 @@ impl<'a> VkCmdRecorder<'a, ITransfer> {
 
     pub fn flush_copy_command(&self, queue: vk::Queue) -> VkResult<()> {
 
+        let wait_fence = self.submit_with_fence(queue)?;
+
         unsafe {
+            let result = self.device.handle.wait_for_fences(&[wait_fence], true, VkTimeDuration::Infinite.into())
+                .map_err(fence_wait_error);
+            self.device.handle.destroy_fence(wait_fence, None);
+            result
+        }
+    }
 
-            let submit_ci = SubmitCI::new()
-                .add_command(self.command);
+    /// Similar to `flush_copy_command`, but wait the commands for at most `time`.
+    ///
+    /// Return None if the commands have finished, or a `PendingCommand` if they do not finish in time.
+    /// In the latter case the command buffer must not be reset or freed until the `PendingCommand` has been waited.
+    pub fn flush_copy_command_with(&self, queue: vk::Queue, time: VkTimeDuration) -> VkResult<Option<PendingCommand>> {
+
+        let wait_fence = self.submit_with_fence(queue)?;
+
+        unsafe {
+            match self.device.handle.wait_for_fences(&[wait_fence], true, time.into()) {
+                | Ok(_) => {
+                    self.device.handle.destroy_fence(wait_fence, None);
+                    Ok(None)
+                },
+                | Err(vk::Result::TIMEOUT) => {
+                    Ok(Some(PendingCommand { fence: wait_fence, command_pool: None }))
+                },
+                | Err(e) => {
+                    self.device.handle.destroy_fence(wait_fence, None);
+                    Err(fence_wait_error(e))
+                },
+            }
+        }
+    }
+
+    /// Submit the recorded commands to `queue`, and return the fence which will be signaled when they finish.
+    fn submit_with_fence(&self, queue: vk::Queue) -> VkResult<vk::Fence> {
+
+        let submit_ci = SubmitCI::new()
+            .add_command(self.command);
+
+        unsafe {
 
             let wait_fence = self.device.handle.create_fence(FenceCI::new(false).as_ref(), None)
-                .or(Err(VkError::create("Fence")))?;
-            self.device.handle.queue_submit(queue, &[*submit_ci.as_ref()], wait_fence)
-                .map_err(|_| VkError::device("Queue Submit"))?;
-            self.device.handle.wait_for_fences(&[wait_fence], true, VkTimeDuration::Infinite.into())
-                .map_err(|_| VkError::device("Wait for fences"))?;
-            self.device.handle.destroy_fence(wait_fence, None);
+                .map_err(|e| VkError::create_with("Fence", e))?;
+
+            if self.device.handle.queue_submit(queue, &[*submit_ci.as_ref()], wait_fence).is_err() {
+                self.device.handle.destroy_fence(wait_fence, None);
+                return Err(VkError::device("Queue Submit"))
+            }
+
+            Ok(wait_fence)
+        }
+    }
+
+    /// Copy `regions` from `src` buffer to `dst` image, together with the layout transitions before and after the copy.
+    ///
+    /// `sub_range` must cover all the mip levels and array layers written by `regions`.
+    /// The image is transitioned from `vk::ImageLayout::UNDEFINED`(its previous content is discarded) to `TRANSFER_DST_OPTIMAL` before the copy,
+    /// and to `final_layout` after the copy.
+    pub fn upload_buf2img(&self, src: vk::Buffer, dst: vk::Image, sub_range: vk::ImageSubresourceRange, regions: &[vk::BufferImageCopy], final_layout: vk::ImageLayout) -> &Self {
+
+        let barrier_before = ImageBarrierCI::new(dst, sub_range)
+            .access_mask(vk::AccessFlags::empty(), vk::AccessFlags::TRANSFER_WRITE)
+            .layout(vk::ImageLayout::UNDEFINED, vk::ImageLayout::TRANSFER_DST_OPTIMAL);
+
+        let barrier_after = ImageBarrierCI::new(dst, sub_range)
+            .access_mask(vk::AccessFlags::TRANSFER_WRITE, vk::AccessFlags::SHADER_READ)
+            .layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL, final_layout);
+
+        self.image_pipeline_barrier(vk::PipelineStageFlags::HOST, vk::PipelineStageFlags::TRANSFER, vk::DependencyFlags::empty(), &[barrier_before.into()])
+            .copy_buf2img(src, dst, vk::ImageLayout::TRANSFER_DST_OPTIMAL, regions)
+            .image_pipeline_barrier(vk::PipelineStageFlags::TRANSFER, vk::PipelineStageFlags::ALL_COMMANDS, vk::DependencyFlags::empty(), &[barrier_after.into()])
+    }
+}
+
+/// The handle of a transfer operation submitted by `VkDevice::flush_transfer_async`, which may still be executing on device.
+pub struct TransferToken {
+
+    /// `semaphore` will be signaled when the transferred resources are ready to be used by graphics queue.
+    ///
+    /// It should be waited by exactly one graphics queue submission.
+    pub semaphore: vk::Semaphore,
+    /// `fence` will be signaled when all the operations of this transfer have finished.
+    pub fence: vk::Fence,
+
+    /// the command pools and semaphores used internally by this transfer.
+    pub(crate) command_pools: Vec<vk::CommandPool>,
+    pub(crate) internal_semaphores: Vec<vk::Semaphore>,
+    /// the staging buffers which must be kept alive until this transfer finishes.
+    pub(crate) staging: Vec<VmaBuffer>,
+}
+
+impl TransferToken {
+
+    /// Block until the transfer has finished.
+    pub fn wait(&self, device: &VkDevice) -> VkResult<()> {
+        device.wait(self.fence, VkTimeDuration::Infinite)
+    }
+
+    /// Wait the transfer for at most `time`.
+    ///
+    /// Return `VkError::timeout` if the transfer does not finish in time, and the token can be waited again later.
+    pub fn wait_with(&self, device: &VkDevice, time: VkTimeDuration) -> VkResult<()> {
+        device.wait(self.fence, time)
+    }
+
+    /// Check if the transfer has finished without blocking.
+    pub fn is_finished(&self, device: &VkDevice) -> VkResult<bool> {
+        device.is_fence_signaled(self.fence)
+    }
+
+    /// Wait for the transfer to finish and destroy all its resources(including the staging buffers).
+    pub fn discard_by(self, device: &mut VkDevice) -> VkResult<()> {
+
+        self.wait(device)?;
+
+        for staging_buffer in self.staging.into_iter() {
+            device.vma_discard(staging_buffer)?;
+        }
+
+        for command_pool in self.command_pools.into_iter() {
+            device.discard(command_pool);
+        }
+
+        for semaphore in self.internal_semaphores.into_iter() {
+            device.discard(semaphore);
+        }
+
+        device.discard(self.semaphore);
+        device.discard(self.fence);
+
+        Ok(())
+    }
+}
+
+/// The commands submitted by `flush_copy_command_with` or `VkDevice::submit_once_with`, which did not finish before timeout.
+///
+/// The resources used by the commands can only be destroyed after they finish,
+/// so poll it by `PendingCommand::is_finished` or wait it again, and discard it at last.
+#[must_use]
+pub struct PendingCommand {
+
+    /// `fence` will be signaled when the commands finish.
+    pub fence: vk::Fence,
+    /// the temporary command pool owning the command buffer, which is destroyed with this pending command.
+    pub(crate) command_pool: Option<vk::CommandPool>,
+}
+
+impl PendingCommand {
+
+    /// Check if the commands have finished without blocking.
+    pub fn is_finished(&self, device: &VkDevice) -> VkResult<bool> {
+        device.is_fence_signaled(self.fence)
+    }
+
+    /// Wait the commands for at most `time`.
+    ///
+    /// Return `VkError::timeout` if the commands still do not finish in time.
+    pub fn wait(&self, device: &VkDevice, time: VkTimeDuration) -> VkResult<()> {
+        device.wait(self.fence, time)
+    }
+
+    /// Wait for the commands to finish and destroy the fence and the command pool.
+    pub fn discard_by(self, device: &VkDevice) -> VkResult<()> {
+
+        self.wait(device, VkTimeDuration::Infinite)?;
+
+        if let Some(command_pool) = self.command_pool {
+            device.discard(command_pool);
         }
+        device.discard(self.fence);
 
         Ok(())
     }
@@ -2,12 +2,15 @@
 pub use self::device::{VkDevice, VkLogicalDevice, VkPhysicalDevice};
 pub use self::device::{VkObjectDiscardable, VkObjectAllocatable, VkObjectBindable};
 pub use self::device::VmaResourceDiscardable;
+pub use self::device::MappedMemory;
 pub use self::device::{VkObjectWaitable, VkSubmitCI};
 pub use self::swapchain::{VkSwapchain, SwapchainSyncError};
 
 pub use self::instance::InstanceConfig;
 pub use self::debug::ValidationConfig;
-pub use self::device::{LogicDevConfig, PhysicalDevConfig};
+pub use self::device::{LogicDevConfig, PhysicalDevConfig, DeviceExtensionType};
+pub use self::device::{PhysicalDevSelection, PhysicalDevCandidate};
+pub(crate) use self::device::is_stencil_format;
 pub use self::swapchain::SwapchainConfig;
 
 mod instance;
@@ -39,6 +42,9 @@ pub struct VulkanContext {
 
     pub swapchain: swapchain::VkSwapchain,
     pub device: device::VkDevice,
+
+    /// the surfaces and swapchains of the secondary windows(see `WindowContext::add_window`), whose index starts from 1.
+    secondaries: Vec<(surface::VkSurface, swapchain::VkSwapchain)>,
 }
 
 impl VulkanContext {
@@ -51,14 +57,72 @@ impl VulkanContext {
         }
     }
 
-    pub(super) fn recreate_swapchain(&mut self, window: &WindowContext) -> VkResult<()> {
+    /// Get the count of windows presented by this context, including the primary window.
+    #[inline]
+    pub fn window_count(&self) -> usize {
+        self.secondaries.len() + 1
+    }
+
+    /// Get the swapchain of the window at `window_index`, where 0 is the primary window(the same as `VulkanContext::swapchain`).
+    pub fn swapchain_at(&self, window_index: usize) -> &swapchain::VkSwapchain {
+        if window_index == 0 {
+            &self.swapchain
+        } else {
+            &self.secondaries[window_index - 1].1
+        }
+    }
+
+    /// Get the device and the swapchain of the window at `window_index` at the same time.
+    pub(super) fn device_with_swapchain(&mut self, window_index: usize) -> (&mut device::VkDevice, &swapchain::VkSwapchain) {
+        if window_index == 0 {
+            (&mut self.device, &self.swapchain)
+        } else {
+            (&mut self.device, &self.secondaries[window_index - 1].1)
+        }
+    }
+
+    /// Check if the surface of the window at `window_index` has zero area(etc. the window is minimized), in which case no swapchain can be created.
+    pub(super) fn is_surface_zero_area(&self, window: &WindowContext, window_index: usize) -> VkResult<bool> {
+
+        let surface = if window_index == 0 { &self.surface } else { &self.secondaries[window_index - 1].0 };
+        let surface_caps = surface.query_capabilities(self.device.phy.handle)?;
+
+        // 0xFFFFFFFF means the extent of surface is determined by the extent of swapchain, so check the window instead.
+        let extent = if surface_caps.current_extent.width == 0xFFFF_FFFF {
+            window.dimension_at(window_index)?
+        } else {
+            surface_caps.current_extent
+        };
+
+        Ok(extent.width == 0 || extent.height == 0)
+    }
+
+    pub(super) fn recreate_swapchain(&mut self, window: &WindowContext, window_index: usize) -> VkResult<()> {
+
+        let dimension = window.dimension_at(window_index)?;
 
-        let dimension = window.dimension()?;
-        self.swapchain.rebuild(&self.instance, &self.device, &self.surface, dimension)?;
+        if window_index == 0 {
+            self.swapchain.rebuild(&self.instance, &self.device, &self.surface, dimension)?;
+            self.swapchain.hidpi_factor = window.hidpi_factor_at(window_index);
+        } else {
+            let (ref surface, ref mut swapchain) = self.secondaries[window_index - 1];
+            swapchain.rebuild(&self.instance, &self.device, surface, dimension)?;
+            swapchain.hidpi_factor = window.hidpi_factor_at(window_index);
+        }
 
         Ok(())
     }
 
+    /// Count a frame rendered to the window at `window_index`, and destroy its swapchains retired by `recreate_swapchain` if they are no longer presented.
+    pub(super) fn release_retired_swapchain(&mut self, window_index: usize) {
+
+        if window_index == 0 {
+            self.swapchain.release_retired(&self.device);
+        } else {
+            self.secondaries[window_index - 1].1.release_retired(&self.device);
+        }
+    }
+
     pub(super) fn wait_idle(&self) -> VkResult<()> {
         unsafe {
             self.device.logic.handle.device_wait_idle()
@@ -73,6 +137,12 @@ impl VulkanContext {
         self.swapchain.discard(&self.device);
         drop(self.swapchain);
 
+        for (surface, swapchain) in self.secondaries.into_iter() {
+            swapchain.discard(&self.device);
+            drop(swapchain);
+            drop(surface);
+        }
+
         self.device.drop_self();
 
         drop(self.surface);
@@ -105,25 +175,49 @@ impl<'a> VulkanContextBuilder<'a> {
         self.config.dev_phy = config; self
     }
 
+    /// Override the `device_selection` of physical device config, leaving its other fields unchanged.
+    ///
+    /// The devices found are listed in `VkPhysicalDevice::candidates` after the context is built.
+    pub fn with_physical_device_selection(mut self, selection: PhysicalDevSelection) -> VulkanContextBuilder<'a> {
+        self.config.dev_phy.device_selection = selection; self
+    }
+
     pub fn with_swapchain_config(mut self, config: SwapchainConfig) -> VulkanContextBuilder<'a> {
         self.config.swapchain = config; self
     }
 
     pub fn build(self) -> VkResult<VulkanContext> {
 
-        let instance = instance::VkInstance::new(self.config.instance, &self.config.debugger)?;
+        let mut instance_config = self.config.instance;
+        // color spaces other than sRGB non-linear require VK_EXT_swapchain_colorspace extension.
+        if self.config.swapchain.color_space != ash::vk::ColorSpaceKHR::SRGB_NONLINEAR {
+            instance_config.enable_swapchain_colorspace = true;
+        }
+
+        let instance = instance::VkInstance::new(instance_config, &self.config.debugger)?;
         let debugger = debug::VkDebugger::new(&instance, self.config.debugger)?;
         let surface = surface::VkSurface::new(&instance, &self.window.handle)?;
 
         let phy_device = device::VkPhysicalDevice::new(&instance, self.config.dev_phy)?;
-        let logic_device = device::VkLogicalDevice::new(&instance, &phy_device, self.config.dev_logic)?;
+        let logic_device = device::VkLogicalDevice::new(&instance, &phy_device, &surface, self.config.dev_logic)?;
         let vma = VulkanContextBuilder::build_vma(&instance, &phy_device, &logic_device)?;
         let device = device::VkDevice::new(logic_device, phy_device, vma)?;
 
         let dimension = self.window.dimension()?;
-        let swapchain = swapchain::VkSwapchain::new(&instance, &device, &surface, self.config.swapchain, dimension)?;
+        let mut swapchain = swapchain::VkSwapchain::new(&instance, &device, &surface, self.config.swapchain.clone(), dimension)?;
+        swapchain.hidpi_factor = self.window.hidpi_factor();
+
+        // all the windows share the same device, and own their surfaces and swapchains.
+        let mut secondaries = Vec::with_capacity(self.window.window_count() - 1);
+        for window_index in 1..self.window.window_count() {
+            let secondary_surface = surface::VkSurface::new(&instance, self.window.handle_at(window_index))?;
+            let secondary_dimension = self.window.dimension_at(window_index)?;
+            let mut secondary_swapchain = swapchain::VkSwapchain::new(&instance, &device, &secondary_surface, self.config.swapchain.clone(), secondary_dimension)?;
+            secondary_swapchain.hidpi_factor = self.window.hidpi_factor_at(window_index);
+            secondaries.push((secondary_surface, secondary_swapchain));
+        }
 
-        let context = VulkanContext { instance, debugger, surface, device, swapchain };
+        let context = VulkanContext { instance, debugger, surface, device, swapchain, secondaries };
         Ok(context)
     }
 
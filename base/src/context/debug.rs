@@ -167,7 +167,7 @@ impl VkDebugReport {
 
         let callback = unsafe {
             loader.create_debug_report_callback(&debug_callback_ci, None)
-                .or(Err(VkError::create("Debug Report Callback")))?
+                .map_err(|e| VkError::create_with("Debug Report Callback", e))?
         };
 
         let report = VkDebugReport { loader, callback };
@@ -276,7 +276,7 @@ impl VkDebugUtils {
 
         let utils_messenger = unsafe {
             loader.create_debug_utils_messenger(&messenger_ci, None)
-                .or(Err(VkError::create("Debug Utils Callback")))?
+                .map_err(|e| VkError::create_with("Debug Utils Callback", e))?
         };
 
         let utils = VkDebugUtils { loader, utils_messenger };
@@ -4,18 +4,24 @@ mod logical;
 mod queue;
 
 pub use self::logical::{VkLogicalDevice, VkQueue, LogicDevConfig};
-pub use self::physical::{VkPhysicalDevice, PhysicalDevConfig};
+pub use self::physical::{VkPhysicalDevice, PhysicalDevConfig, DeviceExtensionType};
+pub use self::physical::{PhysicalDevSelection, PhysicalDevCandidate};
+pub(crate) use self::physical::is_stencil_format;
 
 use ash::vk;
 use ash::version::DeviceV1_0;
 
 use crate::ci::command::{CommandPoolCI, CommandBufferAI};
+use crate::ci::sync::{SemaphoreCI, FenceCI};
+use crate::ci::device::SubmitCI;
+use crate::ci::vma::{VmaBuffer, VmaImage};
 use crate::ci::pipeline::PipelineCacheCI;
+use crate::ci::image::ImageCI;
 use crate::ci::VkObjectBuildableCI;
 
 use crate::utils::time::VkTimeDuration;
-use crate::command::{VkCmdRecorder, ITransfer};
-use crate::{VkResult, VkError};
+use crate::command::{VkCmdRecorder, ITransfer, TransferToken, PendingCommand};
+use crate::{VkResult, VkError, VkErrorKind};
 use crate::{vkbytes, vkuint, vkptr};
 
 pub struct VkDevice {
@@ -75,6 +81,295 @@ impl VkDevice {
         }
     }
 
+    /// Submit the transfer commands recorded by `record` without waiting for them to finish.
+    ///
+    /// Different from `VkDevice::flush_transfer`, the commands are recorded into a new command buffer,
+    /// so that multiple transfers can be in flight at the same time.
+    ///
+    /// `ownership` are the barriers of the resources written by this transfer.
+    /// If the transfer and graphics queue families are different, their ownership will be transferred to graphics queue family,
+    /// and they will be ready to use at `dst_stage` on graphics queue after waiting `TransferToken::semaphore`.
+    ///
+    /// `staging` are the buffers read by this transfer, which will be destroyed with the returned `TransferToken`.
+    pub fn flush_transfer_async(&self, record: impl FnOnce(&VkCmdRecorder<ITransfer>), ownership: &[vk::BufferMemoryBarrier], dst_stage: vk::PipelineStageFlags, staging: Vec<VmaBuffer>) -> VkResult<TransferToken> {
+
+        let is_acquire_required = self.is_transfer_family_separate() && !ownership.is_empty();
+
+        let mut token = TransferToken {
+            semaphore: self.build(&SemaphoreCI::new())?,
+            fence    : self.build(&FenceCI::new(false))?,
+            command_pools: Vec::with_capacity(2),
+            internal_semaphores: Vec::new(),
+            staging,
+        };
+
+        // record and submit the transfer commands.
+        let transfer_pool = CommandPoolCI::new(self.logic.queues.transfer.family_index)
+            .flags(vk::CommandPoolCreateFlags::TRANSIENT)
+            .build(self)?;
+        token.command_pools.push(transfer_pool);
+
+        let transfer_command = CommandBufferAI::new(transfer_pool, 1)
+            .build(self)?.remove(0);
+        let mut recorder: VkCmdRecorder<ITransfer> = VkCmdRecorder::new(&self.logic, transfer_command);
+        recorder.set_usage(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT);
+
+        record(recorder.begin_record()?);
+        if is_acquire_required {
+            recorder.release_ownership(vk::PipelineStageFlags::TRANSFER, ownership, &[]);
+        }
+        recorder.end_record()?;
+
+        if is_acquire_required {
+
+            // the acquire operation on graphics queue must wait for the release operation on transfer queue.
+            let release_semaphore = self.build(&SemaphoreCI::new())?;
+            token.internal_semaphores.push(release_semaphore);
+
+            let transfer_submit = SubmitCI::new()
+                .add_command(transfer_command)
+                .add_signal(release_semaphore);
+            self.submit(transfer_submit, self.logic.queues.transfer.handle, None)?;
+
+            let graphics_pool = CommandPoolCI::new(self.logic.queues.graphics.family_index)
+                .flags(vk::CommandPoolCreateFlags::TRANSIENT)
+                .build(self)?;
+            token.command_pools.push(graphics_pool);
+
+            let acquire_command = CommandBufferAI::new(graphics_pool, 1)
+                .build(self)?.remove(0);
+            let mut recorder: VkCmdRecorder<ITransfer> = VkCmdRecorder::new(&self.logic, acquire_command);
+            recorder.set_usage(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT);
+
+            recorder.begin_record()?
+                .acquire_ownership(dst_stage, ownership, &[])
+                .end_record()?;
+
+            let acquire_submit = SubmitCI::new()
+                .add_wait(dst_stage, release_semaphore)
+                .add_command(acquire_command)
+                .add_signal(token.semaphore);
+            self.submit(acquire_submit, self.logic.queues.graphics.handle, Some(token.fence))?;
+        } else {
+
+            let transfer_submit = SubmitCI::new()
+                .add_command(transfer_command)
+                .add_signal(token.semaphore);
+            self.submit(transfer_submit, self.logic.queues.transfer.handle, Some(token.fence))?;
+        }
+
+        Ok(token)
+    }
+
+    /// Check if the transfer queue and graphics queue belong to different queue families.
+    ///
+    /// If true, the resources written by transfer queue require a queue family ownership transfer before read by graphics queue.
+    #[inline]
+    pub fn is_transfer_family_separate(&self) -> bool {
+        self.logic.queues.transfer.family_index != self.logic.queues.graphics.family_index
+    }
+
+    /// Acquire the ownership of resources on graphics queue, which have been released from transfer queue family.
+    ///
+    /// It submits the acquire barriers to graphics queue and waits until they finish.
+    pub fn acquire_graphics_ownership(&self, dst_stage: vk::PipelineStageFlags, buffer_barriers: &[vk::BufferMemoryBarrier], image_barriers: &[vk::ImageMemoryBarrier]) -> VkResult<()> {
+
+        // use a temporary command pool, since the internal pool belongs to transfer queue family.
+        self.submit_once(&self.logic.queues.graphics, |recorder| {
+            recorder.acquire_ownership(dst_stage, buffer_barriers, image_barriers);
+            Ok(())
+        })
+    }
+
+    /// Record the commands by `record` into a temporary command buffer, submit it to `queue` and wait until it finishes.
+    ///
+    /// The command buffer is allocated from a transient command pool of the queue family of `queue`,
+    /// which is destroyed after the submission, even if `record` or the submission fails.
+    pub fn submit_once(&self, queue: &VkQueue, record: impl FnOnce(&VkCmdRecorder<ITransfer>) -> VkResult<()>) -> VkResult<()> {
+
+        let command_pool = CommandPoolCI::new(queue.family_index)
+            .flags(vk::CommandPoolCreateFlags::TRANSIENT)
+            .build(self)?;
+
+        let result = self.record_once(command_pool, record)
+            .and_then(|recorder| recorder.flush_copy_command(queue.handle));
+
+        // the command buffer is freed together with its pool.
+        self.discard(command_pool);
+
+        result
+    }
+
+    /// Similar to `VkDevice::submit_once`, but wait the commands for at most `time`.
+    ///
+    /// Return None if the commands have finished, or a `PendingCommand` owning the command pool if they do not finish in time.
+    pub fn submit_once_with(&self, queue: &VkQueue, time: VkTimeDuration, record: impl FnOnce(&VkCmdRecorder<ITransfer>) -> VkResult<()>) -> VkResult<Option<PendingCommand>> {
+
+        let command_pool = CommandPoolCI::new(queue.family_index)
+            .flags(vk::CommandPoolCreateFlags::TRANSIENT)
+            .build(self)?;
+
+        let result = self.record_once(command_pool, record)
+            .and_then(|recorder| recorder.flush_copy_command_with(queue.handle, time));
+
+        match result {
+            | Ok(Some(pending)) => {
+                // the command buffer may be still executing, so keep its pool until the pending command is discarded.
+                Ok(Some(PendingCommand { command_pool: Some(command_pool), ..pending }))
+            },
+            | _ => {
+                self.discard(command_pool);
+                result
+            },
+        }
+    }
+
+    /// Allocate a one time submit command buffer from `command_pool`, and record the commands by `record`.
+    fn record_once(&self, command_pool: vk::CommandPool, record: impl FnOnce(&VkCmdRecorder<ITransfer>) -> VkResult<()>) -> VkResult<VkCmdRecorder<ITransfer>> {
+
+        let command = CommandBufferAI::new(command_pool, 1)
+            .build(self)?.remove(0);
+
+        let mut recorder: VkCmdRecorder<ITransfer> = VkCmdRecorder::new(&self.logic, command);
+        recorder.set_usage(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT);
+
+        record(recorder.begin_record()?)?;
+        recorder.end_record()?;
+
+        Ok(recorder)
+    }
+
+    /// Upload `bytes` to a new device local 2D image with a single mip level, and make it ready to be sampled in fragment shader.
+    ///
+    /// `bytes` must be the tightly packed texels of `format`. The returned image is in `vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL` layout.
+    pub fn upload_image(&mut self, bytes: &[u8], extent: vk::Extent2D, format: vk::Format) -> VkResult<VmaImage> {
+
+        use crate::ci::buffer::BufferCI;
+        use crate::ci::image::ImageBarrierCI;
+        use crate::ci::vma::VmaAllocationCI;
+        use crate::command::CmdTransferApi;
+
+        let dst_image = {
+            let image_ci = ImageCI::new_2d(format, extent)
+                .usages(vk::ImageUsageFlags::TRANSFER_DST | vk::ImageUsageFlags::SAMPLED);
+            let allocation_ci = VmaAllocationCI::new(vma::MemoryUsage::GpuOnly, vk::MemoryPropertyFlags::DEVICE_LOCAL);
+            let image_allocation = self.vma.create_image(image_ci.as_ref(), allocation_ci.as_ref())
+                .map_err(VkErrorKind::Vma)?;
+            VmaImage::from(image_allocation)
+        };
+
+        let staging_buffer = {
+            let staging_ci = BufferCI::new(bytes.len() as vkbytes)
+                .usage(vk::BufferUsageFlags::TRANSFER_SRC);
+            let allocation_ci = VmaAllocationCI::new(vma::MemoryUsage::CpuOnly, vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT)
+                .flags(vma::AllocationCreateFlags::MAPPED);
+            let staging_allocation = self.vma.create_buffer(staging_ci.as_ref(), allocation_ci.as_ref())
+                .map_err(VkErrorKind::Vma)?;
+
+            unsafe {
+                let data_ptr = staging_allocation.2.get_mapped_data() as vkptr<u8>;
+                data_ptr.copy_from_nonoverlapping(bytes.as_ptr(), bytes.len());
+            }
+
+            VmaBuffer::from(staging_allocation)
+        };
+
+        let sub_range = vk::ImageSubresourceRange {
+            aspect_mask: vk::ImageAspectFlags::COLOR,
+            base_mip_level: 0,
+            level_count   : 1,
+            base_array_layer: 0,
+            layer_count     : 1,
+        };
+
+        let copy_region = vk::BufferImageCopy {
+            buffer_offset: 0,
+            buffer_row_length  : 0,
+            buffer_image_height: 0,
+            image_subresource: vk::ImageSubresourceLayers {
+                aspect_mask: vk::ImageAspectFlags::COLOR,
+                mip_level: 0,
+                base_array_layer: 0,
+                layer_count     : 1,
+            },
+            image_offset: vk::Offset3D { x: 0, y: 0, z: 0 },
+            image_extent: vk::Extent3D { width: extent.width, height: extent.height, depth: 1 },
+        };
+
+        let recorder = self.get_transfer_recorder();
+        recorder.begin_record()?;
+
+        // the image is written by transfer queue but read by graphics queue.
+        let ownership_barrier = if self.is_transfer_family_separate() {
+
+            let barrier_before = ImageBarrierCI::new(dst_image.handle, sub_range)
+                .access_mask(vk::AccessFlags::empty(), vk::AccessFlags::TRANSFER_WRITE)
+                .layout(vk::ImageLayout::UNDEFINED, vk::ImageLayout::TRANSFER_DST_OPTIMAL);
+            // the layout transition is performed by the release and acquire operations.
+            let barrier_ownership: vk::ImageMemoryBarrier = ImageBarrierCI::new(dst_image.handle, sub_range)
+                .access_mask(vk::AccessFlags::TRANSFER_WRITE, vk::AccessFlags::SHADER_READ)
+                .layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL, vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
+                .queue_family_index(self.logic.queues.transfer.family_index, self.logic.queues.graphics.family_index)
+                .into();
+
+            recorder
+                .image_pipeline_barrier(vk::PipelineStageFlags::HOST, vk::PipelineStageFlags::TRANSFER, vk::DependencyFlags::empty(), &[barrier_before.into()])
+                .copy_buf2img(staging_buffer.handle, dst_image.handle, vk::ImageLayout::TRANSFER_DST_OPTIMAL, &[copy_region])
+                .release_ownership(vk::PipelineStageFlags::TRANSFER, &[], &[barrier_ownership]);
+
+            Some(barrier_ownership)
+        } else {
+
+            recorder.upload_buf2img(staging_buffer.handle, dst_image.handle, sub_range, &[copy_region], vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL);
+            None
+        };
+
+        recorder.end_record()?;
+        self.flush_transfer(recorder)?;
+
+        if let Some(barrier) = ownership_barrier {
+            self.acquire_graphics_ownership(vk::PipelineStageFlags::FRAGMENT_SHADER, &[], &[barrier])?;
+        }
+
+        self.vma_discard(staging_buffer)?;
+
+        Ok(dst_image)
+    }
+
+    /// Create a device local image used as framebuffer attachment.
+    ///
+    /// If `is_transient` is true, the image is created with `vk::ImageUsageFlags::TRANSIENT_ATTACHMENT`,
+    /// and its memory is lazily allocated if device supports it, falling back to regular device local memory otherwise.
+    /// Only use transient image for attachments whose content is never loaded or stored outside render pass(etc. a depth buffer that is never sampled).
+    pub fn create_attachment_image(&mut self, image_ci: ImageCI, is_transient: bool) -> VkResult<VmaImage> {
+
+        use crate::ci::vma::VmaAllocationCI;
+
+        let image_ci = if is_transient {
+            let usage = image_ci.as_ref().usage;
+            image_ci.usages(usage | vk::ImageUsageFlags::TRANSIENT_ATTACHMENT)
+        } else {
+            image_ci
+        };
+
+        if is_transient && self.phy.is_lazily_allocated_support() {
+
+            let allocation_ci = VmaAllocationCI::new(vma::MemoryUsage::GpuOnly, vk::MemoryPropertyFlags::LAZILY_ALLOCATED)
+                .preferred_flags(vk::MemoryPropertyFlags::DEVICE_LOCAL);
+
+            // the lazily allocated memory types may not be acceptable for this image, so fallback to regular memory on failure.
+            if let Ok(image_allocation) = self.vma.create_image(image_ci.as_ref(), allocation_ci.as_ref()) {
+                return Ok(VmaImage::from(image_allocation))
+            }
+        }
+
+        let allocation_ci = VmaAllocationCI::new(vma::MemoryUsage::GpuOnly, vk::MemoryPropertyFlags::DEVICE_LOCAL);
+        let image_allocation = self.vma.create_image(image_ci.as_ref(), allocation_ci.as_ref())
+            .map_err(VkErrorKind::Vma)?;
+
+        Ok(VmaImage::from(image_allocation))
+    }
+
     pub(super) fn drop_self(self) {
 
         self.discard(self.transfer_cmd_pool);
@@ -107,6 +402,15 @@ impl VkDevice {
         Ok(ptr as vkptr<T>)
     }
 
+    /// Map `memory` and return a guard that unmaps it when dropped, so the mapping is not leaked on error paths.
+    ///
+    /// Use `VkDevice::map_memory` instead for persistent mapping(etc. uniform buffers updated every frame).
+    pub fn map_scoped(&self, memory: vk::DeviceMemory, offset: vkbytes, size: vkbytes) -> VkResult<MappedMemory> {
+
+        let ptr = self.map_memory(memory, offset, size)?;
+        Ok(MappedMemory { device: self, memory, ptr, offset, size })
+    }
+
     #[inline]
     pub fn unmap_memory(&self, memory: vk::DeviceMemory) {
         unsafe {
@@ -114,16 +418,120 @@ impl VkDevice {
         }
     }
 
+    /// Copy `data` to the start of mapped memory pointed by `ptr`.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must point to a mapped range which is large enough to hold `data`, and not be read by device at the same time.
+    #[inline]
+    pub unsafe fn copy_to_ptr<T: Copy>(&self, ptr: vkptr, data: &[T]) {
+        self.copy_to_ptr_offset(ptr, 0, data);
+    }
+
+    /// Copy `data` to mapped memory at `byte_offset` from `ptr`, which is useful to write a sub-region of ring buffer or per-frame uniforms.
+    ///
+    /// # Safety
+    ///
+    /// The range from `byte_offset` to the end of `data` must stay in the mapped range pointed by `ptr`.
+    /// `byte_offset` does not need to be aligned to `T`.
+    pub unsafe fn copy_to_ptr_offset<T: Copy>(&self, ptr: vkptr, byte_offset: vkbytes, data: &[T]) {
+
+        let bytes = ::std::slice::from_raw_parts(data.as_ptr() as *const u8, ::std::mem::size_of_val(data));
+        self.copy_bytes_to_ptr_offset(ptr, byte_offset, bytes);
+    }
+
+    /// Copy raw `bytes` to the start of mapped memory pointed by `ptr`.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must point to a mapped range which is large enough to hold `bytes`.
+    #[inline]
+    pub unsafe fn copy_bytes_to_ptr(&self, ptr: vkptr, bytes: &[u8]) {
+        self.copy_bytes_to_ptr_offset(ptr, 0, bytes);
+    }
+
+    unsafe fn copy_bytes_to_ptr_offset(&self, ptr: vkptr, byte_offset: vkbytes, bytes: &[u8]) {
+        (ptr as vkptr<u8>).add(byte_offset as usize).copy_from_nonoverlapping(bytes.as_ptr(), bytes.len());
+    }
+
+    /// Make the host writes to non-coherent mapped memory visible to device.
+    ///
+    /// Each range is `(memory, offset, size)`, which must stay mapped during this call.
+    /// The ranges are expanded to the multiples of `nonCoherentAtomSize` as required by Vulkan,
+    /// so use `vk::WHOLE_SIZE` as size if the expanded range may exceed the end of mapping.
+    /// This is unnecessary for memory with `vk::MemoryPropertyFlags::HOST_COHERENT`.
+    pub fn flush_mapped_ranges(&self, ranges: &[(vk::DeviceMemory, vkbytes, vkbytes)]) -> VkResult<()> {
+
+        let mapped_ranges = self.mapped_memory_ranges(ranges);
+        unsafe {
+            self.logic.handle.flush_mapped_memory_ranges(&mapped_ranges)
+                .map_err(|_| VkError::device("Flush Mapped Memory Ranges"))
+        }
+    }
+
+    /// Make the device writes to non-coherent mapped memory visible to host.
+    ///
+    /// See `VkDevice::flush_mapped_ranges` for the requirements of `ranges`.
+    pub fn invalidate_mapped_ranges(&self, ranges: &[(vk::DeviceMemory, vkbytes, vkbytes)]) -> VkResult<()> {
+
+        let mapped_ranges = self.mapped_memory_ranges(ranges);
+        unsafe {
+            self.logic.handle.invalidate_mapped_memory_ranges(&mapped_ranges)
+                .map_err(|_| VkError::device("Invalidate Mapped Memory Ranges"))
+        }
+    }
+
+    fn mapped_memory_ranges(&self, ranges: &[(vk::DeviceMemory, vkbytes, vkbytes)]) -> Vec<vk::MappedMemoryRange> {
+
+        let atom_size = self.phy.limits.non_coherent_atom_size.max(1);
+
+        ranges.iter().map(|&(memory, offset, size)| {
+
+            let (aligned_offset, aligned_size) = align_mapped_range(offset, size, atom_size);
+
+            vk::MappedMemoryRange {
+                s_type: vk::StructureType::MAPPED_MEMORY_RANGE,
+                p_next: ::std::ptr::null(),
+                memory,
+                offset: aligned_offset,
+                size  : aligned_size,
+            }
+        }).collect()
+    }
+
     #[inline]
     pub fn submit(&self, ci: impl VkSubmitCI, queue: vk::Queue, wait_fence: Option<vk::Fence>) -> VkResult<()> {
         ci.submit(self, queue, wait_fence)
     }
 
+    /// Submit `ci` to the compute queue of device, which is used to dispatch compute pipelines.
+    ///
+    /// Check `QueryFamilies::is_compute_async` to see whether the compute queue runs asynchronously with graphics queue.
+    #[inline]
+    pub fn submit_compute(&self, ci: impl VkSubmitCI, wait_fence: Option<vk::Fence>) -> VkResult<()> {
+        ci.submit(self, self.logic.queues.compute.handle, wait_fence)
+    }
+
+    /// Wait `object` for at most `time`.
+    ///
+    /// Use `VkTimeDuration::Infinite` to wait without timeout. Otherwise `VkError::timeout` is returned if `object` is not signaled in time,
+    /// which can be checked by `VkError::is_timeout` to wait again or give up.
     #[inline]
     pub fn wait(&self, object: impl VkObjectWaitable, time: VkTimeDuration) -> VkResult<()> {
         object.wait(self, time)
     }
 
+    /// Check if `fence` has been signaled without blocking.
+    pub fn is_fence_signaled(&self, fence: vk::Fence) -> VkResult<bool> {
+        unsafe {
+            match self.logic.handle.get_fence_status(fence) {
+                | Ok(_) => Ok(true),
+                | Err(vk::Result::NOT_READY) => Ok(false),
+                | Err(_) => Err(VkError::device("Get Fence Status")),
+            }
+        }
+    }
+
     #[inline]
     pub fn discard(&self, object: impl VkObjectDiscardable) {
         object.discard_by(self);
@@ -148,6 +556,124 @@ impl VkDevice {
         use crate::utils::memory::get_memory_type_index;
         get_memory_type_index(self, type_bits, request_flags)
     }
+
+    /// Similar to `VkDevice::get_memory_type`, but return None if no memory type supports `request_flags`.
+    #[inline]
+    pub fn find_memory_type(&self, type_bits: vkuint, request_flags: vk::MemoryPropertyFlags) -> Option<vkuint> {
+        use crate::utils::memory::find_memory_type_index;
+        find_memory_type_index(self, type_bits, request_flags)
+    }
+
+    /// Return the first memory type index that is both `DEVICE_LOCAL` and `HOST_VISIBLE`, which is usually exposed by Resizable BAR.
+    ///
+    /// Data can be written to such memory directly without staging copy. Return None if the combination is not found.
+    #[inline]
+    pub fn find_direct_upload_memory(&self, type_bits: vkuint) -> Option<vkuint> {
+        use crate::utils::memory::find_direct_upload_memory_index;
+        find_direct_upload_memory_index(self, type_bits)
+    }
+
+    /// Get the limits of physical device(etc. `min_uniform_buffer_offset_alignment`, `max_push_constants_size`, `timestamp_period`).
+    #[inline]
+    pub fn limits(&self) -> &vk::PhysicalDeviceLimits {
+        &self.phy.limits
+    }
+
+    /// Get the properties of physical device.
+    #[inline]
+    pub fn properties(&self) -> &vk::PhysicalDeviceProperties {
+        self.phy.properties()
+    }
+
+    /// Query the features supported by `format`(etc. whether it can be blitted with linear filter, or used as depth attachment).
+    #[inline]
+    pub fn format_properties(&self, format: vk::Format) -> vk::FormatProperties {
+        self.phy.format_properties(format)
+    }
+
+    /// Find the first format in `candidates` supporting all `features` with `tiling`, or None if no candidate is supported.
+    #[inline]
+    pub fn find_supported_format(&self, candidates: &[vk::Format], tiling: vk::ImageTiling, features: vk::FormatFeatureFlags) -> Option<vk::Format> {
+        self.phy.find_supported_format(candidates, tiling, features)
+    }
+}
+
+/// Expand the range of `offset` and `size` to the multiples of `atom_size`, by rounding the offset down and the end up.
+///
+/// `vk::WHOLE_SIZE` is kept, which covers to the end of mapping.
+fn align_mapped_range(offset: vkbytes, size: vkbytes, atom_size: vkbytes) -> (vkbytes, vkbytes) {
+
+    let aligned_offset = offset - offset % atom_size;
+    let aligned_size = if size == vk::WHOLE_SIZE {
+        vk::WHOLE_SIZE
+    } else {
+        let end = offset + size;
+        (end + atom_size - 1) / atom_size * atom_size - aligned_offset
+    };
+
+    (aligned_offset, aligned_size)
+}
+
+/// A mapped range of device memory, which is unmapped when it goes out of scope.
+///
+/// It derefs to the pointer of the start of mapped range.
+pub struct MappedMemory<'a> {
+
+    device: &'a VkDevice,
+    memory: vk::DeviceMemory,
+    ptr: vkptr,
+    /// the offset of mapped range from the start of `memory`.
+    offset: vkbytes,
+    /// the size of mapped range, which may be `vk::WHOLE_SIZE`.
+    size: vkbytes,
+}
+
+impl<'a> MappedMemory<'a> {
+
+    /// Get the pointer of mapped range as `T`.
+    #[inline]
+    pub fn as_ptr<T>(&self) -> vkptr<T> {
+        self.ptr as vkptr<T>
+    }
+
+    /// Copy `data` to the start of mapped range.
+    ///
+    /// # Safety
+    ///
+    /// The mapped range must be large enough to hold `data`.
+    #[inline]
+    pub unsafe fn copy_from<T: Copy>(&self, data: &[T]) {
+        self.device.copy_to_ptr(self.ptr, data);
+    }
+
+    /// Flush the mapped range, which is required before unmapping non-coherent memory.
+    ///
+    /// The range is expanded to the multiples of `nonCoherentAtomSize`(see `VkDevice::flush_mapped_ranges`),
+    /// so map by `vk::WHOLE_SIZE` if the end of mapped range is not aligned and the expanded range may exceed the end of `memory`.
+    #[inline]
+    pub fn flush(&self) -> VkResult<()> {
+        self.device.flush_mapped_ranges(&[(self.memory, self.offset, self.size)])
+    }
+
+    /// Flush the sub-range at `offset` from the start of mapped range, whose size is `size` bytes.
+    pub fn flush_range(&self, offset: vkbytes, size: vkbytes) -> VkResult<()> {
+        self.device.flush_mapped_ranges(&[(self.memory, self.offset + offset, size)])
+    }
+}
+
+impl<'a> ::std::ops::Deref for MappedMemory<'a> {
+    type Target = vkptr;
+
+    fn deref(&self) -> &vkptr {
+        &self.ptr
+    }
+}
+
+impl<'a> Drop for MappedMemory<'a> {
+
+    fn drop(&mut self) {
+        self.device.unmap_memory(self.memory);
+    }
 }
 
 pub trait VkObjectDiscardable: Copy {
@@ -180,3 +706,18 @@ pub trait VkSubmitCI {
 
     fn submit(self, device: &VkDevice, queue: vk::Queue, wait_fence: Option<vk::Fence>) -> VkResult<()>;
 }
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn mapped_range_is_aligned_to_atom_size() {
+
+        assert_eq!(align_mapped_range(0, 64, 64), (0, 64));
+        assert_eq!(align_mapped_range(100, 20, 64), (64, 64));
+        assert_eq!(align_mapped_range(100, 40, 64), (64, 128));
+        assert_eq!(align_mapped_range(130, vk::WHOLE_SIZE, 64), (128, vk::WHOLE_SIZE));
+    }
+}
@@ -3,12 +3,15 @@ use ash::vk;
 use ash::version::{DeviceV1_0, InstanceV1_0};
 
 use crate::context::instance::VkInstance;
+use crate::context::surface::VkSurface;
 use crate::context::device::physical::VkPhysicalDevice;
 use crate::context::device::queue::{QueueRequester, QueueRequestStrategy};
+use crate::ci::pipeline::rendering::{DynamicRenderingFn, PhysicalDeviceDynamicRenderingFeaturesKHR};
 use crate::error::{VkResult, VkError};
 use crate::vkuint;
 
 use std::ptr;
+use std::sync::{Arc, Weak};
 
 
 #[derive(Debug, Clone)]
@@ -22,7 +25,7 @@ impl Default for LogicDevConfig {
     fn default() -> LogicDevConfig {
 
         LogicDevConfig {
-            request_queues: vk::QueueFlags::GRAPHICS | vk::QueueFlags::TRANSFER,
+            request_queues: vk::QueueFlags::GRAPHICS | vk::QueueFlags::COMPUTE | vk::QueueFlags::TRANSFER,
         }
     }
 }
@@ -33,12 +36,37 @@ pub struct VkLogicalDevice {
 
     pub handle: ash::Device,
     pub queues: QueryFamilies,
+
+    /// the commands of `VK_KHR_dynamic_rendering`, which are loaded only if dynamic rendering is enabled.
+    dynamic_rendering: Option<DynamicRenderingFn>,
+    /// a token that is dropped together with the device, so that other objects can tell whether the device is still alive.
+    alive: Arc<()>,
 }
 
 pub struct QueryFamilies {
     pub graphics: VkQueue,
     pub compute : VkQueue,
     pub transfer: VkQueue,
+    /// the queue used to present swapchain images to the surface of main window.
+    ///
+    /// It's the same queue as `graphics` if the graphics family supports presentation, otherwise it's from a separate family.
+    pub present : VkQueue,
+}
+
+impl QueryFamilies {
+
+    /// Check if the compute queue can execute commands asynchronously with the graphics queue.
+    ///
+    /// Return true if the compute queue is a separate queue(from a dedicated compute family or another queue of graphics family),
+    /// or false if it's shared with the graphics queue, in which case compute and graphics commands are executed in submission order.
+    pub fn is_compute_async(&self) -> bool {
+        self.compute.handle != self.graphics.handle
+    }
+
+    /// Check if the compute queue comes from a family without graphics capability.
+    pub fn is_compute_dedicated(&self) -> bool {
+        self.compute.family_index != self.graphics.family_index
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -49,7 +77,7 @@ pub struct VkQueue {
 
 impl VkLogicalDevice {
 
-    pub(crate) fn new(instance: &VkInstance, phy: &VkPhysicalDevice, config: LogicDevConfig) -> VkResult<VkLogicalDevice> {
+    pub(crate) fn new(instance: &VkInstance, phy: &VkPhysicalDevice, surface: &VkSurface, config: LogicDevConfig) -> VkResult<VkLogicalDevice> {
 
         let mut queue_requester = QueueRequester::new(instance, phy, QueueRequestStrategy::ExclusiveQueueCrossFamily);
         let mut queue_requests = QueuesRequestInfo::default();
@@ -59,7 +87,14 @@ impl VkLogicalDevice {
             queue_requests.graphics_index = Some(graphics_index);
         }
         if config.request_queues.contains(vk::QueueFlags::COMPUTE) {
-            let compute_index = queue_requester.request_queue(vk::QueueFlags::COMPUTE, 1.0)?;
+            // prefer the family without graphics for async compute, then another queue of any compute family(usually the graphics family),
+            // and finally share the graphics queue if no queue remains.
+            let compute_index = queue_requester.request_dedicated_queue(vk::QueueFlags::COMPUTE, vk::QueueFlags::GRAPHICS, 1.0)
+                .map(Ok)
+                .or_else(|| queue_requests.graphics_index.map(|graphics_index| {
+                    queue_requester.request_queue(vk::QueueFlags::COMPUTE, 1.0).or(Ok(graphics_index))
+                }))
+                .unwrap_or_else(|| queue_requester.request_queue(vk::QueueFlags::COMPUTE, 1.0))?;
             queue_requests.compute_index = Some(compute_index);
         }
         if config.request_queues.contains(vk::QueueFlags::TRANSFER) {
@@ -67,16 +102,40 @@ impl VkLogicalDevice {
             queue_requests.transfer_index = Some(transfer_index);
         }
 
+        // request the present queue after graphics queue, so that graphics queue would be shared if it can present.
+        let present_index = queue_requester.request_present_queue(|family_index| {
+            surface.query_is_family_presentable(phy.handle, family_index)
+        }, 1.0)?;
+        queue_requests.present_index = Some(present_index);
+
         let queue_cis = queue_requester.queue_cis();
 
         use crate::utils::cast::cstrings2ptrs;
         let enable_layer_names = cstrings2ptrs(&instance.enable_layer_names);
         let enable_extension_names = cstrings2ptrs(phy.enable_extensions());
 
+        // enable all the supported descriptor indexing features if VK_EXT_descriptor_indexing is requested.
+        let indexing_features = phy.descriptor_indexing_features().cloned();
+        let indexing_next = indexing_features.as_ref()
+            .map_or(ptr::null_mut(), |features| features as *const vk::PhysicalDeviceDescriptorIndexingFeaturesEXT as _);
+
+        // enable dynamic rendering if VK_KHR_dynamic_rendering is requested and supported, which is chained in front of descriptor indexing.
+        let rendering_features = if phy.is_dynamic_rendering_support() {
+            Some(PhysicalDeviceDynamicRenderingFeaturesKHR {
+                p_next: indexing_next,
+                dynamic_rendering: vk::TRUE,
+                ..Default::default()
+            })
+        } else {
+            None
+        };
+        let device_next = rendering_features.as_ref()
+            .map_or(indexing_next as *const _, |features| features as *const PhysicalDeviceDynamicRenderingFeaturesKHR as _);
+
         // Create the logical device.
         let device_ci = vk::DeviceCreateInfo {
             s_type                     : vk::StructureType::DEVICE_CREATE_INFO,
-            p_next                     : ptr::null(),
+            p_next                     : device_next,
             // flags is reserved for future use in API version 1.1.82.
             flags                      : vk::DeviceCreateFlags::empty(),
             queue_create_info_count    : queue_cis.len() as _,
@@ -90,7 +149,7 @@ impl VkLogicalDevice {
 
         let handle = unsafe {
             instance.handle.create_device(phy.handle, &device_ci, None)
-                .or(Err(VkError::create("Logical Device")))?
+                .map_err(|e| VkError::create_with("Logical Device", e))?
         };
 
         let queues = queue_requests.dispatch_queues(&handle, &queue_requester);
@@ -104,10 +163,28 @@ impl VkLogicalDevice {
         if config.request_queues.contains(vk::QueueFlags::TRANSFER) {
             debug_assert_ne!(queues.transfer.handle, vk::Queue::null())
         }
+        debug_assert_ne!(queues.present.handle, vk::Queue::null());
+
+        let dynamic_rendering = if rendering_features.is_some() {
+            Some(DynamicRenderingFn::load(&instance.handle, &handle)?)
+        } else {
+            None
+        };
 
-        let device = VkLogicalDevice { handle, queues };
+        let device = VkLogicalDevice { handle, queues, dynamic_rendering, alive: Arc::new(()) };
         Ok(device)
     }
+
+    /// Get the commands of `VK_KHR_dynamic_rendering`, or None if dynamic rendering is not enabled.
+    #[inline]
+    pub fn dynamic_rendering(&self) -> Option<&DynamicRenderingFn> {
+        self.dynamic_rendering.as_ref()
+    }
+
+    /// Get a weak reference that fails to upgrade once this device has been destroyed.
+    pub(crate) fn liveness(&self) -> Weak<()> {
+        Arc::downgrade(&self.alive)
+    }
 }
 
 impl Drop for VkLogicalDevice {
@@ -125,6 +202,7 @@ struct QueuesRequestInfo {
     graphics_index: Option<usize>,
     compute_index : Option<usize>,
     transfer_index: Option<usize>,
+    present_index : Option<usize>,
 }
 
 impl QueuesRequestInfo {
@@ -143,10 +221,15 @@ impl QueuesRequestInfo {
             Some(requester.dispatch_queue(device, transfer_index))
         }).unwrap_or_default();
 
+        let present_queue = self.present_index.and_then(|present_index| {
+            Some(requester.dispatch_queue(device, present_index))
+        }).unwrap_or_default();
+
         QueryFamilies {
             graphics: graphics_queue,
             compute : compute_queue,
             transfer: transfer_queue,
+            present : present_queue,
         }
     }
 }
@@ -1,25 +1,36 @@
 
 use ash::vk;
-use ash::version::InstanceV1_0;
+use ash::vk_make_version;
+use ash::version::{InstanceV1_0, InstanceV1_1};
 
 use crate::context::instance::VkInstance;
+use crate::ci::pipeline::rendering::PhysicalDeviceDynamicRenderingFeaturesKHR;
 use crate::utils::cast::{chars2string, chars2cstring};
 use crate::error::{VkResult, VkError};
+use crate::vkuint;
 
 use std::ffi::CString;
+use std::sync::Arc;
+use std::fmt;
 
 
 #[derive(Debug, Clone)]
 pub struct PhysicalDevConfig {
 
     pub print_device_properties: bool,
+    /// the type of device preferred by `PhysicalDevSelection::Preference`(default is `DISCRETE_GPU`).
     pub device_type_preference: vk::PhysicalDeviceType,
+    /// the way to select device from all the devices supporting `request_extensions`.
+    pub device_selection: PhysicalDevSelection,
 
     pub print_available_extensions: bool,
     pub request_extensions: Vec<CString>,
 
     pub print_available_features: bool,
     pub request_features: vk::PhysicalDeviceFeatures,
+
+    /// `is_require_stencil` specifies whether `VkPhysicalDevice::depth_format` must contain a stencil component.
+    pub is_require_stencil: bool,
 }
 
 impl Default for PhysicalDevConfig {
@@ -29,6 +40,7 @@ impl Default for PhysicalDevConfig {
         PhysicalDevConfig {
             print_device_properties: false,
             device_type_preference: vk::PhysicalDeviceType::DISCRETE_GPU,
+            device_selection: PhysicalDevSelection::Preference,
 
             print_available_extensions: false,
             request_extensions: vec![
@@ -37,10 +49,48 @@ impl Default for PhysicalDevConfig {
 
             print_available_features: false,
             request_features: vk::PhysicalDeviceFeatures::default(),
+
+            is_require_stencil: false,
+        }
+    }
+}
+
+/// The way to select the physical device, when there are several devices supporting all the requested extensions(etc. integrated and discrete GPUs on laptops).
+///
+/// If no device matches `Index` or `Predicate`, a warning is printed and the selection falls back to `Preference`.
+#[derive(Clone)]
+pub enum PhysicalDevSelection {
+    /// select the first device of `PhysicalDevConfig::device_type_preference`, or the first enumerated device if there is none.
+    Preference,
+    /// select the device at this index of enumeration(see `VkPhysicalDevice::candidates`).
+    Index(usize),
+    /// select the first device whose properties satisfy the predicate(etc. checking `device_name` or `vendor_id`).
+    Predicate(Arc<dyn Fn(&vk::PhysicalDeviceProperties) -> bool>),
+}
+
+impl fmt::Debug for PhysicalDevSelection {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            | PhysicalDevSelection::Preference => write!(f, "Preference"),
+            | PhysicalDevSelection::Index(index) => write!(f, "Index({})", index),
+            | PhysicalDevSelection::Predicate(_) => write!(f, "Predicate"),
         }
     }
 }
 
+/// A physical device found during device selection.
+#[derive(Debug, Clone)]
+pub struct PhysicalDevCandidate {
+
+    /// the index of device in enumeration, which can be used by `PhysicalDevSelection::Index`.
+    pub index: usize,
+    pub device_name: String,
+    pub device_type: vk::PhysicalDeviceType,
+    /// whether the device supports all the requested extensions(only the suitable devices can be selected).
+    pub is_suitable: bool,
+}
+
 pub struct VkPhysicalDevice {
 
     pub device_name: String,
@@ -49,49 +99,76 @@ pub struct VkPhysicalDevice {
     pub depth_format: vk::Format,
 
     pub limits: vk::PhysicalDeviceLimits,
-
+    /// the properties of device, including its name, type, vendor and the limits above.
+    properties: vk::PhysicalDeviceProperties,
+
+    /// the subgroup size of device, or None if it can not be queried(requires Vulkan 1.1).
+    subgroup_size: Option<vkuint>,
+    /// the descriptor indexing features supported by device, or None if `VK_EXT_descriptor_indexing` is not requested.
+    descriptor_indexing: Option<vk::PhysicalDeviceDescriptorIndexingFeaturesEXT>,
+    /// whether the `dynamicRendering` feature is supported, which is false if `VK_KHR_dynamic_rendering` is not requested.
+    dynamic_rendering: bool,
     features_enable: vk::PhysicalDeviceFeatures,
     config: PhysicalDevConfig,
+
+    /// all the physical devices found during device selection.
+    candidates: Vec<PhysicalDevCandidate>,
+
+    /// the instance function table, used to query the properties of physical device after its creation.
+    instance: ash::Instance,
 }
 
 impl VkPhysicalDevice {
 
     pub(crate) fn new(instance: &VkInstance, config: PhysicalDevConfig) -> VkResult<VkPhysicalDevice> {
 
-        let alternative_devices = VkPhysicalDevice::query_phy_devices(instance, &config)?;
+        let alternative_devices = VkPhysicalDevice::query_phy_devices(instance)?;
 
-        let mut selected_device = None;
+        let mut candidates = Vec::with_capacity(alternative_devices.len());
+        let mut suitable_devices = Vec::with_capacity(alternative_devices.len());
 
-        for phy_device in alternative_devices.into_iter() {
+        for (index, phy_device) in alternative_devices.into_iter().enumerate() {
 
             // make sure all requested extensions are support by device.
-            if is_all_extension_support(instance, &phy_device, &config)? == false {
-                continue
+            let is_suitable = is_all_extension_support(instance, &phy_device, &config)?;
+
+            candidates.push(PhysicalDevCandidate {
+                index, is_suitable,
+                device_name: chars2string(&phy_device.property.device_name),
+                device_type: phy_device.property.device_type,
+            });
+
+            if is_suitable {
+                suitable_devices.push((index, phy_device));
             }
+        }
+
+        if let Some(selected_index) = select_device(&suitable_devices, &config) {
+
+            let (_, phy_device) = suitable_devices.swap_remove(selected_index);
 
             if config.print_device_properties {
                 print_device_properties(&phy_device.property);
             }
 
-            selected_device = Some(phy_device);
-            break;
-        }
-
-        if let Some(phy_device) = selected_device {
-
             // get memory properties.
             let memories = unsafe {
                 instance.handle.get_physical_device_memory_properties(phy_device.handle)
             };
 
-            let depth_format = query_depth_format(instance, &phy_device);
+            let depth_format = query_depth_format(instance, &phy_device, config.is_require_stencil);
+            let subgroup_size = query_subgroup_size(instance, &phy_device);
+            let descriptor_indexing = query_descriptor_indexing(instance, &phy_device, &config);
+            let dynamic_rendering = query_dynamic_rendering(instance, &phy_device, &config);
 
             let dst_device = VkPhysicalDevice {
                 device_name: chars2string(&phy_device.property.device_name),
                 handle: phy_device.handle,
                 limits: phy_device.property.limits,
+                properties: phy_device.property,
                 features_enable: enable_feature_if_support(&phy_device, &config),
-                config, memories, depth_format,
+                instance: instance.handle.clone(),
+                config, memories, depth_format, subgroup_size, descriptor_indexing, dynamic_rendering, candidates,
             };
 
             Ok(dst_device)
@@ -101,31 +178,116 @@ impl VkPhysicalDevice {
         }
     }
 
-    fn query_phy_devices(instance: &VkInstance, config: &PhysicalDevConfig) -> VkResult<Vec<PhyDeviceTmp>> {
+    /// Query all physical devices in the order of `vkEnumeratePhysicalDevices`.
+    fn query_phy_devices(instance: &VkInstance) -> VkResult<Vec<PhyDeviceTmp>> {
 
         let alternative_devices = unsafe {
             instance.handle.enumerate_physical_devices()
                 .or(Err(VkError::query("Physical Device")))?
         };
 
-        let mut alternative_devices: Vec<PhyDeviceTmp> = alternative_devices.into_iter().map(|phy_device| {
+        let alternative_devices = alternative_devices.into_iter().map(|phy_device| {
             query_device_property(instance, phy_device)
         }).collect();
 
-        // sort available device by their device type.
-        alternative_devices.sort_by(|dev1, dev2| {
-            use std::cmp::Ordering;
+        Ok(alternative_devices)
+    }
 
-            if dev1.property.device_type == config.device_type_preference {
-                Ordering::Less
-            } else if dev2.property.device_type == config.device_type_preference {
-                Ordering::Greater
-            } else {
-                Ordering::Equal
-            }
-        });
+    /// Get all the physical devices found during device selection, including the ones not selected.
+    ///
+    /// Use the `index` of candidate with `PhysicalDevSelection::Index` to select a specific device next time.
+    #[inline]
+    pub fn candidates(&self) -> &[PhysicalDevCandidate] {
+        &self.candidates
+    }
 
-        Ok(alternative_devices)
+    /// Get the count of invocations in each subgroup, which is the preferred granularity of compute local size.
+    ///
+    /// Return None if both instance and device do not support Vulkan 1.1.
+    /// Note that some implementations(etc. Intel and some mobile GPUs) may execute shaders with a subgroup size different from this value,
+    /// so it should be considered as a hint for performance rather than a guarantee.
+    #[inline]
+    pub fn subgroup_size(&self) -> Option<vkuint> {
+        self.subgroup_size
+    }
+
+    /// Get the descriptor indexing features supported by device, which are all enabled in logical device.
+    ///
+    /// Return None if `DeviceExtensionType::DescriptorIndexing` is not requested in `PhysicalDevConfig`, or both instance and device do not support Vulkan 1.1.
+    #[inline]
+    pub fn descriptor_indexing_features(&self) -> Option<&vk::PhysicalDeviceDescriptorIndexingFeaturesEXT> {
+        self.descriptor_indexing.as_ref()
+    }
+
+    /// Check if dynamic rendering can be used, in which case the `dynamicRendering` feature is enabled in logical device.
+    ///
+    /// Return false if `DeviceExtensionType::DynamicRendering` is not requested in `PhysicalDevConfig`, or both instance and device do not support Vulkan 1.1.
+    #[inline]
+    pub fn is_dynamic_rendering_support(&self) -> bool {
+        self.dynamic_rendering
+    }
+
+    /// Get the maximum local size of compute workgroup in each dimension.
+    #[inline]
+    pub fn max_compute_work_group_size(&self) -> [vkuint; 3] {
+        self.limits.max_compute_work_group_size
+    }
+
+    /// Get the maximum count of workgroups that a dispatch can launch in each dimension.
+    ///
+    /// The Vulkan specification only guarantees 65535 workgroups in each dimension.
+    #[inline]
+    pub fn max_compute_work_group_count(&self) -> [vkuint; 3] {
+        self.limits.max_compute_work_group_count
+    }
+
+    /// Get the maximum count of invocations in a compute workgroup(the product of local size in three dimensions).
+    ///
+    /// The Vulkan specification only guarantees 128 invocations, with local size at least (128, 128, 64).
+    #[inline]
+    pub fn max_compute_work_group_invocations(&self) -> vkuint {
+        self.limits.max_compute_work_group_invocations
+    }
+
+    /// Query the features supported by `format` on this physical device.
+    pub fn format_properties(&self, format: vk::Format) -> vk::FormatProperties {
+        unsafe {
+            self.instance.get_physical_device_format_properties(self.handle, format)
+        }
+    }
+
+    /// Find the first format in `candidates` supporting all `features` with `tiling`, or None if no candidate is supported.
+    ///
+    /// Put the preferred formats in front of `candidates`.
+    pub fn find_supported_format(&self, candidates: &[vk::Format], tiling: vk::ImageTiling, features: vk::FormatFeatureFlags) -> Option<vk::Format> {
+        find_supported_format(&self.instance, self.handle, candidates, tiling, features)
+    }
+
+    /// Check if `depth_format` contains a stencil component.
+    #[inline]
+    pub fn is_depth_stencil(&self) -> bool {
+        is_stencil_format(self.depth_format)
+    }
+
+    /// Get the image aspects of `depth_format`, which contains `vk::ImageAspectFlags::STENCIL` only if the format has stencil component.
+    pub fn depth_aspect_mask(&self) -> vk::ImageAspectFlags {
+        if self.is_depth_stencil() {
+            vk::ImageAspectFlags::DEPTH | vk::ImageAspectFlags::STENCIL
+        } else {
+            vk::ImageAspectFlags::DEPTH
+        }
+    }
+
+    /// Check if device has any memory type with `vk::MemoryPropertyFlags::LAZILY_ALLOCATED`, which is common on tile-based GPUs.
+    pub fn is_lazily_allocated_support(&self) -> bool {
+        self.memories.memory_types[..(self.memories.memory_type_count as usize)].iter()
+            .any(|memory_type| memory_type.property_flags.contains(vk::MemoryPropertyFlags::LAZILY_ALLOCATED))
+    }
+
+    /// Get the properties of this physical device(etc. `device_type`, `vendor_id`, `api_version`).
+    #[inline]
+    pub fn properties(&self) -> &vk::PhysicalDeviceProperties {
+        &self.properties
     }
 
     #[inline]
@@ -150,19 +312,79 @@ struct PhyDeviceTmp {
 
 
 
+/// Return the position of selected device in `devices`, or None if `devices` is empty.
+fn select_device(devices: &[(usize, PhyDeviceTmp)], config: &PhysicalDevConfig) -> Option<usize> {
+
+    let selected = match config.device_selection {
+        | PhysicalDevSelection::Preference => None,
+        | PhysicalDevSelection::Index(index) => {
+            devices.iter().position(|(device_index, _)| *device_index == index)
+        },
+        | PhysicalDevSelection::Predicate(ref predicate) => {
+            devices.iter().position(|(_, device)| predicate(&device.property))
+        },
+    };
+
+    if selected.is_some() {
+        return selected
+    }
+
+    if let PhysicalDevSelection::Preference = config.device_selection {} else {
+        println!("[Warning] No suitable device matches {:?} selection, fall back to device type preference.", config.device_selection);
+    }
+
+    devices.iter().position(|(_, device)| device.property.device_type == config.device_type_preference)
+        .or(if devices.is_empty() { None } else { Some(0) })
+}
+
+
+
 // Physical Extension ----------------------------------------------------------------
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
 pub enum DeviceExtensionType {
     Swapchain,
+    /// `VK_KHR_maintenance1`, which allows negative viewport height(see `utils::viewport::viewport_flip_y`). It's core since Vulkan 1.1.
+    Maintenance1,
+    /// `VK_KHR_maintenance3`, which is required by `VK_EXT_descriptor_indexing`. It's core since Vulkan 1.1.
+    Maintenance3,
+    /// `VK_EXT_descriptor_indexing`, which allows large descriptor arrays indexed in shader(etc. bindless textures).
+    DescriptorIndexing,
+    /// `VK_KHR_create_renderpass2`, which is required by `VK_KHR_depth_stencil_resolve`.
+    CreateRenderPass2,
+    /// `VK_KHR_depth_stencil_resolve`, which is required by `VK_KHR_dynamic_rendering`.
+    DepthStencilResolve,
+    /// `VK_KHR_dynamic_rendering`, which renders without render pass and framebuffer objects(see `ci::pipeline::RenderingBI`).
+    ///
+    /// Request it together with `CreateRenderPass2` and `DepthStencilResolve`, on a Vulkan 1.1 instance.
+    DynamicRendering,
 }
 
 impl DeviceExtensionType {
 
-    fn name(&self) -> CString {
+    /// Get the extension name, which can be pushed into `PhysicalDevConfig::request_extensions`.
+    pub fn name(&self) -> CString {
         match self {
             | DeviceExtensionType::Swapchain => {
                 CString::new("VK_KHR_swapchain").unwrap()
             },
+            | DeviceExtensionType::Maintenance1 => {
+                CString::new("VK_KHR_maintenance1").unwrap()
+            },
+            | DeviceExtensionType::Maintenance3 => {
+                CString::new("VK_KHR_maintenance3").unwrap()
+            },
+            | DeviceExtensionType::DescriptorIndexing => {
+                CString::new("VK_EXT_descriptor_indexing").unwrap()
+            },
+            | DeviceExtensionType::CreateRenderPass2 => {
+                CString::new("VK_KHR_create_renderpass2").unwrap()
+            },
+            | DeviceExtensionType::DepthStencilResolve => {
+                CString::new("VK_KHR_depth_stencil_resolve").unwrap()
+            },
+            | DeviceExtensionType::DynamicRendering => {
+                CString::new("VK_KHR_dynamic_rendering").unwrap()
+            },
         }
     }
 }
@@ -214,6 +436,100 @@ fn query_device_property(instance: &VkInstance, phy_device: vk::PhysicalDevice)
     }
 }
 
+fn query_subgroup_size(instance: &VkInstance, phy_device: &PhyDeviceTmp) -> Option<vkuint> {
+
+    // vkGetPhysicalDeviceProperties2 is core in Vulkan 1.1, which must be supported by both instance and device.
+    let version_1_1 = vk_make_version!(1, 1, 0);
+    if instance.api_version < version_1_1 || phy_device.property.api_version < version_1_1 {
+        return None
+    }
+
+    let mut subgroup_property = vk::PhysicalDeviceSubgroupProperties {
+        s_type: vk::StructureType::PHYSICAL_DEVICE_SUBGROUP_PROPERTIES,
+        p_next: ::std::ptr::null_mut(),
+        subgroup_size: 0,
+        supported_stages: vk::ShaderStageFlags::empty(),
+        supported_operations: vk::SubgroupFeatureFlags::empty(),
+        quad_operations_in_all_stages: vk::FALSE,
+    };
+
+    let mut device_property = vk::PhysicalDeviceProperties2 {
+        s_type: vk::StructureType::PHYSICAL_DEVICE_PROPERTIES_2,
+        p_next: &mut subgroup_property as *mut vk::PhysicalDeviceSubgroupProperties as _,
+        properties: phy_device.property.clone(),
+    };
+
+    unsafe {
+        instance.handle.get_physical_device_properties2(phy_device.handle, &mut device_property);
+    }
+
+    if subgroup_property.subgroup_size > 0 {
+        Some(subgroup_property.subgroup_size)
+    } else {
+        None
+    }
+}
+
+fn query_descriptor_indexing(instance: &VkInstance, phy_device: &PhyDeviceTmp, config: &PhysicalDevConfig) -> Option<vk::PhysicalDeviceDescriptorIndexingFeaturesEXT> {
+
+    if config.request_extensions.contains(&DeviceExtensionType::DescriptorIndexing.name()) == false {
+        return None
+    }
+
+    // vkGetPhysicalDeviceFeatures2 is core in Vulkan 1.1, which must be supported by both instance and device.
+    let version_1_1 = vk_make_version!(1, 1, 0);
+    if instance.api_version < version_1_1 || phy_device.property.api_version < version_1_1 {
+        return None
+    }
+
+    let mut indexing_features = vk::PhysicalDeviceDescriptorIndexingFeaturesEXT {
+        s_type: vk::StructureType::PHYSICAL_DEVICE_DESCRIPTOR_INDEXING_FEATURES_EXT,
+        p_next: ::std::ptr::null_mut(),
+        ..Default::default()
+    };
+
+    let mut device_features = vk::PhysicalDeviceFeatures2 {
+        s_type: vk::StructureType::PHYSICAL_DEVICE_FEATURES_2,
+        p_next: &mut indexing_features as *mut vk::PhysicalDeviceDescriptorIndexingFeaturesEXT as _,
+        features: phy_device.features.clone(),
+    };
+
+    unsafe {
+        instance.handle.get_physical_device_features2(phy_device.handle, &mut device_features);
+    }
+
+    // the structure is chained to device creation later, so make sure it does not point to anything.
+    indexing_features.p_next = ::std::ptr::null_mut();
+    Some(indexing_features)
+}
+
+fn query_dynamic_rendering(instance: &VkInstance, phy_device: &PhyDeviceTmp, config: &PhysicalDevConfig) -> bool {
+
+    if config.request_extensions.contains(&DeviceExtensionType::DynamicRendering.name()) == false {
+        return false
+    }
+
+    // vkGetPhysicalDeviceFeatures2 is core in Vulkan 1.1, which must be supported by both instance and device.
+    let version_1_1 = vk_make_version!(1, 1, 0);
+    if instance.api_version < version_1_1 || phy_device.property.api_version < version_1_1 {
+        return false
+    }
+
+    let mut rendering_features = PhysicalDeviceDynamicRenderingFeaturesKHR::default();
+
+    let mut device_features = vk::PhysicalDeviceFeatures2 {
+        s_type: vk::StructureType::PHYSICAL_DEVICE_FEATURES_2,
+        p_next: &mut rendering_features as *mut PhysicalDeviceDynamicRenderingFeaturesKHR as _,
+        features: phy_device.features.clone(),
+    };
+
+    unsafe {
+        instance.handle.get_physical_device_features2(phy_device.handle, &mut device_features);
+    }
+
+    rendering_features.dynamic_rendering == vk::TRUE
+}
+
 fn print_device_properties(property: &vk::PhysicalDeviceProperties) {
 
     let device_name = chars2string(&property.device_name);
@@ -335,29 +651,49 @@ fn enable_feature_if_support(phy_device: &PhyDeviceTmp, config: &PhysicalDevConf
 
 
 // ----------------------------------------------------------------------------------
-fn query_depth_format(instance: &VkInstance, phy_device: &PhyDeviceTmp) -> vk::Format {
+fn query_depth_format(instance: &VkInstance, phy_device: &PhyDeviceTmp, is_require_stencil: bool) -> vk::Format {
 
     // since all depth formats may be optional, we need to find a suitable depth format to use.
     // start with the highest precision packed format.
-    let candidates = [
+    let candidates: Vec<vk::Format> = [
         vk::Format::D32_SFLOAT_S8_UINT,
         vk::Format::D32_SFLOAT,
         vk::Format::D24_UNORM_S8_UINT,
         vk::Format::D16_UNORM_S8_UINT,
         vk::Format::D16_UNORM,
-    ];
+    ].iter().cloned()
+        .filter(|&format| is_require_stencil == false || is_stencil_format(format))
+        .collect();
+
+    // Format must support depth stencil attachment for optimal tiling
+    find_supported_format(&instance.handle, phy_device.handle, &candidates, vk::ImageTiling::OPTIMAL, vk::FormatFeatureFlags::DEPTH_STENCIL_ATTACHMENT)
+        .expect("Failed to find a supported depth format.")
+}
+
+fn find_supported_format(instance: &ash::Instance, phy_device: vk::PhysicalDevice, candidates: &[vk::Format], tiling: vk::ImageTiling, features: vk::FormatFeatureFlags) -> Option<vk::Format> {
+
+    candidates.iter().cloned().find(|&format| {
 
-    for &format in candidates.iter() {
         let format_properties = unsafe {
-            instance.handle.get_physical_device_format_properties(phy_device.handle, format)
+            instance.get_physical_device_format_properties(phy_device, format)
         };
 
-        // Format must support depth stencil attachment for optimal tiling
-        if format_properties.optimal_tiling_features.contains(vk::FormatFeatureFlags::DEPTH_STENCIL_ATTACHMENT) {
-            return format
+        match tiling {
+            | vk::ImageTiling::LINEAR  => format_properties.linear_tiling_features.contains(features),
+            | vk::ImageTiling::OPTIMAL => format_properties.optimal_tiling_features.contains(features),
+            | _ => false,
         }
-    }
+    })
+}
 
-    panic!("Failed to find a supported depth format.")
+/// Check if `format` is a depth format with stencil component.
+pub(crate) fn is_stencil_format(format: vk::Format) -> bool {
+    match format {
+        | vk::Format::D32_SFLOAT_S8_UINT
+        | vk::Format::D24_UNORM_S8_UINT
+        | vk::Format::D16_UNORM_S8_UINT
+        | vk::Format::S8_UINT => true,
+        | _ => false,
+    }
 }
 // ----------------------------------------------------------------------------------
@@ -111,6 +111,65 @@ impl QueueRequester {
         }
     }
 
+    /// Request a queue from the family which supports `request_queue` but none of `exclude_queue`(etc. a compute family without graphics).
+    ///
+    /// Return None if there is no such family or no queue remains in it.
+    pub fn request_dedicated_queue(&mut self, request_queue: vk::QueueFlags, exclude_queue: vk::QueueFlags, priority: QueuePriority) -> Option<usize> {
+
+        let selected_family = self.candidate_family(request_queue).into_iter().find(|&family_index| {
+            self.family_properties[family_index].queue_flags.intersects(exclude_queue) == false
+                && self.cis[family_index].count < self.family_properties[family_index].queue_count
+        });
+
+        selected_family.map(|final_family| {
+
+            let queue_index = self.cis[final_family].count;
+
+            self.cis[final_family].count += 1;
+            self.cis[final_family].priorities.push(priority);
+
+            let requested_index = self.queues_requested.len();
+            self.queues_requested.push((final_family, queue_index as usize));
+
+            requested_index
+        })
+    }
+
+    /// Request a queue from the queue families satisfying `is_presentable`.
+    ///
+    /// The queue requested before is reused if it's presentable(usually the graphics queue), otherwise a new queue is requested from the presentable family.
+    pub fn request_present_queue(&mut self, is_presentable: impl Fn(vkuint) -> bool, priority: QueuePriority) -> VkResult<usize> {
+
+        // try to share a queue which has been requested.
+        let requested_family = self.queues_requested.iter().position(|&(family_index, _)| {
+            is_presentable(family_index as vkuint)
+        });
+
+        if let Some(requested_index) = requested_family {
+            return Ok(requested_index)
+        }
+
+        // select the first presentable family which has remaining queue.
+        let selected_family = (0..self.family_properties.len()).find(|&family_index| {
+            is_presentable(family_index as vkuint) && self.cis[family_index].count < self.family_properties[family_index].queue_count
+        });
+
+        if let Some(final_family) = selected_family {
+
+            let queue_index = self.cis[final_family].count;
+
+            self.cis[final_family].count += 1;
+            self.cis[final_family].priorities.push(priority);
+
+            let requested_index = self.queues_requested.len();
+            self.queues_requested.push((final_family, queue_index as usize));
+
+            Ok(requested_index)
+        } else {
+            Err(VkError::custom("None of queue families is support to present image to platform's surface."))
+        }
+    }
+
     fn candidate_family(&self, request_queue: vk::QueueFlags) -> Vec<FamilyIndex> {
 
         self.family_properties.iter().enumerate().filter_map(|(i, family)| {
@@ -11,6 +11,8 @@ use std::ffi::CString;
 use std::os::raw::c_void;
 use std::ptr;
 
+/// The name of `VK_EXT_swapchain_colorspace` extension.
+const SWAPCHAIN_COLORSPACE_EXTENSION_NAME: &'static [u8] = b"VK_EXT_swapchain_colorspace\0";
 
 /// The configuration parameters used in the initialization of `vk::Instance`.
 #[derive(Debug, Clone)]
@@ -34,6 +36,10 @@ pub struct InstanceConfig {
     pub require_layer_names: Vec<String>,
     /// `debug` specify what the debug tool used in Vulkan instance debugging.
     pub debug: DebugType,
+    /// `enable_swapchain_colorspace` specify program to enable `VK_EXT_swapchain_colorspace` extension if it's available.
+    ///
+    /// This extension is required when swapchain requests a color space other than `vk::ColorSpaceKHR::SRGB_NONLINEAR`(etc. HDR10).
+    pub enable_swapchain_colorspace: bool,
 }
 
 impl Default for InstanceConfig {
@@ -51,6 +57,7 @@ impl Default for InstanceConfig {
                String::from("VK_LAYER_LUNARG_standard_validation"),
            ],
            debug: DebugType::DebugUtils,// default to use Debug Utils for Vulkan instance debugging.
+           enable_swapchain_colorspace: false,
        }
     }
 }
@@ -64,6 +71,10 @@ pub struct VkInstance {
     pub(crate) entry: ash::Entry,
     /// an array to store the names of vulkan layers enabled in instance creation.
     pub(crate) enable_layer_names: Vec<CString>,
+    /// indicate whether `VK_EXT_swapchain_colorspace` extension is enabled in instance creation.
+    pub(crate) is_swapchain_colorspace_enable: bool,
+    /// the Vulkan version requested in instance creation.
+    pub(crate) api_version: vkuint,
 }
 
 impl VkInstance {
@@ -105,8 +116,15 @@ impl VkInstance {
         // get the names of required vulkan layers.
         let enable_layer_names = layer_names_to_cstring(&config.require_layer_names)?;
         let enable_layer_names_ptr = crate::utils::cast::cstrings2ptrs(&enable_layer_names);
+        // check if the extension about swapchain color space is support.
+        let is_swapchain_colorspace_enable = config.enable_swapchain_colorspace
+            && is_instance_extension_support(&entry, SWAPCHAIN_COLORSPACE_EXTENSION_NAME)?;
+
         // get the names of required vulkan extensions.
-        let enable_extension_names = VkInstance::require_extensions(validation_config.debug_type, config.debug);
+        let mut enable_extension_names = VkInstance::require_extensions(validation_config.debug_type, config.debug);
+        if is_swapchain_colorspace_enable {
+            enable_extension_names.push(SWAPCHAIN_COLORSPACE_EXTENSION_NAME.as_ptr() as *const i8);
+        }
 
         let instance_ci = vk::InstanceCreateInfo {
             s_type : vk::StructureType::INSTANCE_CREATE_INFO,
@@ -126,7 +144,7 @@ impl VkInstance {
         };
 
 
-        let instance = VkInstance { entry, handle, enable_layer_names };
+        let instance = VkInstance { entry, handle, enable_layer_names, is_swapchain_colorspace_enable, api_version: config.api_version };
         Ok(instance)
     }
 
@@ -204,6 +222,20 @@ fn is_all_instance_layer_support(entry: &ash::Entry, print_available_layers: boo
     Ok(result)
 }
 
+fn is_instance_extension_support(entry: &ash::Entry, extension_name: &[u8]) -> VkResult<bool> {
+
+    use crate::utils::cast::chars2cstring;
+
+    let extension_properties = entry.enumerate_instance_extension_properties()
+        .or(Err(VkError::query("Instance Extensions")))?;
+
+    let result = extension_properties.iter().any(|available_extension| {
+        chars2cstring(&available_extension.extension_name).as_bytes_with_nul() == extension_name
+    });
+
+    Ok(result)
+}
+
 fn layer_names_to_cstring(layers: &[String]) -> VkResult<Vec<CString>> {
 
     let mut layer_names = Vec::with_capacity(layers.len());
@@ -10,7 +10,7 @@ use crate::ci::image::ImageViewCI;
 use crate::ci::VkObjectBuildableCI;
 use crate::error::{VkResult, VkError};
 use crate::utils::time::VkTimeDuration;
-use crate::{vkuint, vklint};
+use crate::{vkuint, vklint, vkfloat};
 
 use std::ptr;
 
@@ -19,6 +19,23 @@ pub struct SwapchainConfig {
 
     pub present_vsync: bool,
     pub image_acquire_time: VkTimeDuration,
+    /// the candidate surface formats of swapchain images, in order of preference.
+    ///
+    /// The first one supported by the surface will be selected.
+    /// If none of them is supported, the first format offered by the surface will be used instead.
+    pub preferred_formats: Vec<vk::SurfaceFormatKHR>,
+    /// the desired color space of swapchain images(etc. `vk::ColorSpaceKHR::HDR10_ST2084_EXT` for HDR displays).
+    ///
+    /// Color spaces other than `vk::ColorSpaceKHR::SRGB_NONLINEAR` require `VK_EXT_swapchain_colorspace` extension,
+    /// and it will fall back to `vk::ColorSpaceKHR::SRGB_NONLINEAR` if the extension or the surface does not support it.
+    ///
+    /// Notice that the color values written to swapchain images must be encoded for the selected color space.
+    pub color_space: vk::ColorSpaceKHR,
+    /// the preferred count of swapchain images(etc. 2 for double buffering and 3 for triple buffering), or None to use `minImageCount + 1`.
+    ///
+    /// The count is clamped to `[minImageCount, maxImageCount]` of the surface, and the driver may still create more images than requested,
+    /// so check `VkSwapchain::image_count` for the actual count. Fewer images reduces latency, while more images leaves more room for CPU and GPU to work ahead.
+    pub preferred_image_count: Option<vkuint>,
 }
 
 impl Default for SwapchainConfig {
@@ -28,6 +45,12 @@ impl Default for SwapchainConfig {
         SwapchainConfig {
             present_vsync: false,
             image_acquire_time: VkTimeDuration::Infinite,
+            preferred_formats: vec![
+                vk::SurfaceFormatKHR { format: vk::Format::B8G8R8A8_SRGB,  color_space: vk::ColorSpaceKHR::SRGB_NONLINEAR },
+                vk::SurfaceFormatKHR { format: vk::Format::B8G8R8A8_UNORM, color_space: vk::ColorSpaceKHR::SRGB_NONLINEAR },
+            ],
+            color_space: vk::ColorSpaceKHR::SRGB_NONLINEAR,
+            preferred_image_count: None,
         }
     }
 }
@@ -45,14 +68,29 @@ pub struct VkSwapchain {
     pub images: Vec<SwapchainImage>,
     /// the format of presentable images.
     pub backend_format: vk::Format,
-    /// the dimension of presentable images.
+    /// the active color space of presentable images.
+    pub backend_color_space: vk::ColorSpaceKHR,
+    /// the dimension of presentable images, in physical pixels.
     pub dimension: vk::Extent2D,
+    /// the ratio of physical pixels to logical pixels of the window presenting this swapchain(etc. 2.0 on most HiDPI displays).
+    pub hidpi_factor: vkfloat,
 
     pub frame_in_flight: usize,
 
     image_acquire_time: vklint,
 
     config: SwapchainConfig,
+    /// the swapchains retired by `rebuild`, which are waiting to be destroyed.
+    retired: Vec<RetiredSwapchain>,
+}
+
+/// A swapchain retired by `VkSwapchain::rebuild`, whose images may still be held by the presentation engine.
+struct RetiredSwapchain {
+
+    handle: vk::SwapchainKHR,
+    views: Vec<vk::ImageView>,
+    /// the count of frames to render by the new swapchain before destroying this one.
+    frames_left: usize,
 }
 
 pub struct SwapchainImage {
@@ -84,21 +122,70 @@ impl VkSwapchain {
         VkSwapchain::build(instance, device, surface, config, dimension, None)
     }
 
+    /// Recreate the swapchain with `dimension`, and retire the current one.
+    ///
+    /// The current swapchain is passed as `old_swapchain`, so that the driver may reuse its resources and finish presenting its images during transition.
+    /// This retires the current swapchain even if the creation fails, after which no more image can be acquired from it,
+    /// so the recreation must be retried instead of rendering with the current swapchain.
+    ///
+    /// The retired swapchain is not destroyed here, since the presentation engine may still show its images.
+    /// The caller must make sure no frame in flight is rendering to the old images, and call `release_retired` once per frame,
+    /// which destroys the retired swapchain after the new one has rendered as many frames as its image count.
     pub(crate) fn rebuild(&mut self, instance: &VkInstance, device: &VkDevice, surface: &VkSurface, dimension: vk::Extent2D) -> VkResult<()> {
 
         let new_chain = VkSwapchain::build(instance, device, surface, self.config.clone(), dimension, Some(self.handle))?;
-        self.discard(device);
-        *self = new_chain;
+        let old_chain = ::std::mem::replace(self, new_chain);
+        self.retired = old_chain.retire();
 
         Ok(())
     }
 
+    /// Turn this swapchain into a retired one, which is returned together with the swapchains retired before it.
+    fn retire(mut self) -> Vec<RetiredSwapchain> {
+
+        let mut retired = ::std::mem::replace(&mut self.retired, Vec::new());
+        retired.push(RetiredSwapchain {
+            handle: self.handle,
+            views: self.images.iter().map(|swapchain_image| swapchain_image.view).collect(),
+            frames_left: self.frame_in_flight,
+        });
+        retired
+    }
+
+    /// Count a frame rendered by this swapchain, and destroy the retired swapchains which are no longer presented.
+    ///
+    /// Call it after waiting the fence of frame, so that the frames rendered before recreation have completed.
+    pub(crate) fn release_retired(&mut self, device: &VkDevice) {
+
+        for retired in self.retired.iter_mut() {
+            retired.frames_left = retired.frames_left.saturating_sub(1);
+        }
+
+        let loader = &self.loader;
+        self.retired.retain(|retired| {
+            if retired.frames_left == 0 {
+                retired.discard(device, loader);
+                false
+            } else {
+                true
+            }
+        });
+    }
+
     fn build(instance: &VkInstance, device: &VkDevice, surface: &VkSurface, config: SwapchainConfig, dimension: vk::Extent2D, old_chain: Option<vk::SwapchainKHR>) -> VkResult<VkSwapchain> {
 
         let present_queue = query_present_queue(device, surface)
-            .ok_or(VkError::custom("Graphics Queue is not support to present image to platform's surface."))?;
-        let swapchain_format = query_optimal_format(device, surface)?;
-        let swapchain_capability = query_swapchain_capability(device, surface, dimension)?;
+            .ok_or(VkError::custom("Neither present queue nor graphics queue is support to present image to platform's surface."))?;
+
+        // swapchain images are shared between graphics and present queue families without ownership transfer if they are different.
+        let sharing_families = [device.logic.queues.graphics.family_index, present_queue.family_index];
+        let (image_sharing_mode, sharing_family_count) = if sharing_families[0] == sharing_families[1] {
+            (vk::SharingMode::EXCLUSIVE, 0)
+        } else {
+            (vk::SharingMode::CONCURRENT, sharing_families.len() as vkuint)
+        };
+        let swapchain_format = query_optimal_format(instance, device, surface, &config)?;
+        let swapchain_capability = query_swapchain_capability(device, surface, &config, dimension)?;
         let swapchain_present_mode = query_optimal_present_mode(device, surface, &config)?;
 
         let swapchain_ci = vk::SwapchainCreateInfoKHR {
@@ -112,22 +199,22 @@ impl VkSwapchain {
             image_extent             : swapchain_capability.swapchain_extent,
             image_array_layers       : 1,
             image_usage              : swapchain_capability.support_usage,
-            image_sharing_mode       : vk::SharingMode::EXCLUSIVE,
-            queue_family_index_count : 0,
-            p_queue_family_indices   : ptr::null(),
+            image_sharing_mode,
+            queue_family_index_count : sharing_family_count,
+            p_queue_family_indices   : sharing_families.as_ptr(),
             pre_transform            : swapchain_capability.pre_transform,
             composite_alpha          : swapchain_capability.composite_alpha,
             present_mode             : swapchain_present_mode,
             // setting clipped to vk::TRUE allows the implementation to discard rendering outside of the surface area.
             clipped                  : vk::TRUE,
-            old_swapchain: old_chain.and_then(|c| Some(c)).unwrap_or(vk::SwapchainKHR::null()),
+            old_swapchain: old_chain.unwrap_or(vk::SwapchainKHR::null()),
         };
 
         let loader = ash::extensions::khr::Swapchain::new(&instance.handle, &device.logic.handle);
 
         let handle = unsafe {
             loader.create_swapchain(&swapchain_ci, None)
-                .or(Err(VkError::create("Swapchain")))?
+                .map_err(|e| VkError::create_with("Swapchain", e))?
         };
 
         let image_resources = obtain_swapchain_images(device, handle, &loader, &swapchain_format)?;
@@ -138,7 +225,10 @@ impl VkSwapchain {
             handle, loader, present_queue, frame_in_flight, image_acquire_time, config,
             images: image_resources,
             backend_format: swapchain_format.color_format,
+            backend_color_space: swapchain_format.color_space,
             dimension: swapchain_capability.swapchain_extent,
+            hidpi_factor: 1.0,
+            retired: Vec::new(),
         };
 
         Ok(result)
@@ -149,13 +239,17 @@ impl VkSwapchain {
     /// `sign_semaphore` is the semaphore to signal during this function, or None for no semaphore to signal.
     ///
     /// `sign_fence` is the fence to signal during this function, or None for no fence to signal.
+    ///
+    /// A suboptimal swapchain still returns the acquired image, since `semaphore` would be signaled and must be waited by rendering this image.
+    /// The suboptimal state is then reported by `VkSwapchain::present`, after which the swapchain can be safely recreated.
     pub(crate) fn next_image(&self, semaphore: Option<vk::Semaphore>, fence: Option<vk::Fence>) -> Result<vkuint, SwapchainSyncError> {
 
         let semaphore = semaphore.unwrap_or(vk::Semaphore::null());
         let fence = fence.unwrap_or(vk::Fence::null());
 
         // execute next image acquire operation.
-        let (image_index, is_sub_optimal) = unsafe {
+        // the image acquired from suboptimal swapchain is still presentable, so the suboptimal flag is ignored here.
+        let (image_index, _is_sub_optimal) = unsafe {
             self.loader.acquire_next_image(self.handle, self.image_acquire_time, semaphore, fence)
                 .map_err(|error| match error {
                     | vk::Result::TIMEOUT               => SwapchainSyncError::TimeOut,
@@ -164,11 +258,7 @@ impl VkSwapchain {
                 })?
         };
 
-        if is_sub_optimal {
-            Err(SwapchainSyncError::SubOptimal)
-        } else {
-            Ok(image_index)
-        }
+        Ok(image_index)
     }
 
     /// Queue an image for presentation.
@@ -205,10 +295,34 @@ impl VkSwapchain {
         }
     }
 
+    /// Get the actual count of images created by the swapchain, which may be greater than `SwapchainConfig::preferred_image_count`.
+    #[inline]
+    pub fn image_count(&self) -> usize {
+        self.images.len()
+    }
+
     pub fn frame_in_flight(&self) -> usize {
         self.frame_in_flight.clone()
     }
 
+    /// Get the dimension of presentable images in logical pixels, which is the unit to place UI texts(see `ui::TextInfo`).
+    pub fn logical_dimension(&self) -> vk::Extent2D {
+        vk::Extent2D {
+            width : (self.dimension.width  as vkfloat / self.hidpi_factor) as _,
+            height: (self.dimension.height as vkfloat / self.hidpi_factor) as _,
+        }
+    }
+
+    /// Get the surface format selected for presentable images.
+    ///
+    /// Render passes targeting the swapchain images should use the same format.
+    pub fn surface_format(&self) -> vk::SurfaceFormatKHR {
+        vk::SurfaceFormatKHR {
+            format: self.backend_format,
+            color_space: self.backend_color_space,
+        }
+    }
+
     /// Destroy the `vk::SwapchainKHR` object.
     ///
     /// The application must not destroy `vk::SwapchainKHR` until after completion of all outstanding operations on images that were acquired from the `vk::SwapchainKHR`.
@@ -221,6 +335,20 @@ impl VkSwapchain {
         unsafe {
             self.loader.destroy_swapchain(self.handle, None);
         }
+
+        self.retired.iter().for_each(|retired| retired.discard(device, &self.loader));
+    }
+}
+
+impl RetiredSwapchain {
+
+    fn discard(&self, device: &VkDevice, loader: &ash::extensions::khr::Swapchain) {
+
+        self.views.iter().for_each(|&view| device.discard(view));
+
+        unsafe {
+            loader.destroy_swapchain(self.handle, None);
+        }
     }
 }
 
@@ -229,10 +357,13 @@ impl VkSwapchain {
 // -----------------------------------------------------------------------------------
 fn query_present_queue(device: &VkDevice, surface: &VkSurface) -> Option<VkQueue> {
 
-    // TODO: Find an alternative queue if graphics queue is not support present operation.
-    // just check if graphics queue support present operation.
-    if surface.query_is_family_presentable(device.phy.handle, device.logic.queues.graphics.family_index) {
-        Some(device.logic.queues.graphics.clone())
+    // the present queue of device is selected with the surface of main window, so check it again for the surfaces of other windows.
+    let queues = &device.logic.queues;
+
+    if surface.query_is_family_presentable(device.phy.handle, queues.present.family_index) {
+        Some(queues.present.clone())
+    } else if surface.query_is_family_presentable(device.phy.handle, queues.graphics.family_index) {
+        Some(queues.graphics.clone())
     } else {
         None
     }
@@ -312,34 +443,67 @@ struct SwapchainFormat {
     color_space : vk::ColorSpaceKHR,
 }
 
-fn query_optimal_format(device: &VkDevice, surface: &VkSurface) -> VkResult<SwapchainFormat> {
+fn query_optimal_format(instance: &VkInstance, device: &VkDevice, surface: &VkSurface, config: &SwapchainConfig) -> VkResult<SwapchainFormat> {
 
     // Get list of supported surface formats.
     let support_formats = surface.query_formats(device.phy.handle)?;
 
+    if support_formats.is_empty() {
+        return Err(VkError::query("Surface Formats"))
+    }
+
+    // try to find a format matching the desired color space if it's not sRGB non-linear.
+    if config.color_space != vk::ColorSpaceKHR::SRGB_NONLINEAR && instance.is_swapchain_colorspace_enable {
+
+        let candidate_formats: Vec<&vk::SurfaceFormatKHR> = support_formats.iter()
+            .filter(|surface_format| surface_format.color_space == config.color_space)
+            .collect();
+
+        // prefer the format listed in config, otherwise select the first format offered in this color space.
+        let matched_format = candidate_formats.iter()
+            .find(|surface_format| config.preferred_formats.iter().any(|preferred| preferred.format == surface_format.format))
+            .or(candidate_formats.first());
+
+        if let Some(surface_format) = matched_format {
+            let result = SwapchainFormat {
+                color_format: surface_format.format,
+                color_space : surface_format.color_space,
+            };
+            return Ok(result)
+        }
+    }
+
     // If the surface format list only includes one entry with VK_FORMAT_UNDEFINED,
-    // there is no preferred format, so we assume VK_FORMAT_B8G8R8A8_UNORM.
+    // there is no preferred format, so we assume the first preferred format in config(or VK_FORMAT_B8G8R8A8_UNORM).
     let result = if support_formats.len() == 1 && support_formats[0].format == vk::Format::UNDEFINED {
+
+        let preferred = config.preferred_formats.first().cloned()
+            .unwrap_or(vk::SurfaceFormatKHR { format: vk::Format::B8G8R8A8_UNORM, color_space: support_formats[0].color_space });
+
         SwapchainFormat {
-            color_format: vk::Format::B8G8R8A8_UNORM,
-            color_space : support_formats[0].color_space,
+            color_format: preferred.format,
+            color_space : preferred.color_space,
         }
     } else {
 
-        // iterate over the list of available surface format and check for the presence of VK_FORMAT_B8G8R8A8_UNORM.
+        // iterate over the preferred formats and check for the presence of each one in the list of available surface formats.
         let format_searching = || {
 
-            for surface_format in support_formats.iter() {
+            for preferred in config.preferred_formats.iter() {
 
-                if surface_format.format == vk::Format::B8G8R8A8_UNORM {
+                let is_found = support_formats.iter().any(|surface_format| {
+                    surface_format.format == preferred.format && surface_format.color_space == preferred.color_space
+                });
+
+                if is_found {
                     return SwapchainFormat {
-                        color_format: surface_format.format,
-                        color_space : surface_format.color_space,
+                        color_format: preferred.format,
+                        color_space : preferred.color_space,
                     }
                 }
             }
 
-            // in case VK_FORMAT_B8G8R8A8_UNORM is not available, select the first available color format.
+            // in case none of the preferred formats is available, select the first available color format.
             SwapchainFormat {
                 color_format: support_formats[0].format,
                 color_space : support_formats[0].color_space,
@@ -363,7 +527,7 @@ struct SwapchainCapability {
     composite_alpha: vk::CompositeAlphaFlagsKHR,
 }
 
-fn query_swapchain_capability(device: &VkDevice, surface: &VkSurface, dimension: vk::Extent2D) -> VkResult<SwapchainCapability> {
+fn query_swapchain_capability(device: &VkDevice, surface: &VkSurface, config: &SwapchainConfig, dimension: vk::Extent2D) -> VkResult<SwapchainCapability> {
 
     let surface_caps = surface.query_capabilities(device.phy.handle)?;
 
@@ -394,10 +558,17 @@ fn query_swapchain_capability(device: &VkDevice, surface: &VkSurface, dimension:
         // If the surface size is defined, the swap chain size must match.
         surface_caps.current_extent.clone()
     };
+
+    if optimal_extent.width == 0 || optimal_extent.height == 0 {
+        return Err(VkError::custom("Failed to create swapchain for a surface with zero extent(the window may be minimized)."))
+    }
     // --------------------------------------------------------------
 
     // Determine the number of images. ------------------------------
-    let mut optimal_image_count = surface_caps.min_image_count + 1;
+    let mut optimal_image_count = config.preferred_image_count
+        .unwrap_or(surface_caps.min_image_count + 1)
+        .max(surface_caps.min_image_count);
+    // max_image_count of 0 means there is no limit on the number of images.
     if surface_caps.max_image_count > 0 && optimal_image_count > surface_caps.max_image_count {
         optimal_image_count = surface_caps.max_image_count;
     }
@@ -0,0 +1,336 @@
+//! An immediate-mode facade to draw debug primitives(lines, boxes, spheres and labels) from anywhere in program.
+//!
+//! The primitives are queued by the free functions in this module(etc. `debug::line`, `debug::aabb`) or the `add_*` methods of `DebugDraw`,
+//! and they are rendered and cleared when `DebugDraw::flush` is called in the next frame.
+
+pub use self::lines::{LineRenderer, LineVertex};
+
+mod lines;
+
+use ash::vk;
+use lazy_static::lazy_static;
+
+use crate::context::{VkDevice, VkSwapchain};
+use crate::command::{VkCmdRecorder, IGraphics};
+use crate::ui::{UIRenderer, TextInfo, TextID, TextType, WorldAnchor};
+use crate::utils::color::VkColor;
+
+use crate::VkResult;
+use crate::{Vec3F, Vec4F, Mat4F};
+
+use std::sync::Mutex;
+
+/// The maximum count of characters of each text label.
+const LABEL_CHARACTER_CAPACITY: usize = 32;
+/// The count of segments approximating a circle.
+const CIRCLE_SEGMENT_COUNT: usize = 24;
+
+lazy_static! {
+
+    static ref DEBUG_PRIMITIVES: Mutex<DebugPrimitives> = Mutex::new(DebugPrimitives::default());
+}
+
+/// The primitives queued in current frame.
+#[derive(Default)]
+struct DebugPrimitives {
+    lines: Vec<(Vec3F, Vec3F, VkColor)>,
+    texts: Vec<(Vec3F, String, VkColor)>,
+}
+
+impl DebugPrimitives {
+
+    fn line(&mut self, from: Vec3F, to: Vec3F, color: VkColor) {
+        self.lines.push((from, to, color));
+    }
+
+    fn point(&mut self, position: Vec3F, size: f32, color: VkColor) {
+
+        let half = size * 0.5;
+        self.line(position - Vec3F::unit_x() * half, position + Vec3F::unit_x() * half, color);
+        self.line(position - Vec3F::unit_y() * half, position + Vec3F::unit_y() * half, color);
+        self.line(position - Vec3F::unit_z() * half, position + Vec3F::unit_z() * half, color);
+    }
+
+    fn aabb(&mut self, min: Vec3F, max: Vec3F, color: VkColor) {
+
+        let corner = |x: bool, y: bool, z: bool| {
+            Vec3F::new(
+                if x { max.x } else { min.x },
+                if y { max.y } else { min.y },
+                if z { max.z } else { min.z },
+            )
+        };
+
+        for &a in [false, true].iter() {
+            for &b in [false, true].iter() {
+                // edges along x, y and z axis respectively.
+                self.line(corner(false, a, b), corner(true, a, b), color);
+                self.line(corner(a, false, b), corner(a, true, b), color);
+                self.line(corner(a, b, false), corner(a, b, true), color);
+            }
+        }
+    }
+
+    fn sphere(&mut self, center: Vec3F, radius: f32, color: VkColor) {
+
+        let step = ::std::f32::consts::PI * 2.0 / CIRCLE_SEGMENT_COUNT as f32;
+
+        for i in 0..CIRCLE_SEGMENT_COUNT {
+
+            let (sin0, cos0) = (step * i as f32).sin_cos();
+            let (sin1, cos1) = (step * (i + 1) as f32).sin_cos();
+
+            self.line(center + Vec3F::new(cos0, sin0, 0.0) * radius, center + Vec3F::new(cos1, sin1, 0.0) * radius, color);
+            self.line(center + Vec3F::new(0.0, cos0, sin0) * radius, center + Vec3F::new(0.0, cos1, sin1) * radius, color);
+            self.line(center + Vec3F::new(cos0, 0.0, sin0) * radius, center + Vec3F::new(cos1, 0.0, sin1) * radius, color);
+        }
+    }
+
+    fn frustum(&mut self, view_proj: Mat4F, color: VkColor) {
+
+        let inverse = view_proj.inverted();
+        let corner = |x: f32, y: f32, z: f32| {
+            let world_pos = inverse * Vec4F::new(x, y, z, 1.0);
+            Vec3F::new(world_pos.x, world_pos.y, world_pos.z) / world_pos.w
+        };
+
+        let near = [corner(-1.0, -1.0, 0.0), corner(1.0, -1.0, 0.0), corner(1.0, 1.0, 0.0), corner(-1.0, 1.0, 0.0)];
+        let far  = [corner(-1.0, -1.0, 1.0), corner(1.0, -1.0, 1.0), corner(1.0, 1.0, 1.0), corner(-1.0, 1.0, 1.0)];
+
+        for i in 0..4 {
+            let next = (i + 1) % 4;
+            self.line(near[i], near[next], color);
+            self.line(far[i], far[next], color);
+            self.line(near[i], far[i], color);
+        }
+    }
+
+    fn normal(&mut self, position: Vec3F, normal: Vec3F, length: f32, color: VkColor) {
+        self.line(position, position + normal.normalized() * length, color);
+    }
+
+    fn text_3d(&mut self, position: Vec3F, content: String, color: VkColor) {
+        self.texts.push((position, content, color));
+    }
+
+    /// Move all the primitives of `other` to the end of this queue.
+    fn append(&mut self, other: &mut DebugPrimitives) {
+        self.lines.append(&mut other.lines);
+        self.texts.append(&mut other.texts);
+    }
+
+    /// Transform the queued lines to clip space by `view_proj`, and clear the queue of lines.
+    ///
+    /// The lines exceeding `capacity` are discarded.
+    fn drain_line_vertices(&mut self, view_proj: Mat4F, capacity: usize) -> Vec<LineVertex> {
+
+        let mut vertices = Vec::with_capacity(self.lines.len().min(capacity) * 2);
+        for (from, to, color) in self.lines.drain(..).take(capacity) {
+
+            let color: [f32; 4] = color.into();
+            for point in [from, to].iter() {
+                let clip_pos = view_proj * Vec4F::new(point.x, point.y, point.z, 1.0);
+                vertices.push(LineVertex { pos: clip_pos.into_array(), color });
+            }
+        }
+        vertices
+    }
+}
+
+/// Queue primitives to the global queue, which is drained by `DebugDraw::flush`.
+fn with_global_queue(queue: impl FnOnce(&mut DebugPrimitives)) {
+
+    let mut primitives = DEBUG_PRIMITIVES.lock()
+        .expect("Debug primitives lock is poisoned.");
+    queue(&mut primitives);
+}
+
+// --------------------------------------------------------------------------------------
+/// Draw a line from `from` to `to` in world space.
+pub fn line(from: Vec3F, to: Vec3F, color: VkColor) {
+    with_global_queue(|primitives| primitives.line(from, to, color));
+}
+
+/// Draw a point at `position` as a cross of three axis-aligned lines, whose length is `size`.
+pub fn point(position: Vec3F, size: f32, color: VkColor) {
+    with_global_queue(|primitives| primitives.point(position, size, color));
+}
+
+/// Draw the 12 edges of an axis-aligned bounding box.
+pub fn aabb(min: Vec3F, max: Vec3F, color: VkColor) {
+    with_global_queue(|primitives| primitives.aabb(min, max, color));
+}
+
+/// Draw a sphere as three circles lying in the XY, YZ and XZ plane.
+pub fn sphere(center: Vec3F, radius: f32, color: VkColor) {
+    with_global_queue(|primitives| primitives.sphere(center, radius, color));
+}
+
+/// Draw the 12 edges of a camera frustum, where `view_proj` is the product of projection and view matrix of that camera.
+///
+/// The corners are unprojected from the clip volume of Vulkan(x, y in [-1, 1] and z in [0, 1]).
+pub fn frustum(view_proj: Mat4F, color: VkColor) {
+    with_global_queue(|primitives| primitives.frustum(view_proj, color));
+}
+
+/// Draw the normal of a surface as a line of `length` starting from `position`.
+pub fn normal(position: Vec3F, normal: Vec3F, length: f32, color: VkColor) {
+    with_global_queue(|primitives| primitives.normal(position, normal, length, color));
+}
+
+/// Draw a text label centered at `position` in world space.
+///
+/// The characters exceeding the capacity of label(32 characters) are discarded.
+pub fn text_3d(position: Vec3F, content: impl Into<String>, color: VkColor) {
+    let content = content.into();
+    with_global_queue(|primitives| primitives.text_3d(position, content, color));
+}
+// --------------------------------------------------------------------------------------
+
+// --------------------------------------------------------------------------------------
+/// Render the debug primitives queued since last flush.
+///
+/// The lines are rendered by a `LineRenderer`, and the texts are rendered as world labels of `UIRenderer`.
+pub struct DebugDraw {
+
+    lines: LineRenderer,
+    /// the world labels reserved for `text_3d`.
+    labels: Vec<TextID>,
+    /// the primitives queued by the `add_*` methods of this renderer.
+    primitives: DebugPrimitives,
+}
+
+impl DebugDraw {
+
+    /// Create the renderer which can draw at most `line_capacity` lines and `text_capacity` labels in one frame.
+    ///
+    /// The labels are reserved from `ui`, so `text_capacity` is limited by the remaining space of its text pool.
+    pub fn new(device: &mut VkDevice, swapchain: &VkSwapchain, render_pass: vk::RenderPass, ui: &mut UIRenderer, line_capacity: usize, text_capacity: usize) -> VkResult<DebugDraw> {
+
+        let lines = LineRenderer::new(device, swapchain, render_pass, line_capacity)?;
+
+        let mut labels = Vec::with_capacity(text_capacity);
+        for _ in 0..text_capacity {
+
+            let label = TextInfo {
+                scale: 14.0,
+                pivot: (0.5, 0.5),
+                r#type: TextType::Dynamic { capacity: LABEL_CHARACTER_CAPACITY },
+                ..TextInfo::default()
+            };
+            let anchor = WorldAnchor { position: Vec3F::zero(), reference_distance: None };
+
+            let label_id = ui.add_world_label(label, anchor)?;
+            ui.change_world_label(label_id, String::new(), VkColor::WHITE, None);
+            labels.push(label_id);
+        }
+
+        let result = DebugDraw { lines, labels, primitives: DebugPrimitives::default() };
+        Ok(result)
+    }
+
+    /// Queue a line segment to this renderer.
+    #[inline]
+    pub fn add_line(&mut self, from: Vec3F, to: Vec3F, color: VkColor) {
+        self.primitives.line(from, to, color);
+    }
+
+    /// Queue the edges of an axis-aligned bounding box to this renderer.
+    #[inline]
+    pub fn add_aabb(&mut self, min: Vec3F, max: Vec3F, color: VkColor) {
+        self.primitives.aabb(min, max, color);
+    }
+
+    /// Queue the edges of a camera frustum to this renderer.
+    #[inline]
+    pub fn add_frustum(&mut self, view_proj: Mat4F, color: VkColor) {
+        self.primitives.frustum(view_proj, color);
+    }
+
+    /// Queue the normal of a surface as a line of `length` starting from `position` to this renderer.
+    #[inline]
+    pub fn add_normal(&mut self, position: Vec3F, normal: Vec3F, length: f32, color: VkColor) {
+        self.primitives.normal(position, normal, length, color);
+    }
+
+    /// Upload the primitives queued to this renderer and by the free functions of `debug` for rendering to `image_index`, and clear both queues.
+    ///
+    /// `view_proj` is the product of projection and view matrix of camera.
+    /// This also calls `UIRenderer::update_world_labels`, so it should be called in `RenderWorkflow::render_frame` each frame.
+    pub fn flush(&mut self, image_index: usize, view_proj: Mat4F, ui: &mut UIRenderer) {
+
+        with_global_queue(|global| self.primitives.append(global));
+
+        // transform lines to clip space, so that the line pipeline needs no uniform buffer.
+        let vertices = self.primitives.drain_line_vertices(view_proj, self.lines.capacity());
+        self.lines.write_lines(image_index, &vertices);
+
+        // assign the texts to labels, and hide the remaining labels.
+        let mut texts = self.primitives.texts.drain(..);
+        for &label in self.labels.iter() {
+            match texts.next() {
+                | Some((position, content, color)) => {
+                    let content: String = content.chars().take(LABEL_CHARACTER_CAPACITY).collect();
+                    ui.change_world_label(label, content, color, Some(position));
+                },
+                | None => {
+                    ui.change_world_label(label, String::new(), VkColor::WHITE, None);
+                },
+            }
+        }
+
+        ui.update_world_labels(view_proj);
+    }
+
+    /// Record the command drawing lines. The labels are drawn by `UIRenderer::record_command`.
+    pub fn record_command(&self, recorder: &VkCmdRecorder<IGraphics>, image_index: usize) {
+        self.lines.record_command(recorder, image_index);
+    }
+
+    pub fn swapchain_reload(&mut self, device: &VkDevice, new_chain: &VkSwapchain, render_pass: vk::RenderPass) -> VkResult<()> {
+        self.lines.swapchain_reload(device, new_chain, render_pass)
+    }
+
+    pub fn discard_by(self, device: &mut VkDevice) -> VkResult<()> {
+        self.lines.discard_by(device)
+    }
+}
+// --------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn queued_lines_are_drained_to_clip_space() {
+
+        let mut global = DebugPrimitives::default();
+        global.aabb(Vec3F::zero(), Vec3F::one(), VkColor::WHITE);
+
+        let mut primitives = DebugPrimitives::default();
+        primitives.line(Vec3F::zero(), Vec3F::unit_x(), VkColor::WHITE);
+        primitives.append(&mut global);
+
+        assert!(global.lines.is_empty());
+        assert_eq!(primitives.lines.len(), 1 + 12);
+
+        let vertices = primitives.drain_line_vertices(Mat4F::identity(), 64);
+        assert_eq!(vertices.len(), (1 + 12) * 2);
+        assert_eq!(vertices[1].pos, [1.0, 0.0, 0.0, 1.0]);
+        // the queue is cleared after flush.
+        assert!(primitives.lines.is_empty());
+    }
+
+    #[test]
+    fn lines_exceeding_capacity_are_discarded() {
+
+        let mut primitives = DebugPrimitives::default();
+        primitives.sphere(Vec3F::zero(), 1.0, VkColor::WHITE);
+        assert_eq!(primitives.lines.len(), CIRCLE_SEGMENT_COUNT * 3);
+
+        let vertices = primitives.drain_line_vertices(Mat4F::identity(), 4);
+        assert_eq!(vertices.len(), 4 * 2);
+        assert!(primitives.lines.is_empty());
+    }
+}
@@ -0,0 +1,16 @@
+
+#version 450 core
+#extension GL_ARB_separate_shader_objects : enable
+
+// the position has already been transformed to clip space on host.
+layout (location = 0) in vec4 inPos;
+layout (location = 1) in vec4 inColor;
+
+layout (location = 0) out vec4 outColor;
+
+void main() {
+
+    gl_Position = inPos;
+
+    outColor = inColor;
+}
@@ -0,0 +1,218 @@
+
+use ash::vk;
+use memoffset::offset_of;
+
+use crate::ci::buffer::BufferCI;
+use crate::ci::vma::{VmaBuffer, VmaAllocationCI};
+use crate::ci::pipeline::VertexInputSCI;
+use crate::ci::shader::{ShaderModuleCI, ShaderStageCI};
+use crate::ci::VkObjectBuildableCI;
+
+use crate::context::{VkDevice, VkSwapchain};
+use crate::command::{VkCmdRecorder, IGraphics, CmdGraphicsApi};
+use crate::utils::frame::FrameResource;
+
+use crate::{VkResult, VkErrorKind};
+use crate::{vkuint, vkbytes, vkptr};
+
+
+/// The vertex attributes of each line endpoint.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct LineVertex {
+    /// the position in clip space.
+    pub pos  : [f32; 4],
+    pub color: [f32; 4],
+}
+
+impl LineVertex {
+
+    /// A vertex outside the clip volume, used to fill the unused space of vertex buffer.
+    const CULLED: LineVertex = LineVertex {
+        pos  : [0.0, 0.0, -1.0, 1.0],
+        color: [0.0; 4],
+    };
+}
+
+/// Render a list of lines(`vk::PrimitiveTopology::LINE_LIST`), whose vertices can be rewritten each frame.
+///
+/// Each swapchain image owns its vertex buffer, and the draw command always covers the whole capacity,
+/// so that the command buffers can be pre-recorded.
+pub struct LineRenderer {
+
+    /// the maximum count of lines to render in one frame.
+    capacity: usize,
+    vertices: FrameResource<VmaBuffer>,
+
+    pipeline: vk::Pipeline,
+    pipeline_layout: vk::PipelineLayout,
+}
+
+impl LineRenderer {
+
+    pub fn new(device: &mut VkDevice, swapchain: &VkSwapchain, render_pass: vk::RenderPass, capacity: usize) -> VkResult<LineRenderer> {
+
+        let vertices = FrameResource::new(swapchain.frame_in_flight(), |_| {
+
+            let buffer_size = (::std::mem::size_of::<LineVertex>() * capacity * 2) as vkbytes;
+            let vertex_ci = BufferCI::new(buffer_size)
+                .usage(vk::BufferUsageFlags::VERTEX_BUFFER);
+            let allocation_ci = VmaAllocationCI::new(vma::MemoryUsage::CpuToGpu, vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT)
+                .flags(vma::AllocationCreateFlags::MAPPED);
+            let vertex_allocation = device.vma.create_buffer(vertex_ci.as_ref(), allocation_ci.as_ref())
+                .map_err(VkErrorKind::Vma)?;
+
+            Ok(VmaBuffer::from(vertex_allocation))
+        })?;
+
+        let (pipeline, pipeline_layout) = prepare_pipeline(device, swapchain.dimension, render_pass)?;
+
+        let mut renderer = LineRenderer { capacity, vertices, pipeline, pipeline_layout };
+        for i in 0..renderer.vertices.len() {
+            renderer.write_lines(i, &[]);
+        }
+
+        Ok(renderer)
+    }
+
+    /// Write the vertices of lines(two vertices for each line) to the vertex buffer of `image_index`.
+    ///
+    /// The lines exceeding the capacity are ignored.
+    pub fn write_lines(&mut self, image_index: usize, vertices: &[LineVertex]) {
+
+        let vertex_count = vertices.len().min(self.capacity * 2);
+
+        unsafe {
+            let data_ptr = self.vertices.get(image_index).info.get_mapped_data() as vkptr<LineVertex>;
+            data_ptr.copy_from_nonoverlapping(vertices.as_ptr(), vertex_count);
+
+            for i in vertex_count..(self.capacity * 2) {
+                data_ptr.add(i).write(LineVertex::CULLED);
+            }
+        }
+    }
+
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn record_command(&self, recorder: &VkCmdRecorder<IGraphics>, image_index: usize) {
+
+        recorder
+            .bind_pipeline(self.pipeline)
+            .bind_vertex_buffers(0, &[self.vertices.get(image_index).handle], &[0])
+            .draw((self.capacity * 2) as vkuint, 1, 0, 0);
+    }
+
+    pub fn swapchain_reload(&mut self, device: &VkDevice, new_chain: &VkSwapchain, render_pass: vk::RenderPass) -> VkResult<()> {
+
+        device.discard(self.pipeline);
+        device.discard(self.pipeline_layout);
+
+        let (pipeline, pipeline_layout) = prepare_pipeline(device, new_chain.dimension, render_pass)?;
+        self.pipeline = pipeline;
+        self.pipeline_layout = pipeline_layout;
+
+        Ok(())
+    }
+
+    pub fn discard_by(self, device: &mut VkDevice) -> VkResult<()> {
+
+        device.discard(self.pipeline);
+        device.discard(self.pipeline_layout);
+
+        for vertex_buffer in self.vertices.into_inner() {
+            device.vma_discard(vertex_buffer)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn input_descriptions() -> VertexInputSCI {
+
+    VertexInputSCI::new()
+        .add_binding(vk::VertexInputBindingDescription {
+            binding: 0,
+            stride : ::std::mem::size_of::<LineVertex>() as _,
+            input_rate: vk::VertexInputRate::VERTEX,
+        })
+        .add_attribute(vk::VertexInputAttributeDescription {
+            location: 0,
+            binding : 0,
+            format  : vk::Format::R32G32B32A32_SFLOAT,
+            offset  : offset_of!(LineVertex, pos) as _,
+        })
+        .add_attribute(vk::VertexInputAttributeDescription {
+            location: 1,
+            binding : 0,
+            format  : vk::Format::R32G32B32A32_SFLOAT,
+            offset  : offset_of!(LineVertex, color) as _,
+        })
+}
+
+fn prepare_pipeline(device: &VkDevice, dimension: vk::Extent2D, render_pass: vk::RenderPass) -> VkResult<(vk::Pipeline, vk::PipelineLayout)> {
+
+    use crate::ci::pipeline::*;
+
+    let viewport_state = ViewportSCI::new()
+        .add_viewport(vk::Viewport {
+            x: 0.0, y: 0.0,
+            width: dimension.width as f32, height: dimension.height as f32,
+            min_depth: 0.0, max_depth: 1.0,
+        })
+        .add_scissor(vk::Rect2D {
+            offset: vk::Offset2D { x: 0, y: 0 },
+            extent: dimension,
+        });
+
+    let input_assembly_state = InputAssemblySCI::with_topology(vk::PrimitiveTopology::LINE_LIST);
+
+    let blend_state = ColorBlendSCI::new()
+        .add_attachment(BlendAttachmentSCI::new());
+
+    // lines are occluded by the scene, but do not occlude each other.
+    let depth_stencil_state = DepthStencilSCI::new()
+        .depth_test(true, false, vk::CompareOp::LESS_OR_EQUAL);
+
+    let pipeline_layout = PipelineLayoutCI::new()
+        .build(device)?;
+
+    let mut pipeline_ci = GraphicsPipelineCI::new(render_pass, pipeline_layout);
+
+    pipeline_ci.set_vertex_input(input_descriptions());
+    pipeline_ci.set_input_assembly(input_assembly_state);
+    pipeline_ci.set_viewport(viewport_state);
+    pipeline_ci.set_color_blend(blend_state);
+    pipeline_ci.set_depth_stencil(depth_stencil_state);
+
+    let mut shader_compiler = crate::utils::shaderc::VkShaderCompiler::new()?;
+    let vert_codes = shader_compiler.compile_from_str(
+        include_str!("line.vert.glsl"),
+        shaderc::ShaderKind::Vertex,
+        "[Vertex Shader]",
+        "main")?;
+    let frag_codes = shader_compiler.compile_from_str(
+        include_str!("line.frag.glsl"),
+        shaderc::ShaderKind::Fragment,
+        "[Fragment Shader]",
+        "main")?;
+
+    let vert_module = ShaderModuleCI::new(vert_codes).build(device)?;
+    let frag_module = ShaderModuleCI::new(frag_codes).build(device)?;
+
+    let shaders = [
+        ShaderStageCI::new(vk::ShaderStageFlags::VERTEX, vert_module),
+        ShaderStageCI::new(vk::ShaderStageFlags::FRAGMENT, frag_module),
+    ];
+
+    pipeline_ci.set_shaders(&shaders);
+
+    let line_pipeline = device.build(&pipeline_ci)?;
+
+    device.discard(vert_module);
+    device.discard(frag_module);
+
+    Ok((line_pipeline, pipeline_layout))
+}
@@ -1,4 +1,5 @@
 
+use ash::vk;
 use failure::{ Backtrace, Context, Fail };
 
 use std::result;
@@ -32,6 +33,11 @@ impl VkError {
         VkError::from(VkErrorKind::Create { create_target })
     }
 
+    /// Create an error for failed object creation, keeping the `vk::Result` returned by Vulkan.
+    pub fn create_with(create_target: &'static str, result: vk::Result) -> VkError {
+        VkError::from(VkErrorKind::CreateWith { create_target, result })
+    }
+
     pub fn unsupported(feature: &'static str) -> VkError {
         VkError::from(VkErrorKind::UnSupport { feature })
     }
@@ -55,6 +61,25 @@ impl VkError {
         VkError::from(VkErrorKind::Path { path: path.as_ref().to_path_buf() })
     }
 
+    /// Create an error for an operation aborted by user(etc. by `gltf::LoadCancelToken`).
+    pub fn cancelled(operation: &'static str) -> VkError {
+        VkError::from(VkErrorKind::Cancelled { operation })
+    }
+
+    /// Create an error for a wait operation which does not finish before its timeout, so the waited work may be still pending.
+    pub fn timeout(operation: &'static str) -> VkError {
+        VkError::from(VkErrorKind::Timeout { operation })
+    }
+
+    /// Check if this error is caused by timeout, which is usually recoverable by waiting again.
+    #[inline]
+    pub fn is_timeout(&self) -> bool {
+        match self.kind() {
+            | VkErrorKind::Timeout { .. } => true,
+            | _ => false,
+        }
+    }
+
     pub fn unimplemented(function: impl AsRef<str>) -> VkError {
         VkError::from(VkErrorKind::Unimplemented { function: function.as_ref().to_string() })
     }
@@ -103,6 +128,9 @@ pub enum VkErrorKind {
     /// An error occurred while creating Vulkan Object.
     #[fail(display = "Failed to create {}.", create_target)]
     Create { create_target: &'static str },
+    /// An error occurred while creating Vulkan Object, with the result code returned by Vulkan.
+    #[fail(display = "Failed to create {}: {:?}.", create_target, result)]
+    CreateWith { create_target: &'static str, result: vk::Result },
     /// An error indicated requiring some unsupported feature.
     #[fail(display = "Feature {} is not supported in current Vulkan Device.", feature)]
     UnSupport { feature: &'static str },
@@ -126,6 +154,12 @@ pub enum VkErrorKind {
     /// An error that occurred while working with a file path.
     #[fail(display = "Failed to locate file at: {:?}", path)]
     Path { path: PathBuf },
+    /// An operation was cancelled before it finished.
+    #[fail(display = "{} is cancelled.", operation)]
+    Cancelled { operation: &'static str },
+    /// A wait operation did not finish before its timeout.
+    #[fail(display = "{} timed out.", operation)]
+    Timeout { operation: &'static str },
     #[fail(display = "{} is not implemented yet.", function)]
     Unimplemented { function: String },
     #[fail(display = "Failed to serialize data into bytes: {}", _0)]
@@ -1,10 +1,12 @@
 
 pub use self::loader::GltfModelInfo;
-pub use self::loader::load_gltf;
-pub use self::asset::{VkglTFModel, ModelRenderParams};
+pub use self::loader::{load_gltf, load_gltf_async, load_gltf_with_progress};
+pub use self::loader::{GltfLoadProgress, GltfLoadStage, LoadCancelToken};
+pub use self::asset::{VkglTFModel, ModelRenderParams, NodeRef};
+pub use self::scene::{SceneNodes, NodeVisit};
 
-pub use self::meshes::AttributeFlags;
-pub use self::nodes::NodeAttachmentFlags;
+pub use self::meshes::{AttributeFlags, NormalGeneration, INSTANCE_BINDING};
+pub use self::nodes::{NodeAttachmentFlags, Node};
 
 mod loader;
 
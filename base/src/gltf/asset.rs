@@ -1,15 +1,16 @@
 
 use ash::vk;
 
-use crate::gltf::meshes::{MeshAsset, MeshResource, AttributeFlags};
-use crate::gltf::nodes::{NodeAsset, NodeResource, NodeAttachmentFlags};
+use crate::gltf::meshes::{MeshAsset, MeshResource, AttributeFlags, NormalGeneration};
+use crate::gltf::nodes::{NodeAsset, NodeResource, NodeAttachmentFlags, Node};
 use crate::gltf::material::{MaterialAsset, MaterialResource};
-use crate::gltf::scene::Scene;
+use crate::gltf::scene::{Scene, SceneNodes};
 
-use crate::command::{VkCmdRecorder, IGraphics};
+use crate::ci::pipeline::PipelineVariantCache;
+use crate::command::{VkCmdRecorder, IGraphics, TransferToken};
 use crate::context::{VkDevice, VmaResourceDiscardable};
-use crate::error::VkResult;
-use crate::Mat4F;
+use crate::error::{VkResult, VkError};
+use crate::{vkuint, Mat4F};
 
 use std::collections::HashMap;
 use std::convert::TryFrom;
@@ -25,6 +26,7 @@ pub struct GltfDocument {
     pub images : Vec<gltf::image::Data>,
 
     pub transform: Option<Mat4F>,
+    pub normal_generation: NormalGeneration,
 }
 // --------------------------------------------------------------------------------------
 
@@ -68,6 +70,24 @@ impl<T> AssetElementList<T> {
         let storage_index = self.query_table.get(&ref_index).cloned().unwrap();
         &self.list[storage_index]
     }
+
+    pub fn get_mut(&mut self, ref_index: ReferenceIndex) -> &mut T {
+
+        debug_assert!(self.query_table.contains_key(&ref_index));
+
+        let storage_index = self.query_table.get(&ref_index).cloned().unwrap();
+        &mut self.list[storage_index]
+    }
+
+    /// Similar to `AssetElementList::get`, but return None if no element is referenced by `ref_index`.
+    pub fn try_get(&self, ref_index: ReferenceIndex) -> Option<&T> {
+        self.query_table.get(&ref_index)
+            .map(|storage_index| &self.list[*storage_index])
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.list.iter()
+    }
 }
 // --------------------------------------------------------------------------------------
 
@@ -104,6 +124,22 @@ impl AssetRepository {
 
         Ok(result)
     }
+
+    /// Similar to `AssetRepository::allocate`, but the meshes are uploaded asynchronously(see `MeshAsset::allocate_async`).
+    pub fn allocate_async(self, device: &mut VkDevice, scene: Scene) -> VkResult<(VkglTFModel, TransferToken)> {
+
+        let nodes_allocated = self.nodes.allocate(device, device.phy.limits.min_uniform_buffer_offset_alignment)?;
+        let (meshes_allocated, token) = self.meshes.allocate_async(device)?;
+
+        let result = VkglTFModel {
+            scene,
+            meshes: meshes_allocated,
+            nodes : nodes_allocated,
+            materials: self.materials,
+        };
+
+        Ok((result, token))
+    }
 }
 // --------------------------------------------------------------------------------------
 
@@ -130,7 +166,100 @@ impl VkglTFModel {
     pub fn record_command(&self, recorder: &VkCmdRecorder<IGraphics>, params: &ModelRenderParams) {
 
         self.meshes.record_command(recorder);
-        self.scene.record_command(recorder, self, params);
+        self.scene.record_command(recorder, self, params, 1);
+    }
+
+    /// Record the draw commands rendering `instance_count` instances of this model.
+    ///
+    /// The per-instance data(etc. transforms) can be read from a vertex buffer bound at `gltf::INSTANCE_BINDING`,
+    /// whose input description is created by `MeshResource::instanced_vertex_input`. Bind it before calling this method.
+    pub fn record_command_instanced(&self, recorder: &VkCmdRecorder<IGraphics>, params: &ModelRenderParams, instance_count: vkuint) {
+
+        self.meshes.record_command(recorder);
+        self.scene.record_command(recorder, self, params, instance_count);
+    }
+
+    /// Record the draw commands grouped by pipeline variants, binding each variant pipeline once.
+    ///
+    /// The pipelines of all materials in this model must have been built in `variants`(see `MaterialAsset::variant_keys`).
+    pub fn record_command_by_variants(&self, recorder: &VkCmdRecorder<IGraphics>, params: &ModelRenderParams, variants: &PipelineVariantCache) {
+
+        self.meshes.record_command(recorder);
+        self.scene.record_command_by_variants(recorder, self, params, variants);
+    }
+}
+
+impl VkglTFModel {
+
+    /// Iterate the Nodes of the scene in depth-first order, with the parent of each Node.
+    pub fn scene_nodes(&self) -> SceneNodes {
+        self.scene.traverse(&self.nodes.list)
+    }
+
+    /// Get the type of indices used by this model, or None if this model contains no index.
+    #[inline]
+    pub fn index_type(&self) -> Option<vk::IndexType> {
+        self.meshes.index_type()
+    }
+
+    /// Find the first Node whose name is `name`.
+    #[inline]
+    pub fn find_node(&self, name: &str) -> Option<&Node> {
+        self.nodes.find(name)
+    }
+
+    /// Set the morph target weights of the mesh with `json_index`, which are read by vertex shader from `MeshResource::morph_descriptors`.
+    ///
+    /// The weights are written to mapped memory immediately, so make sure that device is not reading them(etc. by waiting the fence of frame).
+    #[inline]
+    pub fn set_morph_weights(&self, json_index: ReferenceIndex, weights: &[f32]) -> VkResult<()> {
+        self.meshes.set_morph_weights(json_index, weights)
+    }
+
+    /// Override the local transform of a Node, and update the transforms of this Node and its descendants in the node attachment buffer.
+    ///
+    /// `node` is either the json index or the name of Node.
+    ///
+    /// The attachment buffer is rewritten immediately, so make sure that device is not using this model(etc. by waiting the device idle).
+    /// Only the Nodes which render meshes are stored in node attachment buffer.
+    pub fn set_node_transform<'s>(&mut self, device: &mut VkDevice, node: impl Into<NodeRef<'s>>, transform: Mat4F) -> VkResult<()> {
+
+        let json_index = match node.into() {
+            | NodeRef::Index(json_index) => {
+                self.nodes.get(json_index)
+                    .ok_or(VkError::custom(format!("Failed to find glTF Node with index {}.", json_index)))?;
+                json_index
+            },
+            | NodeRef::Name(name) => {
+                self.nodes.find(name)
+                    .ok_or(VkError::custom(format!("Failed to find glTF Node named {}.", name)))?
+                    .json_index()
+            },
+        };
+
+        self.nodes.list.get_mut(json_index).set_local_transform(transform);
+        self.nodes.update_attachments(device, &self.scene)
+    }
+}
+
+/// Identify a Node of glTF model by its json index or name.
+#[derive(Debug, Clone, Copy)]
+pub enum NodeRef<'s> {
+    Index(ReferenceIndex),
+    Name(&'s str),
+}
+
+impl<'s> From<ReferenceIndex> for NodeRef<'s> {
+
+    fn from(json_index: ReferenceIndex) -> NodeRef<'s> {
+        NodeRef::Index(json_index)
+    }
+}
+
+impl<'s> From<&'s str> for NodeRef<'s> {
+
+    fn from(name: &'s str) -> NodeRef<'s> {
+        NodeRef::Name(name)
     }
 }
 
@@ -1,13 +1,17 @@
 
 use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use crate::gltf::scene::Scene;
 use crate::gltf::nodes::NodeAttachmentFlags;
-use crate::gltf::meshes::AttributeFlags;
+use crate::gltf::meshes::{AttributeFlags, NormalGeneration};
 use crate::gltf::asset::{GltfDocument, AssetAbstract, AssetRepository};
 use crate::gltf::asset::VkglTFModel;
 
 use crate::context::VkDevice;
+use crate::utils::asset::AssetSource;
+use crate::command::TransferToken;
 use crate::error::{VkResult, VkError, VkErrorKind};
 use crate::Mat4F;
 
@@ -21,17 +25,159 @@ pub struct GltfModelInfo<'a> {
     pub node: NodeAttachmentFlags,
     /// A matrix that will apply to position attribute of the model.
     pub transform: Option<Mat4F>,
+    /// Indicate how to generate normals if `AttributeFlags::NORMAL` is requested but the model contains no normal.
+    pub normal_generation: NormalGeneration,
 }
 
 pub fn load_gltf(device: &mut VkDevice, info: GltfModelInfo) -> VkResult<VkglTFModel> {
 
-    let (doc, buffers, images) = gltf::import(info.path)
-        .map_err(VkErrorKind::ParseGltf)?;
+    let (asset_repo, scene) = read_gltf(info, &mut LoadMonitor::silent())?;
+
+    let result = asset_repo.allocate(device, scene)?;
+    Ok(result)
+}
+
+/// Load glTF model without waiting for its meshes to be uploaded to device.
+///
+/// The model can only be rendered after `TransferToken::semaphore` has been waited by graphics queue,
+/// and the `TransferToken` should be discarded after the upload finishes.
+pub fn load_gltf_async(device: &mut VkDevice, info: GltfModelInfo) -> VkResult<(VkglTFModel, TransferToken)> {
+
+    let (asset_repo, scene) = read_gltf(info, &mut LoadMonitor::silent())?;
+    asset_repo.allocate_async(device, scene)
+}
+
+/// Similar to `load_gltf`, but report the loading progress to `on_progress`, and stop loading when `cancel` is cancelled.
+///
+/// `on_progress` is called on current thread after each step(importing the file, reading each mesh, reading nodes and materials, uploading to device).
+/// The cancellation is checked between steps, so a step in progress(etc. the upload) is never interrupted.
+/// A cancelled loading returns the error of `VkErrorKind::Cancelled`, and no device resource is left allocated.
+pub fn load_gltf_with_progress(device: &mut VkDevice, info: GltfModelInfo, mut on_progress: impl FnMut(GltfLoadProgress), cancel: Option<&LoadCancelToken>) -> VkResult<VkglTFModel> {
+
+    let mut monitor = LoadMonitor {
+        on_progress: Some(&mut on_progress),
+        cancel,
+        finished_steps: 0,
+        total_steps: 0,
+    };
+
+    let (asset_repo, scene) = read_gltf(info, &mut monitor)?;
+
+    // the device resources are only allocated in the last step, so nothing needs to be freed on cancellation.
+    monitor.check_cancel()?;
+    let result = asset_repo.allocate(device, scene)?;
+    monitor.finish_step(GltfLoadStage::Upload);
+
+    Ok(result)
+}
+
+/// The step of glTF loading reported by `load_gltf_with_progress`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum GltfLoadStage {
+    /// The glTF file and its buffers have been read.
+    Import,
+    /// A mesh has been read.
+    Mesh,
+    /// The node hierarchy has been read.
+    Nodes,
+    /// The materials have been read.
+    Materials,
+    /// The model has been uploaded to device.
+    Upload,
+}
+
+/// The progress of glTF loading, counted by steps.
+#[derive(Debug, Clone, Copy)]
+pub struct GltfLoadProgress {
+    /// the step just finished.
+    pub stage: GltfLoadStage,
+    /// the count of steps finished.
+    pub finished_steps: usize,
+    /// the count of all steps, which is known after the glTF file is imported.
+    pub total_steps: usize,
+}
+
+impl GltfLoadProgress {
+
+    /// Get the finished proportion of loading, between 0.0 and 1.0.
+    pub fn ratio(&self) -> f32 {
+        if self.total_steps == 0 { 0.0 } else { (self.finished_steps as f32) / (self.total_steps as f32) }
+    }
+}
+
+/// A token to cancel glTF loading from any thread.
+#[derive(Debug, Clone, Default)]
+pub struct LoadCancelToken {
+    is_cancelled: Arc<AtomicBool>,
+}
+
+impl LoadCancelToken {
+
+    pub fn new() -> LoadCancelToken {
+        Default::default()
+    }
+
+    /// Request the loading using this token to stop.
+    pub fn cancel(&self) {
+        self.is_cancelled.store(true, Ordering::Release);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.is_cancelled.load(Ordering::Acquire)
+    }
+}
+
+struct LoadMonitor<'a> {
+
+    on_progress: Option<&'a mut dyn FnMut(GltfLoadProgress)>,
+    cancel: Option<&'a LoadCancelToken>,
+    finished_steps: usize,
+    total_steps: usize,
+}
+
+impl<'a> LoadMonitor<'a> {
+
+    fn silent() -> LoadMonitor<'static> {
+        LoadMonitor { on_progress: None, cancel: None, finished_steps: 0, total_steps: 0 }
+    }
+
+    fn check_cancel(&self) -> VkResult<()> {
+        match self.cancel {
+            | Some(token) if token.is_cancelled() => Err(VkError::cancelled("glTF loading")),
+            | _ => Ok(()),
+        }
+    }
+
+    fn finish_step(&mut self, stage: GltfLoadStage) {
+
+        self.finished_steps += 1;
+
+        if let Some(ref mut on_progress) = self.on_progress {
+            on_progress(GltfLoadProgress {
+                stage,
+                finished_steps: self.finished_steps,
+                total_steps: self.total_steps,
+            });
+        }
+    }
+}
+
+fn read_gltf(info: GltfModelInfo, monitor: &mut LoadMonitor) -> VkResult<(AssetRepository, Scene)> {
+
+    let (doc, buffers, images) = import_document(info.path)?;
     let document = GltfDocument {
         doc, buffers, images,
         transform: info.transform,
+        normal_generation: info.normal_generation,
     };
 
+    validate_required_extensions(&document.doc)?;
+
+    // import, each mesh, nodes, materials and upload.
+    monitor.total_steps = document.doc.meshes().len() + 4;
+    monitor.finish_step(GltfLoadStage::Import);
+    monitor.check_cancel()?;
+
     // Only support loading the default scene or first scene in glTF file.
     let dst_scene = document.doc.default_scene()
         .or(document.doc.scenes().next())
@@ -39,11 +185,159 @@ pub fn load_gltf(device: &mut VkDevice, info: GltfModelInfo) -> VkResult<VkglTFM
 
     let scene = Scene::from_doc(dst_scene);
     let mut asset_repo = AssetRepository::new(info.attribute, info.node)?;
-    asset_repo.meshes.read_doc(&document, &scene)?;
+
+    asset_repo.meshes.read_doc_with(&document, &mut || {
+        monitor.finish_step(GltfLoadStage::Mesh);
+        monitor.check_cancel()
+    })?;
+
     asset_repo.nodes.read_doc(&document, &scene)?;
+    monitor.finish_step(GltfLoadStage::Nodes);
+    monitor.check_cancel()?;
+
     asset_repo.materials.read_doc(&document, &scene)?;
+    monitor.finish_step(GltfLoadStage::Materials);
 
-    let result = asset_repo.allocate(device, scene)?;
-    Ok(result)
+    Ok((asset_repo, scene))
+}
+
+/// The extension of Draco compressed meshes, whose geometry can not be read without a Draco decoder.
+const KHR_DRACO_MESH_COMPRESSION: &'static str = "KHR_draco_mesh_compression";
+
+/// Reject the glTF documents whose geometry can not be read by this loader.
+///
+/// This only validates the document, and does not decompress anything: there is no Draco decoder in this crate yet.
+/// Draco compressed primitives are only readable if the extension is optional,
+/// in which case the primitives also contain uncompressed geometry as fallback, which is loaded instead.
+fn validate_required_extensions(doc: &gltf::Document) -> VkResult<()> {
+
+    let is_draco_required = doc.as_json().extensions_required.iter()
+        .any(|extension| extension == KHR_DRACO_MESH_COMPRESSION);
+
+    if is_draco_required {
+        return Err(VkError::unsupported("glTF extension KHR_draco_mesh_compression(Draco decoder is not available)"))
+    }
+
+    Ok(())
+}
+
+/// Read glTF document, its buffers and images from current asset source.
+fn import_document(path: &Path) -> VkResult<(gltf::Document, Vec<gltf::buffer::Data>, Vec<gltf::image::Data>)> {
+    import_from_source(&*crate::utils::asset::asset_source(), path)
+}
+
+/// Read glTF document, its buffers and images from `source`.
+fn import_from_source(source: &dyn AssetSource, path: &Path) -> VkResult<(gltf::Document, Vec<gltf::buffer::Data>, Vec<gltf::image::Data>)> {
+
+    use crate::utils::asset::decode_data_uri;
+
+    // use the importer of gltf crate directly if the model is stored as a file.
+    if let Some(local_path) = source.local_path(path) {
+        let result = gltf::import(local_path)
+            .map_err(VkErrorKind::ParseGltf)?;
+        return Ok(result)
+    }
+
+    let bytes = source.read_bytes(path)?;
+    let gltf::Gltf { document, mut blob } = gltf::Gltf::from_slice(&bytes)
+        .map_err(VkErrorKind::ParseGltf)?;
+
+    // external buffers and images are located relative to the glTF file.
+    let base_dir = path.parent().unwrap_or(Path::new(""));
+    let read_uri = |uri: &str| -> VkResult<Vec<u8>> {
+        match decode_data_uri(uri) {
+            | Some(data) => data,
+            | None => source.read_bytes(&base_dir.join(uri)),
+        }
+    };
+
+    let mut buffers = Vec::with_capacity(document.buffers().len());
+    for doc_buffer in document.buffers() {
+
+        let data = match doc_buffer.source() {
+            | gltf::buffer::Source::Bin => {
+                blob.take()
+                    .ok_or(VkError::custom("Missing binary chunk in glTF file."))?
+            },
+            | gltf::buffer::Source::Uri(uri) => {
+                read_uri(uri)?
+            },
+        };
+        buffers.push(gltf::buffer::Data(data));
+    }
+
+    let mut images = Vec::with_capacity(document.images().len());
+    for doc_image in document.images() {
+
+        let encoded = match doc_image.source() {
+            | gltf::image::Source::View { view, .. } => {
+                let buffer = &buffers[view.buffer().index()];
+                buffer[view.offset()..(view.offset() + view.length())].to_vec()
+            },
+            | gltf::image::Source::Uri { uri, .. } => {
+                read_uri(uri)?
+            },
+        };
+        images.push(decode_image(&encoded)?);
+    }
+
+    Ok((document, buffers, images))
 }
 
+/// Decode the encoded bytes of glTF image(PNG or JPEG) to RGBA pixels.
+fn decode_image(encoded: &[u8]) -> VkResult<gltf::image::Data> {
+
+    let decoded = image::load_from_memory(encoded)
+        .map_err(|e| VkError::custom(format!("Failed to decode glTF image: {}", e)))?
+        .to_rgba();
+
+    let data = gltf::image::Data {
+        width : decoded.width(),
+        height: decoded.height(),
+        format: gltf::image::Format::R8G8B8A8,
+        pixels: decoded.into_raw(),
+    };
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::utils::asset::{EmbeddedSource, FileSystemSource};
+
+    /// A glTF document whose buffer is embedded as base64 data URI.
+    const DATA_URI_GLTF: &'static str = r#"{
+        "asset": { "version": "2.0" },
+        "scenes": [{ "nodes": [0] }],
+        "nodes": [{ "name": "root" }],
+        "buffers": [{ "byteLength": 8, "uri": "data:application/octet-stream;base64,AAECAwQFBgc=" }],
+        "bufferViews": [{ "buffer": 0, "byteOffset": 0, "byteLength": 8 }]
+    }"#;
+
+    #[test]
+    fn embedded_and_file_system_sources_import_same_model() {
+
+        let temp_dir = std::env::temp_dir().join(format!("vkbase-gltf-source-{}", std::process::id()));
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        let file_path = temp_dir.join("model.gltf");
+        std::fs::write(&file_path, DATA_URI_GLTF).unwrap();
+
+        let file_result = import_from_source(&FileSystemSource, &file_path);
+        std::fs::remove_dir_all(&temp_dir).unwrap();
+        let (file_doc, file_buffers, file_images) = file_result.unwrap();
+
+        let embedded_source = EmbeddedSource::new()
+            .add_asset("models/model.gltf", DATA_URI_GLTF.as_bytes());
+        let (embedded_doc, embedded_buffers, embedded_images) = import_from_source(&embedded_source, Path::new("models/model.gltf")).unwrap();
+
+        assert_eq!(format!("{:?}", file_doc.as_json()), format!("{:?}", embedded_doc.as_json()));
+
+        let buffer_bytes = |buffers: &[gltf::buffer::Data]| -> Vec<Vec<u8>> {
+            buffers.iter().map(|buffer| buffer.0.clone()).collect()
+        };
+        assert_eq!(buffer_bytes(&embedded_buffers), vec![vec![0, 1, 2, 3, 4, 5, 6, 7]]);
+        assert_eq!(buffer_bytes(&file_buffers), buffer_bytes(&embedded_buffers));
+        assert!(file_images.is_empty() && embedded_images.is_empty());
+    }
+}
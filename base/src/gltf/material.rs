@@ -1,10 +1,12 @@
 
+use ash::vk;
 use serde_derive::Serialize;
 use std::collections::HashMap;
 
 use crate::gltf::asset::{GltfDocument, AssetAbstract};
 use crate::gltf::asset::ReferenceIndex;
 use crate::gltf::scene::Scene;
+use crate::ci::pipeline::{PipelineVariantKey, VariantAlphaMode};
 use crate::error::{VkResult, VkError};
 use crate::{vkfloat, vkuint};
 
@@ -71,6 +73,8 @@ pub struct MaterialAsset {
     material_count: usize,
 
     material_mapping: HashMap<ReferenceIndex, MaterialOffset>,
+    /// the pipeline states required by each material.
+    variant_mapping: HashMap<ReferenceIndex, PipelineVariantKey>,
 }
 
 impl MaterialAsset {
@@ -85,7 +89,10 @@ impl MaterialAsset {
         material_mapping.insert(DEFAULT_MATERIAL_INDEX, DEFAULT_MATERIAL_OFFSET);
         let material_count = 1;
 
-        let result = MaterialAsset { data_content, material_count, material_mapping };
+        let mut variant_mapping = HashMap::new();
+        variant_mapping.insert(DEFAULT_MATERIAL_INDEX, PipelineVariantKey::default());
+
+        let result = MaterialAsset { data_content, material_count, material_mapping, variant_mapping };
         Ok(result)
     }
 
@@ -99,6 +106,25 @@ impl MaterialAsset {
             .unwrap_or(DEFAULT_MATERIAL_OFFSET);
         &self.data_content[offset..(offset + MATERIAL_SIZE)]
     }
+
+    /// Get the pipeline states required by specific material.
+    pub fn get_material_variant(&self, material_index: &Option<ReferenceIndex>) -> PipelineVariantKey {
+
+        self.variant_mapping.get(&material_index.unwrap_or(DEFAULT_MATERIAL_INDEX)).cloned()
+            .unwrap_or_default()
+    }
+
+    /// Get all the unique pipeline states required by materials of this model.
+    pub fn variant_keys(&self) -> Vec<PipelineVariantKey> {
+
+        let mut keys: Vec<PipelineVariantKey> = Vec::new();
+        for key in self.variant_mapping.values() {
+            if !keys.contains(key) {
+                keys.push(key.clone());
+            }
+        }
+        keys
+    }
 }
 
 impl AssetAbstract for MaterialAsset {
@@ -110,6 +136,9 @@ impl AssetAbstract for MaterialAsset {
 
             if let Some(json_index) = doc_material.index() {
 
+                let variant = read_variant(&doc_material);
+                self.variant_mapping.insert(json_index, variant);
+
                 let material = MaterialData::from(doc_material);
                 let material_serialized = material.serialize()?;
                 self.data_content.extend(material_serialized);
@@ -125,3 +154,20 @@ impl AssetAbstract for MaterialAsset {
     }
 }
 // ------------------------------------------------------------------------------------
+
+fn read_variant(doc_material: &gltf::Material) -> PipelineVariantKey {
+
+    let alpha_mode = match doc_material.alpha_mode() {
+        | gltf::material::AlphaMode::Opaque => VariantAlphaMode::Opaque,
+        | gltf::material::AlphaMode::Mask   => VariantAlphaMode::Mask,
+        | gltf::material::AlphaMode::Blend  => VariantAlphaMode::Blend,
+    };
+
+    PipelineVariantKey {
+        // disable back-face culling for double-sided material.
+        cull_mode: if doc_material.double_sided() { vk::CullModeFlags::NONE } else { vk::CullModeFlags::BACK },
+        alpha_mode,
+        // translucent material does not write depth.
+        is_depth_write: alpha_mode != VariantAlphaMode::Blend,
+    }
+}
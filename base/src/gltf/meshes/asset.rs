@@ -1,29 +1,37 @@
 
 use ash::vk;
 
-use crate::gltf::asset::{GltfDocument, AssetAbstract, AssetElementList};
+use crate::gltf::asset::{GltfDocument, AssetAbstract, AssetElementList, ReferenceIndex};
 use crate::gltf::scene::Scene;
 use crate::gltf::meshes::mesh::Mesh;
 use crate::gltf::meshes::attributes::{AttributesData, AttributeFlags};
 use crate::gltf::meshes::indices::IndicesData;
+use crate::gltf::meshes::morph::MorphTargetsData;
 
-use crate::ci::buffer::BufferCI;
+use crate::ci::buffer::{BufferCI, BufferBarrierCI};
 use crate::ci::vma::{VmaAllocationCI, VmaBuffer};
-use crate::ci::pipeline::VertexInputSCI;
+use crate::ci::pipeline::{VertexInputSCI, GraphicsPipelineCI};
+use crate::ci::VkObjectBuildableCI;
 
 use crate::context::VkDevice;
-use crate::command::{VkCmdRecorder, IGraphics, CmdGraphicsApi, CmdTransferApi};
+use crate::command::{VkCmdRecorder, IGraphics, CmdGraphicsApi, TransferToken};
+use crate::utils::staging::{StagingBatch, StagingBuffer};
+use crate::utils::memory::direct_upload_flags;
 
 use crate::error::{VkResult, VkError, VkErrorKind};
-use crate::vkptr;
+use crate::{vkuint, vkptr};
 
 use std::convert::TryFrom;
 
 
+/// The vertex binding of per-instance attributes. Binding 0 is used by the vertex attributes of model.
+pub const INSTANCE_BINDING: vkuint = 1;
+
 pub struct MeshAsset {
 
     attributes: AttributesData,
     indices: IndicesData,
+    morphs: MorphTargetsData,
 
     meshes: AssetElementList<Mesh>,
 }
@@ -32,6 +40,16 @@ struct MeshAssetBlock {
 
     vertices: VmaBuffer,
     indices : Option<VmaBuffer>,
+    morph: Option<MorphResource>,
+}
+
+/// The storage buffers of morph targets(see `MorphTargetsData` for their layout in shader).
+struct MorphResource {
+
+    vertices: VmaBuffer,
+    deltas  : VmaBuffer,
+    /// the persistently mapped weights of all meshes.
+    weights : VmaBuffer,
 }
 
 pub struct MeshResource {
@@ -40,6 +58,11 @@ pub struct MeshResource {
 
     vertices: VmaBuffer,
     indices: Option<VmaBuffer>,
+    /// the type of indices stored in `indices` buffer.
+    index_type: vk::IndexType,
+    morph: Option<MorphResource>,
+    /// the size of each vertex stored in `vertices` buffer.
+    vertex_stride: vkuint,
 
     pub vertex_input: VertexInputSCI,
 }
@@ -52,6 +75,7 @@ impl TryFrom<AttributeFlags> for MeshAsset {
         let result = MeshAsset {
             attributes: AttributesData::try_from(flag)?,
             indices: Default::default(),
+            morphs : Default::default(),
             meshes : Default::default(),
         };
         Ok(result)
@@ -62,51 +86,136 @@ impl AssetAbstract for MeshAsset {
     const ASSET_NAME: &'static str = "Meshes";
 
     fn read_doc(&mut self, source: &GltfDocument, _scene: &Scene) -> VkResult<()> {
+        self.read_doc_with(source, &mut || Ok(()))
+    }
+}
+
+impl MeshAsset {
+
+    /// Similar to `AssetAbstract::read_doc`, but call `on_mesh_read` after each mesh is read.
+    ///
+    /// The reading stops if `on_mesh_read` returns error.
+    pub fn read_doc_with(&mut self, source: &GltfDocument, on_mesh_read: &mut dyn FnMut() -> VkResult<()>) -> VkResult<()> {
 
         for doc_mesh in source.doc.meshes() {
 
             let json_index = doc_mesh.index();
-            let mesh = Mesh::from_doc(doc_mesh, source, &mut self.attributes, &mut self.indices)?;
+            let mesh = Mesh::from_doc(doc_mesh, source, &mut self.attributes, &mut self.indices, &mut self.morphs)?;
 
             self.meshes.push(json_index, mesh);
+            on_mesh_read()?;
         }
 
         Ok(())
     }
-}
-
-impl MeshAsset {
 
+    /// Allocate the mesh buffers and upload the data of all meshes.
+    ///
+    /// The data is written to mesh buffers directly if device has large `DEVICE_LOCAL | HOST_VISIBLE` memory(see `VkDevice::find_direct_upload_memory`),
+    /// otherwise it is copied from a staging buffer.
+    ///
+    /// The copy is waited without timeout. Use `MeshAsset::allocate_async` instead to poll the upload by `TransferToken::is_finished`
+    /// or wait it by `TransferToken::wait_with`, so that a loader can give up on a stuck device.
     pub fn allocate(self, device: &mut VkDevice) -> VkResult<MeshResource> {
 
-        // allocate staging buffer.
-        let staging_block = self.allocate_staging(&mut device.vma)?;
+        // write mesh data to device local memory directly if it is host visible, which skips the staging copy.
+        // otherwise(or if none of these memory types is accepted by mesh buffers) fall back to the staging copy.
+        let type_bits = self.direct_memory_type_bits(device)?;
+        if let Some(memory_index) = device.find_direct_upload_memory(type_bits) {
+
+            let mesh_block = self.allocate_mesh(&mut device.vma, Some(memory_index))?;
+            self.map_direct(&mesh_block);
+            return Ok(self.into_resource(mesh_block))
+        }
+
         // allocate mesh buffer.
-        let mesh_block = self.allocate_mesh(&mut device.vma)?;
+        let mesh_block = self.allocate_mesh(&mut device.vma, None)?;
+        // allocate staging buffer.
+        let staging = self.allocate_staging(&mut device.vma, &mesh_block)?;
 
         // copy data from staging buffer to mesh buffer.
-        MeshAsset::copy_staging2mesh(device, &staging_block, &mesh_block)?;
+        MeshAsset::copy_staging2mesh(device, &staging, &mesh_block)?;
 
         // discard staging resource.
-        staging_block.discard(&mut device.vma)?;
+        staging.discard_by(&mut device.vma)?;
 
-        let result = MeshResource {
+        Ok(self.into_resource(mesh_block))
+    }
+
+    fn into_resource(self, mesh_block: MeshAssetBlock) -> MeshResource {
+
+        // the vertices are written to buffer in the layout described by `input_descriptions`.
+        let vertex_input = self.attributes.input_descriptions();
+
+        MeshResource {
             vertices: mesh_block.vertices,
             indices: mesh_block.indices,
+            index_type: self.indices.index_type(),
+            morph: mesh_block.morph,
             list: self.meshes,
-            vertex_input: self.attributes.input_descriptions(),
-        };
-        Ok(result)
+            vertex_stride: vertex_stride(&vertex_input),
+            vertex_input,
+        }
+    }
+
+    /// Get the allocation info of mesh buffers.
+    ///
+    /// If `direct_memory` is set, the buffers are persistently mapped in this memory type and written by host directly,
+    /// otherwise they are device local and written by transfer commands.
+    fn mesh_allocation(direct_memory: Option<vkuint>) -> (VmaAllocationCI, vk::BufferUsageFlags) {
+
+        match direct_memory {
+            | Some(memory_index) => {
+                let allocate_ci = VmaAllocationCI::new(vma::MemoryUsage::Unknown, direct_upload_flags())
+                    .accept_memory_types(1 << memory_index)
+                    .flags(vma::AllocationCreateFlags::MAPPED);
+                (allocate_ci, vk::BufferUsageFlags::empty())
+            },
+            | None => {
+                let allocate_ci = VmaAllocationCI::new(vma::MemoryUsage::GpuOnly, vk::MemoryPropertyFlags::DEVICE_LOCAL);
+                (allocate_ci, vk::BufferUsageFlags::TRANSFER_DST)
+            },
+        }
+    }
+
+    /// Get the memory types acceptable to all the mesh buffers allocated with direct upload memory.
+    fn direct_memory_type_bits(&self, device: &VkDevice) -> VkResult<vkuint> {
+
+        let mut buffer_cis = vec![
+            BufferCI::new(self.attributes.buffer_size_estimated())
+                .usage(vk::BufferUsageFlags::VERTEX_BUFFER),
+        ];
+        if let Some(indices_size) = self.indices.buffer_size_estimated() {
+            buffer_cis.push(BufferCI::new(indices_size)
+                .usage(vk::BufferUsageFlags::INDEX_BUFFER));
+        }
+        if self.morphs.is_empty() == false {
+            buffer_cis.push(BufferCI::new(self.morphs.vertices_size())
+                .usage(vk::BufferUsageFlags::STORAGE_BUFFER));
+            buffer_cis.push(BufferCI::new(self.morphs.deltas_size())
+                .usage(vk::BufferUsageFlags::STORAGE_BUFFER));
+        }
+
+        // the memory requirements are only reported by buffer objects, so create them temporarily.
+        let mut type_bits = !0;
+        for buffer_ci in buffer_cis.iter() {
+            let (buffer, requirement) = buffer_ci.build(device)?;
+            device.discard(buffer);
+            type_bits &= requirement.memory_type_bits;
+        }
+
+        Ok(type_bits)
     }
 
-    fn allocate_mesh(&self, vma: &mut vma::Allocator) -> VkResult<MeshAssetBlock> {
+    fn allocate_mesh(&self, vma: &mut vma::Allocator, direct_memory: Option<vkuint>) -> VkResult<MeshAssetBlock> {
+
+        let (allocate_ci, upload_usage) = MeshAsset::mesh_allocation(direct_memory);
 
         // allocate vertices buffer for glTF attributes.
         let vertex_buffer = {
 
             let vertex_ci = BufferCI::new(self.attributes.buffer_size_estimated())
-                .usage(vk::BufferUsageFlags::VERTEX_BUFFER | vk::BufferUsageFlags::TRANSFER_DST);
-            let allocate_ci = VmaAllocationCI::new(vma::MemoryUsage::GpuOnly, vk::MemoryPropertyFlags::DEVICE_LOCAL);
+                .usage(vk::BufferUsageFlags::VERTEX_BUFFER | upload_usage);
             let vertices_allocation = vma.create_buffer(
                 vertex_ci.as_ref(), allocate_ci.as_ref())
                 .map_err(VkErrorKind::Vma)?;
@@ -118,8 +227,7 @@ impl MeshAsset {
         let index_buffer = if let Some(indices_size) = self.indices.buffer_size_estimated() {
 
             let indices_ci = BufferCI::new(indices_size)
-                .usage(vk::BufferUsageFlags::INDEX_BUFFER | vk::BufferUsageFlags::TRANSFER_DST);
-            let allocate_ci = VmaAllocationCI::new(vma::MemoryUsage::GpuOnly, vk::MemoryPropertyFlags::DEVICE_LOCAL);
+                .usage(vk::BufferUsageFlags::INDEX_BUFFER | upload_usage);
             let indices_allocation = vma.create_buffer(
                 indices_ci.as_ref(), allocate_ci.as_ref())
                 .map_err(VkErrorKind::Vma)?;
@@ -129,113 +237,160 @@ impl MeshAsset {
             None
         };
 
+        let morph_buffers = if self.morphs.is_empty() {
+            None
+        } else {
+            Some(self.allocate_morph(vma, &allocate_ci, upload_usage)?)
+        };
+
         let mesh_block = MeshAssetBlock {
             vertices: vertex_buffer,
             indices : index_buffer,
+            morph   : morph_buffers,
         };
         Ok(mesh_block)
     }
 
-    fn allocate_staging(&self, vma: &mut vma::Allocator) -> VkResult<MeshAssetBlock> {
+    fn allocate_morph(&self, vma: &mut vma::Allocator, mesh_allocate_ci: &VmaAllocationCI, upload_usage: vk::BufferUsageFlags) -> VkResult<MorphResource> {
 
-        let staging_vertices = {
+        let mut allocate_storage = |size, usage, allocate_ci: &VmaAllocationCI| -> VkResult<VmaBuffer> {
 
-            let vertex_ci = BufferCI::new(self.attributes.buffer_size_estimated())
-                .usage(vk::BufferUsageFlags::TRANSFER_SRC);
-            let allocate_ci = VmaAllocationCI::new(vma::MemoryUsage::CpuToGpu, vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT);
-            let (handle, allocation, info) = vma.create_buffer(
-                vertex_ci.as_ref(), allocate_ci.as_ref())
+            let storage_ci = BufferCI::new(size)
+                .usage(vk::BufferUsageFlags::STORAGE_BUFFER | usage);
+            let storage_allocation = vma.create_buffer(storage_ci.as_ref(), allocate_ci.as_ref())
                 .map_err(VkErrorKind::Vma)?;
+            Ok(VmaBuffer::from(storage_allocation))
+        };
 
-            let data_ptr = vma.map_memory(&allocation)
-                .map_err(VkErrorKind::Vma)? as vkptr;
+        let vertices = allocate_storage(self.morphs.vertices_size(), upload_usage, mesh_allocate_ci)?;
+        let deltas = allocate_storage(self.morphs.deltas_size(), upload_usage, mesh_allocate_ci)?;
+        // the weights are updated by host frequently, so keep them in host visible memory.
+        let weights = allocate_storage(self.morphs.weights_size().max(1), vk::BufferUsageFlags::empty(),
+            &VmaAllocationCI::new(vma::MemoryUsage::CpuToGpu, vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT)
+                .flags(vma::AllocationCreateFlags::MAPPED))?;
 
-            self.attributes.data_content.map_data(data_ptr);
+        self.morphs.map_weights(weights.info.get_mapped_data() as vkptr);
 
-            vma.unmap_memory(&allocation)
-                .map_err(VkErrorKind::Vma)?;
+        let result = MorphResource { vertices, deltas, weights };
+        Ok(result)
+    }
 
-            VmaBuffer { handle, allocation, info }
-        };
+    /// Write all the vertex and index data into the mapped mesh buffers allocated by `MeshAsset::allocate_mesh` with direct upload memory.
+    ///
+    /// The memory is host coherent, so the writes are visible to device at the next queue submission.
+    fn map_direct(&self, meshes: &MeshAssetBlock) {
 
-        // allocate index buffer for glTF attributes.
-        let staging_indices = if let Some(indices_size) = self.indices.buffer_size_estimated() {
+        self.attributes.data_content.map_data(meshes.vertices.info.get_mapped_data() as vkptr);
+        if let Some(ref indices) = meshes.indices {
+            self.indices.map_data(indices.info.get_mapped_data() as vkptr);
+        }
+        if let Some(ref morph) = meshes.morph {
+            self.morphs.map_vertices(morph.vertices.info.get_mapped_data() as vkptr);
+            self.morphs.map_deltas(morph.deltas.info.get_mapped_data() as vkptr);
+        }
+    }
 
-            let indices_ci = BufferCI::new(indices_size)
-                .usage(vk::BufferUsageFlags::TRANSFER_SRC);
-            let allocate_ci = VmaAllocationCI::new(vma::MemoryUsage::CpuToGpu, vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT);
-            let (handle, allocation, info) = vma.create_buffer(
-                indices_ci.as_ref(), allocate_ci.as_ref())
-                .map_err(VkErrorKind::Vma)?;
+    /// Write all the vertex and index data into a single staging buffer, which is copied to `meshes` by `StagingBuffer::record_copies`.
+    fn allocate_staging(&self, vma: &mut vma::Allocator, meshes: &MeshAssetBlock) -> VkResult<StagingBuffer> {
 
-            let data_ptr = vma.map_memory(&allocation)
-                .map_err(VkErrorKind::Vma)? as vkptr;
+        let mut batch = StagingBatch::new();
 
-            self.indices.map_data(data_ptr);
+        let vertex_offset = batch.reserve(meshes.vertices.handle, 0, self.attributes.buffer_size_estimated());
+        let index_offset = match (self.indices.buffer_size_estimated(), &meshes.indices) {
+            | (Some(indices_size), Some(meshes_indices)) => Some(batch.reserve(meshes_indices.handle, 0, indices_size)),
+            | _ => None,
+        };
 
-            vma.unmap_memory(&allocation)
-                .map_err(VkErrorKind::Vma)?;
+        let morph_offsets = meshes.morph.as_ref().map(|morph| {
+            (batch.reserve(morph.vertices.handle, 0, self.morphs.vertices_size()),
+             batch.reserve(morph.deltas.handle, 0, self.morphs.deltas_size()))
+        });
 
-            Some(VmaBuffer { handle, allocation, info })
-        } else {
-            None
-        };
+        let staging = batch.allocate(vma)?;
 
-        let staging_meshes = MeshAssetBlock {
-            vertices: staging_vertices,
-            indices : staging_indices,
-        };
-        Ok(staging_meshes)
+        self.attributes.data_content.map_data(staging.data_ptr(vertex_offset));
+        if let Some(index_offset) = index_offset {
+            self.indices.map_data(staging.data_ptr(index_offset));
+        }
+        if let Some((vertices_offset, deltas_offset)) = morph_offsets {
+            self.morphs.map_vertices(staging.data_ptr(vertices_offset));
+            self.morphs.map_deltas(staging.data_ptr(deltas_offset));
+        }
+
+        Ok(staging)
     }
 
-    fn copy_staging2mesh(device: &VkDevice, staging: &MeshAssetBlock, meshes: &MeshAssetBlock) -> VkResult<()> {
+    fn copy_staging2mesh(device: &VkDevice, staging: &StagingBuffer, meshes: &MeshAssetBlock) -> VkResult<()> {
 
-        let cmd_recorder = device.get_transfer_recorder();
+        // the mesh buffers are written by transfer queue but read by graphics queue.
+        let ownership_barriers = if device.is_transfer_family_separate() {
+            Some(MeshAsset::ownership_barriers(device, meshes))
+        } else {
+            None
+        };
 
-        cmd_recorder.begin_record()?;
+        // execute and wait the copy operation without timeout(see `MeshAsset::allocate`).
+        device.submit_once(&device.logic.queues.transfer, |recorder| {
 
-        let vertex_copy_region = vk::BufferCopy {
-            src_offset: 0, // the starting offset of buffer.
-            dst_offset: 0,
-            size      : staging.vertices.info.get_size() as _,
-        };
-        // copy vertices data to target buffer.
-        cmd_recorder.copy_buf2buf(staging.vertices.handle, meshes.vertices.handle, &[vertex_copy_region]);
-
-        // copy index data to target buffer.
-        if let Some(ref staging_index) = staging.indices {
-            if let Some(ref meshes_indices) = meshes.indices {
-                let index_copy_region = vk::BufferCopy {
-                    src_offset: 0,
-                    dst_offset: 0,
-                    size      : staging_index.info.get_size() as _,
-                };
-                cmd_recorder.copy_buf2buf(staging_index.handle, meshes_indices.handle, &[index_copy_region]);
+            staging.record_copies(recorder);
+            if let Some(ref barriers) = ownership_barriers {
+                recorder.release_ownership(vk::PipelineStageFlags::TRANSFER, barriers, &[]);
             }
-        }
+            Ok(())
+        })?;
 
-        cmd_recorder.end_record()?;
-        // execute and wait the copy operation.
-        device.flush_transfer(cmd_recorder)?;
+        if let Some(barriers) = ownership_barriers {
+            device.acquire_graphics_ownership(MeshAsset::acquire_stage(meshes), &barriers, &[])?;
+        }
 
         Ok(())
     }
-}
 
-impl MeshAssetBlock {
+    /// Similar to `MeshAsset::allocate`, but the mesh data is uploaded asynchronously.
+    ///
+    /// The returned `MeshResource` can only be used after the `TransferToken` has been waited(etc. waiting its semaphore in graphics queue submission).
+    /// The data is always uploaded by staging buffer here.
+    pub fn allocate_async(self, device: &mut VkDevice) -> VkResult<(MeshResource, TransferToken)> {
 
-    fn discard(&self, vma: &mut vma::Allocator) -> VkResult<()> {
+        let mesh_block = self.allocate_mesh(&mut device.vma, None)?;
+        let staging = self.allocate_staging(&mut device.vma, &mesh_block)?;
 
-        vma.destroy_buffer(self.vertices.handle, &self.vertices.allocation)
-            .map_err(VkErrorKind::Vma)?;
+        let barriers = MeshAsset::ownership_barriers(device, &mesh_block);
 
-        if let Some(ref indices) = self.indices {
+        // the staging buffer will be discarded with transfer token.
+        let token = device.flush_transfer_async(|recorder| {
+            staging.record_copies(recorder);
+        }, &barriers, MeshAsset::acquire_stage(&mesh_block), vec![staging.buffer.clone()])?;
 
-            vma.destroy_buffer(indices.handle, &indices.allocation)
-                .map_err(VkErrorKind::Vma)?;
+        Ok((self.into_resource(mesh_block), token))
+    }
+
+    /// Generate the barriers transferring the ownership of mesh buffers from transfer queue family to graphics queue family.
+    fn ownership_barriers(device: &VkDevice, meshes: &MeshAssetBlock) -> Vec<vk::BufferMemoryBarrier> {
+
+        let mut buffers = Vec::with_capacity(4);
+        buffers.push((meshes.vertices.handle, vk::AccessFlags::VERTEX_ATTRIBUTE_READ));
+
+        if let Some(ref meshes_indices) = meshes.indices {
+            buffers.push((meshes_indices.handle, vk::AccessFlags::INDEX_READ));
         }
 
-        Ok(())
+        if let Some(ref morph) = meshes.morph {
+            buffers.push((morph.vertices.handle, vk::AccessFlags::SHADER_READ));
+            buffers.push((morph.deltas.handle, vk::AccessFlags::SHADER_READ));
+        }
+
+        ownership_barriers(&buffers, device.logic.queues.transfer.family_index, device.logic.queues.graphics.family_index)
+    }
+
+    /// The pipeline stages that first read the mesh buffers on graphics queue.
+    fn acquire_stage(meshes: &MeshAssetBlock) -> vk::PipelineStageFlags {
+        if meshes.morph.is_some() {
+            // the morph targets are read as storage buffers in vertex shader.
+            vk::PipelineStageFlags::VERTEX_INPUT | vk::PipelineStageFlags::VERTEX_SHADER
+        } else {
+            vk::PipelineStageFlags::VERTEX_INPUT
+        }
     }
 }
 
@@ -246,8 +401,99 @@ impl MeshResource {
         recorder.bind_vertex_buffers(0, &[self.vertices.handle], &[0]);
 
         if let Some(ref index_buffer) = self.indices {
-            recorder.bind_index_buffer(index_buffer.handle, vk::IndexType::UINT32, 0);
+            recorder.bind_index_buffer(index_buffer.handle, self.index_type, 0);
+        }
+    }
+
+    /// Get the type of indices in the index buffer of model, which is bound by `MeshResource::record_command`.
+    ///
+    /// Return None if the model contains no index.
+    #[inline]
+    pub fn index_type(&self) -> Option<vk::IndexType> {
+        self.indices.as_ref().map(|_| self.index_type)
+    }
+
+    /// Get the size of each vertex in the vertex buffer of model.
+    #[inline]
+    pub fn vertex_stride(&self) -> vkuint {
+        self.vertex_stride
+    }
+
+    /// Make sure the vertex input state of `pipeline_ci` can read the vertex buffer of model, which should be called before building the pipeline.
+    ///
+    /// Return error if binding 0 of the pipeline is missing, or its stride differs from the stride of vertex buffer.
+    pub fn check_pipeline(&self, pipeline_ci: &GraphicsPipelineCI) -> VkResult<()> {
+        check_vertex_stride(pipeline_ci.vertex_input(), self.vertex_stride)
+    }
+
+    /// Make sure the binding 0 of `input`(the vertex input state of pipeline) has the same stride as the vertex buffer of model.
+    pub fn check_vertex_input(&self, input: &VertexInputSCI) -> VkResult<()> {
+        check_vertex_stride(input, self.vertex_stride)
+    }
+
+    /// Extend `vertex_input` with per-instance attributes read from the vertex buffer bound at `INSTANCE_BINDING`.
+    ///
+    /// `stride` is the size of the data of each instance. Each attribute is described by its format and offset,
+    /// and their locations follow the last vertex attribute of model.
+    /// For example, a `mat4` transform takes four `vk::Format::R32G32B32A32_SFLOAT` attributes with offset 0, 16, 32 and 48.
+    pub fn instanced_vertex_input(&self, stride: vkuint, attributes: &[(vk::Format, vkuint)]) -> VertexInputSCI {
+
+        let first_location = self.vertex_input.next_location();
+
+        let mut sci = self.vertex_input.clone()
+            .add_binding(vk::VertexInputBindingDescription {
+                binding: INSTANCE_BINDING,
+                stride,
+                input_rate: vk::VertexInputRate::INSTANCE,
+            });
+
+        for (i, &(format, offset)) in attributes.iter().enumerate() {
+            sci = sci.add_attribute(vk::VertexInputAttributeDescription {
+                location: first_location + i as vkuint,
+                binding : INSTANCE_BINDING,
+                format, offset,
+            });
         }
+
+        sci
+    }
+
+    /// Get the descriptors of morph target storage buffers(vertices, deltas and weights), or None if this model contains no morph target.
+    ///
+    /// See `MorphTargetsData` for the usage of these buffers in vertex shader.
+    pub fn morph_descriptors(&self) -> Option<[vk::DescriptorBufferInfo; 3]> {
+
+        self.morph.as_ref().map(|morph| {
+            let descriptor = |buffer: &VmaBuffer| vk::DescriptorBufferInfo {
+                buffer: buffer.handle,
+                offset: 0,
+                range : vk::WHOLE_SIZE,
+            };
+            [descriptor(&morph.vertices), descriptor(&morph.deltas), descriptor(&morph.weights)]
+        })
+    }
+
+    /// Overwrite the morph weights of mesh with `json_index`, starting from its first weight.
+    ///
+    /// The weights are written to mapped memory immediately, so make sure that device is not reading them(etc. by waiting the fence of frame).
+    pub fn set_morph_weights(&self, json_index: ReferenceIndex, weights: &[f32]) -> VkResult<()> {
+
+        let mesh = self.list.try_get(json_index)
+            .ok_or(VkError::custom(format!("Failed to find glTF Mesh with index {}.", json_index)))?;
+        let morph_weights = mesh.morph_weights();
+
+        if weights.len() > morph_weights.weight_count {
+            return Err(VkError::custom(format!("glTF Mesh {} has {} morph targets, but {} weights are set.", json_index, morph_weights.weight_count, weights.len())))
+        }
+
+        if let Some(ref morph) = self.morph {
+            unsafe {
+                let weights_ptr = (morph.weights.info.get_mapped_data() as vkptr<f32>).offset(morph_weights.weight_offset as isize);
+                weights_ptr.copy_from_nonoverlapping(weights.as_ptr(), weights.len());
+            }
+        }
+
+        Ok(())
     }
 
     pub fn discard_by(&self, vma: &mut vma::Allocator) -> VkResult<()> {
@@ -261,6 +507,98 @@ impl MeshResource {
                 .map_err(VkErrorKind::Vma)?;
         }
 
+        if let Some(ref morph) = self.morph {
+
+            for storage in [&morph.vertices, &morph.deltas, &morph.weights].iter() {
+                vma.destroy_buffer(storage.handle, &storage.allocation)
+                    .map_err(VkErrorKind::Vma)?;
+            }
+        }
+
         Ok(())
     }
 }
+
+/// Get the stride of binding 0 in `input`, or 0 if there is no such binding.
+fn vertex_stride(input: &VertexInputSCI) -> vkuint {
+    input.bindings().iter()
+        .find(|binding| binding.binding == 0)
+        .map_or(0, |binding| binding.stride)
+}
+
+/// Make sure the binding 0 of `input` reads vertices of `vertex_stride` bytes.
+fn check_vertex_stride(input: &VertexInputSCI, vertex_stride: vkuint) -> VkResult<()> {
+
+    let vertex_binding = input.bindings().iter()
+        .find(|binding| binding.binding == 0)
+        .ok_or(VkError::custom("Vertex input binding 0 is missing, which is required by the vertex buffer of glTF model."))?;
+
+    if vertex_binding.stride != vertex_stride {
+        return Err(VkError::custom(format!("Vertex input stride mismatch: vertex buffer of glTF model has stride {} bytes, but pipeline declares {} bytes for binding 0.", vertex_stride, vertex_binding.stride)))
+    }
+
+    if vertex_binding.input_rate != vk::VertexInputRate::VERTEX {
+        return Err(VkError::custom("Vertex input binding 0 of glTF model must use vk::VertexInputRate::VERTEX."))
+    }
+
+    Ok(())
+}
+
+/// Generate the barriers transferring the ownership of `buffers` written by transfer commands, where each buffer is read with its access flags on `graphics_family`.
+///
+/// The same barriers are recorded on both queues: as release barriers on `transfer_family` and as acquire barriers on `graphics_family`.
+fn ownership_barriers(buffers: &[(vk::Buffer, vk::AccessFlags)], transfer_family: vkuint, graphics_family: vkuint) -> Vec<vk::BufferMemoryBarrier> {
+
+    buffers.iter().map(|&(buffer, read_access)| {
+        BufferBarrierCI::new(buffer, 0, vk::WHOLE_SIZE)
+            .access_mask(vk::AccessFlags::TRANSFER_WRITE, read_access)
+            .queue_family_index(transfer_family, graphics_family)
+            .into()
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn vertex_input(stride: vkuint, input_rate: vk::VertexInputRate) -> VertexInputSCI {
+        VertexInputSCI::new()
+            .add_binding(vk::VertexInputBindingDescription { binding: 0, stride, input_rate })
+    }
+
+    #[test]
+    fn matching_stride_passes() {
+        let input = vertex_input(24, vk::VertexInputRate::VERTEX);
+        assert_eq!(vertex_stride(&input), 24);
+        assert!(check_vertex_stride(&input, 24).is_ok());
+    }
+
+    #[test]
+    fn stride_mismatch_is_detected() {
+        let input = vertex_input(12, vk::VertexInputRate::VERTEX);
+        assert!(check_vertex_stride(&input, 24).is_err());
+    }
+
+    #[test]
+    fn ownership_moves_from_transfer_to_graphics_family() {
+
+        let buffers = [(vk::Buffer::null(), vk::AccessFlags::VERTEX_ATTRIBUTE_READ), (vk::Buffer::null(), vk::AccessFlags::INDEX_READ)];
+        // a device whose transfer queue(family 2) is separate from graphics queue(family 0).
+        let barriers = ownership_barriers(&buffers, 2, 0);
+
+        assert_eq!(barriers.len(), 2);
+        for (barrier, &(_, read_access)) in barriers.iter().zip(buffers.iter()) {
+            assert_eq!((barrier.src_queue_family_index, barrier.dst_queue_family_index), (2, 0));
+            assert_eq!(barrier.src_access_mask, vk::AccessFlags::TRANSFER_WRITE);
+            assert_eq!(barrier.dst_access_mask, read_access);
+            assert_eq!((barrier.offset, barrier.size), (0, vk::WHOLE_SIZE));
+        }
+    }
+
+    #[test]
+    fn missing_binding_or_instance_rate_is_detected() {
+        assert!(check_vertex_stride(&VertexInputSCI::new(), 24).is_err());
+        assert!(check_vertex_stride(&vertex_input(24, vk::VertexInputRate::INSTANCE), 24).is_err());
+    }
+}
@@ -14,6 +14,8 @@ use std::convert::TryFrom;
 // --------------------------------------------------------------------------------------
 pub struct AttributesData {
 
+    /// the attributes read from each vertices.
+    pub flags: AttributeFlags,
     /// the size of each vertices.
     pub vertex_size: vkbytes,
     /// the vertices attributes data of all primitive.
@@ -30,7 +32,7 @@ impl TryFrom<AttributeFlags> for AttributesData {
         let content = flags.new_attributes()
             .ok_or(VkError::unimplemented("Primitive attributes combination"))?;
 
-        let result = AttributesData { vertex_size, data_content: content };
+        let result = AttributesData { flags, vertex_size, data_content: content };
         Ok(result)
     }
 }
@@ -54,6 +56,79 @@ pub struct AttributeExtendInfo {
 }
 
 
+// --------------------------------------------------------------------------------------
+/// Specify how to generate the normals of primitives which request `AttributeFlags::NORMAL` but contain no normal in glTF.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NormalGeneration {
+    /// Leave the normals as zero vectors.
+    None,
+    /// Use the normal of triangle for its vertices.
+    ///
+    /// The vertices of indexed primitives may be shared by multiple triangles, which can not take the normal of each triangle,
+    /// so loading an indexed primitive without normals returns error.
+    Flat,
+    /// Average the normals of all triangles sharing a vertex, weighted by their areas.
+    Smooth,
+}
+
+impl Default for NormalGeneration {
+
+    fn default() -> NormalGeneration {
+        NormalGeneration::Smooth
+    }
+}
+
+/// Compute the normals of triangles described by `positions` and `indices`(or consecutive vertices if `indices` is None).
+fn generate_normals(positions: &[Vec3F], indices: Option<&[u32]>, method: NormalGeneration) -> Vec<Vec3F> {
+
+    let mut normals = vec![Vec3F::zero(); positions.len()];
+
+    let sequential_indices: Vec<u32>;
+    let indices = match indices {
+        | Some(indices) => indices,
+        | None => {
+            sequential_indices = (0..positions.len() as u32).collect();
+            &sequential_indices
+        },
+    };
+
+    for triangle in indices.chunks(3).filter(|triangle| triangle.len() == 3) {
+
+        let (i0, i1, i2) = (triangle[0] as usize, triangle[1] as usize, triangle[2] as usize);
+        if i0 >= positions.len() || i1 >= positions.len() || i2 >= positions.len() {
+            continue
+        }
+
+        // the length of cross product is twice the area of triangle.
+        let face_normal = (positions[i1] - positions[i0]).cross(positions[i2] - positions[i0]);
+
+        match method {
+            | NormalGeneration::Smooth => {
+                normals[i0] += face_normal;
+                normals[i1] += face_normal;
+                normals[i2] += face_normal;
+            },
+            | NormalGeneration::Flat => {
+                // the vertices are not shared by triangles(see `NormalGeneration::Flat`).
+                normals[i0] = face_normal;
+                normals[i1] = face_normal;
+                normals[i2] = face_normal;
+            },
+            | NormalGeneration::None => {},
+        }
+    }
+
+    for normal in normals.iter_mut() {
+        if normal.magnitude_squared() > 0.0 {
+            *normal = normal.normalized();
+        }
+    }
+
+    normals
+}
+// --------------------------------------------------------------------------------------
+
+
 // --------------------------------------------------------------------------------------
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct AttributeFlags(u32);
@@ -234,6 +309,19 @@ macro_rules! read_attribute {
                     $target.data[i + $origin_length].normal = Vec3F::from(normal);
                 }
             }
+        } else if $source.normal_generation != NormalGeneration::None && $target.data.len() > $origin_length {
+
+            // the normals are missing in glTF, so compute them from positions.
+            let positions: Vec<Vec3F> = $target.data[$origin_length..].iter()
+                .map(|vertex| vertex.position)
+                .collect();
+            let indices: Option<Vec<u32>> = $reader.read_indices()
+                .map(|index_iter| index_iter.into_u32().collect());
+
+            let normals = generate_normals(&positions, indices.as_ref().map(Vec::as_slice), $source.normal_generation);
+            for (i, normal) in normals.into_iter().enumerate() {
+                $target.data[i + $origin_length].normal = normal;
+            }
         }
 
     };
@@ -445,3 +533,42 @@ define_attributes!(Attr_PNTe0, AttrVertex_PNTe0, { position, normal, texcoord_0,
 /// glTF Primitive with all attributes.
 define_attributes!(Attr_All, AttrVertex_Ultimate, { position, normal, tangents, texcoord_0, texcoord_1, color_0, joints_0, weights_0, });
 // --------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn flat_normals_of_unindexed_triangles() {
+
+        // two triangles on the XY plane and the XZ plane.
+        let positions = [
+            Vec3F::new(0.0, 0.0, 0.0), Vec3F::new(1.0, 0.0, 0.0), Vec3F::new(0.0, 1.0, 0.0),
+            Vec3F::new(0.0, 0.0, 0.0), Vec3F::new(0.0, 0.0, 1.0), Vec3F::new(1.0, 0.0, 0.0),
+        ];
+
+        let normals = generate_normals(&positions, None, NormalGeneration::Flat);
+        assert_eq!(&normals[0..3], &[Vec3F::unit_z(); 3]);
+        assert_eq!(&normals[3..6], &[Vec3F::unit_y(); 3]);
+    }
+
+    #[test]
+    fn smooth_normals_average_shared_vertices() {
+
+        // two triangles sharing the edge from vertex 0 to vertex 1, with the same area.
+        let positions = [
+            Vec3F::new(0.0, 0.0, 0.0), Vec3F::new(1.0, 0.0, 0.0),
+            Vec3F::new(0.0, 1.0, 0.0), Vec3F::new(0.0, 0.0, -1.0),
+        ];
+        let indices = [0, 1, 2, 0, 1, 3];
+
+        let normals = generate_normals(&positions, Some(&indices), NormalGeneration::Smooth);
+        let shared = (Vec3F::unit_z() + Vec3F::unit_y()).normalized();
+
+        assert!((normals[0] - shared).magnitude() < 1e-6);
+        assert!((normals[1] - shared).magnitude() < 1e-6);
+        assert_eq!(normals[2], Vec3F::unit_z());
+        assert_eq!(normals[3], Vec3F::unit_y());
+    }
+}
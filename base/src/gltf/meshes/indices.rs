@@ -1,4 +1,6 @@
 
+use ash::vk;
+
 use crate::gltf::asset::GltfDocument;
 use crate::error::{VkResult, VkError};
 
@@ -8,6 +10,8 @@ pub struct IndicesData {
 
     start_index: u32,
     data_content: Vec<vkuint>,
+    /// the maximum index of all primitives, used to choose the index type.
+    max_index: vkuint,
 }
 
 pub struct IndicesExtendInfo {
@@ -25,7 +29,7 @@ impl IndicesData {
 
         let start_index = self.start_index.clone();
 
-        // TODO: Support other integer type.
+        // u8 and u16 indices are widened here, and narrowed to u16 again in `map_data` if all indices fit.
         let index_iter = reader.read_indices()
             .ok_or(VkError::custom("Missing indices property in glTF primitive."))?
             .into_u32()
@@ -36,16 +40,42 @@ impl IndicesData {
             indices_count: index_iter.size_hint().0 as _,
         };
 
-        self.data_content.extend(index_iter);
+        self.append_indices(index_iter);
         self.start_index += indices_range as u32;
 
         Ok(result)
     }
 
+    /// Append the indices of a primitive, and keep the maximum index of all primitives.
+    fn append_indices(&mut self, indices: impl Iterator<Item = vkuint>) {
+
+        let first_new = self.data_content.len();
+        self.data_content.extend(indices);
+
+        // only the new indices are scanned, so the extending of each primitive does not depend on the count of earlier indices.
+        let new_max = self.data_content[first_new..].iter().cloned().max().unwrap_or(0);
+        self.max_index = self.max_index.max(new_max);
+    }
+
+    /// Get the type of indices stored in index buffer.
+    ///
+    /// `vk::IndexType::UINT16` is used if all indices are less than 0xFFFF(which is reserved for primitive restart), otherwise `vk::IndexType::UINT32`.
+    pub fn index_type(&self) -> vk::IndexType {
+        if self.max_index < 0xFFFF {
+            vk::IndexType::UINT16
+        } else {
+            vk::IndexType::UINT32
+        }
+    }
+
     pub fn buffer_size_estimated(&self) -> Option<vkbytes> {
 
         if self.start_index > 0 {
-            let indices_size = (self.data_content.len() * ::std::mem::size_of::<vkuint>()) as vkbytes;
+            let element_size = match self.index_type() {
+                | vk::IndexType::UINT16 => ::std::mem::size_of::<u16>(),
+                | _ => ::std::mem::size_of::<u32>(),
+            };
+            let indices_size = (self.data_content.len() * element_size) as vkbytes;
             Some(indices_size)
         } else {
             None
@@ -54,8 +84,16 @@ impl IndicesData {
 
     pub fn map_data(&self, memory_ptr: vkptr) {
 
-        unsafe {
-            (memory_ptr as vkptr<vkuint>).copy_from(self.data_content.as_ptr(), self.data_content.len());
+        match self.index_type() {
+            | vk::IndexType::UINT16 => {
+                let memory_ptr = memory_ptr as vkptr<u16>;
+                for (i, &index) in self.data_content.iter().enumerate() {
+                    unsafe { *memory_ptr.add(i) = index as u16; }
+                }
+            },
+            | _ => unsafe {
+                (memory_ptr as vkptr<vkuint>).copy_from(self.data_content.as_ptr(), self.data_content.len());
+            },
         }
     }
 }
@@ -66,6 +104,7 @@ impl Default for IndicesData {
         IndicesData {
             start_index: 0,
             data_content: Vec::new(),
+            max_index: 0,
         }
     }
 }
@@ -93,3 +132,46 @@ fn get_index(value: Option<gltf::json::Value>) -> VkResult<u64> {
         .and_then(|v| v.as_u64())
         .ok_or(VkError::custom("Invalid or missing min/max property for indices property."))
 }
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn read_mapped<T: Copy>(indices: &IndicesData) -> Vec<T> {
+
+        let size = indices.data_content.len() * ::std::mem::size_of::<T>();
+        assert_eq!(indices.buffer_size_estimated(), Some(size as vkbytes));
+
+        let mut mapped: Vec<T> = Vec::with_capacity(indices.data_content.len());
+        indices.map_data(mapped.as_mut_ptr() as vkptr);
+        unsafe { mapped.set_len(indices.data_content.len()); }
+        mapped
+    }
+
+    #[test]
+    fn small_indices_are_narrowed_to_u16() {
+
+        let mut indices = IndicesData::default();
+        indices.append_indices(vec![0, 1, 2].into_iter());
+        indices.append_indices(vec![3, 4, 0xFFFE].into_iter());
+        indices.start_index = 0xFFFF;
+
+        assert_eq!(indices.index_type(), vk::IndexType::UINT16);
+        assert_eq!(read_mapped::<u16>(&indices), [0, 1, 2, 3, 4, 0xFFFE]);
+    }
+
+    #[test]
+    fn large_indices_round_trip_as_u32() {
+
+        let mut indices = IndicesData::default();
+        indices.append_indices(vec![0, 0x1_0000, 2].into_iter());
+        // the smaller indices of a later primitive keep the maximum index of earlier primitives.
+        indices.append_indices(vec![3, 4, 5].into_iter());
+        indices.start_index = 0x1_0001;
+
+        assert_eq!(indices.max_index, 0x1_0000);
+        assert_eq!(indices.index_type(), vk::IndexType::UINT32);
+        assert_eq!(read_mapped::<u32>(&indices), [0, 0x1_0000, 2, 3, 4, 5]);
+    }
+}
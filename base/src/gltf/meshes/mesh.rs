@@ -5,10 +5,12 @@ use crate::gltf::asset::{VkglTFModel, ModelRenderParams};
 use crate::gltf::meshes::primitive::Primitive;
 use crate::gltf::meshes::attributes::AttributesData;
 use crate::gltf::meshes::indices::IndicesData;
+use crate::gltf::meshes::morph::{MorphTargetsData, MorphWeightsInfo};
 
 use crate::command::{VkCmdRecorder, IGraphics};
 
 use crate::error::VkResult;
+use crate::vkuint;
 
 // --------------------------------------------------------------------------------------
 /// A wrapper class for mesh level in glTF, containing the render parameters read from glTF file.
@@ -17,29 +19,45 @@ pub struct Mesh {
 
     /// a mesh may contain multiple glTF::Primitive.
     primitives: Vec<Primitive>,
+    /// the range of morph weights of this mesh in morph weights buffer.
+    morph_weights: MorphWeightsInfo,
 }
 
 impl Mesh {
 
-    pub fn from_doc(doc_mesh: gltf::Mesh, source: &GltfDocument, attributes: &mut AttributesData, indices: &mut IndicesData) -> VkResult<Mesh> {
+    pub fn from_doc(doc_mesh: gltf::Mesh, source: &GltfDocument, attributes: &mut AttributesData, indices: &mut IndicesData, morphs: &mut MorphTargetsData) -> VkResult<Mesh> {
+
+        let morph_weights = morphs.extend_mesh(&doc_mesh);
 
         let mesh_iter = doc_mesh.primitives();
         let mut primitives = Vec::with_capacity(mesh_iter.size_hint().0);
 
         for doc_primitive in mesh_iter {
 
-            let primitive = Primitive::from_doc(doc_primitive, source, attributes, indices)?;
+            let primitive = Primitive::from_doc(doc_primitive, source, attributes, indices, morphs, &morph_weights)?;
             primitives.push(primitive);
         }
 
-        let mesh = Mesh { primitives };
+        let mesh = Mesh { primitives, morph_weights };
         Ok(mesh)
     }
 
-    pub fn record_command(&self, recorder: &VkCmdRecorder<IGraphics>, model: &VkglTFModel, params: &ModelRenderParams) {
+    /// Get the range of morph weights of this mesh.
+    #[inline]
+    pub fn morph_weights(&self) -> &MorphWeightsInfo {
+        &self.morph_weights
+    }
+
+    /// Get the primitives of this mesh.
+    #[inline]
+    pub fn primitives(&self) -> &[Primitive] {
+        &self.primitives
+    }
+
+    pub fn record_command(&self, recorder: &VkCmdRecorder<IGraphics>, model: &VkglTFModel, params: &ModelRenderParams, instance_count: vkuint) {
 
         for primitive in self.primitives.iter() {
-            primitive.record_command(recorder, model, params);
+            primitive.record_command(recorder, model, params, instance_count);
         }
     }
 }
@@ -1,9 +1,10 @@
 
-pub use self::asset::{MeshAsset, MeshResource};
-pub use self::attributes::AttributeFlags;
+pub use self::asset::{MeshAsset, MeshResource, INSTANCE_BINDING};
+pub use self::attributes::{AttributeFlags, NormalGeneration};
 
 mod asset;
 mod attributes;
 mod indices;
 mod mesh;
 mod primitive;
+mod morph;
@@ -0,0 +1,172 @@
+
+use crate::gltf::asset::GltfDocument;
+use crate::gltf::meshes::attributes::AttributeExtendInfo;
+use crate::{vkuint, vkbytes, vkfloat, vkptr};
+use crate::{Vec3F, Vec4F};
+
+use std::mem::size_of;
+
+
+// --------------------------------------------------------------------------------------
+/// The morph target data of all primitives, which is read from storage buffers in vertex shader.
+///
+/// The vertex shader can apply the morph targets as follows:
+///
+/// ```ignore
+/// layout (set = 1, binding = 0) readonly buffer MorphVertices { uvec4 morphVertices[]; };
+/// layout (set = 1, binding = 1) readonly buffer MorphDeltas   { vec4  morphDeltas[];   };
+/// layout (set = 1, binding = 2) readonly buffer MorphWeights  { float morphWeights[];  };
+///
+/// // x: the first delta of this vertex, y: the count of targets, z: the stride between targets, w: the first weight of mesh.
+/// uvec4 morph = morphVertices[gl_VertexIndex];
+/// for (uint i = 0; i < morph.y; i++) {
+///     uint delta = morph.x + i * morph.z;
+///     float weight = morphWeights[morph.w + i];
+///     position += weight * morphDeltas[delta * 2].xyz;
+///     normal   += weight * morphDeltas[delta * 2 + 1].xyz;
+/// }
+/// ```
+#[derive(Default)]
+pub struct MorphTargetsData {
+
+    /// the morph parameters of each vertex, whose index is the same as vertex buffer.
+    vertices: Vec<MorphVertex>,
+    /// the position and normal deltas of all targets.
+    deltas: Vec<MorphDelta>,
+    /// the initial weights of all meshes.
+    weights: Vec<vkfloat>,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+struct MorphVertex {
+    delta_offset: vkuint,
+    target_count: vkuint,
+    target_stride: vkuint,
+    weight_offset: vkuint,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct MorphDelta {
+    position: Vec4F,
+    normal  : Vec4F,
+}
+
+impl Default for MorphDelta {
+
+    fn default() -> MorphDelta {
+        MorphDelta {
+            position: Vec4F::zero(),
+            normal  : Vec4F::zero(),
+        }
+    }
+}
+
+/// The range of morph weights of a glTF mesh.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MorphWeightsInfo {
+
+    pub weight_offset: vkuint,
+    pub weight_count : usize,
+}
+
+impl MorphTargetsData {
+
+    /// Reserve the weights of `doc_mesh`, which are shared by all its primitives.
+    pub fn extend_mesh(&mut self, doc_mesh: &gltf::Mesh) -> MorphWeightsInfo {
+
+        let weight_count = doc_mesh.primitives()
+            .map(|doc_primitive| doc_primitive.morph_targets().count())
+            .max().unwrap_or(0);
+
+        let weight_offset = self.weights.len() as vkuint;
+        let default_weights = doc_mesh.weights().unwrap_or(&[]);
+        self.weights.extend((0..weight_count).map(|i| default_weights.get(i).cloned().unwrap_or(0.0)));
+
+        MorphWeightsInfo { weight_offset, weight_count }
+    }
+
+    /// Read the position and normal deltas of the morph targets of `primitive`, whose vertices have been read to `attribute_info`.
+    pub fn extend(&mut self, primitive: &gltf::Primitive, source: &GltfDocument, attribute_info: &AttributeExtendInfo, weights: &MorphWeightsInfo) {
+
+        debug_assert_eq!(self.vertices.len(), attribute_info.first_vertex, "The morph vertices must keep the same order as vertex buffer.");
+
+        let reader = primitive.reader(|b| Some(&source.buffers[b.index()]));
+        let vertex_count = attribute_info.vertex_count;
+        let first_delta = self.deltas.len();
+        let mut target_count = 0;
+
+        for (positions, normals, _tangents) in reader.read_morph_targets() {
+
+            let target_start = self.deltas.len();
+            self.deltas.extend((0..vertex_count).map(|_| MorphDelta::default()));
+
+            if let Some(positions) = positions {
+                for (i, pos) in positions.take(vertex_count).enumerate() {
+                    let mut delta = Vec3F::from(pos);
+                    // the deltas are offsets, so only the linear part of transform applies.
+                    if let Some(ref transform) = source.transform {
+                        delta = transform.mul_direction(delta);
+                    }
+                    self.deltas[target_start + i].position = Vec4F::new(delta.x, delta.y, delta.z, 0.0);
+                }
+            }
+
+            if let Some(normals) = normals {
+                for (i, nor) in normals.take(vertex_count).enumerate() {
+                    self.deltas[target_start + i].normal = Vec4F::new(nor[0], nor[1], nor[2], 0.0);
+                }
+            }
+
+            target_count += 1;
+        }
+
+        // the count of targets must be the same for all primitives of a mesh, but clamp it anyway.
+        let target_count = target_count.min(weights.weight_count);
+
+        self.vertices.extend((0..vertex_count).map(|i| MorphVertex {
+            delta_offset : (first_delta + i) as _,
+            target_count : target_count as _,
+            target_stride: vertex_count as _,
+            weight_offset: weights.weight_offset,
+        }));
+    }
+
+    /// Check if any primitive contains morph targets.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.deltas.is_empty()
+    }
+
+    pub fn vertices_size(&self) -> vkbytes {
+        (self.vertices.len() * size_of::<MorphVertex>()) as vkbytes
+    }
+
+    pub fn deltas_size(&self) -> vkbytes {
+        (self.deltas.len() * size_of::<MorphDelta>()) as vkbytes
+    }
+
+    pub fn weights_size(&self) -> vkbytes {
+        (self.weights.len() * size_of::<vkfloat>()) as vkbytes
+    }
+
+    pub fn map_vertices(&self, memory_ptr: vkptr) {
+        unsafe {
+            (memory_ptr as vkptr<MorphVertex>).copy_from(self.vertices.as_ptr(), self.vertices.len());
+        }
+    }
+
+    pub fn map_deltas(&self, memory_ptr: vkptr) {
+        unsafe {
+            (memory_ptr as vkptr<MorphDelta>).copy_from(self.deltas.as_ptr(), self.deltas.len());
+        }
+    }
+
+    pub fn map_weights(&self, memory_ptr: vkptr) {
+        unsafe {
+            (memory_ptr as vkptr<vkfloat>).copy_from(self.weights.as_ptr(), self.weights.len());
+        }
+    }
+}
+// --------------------------------------------------------------------------------------
@@ -2,8 +2,9 @@
 use crate::gltf::asset::{GltfDocument, ReferenceIndex};
 use crate::gltf::asset::{VkglTFModel, ModelRenderParams};
 
-use crate::gltf::meshes::attributes::AttributesData;
+use crate::gltf::meshes::attributes::{AttributesData, AttributeFlags, NormalGeneration};
 use crate::gltf::meshes::indices::IndicesData;
+use crate::gltf::meshes::morph::{MorphTargetsData, MorphWeightsInfo};
 
 use crate::command::{VkCmdRecorder, IGraphics, CmdGraphicsApi};
 
@@ -23,15 +24,27 @@ pub struct Primitive {
 
 impl Primitive {
 
-    pub fn from_doc(doc_primitive: gltf::Primitive, source: &GltfDocument, attributes: &mut AttributesData, indices: &mut IndicesData) -> VkResult<Primitive> {
+    pub fn from_doc(doc_primitive: gltf::Primitive, source: &GltfDocument, attributes: &mut AttributesData, indices: &mut IndicesData, morphs: &mut MorphTargetsData, weights: &MorphWeightsInfo) -> VkResult<Primitive> {
 
         if doc_primitive.mode() != gltf::mesh::Mode::Triangles {
             // Currently only support triangle topology.
             return Err(VkError::unimplemented(format!("{} render mode.", translate_draw_mode(doc_primitive.mode()))))
         }
 
+        // the flat normals of triangles can not be generated for the vertices shared by indexed triangles.
+        if source.normal_generation == NormalGeneration::Flat && doc_primitive.indices().is_some()
+            && (attributes.flags & AttributeFlags::NORMAL) == AttributeFlags::NORMAL {
+
+            let reader = doc_primitive.reader(|b| Some(&source.buffers[b.index()]));
+            if reader.read_normals().is_none() {
+                return Err(VkError::unimplemented("Flat normal generation for indexed primitive(use NormalGeneration::Smooth instead)"))
+            }
+        }
+
         // read vertices attribute data of glTF::Primitive.
         let attribute_info = attributes.data_content.extend(&doc_primitive, source);
+        // read morph targets of glTF::Primitive.
+        morphs.extend(&doc_primitive, source, &attribute_info, weights);
 
         let render_params = match doc_primitive.indices() {
             | None => {
@@ -59,7 +72,13 @@ impl Primitive {
         Ok(result)
     }
 
-    pub fn record_command(&self, recorder: &VkCmdRecorder<IGraphics>, model: &VkglTFModel, params: &ModelRenderParams) {
+    /// Get the json index of material of this primitive, or None if it uses the default material.
+    #[inline]
+    pub fn material(&self) -> &Option<ReferenceIndex> {
+        &self.material
+    }
+
+    pub fn record_command(&self, recorder: &VkCmdRecorder<IGraphics>, model: &VkglTFModel, params: &ModelRenderParams, instance_count: vkuint) {
 
         if let Some(material_stage) = params.material_stage {
 
@@ -69,10 +88,10 @@ impl Primitive {
 
         match self.params {
             | RenderParams::DrawArray { vertex_count, first_vertex } => {
-                recorder.draw(vertex_count, 1, first_vertex, 0);
+                recorder.draw(vertex_count, instance_count, first_vertex, 0);
             },
             | RenderParams::DrawIndex {  index_count,  first_index } => {
-                recorder.draw_indexed(index_count, 1, first_index, 0, 0);
+                recorder.draw_indexed(index_count, instance_count, first_index, 0, 0);
             },
         }
     }
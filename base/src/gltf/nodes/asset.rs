@@ -11,7 +11,8 @@ use crate::ci::vma::VmaBuffer;
 use crate::context::{VkDevice, VmaResourceDiscardable};
 use crate::command::CmdTransferApi;
 use crate::error::{VkResult, VkError, VkErrorKind};
-use crate::{vkbytes, vkptr};
+use crate::utils::memory::IntegerAlignable;
+use crate::{vkuint, vkbytes, vkptr};
 
 use std::collections::HashMap;
 use std::convert::TryFrom;
@@ -19,6 +20,7 @@ use std::convert::TryFrom;
 pub struct NodeAsset {
 
     attachments: NodeAttachments,
+    attachment_flag: NodeAttachmentFlags,
 
     nodes: AssetElementList<Node>,
 }
@@ -29,6 +31,8 @@ pub struct NodeResource {
     pub(crate) attachment_size_aligned: vkbytes,
     pub(crate) attachment_mapping: HashMap<ReferenceIndex, usize>,
 
+    attachment_flag: NodeAttachmentFlags,
+    min_alignment: vkbytes,
     buffer: VmaBuffer,
 }
 
@@ -39,6 +43,7 @@ impl TryFrom<NodeAttachmentFlags> for NodeAsset {
 
         let result = NodeAsset {
             attachments: NodeAttachments::try_from(flag)?,
+            attachment_flag: flag,
             nodes: Default::default(),
         };
         Ok(result)
@@ -70,9 +75,10 @@ impl NodeAsset {
 
         use crate::ci::buffer::BufferCI;
         use crate::ci::vma::VmaAllocationCI;
-        use crate::utils::memory::IntegerAlignable;
 
-        let attachment_size_aligned = self.attachments.element_size.align_to(min_alignment);
+        // dynamic offsets must be multiple of `minUniformBufferOffsetAlignment`.
+        let min_alignment = min_alignment.max(1);
+        let attachment_size_aligned = attachment_stride(self.attachments.element_size, min_alignment);
         let request_attachments_size = attachment_size_aligned * (self.attachments.data_content.length() as vkbytes);
 
         // allocate dynamic uniform buffer for Node attachments data.
@@ -88,53 +94,15 @@ impl NodeAsset {
             VmaBuffer::from(attachments_allocation)
         };
 
-        // allocate staging buffer.
-        let staging_buffer = {
-
-            let staging_ci = BufferCI::new(request_attachments_size)
-                .usage(vk::BufferUsageFlags::TRANSFER_SRC);
-            let allocate_ci = VmaAllocationCI::new(vma::MemoryUsage::CpuToGpu, vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT);
-            let (staging_buffer, allocation, info) = device.vma.create_buffer(
-                staging_ci.as_ref(), allocate_ci.as_ref())
-                .map_err(VkErrorKind::Vma)?;
-
-            let data_ptr = device.vma.map_memory(&allocation)
-                .map_err(VkErrorKind::Vma)? as vkptr;
-
-            self.attachments.data_content.map_data(data_ptr, info.get_size() as _, min_alignment);
-
-            device.vma.unmap_memory(&allocation)
-                .map_err(VkErrorKind::Vma)?;
-
-            VmaBuffer { handle: staging_buffer, allocation, info }
-        };
-
-        { // copy staging data to target memory.
-            let cmd_recorder = device.get_transfer_recorder();
-
-            let copy_region = vk::BufferCopy {
-                src_offset: 0,
-                dst_offset: 0,
-                size: staging_buffer.info.get_size() as _,
-            };
-
-            cmd_recorder.begin_record()?
-                .copy_buf2buf(staging_buffer.handle, attachments_buffer.handle, &[copy_region])
-                .end_record()?;
-
-            device.flush_transfer(cmd_recorder)?;
-        }
-
-        { // destroy staging buffer.
-            device.vma_discard(staging_buffer)?;
-        }
+        upload_attachments(device, &self.attachments, &attachments_buffer, request_attachments_size, min_alignment)?;
 
         // done.
         let result = NodeResource {
             list  : self.nodes,
             buffer: attachments_buffer,
             attachment_mapping: self.attachments.attachments_mapping,
-            attachment_size_aligned,
+            attachment_flag: self.attachment_flag,
+            attachment_size_aligned, min_alignment,
         };
         Ok(result)
     }
@@ -142,6 +110,53 @@ impl NodeAsset {
 
 impl NodeResource {
 
+    /// Get the stride between the attachments of two nodes in uniform buffer, which is aligned to `minUniformBufferOffsetAlignment`.
+    #[inline]
+    pub fn attachment_stride(&self) -> vkbytes {
+        self.attachment_size_aligned
+    }
+
+    /// Get the dynamic offset of the attachment of Node with `json_index`, used in binding `node_descriptor`.
+    pub fn dynamic_offset(&self, json_index: ReferenceIndex) -> vkuint {
+
+        let attachment_position = self.attachment_mapping.get(&json_index)
+            .expect("Failed to find the attachment of Node.");
+        (self.attachment_size_aligned as vkuint) * (*attachment_position as vkuint)
+    }
+
+    /// Get the Node with `json_index`, or None if no such Node exists.
+    #[inline]
+    pub fn get(&self, json_index: ReferenceIndex) -> Option<&Node> {
+        self.list.try_get(json_index)
+    }
+
+    /// Find the first Node whose name is `name`.
+    pub fn find(&self, name: &str) -> Option<&Node> {
+        self.list.iter().find(|node| node.name() == Some(name))
+    }
+
+    /// Iterate all the Nodes in this model, in the order of their json index.
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = &Node> {
+        self.list.iter()
+    }
+
+    /// Recalculate the transforms of all Nodes in `scene`, and upload them to the attachment buffer.
+    ///
+    /// The uniform buffer is overwritten by transfer queue immediately,
+    /// so make sure that device is not reading the buffer anymore(etc. by waiting the device idle).
+    pub(crate) fn update_attachments(&self, device: &mut VkDevice, scene: &Scene) -> VkResult<()> {
+
+        let mut attachments = NodeAttachments::try_from(self.attachment_flag)?;
+        scene.read_node_attachment(&self.list, &mut attachments);
+
+        // the traversal order of Nodes is unchanged, so the attachment positions stay the same.
+        debug_assert_eq!(attachments.attachments_mapping, self.attachment_mapping);
+
+        let attachments_size = self.attachment_size_aligned * (attachments.data_content.length() as vkbytes);
+        upload_attachments(device, &attachments, &self.buffer, attachments_size, self.min_alignment)
+    }
+
     pub fn node_descriptor(&self) -> vk::DescriptorBufferInfo {
 
         vk::DescriptorBufferInfo {
@@ -152,6 +167,63 @@ impl NodeResource {
     }
 }
 
+/// Round up the size of node attachment to `min_alignment`, which is a power of two(the spec guarantees it for `minUniformBufferOffsetAlignment`).
+fn attachment_stride(element_size: vkbytes, min_alignment: vkbytes) -> vkbytes {
+
+    debug_assert!(min_alignment.is_power_of_two(), "minUniformBufferOffsetAlignment must be a power of two.");
+    element_size.align_to(min_alignment)
+}
+
+/// Write the attachment data to a staging buffer, and copy it to `dst_buffer`.
+fn upload_attachments(device: &mut VkDevice, attachments: &NodeAttachments, dst_buffer: &VmaBuffer, attachments_size: vkbytes, min_alignment: vkbytes) -> VkResult<()> {
+
+    use crate::ci::buffer::BufferCI;
+    use crate::ci::vma::VmaAllocationCI;
+
+    // allocate staging buffer.
+    let staging_buffer = {
+
+        let staging_ci = BufferCI::new(attachments_size)
+            .usage(vk::BufferUsageFlags::TRANSFER_SRC);
+        let allocate_ci = VmaAllocationCI::new(vma::MemoryUsage::CpuToGpu, vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT);
+        let (staging_buffer, allocation, info) = device.vma.create_buffer(
+            staging_ci.as_ref(), allocate_ci.as_ref())
+            .map_err(VkErrorKind::Vma)?;
+
+        let data_ptr = device.vma.map_memory(&allocation)
+            .map_err(VkErrorKind::Vma)? as vkptr;
+
+        attachments.data_content.map_data(data_ptr, info.get_size() as _, min_alignment);
+
+        device.vma.unmap_memory(&allocation)
+            .map_err(VkErrorKind::Vma)?;
+
+        VmaBuffer { handle: staging_buffer, allocation, info }
+    };
+
+    { // copy staging data to target memory.
+        let cmd_recorder = device.get_transfer_recorder();
+
+        let copy_region = vk::BufferCopy {
+            src_offset: 0,
+            dst_offset: 0,
+            size: staging_buffer.info.get_size() as _,
+        };
+
+        cmd_recorder.begin_record()?
+            .copy_buf2buf(staging_buffer.handle, dst_buffer.handle, &[copy_region])
+            .end_record()?;
+
+        device.flush_transfer(cmd_recorder)?;
+    }
+
+    { // destroy staging buffer.
+        device.vma_discard(staging_buffer)?;
+    }
+
+    Ok(())
+}
+
 impl VmaResourceDiscardable for NodeResource {
 
     fn discard_by(self, vma: &mut vma::Allocator) -> VkResult<()> {
@@ -160,3 +232,26 @@ impl VmaResourceDiscardable for NodeResource {
         Ok(())
     }
 }
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn attachment_stride_is_rounded_up_to_alignment() {
+
+        assert_eq!(attachment_stride(64, 256), 256);
+        assert_eq!(attachment_stride(256, 256), 256);
+        assert_eq!(attachment_stride(260, 256), 512);
+        assert_eq!(attachment_stride(72, 1), 72);
+
+        // the common values of minUniformBufferOffsetAlignment.
+        for &min_alignment in [1, 16, 32, 64, 256].iter() {
+            let stride = attachment_stride(80, min_alignment);
+            assert_eq!(stride % min_alignment, 0);
+            assert!(stride >= 80 && stride < 80 + min_alignment);
+        }
+    }
+}
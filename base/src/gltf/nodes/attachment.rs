@@ -171,7 +171,8 @@ impl NodeAttachmentFlags {
     fn element_size(&self) -> Option<vkbytes> {
         use std::mem::size_of;
         match *self {
-            | NodeAttachmentFlags::NAF_T => Some(size_of::<NA_T>() as _),
+            // the size of uniform data of each node, rather than the container of all nodes.
+            | NodeAttachmentFlags::NAF_T => Some(size_of::<NAttachment_T>() as _),
             | _ => None,
         }
     }
@@ -12,7 +12,7 @@ use crate::{vkuint, Mat4F};
 pub struct Node {
 
     /// the name property of current node.
-    _name: Option<String>,
+    name: Option<String>,
     /// the json index of current node.
     json_index: ReferenceIndex,
     /// the json index of glTF::Mesh.
@@ -42,10 +42,50 @@ impl Node {
             .map(|doc_node| doc_node.index())
             .collect();
 
-        let result = Node { _name: name, json_index, local_mesh, children, local_transform };
+        let result = Node { name, json_index, local_mesh, children, local_transform };
         Ok(result)
     }
 
+    /// Get the name property of this Node.
+    #[inline]
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_ref().map(String::as_str)
+    }
+
+    /// Get the json index of this Node, which identifies this Node in `VkglTFModel`.
+    #[inline]
+    pub fn json_index(&self) -> ReferenceIndex {
+        self.json_index
+    }
+
+    /// Get the json indices of the children of this Node.
+    #[inline]
+    pub fn children(&self) -> &[ReferenceIndex] {
+        &self.children
+    }
+
+    /// Check if this Node references a mesh to render.
+    #[inline]
+    pub fn has_mesh(&self) -> bool {
+        self.local_mesh.is_some()
+    }
+
+    /// Get the json index of the mesh rendered by this Node.
+    #[inline]
+    pub fn local_mesh(&self) -> Option<ReferenceIndex> {
+        self.local_mesh
+    }
+
+    /// Get the transform of this Node relative to its parent.
+    #[inline]
+    pub fn local_transform(&self) -> &Mat4F {
+        &self.local_transform
+    }
+
+    pub(crate) fn set_local_transform(&mut self, transform: Mat4F) {
+        self.local_transform = transform;
+    }
+
     pub fn read_attachment(&self, nodes: &AssetElementList<Node>, attachments: &mut NodeAttachments, parent_transform: &Mat4F) {
 
         // apply parent node's transformation to current node level.
@@ -67,22 +107,22 @@ impl Node {
         }
     }
 
-    pub fn record_command(&self, recorder: &VkCmdRecorder<IGraphics>, model: &VkglTFModel, params: &ModelRenderParams) {
+    pub fn record_command(&self, recorder: &VkCmdRecorder<IGraphics>, model: &VkglTFModel, params: &ModelRenderParams, instance_count: vkuint) {
 
         if let Some(local_mesh) = self.local_mesh {
 
             // calculate the dynamic offset.
-            let dyn_offset = (model.nodes.attachment_size_aligned as vkuint) * (model.nodes.attachment_mapping.get(&self.json_index).unwrap().clone() as vkuint);
+            let dyn_offset = model.nodes.dynamic_offset(self.json_index);
             // bind descriptors with dynamic offset for node attachment.
             recorder.bind_descriptor_sets(params.pipeline_layout, 0, &[params.descriptor_set], &[dyn_offset]);
 
             let mesh = model.meshes.list.get(local_mesh);
-            mesh.record_command(recorder, model, params);
+            mesh.record_command(recorder, model, params, instance_count);
         }
 
         for child_node_index in self.children.iter().cloned() {
             let child_node = model.nodes.list.get(child_node_index);
-            child_node.record_command(recorder, model, params);
+            child_node.record_command(recorder, model, params, instance_count);
         }
     }
 }
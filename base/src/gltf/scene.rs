@@ -2,8 +2,11 @@
 use crate::gltf::asset::{ReferenceIndex, AssetElementList};
 use crate::gltf::asset::{VkglTFModel, ModelRenderParams};
 use crate::gltf::nodes::{Node, NodeAttachments};
-use crate::command::{VkCmdRecorder, IGraphics};
-use crate::Mat4F;
+use crate::ci::pipeline::{PipelineVariantKey, PipelineVariantCache};
+use crate::command::{VkCmdRecorder, IGraphics, CmdGraphicsApi};
+use crate::{vkuint, Mat4F};
+
+use std::collections::BTreeMap;
 
 
 pub struct Scene {
@@ -31,13 +34,139 @@ impl Scene {
         }
     }
 
-    pub fn record_command(&self, recorder: &VkCmdRecorder<IGraphics>, model: &VkglTFModel, params: &ModelRenderParams) {
+    /// Iterate the Nodes of this scene in depth-first order.
+    pub fn traverse<'a>(&'a self, nodes: &'a AssetElementList<Node>) -> SceneNodes<'a> {
+
+        // push the root nodes in reverse order, so that they are visited in their original order.
+        let stack = self.nodes.iter().rev()
+            .map(|&json_index| (json_index, None, 0))
+            .collect();
+
+        SceneNodes { nodes, stack }
+    }
+
+    pub fn record_command(&self, recorder: &VkCmdRecorder<IGraphics>, model: &VkglTFModel, params: &ModelRenderParams, instance_count: vkuint) {
 
         for node_json_index in self.nodes.iter().cloned() {
 
             let node = model.nodes.list.get(node_json_index);
-            node.record_command(recorder, model, params);
+            node.record_command(recorder, model, params, instance_count);
+        }
+    }
+
+    /// Record the primitives of this scene grouped by the pipeline variants of their materials, binding each pipeline of `variants` once.
+    ///
+    /// The scene is traversed only once to bucket the primitives, and the primitives of each variant are recorded in depth-first order.
+    /// The variants are recorded in the order of `PipelineVariantKey`, so that opaque primitives are drawn before masked and blended ones.
+    pub fn record_command_by_variants(&self, recorder: &VkCmdRecorder<IGraphics>, model: &VkglTFModel, params: &ModelRenderParams, variants: &PipelineVariantCache) {
+
+        let draws = self.traverse(&model.nodes.list)
+            .filter_map(|visit| visit.node.local_mesh().map(|local_mesh| (visit.node.json_index(), local_mesh)))
+            .flat_map(move |(node_json_index, local_mesh)| {
+                model.meshes.list.get(local_mesh).primitives().iter()
+                    .map(move |primitive| (node_json_index, primitive))
+            })
+            .map(|(node_json_index, primitive)| (model.materials.get_material_variant(primitive.material()), (node_json_index, primitive)));
+        let buckets = bucket_by_variant(draws);
+
+        for (key, variant_draws) in buckets.iter() {
+
+            // skip the primitives whose variant has not been built.
+            let pipeline = match variants.get(key) {
+                | Some(pipeline) => pipeline,
+                | None => continue,
+            };
+
+            recorder.bind_pipeline(pipeline);
+
+            // the primitives of the same Node are adjacent, so the descriptors are only rebound when the Node changes.
+            let mut bound_node = None;
+            for &(node_json_index, primitive) in variant_draws.iter() {
+
+                if bound_node != Some(node_json_index) {
+                    let dyn_offset = model.nodes.dynamic_offset(node_json_index);
+                    recorder.bind_descriptor_sets(params.pipeline_layout, 0, &[params.descriptor_set], &[dyn_offset]);
+                    bound_node = Some(node_json_index);
+                }
+
+                primitive.record_command(recorder, model, params, 1);
+            }
+        }
+    }
+}
+
+/// A Node visited in the traversal of glTF scene hierarchy.
+pub struct NodeVisit<'a> {
+
+    pub node: &'a Node,
+    /// the json index of parent Node, or None if this is a root Node of scene.
+    pub parent: Option<ReferenceIndex>,
+    /// the count of ancestors of this Node.
+    pub depth: usize,
+}
+
+/// The depth-first iterator over the Nodes of glTF scene, created by `VkglTFModel::scene_nodes`.
+pub struct SceneNodes<'a> {
+
+    nodes: &'a AssetElementList<Node>,
+    /// the json index, parent and depth of the Nodes waiting to be visited.
+    stack: Vec<(ReferenceIndex, Option<ReferenceIndex>, usize)>,
+}
+
+impl<'a> Iterator for SceneNodes<'a> {
+    type Item = NodeVisit<'a>;
+
+    fn next(&mut self) -> Option<NodeVisit<'a>> {
+
+        let (json_index, parent, depth) = self.stack.pop()?;
+        let node = self.nodes.get(json_index);
+
+        for &child_index in node.children().iter().rev() {
+            self.stack.push((child_index, Some(json_index), depth + 1));
         }
+
+        Some(NodeVisit { node, parent, depth })
     }
 }
 
+/// Group `items` by their pipeline variants, keeping the order of items in each group.
+///
+/// The groups are sorted by their variants.
+fn bucket_by_variant<T>(items: impl Iterator<Item = (PipelineVariantKey, T)>) -> BTreeMap<PipelineVariantKey, Vec<T>> {
+
+    let mut buckets = BTreeMap::new();
+    for (key, item) in items {
+        buckets.entry(key).or_insert_with(Vec::new).push(item);
+    }
+    buckets
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::ci::pipeline::VariantAlphaMode;
+    use ash::vk;
+
+    #[test]
+    fn items_are_bucketed_by_variant_in_order() {
+
+        let opaque = PipelineVariantKey::default();
+        let blend = PipelineVariantKey { alpha_mode: VariantAlphaMode::Blend, is_depth_write: false, ..opaque };
+        let mask = PipelineVariantKey { alpha_mode: VariantAlphaMode::Mask, ..opaque };
+        let double_sided = PipelineVariantKey { cull_mode: vk::CullModeFlags::NONE, ..opaque };
+
+        let items = vec![(blend, 0), (opaque, 1), (mask, 2), (opaque, 3), (double_sided, 4), (blend, 5)];
+        let buckets = bucket_by_variant(items.into_iter());
+
+        assert_eq!(buckets.len(), 4);
+        assert_eq!(buckets[&opaque], vec![1, 3]);
+        assert_eq!(buckets[&blend], vec![0, 5]);
+        assert_eq!(buckets[&mask], vec![2]);
+        assert_eq!(buckets[&double_sided], vec![4]);
+
+        // the opaque variants are recorded first, and the blended variant is recorded last.
+        let order: Vec<PipelineVariantKey> = buckets.keys().cloned().collect();
+        assert_eq!(order, vec![double_sided, opaque, mask, blend]);
+    }
+}
@@ -6,6 +6,10 @@ use crate::utils::fps::FpsCounter;
 const SIMULTANEOUS_KEY_COUNT: usize = 12;
 
 
+/// Collect the window events and the input of user between frames.
+///
+/// Focus and minimization are tracked for each window separately, since the windows added by `WindowContext::add_window`
+/// share one event loop. The input(keys and cursor) is shared by all windows.
 pub struct EventController {
 
     pub key: KeyHeap,
@@ -15,11 +19,45 @@ pub struct EventController {
     action: FrameAction,
     is_toggle_key: bool,
     is_toggle_cursor: bool,
+
+    /// the id of each window, indexed by window index.
+    window_ids: Vec<winit::WindowId>,
+    /// the state of each window, indexed by window index.
+    windows: Vec<WindowState>,
+    /// whether any window had input focus at the end of last frame.
+    was_focused: bool,
+}
+
+/// The state of one window, which is updated by its own events.
+#[derive(Debug, Clone, Copy)]
+struct WindowState {
+    /// whether the window has input focus.
+    is_focused: bool,
+    /// whether the window is minimized(its inner size is zero).
+    is_minimized: bool,
+}
+
+impl Default for WindowState {
+
+    fn default() -> WindowState {
+        WindowState { is_focused: true, is_minimized: false }
+    }
 }
 
 impl Default for EventController {
 
+    /// Create the controller for a single window, where the events of any window are treated as the events of window 0.
     fn default() -> EventController {
+        EventController::new(Vec::new())
+    }
+}
+
+impl EventController {
+
+    /// Create the controller for the windows of `window_ids`, where the window index is the position in `window_ids`.
+    pub(crate) fn new(window_ids: Vec<winit::WindowId>) -> EventController {
+
+        let window_count = window_ids.len().max(1);
 
         EventController {
             key: Default::default(),
@@ -29,11 +67,22 @@ impl Default for EventController {
             action: FrameAction::Rendering,
             is_toggle_key: false,
             is_toggle_cursor: false,
+
+            window_ids,
+            windows: vec![WindowState::default(); window_count],
+            was_focused: true,
         }
     }
-}
 
-impl EventController {
+    /// Get the index of window from its id, or None if the window was not registered.
+    fn window_index(&self, window_id: winit::WindowId) -> Option<usize> {
+
+        if self.window_ids.is_empty() {
+            Some(0)
+        } else {
+            self.window_ids.iter().position(|&id| id == window_id)
+        }
+    }
 
     pub(crate) fn record_event(&mut self, event: winit::Event) {
 
@@ -47,7 +96,13 @@ impl EventController {
                     | _ => (),
                 }
             },
-            | winit::Event::WindowEvent { event, .. } => {
+            | winit::Event::WindowEvent { window_id, event } => {
+
+                let window_index = match self.window_index(window_id) {
+                    | Some(index) => index,
+                    | None => return,
+                };
+
                 match event {
                     | winit::WindowEvent::KeyboardInput { input, .. } => {
                         if let Some(code) = input.virtual_keycode {
@@ -62,10 +117,21 @@ impl EventController {
                             }
                         }
                     },
-                    | winit::WindowEvent::Resized(_) => {
+                    | winit::WindowEvent::Resized(size) => {
 
                         // TODO: When window was created, Resized event will be toggled.
                         // self.action = FrameAction::SwapchainRecreate;
+                        self.windows[window_index].is_minimized = size.width == 0.0 || size.height == 0.0;
+                    },
+                    | winit::WindowEvent::Focused(is_focused) => {
+
+                        self.windows[window_index].is_focused = is_focused;
+
+                        if is_focused == false {
+                            // the release events of pressing keys will not be received after focus lost.
+                            self.key.clear();
+                            self.cursor.record_motion(0.0, 0.0);
+                        }
                     },
                     | winit::WindowEvent::CloseRequested => {
                         self.action = FrameAction::Terminal;
@@ -85,11 +151,49 @@ impl EventController {
         self.is_toggle_cursor
     }
 
+    /// Check if any window of program has input focus.
+    #[inline]
+    pub fn is_focused(&self) -> bool {
+        self.windows.iter().any(|window| window.is_focused)
+    }
+
+    /// Check if the window at `window_index` has input focus.
+    #[inline]
+    pub fn is_window_focused(&self, window_index: usize) -> bool {
+        self.windows[window_index].is_focused
+    }
+
+    /// Check if all windows of program are minimized.
+    #[inline]
+    pub fn is_minimized(&self) -> bool {
+        self.windows.iter().all(|window| window.is_minimized)
+    }
+
+    /// Check if the window at `window_index` is minimized.
+    #[inline]
+    pub fn is_window_minimized(&self, window_index: usize) -> bool {
+        self.windows[window_index].is_minimized
+    }
+
+    /// Check if the rendering should be paused, which happens when no window has focus or all windows are minimized.
+    ///
+    /// A minimized window is skipped in rendering even if the others are not paused(see `is_window_minimized`).
+    #[inline]
+    pub fn is_paused(&self) -> bool {
+        self.is_focused() == false || self.is_minimized()
+    }
+
+    /// Check if the focus of program has changed in current frame, which ignores the focus moving between its windows.
+    pub(crate) fn is_focus_toggled(&self) -> bool {
+        self.was_focused != self.is_focused()
+    }
+
     pub(crate) fn tick_frame(&mut self) {
 
         self.fps_counter.tick_frame();
         self.is_toggle_key = false;
         self.is_toggle_cursor = false;
+        self.was_focused = self.is_focused();
         self.action = FrameAction::Rendering;
     }
 
@@ -128,6 +232,10 @@ impl KeyHeap {
         }
     }
 
+    fn clear(&mut self) {
+        self.keys.clear();
+    }
+
     fn key_release(&mut self, code: winit::VirtualKeyCode) {
 
         if let Some(index) = self.keys.iter().position(|&key_code| key_code == code) {
@@ -174,3 +282,46 @@ impl CursorMotion {
         (self.delta_x, self.delta_y)
     }
 }
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn controller(window_count: usize) -> EventController {
+        let mut controller = EventController::default();
+        controller.windows = vec![WindowState::default(); window_count];
+        controller
+    }
+
+    #[test]
+    fn one_minimized_window_does_not_pause() {
+
+        let mut controller = controller(2);
+        controller.windows[1].is_minimized = true;
+
+        assert!(controller.is_window_minimized(1));
+        assert!(controller.is_window_minimized(0) == false);
+        assert!(controller.is_paused() == false);
+
+        controller.windows[0].is_minimized = true;
+        assert!(controller.is_paused());
+    }
+
+    #[test]
+    fn focus_moving_between_windows_is_not_toggled() {
+
+        let mut controller = controller(2);
+        controller.windows[1].is_focused = false;
+        controller.tick_frame();
+
+        // focus moves from window 0 to window 1.
+        controller.windows[0].is_focused = false;
+        controller.windows[1].is_focused = true;
+        assert!(controller.is_focus_toggled() == false);
+
+        controller.windows[1].is_focused = false;
+        assert!(controller.is_focus_toggled());
+        assert!(controller.is_paused());
+    }
+}
@@ -2,9 +2,9 @@
 pub use self::workflow::{RenderWorkflow, WindowContext, WindowConfig};
 pub use self::workflow::ProcPipeline;
 pub use self::error::{VkResult, VkError, VkErrorKind};
-pub use self::utils::frame::FrameAction;
+pub use self::utils::frame::{FrameAction, FrameResource};
 pub use self::input::EventController;
-pub use self::camera::FlightCamera;
+pub use self::camera::{FlightCamera, CameraState, DepthProjection};
 
 pub mod context;
 pub mod ci;
@@ -13,7 +13,10 @@ pub mod command;
 pub mod platforms;
 pub mod gltf;
 pub mod texture;
+pub mod shadow;
+pub mod bloom;
 pub mod ui;
+pub mod debug;
 
 mod error;
 mod camera;
@@ -0,0 +1,228 @@
+//! Helpers to render the scene depth from the point of view of a light(shadow mapping).
+
+use ash::vk;
+
+use crate::ci::vma::{VmaImage, VmaAllocationCI};
+use crate::ci::image::{ImageCI, ImageViewCI, SamplerCI};
+use crate::ci::pipeline::{RenderPassCI, RenderPassBI, RenderPass, AttachmentDescCI, SubpassDescCI, SubpassDependencyCI, FramebufferCI};
+use crate::ci::pipeline::{GraphicsPipelineCI, RasterizationSCI, DepthStencilSCI, ColorBlendSCI, ViewportSCI, DynamicSCI};
+use crate::ci::VkObjectBuildableCI;
+
+use crate::command::{VkCmdRecorder, IGraphics, CmdGraphicsApi};
+use crate::context::VkDevice;
+use crate::gltf::{VkglTFModel, ModelRenderParams};
+use crate::camera::FlightCamera;
+
+use crate::{VkResult, VkErrorKind};
+use crate::{vkfloat, Mat4F};
+
+
+/// The configuration of a `ShadowMapPass`.
+#[derive(Debug, Clone)]
+pub struct ShadowMapConfig {
+
+    /// `dimension` is the resolution of the shadow map.
+    pub dimension: vk::Extent2D,
+    /// `depth_format` is the format of the shadow map, which must support both depth attachment and sampled image usage.
+    pub depth_format: vk::Format,
+    /// `depth_bias_constant` is the constant depth value added to each fragment, used to avoid shadow acne.
+    pub depth_bias_constant: vkfloat,
+    /// `depth_bias_slope` is the depth bias factor applied to the slope of fragment.
+    pub depth_bias_slope: vkfloat,
+}
+
+impl Default for ShadowMapConfig {
+
+    fn default() -> ShadowMapConfig {
+        ShadowMapConfig {
+            dimension: vk::Extent2D { width: 2048, height: 2048 },
+            // 16 bits depth is enough for most scenes and it's supported by all implementations.
+            depth_format: vk::Format::D16_UNORM,
+            depth_bias_constant: 1.25,
+            depth_bias_slope   : 1.75,
+        }
+    }
+}
+
+/// A depth-only render pass which renders the depth of scene into a sampled image(shadow map).
+///
+/// Use `ShadowMapPass::descriptor` to bind the shadow map as `vk::DescriptorType::COMBINED_IMAGE_SAMPLER` in main pass.
+pub struct ShadowMapPass {
+
+    pub config: ShadowMapConfig,
+
+    pub image: VmaImage,
+    pub view : vk::ImageView,
+    /// the sampler with depth comparison enabled, which can be declared as `sampler2DShadow` in shader.
+    pub sampler: vk::Sampler,
+
+    pub render_pass: RenderPass,
+    pub framebuffer: vk::Framebuffer,
+
+    pub descriptor: vk::DescriptorImageInfo,
+}
+
+impl ShadowMapPass {
+
+    pub fn new(device: &mut VkDevice, config: ShadowMapConfig) -> VkResult<ShadowMapPass> {
+
+        let image = {
+            let depth_ci = ImageCI::new_2d(config.depth_format, config.dimension)
+                .usages(vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT | vk::ImageUsageFlags::SAMPLED);
+            let allocation_ci = VmaAllocationCI::new(vma::MemoryUsage::GpuOnly, vk::MemoryPropertyFlags::DEVICE_LOCAL);
+            let depth_allocation = device.vma.create_image(depth_ci.as_ref(), allocation_ci.as_ref())
+                .map_err(VkErrorKind::Vma)?;
+            VmaImage::from(depth_allocation)
+        };
+
+        let view = ImageViewCI::new(image.handle, vk::ImageViewType::TYPE_2D, config.depth_format)
+            .sub_range(vk::ImageSubresourceRange {
+                aspect_mask: vk::ImageAspectFlags::DEPTH,
+                base_mip_level: 0,
+                level_count   : 1,
+                base_array_layer: 0,
+                layer_count     : 1,
+            }).build(device)?;
+
+        // the area outside the shadow map is considered to be lit.
+        let sampler = SamplerCI::new()
+            .address(vk::SamplerAddressMode::CLAMP_TO_EDGE, vk::SamplerAddressMode::CLAMP_TO_EDGE, vk::SamplerAddressMode::CLAMP_TO_EDGE)
+            .border_color(vk::BorderColor::FLOAT_OPAQUE_WHITE)
+            .compare_op(Some(vk::CompareOp::LESS_OR_EQUAL))
+            .lod(0.0, 0.0, 1.0)
+            .build(device)?;
+
+        let render_pass = setup_renderpass(device, config.depth_format)?;
+
+        let framebuffer = FramebufferCI::new_2d(render_pass.handle, config.dimension)
+            .add_attachment(view)
+            .build(device)?;
+
+        let descriptor = vk::DescriptorImageInfo {
+            sampler,
+            image_view: view,
+            image_layout: vk::ImageLayout::DEPTH_STENCIL_READ_ONLY_OPTIMAL,
+        };
+
+        let result = ShadowMapPass { config, image, view, sampler, render_pass, framebuffer, descriptor };
+        Ok(result)
+    }
+
+    /// Generate the matrix transforming world space to the clip space of `light`.
+    ///
+    /// It is usually transferred to the shaders of both shadow pass and main pass.
+    pub fn light_space_matrix(light: &FlightCamera) -> Mat4F {
+        light.proj_matrix() * light.view_matrix()
+    }
+
+    /// Create the base `GraphicsPipelineCI` for depth-only rendering into this shadow map.
+    ///
+    /// Only the vertex shader is required for the pipeline. Viewport, scissor and depth bias are set as dynamic states.
+    pub fn pipeline_ci<'a>(&self, pipeline_layout: vk::PipelineLayout) -> GraphicsPipelineCI<'a> {
+
+        let viewport_state = ViewportSCI::new()
+            .add_viewport(vk::Viewport::default())
+            .add_scissor(vk::Rect2D::default());
+
+        // the depth bias is set in `ShadowMapPass::record_command`.
+        let rasterization_state = RasterizationSCI::new()
+            .polygon(vk::PolygonMode::FILL)
+            .cull_face(vk::CullModeFlags::NONE, vk::FrontFace::CLOCKWISE)
+            .depth_bias(0.0, 0.0, 0.0);
+
+        let depth_stencil_state = DepthStencilSCI::new()
+            .depth_test(true, true, vk::CompareOp::LESS_OR_EQUAL);
+
+        let dynamic_state = DynamicSCI::new()
+            .add_dynamic(vk::DynamicState::VIEWPORT)
+            .add_dynamic(vk::DynamicState::SCISSOR)
+            .add_dynamic(vk::DynamicState::DEPTH_BIAS);
+
+        let mut pipeline_ci = GraphicsPipelineCI::new(self.render_pass.handle, pipeline_layout);
+        pipeline_ci.set_viewport(viewport_state);
+        pipeline_ci.set_rasterization(rasterization_state);
+        pipeline_ci.set_depth_stencil(depth_stencil_state);
+        // no color attachment in shadow pass.
+        pipeline_ci.set_color_blend(ColorBlendSCI::new());
+        pipeline_ci.set_dynamic(dynamic_state);
+
+        pipeline_ci
+    }
+
+    /// Record the commands rendering the depth of `model` into shadow map.
+    ///
+    /// `pipeline` should be created from `ShadowMapPass::pipeline_ci`,
+    /// and the descriptor set in `params` should contain the light space matrix(see `ShadowMapPass::light_space_matrix`).
+    ///
+    /// This must be recorded outside any render pass, before the main pass sampling the shadow map.
+    pub fn record_command(&self, recorder: &VkCmdRecorder<IGraphics>, pipeline: vk::Pipeline, model: &VkglTFModel, params: &ModelRenderParams) {
+
+        let dimension = self.config.dimension;
+
+        let viewport = vk::Viewport {
+            x: 0.0, y: 0.0,
+            width: dimension.width as f32, height: dimension.height as f32,
+            min_depth: 0.0, max_depth: 1.0,
+        };
+        let scissor = vk::Rect2D {
+            extent: dimension,
+            offset: vk::Offset2D { x: 0, y: 0 },
+        };
+
+        let render_pass_bi = RenderPassBI::with_pass(self.render_pass, self.framebuffer)
+            .render_extent(dimension)
+            .add_clear_value(vk::ClearValue { depth_stencil: vk::ClearDepthStencilValue { depth: 1.0, stencil: 0 } });
+
+        recorder
+            .begin_render_pass(render_pass_bi)
+            .set_viewport(0, &[viewport])
+            .set_scissor(0, &[scissor])
+            .set_depth_bias(self.config.depth_bias_constant, 0.0, self.config.depth_bias_slope)
+            .bind_pipeline(pipeline);
+
+        model.record_command(recorder, params);
+
+        recorder.end_render_pass();
+    }
+
+    pub fn discard_by(self, device: &mut VkDevice) -> VkResult<()> {
+
+        device.discard(self.framebuffer);
+        device.discard(self.render_pass);
+        device.discard(self.sampler);
+        device.discard(self.view);
+        device.vma_discard(self.image)
+    }
+}
+
+fn setup_renderpass(device: &VkDevice, depth_format: vk::Format) -> VkResult<RenderPass> {
+
+    // the shadow map will be read in fragment shader after the render pass.
+    let depth_attachment = AttachmentDescCI::new(depth_format)
+        .op(vk::AttachmentLoadOp::CLEAR, vk::AttachmentStoreOp::STORE)
+        .layout(vk::ImageLayout::UNDEFINED, vk::ImageLayout::DEPTH_STENCIL_READ_ONLY_OPTIMAL);
+
+    let subpass_description = SubpassDescCI::new(vk::PipelineBindPoint::GRAPHICS)
+        .set_depth_stencil_attachment(0, vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL); // Attachment 0 is depth.
+
+    // make sure the shadow map sampled in previous frame is no longer in use before writing it.
+    let dependency0 = SubpassDependencyCI::new(vk::SUBPASS_EXTERNAL, 0)
+        .stage_mask(vk::PipelineStageFlags::FRAGMENT_SHADER, vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS)
+        .access_mask(vk::AccessFlags::SHADER_READ, vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE)
+        .flags(vk::DependencyFlags::BY_REGION);
+
+    // make sure the depth writes have finished before sampling the shadow map.
+    let dependency1 = SubpassDependencyCI::new(0, vk::SUBPASS_EXTERNAL)
+        .stage_mask(vk::PipelineStageFlags::LATE_FRAGMENT_TESTS, vk::PipelineStageFlags::FRAGMENT_SHADER)
+        .access_mask(vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE, vk::AccessFlags::SHADER_READ)
+        .flags(vk::DependencyFlags::BY_REGION);
+
+    let render_pass = RenderPassCI::new()
+        .add_attachment(depth_attachment)
+        .add_subpass(subpass_description)
+        .add_dependency(dependency0)
+        .add_dependency(dependency1)
+        .build_with_attachments(device)?;
+
+    Ok(render_pass)
+}
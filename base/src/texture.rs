@@ -1,23 +1,78 @@
 
+mod ktx2;
+
 use ash::vk;
 
 use gli::GliTexture;
 
 use crate::ci::vma::{VmaImage, VmaBuffer, VmaAllocationCI};
-use crate::ci::image::{ImageCI, ImageViewCI, ImageBarrierCI, SamplerCI};
+use crate::ci::image::{ImageCI, ImageViewCI, SamplerCI};
 use crate::ci::buffer::BufferCI;
 use crate::ci::VkObjectBuildableCI;
 
-use crate::command::CmdTransferApi;
 use crate::context::VkDevice;
 
-use crate::{VkResult, VkErrorKind};
+use crate::{VkResult, VkError, VkErrorKind};
 use crate::{vkuint, vkbytes, vkfloat};
 
 use std::path::Path;
 use std::ptr;
 
 
+/// The way to handle the textures whose extent exceeds the maximum image dimension of device.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum OversizePolicy {
+    /// Downscale the texture until it fits the limit.
+    ///
+    /// The mip levels exceeding the limit are skipped first. If the smallest level still does not fit,
+    /// it is downscaled on CPU, which is only supported for uncompressed formats with 4 bytes per texel(etc. `R8G8B8A8_UNORM`).
+    Downscale,
+    /// Return an error instead of loading the texture.
+    Error,
+}
+
+/// The configuration used in loading textures.
+#[derive(Debug, Clone)]
+pub struct TextureLoadConfig {
+
+    /// `oversize` determines how to handle the textures exceeding the maximum image dimension.
+    pub oversize: OversizePolicy,
+    /// `max_dimension` overrides the maximum extent of texture. Use `maxImageDimension2D` of device if it is None.
+    pub max_dimension: Option<vkuint>,
+}
+
+impl Default for TextureLoadConfig {
+
+    fn default() -> TextureLoadConfig {
+        TextureLoadConfig {
+            oversize: OversizePolicy::Downscale,
+            max_dimension: None,
+        }
+    }
+}
+
+/// The data of a mip level.
+struct TextureLevel {
+    width : vkuint,
+    height: vkuint,
+    bytes : Vec<u8>,
+}
+
+impl TextureLevel {
+
+    /// Read the level of `size` bytes at `offset` in `texture_bytes`, and move `offset` to the next level.
+    fn read(texture_bytes: &[u8], offset: &mut usize, width: vkuint, height: vkuint, size: usize) -> TextureLevel {
+
+        let bytes = texture_bytes[*offset..(*offset + size)].to_vec();
+        *offset += size;
+
+        TextureLevel { width, height, bytes }
+    }
+}
+
+/// The count of faces in a cube map, which are stored as array layers in Vulkan.
+pub const CUBE_FACES_COUNT: usize = 6;
+
 /// 2D texture.
 pub struct Texture2D {
 
@@ -27,6 +82,8 @@ pub struct Texture2D {
     pub width      : vkuint,
     pub height     : vkuint,
     pub mip_levels : vkuint,
+    /// the count of array layers. The view type is `vk::ImageViewType::TYPE_2D_ARRAY` if it is greater than 1.
+    pub layer_count: vkuint,
 
     pub sampler: vk::Sampler,
     pub descriptor: vk::DescriptorImageInfo,
@@ -35,151 +92,243 @@ pub struct Texture2D {
 impl Texture2D {
 
     pub fn load_ktx(device: &mut VkDevice, path: impl AsRef<Path>, format: vk::Format) -> VkResult<Texture2D> {
+        Texture2D::load_ktx_with(device, path, format, &TextureLoadConfig::default())
+    }
+
+    /// Load a 2D texture from ktx file, using the custom configuration to handle the textures exceeding device limits.
+    pub fn load_ktx_with(device: &mut VkDevice, path: impl AsRef<Path>, format: vk::Format, config: &TextureLoadConfig) -> VkResult<Texture2D> {
 
-        let tex_2d: gli::Texture2D = gli::load_ktx(path)
+        // keep the local asset alive until it has been read, since it may be a temporary copy.
+        let local_asset = crate::utils::asset::local_asset_path(path)?;
+        let tex_2d: gli::Texture2D = gli::load_ktx(local_asset.path())
             .map_err(VkErrorKind::Gli)?;
 
         debug_assert!(!tex_2d.empty());
 
-        let (width, height) = {
-            let base_image = tex_2d.get_level(0);
-            (base_image.extent().width, base_image.extent().height)
+        // read the data of all mip levels, which are stored contiguously in texture.
+        let texture_bytes = unsafe {
+            ::std::slice::from_raw_parts(tex_2d.data() as *const u8, tex_2d.size())
         };
 
-        // Only use linear tiling if requested (and supported by the device).
-        // Support for linear tiling is mostly limited, so prefer to use optimal tiling instead.
-        // On most implementations linear tiling will only support a very limited amount of formats and features (mip maps, cubemap, arrays, etc.).
+        let mut levels = Vec::with_capacity(tex_2d.levels());
+        let mut level_offset = 0;
+        for i in 0..tex_2d.levels() {
 
-        let staging_buffer = {
+            let image_level_i = tex_2d.get_level(i);
+            let level_extent = image_level_i.extent();
+            levels.push(TextureLevel::read(texture_bytes, &mut level_offset, level_extent.width, level_extent.height, image_level_i.size()));
+        }
 
-            // create a host-visible staging buffer that contains the raw image data.
-            // This buffer is used as a transfer source for the buffer copy.
+        Texture2D::from_layers(device, vec![levels], format, config)
+    }
 
-            let staging_ci = BufferCI::new(tex_2d.size() as vkbytes)
-                .usage(vk::BufferUsageFlags::TRANSFER_SRC);
-            let allocation_ci = VmaAllocationCI::new(vma::MemoryUsage::CpuOnly, vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT);
-            let staging_allocation = device.vma.create_buffer(
-                staging_ci.as_ref(), allocation_ci.as_ref())
-                .map_err(VkErrorKind::Vma)?;
+    /// Load a 2D texture or 2D texture array from KTX2 file.
+    ///
+    /// The image data is uploaded in the format declared in file without decompression(etc. `vk::Format::BC7_UNORM_BLOCK`),
+    /// and an error is returned if the format can not be sampled on this device.
+    pub fn load_ktx2(device: &mut VkDevice, path: impl AsRef<Path>, config: &TextureLoadConfig) -> VkResult<Texture2D> {
 
-            // Copy texture data into staging buffer.
-            let data_ptr = device.vma.map_memory(&staging_allocation.1)
-                .map_err(VkErrorKind::Vma)?;
-            debug_assert_ne!(data_ptr, ptr::null_mut());
+        let ktx2 = ktx2::read_ktx2(path)?;
 
-            unsafe {
-                data_ptr.copy_from(tex_2d.data() as *const u8, tex_2d.size());
-            }
+        if ktx2.face_count != 1 {
+            return Err(VkError::custom("KTX2 file contains a cube map. Use TextureCube::load_ktx2 to load it."))
+        }
+        check_format_support(device, ktx2.format)?;
 
-            device.vma.unmap_memory(&staging_allocation.1)
-                .map_err(VkErrorKind::Vma)?;
+        Texture2D::from_layers(device, ktx2.layers, ktx2.format, config)
+    }
 
-            VmaBuffer::from(staging_allocation)
-        };
+    fn from_layers(device: &mut VkDevice, layers: Vec<Vec<TextureLevel>>, format: vk::Format, config: &TextureLoadConfig) -> VkResult<Texture2D> {
 
-        // setup buffer copy regions for each mip level.
-        let mut buffer_copy_regions = Vec::with_capacity(tex_2d.levels());
-        let mut staging_offset = 0;
+        let max_dimension = config.max_dimension.unwrap_or(device.phy.limits.max_image_dimension2_d);
+        let mut fitted_layers = Vec::with_capacity(layers.len());
+        for levels in layers {
+            fitted_layers.push(fit_texture_levels(levels, format, max_dimension, config.oversize)?);
+        }
 
-        for i in 0..tex_2d.levels() {
+        let (width, height) = (fitted_layers[0][0].width, fitted_layers[0][0].height);
+        let mip_levels = fitted_layers[0].len() as vkuint;
+        let layer_count = fitted_layers.len() as vkuint;
 
-            let image_level_i = tex_2d.get_level(i);
+        let (dst_image, dst_image_view) = upload_texture_layers(device, format, &fitted_layers, false)?;
 
-            let copy_region = vk::BufferImageCopy {
-                buffer_offset: staging_offset,
-                buffer_row_length  : 0,
-                buffer_image_height: 0,
-                image_subresource: vk::ImageSubresourceLayers {
-                    aspect_mask: vk::ImageAspectFlags::COLOR,
-                    mip_level: i as vkuint,
-                    base_array_layer: 0,
-                    layer_count     : 1,
-                },
-                image_offset: vk::Offset3D { x: 0, y: 0, z: 0 },
-                image_extent: vk::Extent3D {
-                    width : image_level_i.extent().width,
-                    height: image_level_i.extent().height,
-                    depth : 1,
-                },
+        let dst_sampler = {
+
+            // Create a default sampler.
+            let mut sampler_ci = SamplerCI::new()
+                .filter(vk::Filter::LINEAR, vk::Filter::LINEAR)
+                .mipmap(vk::SamplerMipmapMode::LINEAR)
+                .address(vk::SamplerAddressMode::REPEAT, vk::SamplerAddressMode::REPEAT, vk::SamplerAddressMode::REPEAT)
+                // max level-of-detail should match mip level count.
+                .lod(0.0, 0.0, mip_levels as vkfloat)
+                .compare_op(Some(vk::CompareOp::NEVER))
+                .border_color(vk::BorderColor::FLOAT_OPAQUE_WHITE);
+
+            // Only enable anisotropic filtering if enabled on the device.
+            sampler_ci = if device.phy.features_enabled().sampler_anisotropy == vk::TRUE {
+                sampler_ci.anisotropy(Some(device.phy.limits.max_sampler_anisotropy))
+            } else {
+                sampler_ci.anisotropy(None)
             };
 
-            buffer_copy_regions.push(copy_region);
-            staging_offset += image_level_i.size() as vkbytes;
-        }
+            sampler_ci.build(device)?
+        };
+
+        let result = Texture2D {
+            image: dst_image,
+            view : dst_image_view,
+            mip_levels, layer_count,
+            sampler: dst_sampler,
+            descriptor: vk::DescriptorImageInfo {
+                sampler: dst_sampler,
+                image_view: dst_image_view,
+                image_layout: vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+            },
+            width, height,
+        };
+        Ok(result)
+    }
+
+    pub fn discard_by(self, device: &mut VkDevice) -> VkResult<()> {
+
+        device.discard(self.sampler);
+        device.discard(self.view);
+        device.vma_discard(self.image)
+    }
+}
+
+/// Cube map texture, usually used to render skybox or environment reflection.
+///
+/// The image is created with `vk::ImageCreateFlags::CUBE_COMPATIBLE` and 6 array layers, and its view type is `vk::ImageViewType::CUBE`.
+/// The faces are in the order of +X, -X, +Y, -Y, +Z, -Z.
+pub struct TextureCube {
+
+    pub image: VmaImage,
+    pub view : vk::ImageView,
+
+    pub width      : vkuint,
+    pub height     : vkuint,
+    pub mip_levels : vkuint,
+
+    pub sampler: vk::Sampler,
+    pub descriptor: vk::DescriptorImageInfo,
+}
+
+impl TextureCube {
 
-        // create optimal tiled target image on the device.
-        let dst_image = {
+    /// Load a cube map from a single ktx file containing all the 6 faces.
+    pub fn load_ktx(device: &mut VkDevice, path: impl AsRef<Path>, format: vk::Format) -> VkResult<TextureCube> {
 
-            let image_ci = ImageCI::new_2d(format, vk::Extent2D { width, height })
-                .mip_levels(tex_2d.levels() as vkuint)
-                .array_layers(1)
-                .samples(vk::SampleCountFlags::TYPE_1)
-                .tiling(vk::ImageTiling::OPTIMAL)
-                .initial_layout(vk::ImageLayout::UNDEFINED)
-                .usages(vk::ImageUsageFlags::TRANSFER_DST | vk::ImageUsageFlags::SAMPLED);
+        let local_asset = crate::utils::asset::local_asset_path(path)?;
+        let tex_cube: gli::TextureCube = gli::load_ktx(local_asset.path())
+            .map_err(VkErrorKind::Gli)?;
 
-            let allocation_ci = VmaAllocationCI::new(
-                vma::MemoryUsage::GpuOnly, vk::MemoryPropertyFlags::DEVICE_LOCAL);
-            let image_allocation = device.vma.create_image(
-                image_ci.as_ref(), allocation_ci.as_ref())
-                .map_err(VkErrorKind::Vma)?;
+        debug_assert!(!tex_cube.empty());
 
-            VmaImage::from(image_allocation)
+        // the data of each face(including all its mip levels) are stored contiguously in texture.
+        let texture_bytes = unsafe {
+            ::std::slice::from_raw_parts(tex_cube.data() as *const u8, tex_cube.size())
         };
 
+        let mut faces = Vec::with_capacity(CUBE_FACES_COUNT);
+        let mut level_offset = 0;
+        for face in 0..CUBE_FACES_COUNT {
 
-        { // transfer image data from staging buffer to dst image.
+            let cube_face = tex_cube.get_face(face);
 
-            let sub_range = vk::ImageSubresourceRange {
-                aspect_mask: vk::ImageAspectFlags::COLOR,
-                base_mip_level: 0,
-                level_count: tex_2d.levels() as vkuint,
-                base_array_layer: 0,
-                layer_count: 1,
-            };
+            let mut levels = Vec::with_capacity(tex_cube.levels());
+            for i in 0..tex_cube.levels() {
+
+                let face_level_i = cube_face.get_level(i);
+                let level_extent = face_level_i.extent();
+                levels.push(TextureLevel::read(texture_bytes, &mut level_offset, level_extent.width, level_extent.height, face_level_i.size()));
+            }
+            faces.push(levels);
+        }
 
-            // Image barrier for optimal image (target).
-            // Optimal image will be used as destination for the copy.
-            let barrier1 = ImageBarrierCI::new(dst_image.handle, sub_range)
-                .access_mask(vk::AccessFlags::empty(), vk::AccessFlags::TRANSFER_WRITE)
-                .layout(vk::ImageLayout::UNDEFINED, vk::ImageLayout::TRANSFER_DST_OPTIMAL);
+        TextureCube::from_faces(device, faces, format)
+    }
 
-            // Change texture image layout to shader read after all mip levels have been copied.
-            let barrier2 = ImageBarrierCI::new(dst_image.handle, sub_range)
-                .access_mask(vk::AccessFlags::TRANSFER_WRITE, vk::AccessFlags::SHADER_READ)
-                .layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL, vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL);
+    /// Load a cube map from KTX2 file, whose face count must be 6.
+    ///
+    /// Same as `Texture2D::load_ktx2`, the image data is uploaded without decompression.
+    pub fn load_ktx2(device: &mut VkDevice, path: impl AsRef<Path>) -> VkResult<TextureCube> {
 
+        let ktx2 = ktx2::read_ktx2(path)?;
 
-            let cmd_recorder = device.get_transfer_recorder();
+        if ktx2.face_count != CUBE_FACES_COUNT as vkuint || ktx2.layers.len() != CUBE_FACES_COUNT {
+            return Err(VkError::custom("KTX2 file does not contain a cube map(or it is a cube map array, which is not supported)."))
+        }
+        check_format_support(device, ktx2.format)?;
 
-            cmd_recorder.begin_record()?
-                .image_pipeline_barrier(vk::PipelineStageFlags::HOST, vk::PipelineStageFlags::TRANSFER, vk::DependencyFlags::empty(), &[barrier1.into()])
-                // Copy mip levels from staging buffer.
-                .copy_buf2img(staging_buffer.handle, dst_image.handle, vk::ImageLayout::TRANSFER_DST_OPTIMAL, &buffer_copy_regions)
-                .image_pipeline_barrier(vk::PipelineStageFlags::TRANSFER, vk::PipelineStageFlags::ALL_COMMANDS, vk::DependencyFlags::empty(), &[barrier2.into()])
-                .end_record()?;
+        TextureCube::from_faces(device, ktx2.layers, ktx2.format)
+    }
 
-            device.flush_transfer(cmd_recorder)?;
+    /// Load a cube map from 6 ktx files, each of which contains a 2D texture as one face.
+    ///
+    /// `paths` are in the order of +X, -X, +Y, -Y, +Z, -Z. All the faces must have the same extent and mip level count.
+    pub fn load_faces<P: AsRef<Path>>(device: &mut VkDevice, paths: [P; CUBE_FACES_COUNT], format: vk::Format) -> VkResult<TextureCube> {
+
+        let mut faces = Vec::with_capacity(CUBE_FACES_COUNT);
+
+        for path in paths.iter() {
+
+            let local_asset = crate::utils::asset::local_asset_path(path)?;
+            let tex_2d: gli::Texture2D = gli::load_ktx(local_asset.path())
+                .map_err(VkErrorKind::Gli)?;
+
+            debug_assert!(!tex_2d.empty());
+
+            let texture_bytes = unsafe {
+                ::std::slice::from_raw_parts(tex_2d.data() as *const u8, tex_2d.size())
+            };
+
+            let mut levels = Vec::with_capacity(tex_2d.levels());
+            let mut level_offset = 0;
+            for i in 0..tex_2d.levels() {
+
+                let image_level_i = tex_2d.get_level(i);
+                let level_extent = image_level_i.extent();
+                levels.push(TextureLevel::read(texture_bytes, &mut level_offset, level_extent.width, level_extent.height, image_level_i.size()));
+            }
+            faces.push(levels);
+        }
+
+        let base_face = &faces[0];
+        let is_faces_match = faces.iter().all(|levels| {
+            levels.len() == base_face.len() && levels[0].width == base_face[0].width && levels[0].height == base_face[0].height
+        });
+        if !is_faces_match {
+            return Err(VkError::custom("The faces of cube map must have the same extent and mip level count."))
         }
 
+        TextureCube::from_faces(device, faces, format)
+    }
 
-        { // clean up staging resources.
-            device.vma_discard(staging_buffer)?;
+    fn from_faces(device: &mut VkDevice, faces: Vec<Vec<TextureLevel>>, format: vk::Format) -> VkResult<TextureCube> {
+
+        let max_dimension = device.phy.limits.max_image_dimension_cube;
+        let mut fitted_faces = Vec::with_capacity(faces.len());
+        for levels in faces {
+            fitted_faces.push(fit_texture_levels(levels, format, max_dimension, OversizePolicy::Downscale)?);
         }
 
+        let (width, height) = (fitted_faces[0][0].width, fitted_faces[0][0].height);
+        let mip_levels = fitted_faces[0].len() as vkuint;
+
+        let (dst_image, dst_image_view) = upload_texture_layers(device, format, &fitted_faces, true)?;
+
         let dst_sampler = {
 
-            // Create a default sampler.
+            // clamp the coordinates to avoid seams between faces.
             let mut sampler_ci = SamplerCI::new()
                 .filter(vk::Filter::LINEAR, vk::Filter::LINEAR)
                 .mipmap(vk::SamplerMipmapMode::LINEAR)
-                .address(vk::SamplerAddressMode::REPEAT, vk::SamplerAddressMode::REPEAT, vk::SamplerAddressMode::REPEAT)
-                // max level-of-detail should match mip level count.
-                .lod(0.0, 0.0, tex_2d.levels() as vkfloat)
+                .address(vk::SamplerAddressMode::CLAMP_TO_EDGE, vk::SamplerAddressMode::CLAMP_TO_EDGE, vk::SamplerAddressMode::CLAMP_TO_EDGE)
+                .lod(0.0, 0.0, mip_levels as vkfloat)
                 .compare_op(Some(vk::CompareOp::NEVER))
                 .border_color(vk::BorderColor::FLOAT_OPAQUE_WHITE);
 
-            // Only enable anisotropic filtering if enabled on the device.
             sampler_ci = if device.phy.features_enabled().sampler_anisotropy == vk::TRUE {
                 sampler_ci.anisotropy(Some(device.phy.limits.max_sampler_anisotropy))
             } else {
@@ -189,27 +338,10 @@ impl Texture2D {
             sampler_ci.build(device)?
         };
 
-        let dst_image_view = ImageViewCI::new(dst_image.handle, vk::ImageViewType::TYPE_2D, format)
-            .components(vk::ComponentMapping {
-                r: vk::ComponentSwizzle::R,
-                g: vk::ComponentSwizzle::G,
-                b: vk::ComponentSwizzle::B,
-                a: vk::ComponentSwizzle::A,
-            })
-            .sub_range(vk::ImageSubresourceRange {
-                aspect_mask: vk::ImageAspectFlags::COLOR,
-                base_mip_level: 0,
-                level_count: tex_2d.levels() as vkuint,
-                base_array_layer: 0,
-                layer_count: 1,
-            })
-            .build(device)?;
-
-
-        let result = Texture2D {
+        let result = TextureCube {
             image: dst_image,
             view : dst_image_view,
-            mip_levels: tex_2d.levels() as vkuint,
+            mip_levels: mip_levels,
             sampler: dst_sampler,
             descriptor: vk::DescriptorImageInfo {
                 sampler: dst_sampler,
@@ -228,3 +360,348 @@ impl Texture2D {
         device.vma_discard(self.image)
     }
 }
+
+/// Make sure `format` can be uploaded to and sampled from an image with optimal tiling on this device.
+///
+/// BCn formats also require the `texture_compression_bc` feature, which must be requested by `PhysicalDevConfig::request_features`.
+fn check_format_support(device: &VkDevice, format: vk::Format) -> VkResult<()> {
+
+    if is_bc_format(format) && device.phy.features_enabled().texture_compression_bc != vk::TRUE {
+        return Err(VkError::custom(format!("Texture format {:?} requires textureCompressionBC feature, which is not enabled(see PhysicalDevConfig::request_features).", format)))
+    }
+
+    // the transfer features of format are only reported by Vulkan 1.1 devices.
+    let is_transfer_reported = device.phy.properties().api_version >= ash::vk_make_version!(1, 1, 0);
+    let supported = device.phy.format_properties(format).optimal_tiling_features;
+    let missing = missing_texture_features(supported, is_transfer_reported);
+
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(VkError::custom(format!("Texture format {:?} does not support {:?} with optimal tiling on this device.", format, missing)))
+    }
+}
+
+/// Check if `format` is one of the BC1 ~ BC7 block compressed formats.
+fn is_bc_format(format: vk::Format) -> bool {
+    let raw = format.as_raw();
+    raw >= vk::Format::BC1_RGB_UNORM_BLOCK.as_raw() && raw <= vk::Format::BC7_SRGB_BLOCK.as_raw()
+}
+
+/// Get the features required by textures but not contained in `supported`.
+///
+/// `TRANSFER_DST` is only required if `is_transfer_reported`, since it's implied by any other feature on Vulkan 1.0 devices.
+fn missing_texture_features(supported: vk::FormatFeatureFlags, is_transfer_reported: bool) -> vk::FormatFeatureFlags {
+
+    let required = if is_transfer_reported {
+        vk::FormatFeatureFlags::SAMPLED_IMAGE | vk::FormatFeatureFlags::TRANSFER_DST
+    } else {
+        vk::FormatFeatureFlags::SAMPLED_IMAGE
+    };
+
+    vk::FormatFeatureFlags::from_raw(required.as_raw() & !supported.as_raw())
+}
+
+/// Upload the mip levels of each array layer to a new device-local image, and create its view.
+///
+/// The image is created as a cube map if `is_cube` is true, where `layers` must contain 6 faces.
+fn upload_texture_layers(device: &mut VkDevice, format: vk::Format, layers: &[Vec<TextureLevel>], is_cube: bool) -> VkResult<(VmaImage, vk::ImageView)> {
+
+    let (width, height) = (layers[0][0].width, layers[0][0].height);
+    let mip_levels = layers[0].len() as vkuint;
+    let layer_count = layers.len() as vkuint;
+    let total_size: usize = layers.iter()
+        .flat_map(|levels| levels.iter())
+        .map(|level| level.bytes.len())
+        .sum();
+
+    // Only use linear tiling if requested (and supported by the device).
+    // Support for linear tiling is mostly limited, so prefer to use optimal tiling instead.
+    // On most implementations linear tiling will only support a very limited amount of formats and features (mip maps, cubemap, arrays, etc.).
+
+    let staging_buffer = {
+
+        // create a host-visible staging buffer that contains the raw image data.
+        // This buffer is used as a transfer source for the buffer copy.
+
+        let staging_ci = BufferCI::new(total_size as vkbytes)
+            .usage(vk::BufferUsageFlags::TRANSFER_SRC);
+        let allocation_ci = VmaAllocationCI::new(vma::MemoryUsage::CpuOnly, vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT);
+        let staging_allocation = device.vma.create_buffer(
+            staging_ci.as_ref(), allocation_ci.as_ref())
+            .map_err(VkErrorKind::Vma)?;
+
+        // Copy texture data into staging buffer.
+        let data_ptr = device.vma.map_memory(&staging_allocation.1)
+            .map_err(VkErrorKind::Vma)?;
+        debug_assert_ne!(data_ptr, ptr::null_mut());
+
+        let mut level_offset = 0;
+        for level in layers.iter().flat_map(|levels| levels.iter()) {
+            unsafe {
+                data_ptr.add(level_offset).copy_from_nonoverlapping(level.bytes.as_ptr(), level.bytes.len());
+            }
+            level_offset += level.bytes.len();
+        }
+
+        device.vma.unmap_memory(&staging_allocation.1)
+            .map_err(VkErrorKind::Vma)?;
+
+        VmaBuffer::from(staging_allocation)
+    };
+
+    // setup buffer copy regions for each layer including all of its mip level.
+    let mut buffer_copy_regions = Vec::with_capacity(layers.len() * mip_levels as usize);
+    let mut staging_offset = 0;
+
+    for (layer, levels) in layers.iter().enumerate() {
+        for (i, level) in levels.iter().enumerate() {
+
+            let copy_region = vk::BufferImageCopy {
+                buffer_offset: staging_offset,
+                buffer_row_length  : 0,
+                buffer_image_height: 0,
+                image_subresource: vk::ImageSubresourceLayers {
+                    aspect_mask: vk::ImageAspectFlags::COLOR,
+                    mip_level: i as vkuint,
+                    base_array_layer: layer as vkuint,
+                    layer_count     : 1,
+                },
+                image_offset: vk::Offset3D { x: 0, y: 0, z: 0 },
+                image_extent: vk::Extent3D {
+                    width : level.width,
+                    height: level.height,
+                    depth : 1,
+                },
+            };
+
+            buffer_copy_regions.push(copy_region);
+            staging_offset += level.bytes.len() as vkbytes;
+        }
+    }
+
+    // create optimal tiled target image on the device.
+    let dst_image = {
+
+        let image_ci = if is_cube {
+            debug_assert_eq!(layers.len(), CUBE_FACES_COUNT);
+            ImageCI::new_cube(format, vk::Extent2D { width, height })
+        } else {
+            ImageCI::new_2d(format, vk::Extent2D { width, height })
+                .array_layers(layer_count)
+        };
+
+        let image_ci = image_ci
+            .mip_levels(mip_levels)
+            .samples(vk::SampleCountFlags::TYPE_1)
+            .tiling(vk::ImageTiling::OPTIMAL)
+            .initial_layout(vk::ImageLayout::UNDEFINED)
+            .usages(vk::ImageUsageFlags::TRANSFER_DST | vk::ImageUsageFlags::SAMPLED);
+
+        let allocation_ci = VmaAllocationCI::new(
+            vma::MemoryUsage::GpuOnly, vk::MemoryPropertyFlags::DEVICE_LOCAL);
+        let image_allocation = device.vma.create_image(
+            image_ci.as_ref(), allocation_ci.as_ref())
+            .map_err(VkErrorKind::Vma)?;
+
+        VmaImage::from(image_allocation)
+    };
+
+    let sub_range = vk::ImageSubresourceRange {
+        aspect_mask: vk::ImageAspectFlags::COLOR,
+        base_mip_level: 0,
+        level_count: mip_levels,
+        base_array_layer: 0,
+        layer_count,
+    };
+
+    { // transfer image data from staging buffer to dst image.
+
+        let cmd_recorder = device.get_transfer_recorder();
+
+        // Copy mip levels from staging buffer, and change texture image layout to shader read after all mip levels have been copied.
+        cmd_recorder.begin_record()?
+            .upload_buf2img(staging_buffer.handle, dst_image.handle, sub_range, &buffer_copy_regions, vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
+            .end_record()?;
+
+        device.flush_transfer(cmd_recorder)?;
+    }
+
+
+    { // clean up staging resources.
+        device.vma_discard(staging_buffer)?;
+    }
+
+    let view_type = match (is_cube, layer_count) {
+        | (true, _) => vk::ImageViewType::CUBE,
+        | (false, 1) => vk::ImageViewType::TYPE_2D,
+        | (false, _) => vk::ImageViewType::TYPE_2D_ARRAY,
+    };
+
+    let dst_image_view = ImageViewCI::new(dst_image.handle, view_type, format)
+        .components(vk::ComponentMapping {
+            r: vk::ComponentSwizzle::R,
+            g: vk::ComponentSwizzle::G,
+            b: vk::ComponentSwizzle::B,
+            a: vk::ComponentSwizzle::A,
+        })
+        .sub_range(sub_range)
+        .build(device)?;
+
+    Ok((dst_image, dst_image_view))
+}
+
+/// Make sure the base level of texture fits `max_dimension` by skipping or downscaling its mip levels.
+fn fit_texture_levels(mut levels: Vec<TextureLevel>, format: vk::Format, max_dimension: vkuint, policy: OversizePolicy) -> VkResult<Vec<TextureLevel>> {
+
+    let is_oversize = |level: &TextureLevel| level.width > max_dimension || level.height > max_dimension;
+
+    let (origin_width, origin_height) = (levels[0].width, levels[0].height);
+    if !is_oversize(&levels[0]) {
+        return Ok(levels)
+    }
+
+    if policy == OversizePolicy::Error {
+        return Err(VkError::custom(format!("Texture extent {}x{} exceeds the maximum image dimension({}) of device.", origin_width, origin_height, max_dimension)))
+    }
+
+    // skip the mip levels which are too large.
+    while levels.len() > 1 && is_oversize(&levels[0]) {
+        levels.remove(0);
+    }
+
+    if is_oversize(&levels[0]) {
+
+        let is_downscale_support = match format {
+            | vk::Format::R8G8B8A8_UNORM
+            | vk::Format::R8G8B8A8_SRGB
+            | vk::Format::B8G8R8A8_UNORM
+            | vk::Format::B8G8R8A8_SRGB => true,
+            | _ => false,
+        };
+
+        if !is_downscale_support {
+            return Err(VkError::custom(format!("Texture extent {}x{} exceeds the maximum image dimension({}) of device, and format {:?} can not be downscaled.", origin_width, origin_height, max_dimension, format)))
+        }
+
+        let mut base_level = levels.remove(0);
+        while is_oversize(&base_level) {
+            base_level = downscale_rgba8(&base_level);
+        }
+        levels.insert(0, base_level);
+    }
+
+    println!("[Warning] Texture is downscaled from {}x{} to {}x{} to fit the maximum image dimension({}).", origin_width, origin_height, levels[0].width, levels[0].height, max_dimension);
+
+    Ok(levels)
+}
+
+/// Halve the extent of an image with 4 bytes per texel, by averaging each 2x2 texels.
+fn downscale_rgba8(level: &TextureLevel) -> TextureLevel {
+
+    const TEXEL_SIZE: usize = 4;
+
+    let (src_width, src_height) = (level.width as usize, level.height as usize);
+    let dst_width  = (src_width  / 2).max(1);
+    let dst_height = (src_height / 2).max(1);
+
+    let mut bytes = vec![0_u8; dst_width * dst_height * TEXEL_SIZE];
+
+    for y in 0..dst_height {
+        for x in 0..dst_width {
+
+            // clamp the source texels for images with odd or 1 pixel extent.
+            let src_xs = [(x * 2).min(src_width - 1), (x * 2 + 1).min(src_width - 1)];
+            let src_ys = [(y * 2).min(src_height - 1), (y * 2 + 1).min(src_height - 1)];
+
+            for channel in 0..TEXEL_SIZE {
+
+                let mut sum = 0_u32;
+                for &src_y in src_ys.iter() {
+                    for &src_x in src_xs.iter() {
+                        sum += level.bytes[(src_y * src_width + src_x) * TEXEL_SIZE + channel] as u32;
+                    }
+                }
+
+                bytes[(y * dst_width + x) * TEXEL_SIZE + channel] = (sum / 4) as u8;
+            }
+        }
+    }
+
+    TextureLevel {
+        width : dst_width  as vkuint,
+        height: dst_height as vkuint,
+        bytes,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn bc_formats_are_detected() {
+
+        assert!(is_bc_format(vk::Format::BC1_RGB_UNORM_BLOCK));
+        assert!(is_bc_format(vk::Format::BC5_UNORM_BLOCK));
+        assert!(is_bc_format(vk::Format::BC7_SRGB_BLOCK));
+        assert!(!is_bc_format(vk::Format::R8G8B8A8_UNORM));
+        assert!(!is_bc_format(vk::Format::ETC2_R8G8B8_UNORM_BLOCK));
+    }
+
+    #[test]
+    fn texture_requires_sampling_and_transfer() {
+
+        let sampled = vk::FormatFeatureFlags::SAMPLED_IMAGE;
+        let transfer = vk::FormatFeatureFlags::TRANSFER_DST;
+
+        assert!(missing_texture_features(sampled | transfer, true).is_empty());
+        assert_eq!(missing_texture_features(sampled, true), transfer);
+        assert_eq!(missing_texture_features(transfer, true), sampled);
+        // Vulkan 1.0 devices do not report the transfer features.
+        assert!(missing_texture_features(sampled, false).is_empty());
+    }
+
+    fn solid_level(width: vkuint, height: vkuint, texel: [u8; 4]) -> TextureLevel {
+        TextureLevel {
+            width, height,
+            bytes: texel.iter().cloned().cycle().take((width * height * 4) as usize).collect(),
+        }
+    }
+
+    #[test]
+    fn oversize_texture_is_downscaled_to_fit() {
+
+        // a mocked limit which is smaller than the texture.
+        let max_dimension = 256;
+        let levels = vec![solid_level(1000, 300, [10, 20, 30, 255])];
+
+        let fitted = fit_texture_levels(levels, vk::Format::R8G8B8A8_UNORM, max_dimension, OversizePolicy::Downscale).unwrap();
+        assert_eq!((fitted[0].width, fitted[0].height), (250, 75));
+        assert_eq!(fitted[0].bytes.len(), 250 * 75 * 4);
+        // averaging a solid color keeps the color.
+        assert_eq!(fitted[0].bytes[0..4], [10, 20, 30, 255]);
+    }
+
+    #[test]
+    fn oversize_mip_levels_are_skipped() {
+
+        let levels = vec![solid_level(512, 512, [0; 4]), solid_level(256, 256, [0; 4]), solid_level(128, 128, [0; 4])];
+
+        let fitted = fit_texture_levels(levels, vk::Format::BC7_UNORM_BLOCK, 256, OversizePolicy::Downscale).unwrap();
+        assert_eq!(fitted.len(), 2);
+        assert_eq!((fitted[0].width, fitted[0].height), (256, 256));
+    }
+
+    #[test]
+    fn oversize_texture_is_rejected_if_configured() {
+
+        let fit = |format: vk::Format, policy: OversizePolicy| fit_texture_levels(vec![solid_level(512, 16, [0; 4])], format, 256, policy);
+
+        assert!(fit(vk::Format::R8G8B8A8_UNORM, OversizePolicy::Error).is_err());
+        // the single level of compressed texture can not be downscaled on CPU.
+        assert!(fit(vk::Format::BC7_UNORM_BLOCK, OversizePolicy::Downscale).is_err());
+        assert!(fit(vk::Format::R8G8B8A8_UNORM, OversizePolicy::Downscale).is_ok());
+    }
+}
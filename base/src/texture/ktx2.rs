@@ -0,0 +1,110 @@
+
+use ash::vk;
+
+use super::TextureLevel;
+
+use crate::{VkResult, VkError};
+use crate::vkuint;
+
+use std::path::Path;
+
+
+/// The identifier at the beginning of each KTX2 file.
+const KTX2_IDENTIFIER: [u8; 12] = [0xAB, 0x4B, 0x54, 0x58, 0x20, 0x32, 0x30, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A];
+/// The size of header and index before the level index.
+const KTX2_LEVEL_INDEX_OFFSET: usize = 80;
+/// The size of each entry in level index(byteOffset, byteLength and uncompressedByteLength).
+const KTX2_LEVEL_INDEX_SIZE: usize = 24;
+
+/// The image data read from a KTX2 file.
+pub(super) struct Ktx2Data {
+
+    pub format: vk::Format,
+    /// the count of faces, which is 6 for cube maps and 1 for others.
+    pub face_count: vkuint,
+    /// the mip levels of each array layer(or face), in the order of layer -> face.
+    pub layers: Vec<Vec<TextureLevel>>,
+}
+
+/// Read the KTX2 file, keeping the image data in its original(possibly block-compressed) format.
+///
+/// Supercompressed files(etc. Basis Universal) and 3D textures are not supported.
+pub(super) fn read_ktx2(path: impl AsRef<Path>) -> VkResult<Ktx2Data> {
+
+    let path = path.as_ref();
+    let bytes = crate::utils::asset::read_asset(path)?;
+
+    let invalid = |reason: &str| VkError::custom(format!("Invalid KTX2 file {:?}: {}.", path, reason));
+
+    if bytes.len() < KTX2_LEVEL_INDEX_OFFSET || bytes[0..12] != KTX2_IDENTIFIER {
+        return Err(invalid("missing KTX2 identifier"))
+    }
+
+    let format        = vk::Format::from_raw(read_u32(&bytes, 12) as _);
+    let pixel_width   = read_u32(&bytes, 20);
+    let pixel_height  = read_u32(&bytes, 24).max(1);
+    let pixel_depth   = read_u32(&bytes, 28);
+    let layer_count   = read_u32(&bytes, 32).max(1);
+    let face_count    = read_u32(&bytes, 36);
+    // level count 0 means the mipmaps should be generated by loader, so only the base level is stored.
+    let level_count   = read_u32(&bytes, 40).max(1);
+    let supercompression = read_u32(&bytes, 44);
+
+    if format == vk::Format::UNDEFINED {
+        return Err(invalid("formats that require transcoding are not supported"))
+    }
+    if supercompression != 0 {
+        return Err(invalid("supercompressed data is not supported"))
+    }
+    if pixel_depth > 1 {
+        return Err(invalid("3D textures are not supported"))
+    }
+    if face_count != 1 && face_count != 6 {
+        return Err(invalid("face count must be 1 or 6"))
+    }
+
+    let image_count = (layer_count * face_count) as usize;
+    if bytes.len() < KTX2_LEVEL_INDEX_OFFSET + KTX2_LEVEL_INDEX_SIZE * level_count as usize {
+        return Err(invalid("level index is truncated"))
+    }
+
+    let mut layers: Vec<Vec<TextureLevel>> = (0..image_count)
+        .map(|_| Vec::with_capacity(level_count as usize))
+        .collect();
+
+    for i in 0..level_count {
+
+        let index_offset = KTX2_LEVEL_INDEX_OFFSET + KTX2_LEVEL_INDEX_SIZE * i as usize;
+        let level_offset = read_u64(&bytes, index_offset) as usize;
+        let level_length = read_u64(&bytes, index_offset + 8) as usize;
+
+        if level_offset + level_length > bytes.len() || level_length % image_count != 0 {
+            return Err(invalid("level data is out of range"))
+        }
+
+        // each level contains the images of all layers and faces, which are tightly packed.
+        let image_size = level_length / image_count;
+        let mut image_offset = level_offset;
+        for levels in layers.iter_mut() {
+            let (width, height) = ((pixel_width >> i).max(1), (pixel_height >> i).max(1));
+            levels.push(TextureLevel::read(&bytes, &mut image_offset, width, height, image_size));
+        }
+    }
+
+    let result = Ktx2Data { format, face_count, layers };
+    Ok(result)
+}
+
+#[inline]
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    let mut raw = [0_u8; 4];
+    raw.copy_from_slice(&bytes[offset..(offset + 4)]);
+    u32::from_le_bytes(raw)
+}
+
+#[inline]
+fn read_u64(bytes: &[u8], offset: usize) -> u64 {
+    let mut raw = [0_u8; 8];
+    raw.copy_from_slice(&bytes[offset..(offset + 8)]);
+    u64::from_le_bytes(raw)
+}
@@ -1,6 +1,8 @@
 
-pub use self::text::{TextInfo, TextID, TextType, TextHAlign};
+pub use self::text::{TextInfo, TextID, TextType, TextHAlign, TextShadow, FontAtlasConfig, WorldAnchor};
+pub use self::graph::{GraphID, MAXIMUM_GRAPH_VALUE_COUNT};
 
+mod graph;
 mod pipeline;
 mod text;
 
@@ -11,6 +13,9 @@ use crate::context::{VkDevice, VkSwapchain};
 use crate::command::{VkCmdRecorder, IGraphics, CmdGraphicsApi};
 use crate::ui::pipeline::UIPipelineAsset;
 use crate::ui::text::TextPool;
+use crate::ui::graph::GraphPool;
+use crate::utils::color::VkColor;
+use crate::{Mat4F, Vec3F};
 use crate::VkResult;
 
 
@@ -21,31 +26,48 @@ pub struct UIRenderer {
     pipeline_asset: UIPipelineAsset,
 
     text_pool: TextPool,
+    graph_pool: GraphPool,
 }
 
 impl UIRenderer {
 
     pub fn new(device: &mut VkDevice, swapchain: &VkSwapchain, renderpass: vk::RenderPass) -> VkResult<UIRenderer> {
+        UIRenderer::new_with_atlas(device, swapchain, renderpass, FontAtlasConfig::default())
+    }
+
+    /// Create the renderer using custom configuration of the font atlas(etc. mipmap levels and LOD bias).
+    pub fn new_with_atlas(device: &mut VkDevice, swapchain: &VkSwapchain, renderpass: vk::RenderPass, atlas: FontAtlasConfig) -> VkResult<UIRenderer> {
 
-        let text_pool = TextPool::new(device, swapchain.dimension)?;
+        let text_pool = TextPool::new(device, swapchain.dimension, swapchain.hidpi_factor, &atlas)?;
         let pipeline_asset = pipeline::UIPipelineAsset::new(device, swapchain, renderpass, text_pool.glyphs_ref())?;
+        let graph_pool = GraphPool::new(device, swapchain, text_pool.glyphs_ref().solid_uv())?;
 
-        let renderer = UIRenderer { pipeline_asset, text_pool };
+        let renderer = UIRenderer { pipeline_asset, text_pool, graph_pool };
         Ok(renderer)
     }
 
-    pub fn record_command(&self, recorder: &VkCmdRecorder<IGraphics>) {
+    /// Record the drawing commands of UI for the command buffer of swapchain image `image_index`.
+    pub fn record_command(&self, recorder: &VkCmdRecorder<IGraphics>, image_index: usize) {
 
         recorder.bind_pipeline(self.pipeline_asset.pipeline)
             .bind_descriptor_sets(self.pipeline_asset.pipeline_layout, 0, &[self.pipeline_asset.descriptor_set], &[]);
 
+        // draw graphs first, so that the texts are rendered over them.
+        self.graph_pool.record_command(recorder, image_index);
         self.text_pool.record_command(recorder);
     }
 
-    pub fn swapchain_reload(&mut self, device: &VkDevice, new_chain: &VkSwapchain, renderpass: vk::RenderPass) -> VkResult<()> {
+    /// Recreate the resources depending on swapchain, and grow the font atlas if any text uses characters missing from it.
+    ///
+    /// The device must be idle, and the commands recorded by `record_command` must be recorded again after this call.
+    pub fn swapchain_reload(&mut self, device: &mut VkDevice, new_chain: &VkSwapchain, renderpass: vk::RenderPass) -> VkResult<()> {
+
+        // the device is idle here, so it's a safe point to replace the glyph image.
+        self.update_glyph_atlas(device)?;
 
         self.pipeline_asset.swapchain_reload(device, new_chain, renderpass)?;
-        self.text_pool.swapchain_reload();
+        self.text_pool.swapchain_reload(new_chain.dimension, new_chain.hidpi_factor);
+        self.graph_pool.swapchain_reload(device, new_chain)?;
 
         Ok(())
     }
@@ -54,13 +76,74 @@ impl UIRenderer {
         self.text_pool.add_text(text)
     }
 
+    pub fn add_rich_text(&mut self, runs: &[(String, VkColor)], scale: f32, align: TextHAlign, location: vk::Offset2D) -> VkResult<TextID> {
+        self.text_pool.add_rich_text(runs, scale, align, location)
+    }
+
+    pub fn add_world_label(&mut self, text: TextInfo, anchor: WorldAnchor) -> VkResult<TextID> {
+        self.text_pool.add_world_label(text, anchor)
+    }
+
+    /// Project the world space labels to screen, which should be called each frame after the camera moved.
+    pub fn update_world_labels(&mut self, view_proj: Mat4F) {
+        self.text_pool.update_world_labels(view_proj);
+    }
+
+    pub fn change_world_label(&mut self, label: TextID, content: String, color: VkColor, position: Option<Vec3F>) {
+        self.text_pool.change_world_label(label, content, color, position);
+    }
+
     pub fn change_text(&mut self, content: String, update_text: TextID) {
         self.text_pool.change_text(content, update_text);
     }
 
+    /// Add a bar graph plotting `values` into `rect`(in logical pixels, the same unit as `TextInfo::location`), which is useful to display recent frame times.
+    ///
+    /// The bars are scaled so that the maximum value reaches the top of `rect`.
+    /// At most `MAXIMUM_GRAPH_VALUE_COUNT` values are plotted, and the last value is plotted at the right edge.
+    pub fn add_graph(&mut self, rect: vk::Rect2D, values: &[f32], color: VkColor) -> VkResult<GraphID> {
+        self.graph_pool.add_graph(rect, values, color)
+    }
+
+    /// Replace the values of graph, which can be called each frame to scroll the graph.
+    ///
+    /// The change is shown after `update_frame` is called for each swapchain image.
+    pub fn change_graph(&mut self, graph: GraphID, values: impl IntoIterator<Item = f32>) {
+        self.graph_pool.change_graph(graph, values);
+    }
+
+    /// Write the changed graphs for swapchain image `image_index`, whose commands are going to be submitted.
+    ///
+    /// Call it in `RenderWorkflow::render_frame`, where the previous submission of this image has completed,
+    /// so that the vertices used by other frames in flight are not overwritten.
+    pub fn update_frame(&mut self, image_index: usize) {
+        self.graph_pool.update_frame(image_index);
+    }
+
+    /// Check if any text uses characters that are not in the font atlas, which are rendered as space currently.
+    pub fn has_missing_glyphs(&self) -> bool {
+        self.text_pool.has_missing_glyphs()
+    }
+
+    /// Regenerate the font atlas to include the characters used by texts but missing from the atlas.
+    ///
+    /// The old atlas image is destroyed, so the device must be idle when calling this method.
+    /// Return true if the atlas has been regenerated, and in this case the commands recorded by `record_command` must be recorded again.
+    pub fn update_glyph_atlas(&mut self, device: &mut VkDevice) -> VkResult<bool> {
+
+        let is_updated = self.text_pool.update_glyph_atlas(device)?;
+        if is_updated {
+            self.pipeline_asset.update_glyphs(device, self.text_pool.glyphs_ref());
+            self.graph_pool.glyphs_reload(self.text_pool.glyphs_ref().solid_uv());
+        }
+
+        Ok(is_updated)
+    }
+
     pub fn discard_by(self, device: &mut VkDevice) -> VkResult<()> {
 
         self.pipeline_asset.discard(device);
+        self.graph_pool.discard(device);
         self.text_pool.discard_by(device)
     }
 }
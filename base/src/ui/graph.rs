@@ -0,0 +1,213 @@
+
+use ash::vk;
+
+use crate::context::{VkDevice, VkSwapchain};
+use crate::command::{VkCmdRecorder, IGraphics, CmdGraphicsApi};
+use crate::ui::text::{CharacterVertex, TextAttrStorage, VERTEX_PER_CHARACTER};
+use crate::utils::color::VkColor;
+use crate::{vkuint, vkptr, vkbytes};
+use crate::{VkResult, VkError};
+
+
+/// the maximum graph count that the buffer can contain.
+const MAXIMUM_GRAPH_COUNT: usize = 4;
+/// the maximum value count that a graph plots. Only the last values are plotted if a series is longer than this.
+pub const MAXIMUM_GRAPH_VALUE_COUNT: usize = 120;
+/// each value is plotted as a bar, which uses the same vertex count as a character.
+const VERTEX_PER_GRAPH: usize = MAXIMUM_GRAPH_VALUE_COUNT * VERTEX_PER_CHARACTER;
+/// the vertex count of one region, which contains all the graphs of a swapchain image.
+const VERTEX_PER_REGION: usize = MAXIMUM_GRAPH_COUNT * VERTEX_PER_GRAPH;
+
+pub type GraphID = usize;
+
+/// A bar graph plotting a series of values in a screen region.
+struct GraphInfo {
+    /// the region of graph in logical pixels, the same unit as the location of texts.
+    rect: vk::Rect2D,
+    values: Vec<f32>,
+    color: VkColor,
+}
+
+pub struct GraphPool {
+
+    /// screen dimension of current window, in physical pixels.
+    dimension: vk::Extent2D,
+    /// the ratio of physical pixels to logical pixels of current window.
+    hidpi_factor: f32,
+    /// the uv to sample the opaque texel of glyph image.
+    solid_uv: [f32; 2],
+
+    graphs: Vec<GraphInfo>,
+    /// the vertex buffer contains one region of all graphs for each swapchain image,
+    /// so that the region used by a frame in flight is never written by the host.
+    attributes: TextAttrStorage,
+    /// indicate whether the region of each swapchain image is out of date.
+    dirty_regions: Vec<bool>,
+}
+
+impl GraphPool {
+
+    pub fn new(device: &VkDevice, swapchain: &VkSwapchain, solid_uv: [f32; 2]) -> VkResult<GraphPool> {
+
+        let region_count = swapchain.frame_in_flight;
+        let attributes = TextAttrStorage::new(device, region_count * VERTEX_PER_REGION)?;
+
+        let result = GraphPool {
+            dimension: swapchain.dimension,
+            hidpi_factor: swapchain.hidpi_factor,
+            graphs: Vec::new(),
+            dirty_regions: vec![true; region_count],
+            solid_uv, attributes,
+        };
+        Ok(result)
+    }
+
+    pub fn add_graph(&mut self, rect: vk::Rect2D, values: &[f32], color: VkColor) -> VkResult<GraphID> {
+
+        if self.graphs.len() < MAXIMUM_GRAPH_COUNT {
+
+            let new_graph_id = self.graphs.len();
+            self.graphs.push(GraphInfo { rect, values: Vec::with_capacity(MAXIMUM_GRAPH_VALUE_COUNT), color });
+            self.change_graph(new_graph_id, values.iter().cloned());
+
+            Ok(new_graph_id)
+        } else {
+            Err(VkError::custom(format!("The graph pool can't contain more than {} graph.", MAXIMUM_GRAPH_COUNT)))
+        }
+    }
+
+    /// Replace the series of graph, where the last value is plotted at the right edge of graph.
+    ///
+    /// The vertices are not written until `update_frame` is called for each swapchain image.
+    pub fn change_graph(&mut self, graph: GraphID, values: impl IntoIterator<Item = f32>) {
+
+        let graph_values = &mut self.graphs[graph].values;
+        graph_values.clear();
+        graph_values.extend(values);
+
+        let exceed_count = graph_values.len().saturating_sub(MAXIMUM_GRAPH_VALUE_COUNT);
+        graph_values.drain(..exceed_count);
+
+        self.mark_dirty();
+    }
+
+    /// Write the graphs into the region of swapchain image `image_index` if they have been changed since its last update.
+    ///
+    /// Call it after the fence of the image is signaled, and before submitting the commands of the image.
+    pub fn update_frame(&mut self, image_index: usize) {
+
+        if self.graphs.is_empty() || !self.dirty_regions[image_index] {
+            return
+        }
+
+        // write the vertices to the mapped memory directly, so that no allocation happens each frame.
+        let region = unsafe {
+            let region_ptr = (self.attributes.data_ptr as vkptr<CharacterVertex>)
+                .offset((VERTEX_PER_REGION * image_index) as isize);
+            ::std::slice::from_raw_parts_mut(region_ptr, VERTEX_PER_REGION)
+        };
+
+        for (graph_index, graph_vertices) in region.chunks_mut(VERTEX_PER_GRAPH).take(self.graphs.len()).enumerate() {
+            self.write_graph(graph_index, graph_vertices);
+        }
+
+        self.dirty_regions[image_index] = false;
+    }
+
+    fn write_graph(&self, write_graph: GraphID, bar_vertices: &mut [CharacterVertex]) {
+
+        let graph = &self.graphs[write_graph];
+
+        // the bars are scaled so that the maximum value reaches the top of graph.
+        let max_value = graph.values.iter().cloned().fold(0.0, f32::max);
+        let max_value = if max_value > 0.0 { max_value } else { 1.0 };
+
+        let color: [f32; 4] = graph.color.into();
+        // convert the region of graph from logical pixels to physical pixels.
+        let scale = self.hidpi_factor;
+        let left = graph.rect.offset.x as f32 * scale;
+        let height = graph.rect.extent.height as f32 * scale;
+        let bar_width = graph.rect.extent.width as f32 * scale / MAXIMUM_GRAPH_VALUE_COUNT as f32;
+        let bottom = graph.rect.offset.y as f32 * scale + height;
+        // align the last value to the right edge, so that the graph scrolls leftwards as new values come.
+        let empty_bars = MAXIMUM_GRAPH_VALUE_COUNT - graph.values.len();
+
+        for (bar_index, bar) in bar_vertices.chunks_mut(VERTEX_PER_CHARACTER).enumerate() {
+
+            let bar_height = if bar_index < empty_bars {
+                0.0
+            } else {
+                let value = graph.values[bar_index - empty_bars].max(0.0);
+                height * value / max_value
+            };
+
+            // map the pixel position to range [-1.0, 1.0].
+            let min_x = (left + bar_width * bar_index as f32) / self.dimension.width as f32 * 2.0 - 1.0;
+            let max_x = (left + bar_width * (bar_index + 1) as f32) / self.dimension.width as f32 * 2.0 - 1.0;
+            let min_y = (bottom - bar_height) / self.dimension.height as f32 * 2.0 - 1.0;
+            let max_y = bottom / self.dimension.height as f32 * 2.0 - 1.0;
+
+            let vertex = |x: f32, y: f32| CharacterVertex {
+                pos: [x, y],
+                uv: self.solid_uv,
+                color,
+            };
+
+            bar.copy_from_slice(&[
+                vertex(min_x, min_y), vertex(min_x, max_y), vertex(max_x, max_y), // triangle 1
+                vertex(min_x, min_y), vertex(max_x, max_y), vertex(max_x, min_y), // triangle 2
+            ]);
+        }
+    }
+
+    /// Record the drawing of graphs in the region of swapchain image `image_index`.
+    pub fn record_command(&self, recorder: &VkCmdRecorder<IGraphics>, image_index: usize) {
+
+        if self.graphs.is_empty() {
+            return
+        }
+
+        let region_offset = (::std::mem::size_of::<CharacterVertex>() * VERTEX_PER_REGION * image_index) as vkbytes;
+        recorder.bind_vertex_buffers(0, &[self.attributes.buffer], &[region_offset]);
+
+        let vertex_count = (self.graphs.len() * VERTEX_PER_GRAPH) as vkuint;
+        recorder.draw(vertex_count, 1, 0, 0);
+    }
+
+    /// Regenerate all the graphs for the dimension, hidpi factor and image count of `new_chain`.
+    ///
+    /// The device must be idle, since the vertex buffer is recreated if the image count changes.
+    pub fn swapchain_reload(&mut self, device: &VkDevice, new_chain: &VkSwapchain) -> VkResult<()> {
+
+        self.dimension = new_chain.dimension;
+        self.hidpi_factor = new_chain.hidpi_factor;
+
+        let region_count = new_chain.frame_in_flight;
+        if region_count != self.dirty_regions.len() {
+
+            let new_attributes = TextAttrStorage::new(device, region_count * VERTEX_PER_REGION)?;
+            let old_attributes = ::std::mem::replace(&mut self.attributes, new_attributes);
+            old_attributes.discard(device);
+
+            self.dirty_regions = vec![true; region_count];
+        }
+
+        self.mark_dirty();
+        Ok(())
+    }
+
+    /// Regenerate all the graphs after the glyph image is regenerated.
+    pub fn glyphs_reload(&mut self, solid_uv: [f32; 2]) {
+
+        self.solid_uv = solid_uv;
+        self.mark_dirty();
+    }
+
+    fn mark_dirty(&mut self) {
+        self.dirty_regions.iter_mut().for_each(|is_dirty| *is_dirty = true);
+    }
+
+    pub fn discard(self, device: &VkDevice) {
+        self.attributes.discard(device);
+    }
+}
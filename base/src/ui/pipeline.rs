@@ -17,6 +17,9 @@ pub(super) struct UIPipelineAsset {
 
     pub pipeline: vk::Pipeline,
     pub pipeline_layout: vk::PipelineLayout,
+
+    /// true if the glyph image stores signed distance field, which is rendered by a different fragment shader.
+    is_distance_field: bool,
 }
 
 impl UIPipelineAsset {
@@ -24,13 +27,14 @@ impl UIPipelineAsset {
     pub fn new(device: &VkDevice, swapchain: &VkSwapchain, render_pass: vk::RenderPass, glyphs: &GlyphImages) -> VkResult<UIPipelineAsset> {
 
         let (desc_pool, desc_set, desc_set_layout) = setup_descriptor(device, glyphs)?;
-        let (pipeline, pipeline_layout) = prepare_pipelines(device, swapchain.dimension, render_pass, desc_set_layout)?;
+        let is_distance_field = glyphs.is_distance_field();
+        let (pipeline, pipeline_layout) = prepare_pipelines(device, swapchain.dimension, render_pass, desc_set_layout, is_distance_field)?;
 
         let result = UIPipelineAsset {
             descriptor_pool: desc_pool,
             descriptor_set: desc_set,
             descriptor_set_layout: desc_set_layout,
-            pipeline, pipeline_layout,
+            pipeline, pipeline_layout, is_distance_field,
         };
         Ok(result)
     }
@@ -40,13 +44,18 @@ impl UIPipelineAsset {
         device.discard(self.pipeline);
         device.discard(self.pipeline_layout);
 
-        let (pipeline, pipeline_layout) = prepare_pipelines(device, new_chain.dimension, renderpass, self.descriptor_set_layout)?;
+        let (pipeline, pipeline_layout) = prepare_pipelines(device, new_chain.dimension, renderpass, self.descriptor_set_layout, self.is_distance_field)?;
         self.pipeline = pipeline;
         self.pipeline_layout = pipeline_layout;
 
         Ok(())
     }
 
+    /// Point the descriptor set to the regenerated glyph image.
+    pub fn update_glyphs(&self, device: &VkDevice, glyphs: &GlyphImages) {
+        write_glyph_descriptor(device, self.descriptor_set, glyphs);
+    }
+
     pub fn discard(&self, device: &VkDevice) {
 
         device.discard(self.descriptor_set_layout);
@@ -60,7 +69,7 @@ impl UIPipelineAsset {
 fn setup_descriptor(device: &VkDevice, glyphs: &GlyphImages) -> VkResult<(vk::DescriptorPool, vk::DescriptorSet, vk::DescriptorSetLayout)> {
 
     use crate::ci::descriptor::{DescriptorPoolCI, DescriptorSetLayoutCI};
-    use crate::ci::descriptor::{DescriptorSetAI, DescriptorImageSetWI, DescriptorSetsUpdateCI};
+    use crate::ci::descriptor::DescriptorSetAI;
 
     // Descriptor Pool.
     let descriptor_pool = DescriptorPoolCI::new(1)
@@ -88,6 +97,15 @@ fn setup_descriptor(device: &VkDevice, glyphs: &GlyphImages) -> VkResult<(vk::De
         .build(device)?;
     let descriptor_set = descriptor_sets.remove(0);
 
+    write_glyph_descriptor(device, descriptor_set, glyphs);
+
+    Ok((descriptor_pool, descriptor_set, set_layout))
+}
+
+fn write_glyph_descriptor(device: &VkDevice, descriptor_set: vk::DescriptorSet, glyphs: &GlyphImages) {
+
+    use crate::ci::descriptor::{DescriptorImageSetWI, DescriptorSetsUpdateCI};
+
     // update descriptorsets.
     let sampled_image_write_info = DescriptorImageSetWI::new(descriptor_set, 0, vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
         .add_image(vk::DescriptorImageInfo {
@@ -99,11 +117,9 @@ fn setup_descriptor(device: &VkDevice, glyphs: &GlyphImages) -> VkResult<(vk::De
     DescriptorSetsUpdateCI::new()
         .add_write(&sampled_image_write_info)
         .update(device);
-
-    Ok((descriptor_pool, descriptor_set, set_layout))
 }
 
-fn prepare_pipelines(device: &VkDevice, dimension: vk::Extent2D, render_pass: vk::RenderPass, set_layout: vk::DescriptorSetLayout) -> VkResult<(vk::Pipeline, vk::PipelineLayout)> {
+fn prepare_pipelines(device: &VkDevice, dimension: vk::Extent2D, render_pass: vk::RenderPass, set_layout: vk::DescriptorSetLayout, is_distance_field: bool) -> VkResult<(vk::Pipeline, vk::PipelineLayout)> {
 
     use crate::ci::pipeline::*;
 
@@ -148,8 +164,13 @@ fn prepare_pipelines(device: &VkDevice, dimension: vk::Extent2D, render_pass: vk
         shaderc::ShaderKind::Vertex,
         "[Vertex Shader]",
         "main")?;
+    let frag_source = if is_distance_field {
+        include_str!("text_sdf.frag.glsl")
+    } else {
+        include_str!("text.frag.glsl")
+    };
     let frag_codes = shader_compiler.compile_from_str(
-        include_str!("text.frag.glsl"),
+        frag_source,
         shaderc::ShaderKind::Fragment,
         "[Fragment Shader]",
         "main")?;
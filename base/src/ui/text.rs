@@ -2,7 +2,7 @@
 use ash::vk;
 use memoffset::offset_of;
 
-use rusttype::{Rect, VMetrics, HMetrics};
+use rusttype::{Font, Rect, VMetrics, HMetrics};
 
 use std::ops::Range;
 use std::collections::HashMap;
@@ -19,22 +19,31 @@ use crate::context::VkDevice;
 use crate::command::{VkCmdRecorder, IGraphics, CmdGraphicsApi, CmdTransferApi};
 
 use crate::utils::color::VkColor;
-use crate::{vkuint, vkbytes, vkptr};
+use crate::{vkuint, vkfloat, vkbytes, vkptr};
+use crate::{Vec3F, Vec4F, Mat4F};
 use crate::{VkResult, VkError, VkErrorKind};
 
 
 /// each character use 6 vertices to draw.
-const VERTEX_PER_CHARACTER: usize = 6;
+pub(super) const VERTEX_PER_CHARACTER: usize = 6;
 /// the maximum sentence count that the buffer can contain.
 const MAXIMUM_SENTENCE_COUNT: usize = 10;
 /// the maximum character count that a sentence may contain.
 const MAXIMUM_SENTENCE_TEXT_COUNT: usize = 100;
+/// the vertex count reserved for each sentence, including the vertices of its characters and their shadows.
+const VERTEX_PER_SENTENCE: usize = MAXIMUM_SENTENCE_TEXT_COUNT * VERTEX_PER_CHARACTER * 2;
 /// Control the font size of sampled glyph.
 const FONT_SCALE: f32 = 48.0;
-/// A magic number.
-const DISPLAY_SCALE_FIX: f32 = 1.0 / 768.0;
 /// The padding attach to sampled glyph image.
 const IMAGE_PADDING: usize = 20;
+/// The maximum width of glyph image. The glyphs are placed in a new row once current row reaches this width.
+const ATLAS_ROW_WIDTH: usize = 2048;
+/// The extra pixels around each glyph sampled in distance field, which keeps the anti-aliased edge of glyph from being clipped.
+const DISTANCE_FIELD_MARGIN: i32 = 2;
+/// The size of the opaque square in glyph image, which is sampled to draw solid shapes(etc. graphs).
+const SOLID_BLOCK_SIZE: usize = 8;
+/// The format of glyph image.
+const GLYPH_FORMAT: vk::Format = vk::Format::R8_UNORM;
 
 pub type TextID = usize;
 type CharacterID = char;
@@ -43,10 +52,10 @@ type GlyphLayouts = HashMap<CharacterID, GlyphLayout>;
 /// The vertices attributes for each character.
 #[repr(C)]
 #[derive(Debug, Clone, Copy)]
-struct CharacterVertex {
-    pos   : [f32; 2],
-    uv    : [f32; 2],
-    color : [f32; 4],
+pub(super) struct CharacterVertex {
+    pub pos   : [f32; 2],
+    pub uv    : [f32; 2],
+    pub color : [f32; 4],
 }
 
 #[derive(Debug, Clone)]
@@ -59,40 +68,134 @@ struct GlyphLayout {
     bounding_box: Rect<f32>,
 }
 
+/// The configuration about how the glyph image(font atlas) is generated and sampled.
+#[derive(Debug, Clone)]
+pub struct FontAtlasConfig {
+
+    /// `mip_levels` is the number of mipmap levels generated for the glyph image, which improves the quality of minified text.
+    ///
+    /// Set to 1 to disable mipmapping. The value will be clamped to the length of full mip chain of the glyph image,
+    /// so `vkuint::max_value()` can be used to generate all the mip levels.
+    pub mip_levels: vkuint,
+    /// `mip_bias` is the bias added to the LOD calculation when sampling the glyph image.
+    ///
+    /// A negative value makes the small text sharper. It takes no effect if `mip_levels` is 1.
+    pub mip_bias: vkfloat,
+    /// `distance_field` is the spread(in pixels of glyph image) of signed distance field, which is stored in glyph image instead of the bitmap of glyphs.
+    ///
+    /// The distance field keeps the edge of text crisp at any scale. The spread will be clamped to half of the padding between glyphs.
+    /// Set to None to use bitmap glyphs.
+    pub distance_field: Option<vkfloat>,
+}
+
+impl Default for FontAtlasConfig {
+
+    fn default() -> FontAtlasConfig {
+        FontAtlasConfig {
+            mip_levels: 1,
+            mip_bias  : 0.0,
+            distance_field: None,
+        }
+    }
+}
+
 pub struct GlyphImages {
 
     pub text_sampler: vk::Sampler,
     pub glyph_image: VmaImage,
     pub glyph_view : vk::ImageView,
+    /// the number of mipmap levels of `glyph_image`.
+    pub mip_levels: vkuint,
+    /// the uv at the center of the opaque square in `glyph_image`.
+    solid_uv: [f32; 2],
 
     layouts: GlyphLayouts,
+    /// all the characters sampled in `glyph_image`, in the order they are placed.
+    characters: Vec<CharacterID>,
+    font: Font<'static>,
+    config: FontAtlasConfig,
 }
 
 impl GlyphImages {
 
-    pub fn from_font(device: &mut VkDevice, bytes: &[u8]) -> VkResult<GlyphImages> {
+    /// Generate the glyph image containing the space and printable ascii characters of the font.
+    pub fn from_font(device: &mut VkDevice, bytes: &[u8], config: &FontAtlasConfig) -> VkResult<GlyphImages> {
 
-        let (layouts, image_bytes, image_dimension) =
-            generate_ascii_glyphs_bytes(bytes, FONT_SCALE)?;
-        let glyph_image = allocate_glyph_image(device, image_bytes, image_dimension)?;
+        /// the ascii character range that render to sampled glyph.
+        const ASCII_RANGE: Range<u8> = (33..127_u8);
 
-        // Just store alpha value in the image.
-        let glyph_view = ImageViewCI::new(glyph_image.handle, vk::ImageViewType::TYPE_2D, vk::Format::R8_UNORM)
-            .sub_range(vk::ImageSubresourceRange {
-                aspect_mask: vk::ImageAspectFlags::COLOR,
-                base_mip_level: 0,
-                level_count   : 1,
-                base_array_layer: 0,
-                layer_count     : 1,
-            }).build(device)?;
+        let font = Font::from_bytes(bytes.to_vec())
+            .map_err(|e| VkError::custom(e.to_string()))?;
 
-        let text_sampler = SamplerCI::new()
-            .build(device)?;
+        let mut characters = vec![' '];
+        characters.extend(ASCII_RANGE.map(|ch| ch as CharacterID));
+
+        let (layouts, solid_uv, image_bytes, image_dimension) =
+            generate_glyphs_bytes(&font, &characters, FONT_SCALE, device.phy.limits.max_image_dimension2_d, config.distance_field)?;
+
+        let mip_levels = supported_mip_levels(device, config.mip_levels, image_dimension);
+        let glyph_image = allocate_glyph_image(device, image_bytes, image_dimension, mip_levels)?;
+        let glyph_view = create_glyph_view(device, glyph_image.handle, mip_levels)?;
 
-        let result = GlyphImages { text_sampler, glyph_image, glyph_view, layouts };
+        let text_sampler = glyph_sampler_ci(config).build(device)?;
+
+        let result = GlyphImages {
+            text_sampler, glyph_image, glyph_view, mip_levels, solid_uv, layouts, characters, font,
+            config: config.clone(),
+        };
         Ok(result)
     }
 
+    /// Check if the glyph image stores signed distance field rather than bitmap of glyphs.
+    pub fn is_distance_field(&self) -> bool {
+        self.config.distance_field.is_some()
+    }
+
+    /// Get the uv to sample the opaque texel in glyph image, which renders a quad in solid color.
+    pub fn solid_uv(&self) -> [f32; 2] {
+        self.solid_uv
+    }
+
+    /// Check if the glyph of `character` has been sampled in the glyph image.
+    pub fn contains(&self, character: char) -> bool {
+        self.layouts.contains_key(&character)
+    }
+
+    /// Regenerate the glyph image to contain `characters` in addition to the existing glyphs.
+    ///
+    /// The glyph layouts are replaced, since all the glyphs are repacked in the new image.
+    /// `text_sampler` is kept, so that the descriptor set layout using it as immutable sampler is still valid,
+    /// and it's valid for any mip count since its max LOD is not clamped.
+    /// The old image is destroyed immediately, so it must not be in use by device.
+    fn extend_characters(&mut self, device: &mut VkDevice, characters: &[CharacterID]) -> VkResult<()> {
+
+        let mut new_characters = self.characters.clone();
+        for &character in characters.iter() {
+            if !new_characters.contains(&character) {
+                new_characters.push(character);
+            }
+        }
+
+        let (layouts, solid_uv, image_bytes, image_dimension) =
+            generate_glyphs_bytes(&self.font, &new_characters, FONT_SCALE, device.phy.limits.max_image_dimension2_d, self.config.distance_field)?;
+
+        let mip_levels = supported_mip_levels(device, self.config.mip_levels, image_dimension);
+        let glyph_image = allocate_glyph_image(device, image_bytes, image_dimension, mip_levels)?;
+        let glyph_view = create_glyph_view(device, glyph_image.handle, mip_levels)?;
+
+        device.discard(self.glyph_view);
+        let old_image = ::std::mem::replace(&mut self.glyph_image, glyph_image);
+        device.vma_discard(old_image)?;
+
+        self.glyph_view = glyph_view;
+        self.mip_levels = mip_levels;
+        self.solid_uv = solid_uv;
+        self.layouts = layouts;
+        self.characters = new_characters;
+
+        Ok(())
+    }
+
     pub fn discard(self, device: &mut VkDevice) -> VkResult<()> {
 
         device.discard(self.text_sampler);
@@ -101,19 +204,20 @@ impl GlyphImages {
     }
 }
 
-struct TextAttrStorage {
+pub(super) struct TextAttrStorage {
     /// the starting pointer of the memory of text attributes.
-    data_ptr: vkptr,
+    pub data_ptr: vkptr,
     /// the buffer which store the text attributes.
-    buffer: vk::Buffer,
+    pub buffer: vk::Buffer,
     memory: vk::DeviceMemory,
 }
 
 impl TextAttrStorage {
 
-    fn new(device: &VkDevice) -> VkResult<TextAttrStorage> {
+    /// Allocate the host visible vertex buffer which contains `vertex_count` vertices.
+    pub fn new(device: &VkDevice, vertex_count: usize) -> VkResult<TextAttrStorage> {
 
-        let pool_size = (::std::mem::size_of::<CharacterVertex>() * MAXIMUM_SENTENCE_COUNT * MAXIMUM_SENTENCE_TEXT_COUNT * VERTEX_PER_CHARACTER) as vkbytes;
+        let pool_size = (::std::mem::size_of::<CharacterVertex>() * vertex_count) as vkbytes;
         let (buffer, requirement) = BufferCI::new(pool_size)
             .usage(vk::BufferUsageFlags::VERTEX_BUFFER)
             .build(device)?;
@@ -129,7 +233,7 @@ impl TextAttrStorage {
         Ok(result)
     }
 
-    fn discard(self, device: &VkDevice) {
+    pub fn discard(self, device: &VkDevice) {
 
         device.unmap_memory(self.memory);
         device.discard(self.buffer);
@@ -140,49 +244,106 @@ impl TextAttrStorage {
 
 pub struct TextPool {
 
-    /// screen dimension of current window.
+    /// screen dimension of current window, in physical pixels.
     dimension: vk::Extent2D,
     // the aspect ratio of current screen dimension.
     aspect_ratio: f32,
+    /// the ratio of physical pixels to logical pixels of current window.
+    hidpi_factor: f32,
 
     /// all the texts to be rendered.
     texts: Vec<TextInfo>,
+    /// the color runs of each text in `texts`, which is empty if the whole text uses `TextInfo::color`.
+    color_runs: Vec<Vec<ColorRun>>,
+    /// the world space anchor of each text in `texts`, which is None for screen space text.
+    world_anchors: Vec<Option<AnchorState>>,
     /// `attributes` contains the resource for rendering texts.
     attributes: TextAttrStorage,
     /// `glyph_layouts` records the layout information to generate text attributes.
     glyphs: GlyphImages,
+    /// the characters used by texts but not yet sampled in `glyphs`, which are rendered as space until the atlas is updated.
+    ///
+    /// The atlas is updated by `update_glyph_atlas`, which `UIRenderer::swapchain_reload` calls automatically.
+    missing_glyphs: Vec<CharacterID>,
 }
 
 pub struct TextInfo {
-    /// `content` is the content of text to render.
+    /// `content` is the content of text to render, which may contain any Unicode character supported by the font.
+    ///
+    /// The characters that are not in the font atlas are rendered after the atlas is updated(see `UIRenderer::update_glyph_atlas`),
+    /// and the characters that the font does not support are rendered as its `.notdef` glyph.
     pub content: String,
-    /// `scale` defines the font size of this text.
+    /// `scale` defines the font size of this text in logical pixels, which is converted to physical pixels by the hidpi factor of window.
+    ///
+    /// So the text keeps the same apparent size on HiDPI displays and after the window is resized.
     pub scale  : f32,
     /// `align` the align method for this text.
     pub align  : TextHAlign,
     /// `color` is color value of this text.
     pub color  : VkColor,
-    /// `location` is the starting position of the first character.
+    /// `location` is the starting position of the first character in logical pixels(see `VkSwapchain::logical_dimension`), the same unit as `scale`.
     pub location: vk::Offset2D,
+    /// `pivot` is the point of text box placed at `location`, in fractions of the text width and height.
+    ///
+    /// (0.0, 0.0) is the top-left corner of text(the default behavior) and (0.5, 0.5) centers the text at `location`.
+    pub pivot: (f32, f32),
+    /// `shadow` is the drop shadow rendered behind the text, which improves the legibility over bright background.
+    ///
+    /// Set to None to render the text without shadow.
+    pub shadow: Option<TextShadow>,
 
     pub r#type: TextType,
 }
 
+impl Default for TextInfo {
+
+    fn default() -> TextInfo {
+        TextInfo {
+            content: String::new(),
+            scale: 20.0,
+            align: TextHAlign::Left,
+            color: VkColor::WHITE,
+            location: vk::Offset2D { x: 0, y: 0 },
+            pivot: (0.0, 0.0),
+            shadow: None,
+            r#type: TextType::Static,
+        }
+    }
+}
+
+/// The drop shadow of a text, which is a copy of the text rendered behind it.
+#[derive(Debug, Clone, Copy)]
+pub struct TextShadow {
+    /// `offset` is the translation of shadow relative to its text in logical pixels, where positive y moves the shadow down.
+    pub offset: (f32, f32),
+    /// `color` is the color of shadow.
+    pub color: VkColor,
+}
+
+impl Default for TextShadow {
+
+    fn default() -> TextShadow {
+        TextShadow {
+            offset: (1.0, 1.0),
+            color: VkColor::BLACK,
+        }
+    }
+}
+
 pub enum TextType {
     /// Render static text to screen. The text can not change after first set.
     Static,
     /// Render text that is dynamically changed in runtime.
     ///
-    /// `capacity` is the maximum length of the text to rendering.
+    /// `capacity` is the maximum number of characters of the text to rendering.
     ///
     /// Use `change_text` method to set the content of text in runtime.
     Dynamic { capacity: usize },
 }
 
 pub struct TextIter<'a> {
-    content: &'a [u8],
+    content: ::std::str::Chars<'a>,
     current: usize,
-    len: usize,
     capacity: usize,
 }
 
@@ -191,14 +352,13 @@ impl<'a> Iterator for TextIter<'a> {
 
     fn next(&mut self) -> Option<Self::Item> {
 
-        let result = if self.current < self.len {
-            Some(self.content[self.current] as char)
-        } else {
-            if self.current < self.capacity {
+        let result = match self.content.next() {
+            | Some(ch) => Some(ch),
+            | None => if self.current < self.capacity {
                 None
             } else {
                 return None
-            }
+            },
         };
 
         self.current += 1;
@@ -208,24 +368,78 @@ impl<'a> Iterator for TextIter<'a> {
 
 impl TextInfo {
 
+    /// Get the number of characters(Unicode scalar values) in `content`.
+    fn char_count(&self) -> usize {
+        self.content.chars().count()
+    }
+
     fn iter(&self) -> TextIter {
 
-        let char_bytes = self.content.as_bytes();
-        let bytes_length = char_bytes.len();
+        let capacity = match self.r#type {
+            | TextType::Static => self.char_count(),
+            | TextType::Dynamic { capacity } => capacity,
+        };
 
-        let mut iter = TextIter {
-            content: char_bytes,
+        TextIter {
+            content: self.content.chars(),
             current: 0,
-            len: bytes_length,
-            capacity: 0,
-        };
+            capacity,
+        }
+    }
+}
 
-        iter.capacity = match self.r#type {
-            | TextType::Static => bytes_length,
-            | TextType::Dynamic { capacity } => capacity,
-        };
-        iter
+/// A contiguous range of characters in a rich text sharing the same color.
+struct ColorRun {
+    /// the index of the character next to the last character of this run, counted in characters rather than bytes.
+    end: usize,
+    color: VkColor,
+}
+
+/// Concatenate the content of `runs`, and get the color run of each of them.
+fn layout_color_runs(runs: &[(String, VkColor)]) -> (String, Vec<ColorRun>) {
+
+    let mut color_runs = Vec::with_capacity(runs.len());
+    let mut content = String::new();
+    let mut char_count = 0;
+
+    for (run_content, run_color) in runs.iter() {
+        content.push_str(run_content);
+        char_count += run_content.chars().count();
+        color_runs.push(ColorRun { end: char_count, color: *run_color });
     }
+
+    (content, color_runs)
+}
+
+/// Find the color of the run containing the character at `character_index`, or `default` if it's not in any run.
+fn run_color(color_runs: &[ColorRun], character_index: usize, default: VkColor) -> VkColor {
+
+    color_runs.iter()
+        .find(|run| character_index < run.end)
+        .map(|run| run.color)
+        .unwrap_or(default)
+}
+
+/// The position in world space which a text label is attached to.
+#[derive(Debug, Clone)]
+pub struct WorldAnchor {
+    /// `position` is the world space position of this label.
+    pub position: Vec3F,
+    /// `reference_distance` is the distance from camera at which the label is rendered in its original scale.
+    ///
+    /// If it is None, the label keeps constant size on screen regardless of its distance.
+    pub reference_distance: Option<f32>,
+}
+
+/// The projected state of a world space text.
+struct AnchorState {
+    anchor: WorldAnchor,
+    /// the scale of text when it was added.
+    base_scale: f32,
+    /// false if the anchor is behind the camera.
+    is_visible: bool,
+    /// true if the label is explicitly hidden by `change_world_label`.
+    is_hidden: bool,
 }
 
 /// The horizontal align of a specific text.
@@ -237,17 +451,20 @@ pub enum TextHAlign {
 
 impl TextPool {
 
-    pub fn new(device: &mut VkDevice, dimension: vk::Extent2D) -> VkResult<TextPool> {
+    pub fn new(device: &mut VkDevice, dimension: vk::Extent2D, hidpi_factor: f32, atlas: &FontAtlasConfig) -> VkResult<TextPool> {
 
-        let attributes = TextAttrStorage::new(device)?;
+        let attributes = TextAttrStorage::new(device, MAXIMUM_SENTENCE_COUNT * VERTEX_PER_SENTENCE)?;
 
         let font_bytes = include_bytes!("../../../assets/fonts/Roboto-Regular.ttf");
-        let glyphs = GlyphImages::from_font(device, font_bytes)?;
+        let glyphs = GlyphImages::from_font(device, font_bytes, atlas)?;
 
         let result = TextPool {
             texts: Vec::new(),
+            color_runs: Vec::new(),
+            world_anchors: Vec::new(),
+            missing_glyphs: Vec::new(),
             aspect_ratio: dimension.width as f32 / dimension.height as f32,
-            attributes, glyphs, dimension,
+            attributes, glyphs, dimension, hidpi_factor,
         };
         Ok(result)
     }
@@ -255,12 +472,13 @@ impl TextPool {
     pub fn add_text(&mut self, mut text: TextInfo) -> VkResult<TextID> {
 
         if self.texts.len() < MAXIMUM_SENTENCE_COUNT {
-            if text.content.len() <= MAXIMUM_SENTENCE_TEXT_COUNT {
-
-                text.scale *= DISPLAY_SCALE_FIX / FONT_SCALE;
+            if text.char_count() <= MAXIMUM_SENTENCE_TEXT_COUNT {
 
                 let new_text_id = self.texts.len();
                 self.texts.push(text);
+                self.color_runs.push(Vec::new());
+                self.world_anchors.push(None);
+                self.record_missing_glyphs(new_text_id);
                 // update the text that is newly added.
                 self.update_texts(new_text_id);
 
@@ -273,78 +491,163 @@ impl TextPool {
         }
     }
 
+    /// Add a static text composed of several runs, where each run is rendered in its own color.
+    ///
+    /// The runs are laid out contiguously in the order of `runs`.
+    pub fn add_rich_text(&mut self, runs: &[(String, VkColor)], scale: f32, align: TextHAlign, location: vk::Offset2D) -> VkResult<TextID> {
+
+        let (content, color_runs) = layout_color_runs(runs);
+
+        let text = TextInfo {
+            content, scale, align, location,
+            pivot: (0.0, 0.0),
+            shadow: None,
+            color: runs.first().map(|run| run.1).unwrap_or(VkColor::WHITE),
+            r#type: TextType::Static,
+        };
+
+        let new_text_id = self.add_text(text)?;
+        self.color_runs[new_text_id] = color_runs;
+        // update again to apply the color of each run.
+        self.update_texts(new_text_id);
+
+        Ok(new_text_id)
+    }
+
+    /// Add a text label attached to a world space position.
+    ///
+    /// The `location` of text is ignored. Call `update_world_labels` each frame to project the label to screen.
+    /// Set the `pivot` of text to (0.5, 0.5) to center the label at its anchor.
+    pub fn add_world_label(&mut self, text: TextInfo, anchor: WorldAnchor) -> VkResult<TextID> {
+
+        let new_text_id = self.add_text(text)?;
+
+        // keep the label hidden until it is projected.
+        self.world_anchors[new_text_id] = Some(AnchorState {
+            anchor,
+            base_scale: self.texts[new_text_id].scale,
+            is_visible: false,
+            is_hidden : false,
+        });
+        self.update_texts(new_text_id);
+
+        Ok(new_text_id)
+    }
+
+    /// Project all the world space labels to screen by `view_proj`(the product of projection and view matrix of camera).
+    ///
+    /// The labels behind the camera will be hidden.
+    pub fn update_world_labels(&mut self, view_proj: Mat4F) {
+
+        for text_id in 0..self.texts.len() {
+
+            let state = match self.world_anchors[text_id] {
+                | Some(ref mut state) => state,
+                | None => continue,
+            };
+
+            let logical_dimension = (self.dimension.width as f32 / self.hidpi_factor, self.dimension.height as f32 / self.hidpi_factor);
+            let projection = project_to_screen(view_proj, state.anchor.position, logical_dimension);
+
+            state.is_visible = !state.is_hidden && projection.is_some();
+
+            if let (true, Some((location, depth))) = (state.is_visible, projection) {
+
+                let text = &mut self.texts[text_id];
+                text.location = location;
+
+                text.scale = match state.anchor.reference_distance {
+                    | Some(reference_distance) => state.base_scale * reference_distance / depth,
+                    | None => state.base_scale,
+                };
+            }
+
+            self.update_texts(text_id);
+        }
+    }
+
+    /// Change the content, color and anchor position of a world space label.
+    ///
+    /// The label is hidden if `position` is None. The new position takes effect in next `update_world_labels` call.
+    pub fn change_world_label(&mut self, label: TextID, content: String, color: VkColor, position: Option<Vec3F>) {
+
+        if let Some(ref mut state) = self.world_anchors[label] {
+
+            match position {
+                | Some(position) => {
+                    state.anchor.position = position;
+                    state.is_hidden = false;
+                },
+                | None => {
+                    state.is_hidden = true;
+                    state.is_visible = false;
+                },
+            }
+        }
+
+        self.texts[label].color = color;
+        self.change_text(content, label);
+    }
+
+    /// Change the content of text.
+    ///
+    /// If the text was added by `add_rich_text`, its color runs are discarded and the whole text will use the color of its first run.
     pub fn change_text(&mut self, content: String, update_text: TextID) {
 
         self.texts[update_text].content = content;
+        self.color_runs[update_text].clear();
+        self.record_missing_glyphs(update_text);
         self.update_texts(update_text);
     }
 
-    fn update_texts(&self, update_text: TextID) {
+    /// Check if there is any character used by texts but not sampled in the font atlas.
+    pub fn has_missing_glyphs(&self) -> bool {
+        !self.missing_glyphs.is_empty()
+    }
 
-        // calculate vertices attributes of rendering texts.
-        let mut char_vertices = Vec::with_capacity(self.texts.len() * MAXIMUM_SENTENCE_TEXT_COUNT * VERTEX_PER_CHARACTER);
+    /// Add the missing characters of texts to the font atlas, and regenerate all the texts by the new glyph layouts.
+    ///
+    /// Return true if the glyph image has been regenerated.
+    pub fn update_glyph_atlas(&mut self, device: &mut VkDevice) -> VkResult<bool> {
 
-        let text = &self.texts[update_text];
+        if self.missing_glyphs.is_empty() {
+            return Ok(false)
+        }
 
-        let mut origin_x = text.location.x as f32 / self.dimension.width as f32;
-        let origin_y = text.location.y as f32 / self.dimension.height as f32;
+        self.glyphs.extend_characters(device, &self.missing_glyphs)?;
+        self.missing_glyphs.clear();
 
-        for ch in text.iter() {
+        for i in 0..self.texts.len() {
+            self.update_texts(i);
+        }
+
+        Ok(true)
+    }
 
-            // use ' '(space) character instead if all the characters of current text has been rendered, but not yet reached its capacity.
-            let character_id = ch.unwrap_or(' ');
+    fn record_missing_glyphs(&mut self, text_id: TextID) {
 
-            let glyph_layout = self.glyphs.layouts.get(&character_id)
-                .expect(&format!("Find invalid character: {}({}).", character_id, character_id as u8));
+        for character in self.texts[text_id].iter().flatten() {
+            if !self.glyphs.contains(character) && !self.missing_glyphs.contains(&character) {
+                self.missing_glyphs.push(character);
+            }
+        }
+    }
 
-            let x_offset     = glyph_layout.bounding_box.min.x    * text.scale;
-            let y_offset     = glyph_layout.bounding_box.min.y    * text.scale * self.aspect_ratio;
-            let glyph_width  = glyph_layout.bounding_box.width()  * text.scale;
-            let glyph_height = glyph_layout.bounding_box.height() * text.scale * self.aspect_ratio;
+    fn update_texts(&self, update_text: TextID) {
 
-            // the x coordinate of top-left position(map to range [-1.0, 1.0]).
-            let min_x = (origin_x + x_offset) * 2.0 - 1.0;
-            // the y coordinate of top-left position.(map to range [-1.0, 1.0]).
-            let min_y = (origin_y + y_offset) * 2.0 - 1.0;
-            // the x coordinate of bottom-right position(map to range [-1.0, 1.0]).
-            let max_x = (origin_x + glyph_width + x_offset) * 2.0 - 1.0;
-            // the y coordinate of bottom-right position(map to range [-1.0, 1.0]).
-            let max_y = (origin_y + glyph_height + y_offset) * 2.0 - 1.0;
+        let text = &self.texts[update_text];
+        let color_runs = &self.color_runs[update_text];
 
-            let top_left = CharacterVertex {
-                pos: [min_x, min_y],
-                uv: glyph_layout.min_uv,
-                color: text.color.into(),
-            };
-            let bottom_left = CharacterVertex {
-                pos: [min_x, max_y],
-                uv: [
-                    glyph_layout.min_uv[0],
-                    glyph_layout.max_uv[1],
-                ],
-                color: text.color.into(),
-            };
-            let bottom_right = CharacterVertex {
-                pos: [max_x, max_y],
-                uv: glyph_layout.max_uv,
-                color: text.color.into(),
-            };
-            let top_right = CharacterVertex {
-                pos: [max_x, min_y],
-                uv: [
-                    glyph_layout.max_uv[0],
-                    glyph_layout.min_uv[1],
-                ],
-                color: text.color.into(),
-            };
+        // convert the glyph metrics of sampled font to the fraction of screen width.
+        let glyph_scale = text.scale * self.hidpi_factor / (FONT_SCALE * self.dimension.width as f32);
 
-            char_vertices.extend_from_slice(&[
-                top_left, bottom_left, bottom_right, // triangle 1
-                top_left, bottom_right, top_right,   // triangle 2
-            ]);
+        // convert the location from logical pixels to the fraction of screen size.
+        let location_x = text.location.x as f32 * self.hidpi_factor / self.dimension.width as f32;
+        let origin_y = text.location.y as f32 * self.hidpi_factor / self.dimension.height as f32;
 
-            origin_x += glyph_layout.h_metrics.advance_width * text.scale;
-        }
+        // calculate vertices attributes of rendering texts.
+        let CharacterLayout { vertices: mut char_vertices, end_x: origin_x, content_end_x, content_count } =
+            layout_characters(text, color_runs, &self.glyphs.layouts, (location_x, origin_y), glyph_scale, self.aspect_ratio);
 
         // adjust the position of each vertices to make text alignment.
         match text.align {
@@ -353,14 +656,14 @@ impl TextPool {
             },
             | TextHAlign::Center => {
                 // move the text the center position.
-                let text_half_length = origin_x - text.location.x as f32 / self.dimension.width as f32;
+                let text_half_length = origin_x - location_x;
                 for char_vertex in char_vertices.iter_mut() {
                     char_vertex.pos[0] -= text_half_length;
                 }
             },
             | TextHAlign::Right => {
                 // make text align to right.
-                let text_half_length = origin_x - text.location.x as f32 / self.dimension.width as f32;
+                let text_half_length = origin_x - location_x;
                 let text_length = text_half_length * 2.0;
                 for char_vertex in char_vertices.iter_mut() {
                     char_vertex.pos[0] -= text_length; // pos[0] is the x coordinate.
@@ -368,10 +671,44 @@ impl TextPool {
             },
         }
 
+        // move the text to make its pivot placed at the location.
+        if text.pivot != (0.0, 0.0) {
+            // the width of text box(in range [0.0, 2.0]).
+            let content_width = (content_end_x - location_x) * 2.0;
+            place_pivot(&mut char_vertices, content_count, origin_y * 2.0 - 1.0, content_width, text.pivot);
+        }
+
+        // prepend the shadow vertices, so that the shadow is drawn behind the text.
+        if let Some(ref shadow) = text.shadow {
+
+            // convert the offset of shadow from logical pixels to range [0.0, 2.0].
+            let offset_x = shadow.offset.0 * self.hidpi_factor / self.dimension.width  as f32 * 2.0;
+            let offset_y = shadow.offset.1 * self.hidpi_factor / self.dimension.height as f32 * 2.0;
+            let shadow_color: [f32; 4] = shadow.color.into();
+
+            let shadow_vertices: Vec<CharacterVertex> = char_vertices.iter()
+                .map(|char_vertex| CharacterVertex {
+                    pos: [char_vertex.pos[0] + offset_x, char_vertex.pos[1] + offset_y],
+                    uv: char_vertex.uv,
+                    color: shadow_color,
+                }).collect();
+
+            char_vertices.splice(0..0, shadow_vertices);
+        }
+
+        // collapse all the vertices to a point to hide the text.
+        if let Some(ref state) = self.world_anchors[update_text] {
+            if !state.is_visible {
+                for char_vertex in char_vertices.iter_mut() {
+                    char_vertex.pos = [0.0, 0.0];
+                }
+            }
+        }
+
         // upload vertices attributes to memory.
         unsafe {
             let target_ptr = (self.attributes.data_ptr as vkptr<CharacterVertex>)
-                .offset((MAXIMUM_SENTENCE_TEXT_COUNT * VERTEX_PER_CHARACTER * update_text) as isize);
+                .offset((VERTEX_PER_SENTENCE * update_text) as isize);
             target_ptr.copy_from(char_vertices.as_ptr(), char_vertices.len());
         }
     }
@@ -384,16 +721,23 @@ impl TextPool {
         for text in self.texts.iter() {
 
             let character_count = match text.r#type {
-                | TextType::Static => text.content.len(),
+                | TextType::Static => text.char_count(),
                 | TextType::Dynamic { capacity } => capacity,
             };
-            let render_vertex_count = (character_count * VERTEX_PER_CHARACTER) as vkuint;
+            // the text with shadow is drawn twice.
+            let layer_count = if text.shadow.is_some() { 2 } else { 1 };
+            let render_vertex_count = (character_count * VERTEX_PER_CHARACTER * layer_count) as vkuint;
             recorder.draw(render_vertex_count, 1, first_vertex, 0);
-            first_vertex += (MAXIMUM_SENTENCE_TEXT_COUNT * VERTEX_PER_CHARACTER) as vkuint;
+            first_vertex += VERTEX_PER_SENTENCE as vkuint;
         }
     }
 
-    pub fn swapchain_reload(&mut self) {
+    /// Regenerate all the texts for the new `dimension` and `hidpi_factor` of window, so that they keep their size in logical pixels.
+    pub fn swapchain_reload(&mut self, dimension: vk::Extent2D, hidpi_factor: f32) {
+
+        self.dimension = dimension;
+        self.aspect_ratio = dimension.width as f32 / dimension.height as f32;
+        self.hidpi_factor = hidpi_factor;
 
         for i in 0..self.texts.len() {
             self.update_texts(i);
@@ -411,47 +755,233 @@ impl TextPool {
     }
 }
 
-fn generate_ascii_glyphs_bytes(font_bytes: &[u8], font_scale: f32) -> VkResult<(GlyphLayouts, Vec<u8>, vk::Extent2D)> {
+/// The vertices of the characters of a text, placed from its location before the text is aligned.
+struct CharacterLayout {
+    vertices: Vec<CharacterVertex>,
+    /// the x coordinate next to the last character, including the spaces padded to the capacity of dynamic text.
+    end_x: f32,
+    /// the x coordinate next to the last character of content.
+    content_end_x: f32,
+    /// the number of characters of content, excluding the padding spaces.
+    content_count: usize,
+}
+
+/// Generate the vertices of each character in `text`, where `origin` is the location of text in the fraction of screen size.
+///
+/// Each character is colored by the run containing it, or by `TextInfo::color` if it's not in any of `color_runs`.
+fn layout_characters(text: &TextInfo, color_runs: &[ColorRun], layouts: &GlyphLayouts, origin: (f32, f32), glyph_scale: f32, aspect_ratio: f32) -> CharacterLayout {
+
+    let mut char_vertices = Vec::with_capacity(text.char_count() * VERTEX_PER_CHARACTER);
+
+    let (mut origin_x, origin_y) = origin;
+    let mut content_end_x = origin_x;
+    let mut content_count = 0;
+
+    for (character_index, ch) in text.iter().enumerate() {
+
+        // use ' '(space) character instead if all the characters of current text has been rendered, but not yet reached its capacity.
+        let character_id = ch.unwrap_or(' ');
+
+        let character_color: [f32; 4] = run_color(color_runs, character_index, text.color).into();
 
-    use rusttype::{Font, Scale, PositionedGlyph, point};
+        // the character missing from font atlas is rendered as space, until it is added by `update_glyph_atlas`.
+        let glyph_layout = layouts.get(&character_id)
+            .unwrap_or_else(|| &layouts[&' ']);
 
-    /// the ascii character range that render to sampled glyph.
-    const ASCII_RANGE: Range<u8> = (33..127_u8);
+        let x_offset     = glyph_layout.bounding_box.min.x    * glyph_scale;
+        let y_offset     = glyph_layout.bounding_box.min.y    * glyph_scale * aspect_ratio;
+        let glyph_width  = glyph_layout.bounding_box.width()  * glyph_scale;
+        let glyph_height = glyph_layout.bounding_box.height() * glyph_scale * aspect_ratio;
 
-    let font = Font::from_bytes(font_bytes)
-        .map_err(|e| VkError::custom(e.to_string()))?;
-    let ascii_bytes: Vec<u8> = ASCII_RANGE.collect();
+        // the x coordinate of top-left position(map to range [-1.0, 1.0]).
+        let min_x = (origin_x + x_offset) * 2.0 - 1.0;
+        // the y coordinate of top-left position.(map to range [-1.0, 1.0]).
+        let min_y = (origin_y + y_offset) * 2.0 - 1.0;
+        // the x coordinate of bottom-right position(map to range [-1.0, 1.0]).
+        let max_x = (origin_x + glyph_width + x_offset) * 2.0 - 1.0;
+        // the y coordinate of bottom-right position(map to range [-1.0, 1.0]).
+        let max_y = (origin_y + glyph_height + y_offset) * 2.0 - 1.0;
 
-    let ascii_characters = unsafe { String::from_utf8_unchecked(ascii_bytes.clone()) };
+        let top_left = CharacterVertex {
+            pos: [min_x, min_y],
+            uv: glyph_layout.min_uv,
+            color: character_color,
+        };
+        let bottom_left = CharacterVertex {
+            pos: [min_x, max_y],
+            uv: [
+                glyph_layout.min_uv[0],
+                glyph_layout.max_uv[1],
+            ],
+            color: character_color,
+        };
+        let bottom_right = CharacterVertex {
+            pos: [max_x, max_y],
+            uv: glyph_layout.max_uv,
+            color: character_color,
+        };
+        let top_right = CharacterVertex {
+            pos: [max_x, min_y],
+            uv: [
+                glyph_layout.max_uv[0],
+                glyph_layout.min_uv[1],
+            ],
+            color: character_color,
+        };
+
+        char_vertices.extend_from_slice(&[
+            top_left, bottom_left, bottom_right, // triangle 1
+            top_left, bottom_right, top_right,   // triangle 2
+        ]);
+
+        origin_x += glyph_layout.h_metrics.advance_width * glyph_scale;
+
+        if ch.is_some() {
+            content_end_x = origin_x;
+            content_count += 1;
+        }
+    }
+
+    CharacterLayout {
+        vertices: char_vertices,
+        end_x: origin_x,
+        content_end_x, content_count,
+    }
+}
+
+/// Project the world space `position` to screen position in logical pixels, where `logical_dimension` is the size of screen in logical pixels.
+///
+/// Return the screen position and the view space depth of `position`, or None if `position` is behind the camera.
+fn project_to_screen(view_proj: Mat4F, position: Vec3F, logical_dimension: (f32, f32)) -> Option<(vk::Offset2D, f32)> {
+
+    let clip_pos = view_proj * Vec4F::new(position.x, position.y, position.z, 1.0);
+
+    // w is the view space depth of this point for perspective projection.
+    if clip_pos.w <= 0.0 {
+        return None
+    }
+
+    // map the normalized device coordinate to screen position.
+    let ndc_x = clip_pos.x / clip_pos.w;
+    let ndc_y = clip_pos.y / clip_pos.w;
+    let location = vk::Offset2D {
+        x: ((ndc_x + 1.0) * 0.5 * logical_dimension.0) as i32,
+        y: ((ndc_y + 1.0) * 0.5 * logical_dimension.1) as i32,
+    };
+    Some((location, clip_pos.w))
+}
+
+/// Move `char_vertices` to make the `pivot` of text box placed at the location of text.
+///
+/// The text box starts from the top line `top`(in range [-1.0, 1.0]) and spans `content_width` horizontally,
+/// and its height covers the first `content_count` characters, so the spaces padded to the capacity of dynamic text are excluded.
+fn place_pivot(char_vertices: &mut [CharacterVertex], content_count: usize, top: f32, content_width: f32, pivot: (f32, f32)) {
+
+    let content_height = char_vertices.iter()
+        .take(content_count * VERTEX_PER_CHARACTER)
+        .map(|char_vertex| char_vertex.pos[1])
+        .fold(top, f32::max) - top;
+
+    for char_vertex in char_vertices.iter_mut() {
+        char_vertex.pos[0] -= content_width  * pivot.0;
+        char_vertex.pos[1] -= content_height * pivot.1;
+    }
+}
+
+/// Sample the glyphs of `characters` into a single image, and return the layout of each glyph.
+///
+/// The glyphs are placed row by row, and a new row is started when the width of current row reaches `ATLAS_ROW_WIDTH`.
+/// An opaque square is placed before all the glyphs, and the uv at its center is returned along with the glyph layouts.
+/// `max_dimension` is the maximum image dimension supported by device.
+/// If `distance_field` is set, the image stores the signed distance field of glyphs with the spread of `distance_field` pixels.
+fn generate_glyphs_bytes(font: &Font<'static>, characters: &[CharacterID], font_scale: f32, max_dimension: vkuint, distance_field: Option<f32>) -> VkResult<(GlyphLayouts, [f32; 2], Vec<u8>, vk::Extent2D)> {
+
+    use rusttype::{Scale, PositionedGlyph, point};
 
     let scale = Scale::uniform(font_scale);
     let v_metrics = font.v_metrics(scale);
 
-    let glyphs_start_point = point(IMAGE_PADDING as f32, IMAGE_PADDING as f32 + v_metrics.ascent);
-    let glyphs: Vec<PositionedGlyph> = font.layout(&ascii_characters, scale, glyphs_start_point)
-        .collect();
     let glyphs_height = (v_metrics.ascent - v_metrics.descent).ceil() as usize;
-    let glyphs_width = {
-        let min_x = glyphs.first()
-            .map(|g| g.pixel_bounding_box().unwrap().min.x)
-            .unwrap();
-        let max_x = glyphs.last()
-            .map(|g| g.pixel_bounding_box().unwrap().max.x)
-            .unwrap();
-        (max_x - min_x) as usize
-    };
+    let row_width_limit = ATLAS_ROW_WIDTH.min(max_dimension as usize);
+    // enlarge the glyph quads for distance field, since its anti-aliased edge may exceed the bounding box of glyph.
+    let margin = if distance_field.is_some() { DISTANCE_FIELD_MARGIN } else { 0 };
 
-    let image_width  = (2 * IMAGE_PADDING) + glyphs_width;
-    let image_height = (2 * IMAGE_PADDING) + glyphs_height;
+    let mut glyph_layouts = GlyphLayouts::new();
+    let mut positioned_glyphs: Vec<(CharacterID, PositionedGlyph)> = Vec::with_capacity(characters.len());
+
+    // reserve space for the opaque square at the beginning of first row.
+    let mut cursor_x = IMAGE_PADDING + SOLID_BLOCK_SIZE + IMAGE_PADDING;
+    let mut cursor_y = IMAGE_PADDING;
+    let mut image_width = cursor_x;
+
+    for &character in characters.iter() {
+
+        // the character that the font does not support is mapped to the `.notdef` glyph(glyph id 0) by rusttype.
+        let glyph = font.glyph(character).scaled(scale);
+        let h_metrics = glyph.h_metrics();
+
+        let exact_box = match glyph.exact_bounding_box() {
+            | Some(exact_box) => exact_box,
+            | None => {
+                // the character without bounding box(etc. space) renders nothing, but still occupies its advance width.
+                glyph_layouts.insert(character, GlyphLayout {
+                    min_uv: [0.0, 0.0],
+                    max_uv: [0.0, 0.0],
+                    h_metrics,
+                    bounding_box: Rect { min: point(0.0, 0.0), max: point(0.0, 0.0) },
+                });
+                continue
+            },
+        };
+
+        // start a new row if the glyph can not be placed in current row.
+        let glyph_width = exact_box.width().ceil() as usize + 1;
+        if cursor_x > IMAGE_PADDING && cursor_x + glyph_width + IMAGE_PADDING > row_width_limit {
+            cursor_x = IMAGE_PADDING;
+            cursor_y += glyphs_height + IMAGE_PADDING;
+        }
+
+        let glyph_layout = GlyphLayout {
+            // uv is set after the image dimension is known.
+            min_uv: [0.0, 0.0],
+            max_uv: [0.0, 0.0],
+            h_metrics,
+            bounding_box: expand_rect(fix_bounding_box_positive(exact_box, &v_metrics), margin as f32),
+        };
+        glyph_layouts.insert(character, glyph_layout);
+
+        let glyph = glyph.positioned(point(cursor_x as f32 - exact_box.min.x, cursor_y as f32 + v_metrics.ascent));
+        if let Some(bounding_box) = glyph.pixel_bounding_box() {
+            cursor_x = bounding_box.max.x as usize + IMAGE_PADDING;
+            image_width = image_width.max(cursor_x);
+        }
+        positioned_glyphs.push((character, glyph));
+    }
+
+    let image_width  = image_width.max(1);
+    let image_height = cursor_y + glyphs_height + IMAGE_PADDING;
     let bytes_per_pixel = 1; // only store the alpha value.
 
+    if image_height > max_dimension as usize {
+        return Err(VkError::custom(format!("The glyph image of {} characters exceeds the maximum image dimension({}) of device.", characters.len(), max_dimension)))
+    }
+
     // fill image data with empty bytes.
     let mut image_bytes = vec![0_u8; image_width * image_height * bytes_per_pixel];
 
-    let mut glyph_layouts = GlyphLayouts::new();
+    for y in IMAGE_PADDING..(IMAGE_PADDING + SOLID_BLOCK_SIZE) {
+        let row_start = (IMAGE_PADDING + y * image_width) * bytes_per_pixel;
+        for value in image_bytes[row_start..(row_start + SOLID_BLOCK_SIZE * bytes_per_pixel)].iter_mut() {
+            *value = 255;
+        }
+    }
+    let solid_uv = [
+        (IMAGE_PADDING + SOLID_BLOCK_SIZE / 2) as f32 / image_width  as f32,
+        (IMAGE_PADDING + SOLID_BLOCK_SIZE / 2) as f32 / image_height as f32,
+    ];
 
     // fill color to image data.
-    for (glyph, character) in glyphs.iter().zip(ascii_bytes.into_iter()) {
+    for (character, glyph) in positioned_glyphs.iter() {
 
         if let Some(bounding_box) = glyph.pixel_bounding_box() {
             // Draw the glyph into the image per-pixel by using the draw closure.
@@ -464,46 +994,193 @@ fn generate_ascii_glyphs_bytes(font_bytes: &[u8], font_scale: f32) -> VkResult<(
                 image_bytes[pos] = (v * 255.0) as u8;
             });
 
-            let min_uv = [
-                bounding_box.min.x as f32 / image_width  as f32,
-                bounding_box.min.y as f32 / image_height as f32,
-            ];
-            let max_uv = [
-                bounding_box.max.x as f32 / image_width  as f32,
-                bounding_box.max.y as f32 / image_height as f32,
-            ];
-
-            let glyph_unpositioned = glyph.unpositioned();
-            let glyph_layout = GlyphLayout {
-                min_uv, max_uv,
-                h_metrics: glyph_unpositioned.h_metrics(),
-                bounding_box: fix_bounding_box_positive(glyph_unpositioned.exact_bounding_box().unwrap(), &v_metrics),
-            };
-            glyph_layouts.insert(character as CharacterID, glyph_layout);
+            if let Some(glyph_layout) = glyph_layouts.get_mut(character) {
+                glyph_layout.min_uv = [
+                    (bounding_box.min.x - margin) as f32 / image_width  as f32,
+                    (bounding_box.min.y - margin) as f32 / image_height as f32,
+                ];
+                glyph_layout.max_uv = [
+                    (bounding_box.max.x + margin) as f32 / image_width  as f32,
+                    (bounding_box.max.y + margin) as f32 / image_height as f32,
+                ];
+            }
         }
     }
 
-    // set the layout of space the same with 't', since space does not have a bounding box.
-    let mut space_layout = glyph_layouts.get(&'t').unwrap().clone();
-    // set the same uv for min and max position, so that nothing will be render for space.
-    space_layout.max_uv = space_layout.min_uv;
-    glyph_layouts.insert(' ', space_layout);
-
+    if let Some(spread) = distance_field {
+        let spread = spread.max(1.0).min(IMAGE_PADDING as f32 * 0.5);
+        convert_to_distance_field(&mut image_bytes, image_width, image_height, spread);
+    }
 
     let dimension = vk::Extent2D {
         width : image_width  as vkuint,
         height: image_height as vkuint,
     };
-    Ok((glyph_layouts, image_bytes, dimension))
+    Ok((glyph_layouts, solid_uv, image_bytes, dimension))
+}
+
+fn expand_rect(mut rect: Rect<f32>, margin: f32) -> Rect<f32> {
+
+    rect.min.x -= margin;
+    rect.min.y -= margin;
+    rect.max.x += margin;
+    rect.max.y += margin;
+
+    rect
+}
+
+/// Convert the coverage values in `image_bytes` to signed distance field in place.
+///
+/// The distance is positive inside the glyphs, and is mapped from [-spread, spread] to [0, 255], so that the edge of glyphs is at about 0.5 after normalized.
+fn convert_to_distance_field(image_bytes: &mut [u8], width: usize, height: usize, spread: f32) {
+
+    let inside: Vec<bool> = image_bytes.iter().map(|&coverage| coverage >= 128).collect();
+    let outside: Vec<bool> = inside.iter().map(|&is_inside| !is_inside).collect();
+
+    // the distance to the nearest inside pixel for outside pixels and vice versa.
+    let distances_to_inside  = nearest_distances(&inside, width, height);
+    let distances_to_outside = nearest_distances(&outside, width, height);
+
+    for (i, value) in image_bytes.iter_mut().enumerate() {
+
+        // measure the distance from the boundary between pixels, rather than from pixel center.
+        let signed_distance = if inside[i] {
+            distances_to_outside[i] - 0.5
+        } else {
+            0.5 - distances_to_inside[i]
+        };
+
+        let normalized = (signed_distance / (spread * 2.0) + 0.5).max(0.0).min(1.0);
+        *value = (normalized * 255.0).round() as u8;
+    }
+}
+
+/// Calculate the Euclidean distance from each pixel to its nearest `targets` pixel, by the 8-points signed sequential Euclidean distance transform.
+fn nearest_distances(targets: &[bool], width: usize, height: usize) -> Vec<f32> {
+
+    /// the offset of pixels that have not found any target.
+    const FAR_OFFSET: i32 = 1 << 14;
+
+    // the offset from each pixel to its nearest target found so far.
+    let mut offsets: Vec<(i32, i32)> = targets.iter()
+        .map(|&is_target| if is_target { (0, 0) } else { (FAR_OFFSET, FAR_OFFSET) })
+        .collect();
+
+    let propagate = |offsets: &mut [(i32, i32)], x: usize, y: usize, dx: i32, dy: i32| {
+
+        let neighbor_x = x as i32 + dx;
+        let neighbor_y = y as i32 + dy;
+        if neighbor_x < 0 || neighbor_y < 0 || neighbor_x >= width as i32 || neighbor_y >= height as i32 {
+            return
+        }
+
+        let neighbor = offsets[neighbor_y as usize * width + neighbor_x as usize];
+        let candidate = (neighbor.0 + dx, neighbor.1 + dy);
+        let current = &mut offsets[y * width + x];
+
+        if candidate.0 * candidate.0 + candidate.1 * candidate.1 < current.0 * current.0 + current.1 * current.1 {
+            *current = candidate;
+        }
+    };
+
+    // the first pass scans from top to bottom.
+    for y in 0..height {
+        for x in 0..width {
+            propagate(&mut offsets, x, y, -1,  0);
+            propagate(&mut offsets, x, y,  0, -1);
+            propagate(&mut offsets, x, y, -1, -1);
+            propagate(&mut offsets, x, y,  1, -1);
+        }
+        for x in (0..width).rev() {
+            propagate(&mut offsets, x, y, 1, 0);
+        }
+    }
+
+    // the second pass scans from bottom to top.
+    for y in (0..height).rev() {
+        for x in (0..width).rev() {
+            propagate(&mut offsets, x, y,  1, 0);
+            propagate(&mut offsets, x, y,  0, 1);
+            propagate(&mut offsets, x, y, -1, 1);
+            propagate(&mut offsets, x, y,  1, 1);
+        }
+        for x in 0..width {
+            propagate(&mut offsets, x, y, -1, 0);
+        }
+    }
+
+    offsets.into_iter()
+        .map(|(dx, dy)| ((dx * dx + dy * dy) as f32).sqrt())
+        .collect()
+}
+
+/// Get the sampler CI of glyph image, which applies the LOD bias of `config`.
+///
+/// The sampler is kept when the glyph image is regenerated with a different mip count(see `extend_characters`),
+/// so leave the LOD unclamped here, and the sampled levels are limited by the level count of `glyph_view` instead.
+fn glyph_sampler_ci(config: &FontAtlasConfig) -> SamplerCI {
+    SamplerCI::new()
+        .lod(config.mip_bias, 0.0, vk::LOD_CLAMP_NONE)
 }
 
-fn allocate_glyph_image(device: &mut VkDevice, image_bytes: Vec<u8>, image_dimension: vk::Extent2D) -> VkResult<VmaImage> {
+fn create_glyph_view(device: &VkDevice, glyph_image: vk::Image, mip_levels: vkuint) -> VkResult<vk::ImageView> {
+
+    // Just store alpha value in the image.
+    ImageViewCI::new(glyph_image, vk::ImageViewType::TYPE_2D, GLYPH_FORMAT)
+        .sub_range(vk::ImageSubresourceRange {
+            aspect_mask: vk::ImageAspectFlags::COLOR,
+            base_mip_level: 0,
+            level_count   : mip_levels,
+            base_array_layer: 0,
+            layer_count     : 1,
+        }).build(device)
+}
+
+/// Get the length of full mip chain for an image of `dimension`.
+fn max_mip_levels(dimension: vk::Extent2D) -> vkuint {
+
+    let max_edge = dimension.width.max(dimension.height).max(1);
+    // equal to floor(log2(max_edge)) + 1.
+    32 - max_edge.leading_zeros()
+}
+
+/// Get the mip count of glyph image, clamping `requested` to the full mip chain of `dimension`.
+///
+/// Mipmapping is disabled with a warning if the glyph format can not be blitted on this device.
+fn supported_mip_levels(device: &VkDevice, requested: vkuint, dimension: vk::Extent2D) -> vkuint {
+
+    let supported = device.phy.format_properties(GLYPH_FORMAT).optimal_tiling_features;
+    let mip_levels = glyph_mip_levels(requested, dimension, supported);
+
+    if mip_levels < requested.max(1).min(max_mip_levels(dimension)) {
+        println!("[Warning] Glyph format {:?} can not be blitted with linear filter on this device, so mipmapping of font atlas is disabled.", GLYPH_FORMAT);
+    }
+    mip_levels
+}
+
+/// Get the mip count of glyph image, where `supported` is the optimal tiling features of glyph format.
+///
+/// The mipmaps are generated by linear blits, which require `BLIT_SRC`, `BLIT_DST` and `SAMPLED_IMAGE_FILTER_LINEAR`.
+fn glyph_mip_levels(requested: vkuint, dimension: vk::Extent2D, supported: vk::FormatFeatureFlags) -> vkuint {
+
+    let blit_features = vk::FormatFeatureFlags::BLIT_SRC | vk::FormatFeatureFlags::BLIT_DST | vk::FormatFeatureFlags::SAMPLED_IMAGE_FILTER_LINEAR;
+
+    if supported.contains(blit_features) {
+        requested.max(1).min(max_mip_levels(dimension))
+    } else {
+        1
+    }
+}
+
+fn allocate_glyph_image(device: &mut VkDevice, image_bytes: Vec<u8>, image_dimension: vk::Extent2D, mip_levels: vkuint) -> VkResult<VmaImage> {
 
     // create vk::Image to store glyphs data.
     let glyphs_image = {
 
-        let glyphs_image_ci = ImageCI::new_2d(vk::Format::R8_UNORM, image_dimension)
-            .usages(vk::ImageUsageFlags::SAMPLED | vk::ImageUsageFlags::TRANSFER_DST);
+        // the image is also the source of blit operation when generating mipmaps.
+        let glyphs_image_ci = ImageCI::new_2d(GLYPH_FORMAT, image_dimension)
+            .usages(vk::ImageUsageFlags::SAMPLED | vk::ImageUsageFlags::TRANSFER_DST | vk::ImageUsageFlags::TRANSFER_SRC)
+            .mip_levels(mip_levels);
         let allocation_ci = VmaAllocationCI::new(vma::MemoryUsage::GpuOnly, vk::MemoryPropertyFlags::DEVICE_LOCAL);
         let image_allocation = device.vma.create_image(glyphs_image_ci.as_ref(), allocation_ci.as_ref())
             .map_err(VkErrorKind::Vma)?;
@@ -549,19 +1226,65 @@ fn allocate_glyph_image(device: &mut VkDevice, image_bytes: Vec<u8>, image_dimen
 
     let image_range = vk::ImageSubresourceRange {
         aspect_mask: vk::ImageAspectFlags::COLOR,
-        base_mip_level  : 0, level_count: 1,
+        base_mip_level  : 0, level_count: mip_levels,
         base_array_layer: 0, layer_count: 1,
     };
+    let level_range = |level: vkuint| vk::ImageSubresourceRange {
+        base_mip_level: level, level_count: 1,
+        ..image_range
+    };
+
     let copy_dst_barrier = ImageBarrierCI::new(glyphs_image.handle, image_range)
         .access_mask(vk::AccessFlags::empty(), vk::AccessFlags::TRANSFER_WRITE)
         .layout(vk::ImageLayout::UNDEFINED, vk::ImageLayout::TRANSFER_DST_OPTIMAL);
-    let shader_read_barrier = ImageBarrierCI::new(glyphs_image.handle, image_range)
-        .access_mask(vk::AccessFlags::TRANSFER_WRITE, vk::AccessFlags::SHADER_READ)
-        .layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL, vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL);
 
     recorder.begin_record()?
         .image_pipeline_barrier(vk::PipelineStageFlags::TOP_OF_PIPE, vk::PipelineStageFlags::TRANSFER, vk::DependencyFlags::empty(), &[copy_dst_barrier.into()])
-        .copy_buf2img(staging_buffer.handle, glyphs_image.handle, vk::ImageLayout::TRANSFER_DST_OPTIMAL, &[copy_region])
+        .copy_buf2img(staging_buffer.handle, glyphs_image.handle, vk::ImageLayout::TRANSFER_DST_OPTIMAL, &[copy_region]);
+
+    // generate each mip level by blitting from its previous level.
+    for level in 1..mip_levels {
+
+        let src_barrier = ImageBarrierCI::new(glyphs_image.handle, level_range(level - 1))
+            .access_mask(vk::AccessFlags::TRANSFER_WRITE, vk::AccessFlags::TRANSFER_READ)
+            .layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL, vk::ImageLayout::TRANSFER_SRC_OPTIMAL);
+        let src_read_barrier = ImageBarrierCI::new(glyphs_image.handle, level_range(level - 1))
+            .access_mask(vk::AccessFlags::TRANSFER_READ, vk::AccessFlags::SHADER_READ)
+            .layout(vk::ImageLayout::TRANSFER_SRC_OPTIMAL, vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL);
+
+        let blit_region = vk::ImageBlit {
+            src_subresource: vk::ImageSubresourceLayers {
+                aspect_mask: vk::ImageAspectFlags::COLOR,
+                mip_level: level - 1,
+                base_array_layer: 0, layer_count: 1,
+            },
+            src_offsets: [
+                vk::Offset3D { x: 0, y: 0, z: 0 },
+                vk::Offset3D { x: mip_extent(image_dimension.width, level - 1), y: mip_extent(image_dimension.height, level - 1), z: 1 },
+            ],
+            dst_subresource: vk::ImageSubresourceLayers {
+                aspect_mask: vk::ImageAspectFlags::COLOR,
+                mip_level: level,
+                base_array_layer: 0, layer_count: 1,
+            },
+            dst_offsets: [
+                vk::Offset3D { x: 0, y: 0, z: 0 },
+                vk::Offset3D { x: mip_extent(image_dimension.width, level), y: mip_extent(image_dimension.height, level), z: 1 },
+            ],
+        };
+
+        recorder
+            .image_pipeline_barrier(vk::PipelineStageFlags::TRANSFER, vk::PipelineStageFlags::TRANSFER, vk::DependencyFlags::empty(), &[src_barrier.into()])
+            .blit_image(glyphs_image.handle, vk::ImageLayout::TRANSFER_SRC_OPTIMAL, glyphs_image.handle, vk::ImageLayout::TRANSFER_DST_OPTIMAL, &[blit_region], vk::Filter::LINEAR)
+            .image_pipeline_barrier(vk::PipelineStageFlags::TRANSFER, vk::PipelineStageFlags::ALL_COMMANDS, vk::DependencyFlags::empty(), &[src_read_barrier.into()]);
+    }
+
+    // the last mip level is still in transfer destination layout.
+    let shader_read_barrier = ImageBarrierCI::new(glyphs_image.handle, level_range(mip_levels - 1))
+        .access_mask(vk::AccessFlags::TRANSFER_WRITE, vk::AccessFlags::SHADER_READ)
+        .layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL, vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL);
+
+    recorder
         .image_pipeline_barrier(vk::PipelineStageFlags::TRANSFER, vk::PipelineStageFlags::ALL_COMMANDS, vk::DependencyFlags::empty(), &[shader_read_barrier.into()])
         .end_record()?;
 
@@ -573,6 +1296,11 @@ fn allocate_glyph_image(device: &mut VkDevice, image_bytes: Vec<u8>, image_dimen
     Ok(glyphs_image)
 }
 
+/// Get the size of an image edge at mip `level`.
+#[inline]
+fn mip_extent(base_extent: vkuint, level: vkuint) -> i32 {
+    (base_extent >> level).max(1) as i32
+}
 
 // TODO: Fix and remove this magic function.
 fn fix_bounding_box_positive(mut rect: Rect<f32>, v_metrics: &VMetrics) -> Rect<f32> {
@@ -610,3 +1338,163 @@ pub fn input_descriptions() -> VertexInputSCI {
             offset  : offset_of!(CharacterVertex, color) as _,
         })
 }
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn color_of(color: VkColor) -> [f32; 4] {
+        color.into()
+    }
+
+    /// Get the layouts of `characters` and the space, where each glyph has the same size.
+    fn uniform_glyph_layouts(characters: &str) -> GlyphLayouts {
+
+        characters.chars().chain(Some(' ')).map(|character| {
+            let layout = GlyphLayout {
+                min_uv: [0.0, 0.0],
+                max_uv: [0.1, 0.1],
+                h_metrics: HMetrics { advance_width: 10.0, left_side_bearing: 0.0 },
+                bounding_box: Rect { min: rusttype::point(0.0, 0.0), max: rusttype::point(8.0, 12.0) },
+            };
+            (character, layout)
+        }).collect()
+    }
+
+    #[test]
+    fn two_runs_color_their_own_characters() {
+
+        let runs = [(String::from("ab"), VkColor::RED), (String::from("çde"), VkColor::GREEN)];
+        let (content, color_runs) = layout_color_runs(&runs);
+        assert_eq!(content, "abçde");
+
+        // one padding character of dynamic text after the content.
+        let text = TextInfo {
+            content, color: VkColor::WHITE,
+            r#type: TextType::Dynamic { capacity: 6 },
+            ..TextInfo::default()
+        };
+        let layouts = uniform_glyph_layouts("abçde");
+        let layout = layout_characters(&text, &color_runs, &layouts, (0.0, 0.0), 0.001, 1.0);
+
+        assert_eq!(layout.content_count, 5);
+        let glyph_colors: Vec<Vec<[f32; 4]>> = layout.vertices.chunks(VERTEX_PER_CHARACTER)
+            .map(|glyph| glyph.iter().map(|vertex| vertex.color).collect())
+            .collect();
+        assert_eq!(glyph_colors.len(), 6);
+
+        // the index is counted in characters, so the multi-byte 'ç' belongs to the second run.
+        for glyph in glyph_colors[0..2].iter() {
+            assert_eq!(glyph[..], [color_of(VkColor::RED); VERTEX_PER_CHARACTER]);
+        }
+        for glyph in glyph_colors[2..5].iter() {
+            assert_eq!(glyph[..], [color_of(VkColor::GREEN); VERTEX_PER_CHARACTER]);
+        }
+        // the padding characters of dynamic text use the color of text.
+        assert_eq!(glyph_colors[5][..], [color_of(VkColor::WHITE); VERTEX_PER_CHARACTER]);
+    }
+
+    #[test]
+    fn text_without_runs_uses_text_color() {
+        assert_eq!(color_of(run_color(&[], 0, VkColor::BLUE)), color_of(VkColor::BLUE));
+    }
+
+    fn quad(min: [f32; 2], max: [f32; 2]) -> Vec<CharacterVertex> {
+
+        let vertex = |x: f32, y: f32| CharacterVertex { pos: [x, y], uv: [0.0, 0.0], color: [1.0; 4] };
+        vec![
+            vertex(min[0], min[1]), vertex(min[0], max[1]), vertex(max[0], max[1]),
+            vertex(min[0], min[1]), vertex(max[0], max[1]), vertex(max[0], min[1]),
+        ]
+    }
+
+    #[test]
+    fn center_pivot_places_content_center_at_location() {
+
+        let (left, top) = (-0.5, -0.5);
+
+        // two characters of content, followed by a padding character of dynamic text.
+        let mut char_vertices = quad([left, top], [left + 0.1, top + 0.2]);
+        char_vertices.extend(quad([left + 0.1, top], [left + 0.2, top + 0.2]));
+        char_vertices.extend(quad([left + 0.2, top], [left + 0.3, top + 0.4]));
+
+        place_pivot(&mut char_vertices, 2, top, 0.2, (0.5, 0.5));
+
+        let content = &char_vertices[..2 * VERTEX_PER_CHARACTER];
+        let min_x = content.iter().map(|v| v.pos[0]).fold(::std::f32::MAX, f32::min);
+        let max_x = content.iter().map(|v| v.pos[0]).fold(::std::f32::MIN, f32::max);
+        let min_y = content.iter().map(|v| v.pos[1]).fold(::std::f32::MAX, f32::min);
+        let max_y = content.iter().map(|v| v.pos[1]).fold(::std::f32::MIN, f32::max);
+
+        assert!(((min_x + max_x) * 0.5 - left).abs() < 1e-6);
+        assert!(((min_y + max_y) * 0.5 - top).abs() < 1e-6);
+    }
+
+    #[test]
+    fn glyph_mip_count_is_clamped_to_chain() {
+
+        let dimension = vk::Extent2D { width: 2048, height: 300 };
+        let blit_features = vk::FormatFeatureFlags::SAMPLED_IMAGE | vk::FormatFeatureFlags::BLIT_SRC | vk::FormatFeatureFlags::BLIT_DST | vk::FormatFeatureFlags::SAMPLED_IMAGE_FILTER_LINEAR;
+
+        assert_eq!(max_mip_levels(dimension), 12);
+        assert_eq!(glyph_mip_levels(4, dimension, blit_features), 4);
+        assert_eq!(glyph_mip_levels(32, dimension, blit_features), 12);
+        // the default config disables mipmapping.
+        assert_eq!(glyph_mip_levels(FontAtlasConfig::default().mip_levels, dimension, blit_features), 1);
+        assert_eq!(glyph_mip_levels(0, dimension, blit_features), 1);
+    }
+
+    #[test]
+    fn glyph_mips_require_blit_support() {
+
+        let dimension = vk::Extent2D { width: 512, height: 512 };
+        assert_eq!(glyph_mip_levels(4, dimension, vk::FormatFeatureFlags::SAMPLED_IMAGE | vk::FormatFeatureFlags::BLIT_SRC), 1);
+        assert_eq!(glyph_mip_levels(4, dimension, vk::FormatFeatureFlags::BLIT_SRC | vk::FormatFeatureFlags::BLIT_DST), 1);
+    }
+
+    #[test]
+    fn atlas_lod_bias_reaches_sampler() {
+
+        let config = FontAtlasConfig { mip_levels: 8, mip_bias: -0.5, ..FontAtlasConfig::default() };
+        let sampler_ci = glyph_sampler_ci(&config);
+        let sampler_ci: &vk::SamplerCreateInfo = sampler_ci.as_ref();
+
+        assert_eq!(sampler_ci.mip_lod_bias, config.mip_bias);
+        assert_eq!(sampler_ci.min_lod, 0.0);
+        // the max LOD is unclamped, so every generated mip level can be sampled.
+        assert_eq!(sampler_ci.max_lod, vk::LOD_CLAMP_NONE);
+        assert!(sampler_ci.max_lod >= (config.mip_levels - 1) as f32);
+    }
+
+    #[test]
+    fn world_point_projects_to_screen_position() {
+
+        // the camera at origin looks at -z, with 90 degrees vertical field of view.
+        let view_proj = Mat4F::perspective_rh_zo(::std::f32::consts::FRAC_PI_2, 1.0, 0.1, 100.0);
+        let screen = (800.0, 600.0);
+
+        let (center, depth) = project_to_screen(view_proj, Vec3F::new(0.0, 0.0, -5.0), screen).unwrap();
+        assert_eq!((center.x, center.y), (400, 300));
+        assert!((depth - 5.0).abs() < 1e-4);
+
+        // a point at the edge of field of view is projected to the edge of screen.
+        let (edge, _) = project_to_screen(view_proj, Vec3F::new(5.0, 5.0, -5.0), screen).unwrap();
+        assert!((edge.x - 800).abs() <= 1 && (edge.y - 600).abs() <= 1);
+    }
+
+    #[test]
+    fn world_point_behind_camera_is_hidden() {
+
+        let view_proj = Mat4F::perspective_rh_zo(::std::f32::consts::FRAC_PI_2, 1.0, 0.1, 100.0);
+        assert!(project_to_screen(view_proj, Vec3F::new(0.0, 0.0, 5.0), (800.0, 600.0)).is_none());
+    }
+
+    #[test]
+    fn default_text_is_anchored_at_top_left() {
+
+        let text = TextInfo::default();
+        assert_eq!(text.pivot, (0.0, 0.0));
+        assert!(text.shadow.is_none());
+    }
+}
@@ -0,0 +1,25 @@
+
+#version 450 core
+#extension GL_ARB_separate_shader_objects : enable
+
+layout (location = 0) in vec2 inUV;
+layout (location = 1) in vec4 inColor;
+
+layout (location = 0) out vec4 outColor;
+
+// the signed distance field of glyphs, where 0.5 is the edge of glyphs.
+layout (binding = 0) uniform sampler2D font_glyphs;
+
+void main() {
+
+    float distance = texture(font_glyphs, inUV).r;
+    // smooth the edge across about one pixel on screen, regardless of the scale of text.
+    float smooth_width = fwidth(distance) * 0.5;
+    float alpha = smoothstep(0.5 - smooth_width, 0.5 + smooth_width, distance);
+
+    if (alpha <= 0.0) {
+        discard;
+    }
+
+    outColor = vec4(inColor.xyz, inColor.w * alpha);
+}
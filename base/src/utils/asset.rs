@@ -0,0 +1,340 @@
+
+//! Load assets(etc. shader sources, models and textures) from a configurable source, such as the file system, embedded bytes
+//! or a zip archive(with `zip` feature).
+
+use lazy_static::lazy_static;
+
+use crate::error::{VkResult, VkError};
+
+use std::collections::HashMap;
+use std::io::{Read, Cursor};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::fs::File;
+
+lazy_static! {
+
+    static ref ASSET_SOURCE: RwLock<Arc<dyn AssetSource>> = RwLock::new(Arc::new(FileSystemSource));
+}
+
+// --------------------------------------------------------------------------------------
+/// The place where assets are loaded from.
+pub trait AssetSource: Send + Sync {
+
+    /// Open the asset at `path` for reading.
+    fn open(&self, path: &Path) -> VkResult<Box<dyn Read>>;
+
+    /// Get the location of asset on the local file system, or None if the asset is not stored as a file.
+    ///
+    /// Some loaders can only read assets from files, and they will use this path directly if it is available.
+    fn local_path(&self, _path: &Path) -> Option<PathBuf> {
+        None
+    }
+
+    /// Read all the bytes of the asset at `path`.
+    fn read_bytes(&self, path: &Path) -> VkResult<Vec<u8>> {
+
+        let mut reader = self.open(path)?;
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)
+            .map_err(|_| VkError::path(path))?;
+
+        Ok(bytes)
+    }
+}
+
+/// Load assets from file system, where relative paths are resolved against the asset root(see `utils::path::set_asset_root`).
+#[derive(Debug, Clone, Default)]
+pub struct FileSystemSource;
+
+impl AssetSource for FileSystemSource {
+
+    fn open(&self, path: &Path) -> VkResult<Box<dyn Read>> {
+
+        let resolved_path = crate::utils::path::resolve_path(path);
+        let file = File::open(&resolved_path)
+            .map_err(|_| VkError::path(resolved_path))?;
+
+        Ok(Box::new(file))
+    }
+
+    fn local_path(&self, path: &Path) -> Option<PathBuf> {
+        Some(crate::utils::path::resolve_path(path))
+    }
+}
+
+/// Load assets from bytes embedded in program(etc. by `include_bytes!` macro).
+#[derive(Debug, Clone, Default)]
+pub struct EmbeddedSource {
+
+    assets: HashMap<PathBuf, &'static [u8]>,
+}
+
+impl EmbeddedSource {
+
+    pub fn new() -> EmbeddedSource {
+        Default::default()
+    }
+
+    /// Register the `bytes` of asset, which can be then opened by `path`.
+    pub fn add_asset(mut self, path: impl Into<PathBuf>, bytes: &'static [u8]) -> EmbeddedSource {
+        self.assets.insert(path.into(), bytes); self
+    }
+}
+
+impl AssetSource for EmbeddedSource {
+
+    fn open(&self, path: &Path) -> VkResult<Box<dyn Read>> {
+
+        let bytes = self.assets.get(path)
+            .ok_or(VkError::path(path))?;
+
+        Ok(Box::new(Cursor::new(*bytes)))
+    }
+}
+
+/// Load assets from a zip archive, where `path` is the name of entry in archive(etc. `models/cube.gltf`).
+#[cfg(feature = "zip")]
+pub struct ZipSource<R: Read + ::std::io::Seek + Send> {
+
+    archive: ::std::sync::Mutex<zip::ZipArchive<R>>,
+}
+
+#[cfg(feature = "zip")]
+impl ZipSource<File> {
+
+    /// Open the zip archive at `path` on file system.
+    pub fn open(path: impl AsRef<Path>) -> VkResult<ZipSource<File>> {
+
+        let resolved_path = crate::utils::path::resolve_path(path);
+        let file = File::open(&resolved_path)
+            .map_err(|_| VkError::path(&resolved_path))?;
+
+        ZipSource::new(file)
+    }
+}
+
+#[cfg(feature = "zip")]
+impl<R: Read + ::std::io::Seek + Send> ZipSource<R> {
+
+    /// Read zip archive from `reader`(etc. `Cursor` of the bytes embedded in program).
+    pub fn new(reader: R) -> VkResult<ZipSource<R>> {
+
+        let archive = zip::ZipArchive::new(reader)
+            .map_err(|e| VkError::custom(format!("Failed to read zip archive: {}", e)))?;
+
+        Ok(ZipSource { archive: ::std::sync::Mutex::new(archive) })
+    }
+}
+
+#[cfg(feature = "zip")]
+impl<R: Read + ::std::io::Seek + Send> AssetSource for ZipSource<R> {
+
+    fn open(&self, path: &Path) -> VkResult<Box<dyn Read>> {
+
+        // the entries of zip archive are always separated by '/'.
+        let entry_name = path.to_string_lossy().replace('\\', "/");
+
+        let mut archive = self.archive.lock()
+            .expect("Zip archive lock is poisoned.");
+        let mut entry = archive.by_name(&entry_name)
+            .map_err(|_| VkError::path(path))?;
+
+        // the entry borrows the archive, so read it out before releasing the lock.
+        let mut bytes = Vec::with_capacity(entry.size() as usize);
+        entry.read_to_end(&mut bytes)
+            .map_err(|_| VkError::path(path))?;
+
+        Ok(Box::new(Cursor::new(bytes)))
+    }
+}
+// --------------------------------------------------------------------------------------
+
+// --------------------------------------------------------------------------------------
+/// Set the source used to load all the assets. Default is `FileSystemSource`.
+pub fn set_asset_source(source: impl AssetSource + 'static) {
+
+    let mut asset_source = ASSET_SOURCE.write()
+        .expect("Asset source lock is poisoned.");
+    *asset_source = Arc::new(source);
+}
+
+/// Get the source used to load assets.
+pub fn asset_source() -> Arc<dyn AssetSource> {
+
+    ASSET_SOURCE.read()
+        .expect("Asset source lock is poisoned.")
+        .clone()
+}
+
+/// Read all the bytes of the asset at `path` from current asset source.
+pub fn read_asset(path: impl AsRef<Path>) -> VkResult<Vec<u8>> {
+    asset_source().read_bytes(path.as_ref())
+}
+
+/// Get a path on local file system, where the asset at `path` can be read from.
+///
+/// If current asset source does not store this asset as a file, its content is copied to a file in a unique temporary directory,
+/// which is removed when the returned `LocalAsset` is dropped. So keep it alive until the file has been read.
+pub fn local_asset_path(path: impl AsRef<Path>) -> VkResult<LocalAsset> {
+
+    let source = asset_source();
+    let path = path.as_ref();
+
+    if let Some(local_path) = source.local_path(path) {
+        return Ok(LocalAsset { path: local_path, temp_dir: None })
+    }
+
+    let bytes = source.read_bytes(path)?;
+
+    let file_name = path.file_name()
+        .ok_or(VkError::path(path))?;
+    let temp_dir = unique_temp_dir()?;
+    let temp_path = temp_dir.join(file_name);
+
+    // create the guard first, so that the directory is removed even if writing fails.
+    let asset = LocalAsset { path: temp_path, temp_dir: Some(temp_dir) };
+    std::fs::write(&asset.path, bytes)
+        .map_err(|_| VkError::path(&asset.path))?;
+
+    Ok(asset)
+}
+
+/// The path of an asset on local file system, which may be a temporary copy(see `local_asset_path`).
+#[derive(Debug)]
+pub struct LocalAsset {
+
+    path: PathBuf,
+    /// the temporary directory containing the copy of asset, which is removed on drop.
+    temp_dir: Option<PathBuf>,
+}
+
+impl LocalAsset {
+
+    #[inline]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Check if the asset is a temporary copy of the asset from current asset source.
+    #[inline]
+    pub fn is_temporary(&self) -> bool {
+        self.temp_dir.is_some()
+    }
+}
+
+impl AsRef<Path> for LocalAsset {
+
+    fn as_ref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for LocalAsset {
+
+    fn drop(&mut self) {
+
+        if let Some(ref temp_dir) = self.temp_dir {
+            if std::fs::remove_dir_all(temp_dir).is_err() {
+                println!("[Warning] Failed to remove temporary asset directory: {}", temp_dir.display());
+            }
+        }
+    }
+}
+
+/// Create a new directory under the temporary directory of system, whose name is unique in all processes.
+fn unique_temp_dir() -> VkResult<PathBuf> {
+
+    lazy_static! {
+        static ref TEMP_DIR_COUNTER: AtomicUsize = AtomicUsize::new(0);
+    }
+
+    let dir_name = format!("vkbase-asset-{}-{}", std::process::id(), TEMP_DIR_COUNTER.fetch_add(1, Ordering::Relaxed));
+    let temp_dir = std::env::temp_dir().join(dir_name);
+
+    std::fs::create_dir_all(&temp_dir)
+        .map_err(|_| VkError::path(&temp_dir))?;
+
+    Ok(temp_dir)
+}
+
+/// Decode the content of `data:` URI(etc. `data:application/octet-stream;base64,...`), or None if `uri` is not a data URI.
+///
+/// Only base64 encoded data URI is supported, which is the only encoding allowed by glTF.
+pub fn decode_data_uri(uri: &str) -> Option<VkResult<Vec<u8>>> {
+
+    if uri.starts_with("data:") == false {
+        return None
+    }
+
+    let result = match uri.find(";base64,") {
+        | Some(index) => {
+            base64::decode(&uri[(index + ";base64,".len())..])
+                .map_err(|e| VkError::custom(format!("Invalid base64 data URI: {}", e)))
+        },
+        | None => {
+            Err(VkError::unsupported("Data URI without base64 encoding"))
+        },
+    };
+
+    Some(result)
+}
+// --------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn embedded_source_reads_registered_bytes() {
+
+        let source = EmbeddedSource::new()
+            .add_asset("shaders/test.vert", b"void main() {}");
+
+        assert_eq!(source.read_bytes(Path::new("shaders/test.vert")).unwrap(), b"void main() {}");
+        assert!(source.read_bytes(Path::new("shaders/missing.vert")).is_err());
+    }
+
+    #[test]
+    fn temporary_directories_are_unique_and_removed() {
+
+        let first  = unique_temp_dir().unwrap();
+        let second = unique_temp_dir().unwrap();
+        assert_ne!(first, second);
+
+        let asset = LocalAsset { path: first.join("a.ktx"), temp_dir: Some(first.clone()) };
+        std::fs::write(asset.path(), b"ktx").unwrap();
+        drop(asset);
+
+        assert!(first.exists() == false);
+        std::fs::remove_dir_all(second).unwrap();
+    }
+
+    #[test]
+    fn base64_data_uri_is_decoded() {
+
+        let bytes = decode_data_uri("data:application/octet-stream;base64,AAECAw==").unwrap().unwrap();
+        assert_eq!(bytes, vec![0, 1, 2, 3]);
+
+        assert!(decode_data_uri("buffer.bin").is_none());
+        assert!(decode_data_uri("data:text/plain,abc").unwrap().is_err());
+    }
+
+    #[cfg(feature = "zip")]
+    #[test]
+    fn zip_source_reads_entries() {
+
+        use std::io::Write;
+
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        writer.start_file("models/cube.gltf", zip::write::FileOptions::default()).unwrap();
+        writer.write_all(b"{}").unwrap();
+        let archive = writer.finish().unwrap().into_inner();
+
+        let source = ZipSource::new(Cursor::new(archive)).unwrap();
+        assert_eq!(source.read_bytes(Path::new("models/cube.gltf")).unwrap(), b"{}");
+        assert!(source.read_bytes(Path::new("models/missing.gltf")).is_err());
+    }
+}
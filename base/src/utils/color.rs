@@ -1,6 +1,14 @@
 
+use ash::vk;
+
 use crate::vkfloat;
 
+/// A RGBA color whose components are in range [0.0, 1.0].
+///
+/// The RGB components of `VkColor` literals(including the constants and `VkColor::new_u8`) are in sRGB space, just like the colors picked in image editors.
+/// Alpha component is always linear.
+/// Use `VkColor::to_linear` before writing the color to an attachment with `_SRGB` format(etc. clear values),
+/// since Vulkan treats such values as linear and encodes them to sRGB when storing.
 #[derive(Debug, Clone, Copy)]
 pub struct VkColor {
     pub r: vkfloat,
@@ -31,6 +39,63 @@ impl VkColor {
             a: (a as f32) / 255.0,
         }
     }
+
+    /// Convert this sRGB color to linear space.
+    pub fn to_linear(&self) -> VkColor {
+        VkColor::new(srgb_to_linear(self.r), srgb_to_linear(self.g), srgb_to_linear(self.b), self.a)
+    }
+
+    /// Convert this linear color to sRGB space.
+    pub fn to_srgb(&self) -> VkColor {
+        VkColor::new(linear_to_srgb(self.r), linear_to_srgb(self.g), linear_to_srgb(self.b), self.a)
+    }
+
+    /// Get the value of this color to write to an attachment of `format`, which is converted to linear space if `format` is sRGB.
+    pub fn for_format(&self, format: vk::Format) -> VkColor {
+        if is_srgb_format(format) {
+            self.to_linear()
+        } else {
+            *self
+        }
+    }
+}
+
+fn srgb_to_linear(value: vkfloat) -> vkfloat {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(value: vkfloat) -> vkfloat {
+    if value <= 0.003_130_8 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Check if `format` stores color in sRGB encoding, whose values are linear when read or written by shaders and clear operations.
+pub fn is_srgb_format(format: vk::Format) -> bool {
+    match format {
+        | vk::Format::R8_SRGB
+        | vk::Format::R8G8_SRGB
+        | vk::Format::R8G8B8_SRGB
+        | vk::Format::B8G8R8_SRGB
+        | vk::Format::R8G8B8A8_SRGB
+        | vk::Format::B8G8R8A8_SRGB
+        | vk::Format::A8B8G8R8_SRGB_PACK32
+        | vk::Format::BC1_RGB_SRGB_BLOCK
+        | vk::Format::BC1_RGBA_SRGB_BLOCK
+        | vk::Format::BC2_SRGB_BLOCK
+        | vk::Format::BC3_SRGB_BLOCK
+        | vk::Format::BC7_SRGB_BLOCK
+        | vk::Format::ETC2_R8G8B8_SRGB_BLOCK
+        | vk::Format::ETC2_R8G8B8A1_SRGB_BLOCK
+        | vk::Format::ETC2_R8G8B8A8_SRGB_BLOCK => true,
+        | _ => false,
+    }
 }
 
 impl From<[vkfloat; 4]> for VkColor {
@@ -0,0 +1,169 @@
+//! Helpers to choose the local workgroup size of compute shaders from the limits of device.
+//!
+//! The local size is transferred to shader by specialization constants, so declare it in compute shader as follows:
+//!
+//! ```ignore
+//! layout (local_size_x_id = 0, local_size_y_id = 1, local_size_z_id = 2) in;
+//! ```
+//!
+//! Portability considerations:
+//! - The subgroup size is only available on Vulkan 1.1, and 64 is used instead on Vulkan 1.0 devices.
+//! - Some devices vary their subgroup size between shaders, so a local size matching the subgroup size is only a performance hint.
+//! - The local size is always clamped to `maxComputeWorkGroupSize` and `maxComputeWorkGroupInvocations`.
+//! - The workgroup count of each dimension is limited by `maxComputeWorkGroupCount`, which is only guaranteed to be 65535.
+
+use ash::vk;
+
+use crate::context::VkPhysicalDevice;
+use crate::vkuint;
+use crate::{VkResult, VkError};
+
+/// The local size used when the subgroup size of device is unknown, which is a multiple of the subgroup size of most desktop GPUs.
+pub const FALLBACK_LOCAL_SIZE: vkuint = 64;
+
+/// Choose the local size of a one-dimensional compute shader, which matches the subgroup size of `phy` if possible.
+pub fn local_size_1d(phy: &VkPhysicalDevice) -> vkuint {
+    clamp_local_size(phy.subgroup_size(), phy.max_compute_work_group_size()[0], phy.max_compute_work_group_invocations())
+}
+
+fn clamp_local_size(subgroup_size: Option<vkuint>, max_size: vkuint, max_invocations: vkuint) -> vkuint {
+
+    subgroup_size.unwrap_or(FALLBACK_LOCAL_SIZE)
+        .min(max_size)
+        .min(max_invocations)
+        .max(1)
+}
+
+/// Get the count of workgroups to dispatch in one dimension, so that `element_count` elements are covered by workgroups of `local_size` invocations.
+///
+/// The shader should discard the invocations whose index is not less than `element_count`.
+///
+/// Return an error if the count exceeds `max_group_count`(see `VkPhysicalDevice::max_compute_work_group_count`),
+/// in which case split the elements into several dispatches or more dimensions.
+pub fn group_count(element_count: vkuint, local_size: vkuint, max_group_count: vkuint) -> VkResult<vkuint> {
+
+    debug_assert!(local_size > 0, "The local size of compute shader must be greater than 0!");
+
+    // compute in 64 bits, since `element_count + local_size - 1` overflows u32 for large element count.
+    let count = (element_count as u64 + local_size as u64 - 1) / local_size as u64;
+
+    if count > max_group_count as u64 {
+        Err(VkError::custom(format!("Dispatching {} elements by local size {} requires {} workgroups, which exceeds the limit {} of device.", element_count, local_size, count, max_group_count)))
+    } else {
+        Ok(count as vkuint)
+    }
+}
+
+/// The specialization constants of local size(constant id 0, 1, 2 for x, y, z dimension).
+#[derive(Debug, Clone)]
+pub struct LocalSizeSpecialization {
+
+    local_size: [vkuint; 3],
+    entries: [vk::SpecializationMapEntry; 3],
+}
+
+impl LocalSizeSpecialization {
+
+    pub fn new(local_size: [vkuint; 3]) -> LocalSizeSpecialization {
+
+        let entry = |id: vkuint| vk::SpecializationMapEntry {
+            constant_id: id,
+            offset: id * ::std::mem::size_of::<vkuint>() as vkuint,
+            size: ::std::mem::size_of::<vkuint>(),
+        };
+
+        LocalSizeSpecialization {
+            local_size,
+            entries: [entry(0), entry(1), entry(2)],
+        }
+    }
+
+    /// Create the specialization of a one-dimensional compute shader, whose local size is chosen by `local_size_1d`.
+    pub fn from_device(phy: &VkPhysicalDevice) -> LocalSizeSpecialization {
+        LocalSizeSpecialization::new([local_size_1d(phy), 1, 1])
+    }
+
+    #[inline]
+    pub fn local_size(&self) -> [vkuint; 3] {
+        self.local_size
+    }
+
+    /// Generate the `vk::SpecializationInfo` for `ShaderStageCI::specialization`.
+    ///
+    /// The result refers to the data of this object, so this object must outlive the pipeline creation.
+    pub fn info(&self) -> vk::SpecializationInfo {
+
+        vk::SpecializationInfo {
+            map_entry_count: self.entries.len() as _,
+            p_map_entries  : self.entries.as_ptr(),
+            data_size      : ::std::mem::size_of_val(&self.local_size),
+            p_data         : self.local_size.as_ptr() as _,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn group_count_covers_all_elements() {
+
+        for &local_size in &[1, 32, 64, 100] {
+            for &element_count in &[1, 31, 32, 33, 64, 1000, 4096] {
+
+                let count = group_count(element_count, local_size, 65535).unwrap();
+                assert!(count * local_size >= element_count);
+                assert!((count - 1) * local_size < element_count);
+            }
+        }
+
+        assert_eq!(group_count(0, 64, 65535).unwrap(), 0);
+    }
+
+    #[test]
+    fn group_count_does_not_overflow() {
+
+        // `u32::MAX + 63` overflows in 32 bits.
+        assert_eq!(group_count(vkuint::max_value(), 64, vkuint::max_value()).unwrap(), 67_108_864);
+        assert_eq!(group_count(vkuint::max_value(), 1, vkuint::max_value()).unwrap(), vkuint::max_value());
+    }
+
+    #[test]
+    fn group_count_is_limited_by_device() {
+
+        // 65535 is the minimum limit of maxComputeWorkGroupCount.
+        assert_eq!(group_count(65535 * 64, 64, 65535).unwrap(), 65535);
+        assert!(group_count(65535 * 64 + 1, 64, 65535).is_err());
+        assert!(group_count(vkuint::max_value(), 64, 65535).is_err());
+    }
+
+    #[test]
+    fn local_size_is_clamped_to_limits() {
+
+        assert_eq!(clamp_local_size(Some(32), 1024, 1024), 32);
+        assert_eq!(clamp_local_size(None, 1024, 1024), FALLBACK_LOCAL_SIZE);
+        assert_eq!(clamp_local_size(Some(128), 64, 1024), 64);
+        assert_eq!(clamp_local_size(Some(128), 1024, 16), 16);
+        assert_eq!(clamp_local_size(Some(0), 1024, 1024), 1);
+    }
+
+    #[test]
+    fn specialization_refers_local_size() {
+
+        let specialization = LocalSizeSpecialization::new([16, 4, 1]);
+        let info = specialization.info();
+
+        assert_eq!(info.map_entry_count, 3);
+        assert_eq!(info.data_size, 12);
+
+        let entries = unsafe { ::std::slice::from_raw_parts(info.p_map_entries, 3) };
+        let data = unsafe { ::std::slice::from_raw_parts(info.p_data as *const vkuint, 3) };
+        for (i, entry) in entries.iter().enumerate() {
+            assert_eq!(entry.constant_id, i as vkuint);
+            assert_eq!(entry.offset as usize, i * 4);
+        }
+        assert_eq!(data, &[16, 4, 1]);
+    }
+}
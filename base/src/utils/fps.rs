@@ -1,7 +1,10 @@
 
 use std::time::Instant;
+use std::collections::VecDeque;
 
 const FPS_SAMPLE_COUNT: usize = 5;
+/// the count of recent frames whose frame time is recorded.
+const FRAME_HISTORY_COUNT: usize = 120;
 const FPS_SAMPLE_COUNT_FLOAT: f32 = FPS_SAMPLE_COUNT as f32;
 const DEFAULT_PREFER_FPS: f32 = 60.0;
 
@@ -14,8 +17,13 @@ pub struct FpsCounter {
     samples: [u32; FPS_SAMPLE_COUNT],
     current_frame: usize,
     delta_frame: u32,
+    /// the frame time of recent frames(unit microseconds), from the oldest to the newest.
+    frame_history: VecDeque<u32>,
 
     second_counter: u32,
+
+    total_frames: u64,
+    total_elapsed: u64, // unit microseconds
 }
 
 impl FpsCounter {
@@ -28,7 +36,10 @@ impl FpsCounter {
             samples: [0; FPS_SAMPLE_COUNT],
             current_frame: 0,
             delta_frame: 0,
+            frame_history: VecDeque::with_capacity(FRAME_HISTORY_COUNT),
             second_counter: 0,
+            total_frames: 0,
+            total_elapsed: 0,
         }
     }
 
@@ -46,6 +57,14 @@ impl FpsCounter {
         self.samples[self.current_frame] = self.delta_frame;
         self.current_frame = (self.current_frame + 1) % FPS_SAMPLE_COUNT;
 
+        if self.frame_history.len() == FRAME_HISTORY_COUNT {
+            self.frame_history.pop_front();
+        }
+        self.frame_history.push_back(self.delta_frame);
+
+        self.total_frames += 1;
+        self.total_elapsed += time_elapsed.as_micros() as u64;
+
 
         if self.is_tick_second() {
             self.second_counter = 0;
@@ -84,4 +103,24 @@ impl FpsCounter {
     pub fn delta_time(&self) -> f32 {
         self.delta_frame as f32 / 1000_000.0_f32 // time in second
     }
+
+    /// Return the frame time in milliseconds of the recent frames(at most 120 frames), from the oldest to the newest.
+    ///
+    /// The times are computed lazily, so it can be called each frame without allocation.
+    pub fn frame_times(&self) -> impl Iterator<Item = f32> + '_ {
+        self.frame_history.iter()
+            .map(|&frame_time| frame_time as f32 / 1000.0_f32)
+    }
+
+    /// Return the count of frames that have been ticked since this counter was created.
+    #[inline]
+    pub fn total_frames(&self) -> u64 {
+        self.total_frames
+    }
+
+    /// Return the time in seconds elapsed since this counter was created until the last tick(etc. for a `time` uniform to animate something in shader).
+    #[inline]
+    pub fn elapsed_secs(&self) -> f32 {
+        (self.total_elapsed as f64 / 1000_000.0_f64) as f32
+    }
 }
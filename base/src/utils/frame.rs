@@ -1,4 +1,6 @@
 
+use crate::error::VkResult;
+
 pub struct FrameCounter {
 
     frame_in_flight: usize,
@@ -27,12 +29,83 @@ impl FrameCounter {
     }
 }
 
+/// A collection of resources keeping one copy for each swapchain image(etc. command buffers, uniform buffers).
+///
+/// `ProcPipeline` waits for the previous frame rendering to an image before calling `RenderWorkflow::render_frame`,
+/// so the resource at `image_index` can be updated safely there without hazarding the frames in flight.
+pub struct FrameResource<T> {
+
+    resources: Vec<T>,
+}
+
+impl<T> FrameResource<T> {
+
+    /// Create `frame_count` copies of resource by `generator`, which receives the index of each copy.
+    pub fn new(frame_count: usize, mut generator: impl FnMut(usize) -> VkResult<T>) -> VkResult<FrameResource<T>> {
+
+        let mut resources = Vec::with_capacity(frame_count);
+        for i in 0..frame_count {
+            resources.push(generator(i)?);
+        }
+
+        Ok(FrameResource { resources })
+    }
+
+    #[inline]
+    pub fn get(&self, index: usize) -> &T {
+        &self.resources[index]
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self, index: usize) -> &mut T {
+        &mut self.resources[index]
+    }
+
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.resources.iter()
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.resources.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.resources.is_empty()
+    }
+
+    /// Take the ownership of all resources(usually for destruction).
+    #[inline]
+    pub fn into_inner(self) -> Vec<T> {
+        self.resources
+    }
+}
+
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
 pub enum FrameAction {
     /// ordinary action.
     Rendering,
     /// tell program the swapchain has to update to adapt current window surface.
     SwapchainRecreate,
+    /// tell program the pre-recorded command buffers are out of date(etc. the pipeline to draw has changed),
+    /// so `RenderWorkflow::rerecord_commands` should be called once the device is idle.
+    CommandRerecord,
     /// Indicate the program to terminal.
     Terminal,
 }
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn frame_counter_cycles_frames_in_flight() {
+
+        let mut counter = FrameCounter::new(3);
+        let frames: Vec<usize> = (0..5).map(|_| { let frame = counter.current_frame(); counter.tick_frame(); frame }).collect();
+        assert_eq!(frames, vec![0, 1, 2, 0, 1]);
+    }
+}
@@ -4,23 +4,61 @@ use ash::vk;
 use std::ops::{Add, Sub, Not, BitAnd};
 
 use crate::context::VkDevice;
-use crate::vkuint;
+use crate::{vkuint, vkbytes};
 
-pub fn get_memory_type_index(device: &VkDevice, mut type_bits: vkuint, properties: vk::MemoryPropertyFlags) -> vkuint {
+/// The size of BAR window that is host visible without Resizable BAR.
+const LIMITED_BAR_SIZE: vkbytes = 256 * 1024 * 1024;
+
+pub fn get_memory_type_index(device: &VkDevice, type_bits: vkuint, properties: vk::MemoryPropertyFlags) -> vkuint {
+
+    find_memory_type_index(device, type_bits, properties)
+        .expect("Could not find a suitable memory type")
+}
+
+/// Similar to `get_memory_type_index`, but return None if there is no suitable memory type.
+pub fn find_memory_type_index(device: &VkDevice, mut type_bits: vkuint, properties: vk::MemoryPropertyFlags) -> Option<vkuint> {
 
     // Iterate over all memory types available for the device used in this example.
     let memories = &device.phy.memories;
     for i in 0..memories.memory_type_count {
         if (type_bits & 1) == 1 {
             if memories.memory_types[i as usize].property_flags.contains(properties) {
-                return i
+                return Some(i)
+            }
+        }
+
+        type_bits >>= 1;
+    }
+
+    None
+}
+
+/// The memory properties of device local memory which can be written by host directly(etc. the memory exposed by Resizable BAR).
+#[inline]
+pub fn direct_upload_flags() -> vk::MemoryPropertyFlags {
+    vk::MemoryPropertyFlags::DEVICE_LOCAL | vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT
+}
+
+/// Find the memory type with `direct_upload_flags`, whose heap is larger than the 256MB BAR window available without Resizable BAR.
+///
+/// Return None if such memory type does not exist, and data should be uploaded by a staging buffer instead.
+pub fn find_direct_upload_memory_index(device: &VkDevice, mut type_bits: vkuint) -> Option<vkuint> {
+
+    let memories = &device.phy.memories;
+    for i in 0..memories.memory_type_count {
+        if (type_bits & 1) == 1 {
+            let memory_type = &memories.memory_types[i as usize];
+            let heap_size = memories.memory_heaps[memory_type.heap_index as usize].size;
+
+            if memory_type.property_flags.contains(direct_upload_flags()) && heap_size > LIMITED_BAR_SIZE {
+                return Some(i)
             }
         }
 
         type_bits >>= 1;
     }
 
-    panic!("Could not find a suitable memory type")
+    None
 }
 
 pub fn is_memory_support_flags(device: &VkDevice, memory_type_index: vkuint, request_flags: vk::MemoryPropertyFlags) -> bool {
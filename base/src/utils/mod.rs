@@ -4,5 +4,15 @@ pub mod time;
 pub mod frame;
 pub mod fps;
 pub mod shaderc;
+pub mod reflect;
+pub mod spirv;
 pub mod memory;
 pub mod color;
+pub mod path;
+pub mod asset;
+pub mod staging;
+pub mod compute;
+pub mod scratch;
+pub mod uniform;
+pub mod viewport;
+pub mod owned;
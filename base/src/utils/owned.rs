@@ -0,0 +1,197 @@
+//! Opt-in RAII wrappers of buffer, image and memory objects, which are destroyed automatically when dropped.
+//!
+//! Each wrapper keeps a copy of the function table of logical device, so they must be dropped before the device is destroyed.
+//! `RenderWorkflow::deinit` consumes the program before the device is destroyed, so wrappers owned by the program are dropped in time.
+//! A wrapper dropped after its device is leaked with a warning, instead of calling into the destroyed device.
+//! Rust drops the fields of a struct in declaration order, so declare a buffer or image before the memory bound to it.
+//! Use `into_raw` to take back the raw handle when a specific destruction order is required.
+
+use ash::vk;
+use ash::version::DeviceV1_0;
+
+use crate::ci::VkObjectBuildableCI;
+use crate::ci::buffer::BufferCI;
+use crate::ci::image::ImageCI;
+use crate::ci::memory::MemoryAI;
+use crate::context::VkDevice;
+use crate::error::{VkResult, VkError};
+use crate::vkbytes;
+
+use std::mem;
+use std::sync::Weak;
+
+// ----------------------------------------------------------------------------------------------
+/// A `vk::Buffer` that is destroyed when dropped.
+pub struct OwnedBuffer {
+
+    handle: vk::Buffer,
+    requirement: vk::MemoryRequirements,
+    device: ash::Device,
+    alive: Weak<()>,
+}
+
+impl OwnedBuffer {
+
+    /// Create a buffer from `ci`.
+    pub fn new(device: &VkDevice, ci: &BufferCI) -> VkResult<OwnedBuffer> {
+
+        let (handle, requirement) = ci.build(device)?;
+        let result = OwnedBuffer { handle, requirement, device: device.logic.handle.clone(), alive: device.logic.liveness() };
+        Ok(result)
+    }
+
+    #[inline(always)]
+    pub fn handle(&self) -> vk::Buffer {
+        self.handle
+    }
+
+    /// Get the memory requirement of this buffer.
+    #[inline(always)]
+    pub fn requirement(&self) -> &vk::MemoryRequirements {
+        &self.requirement
+    }
+
+    /// Bind a specific range of `memory` to this buffer.
+    pub fn bind(&self, memory: &OwnedMemory, offset: vkbytes) -> VkResult<()> {
+        unsafe {
+            self.device.bind_buffer_memory(self.handle, memory.handle, offset)
+                .map_err(|_| VkError::device("Binding Buffer Memory"))
+        }
+    }
+
+    /// Release the ownership of buffer, so that it must be discarded manually by `VkDevice::discard`.
+    pub fn into_raw(self) -> vk::Buffer {
+        let handle = self.handle;
+        mem::forget(self);
+        handle
+    }
+}
+
+impl Drop for OwnedBuffer {
+
+    fn drop(&mut self) {
+
+        if is_device_alive(&self.alive, "Buffer") {
+            unsafe {
+                self.device.destroy_buffer(self.handle, None);
+            }
+        }
+    }
+}
+// ----------------------------------------------------------------------------------------------
+
+// ----------------------------------------------------------------------------------------------
+/// A `vk::Image` that is destroyed when dropped.
+pub struct OwnedImage {
+
+    handle: vk::Image,
+    requirement: vk::MemoryRequirements,
+    device: ash::Device,
+    alive: Weak<()>,
+}
+
+impl OwnedImage {
+
+    /// Create an image from `ci`.
+    pub fn new(device: &VkDevice, ci: &ImageCI) -> VkResult<OwnedImage> {
+
+        let (handle, requirement) = ci.build(device)?;
+        let result = OwnedImage { handle, requirement, device: device.logic.handle.clone(), alive: device.logic.liveness() };
+        Ok(result)
+    }
+
+    #[inline(always)]
+    pub fn handle(&self) -> vk::Image {
+        self.handle
+    }
+
+    /// Get the memory requirement of this image.
+    #[inline(always)]
+    pub fn requirement(&self) -> &vk::MemoryRequirements {
+        &self.requirement
+    }
+
+    /// Bind a specific range of `memory` to this image.
+    pub fn bind(&self, memory: &OwnedMemory, offset: vkbytes) -> VkResult<()> {
+        unsafe {
+            self.device.bind_image_memory(self.handle, memory.handle, offset)
+                .map_err(|_| VkError::device("Binding Image Memory"))
+        }
+    }
+
+    /// Release the ownership of image, so that it must be discarded manually by `VkDevice::discard`.
+    pub fn into_raw(self) -> vk::Image {
+        let handle = self.handle;
+        mem::forget(self);
+        handle
+    }
+}
+
+impl Drop for OwnedImage {
+
+    fn drop(&mut self) {
+
+        if is_device_alive(&self.alive, "Image") {
+            unsafe {
+                self.device.destroy_image(self.handle, None);
+            }
+        }
+    }
+}
+// ----------------------------------------------------------------------------------------------
+
+// ----------------------------------------------------------------------------------------------
+/// A `vk::DeviceMemory` that is freed when dropped.
+pub struct OwnedMemory {
+
+    handle: vk::DeviceMemory,
+    device: ash::Device,
+    alive: Weak<()>,
+}
+
+impl OwnedMemory {
+
+    /// Allocate memory from `ai`.
+    pub fn new(device: &VkDevice, ai: &MemoryAI) -> VkResult<OwnedMemory> {
+
+        let handle = ai.build(device)?;
+        let result = OwnedMemory { handle, device: device.logic.handle.clone(), alive: device.logic.liveness() };
+        Ok(result)
+    }
+
+    #[inline(always)]
+    pub fn handle(&self) -> vk::DeviceMemory {
+        self.handle
+    }
+
+    /// Release the ownership of memory, so that it must be freed manually by `VkDevice::discard`.
+    pub fn into_raw(self) -> vk::DeviceMemory {
+        let handle = self.handle;
+        mem::forget(self);
+        handle
+    }
+}
+
+impl Drop for OwnedMemory {
+
+    fn drop(&mut self) {
+
+        if is_device_alive(&self.alive, "Memory") {
+            unsafe {
+                self.device.free_memory(self.handle, None);
+            }
+        }
+    }
+}
+// ----------------------------------------------------------------------------------------------
+
+/// Check if the device of an owned object is still alive, or print a warning for the leaked object.
+fn is_device_alive(alive: &Weak<()>, object: &str) -> bool {
+
+    if alive.upgrade().is_some() {
+        true
+    } else {
+        println!("[Warning] Owned{} is dropped after its device has been destroyed, and it is leaked.", object);
+        false
+    }
+}
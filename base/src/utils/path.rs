@@ -0,0 +1,73 @@
+
+//! Resolve the paths of assets(etc. shader sources, models and textures) against a configurable root directory.
+
+use lazy_static::lazy_static;
+
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+lazy_static! {
+
+    static ref ASSET_ROOT: RwLock<Option<PathBuf>> = RwLock::new(None);
+}
+
+/// Set the root directory used to resolve relative asset paths.
+///
+/// A common choice is the manifest directory of the binary crate(etc. `env!("CARGO_MANIFEST_DIR")`),
+/// so that assets can be found regardless of the current working directory.
+pub fn set_asset_root(root: impl Into<PathBuf>) {
+
+    let mut asset_root = ASSET_ROOT.write()
+        .expect("Asset root lock is poisoned.");
+    *asset_root = Some(root.into());
+}
+
+/// Get the root directory of assets, or None if it has not been set.
+pub fn asset_root() -> Option<PathBuf> {
+
+    ASSET_ROOT.read()
+        .expect("Asset root lock is poisoned.")
+        .clone()
+}
+
+/// Resolve `path` against the asset root.
+///
+/// Absolute paths are returned unchanged.
+/// If the asset root has not been set, relative paths are returned unchanged, so they are relative to the current working directory.
+pub fn resolve_path(path: impl AsRef<Path>) -> PathBuf {
+    resolve_against(asset_root().as_ref().map(PathBuf::as_path), path.as_ref())
+}
+
+fn resolve_against(root: Option<&Path>, path: &Path) -> PathBuf {
+
+    if path.is_absolute() {
+        return path.to_path_buf()
+    }
+
+    match root {
+        | Some(root) => root.join(path),
+        | None => path.to_path_buf(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn relative_paths_are_resolved_against_root() {
+
+        let root = Path::new("workspace");
+        assert_eq!(resolve_against(Some(root), Path::new("shaders/a.glsl")), Path::new("workspace/shaders/a.glsl"));
+        // without root, the path is relative to the current working directory.
+        assert_eq!(resolve_against(None, Path::new("shaders/a.glsl")), Path::new("shaders/a.glsl"));
+    }
+
+    #[test]
+    fn absolute_paths_are_unchanged() {
+
+        let absolute = ::std::env::current_dir().unwrap().join("model.gltf");
+        assert_eq!(resolve_against(Some(Path::new("workspace")), &absolute), absolute);
+    }
+}
@@ -0,0 +1,933 @@
+//! A minimal SPIR-V reflection, which reads the interface of compiled shaders to generate the layouts of pipeline automatically.
+//!
+//! Only the information needed to create pipeline objects is parsed: the entry points(and whether their positions are invariant),
+//! the descriptor bindings, the push constant ranges and the vertex inputs.
+
+use ash::vk;
+
+use crate::utils::spirv::{SPIRV_MAGIC, spirv_words};
+use crate::error::{VkResult, VkError};
+use crate::vkuint;
+
+use std::collections::HashMap;
+use std::ptr;
+
+/// the count of words in the header of SPIR-V module.
+const SPIRV_HEADER_WORDS: usize = 5;
+/// the first SPIR-V version whose entry points list all the global variables they use, instead of only the input and output ones.
+const SPIRV_VERSION_1_4: u32 = 0x0001_0400;
+
+mod op {
+    pub const ENTRY_POINT        : u32 = 15;
+    pub const TYPE_BOOL          : u32 = 20;
+    pub const TYPE_INT           : u32 = 21;
+    pub const TYPE_FLOAT         : u32 = 22;
+    pub const TYPE_VECTOR        : u32 = 23;
+    pub const TYPE_MATRIX        : u32 = 24;
+    pub const TYPE_IMAGE         : u32 = 25;
+    pub const TYPE_SAMPLER       : u32 = 26;
+    pub const TYPE_SAMPLED_IMAGE : u32 = 27;
+    pub const TYPE_ARRAY         : u32 = 28;
+    pub const TYPE_RUNTIME_ARRAY : u32 = 29;
+    pub const TYPE_STRUCT        : u32 = 30;
+    pub const TYPE_POINTER       : u32 = 32;
+    pub const CONSTANT           : u32 = 43;
+    pub const VARIABLE           : u32 = 59;
+    pub const DECORATE           : u32 = 71;
+    pub const MEMBER_DECORATE    : u32 = 72;
+}
+
+mod decoration {
+    pub const BUFFER_BLOCK : u32 = 3;
+    pub const ARRAY_STRIDE : u32 = 6;
+    pub const MATRIX_STRIDE: u32 = 7;
+    pub const BUILT_IN     : u32 = 11;
+    pub const INVARIANT    : u32 = 18;
+    pub const LOCATION     : u32 = 30;
+    pub const BINDING      : u32 = 33;
+    pub const DESCRIPTOR_SET: u32 = 34;
+    pub const OFFSET       : u32 = 35;
+}
+
+mod storage {
+    pub const UNIFORM_CONSTANT: u32 = 0;
+    pub const INPUT           : u32 = 1;
+    pub const OUTPUT          : u32 = 3;
+    pub const UNIFORM         : u32 = 2;
+    pub const PUSH_CONSTANT   : u32 = 9;
+    pub const STORAGE_BUFFER  : u32 = 12;
+}
+
+/// the `BuiltIn` decoration of `gl_Position`.
+const BUILT_IN_POSITION: u32 = 0;
+
+/// the `Dim` operand of `OpTypeImage`.
+const IMAGE_DIM_BUFFER : u32 = 5;
+const IMAGE_DIM_SUBPASS: u32 = 6;
+
+// ----------------------------------------------------------------------------------------------
+/// An entry point declared in shader module.
+#[derive(Debug, Clone)]
+pub struct ReflectedEntryPoint {
+
+    pub name: String,
+    pub stage: vk::ShaderStageFlags,
+    /// whether the position output of this entry point is decorated as `invariant`(etc. `invariant gl_Position` in GLSL),
+    /// so that its value is the same in all the pipelines using it.
+    pub is_position_invariant: bool,
+    /// the ids of the global variables in the interface of this entry point.
+    interface: Vec<u32>,
+}
+
+/// A descriptor binding used by shader module.
+#[derive(Debug, Clone, Copy)]
+pub struct ReflectedBinding {
+
+    pub set: vkuint,
+    pub binding: vkuint,
+    pub descriptor_type: vk::DescriptorType,
+    /// the count of descriptors in this binding, or 0 if it's a runtime-sized array.
+    pub descriptor_count: vkuint,
+    pub stage_flags: vk::ShaderStageFlags,
+}
+
+/// An input attribute of vertex shader.
+///
+/// A matrix input is reflected as one attribute for each of its columns, in consecutive locations.
+#[derive(Debug, Clone, Copy)]
+pub struct ReflectedVertexInput {
+
+    pub location: vkuint,
+    pub format: vk::Format,
+    /// the size of this attribute in bytes.
+    pub size: vkuint,
+}
+
+/// The interface of shader modules, which is read from their SPIR-V codes.
+///
+/// The reflection can not tell the usage of resources decided by application,
+/// so dynamic uniform/storage buffers are reported as the ordinary ones, and the descriptor type should be changed manually.
+///
+/// The stages of a binding are the stages of the entry points using it. Before SPIR-V 1.4, entry points do not list the resources they use,
+/// so the resources of a module with several entry points are reported for all their stages(use `from_spirv_entry` to reflect one of them).
+#[derive(Debug, Clone, Default)]
+pub struct ShaderReflection {
+
+    pub entry_points: Vec<ReflectedEntryPoint>,
+    pub bindings: Vec<ReflectedBinding>,
+    pub push_constants: Vec<vk::PushConstantRange>,
+    pub vertex_inputs: Vec<ReflectedVertexInput>,
+}
+
+impl ShaderReflection {
+
+    /// Read the interface from SPIR-V `codes`(etc. the output of `VkShaderCompiler`).
+    pub fn from_spirv(codes: &[u8]) -> VkResult<ShaderReflection> {
+
+        let words = spirv_words(codes)?;
+        ShaderReflection::from_words(&words)
+    }
+
+    /// Read the interface from SPIR-V codes in words(etc. the output of `utils::spirv::load_spirv`).
+    pub fn from_words(words: &[u32]) -> VkResult<ShaderReflection> {
+
+        let module = SpirvModule::parse(words)?;
+        module.reflect(None)
+    }
+
+    /// Read the interface of the entry point named `entry_name` from SPIR-V `codes`, ignoring the other entry points in the module.
+    ///
+    /// Return error if the module has no such entry point.
+    pub fn from_spirv_entry(codes: &[u8], entry_name: &str) -> VkResult<ShaderReflection> {
+
+        let words = spirv_words(codes)?;
+        ShaderReflection::from_words_entry(&words, entry_name)
+    }
+
+    /// The same as `from_spirv_entry`, but read SPIR-V codes in words.
+    pub fn from_words_entry(words: &[u32], entry_name: &str) -> VkResult<ShaderReflection> {
+
+        let module = SpirvModule::parse(words)?;
+        module.reflect(Some(entry_name))
+    }
+
+    /// Get all the shader stages of the entry points, which may be more than the stages of a single binding.
+    pub fn stage_flags(&self) -> vk::ShaderStageFlags {
+        self.entry_points.iter()
+            .fold(vk::ShaderStageFlags::empty(), |flags, entry| flags | entry.stage)
+    }
+
+    /// Combine the reflection of another shader module in the same pipeline.
+    ///
+    /// The bindings and push constant ranges used by both modules are merged by uniting their stages.
+    /// Return error if the modules declare different descriptor types in the same binding.
+    pub fn merge(mut self, other: ShaderReflection) -> VkResult<ShaderReflection> {
+
+        self.entry_points.extend(other.entry_points);
+
+        for binding in other.bindings {
+            match self.bindings.iter_mut().find(|b| b.set == binding.set && b.binding == binding.binding) {
+                | Some(exist) => {
+                    if exist.descriptor_type != binding.descriptor_type {
+                        return Err(VkError::custom(format!("Shaders declare different descriptor types({:?} and {:?}) at set {}, binding {}.",
+                            exist.descriptor_type, binding.descriptor_type, binding.set, binding.binding)))
+                    }
+                    exist.stage_flags |= binding.stage_flags;
+                    exist.descriptor_count = exist.descriptor_count.max(binding.descriptor_count);
+                },
+                | None => self.bindings.push(binding),
+            }
+        }
+
+        for range in other.push_constants {
+            match self.push_constants.iter_mut().find(|r| r.offset == range.offset && r.size == range.size) {
+                | Some(exist) => exist.stage_flags |= range.stage_flags,
+                | None => self.push_constants.push(range),
+            }
+        }
+
+        // only the vertex shader has vertex inputs.
+        if self.vertex_inputs.is_empty() {
+            self.vertex_inputs = other.vertex_inputs;
+        }
+
+        Ok(self)
+    }
+
+    /// Get the count of descriptor sets used by shaders.
+    pub fn set_count(&self) -> vkuint {
+        self.bindings.iter().map(|b| b.set + 1).max().unwrap_or(0)
+    }
+
+    /// Generate the layout bindings of descriptor set `set`, in the order of binding index.
+    ///
+    /// Runtime-sized arrays get 1 descriptor, since their real count is decided by application.
+    pub fn layout_bindings(&self, set: vkuint) -> Vec<vk::DescriptorSetLayoutBinding> {
+
+        let mut bindings: Vec<vk::DescriptorSetLayoutBinding> = self.bindings.iter()
+            .filter(|b| b.set == set)
+            .map(|b| vk::DescriptorSetLayoutBinding {
+                binding: b.binding,
+                descriptor_type: b.descriptor_type,
+                descriptor_count: b.descriptor_count.max(1),
+                stage_flags: b.stage_flags,
+                p_immutable_samplers: ptr::null(),
+            })
+            .collect();
+
+        bindings.sort_by_key(|b| b.binding);
+        bindings
+    }
+
+    /// Generate the vertex input descriptions for vertex buffer at `binding`,
+    /// assuming all attributes are interleaved and tightly packed in the order of their locations.
+    pub fn packed_vertex_inputs(&self, binding: vkuint) -> (vk::VertexInputBindingDescription, Vec<vk::VertexInputAttributeDescription>) {
+
+        let mut inputs = self.vertex_inputs.clone();
+        inputs.sort_by_key(|input| input.location);
+
+        let mut offset = 0;
+        let attributes = inputs.iter().map(|input| {
+            let attribute = vk::VertexInputAttributeDescription {
+                location: input.location,
+                binding,
+                format: input.format,
+                offset,
+            };
+            offset += input.size;
+            attribute
+        }).collect();
+
+        let binding_description = vk::VertexInputBindingDescription {
+            binding,
+            stride: offset,
+            input_rate: vk::VertexInputRate::VERTEX,
+        };
+
+        (binding_description, attributes)
+    }
+}
+// ----------------------------------------------------------------------------------------------
+
+// ----------------------------------------------------------------------------------------------
+#[derive(Debug, Clone)]
+enum SpirvType {
+    Scalar { is_float: bool, is_signed: bool, width: u32 },
+    Vector { component: u32, count: u32 },
+    Matrix { column: u32, count: u32 },
+    Image { dim: u32, sampled: u32 },
+    Sampler,
+    SampledImage,
+    Array { element: u32, length_id: u32 },
+    RuntimeArray { element: u32 },
+    Struct { members: Vec<u32> },
+    Pointer { pointee: u32 },
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Decorations {
+    set: Option<u32>,
+    binding: Option<u32>,
+    location: Option<u32>,
+    array_stride: Option<u32>,
+    built_in: Option<u32>,
+    is_invariant: bool,
+    is_buffer_block: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct MemberDecorations {
+    offset: Option<u32>,
+    matrix_stride: Option<u32>,
+    built_in: Option<u32>,
+    is_invariant: bool,
+}
+
+struct SpirvVariable {
+    id: u32,
+    pointer_type: u32,
+    storage: u32,
+}
+
+/// The parsed instructions of SPIR-V module, which are needed by reflection.
+#[derive(Default)]
+struct SpirvModule {
+    version: u32,
+    entry_points: Vec<ReflectedEntryPoint>,
+    types: HashMap<u32, SpirvType>,
+    constants: HashMap<u32, u32>,
+    decorations: HashMap<u32, Decorations>,
+    member_decorations: HashMap<(u32, u32), MemberDecorations>,
+    variables: Vec<SpirvVariable>,
+}
+
+impl SpirvModule {
+
+    fn parse(words: &[u32]) -> VkResult<SpirvModule> {
+
+        if words.len() < SPIRV_HEADER_WORDS || words[0] != SPIRV_MAGIC {
+            return Err(VkError::custom("Invalid SPIR-V codes(missing SPIR-V header)."))
+        }
+
+        let mut module = SpirvModule { version: words[1], ..Default::default() };
+        let mut cursor = SPIRV_HEADER_WORDS;
+
+        while cursor < words.len() {
+
+            let word_count = (words[cursor] >> 16) as usize;
+            let opcode = words[cursor] & 0xFFFF;
+
+            if word_count == 0 || cursor + word_count > words.len() {
+                return Err(VkError::custom(format!("Invalid SPIR-V instruction(opcode {}) at word {}.", opcode, cursor)))
+            }
+
+            module.read_instruction(opcode, &words[(cursor + 1)..(cursor + word_count)]);
+            cursor += word_count;
+        }
+
+        Ok(module)
+    }
+
+    fn read_instruction(&mut self, opcode: u32, operands: &[u32]) {
+
+        // ignore the malformed instructions missing operands.
+        let operand = |i: usize| operands.get(i).cloned().unwrap_or(0);
+
+        match opcode {
+            | op::ENTRY_POINT => {
+                if let Some(stage) = execution_stage(operand(0)) {
+                    let name_operands = &operands[2.min(operands.len())..];
+                    let name = read_string(name_operands);
+                    // the interface ids follow the nul-terminated name.
+                    let name_words = (name.len() / 4 + 1).min(name_operands.len());
+                    let interface = name_operands[name_words..].to_vec();
+                    self.entry_points.push(ReflectedEntryPoint { name, stage, interface, is_position_invariant: false });
+                }
+            },
+            | op::TYPE_BOOL => {
+                self.types.insert(operand(0), SpirvType::Scalar { is_float: false, is_signed: false, width: 32 });
+            },
+            | op::TYPE_INT => {
+                self.types.insert(operand(0), SpirvType::Scalar { is_float: false, is_signed: operand(2) != 0, width: operand(1) });
+            },
+            | op::TYPE_FLOAT => {
+                self.types.insert(operand(0), SpirvType::Scalar { is_float: true, is_signed: true, width: operand(1) });
+            },
+            | op::TYPE_VECTOR => {
+                self.types.insert(operand(0), SpirvType::Vector { component: operand(1), count: operand(2) });
+            },
+            | op::TYPE_MATRIX => {
+                self.types.insert(operand(0), SpirvType::Matrix { column: operand(1), count: operand(2) });
+            },
+            | op::TYPE_IMAGE => {
+                self.types.insert(operand(0), SpirvType::Image { dim: operand(2), sampled: operand(6) });
+            },
+            | op::TYPE_SAMPLER => {
+                self.types.insert(operand(0), SpirvType::Sampler);
+            },
+            | op::TYPE_SAMPLED_IMAGE => {
+                self.types.insert(operand(0), SpirvType::SampledImage);
+            },
+            | op::TYPE_ARRAY => {
+                self.types.insert(operand(0), SpirvType::Array { element: operand(1), length_id: operand(2) });
+            },
+            | op::TYPE_RUNTIME_ARRAY => {
+                self.types.insert(operand(0), SpirvType::RuntimeArray { element: operand(1) });
+            },
+            | op::TYPE_STRUCT => {
+                let members = operands.iter().skip(1).cloned().collect();
+                self.types.insert(operand(0), SpirvType::Struct { members });
+            },
+            | op::TYPE_POINTER => {
+                self.types.insert(operand(0), SpirvType::Pointer { pointee: operand(2) });
+            },
+            | op::CONSTANT => {
+                // only the low 32 bits are needed for the length of arrays.
+                self.constants.insert(operand(1), operand(2));
+            },
+            | op::VARIABLE => {
+                self.variables.push(SpirvVariable { pointer_type: operand(0), id: operand(1), storage: operand(2) });
+            },
+            | op::DECORATE => {
+                let target = self.decorations.entry(operand(0)).or_default();
+                match operand(1) {
+                    | decoration::DESCRIPTOR_SET => target.set = Some(operand(2)),
+                    | decoration::BINDING        => target.binding = Some(operand(2)),
+                    | decoration::LOCATION       => target.location = Some(operand(2)),
+                    | decoration::ARRAY_STRIDE   => target.array_stride = Some(operand(2)),
+                    | decoration::BUILT_IN       => target.built_in = Some(operand(2)),
+                    | decoration::INVARIANT      => target.is_invariant = true,
+                    | decoration::BUFFER_BLOCK   => target.is_buffer_block = true,
+                    | _ => {},
+                }
+            },
+            | op::MEMBER_DECORATE => {
+                let target = self.member_decorations.entry((operand(0), operand(1))).or_default();
+                match operand(2) {
+                    | decoration::OFFSET        => target.offset = Some(operand(3)),
+                    | decoration::MATRIX_STRIDE => target.matrix_stride = Some(operand(3)),
+                    | decoration::BUILT_IN      => target.built_in = Some(operand(3)),
+                    | decoration::INVARIANT     => target.is_invariant = true,
+                    | _ => {},
+                }
+            },
+            | _ => {},
+        }
+    }
+
+    /// Reflect the interface of the entry point named `entry_name`, or all the entry points if it's None.
+    fn reflect(&self, entry_name: Option<&str>) -> VkResult<ShaderReflection> {
+
+        let entry_points: Vec<ReflectedEntryPoint> = match entry_name {
+            | Some(name) => {
+                let entry = self.entry_points.iter().find(|entry| entry.name == name)
+                    .ok_or_else(|| VkError::custom(format!("Shader module has no entry point `{}` to reflect.", name)))?;
+                vec![entry.clone()]
+            },
+            | None => self.entry_points.clone(),
+        };
+
+        let entry_points = entry_points.into_iter()
+            .map(|entry| ReflectedEntryPoint { is_position_invariant: self.is_position_invariant(&entry), ..entry })
+            .collect();
+
+        let mut reflection = ShaderReflection {
+            entry_points,
+            ..Default::default()
+        };
+
+        for variable in self.variables.iter() {
+
+            let pointee = match self.types.get(&variable.pointer_type) {
+                | Some(SpirvType::Pointer { pointee }) => *pointee,
+                | _ => continue,
+            };
+            let decorations = self.decorations.get(&variable.id).cloned().unwrap_or_default();
+
+            let stage_flags = self.variable_stages(&reflection.entry_points, variable);
+            if stage_flags.is_empty() {
+                // the variable is not used by the reflected entry points.
+                continue
+            }
+
+            match variable.storage {
+                | storage::UNIFORM_CONSTANT
+                | storage::UNIFORM
+                | storage::STORAGE_BUFFER => {
+
+                    let (set, binding) = match (decorations.set, decorations.binding) {
+                        | (Some(set), Some(binding)) => (set, binding),
+                        | _ => continue,
+                    };
+
+                    let (element, descriptor_count) = match self.types.get(&pointee) {
+                        | Some(SpirvType::Array { element, length_id }) => (*element, self.constants.get(length_id).cloned().unwrap_or(1)),
+                        | Some(SpirvType::RuntimeArray { element })     => (*element, 0),
+                        | _ => (pointee, 1),
+                    };
+
+                    let descriptor_type = self.descriptor_type(variable.storage, element)
+                        .ok_or_else(|| VkError::custom(format!("Unsupported descriptor type at set {}, binding {} in SPIR-V reflection.", set, binding)))?;
+
+                    reflection.bindings.push(ReflectedBinding { set, binding, descriptor_type, descriptor_count, stage_flags });
+                },
+                | storage::PUSH_CONSTANT => {
+
+                    if let Some(SpirvType::Struct { members }) = self.types.get(&pointee) {
+
+                        let offset = (0..members.len() as u32)
+                            .filter_map(|i| self.member_decorations.get(&(pointee, i)).and_then(|m| m.offset))
+                            .min().unwrap_or(0);
+                        let end = self.type_size(pointee, None);
+
+                        if end > offset {
+                            reflection.push_constants.push(vk::PushConstantRange { stage_flags, offset, size: end - offset });
+                        }
+                    }
+                },
+                | storage::INPUT if stage_flags.contains(vk::ShaderStageFlags::VERTEX) => {
+
+                    if decorations.built_in.is_some() {
+                        continue
+                    }
+
+                    if let Some(location) = decorations.location {
+
+                        // report the unsupported inputs, since skipping them would break the offsets of `packed_vertex_inputs`.
+                        let attributes = self.vertex_attributes(pointee)
+                            .ok_or_else(|| VkError::custom(format!("Unsupported type of vertex input at location {} in SPIR-V reflection.", location)))?;
+
+                        let mut attribute_location = location;
+                        for (format, size) in attributes {
+                            reflection.vertex_inputs.push(ReflectedVertexInput { location: attribute_location, format, size });
+                            // 64-bit vectors with more than 2 components consume 2 locations.
+                            attribute_location += if size > 16 { 2 } else { 1 };
+                        }
+                    }
+                },
+                | _ => {},
+            }
+        }
+
+        Ok(reflection)
+    }
+
+    /// Check if the `gl_Position` output of `entry` is decorated as invariant, either as a variable or as a member of `gl_PerVertex` block.
+    fn is_position_invariant(&self, entry: &ReflectedEntryPoint) -> bool {
+
+        let is_invariant_position = |built_in: Option<u32>, is_invariant: bool| {
+            built_in == Some(BUILT_IN_POSITION) && is_invariant
+        };
+
+        self.variables.iter()
+            .filter(|variable| variable.storage == storage::OUTPUT && entry.interface.contains(&variable.id))
+            .any(|variable| {
+
+                let decorations = self.decorations.get(&variable.id).cloned().unwrap_or_default();
+                if is_invariant_position(decorations.built_in, decorations.is_invariant) {
+                    return true
+                }
+
+                match self.types.get(&variable.pointer_type) {
+                    | Some(SpirvType::Pointer { pointee }) => {
+                        match self.types.get(pointee) {
+                            | Some(SpirvType::Struct { members }) => {
+                                (0..members.len() as u32).any(|i| {
+                                    self.member_decorations.get(&(*pointee, i))
+                                        .map_or(false, |member| is_invariant_position(member.built_in, member.is_invariant))
+                                })
+                            },
+                            | _ => false,
+                        }
+                    },
+                    | _ => false,
+                }
+            })
+    }
+
+    /// Get the stages of `entry_points` using `variable`.
+    fn variable_stages(&self, entry_points: &[ReflectedEntryPoint], variable: &SpirvVariable) -> vk::ShaderStageFlags {
+
+        // the inputs and outputs are always listed in the interface of entry points,
+        // and other global variables are only listed since SPIR-V 1.4.
+        let is_listed = self.version >= SPIRV_VERSION_1_4 || variable.storage == storage::INPUT || variable.storage == storage::OUTPUT;
+
+        entry_points.iter()
+            .filter(|entry| is_listed == false || entry.interface.contains(&variable.id))
+            .fold(vk::ShaderStageFlags::empty(), |flags, entry| flags | entry.stage)
+    }
+
+    fn descriptor_type(&self, storage_class: u32, type_id: u32) -> Option<vk::DescriptorType> {
+
+        let descriptor_type = match (storage_class, self.types.get(&type_id)?) {
+            | (storage::UNIFORM_CONSTANT, SpirvType::Sampler) => vk::DescriptorType::SAMPLER,
+            | (storage::UNIFORM_CONSTANT, SpirvType::SampledImage) => vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+            | (storage::UNIFORM_CONSTANT, SpirvType::Image { dim, sampled }) => {
+                // `sampled` is 1 for images used with sampler, and 2 for storage images.
+                match (*dim, *sampled) {
+                    | (IMAGE_DIM_SUBPASS, _) => vk::DescriptorType::INPUT_ATTACHMENT,
+                    | (IMAGE_DIM_BUFFER, 2)  => vk::DescriptorType::STORAGE_TEXEL_BUFFER,
+                    | (IMAGE_DIM_BUFFER, _)  => vk::DescriptorType::UNIFORM_TEXEL_BUFFER,
+                    | (_, 2) => vk::DescriptorType::STORAGE_IMAGE,
+                    | (_, _) => vk::DescriptorType::SAMPLED_IMAGE,
+                }
+            },
+            | (storage::UNIFORM, SpirvType::Struct { .. }) => {
+                // storage buffers are decorated with `BufferBlock` before SPIR-V 1.3.
+                let is_buffer_block = self.decorations.get(&type_id).map_or(false, |d| d.is_buffer_block);
+                if is_buffer_block {
+                    vk::DescriptorType::STORAGE_BUFFER
+                } else {
+                    vk::DescriptorType::UNIFORM_BUFFER
+                }
+            },
+            | (storage::STORAGE_BUFFER, SpirvType::Struct { .. }) => vk::DescriptorType::STORAGE_BUFFER,
+            | _ => return None,
+        };
+
+        Some(descriptor_type)
+    }
+
+    /// Calculate the size of type in bytes. `matrix_stride` is the `MatrixStride` decoration of the struct member of this type.
+    fn type_size(&self, type_id: u32, matrix_stride: Option<u32>) -> u32 {
+
+        match self.types.get(&type_id) {
+            | Some(SpirvType::Scalar { width, .. }) => width / 8,
+            | Some(SpirvType::Vector { component, count }) => self.type_size(*component, None) * count,
+            | Some(SpirvType::Matrix { column, count }) => {
+                matrix_stride.unwrap_or_else(|| self.type_size(*column, None)) * count
+            },
+            | Some(SpirvType::Array { element, length_id }) => {
+                let length = self.constants.get(length_id).cloned().unwrap_or(1);
+                let stride = self.decorations.get(&type_id).and_then(|d| d.array_stride)
+                    .unwrap_or_else(|| self.type_size(*element, matrix_stride));
+                stride * length
+            },
+            | Some(SpirvType::Struct { members }) => {
+                members.iter().enumerate().map(|(i, member)| {
+                    let member_decorations = self.member_decorations.get(&(type_id, i as u32)).cloned().unwrap_or_default();
+                    member_decorations.offset.unwrap_or(0) + self.type_size(*member, member_decorations.matrix_stride)
+                }).max().unwrap_or(0)
+            },
+            | _ => 0,
+        }
+    }
+
+    /// Get the format and size in bytes of the vertex attributes of input type, or None if the type can not be a vertex input.
+    ///
+    /// A matrix is split into the attributes of its columns.
+    fn vertex_attributes(&self, type_id: u32) -> Option<Vec<(vk::Format, vkuint)>> {
+
+        match self.types.get(&type_id)? {
+            | SpirvType::Matrix { column, count } => {
+                let column_attribute = self.vertex_format(*column)?;
+                Some(vec![column_attribute; *count as usize])
+            },
+            | _ => {
+                self.vertex_format(type_id).map(|attribute| vec![attribute])
+            },
+        }
+    }
+
+    /// Get the vertex attribute format and its size in bytes of scalar or vector type, whose components are 8, 16, 32 or 64 bits.
+    fn vertex_format(&self, type_id: u32) -> Option<(vk::Format, vkuint)> {
+
+        let (component, count) = match self.types.get(&type_id)? {
+            | SpirvType::Scalar { .. } => (type_id, 1),
+            | SpirvType::Vector { component, count } => (*component, *count),
+            | _ => return None,
+        };
+
+        let (is_float, is_signed, width) = match self.types.get(&component)? {
+            | SpirvType::Scalar { is_float, is_signed, width } => (*is_float, *is_signed, *width),
+            | _ => return None,
+        };
+
+        use ash::vk::Format as F;
+        let formats = match (is_float, is_signed, width) {
+            | (true, _, 16) => [F::R16_SFLOAT, F::R16G16_SFLOAT, F::R16G16B16_SFLOAT, F::R16G16B16A16_SFLOAT],
+            | (true, _, 32) => [F::R32_SFLOAT, F::R32G32_SFLOAT, F::R32G32B32_SFLOAT, F::R32G32B32A32_SFLOAT],
+            | (true, _, 64) => [F::R64_SFLOAT, F::R64G64_SFLOAT, F::R64G64B64_SFLOAT, F::R64G64B64A64_SFLOAT],
+            | (false, true,  8)  => [F::R8_SINT,  F::R8G8_SINT,   F::R8G8B8_SINT,    F::R8G8B8A8_SINT],
+            | (false, false, 8)  => [F::R8_UINT,  F::R8G8_UINT,   F::R8G8B8_UINT,    F::R8G8B8A8_UINT],
+            | (false, true,  16) => [F::R16_SINT, F::R16G16_SINT, F::R16G16B16_SINT, F::R16G16B16A16_SINT],
+            | (false, false, 16) => [F::R16_UINT, F::R16G16_UINT, F::R16G16B16_UINT, F::R16G16B16A16_UINT],
+            | (false, true,  32) => [F::R32_SINT, F::R32G32_SINT, F::R32G32B32_SINT, F::R32G32B32A32_SINT],
+            | (false, false, 32) => [F::R32_UINT, F::R32G32_UINT, F::R32G32B32_UINT, F::R32G32B32A32_UINT],
+            | (false, true,  64) => [F::R64_SINT, F::R64G64_SINT, F::R64G64B64_SINT, F::R64G64B64A64_SINT],
+            | (false, false, 64) => [F::R64_UINT, F::R64G64_UINT, F::R64G64B64_UINT, F::R64G64B64A64_UINT],
+            | _ => return None,
+        };
+
+        let format = *formats.get((count as usize).checked_sub(1)?)?;
+        Some((format, count * width / 8))
+    }
+}
+
+fn execution_stage(execution_model: u32) -> Option<vk::ShaderStageFlags> {
+
+    let stage = match execution_model {
+        | 0 => vk::ShaderStageFlags::VERTEX,
+        | 1 => vk::ShaderStageFlags::TESSELLATION_CONTROL,
+        | 2 => vk::ShaderStageFlags::TESSELLATION_EVALUATION,
+        | 3 => vk::ShaderStageFlags::GEOMETRY,
+        | 4 => vk::ShaderStageFlags::FRAGMENT,
+        | 5 => vk::ShaderStageFlags::COMPUTE,
+        | _ => return None,
+    };
+    Some(stage)
+}
+
+/// Read a literal string, which is nul-terminated and packed into words with the lowest-order bytes first.
+fn read_string(words: &[u32]) -> String {
+
+    let bytes: Vec<u8> = words.iter()
+        .flat_map(|word| word.to_le_bytes().to_vec())
+        .take_while(|byte| *byte != 0)
+        .collect();
+
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+// ----------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// Encode an instruction of `opcode` with its `operands`.
+    fn inst(opcode: u32, operands: &[u32]) -> Vec<u32> {
+        let mut words = vec![((operands.len() as u32 + 1) << 16) | opcode];
+        words.extend_from_slice(operands);
+        words
+    }
+
+    /// Encode a literal string as nul-terminated words.
+    fn string(name: &str) -> Vec<u32> {
+        let mut bytes = name.as_bytes().to_vec();
+        bytes.resize((bytes.len() / 4 + 1) * 4, 0);
+        bytes.chunks(4).map(|chunk| u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]])).collect()
+    }
+
+    fn entry_point(model: u32, function: u32, name: &str, interface: &[u32]) -> Vec<u32> {
+        let mut operands = vec![model, function];
+        operands.extend(string(name));
+        operands.extend_from_slice(interface);
+        inst(op::ENTRY_POINT, &operands)
+    }
+
+    fn module(version: u32, instructions: Vec<Vec<u32>>) -> Vec<u32> {
+        let mut words = vec![SPIRV_MAGIC, version, 0, 100, 0];
+        words.extend(instructions.into_iter().flatten());
+        words
+    }
+
+    /// A module with a vertex entry point `vs`(using inputs 10, 11 and 12) and a fragment entry point `fs`(using input 13).
+    /// Both share the uniform buffer 20 at set 0, binding 0, and the fragment stage samples the image array 21 at set 1, binding 2.
+    fn two_stage_module(version: u32) -> Vec<u32> {
+        module(version, vec![
+            entry_point(0, 1, "vs", &[10, 11, 12, 20]),
+            entry_point(4, 2, "fs", &[13, 20, 21]),
+            inst(op::DECORATE, &[10, decoration::LOCATION, 0]),
+            inst(op::DECORATE, &[11, decoration::LOCATION, 1]),
+            inst(op::DECORATE, &[12, decoration::LOCATION, 2]),
+            inst(op::DECORATE, &[13, decoration::LOCATION, 0]),
+            inst(op::DECORATE, &[20, decoration::DESCRIPTOR_SET, 0]),
+            inst(op::DECORATE, &[20, decoration::BINDING, 0]),
+            inst(op::DECORATE, &[21, decoration::DESCRIPTOR_SET, 1]),
+            inst(op::DECORATE, &[21, decoration::BINDING, 2]),
+            inst(op::MEMBER_DECORATE, &[40, 0, decoration::OFFSET, 0]),
+            inst(op::MEMBER_DECORATE, &[40, 0, decoration::MATRIX_STRIDE, 16]),
+            // %30 = float, %31 = vec3, %32 = u16, %33 = u16vec2, %34 = vec4, %35 = mat4.
+            inst(op::TYPE_FLOAT, &[30, 32]),
+            inst(op::TYPE_VECTOR, &[31, 30, 3]),
+            inst(op::TYPE_INT, &[32, 16, 0]),
+            inst(op::TYPE_VECTOR, &[33, 32, 2]),
+            inst(op::TYPE_VECTOR, &[34, 30, 4]),
+            inst(op::TYPE_MATRIX, &[35, 34, 4]),
+            // %40 = struct { mat4 }, %41 = sampled image, %42 = uint, %43 = 4, %44 = sampled image[4].
+            inst(op::TYPE_STRUCT, &[40, 35]),
+            inst(op::TYPE_SAMPLED_IMAGE, &[41, 0]),
+            inst(op::TYPE_INT, &[42, 32, 0]),
+            inst(op::CONSTANT, &[42, 43, 4]),
+            inst(op::TYPE_ARRAY, &[44, 41, 43]),
+            // pointers.
+            inst(op::TYPE_POINTER, &[50, storage::INPUT, 31]),
+            inst(op::TYPE_POINTER, &[51, storage::INPUT, 33]),
+            inst(op::TYPE_POINTER, &[52, storage::INPUT, 35]),
+            inst(op::TYPE_POINTER, &[53, storage::UNIFORM, 40]),
+            inst(op::TYPE_POINTER, &[54, storage::UNIFORM_CONSTANT, 44]),
+            // variables.
+            inst(op::VARIABLE, &[50, 10, storage::INPUT]),
+            inst(op::VARIABLE, &[51, 11, storage::INPUT]),
+            inst(op::VARIABLE, &[52, 12, storage::INPUT]),
+            inst(op::VARIABLE, &[50, 13, storage::INPUT]),
+            inst(op::VARIABLE, &[53, 20, storage::UNIFORM]),
+            inst(op::VARIABLE, &[54, 21, storage::UNIFORM_CONSTANT]),
+        ])
+    }
+
+    #[test]
+    fn entry_points_and_their_interfaces_are_read() {
+
+        let reflection = ShaderReflection::from_words(&two_stage_module(0x0001_0000)).unwrap();
+
+        let names: Vec<&str> = reflection.entry_points.iter().map(|entry| entry.name.as_str()).collect();
+        assert_eq!(names, ["vs", "fs"]);
+        assert_eq!(reflection.entry_points[0].interface, [10, 11, 12, 20]);
+        assert_eq!(reflection.stage_flags(), vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT);
+    }
+
+    #[test]
+    fn fragment_inputs_are_not_vertex_inputs() {
+
+        let reflection = ShaderReflection::from_words(&two_stage_module(0x0001_0000)).unwrap();
+
+        // vec3 at location 0, u16vec2 at location 1, and the 4 columns of mat4 from location 2.
+        let locations: Vec<vkuint> = reflection.vertex_inputs.iter().map(|input| input.location).collect();
+        assert_eq!(locations, [0, 1, 2, 3, 4, 5]);
+        assert_eq!(reflection.vertex_inputs[0].format, vk::Format::R32G32B32_SFLOAT);
+        assert_eq!(reflection.vertex_inputs[1].format, vk::Format::R16G16_UINT);
+        assert_eq!(reflection.vertex_inputs[2].format, vk::Format::R32G32B32A32_SFLOAT);
+
+        let (binding, attributes) = reflection.packed_vertex_inputs(0);
+        assert_eq!(binding.stride, 12 + 4 + 64);
+        assert_eq!(attributes[1].offset, 12);
+        assert_eq!(attributes[2].offset, 16);
+    }
+
+    #[test]
+    fn binding_stages_follow_the_entry_points_using_them() {
+
+        // SPIR-V 1.4 lists all the global variables used by each entry point.
+        let reflection = ShaderReflection::from_words(&two_stage_module(SPIRV_VERSION_1_4)).unwrap();
+
+        let ubo = reflection.bindings.iter().find(|b| b.set == 0 && b.binding == 0).unwrap();
+        assert_eq!(ubo.descriptor_type, vk::DescriptorType::UNIFORM_BUFFER);
+        assert_eq!(ubo.stage_flags, vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT);
+
+        let images = reflection.bindings.iter().find(|b| b.set == 1 && b.binding == 2).unwrap();
+        assert_eq!(images.descriptor_type, vk::DescriptorType::COMBINED_IMAGE_SAMPLER);
+        assert_eq!(images.descriptor_count, 4);
+        assert_eq!(images.stage_flags, vk::ShaderStageFlags::FRAGMENT);
+
+        assert_eq!(reflection.set_count(), 2);
+        assert_eq!(reflection.layout_bindings(1)[0].descriptor_count, 4);
+    }
+
+    #[test]
+    fn single_entry_point_is_reflected_alone() {
+
+        let reflection = ShaderReflection::from_words_entry(&two_stage_module(0x0001_0000), "fs").unwrap();
+
+        assert_eq!(reflection.stage_flags(), vk::ShaderStageFlags::FRAGMENT);
+        assert!(reflection.vertex_inputs.is_empty());
+        assert!(reflection.bindings.iter().all(|b| b.stage_flags == vk::ShaderStageFlags::FRAGMENT));
+
+        assert!(ShaderReflection::from_words_entry(&two_stage_module(0x0001_0000), "main").is_err());
+    }
+
+    #[test]
+    fn push_constant_range_covers_its_struct() {
+
+        let words = module(0x0001_0000, vec![
+            entry_point(0, 1, "main", &[]),
+            inst(op::MEMBER_DECORATE, &[40, 0, decoration::OFFSET, 0]),
+            inst(op::MEMBER_DECORATE, &[40, 0, decoration::MATRIX_STRIDE, 16]),
+            inst(op::MEMBER_DECORATE, &[40, 1, decoration::OFFSET, 64]),
+            inst(op::TYPE_FLOAT, &[30, 32]),
+            inst(op::TYPE_VECTOR, &[34, 30, 4]),
+            inst(op::TYPE_MATRIX, &[35, 34, 4]),
+            inst(op::TYPE_STRUCT, &[40, 35, 34]),
+            inst(op::TYPE_POINTER, &[50, storage::PUSH_CONSTANT, 40]),
+            inst(op::VARIABLE, &[50, 10, storage::PUSH_CONSTANT]),
+        ]);
+
+        let reflection = ShaderReflection::from_words(&words).unwrap();
+        assert_eq!(reflection.push_constants.len(), 1);
+        assert_eq!(reflection.push_constants[0].offset, 0);
+        assert_eq!(reflection.push_constants[0].size, 80);
+        assert_eq!(reflection.push_constants[0].stage_flags, vk::ShaderStageFlags::VERTEX);
+    }
+
+    #[test]
+    fn unsupported_vertex_input_is_reported() {
+
+        // a struct can not be the type of vertex input.
+        let words = module(0x0001_0000, vec![
+            entry_point(0, 1, "main", &[10]),
+            inst(op::DECORATE, &[10, decoration::LOCATION, 0]),
+            inst(op::TYPE_FLOAT, &[30, 32]),
+            inst(op::TYPE_STRUCT, &[40, 30]),
+            inst(op::TYPE_POINTER, &[50, storage::INPUT, 40]),
+            inst(op::VARIABLE, &[50, 10, storage::INPUT]),
+        ]);
+
+        assert!(ShaderReflection::from_words(&words).is_err());
+    }
+
+    /// A vertex entry point writing `gl_Position` by the `gl_PerVertex` block 10, whose member is decorated by `member_decorations`.
+    fn per_vertex_module(member_decorations: &[u32]) -> Vec<u32> {
+
+        let mut position_decorations = vec![
+            inst(op::MEMBER_DECORATE, &[40, 0, decoration::BUILT_IN, BUILT_IN_POSITION]),
+        ];
+        position_decorations.extend(member_decorations.iter().map(|&d| inst(op::MEMBER_DECORATE, &[40, 0, d])));
+
+        let mut instructions = vec![entry_point(0, 1, "main", &[10])];
+        instructions.extend(position_decorations);
+        instructions.extend(vec![
+            inst(op::TYPE_FLOAT, &[30, 32]),
+            inst(op::TYPE_VECTOR, &[34, 30, 4]),
+            inst(op::TYPE_STRUCT, &[40, 34]),
+            inst(op::TYPE_POINTER, &[50, storage::OUTPUT, 40]),
+            inst(op::VARIABLE, &[50, 10, storage::OUTPUT]),
+        ]);
+        module(0x0001_0000, instructions)
+    }
+
+    #[test]
+    fn invariant_position_member_is_detected() {
+
+        let reflection = ShaderReflection::from_words(&per_vertex_module(&[decoration::INVARIANT])).unwrap();
+        assert!(reflection.entry_points[0].is_position_invariant);
+
+        let reflection = ShaderReflection::from_words(&per_vertex_module(&[])).unwrap();
+        assert!(!reflection.entry_points[0].is_position_invariant);
+    }
+
+    #[test]
+    fn invariant_position_variable_is_detected() {
+
+        let words = module(0x0001_0000, vec![
+            entry_point(0, 1, "main", &[10, 11]),
+            inst(op::DECORATE, &[10, decoration::BUILT_IN, BUILT_IN_POSITION]),
+            inst(op::DECORATE, &[10, decoration::INVARIANT]),
+            // an invariant output which is not the position.
+            inst(op::DECORATE, &[11, decoration::LOCATION, 0]),
+            inst(op::DECORATE, &[11, decoration::INVARIANT]),
+            inst(op::TYPE_FLOAT, &[30, 32]),
+            inst(op::TYPE_VECTOR, &[34, 30, 4]),
+            inst(op::TYPE_POINTER, &[50, storage::OUTPUT, 34]),
+            inst(op::VARIABLE, &[50, 10, storage::OUTPUT]),
+            inst(op::VARIABLE, &[50, 11, storage::OUTPUT]),
+        ]);
+        let reflection = ShaderReflection::from_words(&words).unwrap();
+        assert!(reflection.entry_points[0].is_position_invariant);
+
+        // the entry point not writing the invariant position.
+        let words = module(0x0001_0000, vec![
+            entry_point(0, 1, "main", &[11]),
+            inst(op::DECORATE, &[10, decoration::BUILT_IN, BUILT_IN_POSITION]),
+            inst(op::DECORATE, &[10, decoration::INVARIANT]),
+            inst(op::DECORATE, &[11, decoration::INVARIANT]),
+            inst(op::TYPE_FLOAT, &[30, 32]),
+            inst(op::TYPE_VECTOR, &[34, 30, 4]),
+            inst(op::TYPE_POINTER, &[50, storage::OUTPUT, 34]),
+            inst(op::VARIABLE, &[50, 10, storage::OUTPUT]),
+            inst(op::VARIABLE, &[50, 11, storage::OUTPUT]),
+        ]);
+        let reflection = ShaderReflection::from_words(&words).unwrap();
+        assert!(!reflection.entry_points[0].is_position_invariant);
+    }
+}
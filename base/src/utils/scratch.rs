@@ -0,0 +1,169 @@
+//! A linear allocator of transient GPU data, which is reset every frame.
+
+use ash::vk;
+
+use crate::ci::buffer::BufferCI;
+use crate::ci::vma::{VmaBuffer, VmaAllocationCI};
+use crate::context::VkDevice;
+use crate::utils::frame::FrameResource;
+use crate::utils::memory::IntegerAlignable;
+
+use crate::{VkResult, VkError, VkErrorKind};
+use crate::{vkbytes, vkptr};
+
+/// Hand out sub-ranges of a persistently mapped buffer for the data used only in one frame(etc. immediate-mode geometry, culling lists).
+///
+/// Each frame in flight owns its buffer, so the data written in current frame never overwrites the data still read by device.
+/// The sub-ranges of a frame are reclaimed all at once in `FrameScratch::begin_frame`.
+///
+/// The buffer is never grown, because the buffer handles and offsets handed out may have been recorded to command buffers.
+/// Instead, `FrameScratch::alloc` returns an error when the remaining space of current frame is not enough,
+/// so choose a capacity large enough for the busiest frame.
+pub struct FrameScratch {
+
+    buffers: FrameResource<VmaBuffer>,
+    /// the allocated space in the buffer of current frame.
+    cursor: LinearCursor,
+    /// the index of frame in flight using now.
+    current: usize,
+}
+
+/// The bump allocation of sub-ranges in a buffer of `capacity` bytes.
+#[derive(Debug, Clone, Copy)]
+struct LinearCursor {
+    /// the size of buffer in bytes.
+    capacity: vkbytes,
+    /// the size of allocated space in the buffer.
+    cursor: vkbytes,
+}
+
+impl LinearCursor {
+
+    /// Allocate `size` bytes aligned to `alignment`, and return the offset of allocation.
+    fn alloc(&mut self, size: vkbytes, alignment: vkbytes) -> VkResult<vkbytes> {
+
+        // the alignments of buffer offsets required by Vulkan(etc. minUniformBufferOffsetAlignment) are always power of two.
+        debug_assert!(alignment.is_power_of_two(), "The alignment of scratch allocation must be a power of two.");
+        let offset = self.cursor.align_to(alignment);
+
+        if offset + size > self.capacity {
+            return Err(VkError::custom(format!("Frame scratch overflow: {} bytes requested, but only {} bytes remain in current frame.", size, self.capacity.saturating_sub(offset))))
+        }
+
+        self.cursor = offset + size;
+        Ok(offset)
+    }
+
+    fn reset(&mut self) {
+        self.cursor = 0;
+    }
+}
+
+impl FrameScratch {
+
+    /// Create `frame_count` buffers of `capacity` bytes, which can be used as `usage`(etc. `vk::BufferUsageFlags::VERTEX_BUFFER`).
+    pub fn new(device: &mut VkDevice, frame_count: usize, capacity: vkbytes, usage: vk::BufferUsageFlags) -> VkResult<FrameScratch> {
+
+        let buffers = FrameResource::new(frame_count, |_| {
+
+            let buffer_ci = BufferCI::new(capacity)
+                .usage(usage);
+            let allocation_ci = VmaAllocationCI::new(vma::MemoryUsage::CpuToGpu, vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT)
+                .flags(vma::AllocationCreateFlags::MAPPED);
+            let buffer_allocation = device.vma.create_buffer(buffer_ci.as_ref(), allocation_ci.as_ref())
+                .map_err(VkErrorKind::Vma)?;
+
+            Ok(VmaBuffer::from(buffer_allocation))
+        })?;
+
+        let result = FrameScratch { buffers, cursor: LinearCursor { capacity, cursor: 0 }, current: 0 };
+        Ok(result)
+    }
+
+    /// Start allocating from the buffer of `frame_index`, and reclaim all the space allocated in its last use.
+    ///
+    /// The caller must make sure that the device has finished the last frame using `frame_index`(usually by waiting its fence).
+    pub fn begin_frame(&mut self, frame_index: usize) {
+        self.current = frame_index;
+        self.cursor.reset();
+    }
+
+    /// Allocate `size` bytes whose offset is a multiple of `alignment` from the buffer of current frame.
+    ///
+    /// `alignment` must be a power of two(etc. `minUniformBufferOffsetAlignment`).
+    /// Return the buffer handle, the offset of allocation in this buffer and the mapped pointer to the allocation.
+    pub fn alloc(&mut self, size: vkbytes, alignment: vkbytes) -> VkResult<(vk::Buffer, vkbytes, vkptr)> {
+
+        let offset = self.cursor.alloc(size, alignment)?;
+
+        let buffer = self.buffers.get(self.current);
+        let data_ptr = unsafe {
+            (buffer.info.get_mapped_data() as vkptr<u8>).offset(offset as isize) as vkptr
+        };
+
+        Ok((buffer.handle, offset, data_ptr))
+    }
+
+    /// Get the size of each buffer.
+    #[inline]
+    pub fn capacity(&self) -> vkbytes {
+        self.cursor.capacity
+    }
+
+    /// Get the size of space allocated in current frame, including the padding for alignment.
+    #[inline]
+    pub fn used_size(&self) -> vkbytes {
+        self.cursor.cursor
+    }
+
+    pub fn discard_by(self, device: &mut VkDevice) -> VkResult<()> {
+
+        for buffer in self.buffers.into_inner() {
+            device.vma_discard(buffer)?;
+        }
+
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn allocations_are_aligned_and_not_overlapped() {
+
+        let mut cursor = LinearCursor { capacity: 1024, cursor: 0 };
+        let requests = [(10, 4), (3, 1), (64, 256), (20, 16)];
+
+        let mut ranges: Vec<(vkbytes, vkbytes)> = Vec::new();
+        for &(size, alignment) in requests.iter() {
+
+            let offset = cursor.alloc(size, alignment).unwrap();
+            assert_eq!(offset % alignment, 0);
+            assert!(ranges.iter().all(|&(start, end)| offset >= end || offset + size <= start));
+            ranges.push((offset, offset + size));
+        }
+
+        assert_eq!(ranges, [(0, 10), (10, 13), (256, 320), (320, 340)]);
+        assert_eq!(cursor.cursor, 340);
+    }
+
+    #[test]
+    fn overflow_is_reported_and_reset_reclaims_space() {
+
+        let mut cursor = LinearCursor { capacity: 256, cursor: 0 };
+
+        assert_eq!(cursor.alloc(200, 4).unwrap(), 0);
+        // the padding for alignment also counts into the capacity.
+        assert!(cursor.alloc(56, 64).is_err());
+        // a failed allocation does not consume space.
+        assert_eq!(cursor.alloc(56, 4).unwrap(), 200);
+        assert!(cursor.alloc(1, 1).is_err());
+
+        cursor.reset();
+        assert_eq!(cursor.alloc(256, 256).unwrap(), 0);
+    }
+}
@@ -1,9 +1,7 @@
 
 use crate::error::{VkResult, VkError};
 
-use std::path::{PathBuf, Path};
-use std::fs::File;
-use std::io::Read;
+use std::path::Path;
 
 pub struct ShadercOptions {
 
@@ -11,6 +9,8 @@ pub struct ShadercOptions {
     pub debug_info      : bool,
     pub suppress_warning: bool,
     pub error_warning   : bool,
+    /// the language of shader source, which is GLSL by default.
+    pub source_language : shaderc::SourceLanguage,
 }
 
 impl Default for ShadercOptions {
@@ -22,18 +22,20 @@ impl Default for ShadercOptions {
             debug_info       : true,
             suppress_warning : false,
             error_warning    : true,
+            source_language  : shaderc::SourceLanguage::GLSL,
         }
     }
 }
 
 impl ShadercOptions {
 
-    fn to_shaderc_options(&self) -> VkResult<shaderc::CompileOptions> {
+    fn to_shaderc_options(&self, language: shaderc::SourceLanguage) -> VkResult<shaderc::CompileOptions> {
 
-        // Default to compile target is vulkan and GLSL.
+        // Default to compile target is vulkan.
         let mut shaderc_options = shaderc::CompileOptions::new()
             .ok_or(VkError::shaderc("There are conflict in Shader Compile Options."))?;
         shaderc_options.set_optimization_level(self.optimal_level);
+        shaderc_options.set_source_language(language);
 
         if self.debug_info {
             shaderc_options.set_generate_debug_info();
@@ -73,12 +75,56 @@ impl VkShaderCompiler {
         self.options = options;
     }
 
+    /// Set the optimization level of the following compilations(`OptimizationLevel::Performance` by default).
+    ///
+    /// Use `OptimizationLevel::Zero` together with `with_debug_info(true)` to keep the SPIR-V close to source for debugging in captures.
+    #[inline(always)]
+    pub fn optimization(mut self, level: shaderc::OptimizationLevel) -> VkShaderCompiler {
+        self.options.optimal_level = level; self
+    }
+
+    /// Set whether to generate debug information(etc. source text and variable names) in the SPIR-V output(true by default).
+    #[inline(always)]
+    pub fn with_debug_info(mut self, is_enable: bool) -> VkShaderCompiler {
+        self.options.debug_info = is_enable; self
+    }
+
+    /// Set the language of shader source for the following compilations(`SourceLanguage::GLSL` by default).
+    #[inline(always)]
+    pub fn source_language(mut self, language: shaderc::SourceLanguage) -> VkShaderCompiler {
+        self.options.source_language = language; self
+    }
+
+    /// Compile `source_text` in the language set by `source_language`.
+    ///
+    /// `entry_name` is the entry point function of shader, which also becomes the entry point name of the SPIR-V output,
+    /// so pass the same name to `ShaderStageCI::main` when it's not `main`(etc. HLSL shaders).
     pub fn compile_from_str(&mut self, source_text: &str, stage: shaderc::ShaderKind, input_name: &str, entry_name: &str) -> VkResult<Vec<u8>> {
+        let language = self.options.source_language;
+        self.compile(source_text, language, stage, input_name, entry_name)
+    }
+
+    pub fn compile_from_path(&mut self, path: impl AsRef<Path>, stage: shaderc::ShaderKind, input_name: &str, entry_name: &str) -> VkResult<Vec<u8>> {
+
+        let source_text = load_to_string(path.as_ref())?;
+        self.compile_from_str(&source_text, stage, input_name, entry_name)
+    }
+
+    /// Compile the HLSL shader at `path`, regardless of the language set by `source_language`.
+    ///
+    /// HLSL shaders usually name the entry point other than `main`(etc. `VSMain`), so remember to set it by `ShaderStageCI::main`.
+    pub fn compile_hlsl_from_path(&mut self, path: impl AsRef<Path>, stage: shaderc::ShaderKind, input_name: &str, entry_name: &str) -> VkResult<Vec<u8>> {
+
+        let source_text = load_to_string(path.as_ref())?;
+        self.compile(&source_text, shaderc::SourceLanguage::HLSL, stage, input_name, entry_name)
+    }
 
-        let compile_options = self.options.to_shaderc_options()?;
+    fn compile(&mut self, source_text: &str, language: shaderc::SourceLanguage, stage: shaderc::ShaderKind, input_name: &str, entry_name: &str) -> VkResult<Vec<u8>> {
+
+        let compile_options = self.options.to_shaderc_options(language)?;
 
         let result = self.compiler.compile_into_spirv(source_text, stage, input_name, entry_name, Some(&compile_options))
-            .map_err(|e| VkError::shaderc(format!("Failed to compile {}({})", input_name, e)))?;
+            .map_err(|e| VkError::shaderc(format!("Failed to compile {} {} with entry point `{}`({})", language_name(language), input_name, entry_name, e)))?;
 
         if result.get_num_warnings() > 0 {
             println!("{}: {}", input_name, result.get_warning_messages());
@@ -87,20 +133,20 @@ impl VkShaderCompiler {
         let spirv = result.as_binary_u8().to_owned();
         Ok(spirv)
     }
+}
 
-    pub fn compile_from_path(&mut self, path: impl AsRef<Path>, stage: shaderc::ShaderKind, input_name: &str, entry_name: &str) -> VkResult<Vec<u8>> {
-
-        let source_text = load_to_string(PathBuf::from(path.as_ref()))?;
-        self.compile_from_str(&source_text, stage, input_name, entry_name)
+fn language_name(language: shaderc::SourceLanguage) -> &'static str {
+    match language {
+        | shaderc::SourceLanguage::GLSL => "GLSL",
+        | shaderc::SourceLanguage::HLSL => "HLSL",
     }
 }
 
-fn load_to_string(path: PathBuf) -> VkResult<String> {
+fn load_to_string(path: &Path) -> VkResult<String> {
 
-    let mut file = File::open(path.clone())
-        .map_err(|_| VkError::path(path))?;
-    let mut contents = String::new();
-    let _size = file.read_to_string(&mut contents)
+    // read shader source from current asset source.
+    let bytes = crate::utils::asset::read_asset(path)?;
+    let contents = String::from_utf8(bytes)
         .or(Err(VkError::custom("Unable to shader code.")))?;
 
     Ok(contents)
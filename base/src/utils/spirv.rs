@@ -0,0 +1,129 @@
+//! Load SPIR-V codes as 32-bit words, which is the unit Vulkan requires for `vk::ShaderModuleCreateInfo::p_code`.
+
+use crate::error::{VkResult, VkError};
+
+use std::path::Path;
+
+/// The first word of every SPIR-V module.
+pub const SPIRV_MAGIC: u32 = 0x0723_0203;
+
+/// Read the SPIR-V file at `path` from current asset source(see `utils::asset`).
+///
+/// Return error naming the file if it's not a SPIR-V module(etc. a shader source or the output of a wrong compilation).
+pub fn load_spirv(path: impl AsRef<Path>) -> VkResult<Vec<u32>> {
+
+    let path = path.as_ref();
+    let bytes = crate::utils::asset::read_asset(path)?;
+
+    parse_words(&bytes)
+        .map_err(|reason| VkError::custom(format!("{} is not a valid SPIR-V file: {}", path.display(), reason)))
+}
+
+/// Convert SPIR-V `bytes` into words, which are kept in a `Vec<u32>` to satisfy the 4-byte alignment of `p_code`.
+///
+/// The codes written in the opposite endianness are swapped to native endianness.
+/// Return error if the size of `bytes` is not a multiple of 4, or it does not start with the SPIR-V magic number.
+pub fn spirv_words(bytes: &[u8]) -> VkResult<Vec<u32>> {
+
+    parse_words(bytes)
+        .map_err(|reason| VkError::custom(format!("Invalid SPIR-V codes: {}", reason)))
+}
+
+fn parse_words(bytes: &[u8]) -> Result<Vec<u32>, String> {
+
+    if bytes.len() % 4 != 0 {
+        return Err(format!("the size({} bytes) is not a multiple of 4.", bytes.len()))
+    }
+
+    let mut words: Vec<u32> = bytes.chunks_exact(4)
+        .map(|word| u32::from_ne_bytes([word[0], word[1], word[2], word[3]]))
+        .collect();
+
+    match words.first().cloned() {
+        | Some(SPIRV_MAGIC) => {},
+        | Some(magic) if magic == SPIRV_MAGIC.swap_bytes() => {
+            // the module is written in the opposite endianness of this machine.
+            for word in words.iter_mut() {
+                *word = word.swap_bytes();
+            }
+        },
+        | Some(magic) => {
+            return Err(format!("the magic number is {:#010x}, but {:#010x} is expected.", magic, SPIRV_MAGIC))
+        },
+        | None => {
+            return Err(String::from("the codes are empty."))
+        },
+    }
+
+    Ok(words)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// A minimal SPIR-V 1.0 module: `OpCapability Shader` and `OpMemoryModel Logical GLSL450`.
+    const MODULE_WORDS: [u32; 10] = [
+        SPIRV_MAGIC, 0x0001_0000, 0, 1, 0,
+        0x0002_0011, 1,
+        0x0003_000E, 0, 1,
+    ];
+
+    /// `MODULE_WORDS` in little-endian bytes, as written by glslang or shaderc.
+    const MODULE_BYTES: [u8; 40] = [
+        0x03, 0x02, 0x23, 0x07, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x11, 0x00, 0x02, 0x00, 0x01, 0x00, 0x00, 0x00,
+        0x0E, 0x00, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00,
+    ];
+
+    fn swap_endianness(bytes: &[u8]) -> Vec<u8> {
+        bytes.chunks(4).flat_map(|word| word.iter().rev().cloned()).collect()
+    }
+
+    #[test]
+    fn module_bytes_are_read_as_words() {
+
+        let little_endian = spirv_words(&MODULE_BYTES).unwrap();
+        let big_endian = spirv_words(&swap_endianness(&MODULE_BYTES)).unwrap();
+
+        if cfg!(target_endian = "little") {
+            assert_eq!(little_endian, MODULE_WORDS);
+        } else {
+            assert_eq!(big_endian, MODULE_WORDS);
+        }
+        // the codes in either endianness are converted to the same words.
+        assert_eq!(little_endian, big_endian);
+    }
+
+    #[test]
+    fn invalid_codes_are_rejected() {
+
+        // the size is not a multiple of 4.
+        assert!(spirv_words(&MODULE_BYTES[..38]).is_err());
+        // the magic number is missing.
+        assert!(spirv_words(&MODULE_BYTES[4..]).is_err());
+        // a shader source instead of its SPIR-V output.
+        assert!(spirv_words(b"#version 450\n\nvoid main() {}\n\n\n\n").is_err());
+        assert!(spirv_words(&[]).is_err());
+    }
+
+    #[test]
+    fn spirv_file_is_loaded() {
+
+        let dir = std::env::temp_dir().join(format!("vkbase-spirv-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let module_path = dir.join("minimal.spv");
+        let source_path = dir.join("minimal.vert.glsl");
+        std::fs::write(&module_path, &MODULE_BYTES[..]).unwrap();
+        std::fs::write(&source_path, b"#version 450\nvoid main() {}\n\0\0\0\0").unwrap();
+
+        let words = load_spirv(&module_path);
+        let source_error = load_spirv(&source_path);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(words.unwrap(), spirv_words(&MODULE_BYTES).unwrap());
+        assert!(source_error.is_err());
+    }
+}
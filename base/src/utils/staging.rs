@@ -0,0 +1,442 @@
+//! Staging buffers to upload data from host to device-local resources.
+
+use ash::vk;
+use ash::version::DeviceV1_0;
+
+use crate::ci::buffer::BufferCI;
+use crate::ci::vma::{VmaBuffer, VmaAllocationCI};
+use crate::ci::sync::FenceCI;
+use crate::context::VkDevice;
+use crate::command::{VkCmdRecorder, ITransfer, CmdTransferApi};
+use crate::utils::memory::IntegerAlignable;
+use crate::utils::time::VkTimeDuration;
+
+use crate::{VkResult, VkError, VkErrorKind};
+use crate::{vkbytes, vkptr};
+
+/// The alignment of each region in staging buffer, which is enough for any `f32` or `u32` data.
+const STAGING_REGION_ALIGNMENT: vkbytes = 16;
+
+/// Collect the regions to upload, before the staging buffer is allocated.
+///
+/// # Examples
+///
+/// ```ignore
+/// let mut batch = StagingBatch::new();
+/// let vertex_offset = batch.reserve(vertex_buffer, 0, vertex_size);
+/// let index_offset  = batch.reserve(index_buffer, 0, index_size);
+///
+/// let staging = batch.allocate(&mut device.vma)?;
+/// // write data to `staging.data_ptr(vertex_offset)` and `staging.data_ptr(index_offset)`...
+/// staging.record_copies(&recorder);
+/// ```
+#[derive(Debug, Default)]
+pub struct StagingBatch {
+
+    /// the total size of the staging buffer.
+    size: vkbytes,
+    /// the destination buffer and the copy region of each upload.
+    regions: Vec<(vk::Buffer, vk::BufferCopy)>,
+}
+
+impl StagingBatch {
+
+    pub fn new() -> StagingBatch {
+        Default::default()
+    }
+
+    /// Reserve `size` bytes in staging buffer, which will be copied to `dst_offset` of `dst_buffer`.
+    ///
+    /// Return the offset of this region in staging buffer.
+    pub fn reserve(&mut self, dst_buffer: vk::Buffer, dst_offset: vkbytes, size: vkbytes) -> vkbytes {
+
+        let src_offset = self.size.align_to(STAGING_REGION_ALIGNMENT);
+        self.size = src_offset + size;
+
+        self.regions.push((dst_buffer, vk::BufferCopy { src_offset, dst_offset, size }));
+
+        src_offset
+    }
+
+    /// Allocate one host-visible buffer containing all the reserved regions.
+    pub fn allocate(self, vma: &mut vma::Allocator) -> VkResult<StagingBuffer> {
+
+        // zero-sized buffer is not allowed in Vulkan.
+        let staging_ci = BufferCI::new(self.size.max(STAGING_REGION_ALIGNMENT))
+            .usage(vk::BufferUsageFlags::TRANSFER_SRC);
+        let allocate_ci = VmaAllocationCI::new(vma::MemoryUsage::CpuToGpu, vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT)
+            .flags(vma::AllocationCreateFlags::MAPPED);
+        let staging_allocation = vma.create_buffer(staging_ci.as_ref(), allocate_ci.as_ref())
+            .map_err(VkErrorKind::Vma)?;
+
+        let result = StagingBuffer {
+            buffer: VmaBuffer::from(staging_allocation),
+            regions: self.regions,
+        };
+        Ok(result)
+    }
+
+    /// Get the count of regions reserved in this batch.
+    #[inline]
+    pub fn region_count(&self) -> usize {
+        self.regions.len()
+    }
+}
+
+/// A persistently mapped staging buffer allocated by `StagingBatch`.
+pub struct StagingBuffer {
+
+    pub buffer: VmaBuffer,
+    regions: Vec<(vk::Buffer, vk::BufferCopy)>,
+}
+
+impl StagingBuffer {
+
+    /// Get the mapped pointer at `offset`(the value returned by `StagingBatch::reserve`).
+    #[inline]
+    pub fn data_ptr(&self, offset: vkbytes) -> vkptr {
+
+        unsafe {
+            (self.buffer.info.get_mapped_data() as vkptr<u8>).offset(offset as isize) as vkptr
+        }
+    }
+
+    /// Record the copy commands of all regions.
+    ///
+    /// The regions with the same destination are copied by a single `vkCmdCopyBuffer` call.
+    pub fn record_copies(&self, recorder: &VkCmdRecorder<ITransfer>) {
+
+        for (dst_buffer, regions) in group_by_destination(&self.regions).iter() {
+            recorder.copy_buf2buf(self.buffer.handle, *dst_buffer, regions);
+        }
+    }
+
+    pub fn discard_by(self, vma: &mut vma::Allocator) -> VkResult<()> {
+
+        vma.destroy_buffer(self.buffer.handle, &self.buffer.allocation)
+            .map_err(VkErrorKind::Vma)
+    }
+}
+
+/// Group the copy regions by their destination buffers, keeping the order of first appearance.
+fn group_by_destination<B: Copy + PartialEq>(regions: &[(B, vk::BufferCopy)]) -> Vec<(B, Vec<vk::BufferCopy>)> {
+
+    let mut destinations: Vec<(B, Vec<vk::BufferCopy>)> = Vec::new();
+
+    for (dst_buffer, region) in regions.iter() {
+
+        match destinations.iter_mut().find(|(buffer, _)| buffer == dst_buffer) {
+            | Some((_, regions)) => regions.push(region.clone()),
+            | None => destinations.push((*dst_buffer, vec![region.clone()])),
+        }
+    }
+    destinations
+}
+
+// --------------------------------------------------------------------------------------
+/// A persistently mapped staging buffer split into one region for each frame in flight, used to stream data(etc. mip levels) every frame.
+///
+/// Each frame writes its uploads into its own region, and the region is reused only after the fence of its last upload signaled,
+/// so the host never overwrites the data still being read by device.
+///
+/// The workflow of each frame is: `begin_frame` -> `write`(several times) -> record copy commands from `buffer` -> `submit_fence`.
+pub struct StreamingStagingRing {
+
+    buffer: VmaBuffer,
+    /// the fence of each region, which is signaled when the uploads of this region finished.
+    fences: Vec<vk::Fence>,
+    regions: RegionRing,
+}
+
+/// The bookkeeping of regions in `StreamingStagingRing`, which decides when a region can be reused.
+struct RegionRing {
+    /// the size of each region in bytes.
+    region_size: vkbytes,
+    regions: Vec<StreamingRegion>,
+    /// the index of region used by current frame.
+    current: usize,
+}
+
+struct StreamingRegion {
+    /// true if the fence has been submitted but not yet waited.
+    is_pending: bool,
+    /// the size of data written in this region.
+    cursor: vkbytes,
+}
+
+impl RegionRing {
+
+    fn new(region_size: vkbytes, region_count: usize) -> RegionRing {
+
+        let regions = (0..region_count)
+            .map(|_| StreamingRegion { is_pending: false, cursor: 0 })
+            .collect();
+
+        // start from the last region, so the first `begin_frame` call moves to region 0.
+        RegionRing { region_size, regions, current: region_count - 1 }
+    }
+
+    /// Move to the next region, calling `wait_fence` with its index before reusing it if its uploads may not have finished.
+    fn begin_frame(&mut self, wait_fence: impl FnOnce(usize) -> VkResult<()>) -> VkResult<usize> {
+
+        self.current = (self.current + 1) % self.regions.len();
+
+        let region = &mut self.regions[self.current];
+        if region.is_pending {
+            wait_fence(self.current)?;
+            region.is_pending = false;
+        }
+        region.cursor = 0;
+
+        Ok(self.current)
+    }
+
+    /// Allocate `data_size` bytes in current region, and return its offset in the whole buffer.
+    fn allocate(&mut self, data_size: vkbytes) -> VkResult<vkbytes> {
+
+        let region = &mut self.regions[self.current];
+        debug_assert!(!region.is_pending, "Staging region {} has been submitted. Call begin_frame before writing new data.", self.current);
+
+        let local_offset = region.cursor.align_to(STAGING_REGION_ALIGNMENT);
+
+        if local_offset + data_size > self.region_size {
+            return Err(VkError::custom(format!("Streaming staging region overflow: {} bytes requested, but only {} bytes remain.", data_size, self.region_size.saturating_sub(local_offset))))
+        }
+
+        region.cursor = local_offset + data_size;
+
+        Ok(self.region_size * self.current as vkbytes + local_offset)
+    }
+
+    fn submit(&mut self) {
+        self.regions[self.current].is_pending = true;
+    }
+
+    fn remain_size(&self) -> vkbytes {
+        self.region_size.saturating_sub(self.regions[self.current].cursor)
+    }
+}
+
+impl StreamingStagingRing {
+
+    /// Create a ring with `region_count` regions(usually the count of frames in flight), each of which is `region_size` bytes.
+    pub fn new(device: &mut VkDevice, region_size: vkbytes, region_count: usize) -> VkResult<StreamingStagingRing> {
+
+        debug_assert!(region_count > 0, "Streaming staging ring requires at least one region.");
+        let region_size = region_size.align_to(STAGING_REGION_ALIGNMENT);
+
+        let buffer = {
+            let staging_ci = BufferCI::new(region_size * region_count as vkbytes)
+                .usage(vk::BufferUsageFlags::TRANSFER_SRC);
+            let allocate_ci = VmaAllocationCI::new(vma::MemoryUsage::CpuToGpu, vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT)
+                .flags(vma::AllocationCreateFlags::MAPPED);
+            let staging_allocation = device.vma.create_buffer(staging_ci.as_ref(), allocate_ci.as_ref())
+                .map_err(VkErrorKind::Vma)?;
+            VmaBuffer::from(staging_allocation)
+        };
+
+        let mut fences = Vec::with_capacity(region_count);
+        for _ in 0..region_count {
+            fences.push(device.build(&FenceCI::new(false))?);
+        }
+
+        let result = StreamingStagingRing { buffer, fences, regions: RegionRing::new(region_size, region_count) };
+        Ok(result)
+    }
+
+    /// Move to the next region for a new frame, and return its index.
+    ///
+    /// This blocks until the previous uploads from that region have finished.
+    pub fn begin_frame(&mut self, device: &VkDevice) -> VkResult<usize> {
+
+        let fences = &self.fences;
+        self.regions.begin_frame(|region_index| device.wait(fences[region_index], VkTimeDuration::Infinite))
+    }
+
+    /// Copy `data` into the region of current frame.
+    ///
+    /// Return the offset of data in `StreamingStagingRing::buffer`, which is used as the `src_offset` of copy commands.
+    pub fn write(&mut self, data: &[u8]) -> VkResult<vkbytes> {
+
+        let buffer_offset = self.regions.allocate(data.len() as vkbytes)?;
+        unsafe {
+            let data_ptr = (self.buffer.info.get_mapped_data() as vkptr<u8>).offset(buffer_offset as isize);
+            data_ptr.copy_from_nonoverlapping(data.as_ptr(), data.len());
+        }
+
+        Ok(buffer_offset)
+    }
+
+    /// Get the fence that must be signaled by the submission reading the region of current frame.
+    ///
+    /// The fence is reset here, and it will be waited when this region is used again.
+    pub fn submit_fence(&mut self, device: &VkDevice) -> VkResult<vk::Fence> {
+
+        let fence = self.fences[self.regions.current];
+
+        unsafe {
+            device.logic.handle.reset_fences(&[fence])
+                .map_err(|_| VkError::device("Reset fences"))?;
+        }
+        self.regions.submit();
+
+        Ok(fence)
+    }
+
+    /// The staging buffer containing all regions.
+    #[inline]
+    pub fn buffer(&self) -> vk::Buffer {
+        self.buffer.handle
+    }
+
+    /// Get the index of region used by current frame.
+    #[inline]
+    pub fn current_region(&self) -> usize {
+        self.regions.current
+    }
+
+    /// Get the remaining bytes in the region of current frame.
+    #[inline]
+    pub fn remain_size(&self) -> vkbytes {
+        self.regions.remain_size()
+    }
+
+    /// Wait for all pending uploads and destroy the ring.
+    pub fn discard_by(self, device: &mut VkDevice) -> VkResult<()> {
+
+        for (region, &fence) in self.regions.regions.iter().zip(self.fences.iter()) {
+            if region.is_pending {
+                device.wait(fence, VkTimeDuration::Infinite)?;
+            }
+            device.discard(fence);
+        }
+
+        device.vma_discard(self.buffer)
+    }
+}
+// --------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn batch_reserves_aligned_regions_in_one_buffer() {
+
+        let mut batch = StagingBatch::new();
+        // the attributes and indices of two primitives, which used to allocate one staging buffer each.
+        let offsets: Vec<vkbytes> = [100, 36, 64, 6].iter()
+            .map(|&size| batch.reserve(vk::Buffer::null(), 0, size))
+            .collect();
+
+        assert_eq!(batch.region_count(), 4);
+        assert_eq!(offsets, vec![0, 112, 160, 224]);
+        // all the regions share a single allocation of this size.
+        assert_eq!(batch.size, 230);
+    }
+
+    /// Get the size of the attribute data and index data of each primitive in glTF `doc`.
+    fn primitive_upload_sizes(doc: &gltf::Document) -> Vec<vkbytes> {
+
+        let mut sizes = Vec::new();
+        for primitive in doc.meshes().flat_map(|mesh| mesh.primitives()) {
+
+            let attributes_size: usize = primitive.attributes()
+                .map(|(_, accessor)| accessor.count() * accessor.size())
+                .sum();
+            sizes.push(attributes_size as vkbytes);
+
+            if let Some(indices) = primitive.indices() {
+                sizes.push((indices.count() * indices.size()) as vkbytes);
+            }
+        }
+        sizes
+    }
+
+    #[test]
+    fn treasure_model_is_uploaded_by_one_staging_buffer() {
+
+        let model_path = concat!(env!("CARGO_MANIFEST_DIR"), "/../assets/models/treasure_smooth.gltf");
+        let bytes = std::fs::read(model_path).unwrap();
+
+        // the models are stored by Git LFS, which may not be fetched in this checkout.
+        if bytes.starts_with(b"version https://git-lfs") {
+            println!("[Warning] Skip the staging test, since the treasure model is a Git LFS pointer.");
+            return
+        }
+
+        let gltf = gltf::Gltf::from_slice(&bytes).unwrap();
+        let sizes = primitive_upload_sizes(&gltf.document);
+
+        // without batching, each attribute and index block of primitives allocates a staging buffer.
+        let unbatched_allocations = sizes.len();
+        assert!(unbatched_allocations > 1);
+
+        let mut batch = StagingBatch::new();
+        for &size in sizes.iter() {
+            batch.reserve(vk::Buffer::null(), 0, size);
+        }
+        let batched_allocations = 1;
+
+        assert_eq!(batch.region_count(), unbatched_allocations);
+        assert!(batch.size >= sizes.iter().sum::<vkbytes>());
+        assert!(batched_allocations < unbatched_allocations);
+        println!("staging allocations of treasure model: {} -> {}", unbatched_allocations, batched_allocations);
+    }
+
+    #[test]
+    fn copies_are_grouped_by_destination() {
+
+        let copy = |src_offset: vkbytes, size: vkbytes| vk::BufferCopy { src_offset, dst_offset: src_offset, size };
+        let (vertex_buffer, index_buffer) = (1_u32, 2_u32);
+
+        let regions = [(vertex_buffer, copy(0, 16)), (index_buffer, copy(16, 16)), (vertex_buffer, copy(32, 16))];
+        let destinations = group_by_destination(&regions);
+
+        // one copy command for each destination buffer.
+        assert_eq!(destinations.len(), 2);
+        assert_eq!(destinations[0].0, vertex_buffer);
+        assert_eq!(destinations[0].1.iter().map(|region| region.src_offset).collect::<Vec<_>>(), vec![0, 32]);
+        assert_eq!(destinations[1].0, index_buffer);
+        assert_eq!(destinations[1].1.len(), 1);
+    }
+
+    #[test]
+    fn ring_cycles_regions_and_waits_before_reuse() {
+
+        let mut ring = RegionRing::new(256, 3);
+        let mut waited = Vec::new();
+
+        let mut frames = Vec::new();
+        for _ in 0..5 {
+            let region = ring.begin_frame(|region| { waited.push(region); Ok(()) }).unwrap();
+            frames.push(region);
+
+            // each frame writes into its own region.
+            let offset = ring.allocate(100).unwrap();
+            assert_eq!(offset, 256 * region as vkbytes);
+            ring.submit();
+        }
+
+        assert_eq!(frames, vec![0, 1, 2, 0, 1]);
+        // the regions are only reused after waiting the fence of their last uploads.
+        assert_eq!(waited, vec![0, 1]);
+    }
+
+    #[test]
+    fn ring_region_overflow_is_reported() {
+
+        let mut ring = RegionRing::new(256, 2);
+        ring.begin_frame(|_| Ok(())).unwrap();
+
+        assert_eq!(ring.allocate(200).unwrap(), 0);
+        assert!(ring.allocate(100).is_err());
+        assert_eq!(ring.remain_size(), 56);
+
+        // the cursor is reset when the region is used again.
+        ring.begin_frame(|_| Ok(())).unwrap();
+        ring.begin_frame(|_| Ok(())).unwrap();
+        assert_eq!(ring.remain_size(), 256);
+    }
+}
@@ -0,0 +1,254 @@
+//! Persistently mapped uniform buffers holding a `#[repr(C)]` value of `T`.
+
+use ash::vk;
+
+use crate::ci::buffer::BufferCI;
+use crate::ci::vma::{VmaBuffer, VmaAllocationCI};
+use crate::context::VkDevice;
+use crate::utils::memory::IntegerAlignable;
+
+use crate::{VkResult, VkError, VkErrorKind};
+use crate::{vkbytes, vkptr};
+
+use std::marker::PhantomData;
+use std::mem;
+
+/// A uniform buffer of the size of `T`, which stays mapped until it's discarded.
+///
+/// The memory is host coherent, so the writes in `UniformBuffer::update` are visible to device without flushing.
+pub struct UniformBuffer<T: Copy> {
+
+    buffer: VmaBuffer,
+    phantom_type: PhantomData<T>,
+}
+
+impl<T: Copy> UniformBuffer<T> {
+
+    /// Create the uniform buffer and write `data` to it.
+    pub fn new(device: &mut VkDevice, data: &T) -> VkResult<UniformBuffer<T>> {
+
+        let uniform_ci = BufferCI::new(mem::size_of::<T>() as vkbytes)
+            .usage(vk::BufferUsageFlags::UNIFORM_BUFFER);
+        let allocation_ci = VmaAllocationCI::new(vma::MemoryUsage::CpuOnly, vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT)
+            .flags(vma::AllocationCreateFlags::MAPPED);
+        let uniform_allocation = device.vma.create_buffer(uniform_ci.as_ref(), allocation_ci.as_ref())
+            .map_err(VkErrorKind::Vma)?;
+
+        let mut uniform = UniformBuffer {
+            buffer: VmaBuffer::from(uniform_allocation),
+            phantom_type: PhantomData,
+        };
+        debug_assert!(!uniform.buffer.info.get_mapped_data().is_null());
+
+        uniform.update(data);
+        Ok(uniform)
+    }
+
+    /// Overwrite the content of buffer with `data`.
+    ///
+    /// The caller must make sure that the device is not reading this buffer, see `FrameUniforms` to update uniforms while frames are in flight.
+    pub fn update(&mut self, data: &T) {
+
+        unsafe {
+            let data_ptr = self.buffer.info.get_mapped_data() as vkptr<T>;
+            data_ptr.copy_from_nonoverlapping(data, 1);
+        }
+    }
+
+    /// Get the handle of `vk::Buffer`.
+    #[inline]
+    pub fn handle(&self) -> vk::Buffer {
+        self.buffer.handle
+    }
+
+    /// Get the descriptor info covering the whole value of `T`, which is used to write `vk::DescriptorType::UNIFORM_BUFFER` descriptor.
+    pub fn descriptor_info(&self) -> vk::DescriptorBufferInfo {
+        vk::DescriptorBufferInfo {
+            buffer: self.buffer.handle,
+            offset: 0,
+            range : mem::size_of::<T>() as vkbytes,
+        }
+    }
+
+    pub fn discard_by(self, device: &mut VkDevice) -> VkResult<()> {
+        device.vma_discard(self.buffer)
+    }
+}
+
+/// One slot of uniform data for each frame in flight, placed in a single mapped buffer.
+///
+/// Each slot starts at `frame_index * aligned_size`, where `aligned_size` is the size of `T` rounded up to `min_uniform_buffer_offset_alignment`.
+/// Updating a slot marks it in use by device, and it can not be updated again until `FrameUniforms::mark_waited` is called after the fence of that frame has been waited,
+/// so that the data read by the frames still executing on device is never overwritten.
+pub struct FrameUniforms<T: Copy> {
+
+    buffer: VmaBuffer,
+    slots: FrameSlots,
+    phantom_type: PhantomData<T>,
+}
+
+impl<T: Copy> FrameUniforms<T> {
+
+    /// Create the uniform buffer with `frame_count` slots, each of them is initialized with `data`.
+    pub fn new(device: &mut VkDevice, frame_count: usize, data: &T) -> VkResult<FrameUniforms<T>> {
+
+        let aligned_size = (mem::size_of::<T>() as vkbytes).align_to(device.phy.limits.min_uniform_buffer_offset_alignment);
+
+        let uniform_ci = BufferCI::new(aligned_size * frame_count as vkbytes)
+            .usage(vk::BufferUsageFlags::UNIFORM_BUFFER);
+        let allocation_ci = VmaAllocationCI::new(vma::MemoryUsage::CpuOnly, vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT)
+            .flags(vma::AllocationCreateFlags::MAPPED);
+        let uniform_allocation = device.vma.create_buffer(uniform_ci.as_ref(), allocation_ci.as_ref())
+            .map_err(VkErrorKind::Vma)?;
+
+        let buffer = VmaBuffer::from(uniform_allocation);
+        debug_assert!(!buffer.info.get_mapped_data().is_null());
+
+        let mapped_ptr = buffer.info.get_mapped_data() as vkptr<u8>;
+        for frame_index in 0..frame_count {
+            unsafe { write_slot(mapped_ptr, FrameSlots::offset_of(frame_index, aligned_size), data) };
+        }
+
+        let uniforms = FrameUniforms {
+            buffer,
+            slots: FrameSlots::new(frame_count, aligned_size),
+            phantom_type: PhantomData,
+        };
+        Ok(uniforms)
+    }
+
+    /// Overwrite the slot of `frame_index` with `data`.
+    ///
+    /// Return error if this slot has been updated since the last `FrameUniforms::mark_waited` of this frame.
+    pub fn update(&mut self, frame_index: usize, data: &T) -> VkResult<()> {
+
+        let offset = self.slots.acquire(frame_index)?;
+        unsafe { write_slot(self.buffer.info.get_mapped_data() as vkptr<u8>, offset, data) };
+
+        Ok(())
+    }
+
+    /// Tell that the fence of `frame_index` has been waited, so its slot is no longer read by device and can be updated again.
+    #[inline]
+    pub fn mark_waited(&mut self, frame_index: usize) {
+        self.slots.release(frame_index);
+    }
+
+    /// Get the handle of `vk::Buffer` holding all the slots.
+    #[inline]
+    pub fn handle(&self) -> vk::Buffer {
+        self.buffer.handle
+    }
+
+    /// Get the descriptor info covering the slot of `frame_index`.
+    pub fn descriptor_info(&self, frame_index: usize) -> vk::DescriptorBufferInfo {
+        vk::DescriptorBufferInfo {
+            buffer: self.buffer.handle,
+            offset: FrameSlots::offset_of(frame_index, self.slots.aligned_size),
+            range : mem::size_of::<T>() as vkbytes,
+        }
+    }
+
+    /// Get the count of slots, which equals to the count of frames in flight.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.slots.in_use.len()
+    }
+
+    /// Check if there is no slot, which happens only if the frame count is 0.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.slots.in_use.is_empty()
+    }
+
+    pub fn discard_by(self, device: &mut VkDevice) -> VkResult<()> {
+        device.vma_discard(self.buffer)
+    }
+}
+
+/// The offsets of uniform slots and whether each of them may still be read by device.
+struct FrameSlots {
+
+    aligned_size: vkbytes,
+    in_use: Vec<bool>,
+}
+
+impl FrameSlots {
+
+    fn new(frame_count: usize, aligned_size: vkbytes) -> FrameSlots {
+        FrameSlots { aligned_size, in_use: vec![false; frame_count] }
+    }
+
+    #[inline]
+    fn offset_of(frame_index: usize, aligned_size: vkbytes) -> vkbytes {
+        frame_index as vkbytes * aligned_size
+    }
+
+    /// Mark the slot of `frame_index` in use and return its offset, or return error if the slot is still in use.
+    fn acquire(&mut self, frame_index: usize) -> VkResult<vkbytes> {
+
+        if self.in_use[frame_index] {
+            return Err(VkError::custom(format!("Uniform of frame {} is updated before its fence was waited.", frame_index)))
+        }
+
+        self.in_use[frame_index] = true;
+        Ok(FrameSlots::offset_of(frame_index, self.aligned_size))
+    }
+
+    #[inline]
+    fn release(&mut self, frame_index: usize) {
+        self.in_use[frame_index] = false;
+    }
+}
+
+/// Copy the bytes of `data` to `mapped_ptr` at `offset`.
+///
+/// `mapped_ptr` must point to a memory with at least `offset + size_of::<T>()` bytes.
+unsafe fn write_slot<T: Copy>(mapped_ptr: vkptr<u8>, offset: vkbytes, data: &T) {
+
+    let data_ptr = data as *const T as *const u8;
+    data_ptr.copy_to_nonoverlapping(mapped_ptr.add(offset as usize), mem::size_of::<T>());
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn update_writes_at_frame_slot_offset() {
+
+        let aligned_size: vkbytes = 256;
+        let mut slots = FrameSlots::new(3, aligned_size);
+        let mut memory = vec![0_u8; (aligned_size * 3) as usize];
+
+        let data: [u32; 4] = [1, 2, 3, 4];
+        let offset = slots.acquire(1).unwrap();
+        unsafe { write_slot(memory.as_mut_ptr(), offset, &data) };
+
+        let data_size = mem::size_of_val(&data);
+        let written = 1 * aligned_size as usize;
+        assert_eq!(offset, 1 * aligned_size);
+        assert_eq!(&memory[written..(written + data_size)], unsafe { std::slice::from_raw_parts(data.as_ptr() as *const u8, data_size) });
+        // the slots of other frames may still be read by device, so they must be untouched.
+        assert!(memory[..written].iter().all(|&byte| byte == 0));
+        assert!(memory[(written + data_size)..].iter().all(|&byte| byte == 0));
+
+        assert_eq!(slots.acquire(0).unwrap(), 0);
+        assert_eq!(slots.acquire(2).unwrap(), 2 * aligned_size);
+    }
+
+    #[test]
+    fn frame_in_use_can_not_be_updated_until_waited() {
+
+        let mut slots = FrameSlots::new(2, 256);
+
+        assert!(slots.acquire(0).is_ok());
+        assert!(slots.acquire(0).is_err());
+        // other frames are not affected.
+        assert!(slots.acquire(1).is_ok());
+
+        slots.release(0);
+        assert!(slots.acquire(0).is_ok());
+    }
+}
@@ -0,0 +1,89 @@
+//! Helpers to split the screen into several viewports(etc. split-screen, side by side comparison),
+//! and to flip the Y axis of viewports.
+
+use ash::vk;
+
+use crate::{vkuint, vkfloat};
+
+/// Get the viewport covering the whole `dimension`, with depth range [0.0, 1.0].
+pub fn viewport_full(dimension: vk::Extent2D) -> vk::Viewport {
+    vk::Viewport {
+        x: 0.0, y: 0.0,
+        width: dimension.width as f32, height: dimension.height as f32,
+        min_depth: 0.0, max_depth: 1.0,
+    }
+}
+
+/// Restrict `viewport` to the depth range [`min_depth`, `max_depth`], which remaps the depth of geometry without changing its transform.
+///
+/// Drawing with a near sub-range(etc. [0.0, 0.1]) makes the geometry(etc. gizmos) always appear in front of the scene drawn with [0.1, 1.0] or [0.0, 1.0],
+/// since the depth test compares the remapped depth. Depth clearing is not affected by viewport, so the depth attachment is still cleared to `DepthConfig::clear_depth`.
+/// For reverse-Z, the near sub-range is at the top instead(etc. [0.9, 1.0]).
+///
+/// The depth values must be in [0.0, 1.0] unless `VK_EXT_depth_range_unrestricted` extension is enabled.
+pub fn viewport_depth_range(viewport: vk::Viewport, min_depth: vkfloat, max_depth: vkfloat) -> vk::Viewport {
+    vk::Viewport { min_depth, max_depth, ..viewport }
+}
+
+/// Flip `viewport` upside down by a negative height, so that the +Y axis of clip space points upwards like OpenGL.
+///
+/// With a flipped viewport, the right-handed, Y-up data(etc. glTF models) renders correctly by an ordinary projection matrix,
+/// so disable `FlightCamera` flipping by `FlightCameraBuilder::flip_vertically(false)`.
+/// The winding order of triangles is also kept as in model space, so counter-clockwise triangles are front-facing(`vk::FrontFace::COUNTER_CLOCKWISE`).
+///
+/// Negative viewport height requires Vulkan 1.1 or `VK_KHR_maintenance1` extension(see `DeviceExtensionType::Maintenance1`).
+pub fn viewport_flip_y(viewport: vk::Viewport) -> vk::Viewport {
+    vk::Viewport {
+        y: viewport.y + viewport.height,
+        height: -viewport.height,
+        ..viewport
+    }
+}
+
+/// Split `dimension` horizontally into `n` columns of equal width, and get the viewport of the column at `index`(counted from left).
+pub fn viewport_split(dimension: vk::Extent2D, n: vkuint, index: vkuint) -> vk::Viewport {
+
+    let rect = scissor_split(dimension, n, index);
+
+    vk::Viewport {
+        x: rect.offset.x as f32,
+        y: rect.offset.y as f32,
+        width : rect.extent.width  as f32,
+        height: rect.extent.height as f32,
+        min_depth: 0.0, max_depth: 1.0,
+    }
+}
+
+/// Split `dimension` horizontally into `n` columns, and get the scissor of the column at `index`(counted from left).
+///
+/// The columns cover `dimension` without gap or overlapping, even if the width can not be divided by `n` exactly.
+pub fn scissor_split(dimension: vk::Extent2D, n: vkuint, index: vkuint) -> vk::Rect2D {
+
+    debug_assert!(n > 0 && index < n, "The index of split viewport is out of range.");
+
+    let start = dimension.width * index / n;
+    let end   = dimension.width * (index + 1) / n;
+
+    vk::Rect2D {
+        offset: vk::Offset2D { x: start as i32, y: 0 },
+        extent: vk::Extent2D { width: end - start, height: dimension.height },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn depth_range_is_carried_by_viewport() {
+
+        let full = viewport_full(vk::Extent2D { width: 800, height: 600 });
+        let viewport = viewport_depth_range(full, 0.9, 1.0);
+
+        assert_eq!(viewport.min_depth, 0.9);
+        assert_eq!(viewport.max_depth, 1.0);
+        // the transform of viewport is kept.
+        assert_eq!((viewport.x, viewport.y, viewport.width, viewport.height), (0.0, 0.0, 800.0, 600.0));
+    }
+}
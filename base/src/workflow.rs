@@ -10,29 +10,96 @@ use ash::vk;
 use crate::context::{VkDevice, VkSwapchain};
 use crate::utils::frame::FrameAction;
 use crate::input::EventController;
-use crate::error::VkResult;
+use crate::error::{VkResult, VkError};
+use crate::vkuint;
 
 //
 // Initialize Vulkan Context
 //        ↓             <---------------------------------------↑
 //        ↓             ↓      (swapchain_reload if happen)     ↓
+//        ↓             ↓   (on_resize if dimension changed)    ↓
 //        ↓             ↓                                       ↑(game loop)
 //        ↓             ↓                                       ↑
 //      init() -------------> receive_input --> render_frame --------> deinit ----------> destroy Vulkan Context.
-//                                                                  (terminate program)
+//                                   ↓                              (terminate program)
+//                     (rerecord_commands if requested)
 pub trait RenderWorkflow {
 
     fn init(&mut self, _device: &VkDevice) -> VkResult<()> {
         Ok(())
     }
 
+    /// Submit the rendering commands for swapchain image at `image_index`.
+    ///
+    /// It's guaranteed that the previous frame rendering to the same image has finished when this method is called,
+    /// so resources kept for each swapchain image(see `FrameResource`) can be updated here directly.
     fn render_frame(&mut self, device: &mut VkDevice, device_available: vk::Fence, await_present: vk::Semaphore, image_index: usize, delta_time: f32) -> VkResult<vk::Semaphore>;
 
+    /// Recreate the resources depending on swapchain, which is called after the swapchain of primary window is recreated.
+    ///
+    /// All the frames rendered to the window have completed when this method is called,
+    /// so the resources used by them(etc. framebuffers and command buffers) can be destroyed and recreated here.
     fn swapchain_reload(&mut self, _device: &mut VkDevice, _new_chain: &VkSwapchain) -> VkResult<()> {
         Ok(())
     }
 
+    /// Submit the rendering commands for swapchain image at `image_index` of the window at `window_index`.
+    ///
+    /// Window 0 is the primary window, and the others are added by `WindowContext::add_window`.
+    /// The default implementation only supports the primary window by calling `render_frame`,
+    /// so programs rendering to multiple windows must override this method.
+    fn render_window_frame(&mut self, device: &mut VkDevice, device_available: vk::Fence, await_present: vk::Semaphore, window_index: usize, image_index: usize, delta_time: f32) -> VkResult<vk::Semaphore> {
+
+        if window_index == 0 {
+            self.render_frame(device, device_available, await_present, image_index, delta_time)
+        } else {
+            Err(VkError::custom("RenderWorkflow::render_window_frame must be implemented to render secondary windows."))
+        }
+    }
+
+    /// Called when the swapchain of the window at `window_index` has been recreated.
+    ///
+    /// The default implementation calls `swapchain_reload` for the primary window, and ignores the others.
+    fn window_swapchain_reload(&mut self, device: &mut VkDevice, window_index: usize, new_chain: &VkSwapchain) -> VkResult<()> {
+
+        if window_index == 0 {
+            self.swapchain_reload(device, new_chain)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Called after the swapchain is recreated with a dimension different from the previous one.
+    ///
+    /// `width` and `height` are the new dimension of swapchain images. Unlike `swapchain_reload`, this method does not concern
+    /// the resources depending on the swapchain, so it's the place to update the screen-size state(etc. camera aspect ratio, UI layout).
+    fn on_resize(&mut self, _width: vkuint, _height: vkuint) {}
+
+    /// Called when the swapchain of the window at `window_index` has been recreated with a new dimension.
+    ///
+    /// The default implementation calls `on_resize` for the primary window, and ignores the others.
+    fn on_window_resize(&mut self, window_index: usize, width: vkuint, height: vkuint) {
+
+        if window_index == 0 {
+            self.on_resize(width, height);
+        }
+    }
+
+    /// Re-record the command buffers, which is called after `receive_input` returns `FrameAction::CommandRerecord`.
+    ///
+    /// The device is idle when this method is called, so all command buffers can be reset and recorded again here.
+    /// Unlike `swapchain_reload`, the swapchain and its images are not changed.
+    fn rerecord_commands(&mut self, _device: &mut VkDevice) -> VkResult<()> {
+        Ok(())
+    }
+
     fn receive_input(&mut self, inputer: &EventController, delta_time: f32) -> FrameAction;
 
+    /// Called when the program gains(`is_focused` is true) or loses input focus, where the program is focused if any of its windows is.
+    ///
+    /// `receive_input` and `render_frame` are not called while no window is focused or all windows are minimized,
+    /// and `render_window_frame` is not called for a minimized window.
+    fn on_focus_changed(&mut self, _is_focused: bool) {}
+
     fn deinit(self, device: &mut VkDevice) -> VkResult<()>;
 }
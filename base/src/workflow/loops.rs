@@ -10,31 +10,97 @@ use crate::utils::time::VkTimeDuration;
 use crate::utils::frame::{FrameCounter, FrameAction};
 use crate::error::{VkResult, VkError};
 
+use std::time::Duration;
 
+/// The interval of polling window events while rendering is paused.
+const PAUSE_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+
+/// The main loop of program, which drives `RenderWorkflow` to receive input and render frames.
+///
+/// When the window is minimized, rendering is paused and the swapchain recreation is deferred,
+/// since a swapchain can not be created for a surface of zero extent.
+/// The loop keeps polling window events in the meantime, and recreates the swapchain once the window is restored.
+///
+/// If secondary windows are added by `WindowContext::add_window`, each frame is rendered to all windows in the order of their indices,
+/// by calling `RenderWorkflow::render_window_frame`. Closing any window terminates the program.
+/// Each window is minimized and restored on its own, while rendering is paused only when no window has focus or all are minimized.
 pub struct ProcPipeline {
 
     window: WindowContext,
     vulkan: VulkanContext,
 
-    syncs: SyncResource,
+    /// the synchronization resources of each window, indexed by window index.
+    targets: Vec<PresentTarget>,
+}
+
+/// The resources to render frames to the swapchain of one window.
+struct PresentTarget {
 
+    syncs: SyncResource,
     frame_counter: FrameCounter,
+    /// whether a swapchain recreation is deferred until the window is restored from minimized.
+    is_recreate_pending: bool,
+    /// the dimension of swapchain images when the swapchain was created last time.
+    dimension: vk::Extent2D,
 }
 
 impl ProcPipeline {
 
     pub fn new(window: WindowContext, vulkan: VulkanContext) -> VkResult<ProcPipeline> {
 
-        let frame_in_flight = vulkan.swapchain.frame_in_flight();
-        let syncs = SyncResource::new(&vulkan.device, frame_in_flight)?;
-        let frame_counter = FrameCounter::new(frame_in_flight);
+        let mut targets = Vec::with_capacity(vulkan.window_count());
+
+        for window_index in 0..vulkan.window_count() {
+
+            // by default, the CPU can be ahead of GPU by as many frames as the swapchain images.
+            let swapchain = vulkan.swapchain_at(window_index);
+            let image_count = swapchain.frame_in_flight();
+            let target = PresentTarget {
+                syncs: SyncResource::new(&vulkan.device, image_count, image_count)?,
+                frame_counter: FrameCounter::new(image_count),
+                is_recreate_pending: false,
+                dimension: swapchain.dimension,
+            };
+            targets.push(target);
+        }
 
-        let target = ProcPipeline { window, vulkan, syncs, frame_counter };
+        let target = ProcPipeline { window, vulkan, targets };
         Ok(target)
     }
 
+    /// Set the maximum count of frames that CPU can record and submit ahead of GPU, regardless of the count of swapchain images.
+    ///
+    /// A smaller count reduces input latency, while a larger count keeps GPU busier.
+    /// The resources kept for each swapchain image(see `FrameResource`) are still indexed by the acquired image index,
+    /// and `ProcPipeline` keeps waiting the frame previously using the acquired image, so they stay safe to update in `RenderWorkflow::render_frame`.
+    ///
+    /// Return error if `count` is 0. A warning is printed if `count` exceeds the count of swapchain images,
+    /// since the extra frames can not acquire images before the previous ones are presented.
+    pub fn with_frames_in_flight(mut self, count: usize) -> VkResult<ProcPipeline> {
+
+        if count == 0 {
+            return Err(VkError::custom("The count of frames in flight must be at least 1."))
+        }
+
+        for (window_index, target) in self.targets.iter_mut().enumerate() {
+
+            let image_count = self.vulkan.swapchain_at(window_index).frame_in_flight();
+            if count > image_count {
+                println!("[Warning] {} frames in flight exceed the {} swapchain images of window {}.", count, image_count, window_index);
+            }
+
+            target.syncs.discard(&self.vulkan.device);
+            target.syncs = SyncResource::new(&self.vulkan.device, count, image_count)?;
+            target.frame_counter = FrameCounter::new(count);
+        }
+
+        Ok(self)
+    }
+
+    /// Get the maximum count of frames that CPU can be ahead of GPU.
     pub fn frame_in_flight(&self) -> usize {
-        self.vulkan.swapchain.frame_in_flight()
+        self.targets[0].syncs.frame_count
     }
 
     pub fn launch(mut self, mut app: impl RenderWorkflow) -> VkResult<()> {
@@ -45,9 +111,12 @@ impl ProcPipeline {
 
         self.vulkan.wait_idle()?;
         // free the program specific resource.
+        // `deinit` consumes the program, so that the resources it owns(etc. `OwnedBuffer`) are destroyed while device is still alive.
         app.deinit(&mut self.vulkan.device)?;
         // and then free vulkan context resource.
-        self.syncs.discard(&self.vulkan.device);
+        for target in self.targets.iter_mut() {
+            target.syncs.discard(&self.vulkan.device);
+        }
         self.vulkan.discard();
 
         Ok(())
@@ -55,19 +124,25 @@ impl ProcPipeline {
 
     fn main_loop(&mut self, app: &mut impl RenderWorkflow) -> VkResult<()> {
 
-        let mut event_handler = EventController::default();
+        let mut event_handler = EventController::new(self.window.window_ids());
 
         'loop_marker: loop {
 
             macro_rules! response_feedback {
-                ($action:ident) => {
+                ($action:expr, $window_indices:expr) => {
                     match $action {
                         | FrameAction::Rendering => {},
                         | FrameAction::SwapchainRecreate => {
-
+                            for window_index in $window_indices {
+                                if self.recreate_swapchain(app, &mut event_handler, window_index)? == false {
+                                    break 'loop_marker
+                                }
+                            }
+                        },
+                        | FrameAction::CommandRerecord => {
+                            // the command buffers of any frame in flight may still be executing.
                             self.vulkan.wait_idle()?;
-                            self.vulkan.recreate_swapchain(&self.window)?;
-                            app.swapchain_reload(&mut self.vulkan.device, &self.vulkan.swapchain)?;
+                            app.rerecord_commands(&mut self.vulkan.device)?;
                         },
                         | FrameAction::Terminal => {
                             break 'loop_marker
@@ -82,33 +157,120 @@ impl ProcPipeline {
                 event_handler.record_event(event);
             });
             let window_feedback = event_handler.current_action();
-            response_feedback!(window_feedback);
+            response_feedback!(window_feedback, 0..self.targets.len());
+
+            if event_handler.is_focus_toggled() {
+                app.on_focus_changed(event_handler.is_focused());
+            }
+
+            if event_handler.is_paused() {
+                // keep pumping window events, but skip the input and rendering until the window is focused and restored.
+                event_handler.tick_frame();
+                ::std::thread::sleep(PAUSE_POLL_INTERVAL);
+                continue
+            }
+
+            for window_index in 0..self.targets.len() {
+                if self.targets[window_index].is_recreate_pending {
+                    response_feedback!(FrameAction::SwapchainRecreate, Some(window_index));
+                }
+            }
 
             let input_feedback = app.receive_input(&event_handler, delta_time);
-            response_feedback!(input_feedback);
+            response_feedback!(input_feedback, 0..self.targets.len());
 
-            let render_feedback = self.render_frame(app, delta_time)?;
-            response_feedback!(render_feedback);
+            for window_index in 0..self.targets.len() {
+                if event_handler.is_window_minimized(window_index) {
+                    // a minimized window has no image to present, while the other windows keep rendering.
+                    continue
+                }
+                let render_feedback = self.render_frame(app, window_index, delta_time)?;
+                response_feedback!(render_feedback, Some(window_index));
+            }
 
             event_handler.tick_frame();
-            self.frame_counter.tick_frame();
+            for target in self.targets.iter_mut() {
+                target.frame_counter.tick_frame();
+            }
         }
 
         Ok(())
     }
 
-    fn render_frame(&mut self, app: &mut impl RenderWorkflow, delta_time: f32) -> VkResult<FrameAction> {
+    /// Recreate the swapchain of the window at `window_index`, or defer it if the window is minimized.
+    ///
+    /// Return false if the window is requested to close during waiting.
+    fn recreate_swapchain(&mut self, app: &mut impl RenderWorkflow, event_handler: &mut EventController, window_index: usize) -> VkResult<bool> {
+
+        if event_handler.is_window_minimized(window_index) {
+            // the surface of a minimized window has zero extent, so the swapchain can not be created now.
+            self.targets[window_index].is_recreate_pending = true;
+            return Ok(true)
+        }
+
+        // the window may be minimized without a resize event, so check the surface again.
+        if self.wait_until_restored(event_handler, window_index)? == false {
+            return Ok(false)
+        }
+
+        // only the frames of this window render to its swapchain images, so the other windows and queues are not waited.
+        self.targets[window_index].syncs.wait_frames(&self.vulkan.device)?;
+        self.vulkan.recreate_swapchain(&self.window, window_index)?;
+
+        let (device, new_chain) = self.vulkan.device_with_swapchain(window_index);
+        let new_dimension = new_chain.dimension;
+        self.targets[window_index].syncs.reset_image_fences(new_chain.frame_in_flight());
+        app.window_swapchain_reload(device, window_index, new_chain)?;
+        self.targets[window_index].is_recreate_pending = false;
+
+        // the swapchain may also be recreated without resizing(etc. out of date surface), so only notify the real size change.
+        let target = &mut self.targets[window_index];
+        if target.dimension.width != new_dimension.width || target.dimension.height != new_dimension.height {
+            target.dimension = new_dimension;
+            app.on_window_resize(window_index, new_dimension.width, new_dimension.height);
+        }
+
+        Ok(true)
+    }
+
+    /// Block until the surface of the window at `window_index` has non-zero extent, while keep pumping window events.
+    ///
+    /// Return false if the window is requested to close during waiting.
+    fn wait_until_restored(&mut self, event_handler: &mut EventController, window_index: usize) -> VkResult<bool> {
+
+        while self.vulkan.is_surface_zero_area(&self.window, window_index)? {
+
+            self.window.event_loop.poll_events(|event| {
+                event_handler.record_event(event);
+            });
+
+            if event_handler.current_action() == FrameAction::Terminal {
+                return Ok(false)
+            }
+
+            ::std::thread::sleep(PAUSE_POLL_INTERVAL);
+        }
+
+        Ok(true)
+    }
+
+    fn render_frame(&mut self, app: &mut impl RenderWorkflow, window_index: usize, delta_time: f32) -> VkResult<FrameAction> {
+
+        let target = &mut self.targets[window_index];
 
         // wait and acquire next image. -------------------------------------
-        let fence_ready = self.syncs.sync_fences[self.frame_counter.current_frame()];
+        let fence_ready = target.syncs.sync_fences[target.frame_counter.current_frame()];
+        let await_present = target.syncs.await_present;
         unsafe {
             self.vulkan.device.logic.handle.wait_for_fences(&[fence_ready], true, VkTimeDuration::Infinite.into())
                 .map_err(|_| VkError::device("Fence waiting"))?;
         }
+        self.vulkan.release_retired_swapchain(window_index);
 
-        let acquire_image_index = match self.vulkan.swapchain.next_image(Some(self.syncs.await_present), None) {
+        let acquire_image_index = match self.vulkan.swapchain_at(window_index).next_image(Some(await_present), None) {
             | Ok(image_index) => image_index,
             | Err(e) => match e {
+                // no image is acquired, so `await_present` is left unsignaled.
                 | SwapchainSyncError::SurfaceOutDate
                 | SwapchainSyncError::SubOptimal => {
                     return Ok(FrameAction::SwapchainRecreate)
@@ -120,6 +282,20 @@ impl ProcPipeline {
             }
         };
 
+        // wait until the previous frame rendering to this image finished,
+        // so that the per-image resources(command buffer, uniform buffer...) are no longer in use by GPU.
+        let image_fence = target.syncs.image_fences[acquire_image_index as usize];
+        if image_fence != vk::Fence::null() && image_fence != fence_ready {
+            unsafe {
+                self.vulkan.device.logic.handle.wait_for_fences(&[image_fence], true, VkTimeDuration::Infinite.into())
+                    .map_err(|_| VkError::device("Fence waiting"))?;
+                // resources bound to this image are going to be updated in `RenderWorkflow::render_frame`.
+                debug_assert!(self.vulkan.device.logic.handle.get_fence_status(image_fence).is_ok(),
+                    "Previous frame rendering to this image is still in flight.");
+            }
+        }
+        target.syncs.image_fences[acquire_image_index as usize] = fence_ready;
+
         unsafe {
             self.vulkan.device.logic.handle.reset_fences(&[fence_ready])
                 .map_err(|_| VkError::device("Fence Resetting"))?;
@@ -127,14 +303,14 @@ impl ProcPipeline {
         // ------------------------------------------------------------------
 
         // call command buffer(activate pipeline to draw) -------------------
-        let await_render = app.render_frame(&mut self.vulkan.device, fence_ready, self.syncs.await_present, acquire_image_index as _, delta_time)?;
+        let await_render = app.render_window_frame(&mut self.vulkan.device, fence_ready, await_present, window_index, acquire_image_index as _, delta_time)?;
         // ------------------------------------------------------------------
 
         // present image. ---------------------------------------------------
         // TODO: Add ownership transfer if need.
         // see https://github.com/KhronosGroup/Vulkan-Docs/wiki/Synchronization-Examples.
         // or see https://software.intel.com/en-us/articles/api-without-secrets-introduction-to-vulkan-part-3#inpage-nav-6-3
-        match self.vulkan.swapchain.present(&[await_render], acquire_image_index) {
+        match self.vulkan.swapchain_at(window_index).present(&[await_render], acquire_image_index) {
             | Ok(_) => {},
             | Err(e) => match e {
                 | SwapchainSyncError::SurfaceOutDate
@@ -161,11 +337,14 @@ struct SyncResource {
 
     await_present: vk::Semaphore,
     sync_fences : Vec<vk::Fence>,
+    /// the fence of the frame which is rendering to each swapchain image(or null if no frame is using it).
+    image_fences: Vec<vk::Fence>,
 }
 
 impl SyncResource {
 
-    pub fn new(device: &VkDevice, frame_count: usize) -> VkResult<SyncResource> {
+    /// Create the fences of `frame_count` frames in flight, which render to `image_count` swapchain images.
+    pub fn new(device: &VkDevice, frame_count: usize, image_count: usize) -> VkResult<SyncResource> {
 
         use crate::ci::sync::{SemaphoreCI, FenceCI};
 
@@ -178,19 +357,37 @@ impl SyncResource {
             sync_fences.push(device.build(&fence_ci)?);
         }
 
-        let syncs = SyncResource { frame_count, await_present, sync_fences };
+        let image_fences = vec![vk::Fence::null(); image_count];
+
+        let syncs = SyncResource { frame_count, await_present, sync_fences, image_fences };
         Ok(syncs)
     }
 
     #[allow(dead_code)]
     fn reset(&mut self, device: &VkDevice) -> VkResult<()> {
 
+        let image_count = self.image_fences.len();
         self.discard(device);
-        *self = SyncResource::new(device, self.frame_count)?;
+        *self = SyncResource::new(device, self.frame_count, image_count)?;
 
         Ok(())
     }
 
+    /// Wait until all the frames in flight have completed.
+    fn wait_frames(&self, device: &VkDevice) -> VkResult<()> {
+
+        unsafe {
+            device.logic.handle.wait_for_fences(&self.sync_fences, true, VkTimeDuration::Infinite.into())
+                .map_err(|_| VkError::device("Fence waiting"))
+        }
+    }
+
+    fn reset_image_fences(&mut self, image_count: usize) {
+
+        self.image_fences.clear();
+        self.image_fences.resize(image_count, vk::Fence::null());
+    }
+
     fn discard(&mut self, device: &VkDevice) {
 
         device.discard(self.await_present);
@@ -55,7 +55,10 @@ pub enum WindowMode {
 pub struct WindowContext {
 
     pub(crate) event_loop: winit::EventsLoop,
+    /// the primary window, whose index is 0.
     pub(crate) handle: winit::Window,
+    /// the windows added by `WindowContext::add_window`, whose index starts from 1.
+    secondaries: Vec<winit::Window>,
 }
 
 impl WindowContext {
@@ -63,55 +66,99 @@ impl WindowContext {
     pub fn new(config: WindowConfig) -> VkResult<WindowContext> {
 
         let event_loop = winit::EventsLoop::new();
+        let handle = build_window(config, &event_loop)?;
 
-        let mut builder = winit::WindowBuilder::new()
-            .with_title(config.title)
-            .with_dimensions((config.dimension.width, config.dimension.height).into())
-            .with_always_on_top(config.always_on_top)
-            .with_resizable(config.is_resizable);
+        let window = WindowContext { event_loop, handle, secondaries: Vec::new() };
+        Ok(window)
+    }
 
-        if let Some(min) = config.min_dimension {
-            builder = builder.with_min_dimensions((min.width, min.height).into());
-        }
+    /// Create another window sharing the event loop of the primary window, and return its index.
+    ///
+    /// The windows must be added before creating `VulkanContext`, so that a swapchain is created for each of them.
+    pub fn add_window(&mut self, config: WindowConfig) -> VkResult<usize> {
 
-        if let Some(max) = config.max_dimension {
-            builder = builder.with_max_dimensions((max.width, max.height).into());
-        }
+        let handle = build_window(config, &self.event_loop)?;
+        self.secondaries.push(handle);
 
-        builder = match config.mode {
-            | WindowMode::Maximized => {
-                builder.with_maximized(true)
-            },
-            | WindowMode::Fullscreen => {
-                let primary_monitor = event_loop.get_primary_monitor();
-                builder.with_fullscreen(Some(primary_monitor))
-            },
-            | WindowMode::Normal => {
-                builder
-            },
-        };
+        Ok(self.secondaries.len())
+    }
 
-        let window = WindowContext {
-            handle: builder.build(&event_loop)
-                .map_err(|e| VkError::window(e.to_string()))?,
-            event_loop,
-        };
+    /// Get the count of windows, including the primary window.
+    #[inline]
+    pub fn window_count(&self) -> usize {
+        self.secondaries.len() + 1
+    }
 
-        window.handle.grab_cursor(config.is_cursor_grap)
-            .map_err(|e| VkError::window(e.to_string()))?;
-        window.handle.hide_cursor(config.is_cursor_hide);
+    /// Get the id of each window, indexed by window index.
+    pub(crate) fn window_ids(&self) -> Vec<winit::WindowId> {
+        (0..self.window_count()).map(|i| self.handle_at(i).id()).collect()
+    }
 
-        Ok(window)
+    pub(crate) fn handle_at(&self, window_index: usize) -> &winit::Window {
+        if window_index == 0 {
+            &self.handle
+        } else {
+            &self.secondaries[window_index - 1]
+        }
     }
 
     pub fn dimension(&self) -> VkResult<vk::Extent2D> {
+        self.dimension_at(0)
+    }
+
+    /// Get the inner dimension of the window at `window_index`.
+    pub fn dimension_at(&self, window_index: usize) -> VkResult<vk::Extent2D> {
 
-        self.handle.get_inner_size()
+        self.handle_at(window_index).get_inner_size()
             .and_then(|dim| Some(ash::vk::Extent2D { width : dim.width as _, height: dim.height as _, }))
             .ok_or(VkError::window("Failed to get dimension of current window."))
     }
 
     pub fn hidpi_factor(&self) -> f32 {
-        self.handle.get_hidpi_factor() as f32
+        self.hidpi_factor_at(0)
+    }
+
+    /// Get the ratio of physical pixels to logical pixels of the window at `window_index`, which depends on the monitor it's placed on.
+    pub fn hidpi_factor_at(&self, window_index: usize) -> f32 {
+        self.handle_at(window_index).get_hidpi_factor() as f32
+    }
+}
+
+fn build_window(config: WindowConfig, event_loop: &winit::EventsLoop) -> VkResult<winit::Window> {
+
+    let mut builder = winit::WindowBuilder::new()
+        .with_title(config.title)
+        .with_dimensions((config.dimension.width, config.dimension.height).into())
+        .with_always_on_top(config.always_on_top)
+        .with_resizable(config.is_resizable);
+
+    if let Some(min) = config.min_dimension {
+        builder = builder.with_min_dimensions((min.width, min.height).into());
     }
+
+    if let Some(max) = config.max_dimension {
+        builder = builder.with_max_dimensions((max.width, max.height).into());
+    }
+
+    builder = match config.mode {
+        | WindowMode::Maximized => {
+            builder.with_maximized(true)
+        },
+        | WindowMode::Fullscreen => {
+            let primary_monitor = event_loop.get_primary_monitor();
+            builder.with_fullscreen(Some(primary_monitor))
+        },
+        | WindowMode::Normal => {
+            builder
+        },
+    };
+
+    let handle = builder.build(event_loop)
+        .map_err(|e| VkError::window(e.to_string()))?;
+
+    handle.grab_cursor(config.is_cursor_grap)
+        .map_err(|e| VkError::window(e.to_string()))?;
+    handle.hide_cursor(config.is_cursor_hide);
+
+    Ok(handle)
 }
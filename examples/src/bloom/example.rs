@@ -0,0 +1,442 @@
+
+use ash::vk;
+
+use std::path::Path;
+
+use vkbase::context::{VulkanContext, VkDevice, VkSwapchain};
+use vkbase::ci::VkObjectBuildableCI;
+use vkbase::ci::vma::{VmaImage, VmaAllocationCI};
+use vkbase::ci::pipeline::{PipelineLayout, RenderPass};
+use vkbase::bloom::{BloomPass, BloomConfig};
+use vkbase::utils::color::VkColor;
+use vkbase::ui::{TextInfo, TextID, TextType, TextHAlign};
+use vkbase::FrameAction;
+use vkbase::vkfloat;
+use vkbase::{VkResult, VkErrorKind};
+
+use vkexamples::VkExampleBackend;
+
+const SHADER_VERTEX_PATH  : &'static str = "examples/src/bloom/fullscreen.vert.glsl";
+const SHADER_FRAGMENT_PATH: &'static str = "examples/src/bloom/scene.frag.glsl";
+
+/// The format of offscreen scene, which keeps the HDR range of emissive pixels.
+const SCENE_FORMAT: vk::Format = vk::Format::R16G16B16A16_SFLOAT;
+
+/// The keys to decrease and increase threshold, intensity and blur radius respectively.
+const ADJUST_KEYS: [(winit::VirtualKeyCode, winit::VirtualKeyCode); 3] = [
+    (winit::VirtualKeyCode::Key1, winit::VirtualKeyCode::Key2),
+    (winit::VirtualKeyCode::Key3, winit::VirtualKeyCode::Key4),
+    (winit::VirtualKeyCode::Key5, winit::VirtualKeyCode::Key6),
+];
+
+pub struct VulkanExample {
+
+    backend: VkExampleBackend,
+
+    scene: SceneStaff,
+    bloom: BloomPass,
+
+    config_text_id: Option<TextID>,
+    is_adjust_key_held: bool,
+}
+
+impl VulkanExample {
+
+    pub fn new(context: &mut VulkanContext) -> VkResult<VulkanExample> {
+
+        let device = &mut context.device;
+        let swapchain = &context.swapchain;
+
+        let render_pass = setup_renderpass(device, &context.swapchain)?;
+        let backend = VkExampleBackend::new(device, swapchain, render_pass)?;
+
+        let scene = SceneStaff::new(device, backend.dimension)?;
+        let bloom = BloomPass::new(device, scene.descriptor(), backend.dimension, backend.render_pass.handle, BloomConfig::default())?;
+
+        let target = VulkanExample {
+            backend, scene, bloom,
+            config_text_id: None,
+            is_adjust_key_held: false,
+        };
+        Ok(target)
+    }
+}
+
+impl vkbase::RenderWorkflow for VulkanExample {
+
+    fn init(&mut self, device: &VkDevice) -> VkResult<()> {
+
+        self.backend.set_basic_ui(device, super::WINDOW_TITLE)?;
+
+        let config_text = TextInfo {
+            content: config_content(self.bloom.config()),
+            scale: 23.0,
+            align: TextHAlign::Left,
+            color: VkColor::WHITE,
+            location: vk::Offset2D { x: 5, y: 140 },
+            pivot: (0.0, 0.0),
+            shadow: None,
+            r#type: TextType::Dynamic { capacity: 80 },
+        };
+        self.config_text_id = self.backend.add_text(config_text)?;
+
+        self.record_commands(device, self.backend.dimension)?;
+        Ok(())
+    }
+
+    fn render_frame(&mut self, device: &mut VkDevice, device_available: vk::Fence, await_present: vk::Semaphore, image_index: usize, _delta_time: f32) -> VkResult<vk::Semaphore> {
+
+        let submit_ci = vkbase::ci::device::SubmitCI::new()
+            .add_wait(vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT, await_present)
+            .add_command(self.backend.commands[image_index])
+            .add_signal(self.backend.await_rendering);
+
+        device.submit(submit_ci, device.logic.queues.graphics.handle, Some(device_available))?;
+
+        Ok(self.backend.await_rendering)
+    }
+
+    fn swapchain_reload(&mut self, device: &mut VkDevice, new_chain: &VkSwapchain) -> VkResult<()> {
+
+        let render_pass = setup_renderpass(device, new_chain)?;
+        self.backend.swapchain_reload(device, new_chain, render_pass)?;
+
+        // the scene target follows the dimension of swapchain, and the composite pipeline follows the new render pass.
+        let config = self.bloom.config().clone();
+        let new_scene = SceneStaff::new(device, self.backend.dimension)?;
+        let new_bloom = BloomPass::new(device, new_scene.descriptor(), self.backend.dimension, self.backend.render_pass.handle, config)?;
+
+        ::std::mem::replace(&mut self.scene, new_scene).discard_by(device)?;
+        ::std::mem::replace(&mut self.bloom, new_bloom).discard_by(device)?;
+
+        self.record_commands(device, self.backend.dimension)?;
+
+        Ok(())
+    }
+
+    fn receive_input(&mut self, inputer: &vkbase::EventController, _delta_time: f32) -> FrameAction {
+
+        if inputer.is_key_active() && inputer.key.is_key_pressed(winit::VirtualKeyCode::Escape) {
+            return FrameAction::Terminal
+        }
+
+        self.backend.update_fps_text(inputer);
+
+        // adjust the configuration once per key press.
+        let adjustment = ADJUST_KEYS.iter().enumerate().find_map(|(i, &(decrease, increase))| {
+            if inputer.key.is_key_pressed(decrease) {
+                Some((i, -1.0))
+            } else if inputer.key.is_key_pressed(increase) {
+                Some((i, 1.0))
+            } else {
+                None
+            }
+        });
+        let is_adjust_triggered = adjustment.is_some() && !self.is_adjust_key_held;
+        self.is_adjust_key_held = adjustment.is_some();
+
+        if let Some((control, direction)) = adjustment.filter(|_| is_adjust_triggered) {
+
+            let config = adjust_config(self.bloom.config().clone(), control, direction);
+            self.backend.change_text(config_content(&config), self.config_text_id);
+            self.bloom.set_config(config);
+
+            // the parameters of bloom are pushed as push constants, so the commands must be recorded again.
+            return FrameAction::CommandRerecord
+        }
+
+        self.backend.ui_action()
+    }
+
+    fn rerecord_commands(&mut self, device: &mut VkDevice) -> VkResult<()> {
+
+        self.backend.update_glyph_atlas(device)?;
+        self.record_commands(device, self.backend.dimension)
+    }
+
+    fn deinit(self, device: &mut VkDevice) -> VkResult<()> {
+
+        self.bloom.discard_by(device)?;
+        self.scene.discard_by(device)?;
+        self.backend.discard_by(device)
+    }
+}
+
+impl VulkanExample {
+
+    fn record_commands(&self, device: &VkDevice, dimension: vk::Extent2D) -> VkResult<()> {
+
+        let viewport = vk::Viewport {
+            x: 0.0, y: 0.0,
+            width: dimension.width as f32, height: dimension.height as f32,
+            min_depth: 0.0, max_depth: 1.0,
+        };
+
+        let scissor = vk::Rect2D {
+            extent: dimension.clone(),
+            offset: vk::Offset2D { x: 0, y: 0 },
+        };
+
+        let scene_params = SceneParams {
+            aspect: dimension.width as vkfloat / dimension.height as vkfloat,
+        };
+
+        for (i, &command) in self.backend.commands.iter().enumerate() {
+
+            use vkbase::command::{VkCmdRecorder, CmdGraphicsApi, IGraphics};
+            use vkbase::ci::pipeline::RenderPassBI;
+
+            let recorder: VkCmdRecorder<IGraphics> = VkCmdRecorder::new(&device.logic, command);
+
+            // the scene covers the whole target, so it's not cleared.
+            let scene_bi = RenderPassBI::with_pass(self.scene.render_pass, self.scene.framebuffer)
+                .render_extent(dimension);
+
+            recorder.begin_record()?
+                .begin_render_pass(scene_bi)
+                .set_viewport(0, &[viewport])
+                .set_scissor(0, &[scissor])
+                .bind_pipeline(self.scene.pipeline)
+                .push_constant_data(&self.scene.pipeline_layout, vk::ShaderStageFlags::FRAGMENT, 0, &scene_params)
+                .draw(3, 1, 0, 0)
+                .end_render_pass();
+
+            self.bloom.record_command(&recorder);
+
+            let render_pass_bi = RenderPassBI::with_pass(self.backend.render_pass, self.backend.framebuffers[i])
+                .render_extent(dimension)
+                .set_clear_values(self.backend.clear_values());
+
+            recorder
+                .begin_render_pass(render_pass_bi)
+                .set_viewport(0, &[viewport])
+                .set_scissor(0, &[scissor]);
+
+            self.bloom.record_composite(&recorder);
+            self.backend.record_ui_command(&recorder, i);
+
+            recorder.end_render_pass()
+                .end_record()?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Change the control `control`(0 for threshold, 1 for intensity, 2 for blur radius) of `config` by one step in `direction`.
+fn adjust_config(config: BloomConfig, control: usize, direction: vkfloat) -> BloomConfig {
+
+    match control {
+        | 0 => BloomConfig { threshold: (config.threshold + direction * 0.1).max(0.0), ..config },
+        | 1 => BloomConfig { intensity: (config.intensity + direction * 0.1).max(0.0), ..config },
+        | _ => BloomConfig { blur_radius: (config.blur_radius as isize + direction as isize).max(0) as usize, ..config },
+    }
+}
+
+fn config_content(config: &BloomConfig) -> String {
+    format!("Threshold: {:1.1}(1/2)  Intensity: {:1.1}(3/4)  Radius: {}(5/6)", config.threshold, config.intensity, config.blur_radius)
+}
+
+
+
+/// The push constants of scene.frag.glsl.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct SceneParams {
+    aspect: vkfloat,
+}
+
+/// The offscreen HDR scene, which is sampled by bloom.
+struct SceneStaff {
+    image: VmaImage,
+    view: vk::ImageView,
+    sampler: vk::Sampler,
+    render_pass: RenderPass,
+    framebuffer: vk::Framebuffer,
+
+    pipeline: vk::Pipeline,
+    pipeline_layout: PipelineLayout,
+}
+
+impl SceneStaff {
+
+    fn new(device: &mut VkDevice, dimension: vk::Extent2D) -> VkResult<SceneStaff> {
+
+        use vkbase::ci::image::{ImageCI, ImageViewCI, SamplerCI};
+        use vkbase::ci::pipeline::{RenderPassCI, AttachmentDescCI, SubpassDescCI, SubpassDependencyCI, FramebufferCI};
+
+        let image = {
+            let image_ci = ImageCI::new_2d(SCENE_FORMAT, dimension)
+                .usages(vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::SAMPLED);
+            let allocation_ci = VmaAllocationCI::new(vma::MemoryUsage::GpuOnly, vk::MemoryPropertyFlags::DEVICE_LOCAL);
+            let image_allocation = device.vma.create_image(image_ci.as_ref(), allocation_ci.as_ref())
+                .map_err(VkErrorKind::Vma)?;
+            VmaImage::from(image_allocation)
+        };
+
+        let view = ImageViewCI::new(image.handle, vk::ImageViewType::TYPE_2D, SCENE_FORMAT)
+            .sub_range(vk::ImageSubresourceRange {
+                aspect_mask: vk::ImageAspectFlags::COLOR,
+                base_mip_level: 0,
+                level_count   : 1,
+                base_array_layer: 0,
+                layer_count     : 1,
+            }).build(device)?;
+
+        let sampler = SamplerCI::new()
+            .filter(vk::Filter::LINEAR, vk::Filter::LINEAR)
+            .address(vk::SamplerAddressMode::CLAMP_TO_EDGE, vk::SamplerAddressMode::CLAMP_TO_EDGE, vk::SamplerAddressMode::CLAMP_TO_EDGE)
+            .build(device)?;
+
+        // the scene is sampled by the bright-pass and composition of bloom after rendering.
+        let color_attachment = AttachmentDescCI::new(SCENE_FORMAT)
+            .op(vk::AttachmentLoadOp::DONT_CARE, vk::AttachmentStoreOp::STORE)
+            .layout(vk::ImageLayout::UNDEFINED, vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL);
+
+        let subpass_description = SubpassDescCI::new(vk::PipelineBindPoint::GRAPHICS)
+            .add_color_attachment(0, vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL);
+
+        let dependency0 = SubpassDependencyCI::new(vk::SUBPASS_EXTERNAL, 0)
+            .stage_mask(vk::PipelineStageFlags::FRAGMENT_SHADER, vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT)
+            .access_mask(vk::AccessFlags::SHADER_READ, vk::AccessFlags::COLOR_ATTACHMENT_WRITE)
+            .flags(vk::DependencyFlags::BY_REGION);
+
+        let dependency1 = SubpassDependencyCI::new(0, vk::SUBPASS_EXTERNAL)
+            .stage_mask(vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT, vk::PipelineStageFlags::FRAGMENT_SHADER)
+            .access_mask(vk::AccessFlags::COLOR_ATTACHMENT_WRITE, vk::AccessFlags::SHADER_READ)
+            .flags(vk::DependencyFlags::BY_REGION);
+
+        let render_pass = RenderPassCI::new()
+            .add_attachment(color_attachment)
+            .add_subpass(subpass_description)
+            .add_dependency(dependency0)
+            .add_dependency(dependency1)
+            .build_with_attachments(device)?;
+
+        let framebuffer = FramebufferCI::new_2d(render_pass.handle, dimension)
+            .add_attachment(view)
+            .build(device)?;
+
+        let (pipeline, pipeline_layout) = prepare_scene_pipeline(device, render_pass.handle)?;
+
+        let result = SceneStaff { image, view, sampler, render_pass, framebuffer, pipeline, pipeline_layout };
+        Ok(result)
+    }
+
+    fn descriptor(&self) -> vk::DescriptorImageInfo {
+        vk::DescriptorImageInfo {
+            sampler: self.sampler,
+            image_view: self.view,
+            image_layout: vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+        }
+    }
+
+    fn discard_by(self, device: &mut VkDevice) -> VkResult<()> {
+
+        device.discard(self.pipeline);
+        self.pipeline_layout.discard_by(device);
+
+        device.discard(self.framebuffer);
+        device.discard(self.render_pass);
+        device.discard(self.sampler);
+        device.discard(self.view);
+        device.vma_discard(self.image)
+    }
+}
+
+fn setup_renderpass(device: &VkDevice, swapchain: &VkSwapchain) -> VkResult<RenderPass> {
+
+    use vkbase::ci::pipeline::RenderPassCI;
+    use vkbase::ci::pipeline::{AttachmentDescCI, SubpassDescCI, SubpassDependencyCI};
+
+    let color_attachment = AttachmentDescCI::new(swapchain.backend_format)
+        .op(vk::AttachmentLoadOp::CLEAR, vk::AttachmentStoreOp::STORE)
+        .layout(vk::ImageLayout::UNDEFINED, vk::ImageLayout::PRESENT_SRC_KHR);
+
+    let depth_attachment = AttachmentDescCI::new(device.phy.depth_format)
+        .op(vk::AttachmentLoadOp::CLEAR, vk::AttachmentStoreOp::DONT_CARE)
+        .layout(vk::ImageLayout::UNDEFINED, vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL);
+
+    let subpass_description = SubpassDescCI::new(vk::PipelineBindPoint::GRAPHICS)
+        .add_color_attachment(0, vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL)
+        .set_depth_stencil_attachment(1, vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL);
+
+    let dependency0 = SubpassDependencyCI::new(vk::SUBPASS_EXTERNAL, 0)
+        .stage_mask(vk::PipelineStageFlags::BOTTOM_OF_PIPE, vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT)
+        .access_mask(vk::AccessFlags::MEMORY_READ, vk::AccessFlags::COLOR_ATTACHMENT_READ | vk::AccessFlags::COLOR_ATTACHMENT_WRITE)
+        .flags(vk::DependencyFlags::BY_REGION);
+
+    let dependency1 = SubpassDependencyCI::new(0, vk::SUBPASS_EXTERNAL)
+        .stage_mask(vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT, vk::PipelineStageFlags::BOTTOM_OF_PIPE)
+        .access_mask(vk::AccessFlags::COLOR_ATTACHMENT_READ | vk::AccessFlags::COLOR_ATTACHMENT_WRITE, vk::AccessFlags::MEMORY_READ)
+        .flags(vk::DependencyFlags::BY_REGION);
+
+    let render_pass = RenderPassCI::new()
+        .add_attachment(color_attachment)
+        .add_attachment(depth_attachment)
+        .add_subpass(subpass_description)
+        .add_dependency(dependency0)
+        .add_dependency(dependency1)
+        .build_with_attachments(device)?;
+
+    Ok(render_pass)
+}
+
+fn prepare_scene_pipeline(device: &VkDevice, render_pass: vk::RenderPass) -> VkResult<(vk::Pipeline, PipelineLayout)> {
+
+    use vkbase::ci::pipeline::*;
+
+    let viewport_state = ViewportSCI::new()
+        .add_viewport(vk::Viewport::default())
+        .add_scissor(vk::Rect2D::default());
+
+    let rasterization_state = RasterizationSCI::new()
+        .polygon(vk::PolygonMode::FILL)
+        .cull_face(vk::CullModeFlags::NONE, vk::FrontFace::CLOCKWISE);
+
+    let blend_state = ColorBlendSCI::new()
+        .add_attachment(BlendAttachmentSCI::new());
+
+    let dynamic_state = DynamicSCI::new()
+        .add_dynamic(vk::DynamicState::VIEWPORT)
+        .add_dynamic(vk::DynamicState::SCISSOR);
+
+    // shaders
+    use vkbase::ci::shader::{ShaderModuleCI, ShaderStageCI};
+
+    let mut shader_compiler = vkbase::utils::shaderc::VkShaderCompiler::new()?;
+    let vert_codes = shader_compiler.compile_from_path(Path::new(SHADER_VERTEX_PATH), shaderc::ShaderKind::Vertex, "[Vertex Shader]", "main")?;
+    let frag_codes = shader_compiler.compile_from_path(Path::new(SHADER_FRAGMENT_PATH), shaderc::ShaderKind::Fragment, "[Fragment Shader]", "main")?;
+
+    let vert_module = ShaderModuleCI::new(vert_codes).build(device)?;
+    let frag_module = ShaderModuleCI::new(frag_codes).build(device)?;
+
+    // Pipeline Layout.
+    let push_ranges = PushConstantRanges::new()
+        .push::<SceneParams>(vk::ShaderStageFlags::FRAGMENT)
+        .build(device)?;
+    let layout = PipelineLayoutCI::new()
+        .add_push_constant_ranges(&push_ranges)
+        .build_with_ranges(device)?;
+
+    // Pipeline.
+    let mut pipeline_ci = GraphicsPipelineCI::new(render_pass, layout.handle);
+
+    let shaders = [
+        ShaderStageCI::new(vk::ShaderStageFlags::VERTEX, vert_module),
+        ShaderStageCI::new(vk::ShaderStageFlags::FRAGMENT, frag_module),
+    ];
+    pipeline_ci.set_shaders(&shaders);
+    pipeline_ci.set_viewport(viewport_state);
+    pipeline_ci.set_rasterization(rasterization_state);
+    pipeline_ci.set_color_blend(blend_state);
+    pipeline_ci.set_dynamic(dynamic_state);
+
+    let pipeline = device.build(&pipeline_ci)?;
+
+    // Destroy shader module.
+    device.discard(vert_module);
+    device.discard(frag_module);
+
+    Ok((pipeline, layout))
+}
@@ -0,0 +1,45 @@
+//!
+//! Vulkan Example - Bloom
+//!
+//! Render a HDR scene offscreen, and add the blurred bright pixels back by `vkbase::bloom::BloomPass` before tone mapping.
+//!
+
+mod example;
+
+const WINDOW_WIDTH : u32 = 1280;
+const WINDOW_HEIGHT: u32 = 720;
+const WINDOW_TITLE: &'static str = "Vulkan Example - Bloom";
+
+fn main() {
+
+    use vkbase::{WindowConfig, WindowContext};
+    use vkbase::context::VulkanContext;
+    use vkbase::ProcPipeline;
+
+    vkbase::utils::path::set_asset_root(vkexamples::ASSET_ROOT);
+
+    let mut win_config = WindowConfig::default();
+    win_config.dimension.width  = WINDOW_WIDTH;
+    win_config.dimension.height = WINDOW_HEIGHT;
+    win_config.title = WINDOW_TITLE.to_string();
+    win_config.is_cursor_hide = true; // hide cursor.
+    win_config.is_cursor_grap = true; // grap cursor operations.
+
+    let window = WindowContext::new(win_config)
+        .expect("Error when creating Window Context");
+
+    let mut vk_context = VulkanContext::new(&window)
+        .build().expect("Error when creating Vulkan Context");
+
+    let app = example::VulkanExample::new(&mut vk_context)
+        .expect("Error when initializing application");
+
+    let entry = ProcPipeline::new(window, vk_context).unwrap();
+
+    match entry.launch(app) {
+        | Ok(_) => {},
+        | Err(e) => {
+            eprintln!("{}", e)
+        }
+    }
+}
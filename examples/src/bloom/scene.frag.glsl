@@ -0,0 +1,42 @@
+#version 450
+
+layout (push_constant) uniform SceneParams {
+	// the ratio of width to height of screen.
+	float aspect;
+} params;
+
+layout (location = 0) in vec2 inUV;
+
+layout (location = 0) out vec4 outFragColor;
+
+// the emissive discs, where xy is the center, z is the radius and w is the HDR intensity.
+const vec4 LIGHTS[4] = vec4[](
+	vec4(0.25, 0.35, 0.05, 4.0),
+	vec4(0.70, 0.30, 0.08, 2.0),
+	vec4(0.45, 0.70, 0.03, 8.0),
+	vec4(0.80, 0.75, 0.06, 1.2)
+);
+
+const vec3 COLORS[4] = vec3[](
+	vec3(1.0, 0.5, 0.2),
+	vec3(0.3, 0.6, 1.0),
+	vec3(1.0, 1.0, 1.0),
+	vec3(0.4, 1.0, 0.4)
+);
+
+void main() {
+
+	vec2 position = vec2(inUV.x * params.aspect, inUV.y);
+
+	// a dim background, which stays below the default threshold.
+	vec3 color = mix(vec3(0.02, 0.02, 0.05), vec3(0.1, 0.08, 0.12), inUV.y);
+
+	for (int i = 0; i < 4; i++) {
+		vec2 center = vec2(LIGHTS[i].x * params.aspect, LIGHTS[i].y);
+		if (length(position - center) < LIGHTS[i].z) {
+			color = COLORS[i] * LIGHTS[i].w;
+		}
+	}
+
+	outFragColor = vec4(color, 1.0);
+}
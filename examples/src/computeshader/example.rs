@@ -0,0 +1,528 @@
+
+use ash::vk;
+
+use std::path::Path;
+
+use vkbase::context::{VulkanContext, VkDevice, VkSwapchain};
+use vkbase::ci::VkObjectBuildableCI;
+use vkbase::ci::pipeline::RenderPass;
+use vkbase::ci::vma::{VmaImage, VmaAllocationCI};
+use vkbase::utils::color::VkColor;
+use vkbase::ui::{TextInfo, TextType, TextHAlign};
+use vkbase::FrameAction;
+use vkbase::vkuint;
+use vkbase::{VkResult, VkErrorKind};
+
+use vkexamples::VkExampleBackend;
+
+const SHADER_COMPUTE_PATH : &'static str = "examples/src/computeshader/generate.comp.glsl";
+const SHADER_VERTEX_PATH  : &'static str = "examples/src/computeshader/texture.vert.glsl";
+const SHADER_FRAGMENT_PATH: &'static str = "examples/src/computeshader/texture.frag.glsl";
+
+/// the dimension of generated texture.
+///
+/// Its texels are dispatched in one dimension, so 1024 x 1024 texels by 64 local size need 16384 workgroups,
+/// which is within the minimum `maxComputeWorkGroupCount` guaranteed by specification(65535).
+const TEXTURE_DIMENSION: vk::Extent2D = vk::Extent2D { width: 1024, height: 1024 };
+const TEXTURE_FORMAT: vk::Format = vk::Format::R8G8B8A8_UNORM;
+
+pub struct VulkanExample {
+
+    backend: VkExampleBackend,
+
+    texture: GeneratedTexture,
+
+    pipelines: PipelineStaff,
+    descriptors: DescriptorStaff,
+
+    /// the description of the dispatch used to generate the texture.
+    dispatch_info: String,
+}
+
+impl VulkanExample {
+
+    pub fn new(context: &mut VulkanContext) -> VkResult<VulkanExample> {
+
+        let device = &mut context.device;
+        let swapchain = &context.swapchain;
+
+        let render_pass = setup_renderpass(device, &context.swapchain)?;
+        let backend = VkExampleBackend::new(device, swapchain, render_pass)?;
+
+        let (texture, dispatch_info) = GeneratedTexture::generate(device)?;
+
+        let descriptors = setup_descriptor(device, &texture)?;
+
+        let pipelines = prepare_pipelines(device, &backend, descriptors.layout)?;
+
+        let target = VulkanExample {
+            backend, texture, descriptors, pipelines, dispatch_info,
+        };
+        Ok(target)
+    }
+}
+
+impl vkbase::RenderWorkflow for VulkanExample {
+
+    fn init(&mut self, device: &VkDevice) -> VkResult<()> {
+
+        self.backend.set_basic_ui(device, super::WINDOW_TITLE)?;
+
+        let dispatch_text = TextInfo {
+            content: self.dispatch_info.clone(),
+            scale: 23.0,
+            align: TextHAlign::Left,
+            color: VkColor::WHITE,
+            location: vk::Offset2D { x: 5, y: 140 },
+            pivot: (0.0, 0.0),
+            shadow: None,
+            r#type: TextType::Static,
+        };
+        self.backend.add_text(dispatch_text)?;
+
+        self.record_commands(device, self.backend.dimension)?;
+        Ok(())
+    }
+
+    fn render_frame(&mut self, device: &mut VkDevice, device_available: vk::Fence, await_present: vk::Semaphore, image_index: usize, _delta_time: f32) -> VkResult<vk::Semaphore> {
+
+        let submit_ci = vkbase::ci::device::SubmitCI::new()
+            .add_wait(vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT, await_present)
+            .add_command(self.backend.commands[image_index])
+            .add_signal(self.backend.await_rendering);
+
+        device.submit(submit_ci, device.logic.queues.graphics.handle, Some(device_available))?;
+
+        Ok(self.backend.await_rendering)
+    }
+
+    fn swapchain_reload(&mut self, device: &mut VkDevice, new_chain: &VkSwapchain) -> VkResult<()> {
+
+        // recreate the resources.
+        device.discard(self.pipelines.pipeline);
+        device.discard(self.pipelines.layout);
+
+        let render_pass = setup_renderpass(device, new_chain)?;
+        self.backend.swapchain_reload(device, new_chain, render_pass)?;
+        self.pipelines = prepare_pipelines(device, &self.backend, self.descriptors.layout)?;
+
+        self.record_commands(device, self.backend.dimension)?;
+
+        Ok(())
+    }
+
+    fn receive_input(&mut self, inputer: &vkbase::EventController, _delta_time: f32) -> FrameAction {
+
+        if inputer.is_key_active() && inputer.key.is_key_pressed(winit::VirtualKeyCode::Escape) {
+            return FrameAction::Terminal
+        }
+
+        self.backend.update_fps_text(inputer);
+        self.backend.ui_action()
+    }
+
+    fn rerecord_commands(&mut self, device: &mut VkDevice) -> VkResult<()> {
+
+        self.backend.update_glyph_atlas(device)?;
+        self.record_commands(device, self.backend.dimension)
+    }
+
+    fn deinit(self, device: &mut VkDevice) -> VkResult<()> {
+
+        device.discard(self.descriptors.layout);
+        device.discard(self.descriptors.pool);
+
+        device.discard(self.pipelines.pipeline);
+        device.discard(self.pipelines.layout);
+
+        self.texture.discard_by(device)?;
+        self.backend.discard_by(device)
+    }
+}
+
+impl VulkanExample {
+
+    fn record_commands(&self, device: &VkDevice, dimension: vk::Extent2D) -> VkResult<()> {
+
+        let viewport = vk::Viewport {
+            x: 0.0, y: 0.0,
+            width: dimension.width as f32, height: dimension.height as f32,
+            min_depth: 0.0, max_depth: 1.0,
+        };
+
+        let scissor = vk::Rect2D {
+            extent: dimension.clone(),
+            offset: vk::Offset2D { x: 0, y: 0 },
+        };
+
+        for (i, &command) in self.backend.commands.iter().enumerate() {
+
+            use vkbase::command::{VkCmdRecorder, CmdGraphicsApi, IGraphics};
+            use vkbase::ci::pipeline::RenderPassBI;
+
+            let recorder: VkCmdRecorder<IGraphics> = VkCmdRecorder::new(&device.logic, command);
+
+            let render_pass_bi = RenderPassBI::with_pass(self.backend.render_pass, self.backend.framebuffers[i])
+                .render_extent(dimension)
+                .set_clear_values(self.backend.clear_values());
+
+            // the full screen triangle is generated in vertex shader, so no vertex buffer is bound.
+            recorder.begin_record()?
+                .begin_render_pass(render_pass_bi)
+                .set_viewport(0, &[viewport])
+                .set_scissor(0, &[scissor])
+                .bind_pipeline(self.pipelines.pipeline)
+                .bind_descriptor_sets(self.pipelines.layout, 0, &[self.descriptors.set], &[])
+                .draw(3, 1, 0, 0);
+
+            self.backend.record_ui_command(&recorder, i);
+
+            recorder.end_render_pass()
+                .end_record()?;
+        }
+
+        Ok(())
+    }
+}
+
+
+
+/// The texture written by the compute shader, and sampled by the fragment shader.
+struct GeneratedTexture {
+    image: VmaImage,
+    view: vk::ImageView,
+    sampler: vk::Sampler,
+}
+
+/// The push constants of generate.comp.glsl.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+struct GenerateConsts {
+    extent: [vkuint; 2],
+}
+
+impl GeneratedTexture {
+
+    /// Generate the texture by dispatching the compute shader once, and return it with the description of the dispatch.
+    ///
+    /// The texture is in `vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL` layout and owned by the graphics queue family after return.
+    fn generate(device: &mut VkDevice) -> VkResult<(GeneratedTexture, String)> {
+
+        use vkbase::ci::image::{ImageCI, ImageViewCI, SamplerCI, ImageBarrierCI};
+        use vkbase::ci::descriptor::{DescriptorPoolCI, DescriptorSetLayoutCI};
+        use vkbase::ci::descriptor::{DescriptorSetAI, DescriptorImageSetWI, DescriptorSetsUpdateCI};
+        use vkbase::ci::pipeline::{PipelineLayoutCI, PushConstantRanges, ComputePipelineCI};
+        use vkbase::ci::shader::{ShaderModuleCI, ShaderStageCI};
+        use vkbase::ci::command::{CommandPoolCI, CommandBufferAI};
+        use vkbase::ci::sync::FenceCI;
+        use vkbase::ci::device::SubmitCI;
+        use vkbase::command::{VkCmdRecorder, CmdComputeApi, ICompute};
+        use vkbase::utils::compute::{LocalSizeSpecialization, group_count};
+        use vkbase::utils::time::VkTimeDuration;
+
+        // the image is written as storage image in compute shader, and then sampled in fragment shader.
+        let image = {
+            let image_ci = ImageCI::new_2d(TEXTURE_FORMAT, TEXTURE_DIMENSION)
+                .usages(vk::ImageUsageFlags::STORAGE | vk::ImageUsageFlags::SAMPLED);
+            let allocation_ci = VmaAllocationCI::new(vma::MemoryUsage::GpuOnly, vk::MemoryPropertyFlags::DEVICE_LOCAL);
+            let image_allocation = device.vma.create_image(image_ci.as_ref(), allocation_ci.as_ref())
+                .map_err(VkErrorKind::Vma)?;
+            VmaImage::from(image_allocation)
+        };
+
+        let sub_range = vk::ImageSubresourceRange {
+            aspect_mask: vk::ImageAspectFlags::COLOR,
+            base_mip_level: 0,
+            level_count: 1,
+            base_array_layer: 0,
+            layer_count: 1,
+        };
+
+        let view = ImageViewCI::new(image.handle, vk::ImageViewType::TYPE_2D, TEXTURE_FORMAT)
+            .sub_range(sub_range)
+            .build(device)?;
+
+        let sampler = SamplerCI::new()
+            .address(vk::SamplerAddressMode::CLAMP_TO_EDGE, vk::SamplerAddressMode::CLAMP_TO_EDGE, vk::SamplerAddressMode::CLAMP_TO_EDGE)
+            .build(device)?;
+
+        // the compute resources are only used once, so they are destroyed after the dispatch.
+        let mut shader_compiler = vkbase::utils::shaderc::VkShaderCompiler::new()?;
+        let comp_codes = shader_compiler.compile_from_path(Path::new(SHADER_COMPUTE_PATH), shaderc::ShaderKind::Compute, "[Compute Shader]", "main")?;
+        let comp_module_ci = ShaderModuleCI::new(comp_codes);
+        // layout (set = 0, binding = 0, rgba8) uniform writeonly image2D outputImage;
+        let reflection = comp_module_ci.reflect()?;
+        let comp_module = comp_module_ci.build(device)?;
+
+        let descriptor_pool = DescriptorPoolCI::new(1)
+            .add_descriptor(vk::DescriptorType::STORAGE_IMAGE, 1)
+            .build(device)?;
+        let set_layout = DescriptorSetLayoutCI::from_reflection(&reflection, 0)
+            .build(device)?;
+        let descriptor_set = DescriptorSetAI::new(descriptor_pool)
+            .add_set_layout(set_layout)
+            .build(device)?.remove(0);
+
+        // storage images are accessed in general layout.
+        let image_write = DescriptorImageSetWI::new(descriptor_set, 0, vk::DescriptorType::STORAGE_IMAGE)
+            .add_image(vk::DescriptorImageInfo {
+                sampler      : vk::Sampler::null(),
+                image_view   : view,
+                image_layout : vk::ImageLayout::GENERAL,
+            });
+        DescriptorSetsUpdateCI::new()
+            .add_write(&image_write)
+            .update(device);
+
+        let push_ranges = PushConstantRanges::new()
+            .push::<GenerateConsts>(vk::ShaderStageFlags::COMPUTE)
+            .build(device)?;
+        let pipeline_layout = PipelineLayoutCI::new()
+            .add_set_layout(set_layout)
+            .add_push_constant_ranges(&push_ranges)
+            .build_with_ranges(device)?;
+
+        // the specialization must outlive the pipeline creation.
+        let specialization = LocalSizeSpecialization::from_device(&device.phy);
+        let comp_stage = ShaderStageCI::new(vk::ShaderStageFlags::COMPUTE, comp_module)
+            .reflection(&reflection)
+            .specialization(specialization.info());
+        let pipeline = device.build(&ComputePipelineCI::new(&comp_stage, pipeline_layout.handle))?;
+        device.discard(comp_module);
+
+        let local_size = specialization.local_size()[0];
+        let texel_count = TEXTURE_DIMENSION.width * TEXTURE_DIMENSION.height;
+        let groups = group_count(texel_count, local_size, device.phy.max_compute_work_group_count()[0])?;
+
+        // the ownership of image must be transferred if the compute queue comes from another queue family.
+        let compute_queue = device.logic.queues.compute.clone();
+        let graphics_queue = device.logic.queues.graphics.clone();
+        let is_transfer_required = compute_queue.family_index != graphics_queue.family_index;
+
+        let to_general_barrier = ImageBarrierCI::new(image.handle, sub_range)
+            .access_mask(vk::AccessFlags::empty(), vk::AccessFlags::SHADER_WRITE)
+            .layout(vk::ImageLayout::UNDEFINED, vk::ImageLayout::GENERAL);
+        let to_sampled_barrier = ImageBarrierCI::new(image.handle, sub_range)
+            .access_mask(vk::AccessFlags::SHADER_WRITE, vk::AccessFlags::SHADER_READ)
+            .layout(vk::ImageLayout::GENERAL, vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL);
+        let to_sampled_barrier = if is_transfer_required {
+            to_sampled_barrier.queue_family_index(compute_queue.family_index, graphics_queue.family_index)
+        } else {
+            to_sampled_barrier
+        };
+
+        let command_pool = CommandPoolCI::new(compute_queue.family_index)
+            .flags(vk::CommandPoolCreateFlags::TRANSIENT)
+            .build(device)?;
+        let command = CommandBufferAI::new(command_pool, 1)
+            .build(device)?.remove(0);
+
+        let mut recorder: VkCmdRecorder<ICompute> = VkCmdRecorder::new(&device.logic, command);
+        recorder.set_usage(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT);
+
+        let push_consts = GenerateConsts { extent: [TEXTURE_DIMENSION.width, TEXTURE_DIMENSION.height] };
+
+        recorder.begin_record()?
+            .image_pipeline_barrier(vk::PipelineStageFlags::TOP_OF_PIPE, vk::PipelineStageFlags::COMPUTE_SHADER, vk::DependencyFlags::empty(), &[to_general_barrier.into()])
+            .bind_pipeline(pipeline)
+            .bind_descriptor_sets(pipeline_layout.handle, 0, &[descriptor_set], &[])
+            .push_constant_data(&pipeline_layout, vk::ShaderStageFlags::COMPUTE, 0, &push_consts)
+            .dispatch(groups, 1, 1);
+
+        if is_transfer_required {
+            recorder.release_ownership(vk::PipelineStageFlags::COMPUTE_SHADER, &[], &[to_sampled_barrier.clone().into()]);
+        } else {
+            recorder.image_pipeline_barrier(vk::PipelineStageFlags::COMPUTE_SHADER, vk::PipelineStageFlags::ALL_COMMANDS, vk::DependencyFlags::empty(), &[to_sampled_barrier.clone().into()]);
+        }
+        recorder.end_record()?;
+
+        let fence = FenceCI::new(false).build(device)?;
+        let submit_ci = SubmitCI::new()
+            .add_command(command);
+        device.submit_compute(submit_ci, Some(fence))?;
+        device.wait(fence, VkTimeDuration::Infinite)?;
+
+        // the acquire operation is submitted after the release operation completes, which is ensured by the fence.
+        if is_transfer_required {
+            device.submit_once(&graphics_queue, |recorder| {
+                recorder.acquire_ownership(vk::PipelineStageFlags::FRAGMENT_SHADER, &[], &[to_sampled_barrier.into()]);
+                Ok(())
+            })?;
+        }
+
+        device.discard(fence);
+        device.discard(command_pool);
+        device.discard(pipeline);
+        pipeline_layout.discard_by(device);
+        device.discard(set_layout);
+        device.discard(descriptor_pool);
+
+        let dispatch_info = format!("Dispatch: {} workgroups of local size {} ({} queue)",
+            groups, local_size, if device.logic.queues.is_compute_async() { "async compute" } else { "graphics" });
+
+        let texture = GeneratedTexture { image, view, sampler };
+        Ok((texture, dispatch_info))
+    }
+
+    fn discard_by(self, device: &mut VkDevice) -> VkResult<()> {
+
+        device.discard(self.sampler);
+        device.discard(self.view);
+        device.vma_discard(self.image)
+    }
+}
+
+struct DescriptorStaff {
+    pool   : vk::DescriptorPool,
+    set    : vk::DescriptorSet,
+    layout : vk::DescriptorSetLayout,
+}
+
+fn setup_descriptor(device: &VkDevice, texture: &GeneratedTexture) -> VkResult<DescriptorStaff> {
+
+    use vkbase::ci::descriptor::{DescriptorPoolCI, DescriptorSetLayoutCI};
+    use vkbase::ci::descriptor::{DescriptorSetAI, DescriptorImageSetWI, DescriptorSetsUpdateCI};
+    use vkbase::ci::shader::ShaderModuleCI;
+
+    let descriptor_pool = DescriptorPoolCI::new(1)
+        .add_descriptor(vk::DescriptorType::COMBINED_IMAGE_SAMPLER, 1)
+        .build(device)?;
+
+    // the binding is reflected from texture.frag.glsl:
+    // layout (set = 0, binding = 0) uniform sampler2D samplerColor;
+    let mut shader_compiler = vkbase::utils::shaderc::VkShaderCompiler::new()?;
+    let frag_codes = shader_compiler.compile_from_path(Path::new(SHADER_FRAGMENT_PATH), shaderc::ShaderKind::Fragment, "[Fragment Shader]", "main")?;
+    let reflection = ShaderModuleCI::new(frag_codes).reflect()?;
+    let set_layout = DescriptorSetLayoutCI::from_reflection(&reflection, 0)
+        .build(device)?;
+
+    let descriptor_set = DescriptorSetAI::new(descriptor_pool)
+        .add_set_layout(set_layout)
+        .build(device)?.remove(0);
+
+    let sampler_write = DescriptorImageSetWI::new(descriptor_set, 0, vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+        .add_image(vk::DescriptorImageInfo {
+            sampler      : texture.sampler,
+            image_view   : texture.view,
+            image_layout : vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+        });
+
+    DescriptorSetsUpdateCI::new()
+        .add_write(&sampler_write)
+        .update(device);
+
+    let result = DescriptorStaff {
+        pool: descriptor_pool,
+        set : descriptor_set,
+        layout: set_layout,
+    };
+    Ok(result)
+}
+
+fn setup_renderpass(device: &VkDevice, swapchain: &VkSwapchain) -> VkResult<RenderPass> {
+
+    use vkbase::ci::pipeline::RenderPassCI;
+    use vkbase::ci::pipeline::{AttachmentDescCI, SubpassDescCI, SubpassDependencyCI};
+
+    let color_attachment = AttachmentDescCI::new(swapchain.backend_format)
+        .op(vk::AttachmentLoadOp::CLEAR, vk::AttachmentStoreOp::STORE)
+        .layout(vk::ImageLayout::UNDEFINED, vk::ImageLayout::PRESENT_SRC_KHR);
+
+    let depth_attachment = AttachmentDescCI::new(device.phy.depth_format)
+        .op(vk::AttachmentLoadOp::CLEAR, vk::AttachmentStoreOp::DONT_CARE)
+        .layout(vk::ImageLayout::UNDEFINED, vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL);
+
+    let subpass_description = SubpassDescCI::new(vk::PipelineBindPoint::GRAPHICS)
+        .add_color_attachment(0, vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL)
+        .set_depth_stencil_attachment(1, vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL);
+
+    let dependency0 = SubpassDependencyCI::new(vk::SUBPASS_EXTERNAL, 0)
+        .stage_mask(vk::PipelineStageFlags::BOTTOM_OF_PIPE, vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT)
+        .access_mask(vk::AccessFlags::MEMORY_READ, vk::AccessFlags::COLOR_ATTACHMENT_READ | vk::AccessFlags::COLOR_ATTACHMENT_WRITE)
+        .flags(vk::DependencyFlags::BY_REGION);
+
+    let dependency1 = SubpassDependencyCI::new(0, vk::SUBPASS_EXTERNAL)
+        .stage_mask(vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT, vk::PipelineStageFlags::BOTTOM_OF_PIPE)
+        .access_mask(vk::AccessFlags::COLOR_ATTACHMENT_READ | vk::AccessFlags::COLOR_ATTACHMENT_WRITE, vk::AccessFlags::MEMORY_READ)
+        .flags(vk::DependencyFlags::BY_REGION);
+
+    let render_pass = RenderPassCI::new()
+        .add_attachment(color_attachment)
+        .add_attachment(depth_attachment)
+        .add_subpass(subpass_description)
+        .add_dependency(dependency0)
+        .add_dependency(dependency1)
+        .build_with_attachments(device)?;
+
+    Ok(render_pass)
+}
+
+
+struct PipelineStaff {
+    pipeline: vk::Pipeline,
+    layout: vk::PipelineLayout,
+}
+
+fn prepare_pipelines(device: &VkDevice, backend: &VkExampleBackend, set_layout: vk::DescriptorSetLayout) -> VkResult<PipelineStaff> {
+
+    use vkbase::ci::pipeline::*;
+
+    let viewport_state = ViewportSCI::new()
+        .add_viewport(vk::Viewport::default())
+        .add_scissor(vk::Rect2D::default());
+
+    let rasterization_state = RasterizationSCI::new()
+        .polygon(vk::PolygonMode::FILL)
+        .cull_face(vk::CullModeFlags::NONE, vk::FrontFace::CLOCKWISE);
+
+    let blend_attachment = BlendAttachmentSCI::new();
+    let blend_state = ColorBlendSCI::new()
+        .add_attachment(blend_attachment);
+
+    // follow the depth configuration of backend, so that the depth test matches the cleared depth value.
+    let depth_stencil_state = backend.depth_stencil_state();
+
+    let dynamic_state = DynamicSCI::new()
+        .add_dynamic(vk::DynamicState::VIEWPORT)
+        .add_dynamic(vk::DynamicState::SCISSOR);
+
+    // shaders
+    use vkbase::ci::shader::{ShaderModuleCI, ShaderStageCI};
+
+    let mut shader_compiler = vkbase::utils::shaderc::VkShaderCompiler::new()?;
+    let vert_codes = shader_compiler.compile_from_path(Path::new(SHADER_VERTEX_PATH), shaderc::ShaderKind::Vertex, "[Vertex Shader]", "main")?;
+    let frag_codes = shader_compiler.compile_from_path(Path::new(SHADER_FRAGMENT_PATH), shaderc::ShaderKind::Fragment, "[Fragment Shader]", "main")?;
+
+    let vert_module = ShaderModuleCI::new(vert_codes).build(device)?;
+    let frag_module = ShaderModuleCI::new(frag_codes).build(device)?;
+
+    // Pipeline Layout.
+    let layout = PipelineLayoutCI::new()
+        .add_set_layout(set_layout)
+        .build(device)?;
+
+    // Pipeline.
+    let mut pipeline_ci = GraphicsPipelineCI::new(backend.render_pass.handle, layout);
+
+    let shaders = [
+        ShaderStageCI::new(vk::ShaderStageFlags::VERTEX, vert_module),
+        ShaderStageCI::new(vk::ShaderStageFlags::FRAGMENT, frag_module),
+    ];
+    // the vertices are generated by gl_VertexIndex, so the vertex input state is empty.
+    pipeline_ci.set_shaders(&shaders);
+    pipeline_ci.set_vertex_input(VertexInputSCI::new());
+    pipeline_ci.set_viewport(viewport_state);
+    pipeline_ci.set_depth_stencil(depth_stencil_state);
+    pipeline_ci.set_rasterization(rasterization_state);
+    pipeline_ci.set_color_blend(blend_state);
+    pipeline_ci.set_dynamic(dynamic_state);
+
+    let pipeline = device.build(&pipeline_ci)?;
+
+    // Destroy shader module.
+    device.discard(vert_module);
+    device.discard(frag_module);
+
+    let result = PipelineStaff { pipeline, layout };
+    Ok(result)
+}
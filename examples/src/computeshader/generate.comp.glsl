@@ -0,0 +1,31 @@
+#version 450
+
+// the local size is given by specialization constants(see vkbase::utils::compute::LocalSizeSpecialization).
+layout (local_size_x_id = 0, local_size_y_id = 1, local_size_z_id = 2) in;
+
+layout (set = 0, binding = 0, rgba8) uniform writeonly image2D outputImage;
+
+layout (push_constant) uniform PushConsts {
+	uvec2 extent;
+} pushConsts;
+
+void main() {
+
+	// the texels are dispatched in one dimension, row by row.
+	uint index = gl_GlobalInvocationID.x;
+
+	// the last workgroup may contain invocations outside the image.
+	if (index >= pushConsts.extent.x * pushConsts.extent.y) {
+		return;
+	}
+
+	ivec2 texel = ivec2(index % pushConsts.extent.x, index / pushConsts.extent.x);
+	vec2 uv = (vec2(texel) + 0.5) / vec2(pushConsts.extent);
+
+	// concentric rings over a checkerboard.
+	float rings = 0.5 + 0.5 * cos(length(uv - 0.5) * 60.0);
+	float checker = mod(floor(uv.x * 8.0) + floor(uv.y * 8.0), 2.0);
+	vec3 color = mix(vec3(0.1, 0.3, 0.6), vec3(0.9, 0.6, 0.2), rings) * (0.6 + 0.4 * checker);
+
+	imageStore(outputImage, texel, vec4(color, 1.0));
+}
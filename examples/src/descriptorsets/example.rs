@@ -9,6 +9,7 @@ use arrayvec::ArrayVec;
 
 use vkbase::context::{VkDevice, VkSwapchain};
 use vkbase::ci::VkObjectBuildableCI;
+use vkbase::ci::pipeline::RenderPass;
 use vkbase::ci::buffer::BufferCI;
 use vkbase::ci::vma::{VmaBuffer, VmaAllocationCI};
 use vkbase::gltf::VkglTFModel;
@@ -25,6 +26,8 @@ const FRAGMENT_SHADER_SOURCE_PATH: &'static str = "examples/src/descriptorsets/c
 
 const CUBE_MODEL_PATH: &'static str = "assets/models/cube.gltf";
 const CUBE_COUNT: usize = 2;
+/// The fraction of depth range used by the last cube, which is rendered like a gizmo in front of the other cube.
+const GIZMO_DEPTH_FRACTION: f32 = 0.1;
 const CUBE_TEXTURE_PATHS: [&'static str; CUBE_COUNT] = [
     "assets/textures/crate01_color_height_rgba.ktx",
     "assets/textures/crate02_color_height_rgba.ktx",
@@ -63,23 +66,23 @@ impl VulkanExample {
         let swapchain = &context.swapchain;
         let dimension = swapchain.dimension;
 
+        let render_pass = setup_renderpass(device, &context.swapchain)?;
+        let backend = VkExampleBackend::new(device, swapchain, render_pass)?;
+
         let mut camera = FlightCamera::new()
             .place_at(Vec3F::new(0.0, 0.0, 5.0))
             .view_distance(0.1, 512.0)
             .screen_aspect_ratio(dimension.width as f32 / dimension.height as f32)
+            .depth_projection(backend.depth_config().projection)
             .build();
         camera.set_move_speed(5.0);
 
-
-        let render_pass = setup_renderpass(device, &context.swapchain)?;
-        let backend = VkExampleBackend::new(device, swapchain, render_pass)?;
-
         let model = prepare_model(device)?;
 
         let mut cubes = prepare_uniform(device, &camera)?;
         let descriptors = setup_descriptor(device, &mut cubes, &model)?;
 
-        let pipelines = prepare_pipelines(device, &model, backend.render_pass, descriptors.layout)?;
+        let pipelines = prepare_pipelines(device, &model, &backend, descriptors.layout)?;
 
         let target = VulkanExample {
             backend, model, cubes, descriptors, pipelines, camera,
@@ -94,6 +97,11 @@ impl vkbase::RenderWorkflow for VulkanExample {
     fn init(&mut self, device: &VkDevice) -> VkResult<()> {
 
         self.backend.set_basic_ui(device, super::WINDOW_TITLE)?;
+        // plot the recent frame times under the fps text to spot frame hitches.
+        self.backend.add_fps_graph(vk::Rect2D {
+            offset: vk::Offset2D { x: 5, y: 120 },
+            extent: vk::Extent2D { width: 240, height: 60 },
+        })?;
 
         self.record_commands(device, self.backend.dimension)?;
 
@@ -103,6 +111,8 @@ impl vkbase::RenderWorkflow for VulkanExample {
     fn render_frame(&mut self, device: &mut VkDevice, device_available: vk::Fence, await_present: vk::Semaphore, image_index: usize, delta_time: f32) -> VkResult<vk::Semaphore> {
 
         self.update_uniforms(delta_time)?;
+        // the fence of this image has been waited, so its region of fps graph can be written now.
+        self.backend.update_ui_frame(image_index);
 
         let submit_ci = vkbase::ci::device::SubmitCI::new()
             .add_wait(vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT, await_present)
@@ -120,7 +130,7 @@ impl vkbase::RenderWorkflow for VulkanExample {
 
         let render_pass = setup_renderpass(device, new_chain)?;
         self.backend.swapchain_reload(device, new_chain, render_pass)?;
-        self.pipelines = prepare_pipelines(device, &self.model, self.backend.render_pass, self.descriptors.layout)?;
+        self.pipelines = prepare_pipelines(device, &self.model, &self.backend, self.descriptors.layout)?;
 
         self.record_commands(device, self.backend.dimension)?;
 
@@ -185,9 +195,9 @@ impl VulkanExample {
 
             let recorder: VkCmdRecorder<IGraphics> = VkCmdRecorder::new(&device.logic, command);
 
-            let render_pass_bi = RenderPassBI::new(self.backend.render_pass, self.backend.framebuffers[i])
+            let render_pass_bi = RenderPassBI::with_pass(self.backend.render_pass, self.backend.framebuffers[i])
                 .render_extent(dimension)
-                .set_clear_values(vkexamples::DEFAULT_CLEAR_VALUES.clone());
+                .set_clear_values(self.backend.clear_values());
 
             recorder.begin_record()?
                 .begin_render_pass(render_pass_bi)
@@ -198,6 +208,12 @@ impl VulkanExample {
             // Render cubes with separate descriptor sets.
             for j in 0..CUBE_COUNT {
 
+                // squeeze the depth of last cube into the near sub-range, so that it always appears on top.
+                if j == CUBE_COUNT - 1 {
+                    let (min_depth, max_depth) = self.backend.depth_config().near_depth_range(GIZMO_DEPTH_FRACTION);
+                    recorder.set_viewport_depth_range(viewport, min_depth, max_depth);
+                }
+
                 let render_params = vkbase::gltf::ModelRenderParams {
                     descriptor_set : self.cubes[j].descriptor_set,
                     pipeline_layout: self.pipelines.layout,
@@ -207,7 +223,7 @@ impl VulkanExample {
                 self.model.record_command(&recorder, &render_params);
             }
 
-            self.backend.ui_renderer.record_command(&recorder);
+            self.backend.record_ui_command(&recorder, i);
 
             recorder
                 .end_render_pass()
@@ -249,7 +265,7 @@ impl VulkanExample {
 pub fn prepare_model(device: &mut VkDevice) -> VkResult<VkglTFModel> {
 
     use vkbase::gltf::{GltfModelInfo, load_gltf};
-    use vkbase::gltf::{AttributeFlags, NodeAttachmentFlags};
+    use vkbase::gltf::{AttributeFlags, NodeAttachmentFlags, NormalGeneration};
 
     let model_info = GltfModelInfo {
         path: Path::new(CUBE_MODEL_PATH),
@@ -265,6 +281,7 @@ pub fn prepare_model(device: &mut VkDevice) -> VkResult<VkglTFModel> {
         // } dyn_node;
         node: NodeAttachmentFlags::TRANSFORM_MATRIX,
         transform: None,
+        normal_generation: NormalGeneration::None,
     };
 
     let model = load_gltf(device, model_info)?;
@@ -503,7 +520,7 @@ fn setup_descriptor(device: &VkDevice, cubes: &mut ArrayVec<[Cube; 2]>, model: &
     Ok(descriptors)
 }
 
-fn setup_renderpass(device: &VkDevice, swapchain: &VkSwapchain) -> VkResult<vk::RenderPass> {
+fn setup_renderpass(device: &VkDevice, swapchain: &VkSwapchain) -> VkResult<RenderPass> {
 
     use vkbase::ci::pipeline::RenderPassCI;
     use vkbase::ci::pipeline::{AttachmentDescCI, SubpassDescCI, SubpassDependencyCI};
@@ -536,12 +553,12 @@ fn setup_renderpass(device: &VkDevice, swapchain: &VkSwapchain) -> VkResult<vk::
         .add_subpass(subpass_description)
         .add_dependency(dependency0)
         .add_dependency(dependency1)
-        .build(device)?;
+        .build_with_attachments(device)?;
 
     Ok(render_pass)
 }
 
-fn prepare_pipelines(device: &VkDevice, model: &VkglTFModel, render_pass: vk::RenderPass, set_layout: vk::DescriptorSetLayout) -> VkResult<PipelineStaff> {
+fn prepare_pipelines(device: &VkDevice, model: &VkglTFModel, backend: &VkExampleBackend, set_layout: vk::DescriptorSetLayout) -> VkResult<PipelineStaff> {
 
     use vkbase::ci::pipeline::*;
 
@@ -557,8 +574,8 @@ fn prepare_pipelines(device: &VkDevice, model: &VkglTFModel, render_pass: vk::Re
     let blend_state = ColorBlendSCI::new()
         .add_attachment(blend_attachment);
 
-    let depth_stencil_state = DepthStencilSCI::new()
-        .depth_test(true, true, vk::CompareOp::LESS_OR_EQUAL);
+    // follow the depth configuration of backend, so that the gizmo range and depth test agree with each other.
+    let depth_stencil_state = backend.depth_stencil_state();
 
     let dynamic_state = DynamicSCI::new()
         .add_dynamic(vk::DynamicState::VIEWPORT)
@@ -582,7 +599,7 @@ fn prepare_pipelines(device: &VkDevice, model: &VkglTFModel, render_pass: vk::Re
     let frag_module = ShaderModuleCI::new(frag_codes).build(device)?;
 
     // Pipeline.
-    let mut pipeline_ci = GraphicsPipelineCI::new(render_pass, layout);
+    let mut pipeline_ci = GraphicsPipelineCI::new(backend.render_pass.handle, layout);
 
     let shaders = [
         ShaderStageCI::new(vk::ShaderStageFlags::VERTEX, vert_module),
@@ -591,6 +608,7 @@ fn prepare_pipelines(device: &VkDevice, model: &VkglTFModel, render_pass: vk::Re
     pipeline_ci.set_shaders(&shaders);
 
     pipeline_ci.set_vertex_input(model.meshes.vertex_input.clone());
+    model.meshes.check_pipeline(&pipeline_ci)?;
     pipeline_ci.set_viewport(viewport_state);
     pipeline_ci.set_depth_stencil(depth_stencil_state);
     pipeline_ci.set_rasterization(rasterization_state);
@@ -0,0 +1,547 @@
+
+use ash::vk;
+use ash::version::DeviceV1_0;
+
+use std::ptr;
+use std::mem;
+use std::path::Path;
+
+use vkbase::context::{VkDevice, VkSwapchain};
+use vkbase::ci::VkObjectBuildableCI;
+use vkbase::ci::buffer::BufferCI;
+use vkbase::ci::image::{ImageCI, ImageViewCI, ImageBarrierCI};
+use vkbase::ci::vma::{VmaBuffer, VmaImage, VmaAllocationCI};
+use vkbase::ci::shader::{ShaderModuleCI, ShaderStageCI};
+use vkbase::gltf::VkglTFModel;
+use vkbase::context::VulkanContext;
+use vkbase::{FlightCamera, FrameAction, FrameResource};
+use vkbase::{vkbytes, vkptr, vkuint, Vec3F, Mat4F, Vec4F};
+use vkbase::{VkResult, VkError, VkErrorKind};
+
+use vkexamples::DEFAULT_CLEAR_COLOR;
+
+const MESH_VERTEX_SHADER_SOURCE_PATH  : &'static str = "examples/src/geometryshader/mesh.vert.glsl";
+const MESH_FRAGMENT_SHADER_SOURCE_PATH: &'static str = "examples/src/geometryshader/mesh.frag.glsl";
+const MODEL_PATH: &'static str = "assets/models/treasure_smooth.gltf";
+
+
+/// This example does not use `VkExampleBackend`, since its UI renderer and framebuffers are built on a render pass.
+pub struct VulkanExample {
+
+    dimension: vk::Extent2D,
+    color_format: vk::Format,
+    /// the swapchain images and their views, which are rendered into directly.
+    color_targets: Vec<(vk::Image, vk::ImageView)>,
+    depth_image: DepthImage,
+
+    command_pool: vk::CommandPool,
+    commands: Vec<vk::CommandBuffer>,
+    await_rendering: vk::Semaphore,
+
+    model: VkglTFModel,
+    uniform_buffers: FrameResource<VmaBuffer>,
+
+    pipelines: PipelineStaff,
+    descriptors: DescriptorStaff,
+
+    ubo_data: UboVS,
+    camera: FlightCamera,
+}
+
+struct DepthImage {
+    image: VmaImage,
+    view : vk::ImageView,
+}
+
+struct PipelineStaff {
+    solid: vk::Pipeline,
+    layout: vk::PipelineLayout,
+}
+
+impl VulkanExample {
+
+    pub fn new(context: &mut VulkanContext) -> VkResult<VulkanExample> {
+
+        let device = &mut context.device;
+        let swapchain = &context.swapchain;
+        let dimension = swapchain.dimension;
+
+        if !device.phy.is_dynamic_rendering_support() {
+            return Err(VkError::unsupported("Dynamic Rendering"))
+        }
+
+        let mut camera = FlightCamera::new()
+            .place_at(Vec3F::new(0.25, 6.25, 8.75))
+            .screen_aspect_ratio(dimension.width as f32 / dimension.height as f32)
+            .look_at(Vec3F::new(0.25, 0.0, 2.5))
+            .build();
+        camera.set_move_speed(50.0);
+
+        let ubo_data = UboVS {
+            projection : camera.proj_matrix(),
+            view       : camera.view_matrix(),
+            model      : Mat4F::identity(),
+            light_pos  : Vec4F::new(0.0, 2.0, 1.0, 0.0),
+        };
+
+        let (command_pool, commands) = setup_commands(device, swapchain.frame_in_flight() as _)?;
+        let await_rendering = device.build(&vkbase::ci::sync::SemaphoreCI::new())?;
+        let depth_image = setup_depth_image(device, dimension)?;
+
+        let model = prepare_model(device)?;
+        let uniform_buffers = FrameResource::new(swapchain.frame_in_flight(), |_| prepare_uniform(device, &ubo_data))?;
+        let descriptors = setup_descriptor(device, &uniform_buffers, &model)?;
+
+        let pipelines = prepare_pipelines(device, &model, swapchain.backend_format, descriptors.layout)?;
+
+        let target = VulkanExample {
+            dimension,
+            color_format: swapchain.backend_format,
+            color_targets: collect_color_targets(swapchain),
+            depth_image, command_pool, commands, await_rendering,
+            model, uniform_buffers, descriptors, pipelines, camera, ubo_data,
+        };
+        Ok(target)
+    }
+}
+
+impl vkbase::RenderWorkflow for VulkanExample {
+
+    fn init(&mut self, device: &VkDevice) -> VkResult<()> {
+
+        self.record_commands(device, self.dimension)?;
+        Ok(())
+    }
+
+    fn render_frame(&mut self, device: &mut VkDevice, device_available: vk::Fence, await_present: vk::Semaphore, image_index: usize, _delta_time: f32) -> VkResult<vk::Semaphore> {
+
+        self.update_uniforms(image_index)?;
+
+        let submit_ci = vkbase::ci::device::SubmitCI::new()
+            .add_wait(vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT, await_present)
+            .add_command(self.commands[image_index])
+            .add_signal(self.await_rendering);
+
+        // Submit to the graphics queue passing a wait fence.
+        device.submit(submit_ci, device.logic.queues.graphics.handle, Some(device_available))?;
+
+        Ok(self.await_rendering)
+    }
+
+    fn swapchain_reload(&mut self, device: &mut VkDevice, new_chain: &VkSwapchain) -> VkResult<()> {
+
+        self.dimension = new_chain.dimension;
+        self.color_targets = collect_color_targets(new_chain);
+
+        let mut new_depth_image = setup_depth_image(device, self.dimension)?;
+        mem::swap(&mut new_depth_image, &mut self.depth_image);
+        device.discard(new_depth_image.view);
+        device.vma_discard(new_depth_image.image)?;
+
+        // the pipeline depends on the format of swapchain images instead of a render pass.
+        if self.color_format != new_chain.backend_format {
+            self.color_format = new_chain.backend_format;
+            device.discard(self.pipelines.solid);
+            device.discard(self.pipelines.layout);
+            self.pipelines = prepare_pipelines(device, &self.model, self.color_format, self.descriptors.layout)?;
+        }
+
+        unsafe {
+            device.logic.handle.reset_command_pool(self.command_pool, vk::CommandPoolResetFlags::empty())
+                .map_err(|_| VkError::device("Reset Command Pool"))?;
+        }
+        self.record_commands(device, self.dimension)?;
+
+        Ok(())
+    }
+
+    fn receive_input(&mut self, inputer: &vkbase::EventController, delta_time: f32) -> FrameAction {
+
+        if inputer.is_key_active() || inputer.is_cursor_active() {
+
+            if inputer.key.is_key_pressed(winit::VirtualKeyCode::Escape) {
+                return FrameAction::Terminal
+            }
+
+            self.camera.receive_input(inputer, delta_time);
+        }
+
+        FrameAction::Rendering
+    }
+
+    fn deinit(self, device: &mut VkDevice) -> VkResult<()> {
+
+        device.discard(self.descriptors.layout);
+        device.discard(self.descriptors.pool);
+
+        device.discard(self.pipelines.solid);
+        device.discard(self.pipelines.layout);
+
+        for uniform_buffer in self.uniform_buffers.into_inner() {
+            device.vma_discard(uniform_buffer)?;
+        }
+        device.vma_discard(self.model)?;
+
+        device.discard(self.depth_image.view);
+        device.vma_discard(self.depth_image.image)?;
+
+        device.discard(self.command_pool);
+        device.discard(self.await_rendering);
+
+        Ok(())
+    }
+}
+
+impl VulkanExample {
+
+    fn record_commands(&self, device: &VkDevice, dimension: vk::Extent2D) -> VkResult<()> {
+
+        use vkbase::command::{VkCmdRecorder, CmdGraphicsApi, IGraphics};
+        use vkbase::ci::pipeline::{RenderingBI, RenderingAttachmentCI};
+
+        let scissor = vk::Rect2D {
+            extent: dimension.clone(),
+            offset: vk::Offset2D { x: 0, y: 0 },
+        };
+
+        let viewport = vk::Viewport {
+            x: 0.0, y: 0.0,
+            width: dimension.width as f32, height: dimension.height as f32,
+            min_depth: 0.0, max_depth: 1.0,
+        };
+
+        let color_range = vk::ImageSubresourceRange {
+            aspect_mask: vk::ImageAspectFlags::COLOR,
+            base_mip_level: 0,
+            level_count   : 1,
+            base_array_layer: 0,
+            layer_count     : 1,
+        };
+        let depth_range = vk::ImageSubresourceRange {
+            aspect_mask: device.phy.depth_aspect_mask(),
+            ..color_range
+        };
+
+        let clear_color = vk::ClearValue {
+            color: vk::ClearColorValue { float32: DEFAULT_CLEAR_COLOR.for_format(self.color_format).into() },
+        };
+        let clear_depth = vk::ClearValue {
+            depth_stencil: vk::ClearDepthStencilValue { depth: 1.0, stencil: 0 },
+        };
+
+        let depth_stages = vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS | vk::PipelineStageFlags::LATE_FRAGMENT_TESTS;
+
+        for (i, &command) in self.commands.iter().enumerate() {
+
+            let (color_image, color_view) = self.color_targets[i];
+
+            let render_params = vkbase::gltf::ModelRenderParams {
+                descriptor_set : *self.descriptors.sets.get(i),
+                pipeline_layout: self.pipelines.layout,
+                material_stage : Some(vk::ShaderStageFlags::VERTEX),
+            };
+
+            // Without render pass, the layout transitions of attachments must be recorded manually.
+            // The previous content of both attachments is discarded, since they are cleared at the beginning of rendering.
+            let color_barrier = ImageBarrierCI::new(color_image, color_range)
+                .access_mask(vk::AccessFlags::empty(), vk::AccessFlags::COLOR_ATTACHMENT_WRITE)
+                .layout(vk::ImageLayout::UNDEFINED, vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL);
+            // the depth image is shared by all frames, so wait for the depth writes of the previous frame.
+            let depth_barrier = ImageBarrierCI::new(self.depth_image.image.handle, depth_range)
+                .access_mask(vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE, vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_READ | vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE)
+                .layout(vk::ImageLayout::UNDEFINED, vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL);
+            let present_barrier = ImageBarrierCI::new(color_image, color_range)
+                .access_mask(vk::AccessFlags::COLOR_ATTACHMENT_WRITE, vk::AccessFlags::empty())
+                .layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL, vk::ImageLayout::PRESENT_SRC_KHR);
+
+            let depth_attachment = RenderingAttachmentCI::new(self.depth_image.view, vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL)
+                .op(vk::AttachmentLoadOp::CLEAR, vk::AttachmentStoreOp::DONT_CARE)
+                .clear(clear_depth);
+
+            let mut rendering_bi = RenderingBI::new(dimension)
+                .add_color(RenderingAttachmentCI::new(color_view, vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL)
+                    .op(vk::AttachmentLoadOp::CLEAR, vk::AttachmentStoreOp::STORE)
+                    .clear(clear_color))
+                .depth(depth_attachment);
+            if device.phy.is_depth_stencil() {
+                rendering_bi = rendering_bi.stencil(depth_attachment);
+            }
+
+            let recorder: VkCmdRecorder<IGraphics> = VkCmdRecorder::new(&device.logic, command);
+
+            recorder.begin_record()?
+                .image_pipeline_barrier(vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT, vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT, vk::DependencyFlags::empty(), &[color_barrier.into()])
+                .image_pipeline_barrier(depth_stages, depth_stages, vk::DependencyFlags::empty(), &[depth_barrier.into()])
+                .begin_rendering(&rendering_bi)
+                .set_viewport(0, &[viewport])
+                .set_scissor(0, &[scissor])
+                .bind_pipeline(self.pipelines.solid);
+
+            self.model.record_command(&recorder, &render_params);
+
+            recorder
+                .end_rendering()
+                .image_pipeline_barrier(vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT, vk::PipelineStageFlags::BOTTOM_OF_PIPE, vk::DependencyFlags::empty(), &[present_barrier.into()])
+                .end_record()?;
+        }
+
+        Ok(())
+    }
+
+    fn update_uniforms(&mut self, image_index: usize) -> VkResult<()> {
+
+        self.ubo_data.view = self.camera.view_matrix();
+
+        unsafe {
+            let data_ptr = self.uniform_buffers.get(image_index).info.get_mapped_data() as vkptr<UboVS>;
+            data_ptr.copy_from_nonoverlapping(&self.ubo_data, 1);
+        }
+
+        Ok(())
+    }
+}
+
+fn collect_color_targets(swapchain: &VkSwapchain) -> Vec<(vk::Image, vk::ImageView)> {
+    swapchain.images.iter()
+        .map(|image| (image.image, image.view))
+        .collect()
+}
+
+fn setup_depth_image(device: &mut VkDevice, dimension: vk::Extent2D) -> VkResult<DepthImage> {
+
+    // the content of depth image is never stored, so its memory can be lazily allocated.
+    let depth_ci = ImageCI::new_2d(device.phy.depth_format, dimension)
+        .usages(vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT);
+    let image = device.create_attachment_image(depth_ci, true)?;
+
+    let view = ImageViewCI::new(image.handle, vk::ImageViewType::TYPE_2D, device.phy.depth_format)
+        .sub_range(vk::ImageSubresourceRange {
+            aspect_mask: device.phy.depth_aspect_mask(),
+            base_mip_level: 0,
+            level_count   : 1,
+            base_array_layer: 0,
+            layer_count     : 1,
+        }).build(device)?;
+
+    let result = DepthImage { image, view };
+    Ok(result)
+}
+
+fn setup_commands(device: &VkDevice, buffer_count: vkuint) -> VkResult<(vk::CommandPool, Vec<vk::CommandBuffer>)> {
+
+    use vkbase::ci::command::{CommandPoolCI, CommandBufferAI};
+
+    let command_pool = CommandPoolCI::new(device.logic.queues.graphics.family_index)
+        .flags(vk::CommandPoolCreateFlags::RESET_COMMAND_BUFFER)
+        .build(device)?;
+
+    let command_buffers = CommandBufferAI::new(command_pool, buffer_count)
+        .build(device)?;
+
+    Ok((command_pool, command_buffers))
+}
+
+// Prepare model from glTF file.
+pub fn prepare_model(device: &mut VkDevice) -> VkResult<VkglTFModel> {
+
+    use vkbase::gltf::{GltfModelInfo, load_gltf};
+    use vkbase::gltf::{AttributeFlags, NodeAttachmentFlags, NormalGeneration};
+
+    let model_info = GltfModelInfo {
+        path: Path::new(MODEL_PATH),
+        attribute: AttributeFlags::POSITION | AttributeFlags::NORMAL, // specify model's vertices layout.
+        node: NodeAttachmentFlags::TRANSFORM_MATRIX, // specify model's node attachment layout.
+        transform: None,
+        normal_generation: NormalGeneration::Smooth,
+    };
+
+    let model = load_gltf(device, model_info)?;
+    Ok(model)
+}
+
+
+// The uniform data that will be transferred to shader.
+//
+// layout (set = 0, binding = 0) uniform UBO {
+//     mat4 projection;
+//     mat4 view;
+//     mat4 model;
+//     vec4 lightPos;
+// } ubo;
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+struct UboVS {
+    projection   : Mat4F,
+    view         : Mat4F,
+    model        : Mat4F,
+    light_pos    : Vec4F,
+}
+
+fn prepare_uniform(device: &mut VkDevice, ubo_data: &UboVS) -> VkResult<VmaBuffer> {
+
+    let uniform_buffer = {
+        let uniform_ci = BufferCI::new(mem::size_of::<UboVS>() as vkbytes)
+            .usage(vk::BufferUsageFlags::UNIFORM_BUFFER);
+        let allocation_ci = VmaAllocationCI::new(vma::MemoryUsage::CpuOnly, vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT)
+            .flags(vma::AllocationCreateFlags::MAPPED);
+        let uniform_allocation = device.vma.create_buffer(uniform_ci.as_ref(), allocation_ci.as_ref())
+            .map_err(VkErrorKind::Vma)?;
+        VmaBuffer::from(uniform_allocation)
+    };
+
+    // keep the uniform memory map during the program running.
+    unsafe {
+        let data_ptr = uniform_buffer.info.get_mapped_data() as vkptr<UboVS>;
+        debug_assert_ne!(data_ptr, ptr::null_mut());
+        data_ptr.copy_from_nonoverlapping(ubo_data, 1);
+    }
+
+    Ok(uniform_buffer)
+}
+
+struct DescriptorStaff {
+    pool   : vk::DescriptorPool,
+    sets   : FrameResource<vk::DescriptorSet>,
+    layout : vk::DescriptorSetLayout,
+}
+
+fn setup_descriptor(device: &VkDevice, uniform_buffers: &FrameResource<VmaBuffer>, model: &VkglTFModel) -> VkResult<DescriptorStaff> {
+
+    use vkbase::ci::descriptor::{DescriptorPoolCI, DescriptorSetLayoutCI};
+    use vkbase::ci::descriptor::{DescriptorSetAI, DescriptorBufferSetWI, DescriptorSetsUpdateCI};
+
+    // Descriptor Pool.
+    // one descriptor set for each uniform buffer.
+    let set_count = uniform_buffers.len() as _;
+    let descriptor_pool = DescriptorPoolCI::new(set_count)
+        .add_descriptor(vk::DescriptorType::UNIFORM_BUFFER, set_count)
+        .add_descriptor(vk::DescriptorType::UNIFORM_BUFFER_DYNAMIC, set_count)
+        .build(device)?;
+
+    let ubo_descriptor = vk::DescriptorSetLayoutBinding {
+        binding: 0,
+        descriptor_type: vk::DescriptorType::UNIFORM_BUFFER,
+        descriptor_count: 1,
+        stage_flags: vk::ShaderStageFlags::VERTEX,
+        p_immutable_samplers: ptr::null(),
+    };
+
+    let node_descriptor = vk::DescriptorSetLayoutBinding {
+        binding: 1,
+        descriptor_type: vk::DescriptorType::UNIFORM_BUFFER_DYNAMIC,
+        descriptor_count: 1,
+        stage_flags: vk::ShaderStageFlags::VERTEX,
+        p_immutable_samplers: ptr::null(),
+    };
+
+    let set_layout = DescriptorSetLayoutCI::new()
+        .add_binding(ubo_descriptor)
+        .add_binding(node_descriptor)
+        .build(device)?;
+
+    // Descriptor sets.
+    let descriptor_sets = FrameResource::new(uniform_buffers.len(), |i| {
+
+        let mut descriptor_sets = DescriptorSetAI::new(descriptor_pool)
+            .add_set_layout(set_layout)
+            .build(device)?;
+        let descriptor_set = descriptor_sets.remove(0);
+
+        let ubo_write_info = DescriptorBufferSetWI::new(descriptor_set, 0, vk::DescriptorType::UNIFORM_BUFFER)
+            .add_buffer(vk::DescriptorBufferInfo {
+                buffer: uniform_buffers.get(i).handle,
+                offset: 0,
+                range : mem::size_of::<UboVS>() as vkbytes,
+            });
+        let node_write_info = DescriptorBufferSetWI::new(descriptor_set, 1, vk::DescriptorType::UNIFORM_BUFFER_DYNAMIC)
+            .add_buffer(model.nodes.node_descriptor());
+
+        DescriptorSetsUpdateCI::new()
+            .add_write(&ubo_write_info)
+            .add_write(&node_write_info)
+            .update(device);
+
+        Ok(descriptor_set)
+    })?;
+
+    let descriptors = DescriptorStaff {
+        pool   : descriptor_pool,
+        sets   : descriptor_sets,
+        layout : set_layout,
+    };
+    Ok(descriptors)
+}
+
+fn prepare_pipelines(device: &VkDevice, model: &VkglTFModel, color_format: vk::Format, set_layout: vk::DescriptorSetLayout) -> VkResult<PipelineStaff> {
+
+    use vkbase::ci::pipeline::*;
+
+    let viewport_state = ViewportSCI::new()
+        .add_viewport(vk::Viewport::default())
+        .add_scissor(vk::Rect2D::default());
+
+    let rasterization_state = RasterizationSCI::new()
+        .polygon(vk::PolygonMode::FILL)
+        .cull_face(vk::CullModeFlags::BACK, vk::FrontFace::CLOCKWISE);
+
+    let blend_attachment = BlendAttachmentSCI::new();
+    let blend_state = ColorBlendSCI::new()
+        .add_attachment(blend_attachment);
+
+    let depth_stencil_state = DepthStencilSCI::new()
+        .depth_test(true, true, vk::CompareOp::LESS_OR_EQUAL);
+
+    let dynamic_state = DynamicSCI::new()
+        .add_dynamic(vk::DynamicState::VIEWPORT)
+        .add_dynamic(vk::DynamicState::SCISSOR);
+
+    let push_constant_ranges = PushConstantRanges::new()
+        .push_sized(vk::ShaderStageFlags::VERTEX, model.materials.material_size())
+        .build(device)?;
+
+    // Pipeline Layout.
+    let pipeline_layout = PipelineLayoutCI::new()
+        .add_set_layout(set_layout)
+        .add_push_constant_ranges(&push_constant_ranges)
+        .build(device)?;
+
+    // The attachment formats are declared here, in the same order as the attachments of `RenderingBI`.
+    let rendering_ci = PipelineRenderingCI::new()
+        .add_color_format(color_format)
+        .depth_format(device.phy.depth_format);
+
+    let mut pipeline_ci = GraphicsPipelineCI::new_dynamic_rendering(pipeline_layout, rendering_ci);
+
+    pipeline_ci.set_vertex_input(model.meshes.vertex_input.clone());
+    model.meshes.check_pipeline(&pipeline_ci)?;
+    pipeline_ci.set_viewport(viewport_state);
+    pipeline_ci.set_rasterization(rasterization_state);
+    pipeline_ci.set_depth_stencil(depth_stencil_state);
+    pipeline_ci.set_color_blend(blend_state);
+    pipeline_ci.set_dynamic(dynamic_state);
+
+    let mut shader_compiler = vkbase::utils::shaderc::VkShaderCompiler::new()?;
+
+    let vert_codes = shader_compiler.compile_from_path(Path::new(MESH_VERTEX_SHADER_SOURCE_PATH), shaderc::ShaderKind::Vertex, "[Vertex Shader]", "main")?;
+    let frag_codes = shader_compiler.compile_from_path(Path::new(MESH_FRAGMENT_SHADER_SOURCE_PATH), shaderc::ShaderKind::Fragment, "[Fragment Shader]", "main")?;
+
+    let vert_module = ShaderModuleCI::new(vert_codes).build(device)?;
+    let frag_module = ShaderModuleCI::new(frag_codes).build(device)?;
+
+    let shaders = [
+        ShaderStageCI::new(vk::ShaderStageFlags::VERTEX, vert_module),
+        ShaderStageCI::new(vk::ShaderStageFlags::FRAGMENT, frag_module),
+    ];
+    pipeline_ci.set_shaders(&shaders);
+
+    let solid_pipeline = device.build(&pipeline_ci)?;
+
+    device.discard(vert_module);
+    device.discard(frag_module);
+
+    let result = PipelineStaff {
+        solid : solid_pipeline,
+        layout: pipeline_layout,
+    };
+    Ok(result)
+}
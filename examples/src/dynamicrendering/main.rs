@@ -0,0 +1,56 @@
+//!
+//! Vulkan Example - Dynamic rendering
+//!
+//! Rendering a glTF model by `VK_KHR_dynamic_rendering`, without any render pass or framebuffer object.
+//!
+
+mod example;
+
+const WINDOW_WIDTH : u32 = 1280;
+const WINDOW_HEIGHT: u32 = 720;
+const WINDOW_TITLE: &'static str = "Vulkan Example - Dynamic rendering";
+
+fn main() {
+
+    use vkbase::{WindowConfig, WindowContext};
+    use vkbase::context::{InstanceConfig, PhysicalDevConfig, DeviceExtensionType, VulkanContext};
+    use vkbase::ProcPipeline;
+
+    vkbase::utils::path::set_asset_root(vkexamples::ASSET_ROOT);
+
+    let mut win_config = WindowConfig::default();
+    win_config.dimension.width  = WINDOW_WIDTH;
+    win_config.dimension.height = WINDOW_HEIGHT;
+    win_config.title = WINDOW_TITLE.to_string();
+    win_config.is_cursor_hide = true;
+    win_config.is_cursor_grap = true;
+
+    let window = WindowContext::new(win_config)
+        .expect("Error when creating Window Context");
+
+    // the feature of dynamic rendering is queried by vkGetPhysicalDeviceFeatures2, which is core in Vulkan 1.1.
+    let mut instance_config = InstanceConfig::default();
+    instance_config.api_version = ash::vk_make_version!(1, 1, 0);
+
+    let mut phy_config = PhysicalDevConfig::default();
+    phy_config.request_extensions.push(DeviceExtensionType::CreateRenderPass2.name());
+    phy_config.request_extensions.push(DeviceExtensionType::DepthStencilResolve.name());
+    phy_config.request_extensions.push(DeviceExtensionType::DynamicRendering.name());
+
+    let mut vk_context = VulkanContext::new(&window)
+        .with_instance_config(instance_config)
+        .with_physical_device_config(phy_config)
+        .build().expect("Error when creating Vulkan Context");
+
+    let app = example::VulkanExample::new(&mut vk_context)
+        .expect("Error when initializing application");
+
+    let entry = ProcPipeline::new(window, vk_context).unwrap();
+
+    match entry.launch(app) {
+        | Ok(_) => {},
+        | Err(e) => {
+            eprintln!("{}", e)
+        }
+    }
+}
@@ -19,6 +19,9 @@ out gl_PerVertex {
     vec4 gl_Position;   
 };
 
+// the depth pre-pass and the color pass must compute the same depth values.
+invariant gl_Position;
+
 
 void main() {
 
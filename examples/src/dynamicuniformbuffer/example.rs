@@ -6,6 +6,7 @@ use std::path::Path;
 
 use vkbase::context::{VulkanContext, VkDevice, VkSwapchain};
 use vkbase::ci::VkObjectBuildableCI;
+use vkbase::ci::pipeline::RenderPass;
 use vkbase::ci::vma::VmaBuffer;
 use vkbase::{FlightCamera, FrameAction};
 use vkbase::{vkbytes, vkuint, vkptr, Vec3F};
@@ -64,7 +65,7 @@ impl VulkanExample {
 
         let descriptors = setup_descriptor(device, &ubo_view, &ubo_dynamics, dynamic_alignment)?;
 
-        let pipelines = prepare_pipelines(device, backend.render_pass, descriptors.layout)?;
+        let pipelines = prepare_pipelines(device, &backend, descriptors.layout)?;
 
         let target = VulkanExample {
             backend, descriptors, pipelines, camera,
@@ -105,11 +106,12 @@ impl vkbase::RenderWorkflow for VulkanExample {
     fn swapchain_reload(&mut self, device: &mut VkDevice, new_chain: &VkSwapchain) -> VkResult<()> {
 
         // recreate the resources.
+        device.discard(self.pipelines.depth_pipeline);
         device.discard(self.pipelines.pipeline);
 
         let render_pass = setup_renderpass(device, new_chain)?;
         self.backend.swapchain_reload(device, new_chain, render_pass)?;
-        self.pipelines = prepare_pipelines(device, self.backend.render_pass, self.descriptors.layout)?;
+        self.pipelines = prepare_pipelines(device, &self.backend, self.descriptors.layout)?;
 
         self.record_commands(device, self.backend.dimension)?;
 
@@ -140,6 +142,7 @@ impl vkbase::RenderWorkflow for VulkanExample {
         device.discard(self.descriptors.layout);
         device.discard(self.descriptors.pool);
 
+        device.discard(self.pipelines.depth_pipeline);
         device.discard(self.pipelines.pipeline);
         device.discard(self.pipelines.layout);
 
@@ -174,28 +177,34 @@ impl VulkanExample {
 
             let recorder: VkCmdRecorder<IGraphics> = VkCmdRecorder::new(&device.logic, command);
 
-            let render_pass_bi = RenderPassBI::new(self.backend.render_pass, self.backend.framebuffers[i])
+            let render_pass_bi = RenderPassBI::with_pass(self.backend.render_pass, self.backend.framebuffers[i])
                 .render_extent(dimension)
-                .set_clear_values(vkexamples::DEFAULT_CLEAR_VALUES.clone());
+                .set_clear_values(self.backend.clear_values());
 
             recorder.begin_record()?
                 .begin_render_pass(render_pass_bi)
                 .set_viewport(0, &[viewport])
                 .set_scissor(0, &[scissor])
-                .bind_pipeline(self.pipelines.pipeline)
                 .bind_vertex_buffers(0, &[self.vertices.handle], &[0])
                 .bind_index_buffer(self.indices.handle, vk::IndexType::UINT32, 0);
 
-            // Render multiple objects using different model matrices by dynamically offsetting into one uniform buffer.
-            for i in 0..(OBJECT_INSTANCES as vkuint) {
-                // One dynamic offset per dynamic descriptor to offset into the ubo containing all model matrices.
-                let dynamic_offset = i * self.dynamic_alignment;
-                recorder
-                    .bind_descriptor_sets(self.pipelines.layout, 0, &[self.descriptors.set], &[dynamic_offset])
-                    .draw_indexed(INDEX_DATA.len() as vkuint, 1, 0, 0, 0);
+            // Render the cubes twice: the depth pre-pass resolves the overlaps between cubes,
+            // then the color pass only shades the visible fragments.
+            for &pipeline in [self.pipelines.depth_pipeline, self.pipelines.pipeline].iter() {
+
+                recorder.bind_pipeline(pipeline);
+
+                // Render multiple objects using different model matrices by dynamically offsetting into one uniform buffer.
+                for j in 0..(OBJECT_INSTANCES as vkuint) {
+                    // One dynamic offset per dynamic descriptor to offset into the ubo containing all model matrices.
+                    let dynamic_offset = j * self.dynamic_alignment;
+                    recorder
+                        .bind_descriptor_sets(self.pipelines.layout, 0, &[self.descriptors.set], &[dynamic_offset])
+                        .draw_indexed(INDEX_DATA.len() as vkuint, 1, 0, 0, 0);
+                }
             }
 
-            self.backend.ui_renderer.record_command(&recorder);
+            self.backend.record_ui_command(&recorder, i);
 
             recorder.end_render_pass()
                 .end_record()?;
@@ -323,7 +332,7 @@ fn setup_descriptor(device: &VkDevice, ubo_view: &VmaBuffer, ubo_dynamics: &VmaB
     Ok(result)
 }
 
-fn setup_renderpass(device: &VkDevice, swapchain: &VkSwapchain) -> VkResult<vk::RenderPass> {
+fn setup_renderpass(device: &VkDevice, swapchain: &VkSwapchain) -> VkResult<RenderPass> {
 
     use vkbase::ci::pipeline::RenderPassCI;
     use vkbase::ci::pipeline::{AttachmentDescCI, SubpassDescCI, SubpassDependencyCI};
@@ -356,18 +365,20 @@ fn setup_renderpass(device: &VkDevice, swapchain: &VkSwapchain) -> VkResult<vk::
         .add_subpass(subpass_description)
         .add_dependency(dependency0)
         .add_dependency(dependency1)
-        .build(device)?;
+        .build_with_attachments(device)?;
 
     Ok(render_pass)
 }
 
 
 struct PipelineStaff {
+    /// the depth-only pipeline of depth pre-pass.
+    depth_pipeline: vk::Pipeline,
     pipeline: vk::Pipeline,
     layout: vk::PipelineLayout,
 }
 
-fn prepare_pipelines(device: &VkDevice, render_pass: vk::RenderPass, set_layout: vk::DescriptorSetLayout) -> VkResult<PipelineStaff> {
+fn prepare_pipelines(device: &VkDevice, backend: &VkExampleBackend, set_layout: vk::DescriptorSetLayout) -> VkResult<PipelineStaff> {
 
     use vkbase::ci::pipeline::*;
 
@@ -383,9 +394,6 @@ fn prepare_pipelines(device: &VkDevice, render_pass: vk::RenderPass, set_layout:
     let blend_state = ColorBlendSCI::new()
         .add_attachment(blend_attachment);
 
-    let depth_stencil_state = DepthStencilSCI::new()
-        .depth_test(true, true, vk::CompareOp::LESS_OR_EQUAL);
-
     let dynamic_state = DynamicSCI::new()
         .add_dynamic(vk::DynamicState::VIEWPORT)
         .add_dynamic(vk::DynamicState::SCISSOR);
@@ -395,7 +403,7 @@ fn prepare_pipelines(device: &VkDevice, render_pass: vk::RenderPass, set_layout:
         .add_set_layout(set_layout)
         .build(device)?;
 
-    let mut pipeline_ci = GraphicsPipelineCI::new(render_pass, layout);
+    let mut pipeline_ci = GraphicsPipelineCI::new(backend.render_pass.handle, layout);
 
     // shaders
     use vkbase::ci::shader::{ShaderModuleCI, ShaderStageCI};
@@ -404,26 +412,29 @@ fn prepare_pipelines(device: &VkDevice, render_pass: vk::RenderPass, set_layout:
     let vert_codes = shader_compiler.compile_from_path(Path::new(SHADER_VERTEX_PATH), shaderc::ShaderKind::Vertex, "[Vertex Shader]", "main")?;
     let frag_codes = shader_compiler.compile_from_path(Path::new(SHADER_FRAGMENT_PATH), shaderc::ShaderKind::Fragment, "[Fragment Shader]", "main")?;
 
-    let vert_module = ShaderModuleCI::new(vert_codes).build(device)?;
+    let vert_module_ci = ShaderModuleCI::new(vert_codes);
+    // reflect the vertex shader, so that the depth pre-pass can check the invariance of its position.
+    let vert_reflection = vert_module_ci.reflect()?;
+    let vert_module = vert_module_ci.build(device)?;
     let frag_module = ShaderModuleCI::new(frag_codes).build(device)?;
 
     let shaders = [
-        ShaderStageCI::new(vk::ShaderStageFlags::VERTEX, vert_module),
+        ShaderStageCI::new(vk::ShaderStageFlags::VERTEX, vert_module).reflection(&vert_reflection),
         ShaderStageCI::new(vk::ShaderStageFlags::FRAGMENT, frag_module),
     ];
     pipeline_ci.set_shaders(&shaders);
     pipeline_ci.set_vertex_input(Vertex::input_description());
     pipeline_ci.set_viewport(viewport_state);
-    pipeline_ci.set_depth_stencil(depth_stencil_state);
     pipeline_ci.set_rasterization(rasterization_state);
     pipeline_ci.set_color_blend(blend_state);
     pipeline_ci.set_dynamic(dynamic_state);
 
-    let pipeline = device.build(&pipeline_ci)?;
+    // the depth-stencil states of both pipelines are decided by the backend.
+    let (depth_pipeline, pipeline) = backend.build_depth_prepass_pipelines(device, &mut pipeline_ci)?;
 
     device.discard(vert_module);
     device.discard(frag_module);
 
-    let result = PipelineStaff { pipeline, layout };
+    let result = PipelineStaff { depth_pipeline, pipeline, layout };
     Ok(result)
 }
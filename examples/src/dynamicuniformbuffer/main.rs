@@ -22,6 +22,8 @@ fn main() {
     use vkbase::context::VulkanContext;
     use vkbase::ProcPipeline;
 
+    vkbase::utils::path::set_asset_root(vkexamples::ASSET_ROOT);
+
     let mut win_config = WindowConfig::default();
     win_config.dimension.width  = WINDOW_WIDTH;
     win_config.dimension.height = WINDOW_HEIGHT;
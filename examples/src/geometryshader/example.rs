@@ -0,0 +1,511 @@
+
+use ash::vk;
+
+use std::ptr;
+use std::mem;
+use std::path::Path;
+
+use vkbase::context::{VkDevice, VkSwapchain};
+use vkbase::ci::VkObjectBuildableCI;
+use vkbase::ci::pipeline::RenderPass;
+use vkbase::ci::buffer::BufferCI;
+use vkbase::ci::vma::{VmaBuffer, VmaAllocationCI};
+use vkbase::ci::shader::{ShaderModuleCI, ShaderStageCI};
+use vkbase::gltf::VkglTFModel;
+use vkbase::ui::{TextInfo, TextType, TextHAlign};
+use vkbase::context::VulkanContext;
+use vkbase::utils::color::VkColor;
+use vkbase::{FlightCamera, FrameAction, FrameResource};
+use vkbase::{vkbytes, vkptr, Vec3F, Mat4F, Vec4F};
+use vkbase::{VkResult, VkErrorKind};
+
+use vkexamples::VkExampleBackend;
+
+const MESH_VERTEX_SHADER_SOURCE_PATH          : &'static str = "examples/src/geometryshader/mesh.vert.glsl";
+const MESH_FRAGMENT_SHADER_SOURCE_PATH        : &'static str = "examples/src/geometryshader/mesh.frag.glsl";
+const NORMAL_VERTEX_SHADER_SOURCE_PATH        : &'static str = "examples/src/geometryshader/base.vert.glsl";
+const NORMAL_GEOMETRY_SHADER_SOURCE_PATH      : &'static str = "examples/src/geometryshader/normaldebug.geom.glsl";
+const NORMAL_FRAGMENT_SHADER_SOURCE_PATH      : &'static str = "examples/src/geometryshader/base.frag.glsl";
+const MODEL_PATH: &'static str = "assets/models/treasure_smooth.gltf";
+
+
+pub struct VulkanExample {
+
+    backend: VkExampleBackend,
+
+    model: VkglTFModel,
+    /// one uniform buffer for each swapchain image, so that updating the uniform of current frame never touches the buffer still read by other frames in flight.
+    uniform_buffers: FrameResource<VmaBuffer>,
+
+    pipelines: PipelineStaff,
+    descriptors: DescriptorStaff,
+
+    ubo_data: UboVS,
+    camera: FlightCamera,
+}
+
+struct PipelineStaff {
+    solid: vk::Pipeline,
+    /// the normal visualization pipeline is only available when `geometry_shader` feature is enabled.
+    normal: Option<vk::Pipeline>,
+    layout: vk::PipelineLayout,
+}
+
+impl VulkanExample {
+
+    pub fn new(context: &mut VulkanContext) -> VkResult<VulkanExample> {
+
+        let device = &mut context.device;
+        let swapchain = &context.swapchain;
+        let dimension = swapchain.dimension;
+
+        let mut camera = FlightCamera::new()
+            .place_at(Vec3F::new(0.25, 6.25, 8.75))
+            .screen_aspect_ratio(dimension.width as f32 / dimension.height as f32)
+            .look_at(Vec3F::new(0.25, 0.0, 2.5))
+            .build();
+        camera.set_move_speed(50.0);
+
+        let ubo_data = UboVS {
+            projection : camera.proj_matrix(),
+            view       : camera.view_matrix(),
+            model      : Mat4F::identity(),
+            light_pos  : Vec4F::new(0.0, 2.0, 1.0, 0.0),
+        };
+
+        let render_pass = setup_renderpass(device, &context.swapchain)?;
+        let backend = VkExampleBackend::new(device, swapchain, render_pass)?;
+
+        let model = prepare_model(device)?;
+        let uniform_buffers = FrameResource::new(swapchain.frame_in_flight(), |_| prepare_uniform(device, &ubo_data))?;
+        let descriptors = setup_descriptor(device, &uniform_buffers, &model)?;
+
+        let pipelines = prepare_pipelines(device, &model, backend.render_pass.handle, descriptors.layout)?;
+
+        let target = VulkanExample {
+            backend, model, uniform_buffers, descriptors, pipelines, camera, ubo_data,
+        };
+        Ok(target)
+    }
+}
+
+impl vkbase::RenderWorkflow for VulkanExample {
+
+    fn init(&mut self, device: &VkDevice) -> VkResult<()> {
+
+        self.backend.set_basic_ui(device, super::WINDOW_TITLE)?;
+
+        let hint = if self.pipelines.normal.is_some() {
+            "Vertex normals generated by geometry shader"
+        } else {
+            "Geometry shader is not supported on this device"
+        };
+
+        let hint_text = TextInfo {
+            content: String::from(hint),
+            scale: 27.0,
+            align: TextHAlign::Center,
+            color: VkColor::WHITE,
+            location: vk::Offset2D { x: self.backend.logical_dimension.width as i32 / 2, y: self.backend.logical_dimension.height as i32 / 8 * 7 },
+            pivot: (0.0, 0.0),
+            shadow: None,
+            r#type: TextType::Static,
+        };
+        self.backend.add_text(hint_text)?;
+
+        self.record_commands(device, self.backend.dimension)?;
+
+        Ok(())
+    }
+
+    fn render_frame(&mut self, device: &mut VkDevice, device_available: vk::Fence, await_present: vk::Semaphore, image_index: usize, _delta_time: f32) -> VkResult<vk::Semaphore> {
+
+        self.update_uniforms(image_index)?;
+
+        let submit_ci = vkbase::ci::device::SubmitCI::new()
+            .add_wait(vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT, await_present)
+            .add_command(self.backend.commands[image_index])
+            .add_signal(self.backend.await_rendering);
+
+        // Submit to the graphics queue passing a wait fence.
+        device.submit(submit_ci, device.logic.queues.graphics.handle, Some(device_available))?;
+
+        Ok(self.backend.await_rendering)
+    }
+
+    fn swapchain_reload(&mut self, device: &mut VkDevice, new_chain: &VkSwapchain) -> VkResult<()> {
+
+        // recreate the resources.
+        device.discard(self.pipelines.solid);
+        if let Some(normal_pipeline) = self.pipelines.normal {
+            device.discard(normal_pipeline);
+        }
+        device.discard(self.pipelines.layout);
+
+        let render_pass = setup_renderpass(device, new_chain)?;
+        self.backend.swapchain_reload(device, new_chain, render_pass)?;
+        self.pipelines = prepare_pipelines(device, &self.model, self.backend.render_pass.handle, self.descriptors.layout)?;
+
+        self.record_commands(device, self.backend.dimension)?;
+
+        Ok(())
+    }
+
+    fn receive_input(&mut self, inputer: &vkbase::EventController, delta_time: f32) -> FrameAction {
+
+        if inputer.is_key_active() || inputer.is_cursor_active() {
+
+            if inputer.key.is_key_pressed(winit::VirtualKeyCode::Escape) {
+                return FrameAction::Terminal
+            }
+
+            self.camera.receive_input(inputer, delta_time);
+        }
+
+        self.backend.update_fps_text(inputer);
+
+        FrameAction::Rendering
+    }
+
+    fn deinit(self, device: &mut VkDevice) -> VkResult<()> {
+
+        device.discard(self.descriptors.layout);
+        device.discard(self.descriptors.pool);
+
+        device.discard(self.pipelines.solid);
+        if let Some(normal_pipeline) = self.pipelines.normal {
+            device.discard(normal_pipeline);
+        }
+        device.discard(self.pipelines.layout);
+
+        for uniform_buffer in self.uniform_buffers.into_inner() {
+            device.vma_discard(uniform_buffer)?;
+        }
+        device.vma_discard(self.model)?;
+        self.backend.discard_by(device)
+    }
+}
+
+impl VulkanExample {
+
+    fn record_commands(&self, device: &VkDevice, dimension: vk::Extent2D) -> VkResult<()> {
+
+        let scissor = vk::Rect2D {
+            extent: dimension.clone(),
+            offset: vk::Offset2D { x: 0, y: 0 },
+        };
+
+        let viewport = vk::Viewport {
+            x: 0.0, y: 0.0,
+            width: dimension.width as f32, height: dimension.height as f32,
+            min_depth: 0.0, max_depth: 1.0,
+        };
+
+        for (i, &command) in self.backend.commands.iter().enumerate() {
+
+            use vkbase::command::{VkCmdRecorder, CmdGraphicsApi, IGraphics};
+            use vkbase::ci::pipeline::RenderPassBI;
+
+            let render_params = vkbase::gltf::ModelRenderParams {
+                descriptor_set : *self.descriptors.sets.get(i),
+                pipeline_layout: self.pipelines.layout,
+                material_stage : Some(vk::ShaderStageFlags::VERTEX),
+            };
+
+            let recorder: VkCmdRecorder<IGraphics> = VkCmdRecorder::new(&device.logic, command);
+
+            let render_pass_bi = RenderPassBI::with_pass(self.backend.render_pass, self.backend.framebuffers[i])
+                .render_extent(dimension)
+                .set_clear_values(self.backend.clear_values());
+
+            recorder.begin_record()?
+                .begin_render_pass(render_pass_bi)
+                .set_viewport(0, &[viewport])
+                .set_scissor(0, &[scissor]);
+
+            // Solid shading.
+            recorder.bind_pipeline(self.pipelines.solid);
+            self.model.record_command(&recorder, &render_params);
+
+            // Vertex normals generated by geometry shader.
+            if let Some(normal_pipeline) = self.pipelines.normal {
+                recorder.bind_pipeline(normal_pipeline);
+                self.model.record_command(&recorder, &render_params);
+            }
+
+            self.backend.record_ui_command(&recorder, i);
+
+            recorder
+                .end_render_pass()
+                .end_record()?;
+        }
+
+        Ok(())
+    }
+
+    fn update_uniforms(&mut self, image_index: usize) -> VkResult<()> {
+
+        self.ubo_data.view = self.camera.view_matrix();
+
+        unsafe {
+            let data_ptr = self.uniform_buffers.get(image_index).info.get_mapped_data() as vkptr<UboVS>;
+            data_ptr.copy_from_nonoverlapping(&self.ubo_data, 1);
+        }
+
+        Ok(())
+    }
+}
+
+// Prepare model from glTF file.
+pub fn prepare_model(device: &mut VkDevice) -> VkResult<VkglTFModel> {
+
+    use vkbase::gltf::{GltfModelInfo, load_gltf};
+    use vkbase::gltf::{AttributeFlags, NodeAttachmentFlags, NormalGeneration};
+
+    let model_info = GltfModelInfo {
+        path: Path::new(MODEL_PATH),
+        attribute: AttributeFlags::POSITION | AttributeFlags::NORMAL, // specify model's vertices layout.
+        node: NodeAttachmentFlags::TRANSFORM_MATRIX, // specify model's node attachment layout.
+        transform: None,
+        normal_generation: NormalGeneration::Smooth,
+    };
+
+    let model = load_gltf(device, model_info)?;
+    Ok(model)
+}
+
+
+// The uniform data that will be transferred to shader.
+//
+// layout (set = 0, binding = 0) uniform UBO {
+//     mat4 projection;
+//     mat4 view;
+//     mat4 model;
+//     vec4 lightPos;
+// } ubo;
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+struct UboVS {
+    projection   : Mat4F,
+    view         : Mat4F,
+    model        : Mat4F,
+    light_pos    : Vec4F,
+}
+
+fn prepare_uniform(device: &mut VkDevice, ubo_data: &UboVS) -> VkResult<VmaBuffer> {
+
+    let uniform_buffer = {
+        let uniform_ci = BufferCI::new(mem::size_of::<UboVS>() as vkbytes)
+            .usage(vk::BufferUsageFlags::UNIFORM_BUFFER);
+        let allocation_ci = VmaAllocationCI::new(vma::MemoryUsage::CpuOnly, vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT)
+            .flags(vma::AllocationCreateFlags::MAPPED);
+        let uniform_allocation = device.vma.create_buffer(uniform_ci.as_ref(), allocation_ci.as_ref())
+            .map_err(VkErrorKind::Vma)?;
+        VmaBuffer::from(uniform_allocation)
+    };
+
+    // keep the uniform memory map during the program running.
+    unsafe {
+        let data_ptr = uniform_buffer.info.get_mapped_data() as vkptr<UboVS>;
+        debug_assert_ne!(data_ptr, ptr::null_mut());
+        data_ptr.copy_from_nonoverlapping(ubo_data, 1);
+    }
+
+    Ok(uniform_buffer)
+}
+
+struct DescriptorStaff {
+    pool   : vk::DescriptorPool,
+    sets   : FrameResource<vk::DescriptorSet>,
+    layout : vk::DescriptorSetLayout,
+}
+
+fn setup_descriptor(device: &VkDevice, uniform_buffers: &FrameResource<VmaBuffer>, model: &VkglTFModel) -> VkResult<DescriptorStaff> {
+
+    use vkbase::ci::descriptor::{DescriptorPoolCI, DescriptorSetLayoutCI};
+    use vkbase::ci::descriptor::{DescriptorSetAI, DescriptorBufferSetWI, DescriptorSetsUpdateCI};
+
+    // Descriptor Pool.
+    // one descriptor set for each uniform buffer.
+    let set_count = uniform_buffers.len() as _;
+    let descriptor_pool = DescriptorPoolCI::new(set_count)
+        .add_descriptor(vk::DescriptorType::UNIFORM_BUFFER, set_count)
+        .add_descriptor(vk::DescriptorType::UNIFORM_BUFFER_DYNAMIC, set_count)
+        .build(device)?;
+
+    // the uniforms are read by vertex shader in solid pipeline, and by geometry shader in normal pipeline.
+    let ubo_descriptor = vk::DescriptorSetLayoutBinding {
+        binding: 0,
+        descriptor_type: vk::DescriptorType::UNIFORM_BUFFER,
+        descriptor_count: 1,
+        stage_flags: vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::GEOMETRY,
+        p_immutable_samplers: ptr::null(),
+    };
+
+    let node_descriptor = vk::DescriptorSetLayoutBinding {
+        binding: 1,
+        descriptor_type: vk::DescriptorType::UNIFORM_BUFFER_DYNAMIC,
+        descriptor_count: 1,
+        stage_flags: vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::GEOMETRY,
+        p_immutable_samplers: ptr::null(),
+    };
+
+    let set_layout = DescriptorSetLayoutCI::new()
+        .add_binding(ubo_descriptor)
+        .add_binding(node_descriptor)
+        .build(device)?;
+
+    // Descriptor sets.
+    let descriptor_sets = FrameResource::new(uniform_buffers.len(), |i| {
+
+        let mut descriptor_sets = DescriptorSetAI::new(descriptor_pool)
+            .add_set_layout(set_layout)
+            .build(device)?;
+        let descriptor_set = descriptor_sets.remove(0);
+
+        let ubo_write_info = DescriptorBufferSetWI::new(descriptor_set, 0, vk::DescriptorType::UNIFORM_BUFFER)
+            .add_buffer(vk::DescriptorBufferInfo {
+                buffer: uniform_buffers.get(i).handle,
+                offset: 0,
+                range : mem::size_of::<UboVS>() as vkbytes,
+            });
+        let node_write_info = DescriptorBufferSetWI::new(descriptor_set, 1, vk::DescriptorType::UNIFORM_BUFFER_DYNAMIC)
+            .add_buffer(model.nodes.node_descriptor());
+
+        DescriptorSetsUpdateCI::new()
+            .add_write(&ubo_write_info)
+            .add_write(&node_write_info)
+            .update(device);
+
+        Ok(descriptor_set)
+    })?;
+
+    let descriptors = DescriptorStaff {
+        pool   : descriptor_pool,
+        sets   : descriptor_sets,
+        layout : set_layout,
+    };
+    Ok(descriptors)
+}
+
+fn setup_renderpass(device: &VkDevice, swapchain: &VkSwapchain) -> VkResult<RenderPass> {
+
+    use vkbase::ci::pipeline::{RenderGraph, GraphPass};
+
+    // The render graph derives the attachment descriptions and subpass dependencies from the usage of attachments.
+    let mut graph = RenderGraph::new();
+    let color = graph.add_output(swapchain.backend_format, vk::ImageLayout::PRESENT_SRC_KHR); // Attachment 0 is color.
+    let depth = graph.add_transient(device.phy.depth_format); // Attachment 1 is depth-stencil.
+
+    graph.add_pass(GraphPass::new()
+        .write_color(color)
+        .write_depth(depth));
+
+    let render_pass = graph.render_pass_ci().build_with_attachments(device)?;
+
+    Ok(render_pass)
+}
+
+fn prepare_pipelines(device: &VkDevice, model: &VkglTFModel, render_pass: vk::RenderPass, set_layout: vk::DescriptorSetLayout) -> VkResult<PipelineStaff> {
+
+    use vkbase::ci::pipeline::*;
+
+    let viewport_state = ViewportSCI::new()
+        .add_viewport(vk::Viewport::default())
+        .add_scissor(vk::Rect2D::default());
+
+    let rasterization_state = RasterizationSCI::new()
+        .polygon(vk::PolygonMode::FILL)
+        .cull_face(vk::CullModeFlags::BACK, vk::FrontFace::CLOCKWISE);
+
+    let blend_attachment = BlendAttachmentSCI::new();
+    let blend_state = ColorBlendSCI::new()
+        .add_attachment(blend_attachment);
+
+    let depth_stencil_state = DepthStencilSCI::new()
+        .depth_test(true, true, vk::CompareOp::LESS_OR_EQUAL);
+
+    let dynamic_state = DynamicSCI::new()
+        .add_dynamic(vk::DynamicState::VIEWPORT)
+        .add_dynamic(vk::DynamicState::SCISSOR);
+
+    let push_constant_ranges = PushConstantRanges::new()
+        .push_sized(vk::ShaderStageFlags::VERTEX, model.materials.material_size())
+        .build(device)?;
+
+    // Pipeline Layout.
+    let pipeline_layout = PipelineLayoutCI::new()
+        .add_set_layout(set_layout)
+        .add_push_constant_ranges(&push_constant_ranges)
+        .build(device)?;
+
+    let mut pipeline_ci = GraphicsPipelineCI::new(render_pass, pipeline_layout);
+
+    pipeline_ci.set_vertex_input(model.meshes.vertex_input.clone());
+    model.meshes.check_pipeline(&pipeline_ci)?;
+    pipeline_ci.set_viewport(viewport_state);
+    pipeline_ci.set_rasterization(rasterization_state);
+    pipeline_ci.set_depth_stencil(depth_stencil_state);
+    pipeline_ci.set_color_blend(blend_state);
+    pipeline_ci.set_dynamic(dynamic_state);
+
+    let mut shader_compiler = vkbase::utils::shaderc::VkShaderCompiler::new()?;
+
+    let solid_pipeline = {
+
+        let vert_codes = shader_compiler.compile_from_path(Path::new(MESH_VERTEX_SHADER_SOURCE_PATH), shaderc::ShaderKind::Vertex, "[Vertex Shader]", "main")?;
+        let frag_codes = shader_compiler.compile_from_path(Path::new(MESH_FRAGMENT_SHADER_SOURCE_PATH), shaderc::ShaderKind::Fragment, "[Fragment Shader]", "main")?;
+
+        let vert_module = ShaderModuleCI::new(vert_codes).build(device)?;
+        let frag_module = ShaderModuleCI::new(frag_codes).build(device)?;
+
+        let shaders = [
+            ShaderStageCI::new(vk::ShaderStageFlags::VERTEX, vert_module),
+            ShaderStageCI::new(vk::ShaderStageFlags::FRAGMENT, frag_module),
+        ];
+        pipeline_ci.set_shaders(&shaders);
+
+        let pipeline = device.build(&pipeline_ci)?;
+
+        device.discard(vert_module);
+        device.discard(frag_module);
+
+        pipeline
+    };
+
+    // Geometry shader is not a mandatory Vulkan feature, and building a pipeline with geometry stage fails if it is not enabled.
+    let normal_pipeline = if device.phy.features_enabled().geometry_shader == vk::TRUE {
+
+        let vert_codes = shader_compiler.compile_from_path(Path::new(NORMAL_VERTEX_SHADER_SOURCE_PATH), shaderc::ShaderKind::Vertex, "[Vertex Shader]", "main")?;
+        let geom_codes = shader_compiler.compile_from_path(Path::new(NORMAL_GEOMETRY_SHADER_SOURCE_PATH), shaderc::ShaderKind::Geometry, "[Geometry Shader]", "main")?;
+        let frag_codes = shader_compiler.compile_from_path(Path::new(NORMAL_FRAGMENT_SHADER_SOURCE_PATH), shaderc::ShaderKind::Fragment, "[Fragment Shader]", "main")?;
+
+        let vert_module = ShaderModuleCI::new(vert_codes).build(device)?;
+        let geom_module = ShaderModuleCI::new(geom_codes).build(device)?;
+        let frag_module = ShaderModuleCI::new(frag_codes).build(device)?;
+
+        let shaders = [
+            ShaderStageCI::new(vk::ShaderStageFlags::VERTEX, vert_module),
+            ShaderStageCI::new(vk::ShaderStageFlags::GEOMETRY, geom_module),
+            ShaderStageCI::new(vk::ShaderStageFlags::FRAGMENT, frag_module),
+        ];
+        pipeline_ci.set_shaders(&shaders);
+
+        let pipeline = device.build(&pipeline_ci)?;
+
+        device.discard(vert_module);
+        device.discard(geom_module);
+        device.discard(frag_module);
+
+        Some(pipeline)
+    } else {
+        None
+    };
+
+    let result = PipelineStaff {
+        solid : solid_pipeline,
+        normal: normal_pipeline,
+        layout: pipeline_layout,
+    };
+    Ok(result)
+}
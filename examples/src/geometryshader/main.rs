@@ -0,0 +1,49 @@
+//!
+//! Vulkan Example - Geometry shader (vertex normals)
+//!
+//! Using a geometry shader to visualize the vertex normals of model.
+//!
+
+mod example;
+
+const WINDOW_WIDTH : u32 = 1280;
+const WINDOW_HEIGHT: u32 = 720;
+const WINDOW_TITLE: &'static str = "Vulkan Example - Geometry shader (vertex normals)";
+
+fn main() {
+
+    use vkbase::{WindowConfig, WindowContext};
+    use vkbase::context::{PhysicalDevConfig, VulkanContext};
+    use vkbase::ProcPipeline;
+
+    vkbase::utils::path::set_asset_root(vkexamples::ASSET_ROOT);
+
+    let mut win_config = WindowConfig::default();
+    win_config.dimension.width  = WINDOW_WIDTH;
+    win_config.dimension.height = WINDOW_HEIGHT;
+    win_config.title = WINDOW_TITLE.to_string();
+    win_config.is_cursor_hide = true;
+    win_config.is_cursor_grap = true;
+
+    let window = WindowContext::new(win_config)
+        .expect("Error when creating Window Context");
+
+    let mut phy_config = PhysicalDevConfig::default();
+    phy_config.request_features.geometry_shader = ash::vk::TRUE;
+
+    let mut vk_context = VulkanContext::new(&window)
+        .with_physical_device_config(phy_config)
+        .build().expect("Error when creating Vulkan Context");
+
+    let app = example::VulkanExample::new(&mut vk_context)
+        .expect("Error when initializing application");
+
+    let entry = ProcPipeline::new(window, vk_context).unwrap();
+
+    match entry.launch(app) {
+        | Ok(_) => {},
+        | Err(e) => {
+            eprintln!("{}", e)
+        }
+    }
+}
@@ -0,0 +1,53 @@
+
+#version 450
+
+layout (triangles) in;
+layout (line_strip, max_vertices = 6) out;
+
+layout (location = 0) in vec3 inNormal[];
+
+layout (location = 0) out vec3 outColor;
+
+layout (set = 0, binding = 0) uniform UBO {
+	mat4 projection;
+	mat4 view;
+	mat4 model;
+	vec4 lightPos;
+} ubo;
+
+layout (set = 0, binding = 1) uniform NodeAttachments {
+	mat4 transform;
+} node_attachments;
+
+in gl_PerVertex {
+	vec4 gl_Position;
+} gl_in[];
+
+out gl_PerVertex {
+	vec4 gl_Position;
+};
+
+const float NORMAL_LENGTH = 0.1;
+
+void main() {
+
+	mat4 model = ubo.model * node_attachments.transform;
+	mat4 view_proj = ubo.projection * ubo.view;
+
+	// emit one line along the normal for each vertex of triangle.
+	for (int i = 0; i < gl_in.length(); i++) {
+
+		vec3 pos = (model * gl_in[i].gl_Position).xyz;
+		vec3 normal = normalize(mat3(model) * inNormal[i]);
+
+		gl_Position = view_proj * vec4(pos, 1.0);
+		outColor = vec3(1.0, 0.0, 0.0);
+		EmitVertex();
+
+		gl_Position = view_proj * vec4(pos + normal * NORMAL_LENGTH, 1.0);
+		outColor = vec3(0.0, 0.0, 1.0);
+		EmitVertex();
+
+		EndPrimitive();
+	}
+}
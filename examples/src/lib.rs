@@ -2,31 +2,102 @@
 use ash::vk;
 use ash::version::DeviceV1_0;
 
-use lazy_static::lazy_static;
-
 use vkbase::ci::VkObjectBuildableCI;
 use vkbase::ci::sync::SemaphoreCI;
-use vkbase::ci::image::{ImageCI, ImageViewCI};
-use vkbase::ci::vma::{VmaImage, VmaAllocationCI};
-use vkbase::ui::{UIRenderer, TextInfo, TextID, TextType, TextHAlign};
+use vkbase::ci::image::{ImageCI, ImageViewCI, ImageBarrierCI};
+use vkbase::ci::pipeline::{AttachmentDescCI, DepthStencilSCI, GraphicsPipelineCI, RenderPass};
+use vkbase::ci::vma::VmaImage;
+use vkbase::ui::{UIRenderer, TextInfo, TextID, TextType, TextHAlign, GraphID};
 
 use vkbase::context::{VkDevice, VkSwapchain};
+use vkbase::command::{VkCmdRecorder, IGraphics, CmdGraphicsApi};
 use vkbase::utils::color::VkColor;
-use vkbase::vkuint;
-use vkbase::{VkResult, VkError, VkErrorKind};
+use vkbase::{DepthProjection, FrameAction};
+use vkbase::{vkuint, vkfloat};
+use vkbase::{VkResult, VkError};
+
+/// The background color of examples in sRGB space, which is converted to the space of swapchain format by `VkExampleBackend::clear_values`.
+pub const DEFAULT_CLEAR_COLOR: VkColor = VkColor::new(0.025, 0.025, 0.025, 1.0);
+
+/// The root directory of shaders and assets used in examples(the workspace directory).
+///
+/// Each example passes it to `vkbase::utils::path::set_asset_root` at startup, so that shaders and assets are resolved against the workspace directory, regardless of current working directory.
+pub const ASSET_ROOT: &'static str = concat!(env!("CARGO_MANIFEST_DIR"), "/..");
+
+/// The way to initialize the color attachment at the beginning of each frame.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ColorLoadMode {
+    /// Clear the color attachment every frame by `vk::AttachmentLoadOp::CLEAR`(the default).
+    Clear,
+    /// Keep the content of the last frame rendered to the same image by `vk::AttachmentLoadOp::LOAD`,
+    /// which is useful for the effects accumulating across frames(etc. trails).
+    ///
+    /// `vk::AttachmentLoadOp::LOAD` requires the content of attachment to be in a valid layout,
+    /// so the render pass must use `vk::ImageLayout::PRESENT_SRC_KHR` as the initial layout of color attachment(see `VkExampleBackend::color_attachment`).
+    /// Because the swapchain images are in `UNDEFINED` layout when they are first acquired,
+    /// `VkExampleBackend::record_accumulate_barrier` must be recorded before the render pass to initialize their layout.
+    Accumulate,
+}
+
+/// The depth test configuration shared by the pipelines of examples.
+#[derive(Debug, Clone)]
+pub struct DepthConfig {
+
+    /// `compare_op` is the comparison operator used in depth test.
+    pub compare_op: vk::CompareOp,
+    /// `is_enable_write` controls whether depth writes are enabled.
+    pub is_enable_write: bool,
+    /// `clear_depth` is the value that depth attachment is cleared to, which should be the farthest depth.
+    pub clear_depth: vkfloat,
+    /// `projection` is the depth mapping that the projection matrix of camera must use to match `compare_op`.
+    pub projection: DepthProjection,
+}
+
+impl Default for DepthConfig {
+
+    fn default() -> DepthConfig {
+        DepthConfig {
+            compare_op: vk::CompareOp::LESS_OR_EQUAL,
+            is_enable_write: true,
+            clear_depth: 1.0,
+            projection: DepthProjection::Standard,
+        }
+    }
+}
+
+impl DepthConfig {
+
+    /// The configuration for reverse-Z, where the near plane is mapped to depth 1.0 and the far plane to depth 0.0.
+    pub fn reverse_z() -> DepthConfig {
+        DepthConfig {
+            compare_op: vk::CompareOp::GREATER_OR_EQUAL,
+            is_enable_write: true,
+            clear_depth: 0.0,
+            projection: DepthProjection::Reversed,
+        }
+    }
 
-lazy_static! {
+    /// Get the viewport depth range covering the nearest `fraction` of depth, which is drawn in front of the rest of scene.
+    ///
+    /// It's [0.0, `fraction`] by default, and [1.0 - `fraction`, 1.0] for reverse-Z(see `vkbase::utils::viewport::viewport_depth_range`).
+    pub fn near_depth_range(&self, fraction: vkfloat) -> (vkfloat, vkfloat) {
 
-    pub static ref DEFAULT_CLEAR_VALUES: Vec<vk::ClearValue> = vec![
-        vk::ClearValue { color: vk::ClearColorValue { float32: [0.025, 0.025, 0.025, 1.0] } },
-        vk::ClearValue { depth_stencil: vk::ClearDepthStencilValue { depth: 1.0, stencil: 0 } },
-    ];
+        match self.projection {
+            | DepthProjection::Standard => (0.0, fraction),
+            | DepthProjection::Reversed
+            | DepthProjection::ReversedInfinite => (1.0 - fraction, 1.0),
+        }
+    }
 }
 
 pub struct VkExampleBackend {
 
+    /// the dimension of swapchain images, in physical pixels.
     pub dimension: vk::Extent2D,
-    pub render_pass: vk::RenderPass,
+    /// the dimension of swapchain images in logical pixels, which is used to place UI texts.
+    pub logical_dimension: vk::Extent2D,
+    /// the render pass of swapchain images, which validates the clear values of `RenderPassBI::with_pass` in debug build.
+    pub render_pass: RenderPass,
     pub framebuffers: Vec<vk::Framebuffer>,
 
     pub await_rendering: vk::Semaphore,
@@ -35,11 +106,21 @@ pub struct VkExampleBackend {
     /// render command buffer for each framebuffer.
     pub commands: Vec<vk::CommandBuffer>,
 
-    pub ui_renderer: UIRenderer,
+    /// the renderer of text overlay, or None if the backend is created without UI(see `VkExampleBackend::with_ui`).
+    pub ui_renderer: Option<UIRenderer>,
     fps_text_id: Option<TextID>,
+    fps_graph_id: Option<GraphID>,
+
+    /// the format of swapchain images, which decides the space of clear color.
+    color_format: vk::Format,
 
     depth_image: DepthImage,
     is_use_depth_attachment: bool,
+    depth_config: DepthConfig,
+
+    color_load_mode: ColorLoadMode,
+    /// the color attachments waiting to be cleared(only used in accumulate mode).
+    pending_clears: PendingClears,
 }
 
 struct DepthImage {
@@ -47,24 +128,83 @@ struct DepthImage {
     view : vk::ImageView,
 }
 
+/// Whether the color attachment of each swapchain image should be cleared in its next frame.
+struct PendingClears {
+    is_pending: Vec<bool>,
+}
+
+impl PendingClears {
+
+    fn new(image_count: usize) -> PendingClears {
+        PendingClears { is_pending: vec![true; image_count] }
+    }
+
+    fn request_all(&mut self) {
+        self.is_pending.iter_mut().for_each(|is_pending| *is_pending = true);
+    }
+
+    fn is_pending(&self, image_index: usize) -> bool {
+        self.is_pending[image_index]
+    }
+
+    /// Return true if the clear of `image_index` was pending.
+    fn finish(&mut self, image_index: usize) -> bool {
+        ::std::mem::replace(&mut self.is_pending[image_index], false)
+    }
+}
+
+fn color_attachment_for(mode: ColorLoadMode, format: vk::Format) -> AttachmentDescCI {
+
+    match mode {
+        | ColorLoadMode::Clear => {
+            AttachmentDescCI::new(format)
+                .op(vk::AttachmentLoadOp::CLEAR, vk::AttachmentStoreOp::STORE)
+                .layout(vk::ImageLayout::UNDEFINED, vk::ImageLayout::PRESENT_SRC_KHR)
+        },
+        | ColorLoadMode::Accumulate => {
+            AttachmentDescCI::new(format)
+                .op(vk::AttachmentLoadOp::LOAD, vk::AttachmentStoreOp::STORE)
+                .layout(vk::ImageLayout::PRESENT_SRC_KHR, vk::ImageLayout::PRESENT_SRC_KHR)
+        },
+    }
+}
+
 impl VkExampleBackend {
 
-    pub fn new(device: &mut VkDevice, swapchain: &VkSwapchain, renderpass: vk::RenderPass) -> VkResult<VkExampleBackend> {
+    pub fn new(device: &mut VkDevice, swapchain: &VkSwapchain, renderpass: RenderPass) -> VkResult<VkExampleBackend> {
+        VkExampleBackend::with_ui(device, swapchain, renderpass, true)
+    }
+
+    /// Create the backend, and skip the creation of UI renderer if `is_ui_enable` is false.
+    ///
+    /// Without UI, the text methods and `record_ui_command` do nothing, which removes the fixed cost of text overlay(etc. for benchmark).
+    pub fn with_ui(device: &mut VkDevice, swapchain: &VkSwapchain, renderpass: RenderPass, is_ui_enable: bool) -> VkResult<VkExampleBackend> {
 
         let dimension = swapchain.dimension;
         let (command_pool, commands) = setup_commands(device, swapchain.frame_in_flight as _)?;
         let depth_image = setup_depth_image(device, swapchain.dimension)?;
         let await_rendering = device.build(&SemaphoreCI::new())?;
 
-        let ui_renderer = UIRenderer::new(device, swapchain, renderpass)?;
+        let ui_renderer = if is_ui_enable {
+            Some(UIRenderer::new(device, swapchain, renderpass.handle)?)
+        } else {
+            None
+        };
 
         let mut target = VkExampleBackend {
             depth_image, await_rendering, ui_renderer,
             commands, command_pool, dimension,
+            logical_dimension: swapchain.logical_dimension(),
             fps_text_id: None,
+            fps_graph_id: None,
+            color_format: swapchain.backend_format,
             render_pass: renderpass,
             framebuffers: Vec::new(),
             is_use_depth_attachment: true,
+            depth_config: DepthConfig::default(),
+            color_load_mode: ColorLoadMode::Clear,
+            // the images have no valid content before their first frame.
+            pending_clears: PendingClears::new(swapchain.frame_in_flight()),
         };
         target.setup_framebuffers(device, swapchain)?;
 
@@ -75,6 +215,161 @@ impl VkExampleBackend {
         self.is_use_depth_attachment = is_enable;
     }
 
+    /// Set the depth test configuration. The pipelines must be created by `VkExampleBackend::depth_stencil_state` after this call.
+    pub fn set_depth_config(&mut self, config: DepthConfig) {
+        self.depth_config = config;
+    }
+
+    /// Switch between the default depth configuration and the one for reverse-Z.
+    pub fn enable_reverse_z(&mut self, is_enable: bool) {
+        self.depth_config = if is_enable { DepthConfig::reverse_z() } else { DepthConfig::default() };
+    }
+
+    #[inline]
+    pub fn depth_config(&self) -> &DepthConfig {
+        &self.depth_config
+    }
+
+    /// Create the depth-stencil state corresponding to current `DepthConfig`, with depth test enabled.
+    pub fn depth_stencil_state(&self) -> DepthStencilSCI {
+        DepthStencilSCI::new()
+            .depth_test(true, self.depth_config.is_enable_write, self.depth_config.compare_op)
+    }
+
+    /// Build the pipelines of depth pre-pass from `pipeline_ci`, which has been configured for the color pass.
+    ///
+    /// Return the depth-only pipeline and the color pipeline, and `pipeline_ci` is left in the state of depth-only pipeline.
+    /// Record the draw commands of scene with the depth-only pipeline first, then record them again with the color pipeline in the same subpass.
+    /// The pre-pass writes depth by current `DepthConfig`, and the color pass tests depth by `DepthStencilSCI::depth_equal`,
+    /// so that each pixel is shaded only once in overdraw-heavy scenes.
+    ///
+    /// The depth values must match between both passes, so the vertex stage should be reflected and declare `invariant gl_Position`,
+    /// otherwise an error is returned(see `GraphicsPipelineCI::check_invariant_position`).
+    pub fn build_depth_prepass_pipelines(&self, device: &VkDevice, pipeline_ci: &mut GraphicsPipelineCI) -> VkResult<(vk::Pipeline, vk::Pipeline)> {
+
+        if !self.is_use_depth_attachment {
+            return Err(VkError::custom(String::from("Depth pre-pass requires the depth attachment of example backend.")))
+        }
+
+        pipeline_ci.check_invariant_position()?;
+
+        pipeline_ci.set_depth_stencil(DepthStencilSCI::depth_equal());
+        let color_pipeline = device.build(&*pipeline_ci)?;
+
+        // the pre-pass must write depth even if current configuration disables depth writes.
+        pipeline_ci.set_depth_prepass(self.depth_stencil_state().depth_write(true));
+        let depth_pipeline = match device.build(&*pipeline_ci) {
+            | Ok(pipeline) => pipeline,
+            | Err(e) => {
+                device.discard(color_pipeline);
+                return Err(e)
+            },
+        };
+
+        Ok((depth_pipeline, color_pipeline))
+    }
+
+    /// Get the clear values of color and depth attachment, where depth is cleared to `DepthConfig::clear_depth`.
+    ///
+    /// The color is `DEFAULT_CLEAR_COLOR` converted to linear space if swapchain format is sRGB.
+    pub fn clear_values(&self) -> Vec<vk::ClearValue> {
+        vec![
+            self.clear_color_value(),
+            vk::ClearValue { depth_stencil: vk::ClearDepthStencilValue { depth: self.depth_config.clear_depth, stencil: 0 } },
+        ]
+    }
+
+    /// Get the clear value of color attachment, which is `DEFAULT_CLEAR_COLOR` in the space of swapchain format.
+    pub fn clear_color_value(&self) -> vk::ClearValue {
+        vk::ClearValue {
+            color: vk::ClearColorValue { float32: DEFAULT_CLEAR_COLOR.for_format(self.color_format).into() },
+        }
+    }
+
+    /// Set the way to initialize color attachment. The render pass must be created by `VkExampleBackend::color_attachment` after this call.
+    pub fn set_color_load_mode(&mut self, mode: ColorLoadMode) {
+        self.color_load_mode = mode;
+    }
+
+    #[inline]
+    pub fn color_load_mode(&self) -> ColorLoadMode {
+        self.color_load_mode
+    }
+
+    /// Create the description of color attachment of swapchain image corresponding to current `ColorLoadMode`.
+    pub fn color_attachment(&self, format: vk::Format) -> AttachmentDescCI {
+        color_attachment_for(self.color_load_mode, format)
+    }
+
+    /// Request to clear the color attachment in the next frame of every swapchain image.
+    ///
+    /// This only takes effect in `ColorLoadMode::Accumulate`, since the attachment is always cleared in `ColorLoadMode::Clear`.
+    /// The command buffers must be recorded again after this call, so that they contain the clear operation.
+    pub fn request_clear(&mut self) {
+        self.pending_clears.request_all();
+    }
+
+    /// Check if the command buffer of `image_index` needs to record a clear operation.
+    ///
+    /// In accumulate mode, the command buffer must be recorded again when this changes(etc. before and after the clear).
+    #[inline]
+    pub fn is_clear_pending(&self, image_index: usize) -> bool {
+        self.color_load_mode == ColorLoadMode::Accumulate && self.pending_clears.is_pending(image_index)
+    }
+
+    /// Mark the pending clear of `image_index` as done, which must be called after the frame of `image_index` has been submitted.
+    ///
+    /// Return true if a clear was pending, and then the command buffer of `image_index` must be recorded again to remove the clear operation.
+    /// The request is kept until this call, so the clear is not lost if the command buffer is recorded again before its frame is submitted.
+    pub fn finish_pending_clear(&mut self, image_index: usize) -> bool {
+        self.color_load_mode == ColorLoadMode::Accumulate && self.pending_clears.finish(image_index)
+    }
+
+    /// Initialize the layout of swapchain image in accumulate mode, which must be recorded before beginning the render pass.
+    ///
+    /// If a clear is pending, the previous content of `image` is discarded by transitioning it from `UNDEFINED` layout.
+    pub fn record_accumulate_barrier(&self, recorder: &VkCmdRecorder<IGraphics>, image: vk::Image, image_index: usize) {
+
+        if self.is_clear_pending(image_index) {
+
+            let sub_range = vk::ImageSubresourceRange {
+                aspect_mask: vk::ImageAspectFlags::COLOR,
+                base_mip_level: 0,
+                level_count   : 1,
+                base_array_layer: 0,
+                layer_count     : 1,
+            };
+
+            let barrier = ImageBarrierCI::new(image, sub_range)
+                .access_mask(vk::AccessFlags::empty(), vk::AccessFlags::COLOR_ATTACHMENT_READ | vk::AccessFlags::COLOR_ATTACHMENT_WRITE)
+                .layout(vk::ImageLayout::UNDEFINED, vk::ImageLayout::PRESENT_SRC_KHR);
+
+            recorder.image_pipeline_barrier(vk::PipelineStageFlags::TOP_OF_PIPE, vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT, vk::DependencyFlags::empty(), &[barrier.into()]);
+        }
+    }
+
+    /// Clear the color attachment(attachment 0) if a clear is pending in accumulate mode, which must be recorded inside the render pass.
+    ///
+    /// The request of `image_index` is kept until `finish_pending_clear` is called.
+    pub fn record_pending_clear(&self, recorder: &VkCmdRecorder<IGraphics>, image_index: usize) {
+
+        if self.is_clear_pending(image_index) {
+
+            let clear_attachment = vk::ClearAttachment {
+                aspect_mask: vk::ImageAspectFlags::COLOR,
+                color_attachment: 0,
+                clear_value: self.clear_color_value(),
+            };
+            let clear_rect = vk::ClearRect {
+                rect: vk::Rect2D { offset: vk::Offset2D { x: 0, y: 0 }, extent: self.dimension },
+                base_array_layer: 0,
+                layer_count: 1,
+            };
+
+            recorder.clear_attachments(&[clear_attachment], &[clear_rect]);
+        }
+    }
+
     fn setup_framebuffers(&mut self, device: &VkDevice, swapchain: &VkSwapchain) -> VkResult<()> {
 
         use vkbase::ci::pipeline::FramebufferCI;
@@ -84,7 +379,7 @@ impl VkExampleBackend {
 
         for i in 0..swapchain.frame_in_flight() {
 
-            let mut framebuffer_ci = FramebufferCI::new_2d(self.render_pass, self.dimension)
+            let mut framebuffer_ci = FramebufferCI::new_2d(self.render_pass.handle, self.dimension)
                 .add_attachment(swapchain.images[i].view); // color attachment is the view of the swapchain image.
 
             if self.is_use_depth_attachment {
@@ -98,10 +393,14 @@ impl VkExampleBackend {
         Ok(())
     }
 
-    pub fn swapchain_reload(&mut self, device: &mut VkDevice, new_chain: &VkSwapchain, render_pass: vk::RenderPass) -> VkResult<()> {
+    pub fn swapchain_reload(&mut self, device: &mut VkDevice, new_chain: &VkSwapchain, render_pass: RenderPass) -> VkResult<()> {
 
         self.dimension = new_chain.dimension;
-        self.ui_renderer.swapchain_reload(device, new_chain, render_pass)?;
+        self.logical_dimension = new_chain.logical_dimension();
+        self.color_format = new_chain.backend_format;
+        if let Some(ref mut ui_renderer) = self.ui_renderer {
+            ui_renderer.swapchain_reload(device, new_chain, render_pass.handle)?;
+        }
 
         let mut new_depth_image = setup_depth_image(device, self.dimension)?;
         std::mem::swap(&mut new_depth_image, &mut self.depth_image);
@@ -115,6 +414,9 @@ impl VkExampleBackend {
         self.render_pass = render_pass;
         self.setup_framebuffers(device, new_chain)?;
 
+        // the new swapchain images have no valid content.
+        self.pending_clears = PendingClears::new(new_chain.frame_in_flight());
+
         unsafe {
             device.logic.handle.reset_command_pool(self.command_pool, vk::CommandPoolResetFlags::empty())
                 .map_err(|_| VkError::device("Reset Command Pool"))?;
@@ -124,55 +426,151 @@ impl VkExampleBackend {
 
     pub fn set_basic_ui(&mut self, device: &VkDevice, title: &str) -> VkResult<()> {
 
+        if self.ui_renderer.is_none() {
+            return Ok(())
+        }
+
         let title_text = TextInfo {
             content: String::from(title),
-            scale: 12.0,
+            scale: 20.0,
             align: TextHAlign::Left,
             color: VkColor::WHITE,
             location: vk::Offset2D { x: 5, y: 0 },
+            pivot: (0.0, 0.0),
+            shadow: None,
             r#type: TextType::Static,
         };
 
         let device_text = TextInfo {
             content: device.phy.device_name.clone(),
-            scale: 12.0,
+            scale: 20.0,
             align: TextHAlign::Left,
             color: VkColor::WHITE,
             location: vk::Offset2D { x: 5, y: 40 },
+            pivot: (0.0, 0.0),
+            shadow: None,
             r#type: TextType::Static,
         };
 
         let fps_text = TextInfo {
             content: String::from("FPS: 00.00"),
-            scale: 12.0,
+            scale: 20.0,
             align: TextHAlign::Left,
             color: VkColor::WHITE,
             location: vk::Offset2D { x: 5, y: 80 },
+            pivot: (0.0, 0.0),
+            shadow: None,
             r#type: TextType::Dynamic { capacity: 15 },
         };
 
-        self.ui_renderer.add_text(title_text)?;
-        self.ui_renderer.add_text(device_text)?;
-        self.fps_text_id = Some(self.ui_renderer.add_text(fps_text)?);
+        self.add_text(title_text)?;
+        self.add_text(device_text)?;
+        self.fps_text_id = self.add_text(fps_text)?;
+
+        Ok(())
+    }
+
+    /// Add a graph of recent frame times into `rect`, which is updated by `update_fps_text` each frame.
+    ///
+    /// The graph is written by `update_ui_frame`, which must be called in `RenderWorkflow::render_frame`.
+    /// It does nothing if the backend has no UI.
+    pub fn add_fps_graph(&mut self, rect: vk::Rect2D) -> VkResult<()> {
 
+        if let Some(ref mut ui_renderer) = self.ui_renderer {
+            let graph_id = ui_renderer.add_graph(rect, &[], VkColor::new(0.2, 0.9, 0.3, 0.8))?;
+            self.fps_graph_id = Some(graph_id);
+        }
         Ok(())
     }
 
     pub fn update_fps_text(&mut self, inputer: &vkbase::EventController) {
 
+        if let (Some(ui_renderer), Some(graph_id)) = (self.ui_renderer.as_mut(), self.fps_graph_id) {
+            ui_renderer.change_graph(graph_id, inputer.fps_counter.frame_times());
+        }
+
         // update text on fps per second.
         if inputer.fps_counter.is_tick_second() {
 
-            if let Some(text_id) = self.fps_text_id {
+            if self.fps_text_id.is_some() {
                 let fps = format!("FPS: {}", inputer.fps_counter.fps());
-                self.ui_renderer.change_text(fps, text_id);
+                self.change_text(fps, self.fps_text_id);
             }
         }
     }
 
+    /// Check if the backend is created with UI renderer.
+    #[inline]
+    pub fn is_ui_enable(&self) -> bool {
+        self.ui_renderer.is_some()
+    }
+
+    /// Add `text` to UI renderer, and return its id, or None if the backend has no UI.
+    pub fn add_text(&mut self, text: TextInfo) -> VkResult<Option<TextID>> {
+
+        if let Some(ref mut ui_renderer) = self.ui_renderer {
+            ui_renderer.add_text(text).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Change the content of text `text_id`, which does nothing if the backend has no UI.
+    pub fn change_text(&mut self, content: String, text_id: Option<TextID>) {
+
+        if let (Some(ui_renderer), Some(text_id)) = (self.ui_renderer.as_mut(), text_id) {
+            ui_renderer.change_text(content, text_id);
+        }
+    }
+
+    /// Get the action that UI requires to show the latest texts, which should be returned by `RenderWorkflow::receive_input`
+    /// if no other action is requested.
+    ///
+    /// It's `FrameAction::CommandRerecord` if `add_text` or `change_text` used characters missing from the font atlas(etc. non-ASCII characters),
+    /// so that the atlas is grown by `update_glyph_atlas` in `RenderWorkflow::rerecord_commands`, where the device is idle.
+    pub fn ui_action(&self) -> FrameAction {
+
+        match self.ui_renderer {
+            | Some(ref ui_renderer) if ui_renderer.has_missing_glyphs() => FrameAction::CommandRerecord,
+            | _ => FrameAction::Rendering,
+        }
+    }
+
+    /// Add the characters used by texts but missing from the font atlas(etc. non-ASCII characters) to the atlas.
+    ///
+    /// The device must be idle, so call it in `RenderWorkflow::rerecord_commands` before recording commands(see `ui_action`).
+    /// It's called by `swapchain_reload` automatically.
+    /// Return true if the atlas has been regenerated, in which case the UI commands must be recorded again.
+    pub fn update_glyph_atlas(&mut self, device: &mut VkDevice) -> VkResult<bool> {
+
+        if let Some(ref mut ui_renderer) = self.ui_renderer {
+            ui_renderer.update_glyph_atlas(device)
+        } else {
+            Ok(false)
+        }
+    }
+
+    /// Record the drawing commands of UI into the command buffer of swapchain image `image_index`, which does nothing if the backend has no UI.
+    pub fn record_ui_command(&self, recorder: &VkCmdRecorder<IGraphics>, image_index: usize) {
+
+        if let Some(ref ui_renderer) = self.ui_renderer {
+            ui_renderer.record_command(recorder, image_index);
+        }
+    }
+
+    /// Write the UI changes for swapchain image `image_index` before submitting its commands, which does nothing if the backend has no UI.
+    pub fn update_ui_frame(&mut self, image_index: usize) {
+
+        if let Some(ref mut ui_renderer) = self.ui_renderer {
+            ui_renderer.update_frame(image_index);
+        }
+    }
+
     pub fn discard_by(self, device: &mut VkDevice) -> VkResult<()> {
 
-        self.ui_renderer.discard_by(device)?;
+        if let Some(ui_renderer) = self.ui_renderer {
+            ui_renderer.discard_by(device)?;
+        }
 
         device.discard(self.render_pass);
         device.discard(&self.framebuffers);
@@ -190,19 +588,14 @@ impl VkExampleBackend {
 
 fn setup_depth_image(device: &mut VkDevice, dimension: vk::Extent2D) -> VkResult<DepthImage> {
 
-    let image = {
-        let depth_ci = ImageCI::new_2d(device.phy.depth_format, dimension)
-            .usages(vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT);
-        let allocation_ci = VmaAllocationCI::new(vma::MemoryUsage::GpuOnly, vk::MemoryPropertyFlags::DEVICE_LOCAL);
-        let depth_allocation = device.vma.create_image(
-            depth_ci.as_ref(), allocation_ci.as_ref())
-            .map_err(VkErrorKind::Vma)?;
-        VmaImage::from(depth_allocation)
-    };
+    // the depth image is never sampled, so its memory can be lazily allocated.
+    let depth_ci = ImageCI::new_2d(device.phy.depth_format, dimension)
+        .usages(vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT);
+    let image = device.create_attachment_image(depth_ci, true)?;
 
     let view = ImageViewCI::new(image.handle, vk::ImageViewType::TYPE_2D, device.phy.depth_format)
         .sub_range(vk::ImageSubresourceRange {
-            aspect_mask: vk::ImageAspectFlags::DEPTH | vk::ImageAspectFlags::STENCIL,
+            aspect_mask: device.phy.depth_aspect_mask(),
             base_mip_level: 0,
             level_count   : 1,
             base_array_layer: 0,
@@ -226,3 +619,47 @@ fn setup_commands(device: &VkDevice, buffer_count: vkuint) -> VkResult<(vk::Comm
 
     Ok((command_pool, command_buffers))
 }
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn near_depth_range_follows_depth_config() {
+
+        assert_eq!(DepthConfig::default().near_depth_range(0.1), (0.0, 0.1));
+
+        let (min_depth, max_depth) = DepthConfig::reverse_z().near_depth_range(0.1);
+        assert!((min_depth - 0.9).abs() < 1e-6);
+        assert_eq!(max_depth, 1.0);
+    }
+
+    #[test]
+    fn accumulate_mode_loads_color_attachment() {
+
+        let accumulate: vk::AttachmentDescription = color_attachment_for(ColorLoadMode::Accumulate, vk::Format::B8G8R8A8_UNORM).into();
+        assert_eq!(accumulate.load_op, vk::AttachmentLoadOp::LOAD);
+        assert_eq!(accumulate.initial_layout, vk::ImageLayout::PRESENT_SRC_KHR);
+
+        let clear: vk::AttachmentDescription = color_attachment_for(ColorLoadMode::Clear, vk::Format::B8G8R8A8_UNORM).into();
+        assert_eq!(clear.load_op, vk::AttachmentLoadOp::CLEAR);
+    }
+
+    #[test]
+    fn clear_request_lasts_one_frame() {
+
+        let mut clears = PendingClears::new(2);
+        // the images have no valid content in their first frame.
+        assert!(clears.finish(0) && clears.finish(1));
+        assert!(!clears.is_pending(0) && !clears.is_pending(1));
+
+        clears.request_all();
+        // recording the commands does not consume the request, only finishing the frame does.
+        assert!(clears.is_pending(0));
+        assert!(clears.finish(0));
+        assert!(!clears.is_pending(0));
+        assert!(!clears.finish(0));
+        assert!(clears.is_pending(1));
+    }
+}
@@ -2,21 +2,20 @@
 use ash::vk;
 
 use std::ptr;
-use std::mem;
 use std::path::Path;
 
 use vkbase::context::{VkDevice, VkSwapchain};
 use vkbase::ci::VkObjectBuildableCI;
-use vkbase::ci::buffer::BufferCI;
-use vkbase::ci::vma::{VmaBuffer, VmaAllocationCI};
+use vkbase::ci::pipeline::RenderPass;
 use vkbase::ci::shader::{ShaderModuleCI, ShaderStageCI};
 use vkbase::gltf::VkglTFModel;
 use vkbase::ui::{TextInfo, TextType, TextHAlign};
 use vkbase::context::VulkanContext;
 use vkbase::utils::color::VkColor;
-use vkbase::{FlightCamera, FrameAction};
-use vkbase::{vkbytes, vkptr, Vec3F, Mat4F, Vec4F};
-use vkbase::{VkResult, VkErrorKind};
+use vkbase::utils::uniform::FrameUniforms;
+use vkbase::{FlightCamera, FrameAction, FrameResource};
+use vkbase::{vkuint, vkfloat, Vec3F, Mat4F, Vec4F};
+use vkbase::VkResult;
 
 use vkexamples::VkExampleBackend;
 
@@ -26,7 +25,11 @@ const TOON_VERTEX_SHADER_SOURCE_PATH       : &'static str = "examples/src/pipeli
 const TOON_FRAGMENT_SHADER_SOURCE_PATH     : &'static str = "examples/src/pipelines/toon.frag.glsl";
 const WIREFRAME_VERTEX_SHADER_SOURCE_PATH  : &'static str = "examples/src/pipelines/wireframe.vert.glsl";
 const WIREFRAME_FRAGMENT_SHADER_SOURCE_PATH: &'static str = "examples/src/pipelines/wireframe.frag.glsl";
+const BARYCENTRIC_GEOMETRY_SHADER_SOURCE_PATH: &'static str = "examples/src/pipelines/wireframe.geom.glsl";
+const BARYCENTRIC_FRAGMENT_SHADER_SOURCE_PATH: &'static str = "examples/src/pipelines/wireframe_barycentric.frag.glsl";
 const MODEL_PATH: &'static str = "assets/models/treasure_smooth.gltf";
+/// The angular speed of light rotating around the model(radians per second).
+const LIGHT_ROTATE_SPEED: vkfloat = 0.5;
 
 
 pub struct VulkanExample {
@@ -34,7 +37,8 @@ pub struct VulkanExample {
     backend: VkExampleBackend,
 
     model: VkglTFModel,
-    uniform_buffer: VmaBuffer,
+    /// one uniform slot for each swapchain image, so that updating the uniform of current frame never touches the slot still read by other frames in flight.
+    uniform_buffers: FrameUniforms<UboVS>,
 
     pipelines: PipelineStaff,
     descriptors: DescriptorStaff,
@@ -42,7 +46,43 @@ pub struct VulkanExample {
     ubo_data: UboVS,
     camera: FlightCamera,
 
-    is_toggle_event: bool,
+    display_mode: DisplayMode,
+    /// whether the key cycling `display_mode` is held in last frame, so that holding it only cycles once.
+    is_cycle_key_held: bool,
+}
+
+/// The key to cycle `DisplayMode`.
+const CYCLE_DISPLAY_KEY: winit::VirtualKeyCode = winit::VirtualKeyCode::Tab;
+/// The count of pipelines drawn in this example(phong, toon and wireframe).
+const PANEL_COUNT: usize = 3;
+
+/// The way to arrange the pipelines on screen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DisplayMode {
+    /// Draw all pipelines side by side(the default).
+    SideBySide,
+    /// Draw only the pipeline at this index in full screen.
+    Single(usize),
+}
+
+impl DisplayMode {
+
+    /// Get the next mode in the order of side by side, phong, toon and wireframe.
+    fn next(self) -> DisplayMode {
+        match self {
+            | DisplayMode::SideBySide => DisplayMode::Single(0),
+            | DisplayMode::Single(index) if index + 1 < PANEL_COUNT => DisplayMode::Single(index + 1),
+            | DisplayMode::Single(_) => DisplayMode::SideBySide,
+        }
+    }
+
+    /// Get the count of columns that screen is split into.
+    fn column_count(self) -> vkuint {
+        match self {
+            | DisplayMode::SideBySide => PANEL_COUNT as vkuint,
+            | DisplayMode::Single(_)  => 1,
+        }
+    }
 }
 
 struct PipelineStaff {
@@ -50,6 +90,64 @@ struct PipelineStaff {
     wireframe : vk::Pipeline,
     toon      : vk::Pipeline,
     layout: vk::PipelineLayout,
+    /// the way `wireframe` pipeline draws edges, or None if neither way is supported by device.
+    wireframe_mode: Option<WireframeMode>,
+    line_mode: LineWidthMode,
+}
+
+/// The width of lines in pixels.
+const LINE_WIDTH: vkfloat = 2.0;
+
+/// The ways to render lines wider than 1 pixel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LineWidthMode {
+    /// Set the width by `set_line_width`, which requires `wide_lines` feature.
+    Dynamic,
+    /// Expand the lines in screen space by `WireframeMode::Barycentric`, whose width is set by specialization constant.
+    Expanded,
+}
+
+impl LineWidthMode {
+
+    fn select(device: &VkDevice) -> LineWidthMode {
+        if device.phy.features_enabled().wide_lines == vk::TRUE {
+            LineWidthMode::Dynamic
+        } else {
+            LineWidthMode::Expanded
+        }
+    }
+}
+
+/// The ways to render the wireframe of model.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum WireframeMode {
+    /// Rasterize polygon edges by `vk::PolygonMode::LINE`, which requires `fill_mode_non_solid` feature.
+    PolygonLine,
+    /// Fill the polygons, and discard the fragments away from edges by barycentric coordinates generated in geometry shader.
+    ///
+    /// This requires `geometry_shader` feature instead of `fill_mode_non_solid`.
+    Barycentric,
+}
+
+impl WireframeMode {
+
+    /// Pick the wireframe path supported by the enabled features of device.
+    ///
+    /// `WireframeMode::PolygonLine` is preferred, unless the lines can not be widened by `line_mode`.
+    fn select(device: &VkDevice, line_mode: LineWidthMode) -> Option<WireframeMode> {
+
+        let features = device.phy.features_enabled();
+        let is_polygon_line_support = features.fill_mode_non_solid == vk::TRUE;
+        let is_barycentric_support  = features.geometry_shader == vk::TRUE;
+
+        match line_mode {
+            | LineWidthMode::Dynamic if is_polygon_line_support => Some(WireframeMode::PolygonLine),
+            | _ if is_barycentric_support  => Some(WireframeMode::Barycentric),
+            // render 1 pixel lines if expanding is not available.
+            | _ if is_polygon_line_support => Some(WireframeMode::PolygonLine),
+            | _ => None,
+        }
+    }
 }
 
 impl VulkanExample {
@@ -60,9 +158,13 @@ impl VulkanExample {
         let swapchain = &context.swapchain;
         let dimension = swapchain.dimension;
 
+        let render_pass = setup_renderpass(device, &context.swapchain)?;
+        let backend = VkExampleBackend::new(device, swapchain, render_pass)?;
+
         let mut camera = FlightCamera::new()
             .place_at(Vec3F::new(0.25, 6.25, 8.75))
             .screen_aspect_ratio((dimension.width as f32 / 3.0) / dimension.height as f32)
+            .depth_projection(backend.depth_config().projection)
             .pitch(-45.0)
             .build();
         camera.set_move_speed(50.0);
@@ -74,18 +176,16 @@ impl VulkanExample {
             light_pos  : Vec4F::new(0.0, 2.0, 1.0, 0.0),
         };
 
-        let render_pass = setup_renderpass(device, &context.swapchain)?;
-        let backend = VkExampleBackend::new(device, swapchain, render_pass)?;
-
         let model = prepare_model(device)?;
-        let uniform_buffer = prepare_uniform(device, &ubo_data)?;
-        let descriptors = setup_descriptor(device, &uniform_buffer, &model)?;
+        let uniform_buffers = FrameUniforms::new(device, swapchain.frame_in_flight(), &ubo_data)?;
+        let descriptors = setup_descriptor(device, &uniform_buffers, &model)?;
 
-        let pipelines = prepare_pipelines(device, &model, backend.render_pass, descriptors.layout)?;
+        let pipelines = prepare_pipelines(device, &model, &backend, descriptors.layout)?;
 
         let target = VulkanExample {
-            backend, model, uniform_buffer, descriptors, pipelines, camera, ubo_data,
-            is_toggle_event: false,
+            backend, model, uniform_buffers, descriptors, pipelines, camera, ubo_data,
+            display_mode: DisplayMode::SideBySide,
+            is_cycle_key_held: false,
         };
         Ok(target)
     }
@@ -97,38 +197,45 @@ impl vkbase::RenderWorkflow for VulkanExample {
 
         self.backend.set_basic_ui(device, super::WINDOW_TITLE)?;
 
-        let screen_width  = self.backend.dimension.width  as i32;
-        let screen_height = self.backend.dimension.height as i32;
+        // texts are placed in logical pixels.
+        let screen_width  = self.backend.logical_dimension.width  as i32;
+        let screen_height = self.backend.logical_dimension.height as i32;
 
         let phong_text = TextInfo {
             content: String::from("Phong Shading Pipeline"),
-            scale: 16.0,
+            scale: 27.0,
             align: TextHAlign::Center,
             color: VkColor::WHITE,
             location: vk::Offset2D { x: screen_width / 6, y: screen_height / 8 * 7 },
+            pivot: (0.0, 0.0),
+            shadow: None,
             r#type: TextType::Static,
         };
-        self.backend.ui_renderer.add_text(phong_text)?;
+        self.backend.add_text(phong_text)?;
 
         let toon_text = TextInfo {
             content: String::from("Toon Shading Pipeline"),
-            scale: 16.0,
+            scale: 27.0,
             align: TextHAlign::Center,
             color: VkColor::WHITE,
             location: vk::Offset2D { x: screen_width / 6 * 3, y: screen_height / 8 * 7 },
+            pivot: (0.0, 0.0),
+            shadow: None,
             r#type: TextType::Static,
         };
-        self.backend.ui_renderer.add_text(toon_text)?;
+        self.backend.add_text(toon_text)?;
 
         let wireframe_text = TextInfo {
             content: String::from("Wireframe Pipeline"),
-            scale: 16.0,
+            scale: 27.0,
             align: TextHAlign::Center,
             color: VkColor::WHITE,
             location: vk::Offset2D { x: screen_width / 6 * 5 , y: screen_height / 8 * 7 },
+            pivot: (0.0, 0.0),
+            shadow: None,
             r#type: TextType::Static,
         };
-        self.backend.ui_renderer.add_text(wireframe_text)?;
+        self.backend.add_text(wireframe_text)?;
 
         self.record_commands(device, self.backend.dimension)?;
 
@@ -137,9 +244,10 @@ impl vkbase::RenderWorkflow for VulkanExample {
 
     fn render_frame(&mut self, device: &mut VkDevice, device_available: vk::Fence, await_present: vk::Semaphore, image_index: usize, _delta_time: f32) -> VkResult<vk::Semaphore> {
 
-        if self.is_toggle_event {
-            self.update_uniforms()?;
-        }
+        // the frame rendered to this image last time has finished(guaranteed by `ProcPipeline`),
+        // so the uniform slot of this image is free to be written now.
+        self.uniform_buffers.mark_waited(image_index);
+        self.update_uniforms(image_index)?;
 
         let submit_ci = vkbase::ci::device::SubmitCI::new()
             .add_wait(vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT, await_present)
@@ -161,7 +269,7 @@ impl vkbase::RenderWorkflow for VulkanExample {
 
         let render_pass = setup_renderpass(device, new_chain)?;
         self.backend.swapchain_reload(device, new_chain, render_pass)?;
-        self.pipelines = prepare_pipelines(device, &self.model, self.backend.render_pass, self.descriptors.layout)?;
+        self.pipelines = prepare_pipelines(device, &self.model, &self.backend, self.descriptors.layout)?;
 
         self.record_commands(device, self.backend.dimension)?;
 
@@ -176,15 +284,40 @@ impl vkbase::RenderWorkflow for VulkanExample {
                 return FrameAction::Terminal
             }
 
-            self.is_toggle_event = true;
             self.camera.receive_input(inputer, delta_time);
-        } else {
-            self.is_toggle_event = false;
         }
 
         self.backend.update_fps_text(inputer);
 
-        FrameAction::Rendering
+        // rotate the light around the model over time.
+        let light_angle = inputer.fps_counter.elapsed_secs() * LIGHT_ROTATE_SPEED;
+        self.ubo_data.light_pos = Vec4F::new(light_angle.sin() * 2.0, 2.0, light_angle.cos() * 2.0, 0.0);
+
+        // the command buffers are pre-recorded, so they must be recorded again to draw the pipelines in new arrangement.
+        let is_cycle_key_pressed = inputer.key.is_key_pressed(CYCLE_DISPLAY_KEY);
+        let is_cycle_triggered = is_cycle_key_pressed && !self.is_cycle_key_held;
+        self.is_cycle_key_held = is_cycle_key_pressed;
+
+        if is_cycle_triggered {
+            self.display_mode = self.display_mode.next();
+
+            let dimension = self.backend.dimension;
+            self.reset_camera_dimension(dimension.width, dimension.height);
+
+            return FrameAction::CommandRerecord
+        }
+
+        self.backend.ui_action()
+    }
+
+    fn on_resize(&mut self, width: vkuint, height: vkuint) {
+        self.reset_camera_dimension(width, height);
+    }
+
+    fn rerecord_commands(&mut self, device: &mut VkDevice) -> VkResult<()> {
+
+        self.backend.update_glyph_atlas(device)?;
+        self.record_commands(device, self.backend.dimension)
     }
 
     fn deinit(self, device: &mut VkDevice) -> VkResult<()> {
@@ -197,7 +330,7 @@ impl vkbase::RenderWorkflow for VulkanExample {
         device.discard(self.pipelines.wireframe);
         device.discard(self.pipelines.layout);
 
-        device.vma_discard(self.uniform_buffer)?;
+        self.uniform_buffers.discard_by(device)?;
         device.vma_discard(self.model)?;
         self.backend.discard_by(device)
     }
@@ -216,61 +349,53 @@ impl VulkanExample {
 
             use vkbase::command::{VkCmdRecorder, CmdGraphicsApi, IGraphics};
             use vkbase::ci::pipeline::RenderPassBI;
+            use vkbase::utils::viewport::viewport_split;
 
             let render_params = vkbase::gltf::ModelRenderParams {
-                descriptor_set : self.descriptors.set,
+                descriptor_set : *self.descriptors.sets.get(i),
                 pipeline_layout: self.pipelines.layout,
                 material_stage : Some(vk::ShaderStageFlags::VERTEX),
             };
 
-            let mut viewport = vk::Viewport {
-                x: 0.0, y: 0.0,
-                width: dimension.width as f32, height: dimension.height as f32,
-                min_depth: 0.0, max_depth: 1.0,
-            };
-
             let recorder: VkCmdRecorder<IGraphics> = VkCmdRecorder::new(&device.logic, command);
 
-            let render_pass_bi = RenderPassBI::new(self.backend.render_pass, self.backend.framebuffers[i])
+            let render_pass_bi = RenderPassBI::with_pass(self.backend.render_pass, self.backend.framebuffers[i])
                 .render_extent(dimension)
-                .set_clear_values(vkexamples::DEFAULT_CLEAR_VALUES.clone());
+                .set_clear_values(self.backend.clear_values());
 
             recorder.begin_record()?
                 .begin_render_pass(render_pass_bi)
                 .set_scissor(0, &[scissor]);
 
-            { // Left: Solid colored
-                viewport.width = dimension.width as f32 / 3.0;
-                recorder
-                    .set_viewport(0, &[viewport])
-                    .bind_pipeline(self.pipelines.phong);
-                self.model.record_command(&recorder, &render_params);
+            // Line width > 1.0f only if wide lines feature is supported, otherwise the lines are expanded by shader.
+            if self.pipelines.line_mode == LineWidthMode::Dynamic {
+                recorder.set_line_width(LINE_WIDTH);
             }
 
-            { // Center: Toon
-                viewport.x = dimension.width as f32 / 3.0;
-                recorder
-                    .set_viewport(0, &[viewport])
-                    .bind_pipeline(self.pipelines.toon);
+            // Left: Solid colored, Center: Toon, Right: Wireframe.
+            let panels = [
+                (self.pipelines.phong, true),
+                (self.pipelines.toon, true),
+                (self.pipelines.wireframe, self.pipelines.wireframe_mode.is_some()),
+            ];
 
-                // Line width > 1.0f only if wide lines feature is supported.
-                if device.phy.features_enabled().wide_lines == vk::TRUE {
-                    recorder.set_line_width(2.0);
-                }
-                self.model.record_command(&recorder, &render_params);
-            }
+            for (panel_index, &(pipeline, is_available)) in panels.iter().enumerate() {
+
+                let column = match self.display_mode {
+                    | DisplayMode::SideBySide => panel_index as vkuint,
+                    | DisplayMode::Single(index) if index == panel_index => 0,
+                    | DisplayMode::Single(_) => continue,
+                };
 
-            { // Right: Wireframe
-                if device.phy.features_enabled().fill_mode_non_solid == vk::TRUE {
-                    viewport.x = dimension.width as f32 / 3.0 * 2.0;
+                if is_available {
                     recorder
-                        .set_viewport(0, &[viewport])
-                        .bind_pipeline(self.pipelines.wireframe);
+                        .set_viewport(0, &[viewport_split(dimension, self.display_mode.column_count(), column)])
+                        .bind_pipeline(pipeline);
                     self.model.record_command(&recorder, &render_params);
                 }
             }
 
-            self.backend.ui_renderer.record_command(&recorder);
+            self.backend.record_ui_command(&recorder, i);
 
             recorder
                 .end_render_pass()
@@ -280,16 +405,18 @@ impl VulkanExample {
         Ok(())
     }
 
-    fn update_uniforms(&mut self) -> VkResult<()> {
+    /// Keep the aspect ratio of camera matching the viewport of each pipeline, which is a column of the swapchain images of `width` x `height`.
+    fn reset_camera_dimension(&mut self, width: vkuint, height: vkuint) {
 
-        self.ubo_data.view = self.camera.view_matrix();
+        self.camera.reset_screen_dimension(width / self.display_mode.column_count(), height);
+        self.ubo_data.projection = self.camera.proj_matrix();
+    }
 
-        unsafe {
-            let data_ptr = self.uniform_buffer.info.get_mapped_data() as vkptr<UboVS>;
-            data_ptr.copy_from_nonoverlapping(&self.ubo_data, 1);
-        }
+    fn update_uniforms(&mut self, image_index: usize) -> VkResult<()> {
 
-        Ok(())
+        self.ubo_data.view = self.camera.view_matrix();
+
+        self.uniform_buffers.update(image_index, &self.ubo_data)
     }
 }
 
@@ -297,13 +424,14 @@ impl VulkanExample {
 pub fn prepare_model(device: &mut VkDevice) -> VkResult<VkglTFModel> {
 
     use vkbase::gltf::{GltfModelInfo, load_gltf};
-    use vkbase::gltf::{AttributeFlags, NodeAttachmentFlags};
+    use vkbase::gltf::{AttributeFlags, NodeAttachmentFlags, NormalGeneration};
 
     let model_info = GltfModelInfo {
         path: Path::new(MODEL_PATH),
         attribute: AttributeFlags::POSITION | AttributeFlags::NORMAL, // specify model's vertices layout.
         node: NodeAttachmentFlags::TRANSFORM_MATRIX, // specify model's node attachment layout.
         transform: None,
+        normal_generation: NormalGeneration::Smooth,
     };
 
     let model = load_gltf(device, model_info)?;
@@ -328,43 +456,23 @@ struct UboVS {
     light_pos    : Vec4F,
 }
 
-fn prepare_uniform(device: &mut VkDevice, ubo_data: &UboVS) -> VkResult<VmaBuffer> {
-
-    let uniform_buffer = {
-        let uniform_ci = BufferCI::new(mem::size_of::<UboVS>() as vkbytes)
-            .usage(vk::BufferUsageFlags::UNIFORM_BUFFER);
-        let allocation_ci = VmaAllocationCI::new(vma::MemoryUsage::CpuOnly, vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT)
-            .flags(vma::AllocationCreateFlags::MAPPED);
-        let uniform_allocation = device.vma.create_buffer(uniform_ci.as_ref(), allocation_ci.as_ref())
-            .map_err(VkErrorKind::Vma)?;
-        VmaBuffer::from(uniform_allocation)
-    };
-
-    // keep the uniform memory map during the program running.
-    unsafe {
-        let data_ptr = uniform_buffer.info.get_mapped_data() as vkptr<UboVS>;
-        debug_assert_ne!(data_ptr, ptr::null_mut());
-        data_ptr.copy_from_nonoverlapping(ubo_data, 1);
-    }
-
-    Ok(uniform_buffer)
-}
-
 struct DescriptorStaff {
     pool   : vk::DescriptorPool,
-    set    : vk::DescriptorSet,
+    sets   : FrameResource<vk::DescriptorSet>,
     layout : vk::DescriptorSetLayout,
 }
 
-fn setup_descriptor(device: &VkDevice, uniform_buffer: &VmaBuffer, model: &VkglTFModel) -> VkResult<DescriptorStaff> {
+fn setup_descriptor(device: &VkDevice, uniform_buffers: &FrameUniforms<UboVS>, model: &VkglTFModel) -> VkResult<DescriptorStaff> {
 
     use vkbase::ci::descriptor::{DescriptorPoolCI, DescriptorSetLayoutCI};
-    use vkbase::ci::descriptor::{DescriptorSetAI, DescriptorBufferSetWI, DescriptorSetsUpdateCI};
+    use vkbase::ci::descriptor::{DescriptorSetAI, DescriptorSetWriter};
 
     // Descriptor Pool.
-    let descriptor_pool = DescriptorPoolCI::new(1)
-        .add_descriptor(vk::DescriptorType::UNIFORM_BUFFER, 1)
-        .add_descriptor(vk::DescriptorType::UNIFORM_BUFFER_DYNAMIC, 1)
+    // one descriptor set for each uniform buffer.
+    let set_count = uniform_buffers.len() as _;
+    let descriptor_pool = DescriptorPoolCI::new(set_count)
+        .add_descriptor(vk::DescriptorType::UNIFORM_BUFFER, set_count)
+        .add_descriptor(vk::DescriptorType::UNIFORM_BUFFER_DYNAMIC, set_count)
         .build(device)?;
 
     // in phone.vert.glsl:
@@ -372,7 +480,6 @@ fn setup_descriptor(device: &VkDevice, uniform_buffer: &VmaBuffer, model: &VkglT
     //     mat4 projection;
     //     mat4 view;
     //     mat4 model;
-    //     mat4 y_correction;
     //     vec4 lightPos;
     // } ubo;
     let ubo_descriptor = vk::DescriptorSetLayoutBinding {
@@ -395,40 +502,36 @@ fn setup_descriptor(device: &VkDevice, uniform_buffer: &VmaBuffer, model: &VkglT
         p_immutable_samplers: ptr::null(),
     };
 
-    let set_layout = DescriptorSetLayoutCI::new()
+    let set_layout_ci = DescriptorSetLayoutCI::new()
         .add_binding(ubo_descriptor)
-        .add_binding(node_descriptor)
-        .build(device)?;
+        .add_binding(node_descriptor);
+    let set_layout = set_layout_ci.build(device)?;
 
-    // Descriptor set.
-    let mut descriptor_sets = DescriptorSetAI::new(descriptor_pool)
-        .add_set_layout(set_layout)
-        .build(device)?;
-    let descriptor_set = descriptor_sets.remove(0);
-
-    let ubo_write_info = DescriptorBufferSetWI::new(descriptor_set, 0, vk::DescriptorType::UNIFORM_BUFFER)
-        .add_buffer(vk::DescriptorBufferInfo {
-            buffer: uniform_buffer.handle,
-            offset: 0,
-            range : mem::size_of::<UboVS>() as vkbytes,
-        });
-    let node_write_info = DescriptorBufferSetWI::new(descriptor_set, 1, vk::DescriptorType::UNIFORM_BUFFER_DYNAMIC)
-        .add_buffer(model.nodes.node_descriptor());
-
-    DescriptorSetsUpdateCI::new()
-        .add_write(&ubo_write_info)
-        .add_write(&node_write_info)
-        .update(device);
+    // Descriptor sets.
+    let descriptor_sets = FrameResource::new(uniform_buffers.len(), |i| {
+
+        let mut descriptor_sets = DescriptorSetAI::new(descriptor_pool)
+            .add_set_layout(set_layout)
+            .build(device)?;
+        let descriptor_set = descriptor_sets.remove(0);
+
+        let writer = DescriptorSetWriter::new(descriptor_set, &set_layout_ci)
+            .buffer(0, uniform_buffers.descriptor_info(i))
+            .buffer(1, model.nodes.node_descriptor());
+        writer.build()?.update(device);
+
+        Ok(descriptor_set)
+    })?;
 
     let descriptors = DescriptorStaff {
         pool   : descriptor_pool,
-        set    : descriptor_set,
+        sets   : descriptor_sets,
         layout : set_layout,
     };
     Ok(descriptors)
 }
 
-fn setup_renderpass(device: &VkDevice, swapchain: &VkSwapchain) -> VkResult<vk::RenderPass> {
+fn setup_renderpass(device: &VkDevice, swapchain: &VkSwapchain) -> VkResult<RenderPass> {
 
     use vkbase::ci::pipeline::RenderPassCI;
     use vkbase::ci::pipeline::{AttachmentDescCI, SubpassDescCI, SubpassDependencyCI};
@@ -461,12 +564,12 @@ fn setup_renderpass(device: &VkDevice, swapchain: &VkSwapchain) -> VkResult<vk::
         .add_subpass(subpass_description)
         .add_dependency(dependency0)
         .add_dependency(dependency1)
-        .build(device)?;
+        .build_with_attachments(device)?;
 
     Ok(render_pass)
 }
 
-fn prepare_pipelines(device: &VkDevice, model: &VkglTFModel, render_pass: vk::RenderPass, set_layout: vk::DescriptorSetLayout) -> VkResult<PipelineStaff> {
+fn prepare_pipelines(device: &VkDevice, model: &VkglTFModel, backend: &VkExampleBackend, set_layout: vk::DescriptorSetLayout) -> VkResult<PipelineStaff> {
 
     use vkbase::ci::pipeline::*;
 
@@ -482,14 +585,15 @@ fn prepare_pipelines(device: &VkDevice, model: &VkglTFModel, render_pass: vk::Re
     let blend_state = ColorBlendSCI::new()
         .add_attachment(blend_attachment);
 
-    let depth_stencil_state = DepthStencilSCI::new()
-        .depth_test(true, true, vk::CompareOp::LESS_OR_EQUAL);
+    // follow the depth configuration of backend, so that the depth test matches the cleared depth value.
+    let depth_stencil_state = backend.depth_stencil_state();
 
     let mut dynamic_state = DynamicSCI::new()
         .add_dynamic(vk::DynamicState::VIEWPORT)
         .add_dynamic(vk::DynamicState::SCISSOR);
 
-    if device.phy.features_enabled().wide_lines == vk::TRUE {
+    let line_mode = LineWidthMode::select(device);
+    if line_mode == LineWidthMode::Dynamic {
         dynamic_state = dynamic_state.add_dynamic(vk::DynamicState::LINE_WIDTH)
     };
 
@@ -506,9 +610,10 @@ fn prepare_pipelines(device: &VkDevice, model: &VkglTFModel, render_pass: vk::Re
         .build(device)?;
 
     // base pipeline.
-    let mut pipeline_ci = GraphicsPipelineCI::new(render_pass, pipeline_layout);
+    let mut pipeline_ci = GraphicsPipelineCI::new(backend.render_pass.handle, pipeline_layout);
 
     pipeline_ci.set_vertex_input(model.meshes.vertex_input.clone());
+    model.meshes.check_pipeline(&pipeline_ci)?;
     pipeline_ci.set_viewport(viewport_state);
     pipeline_ci.set_rasterization(rasterization_state.clone());
     pipeline_ci.set_depth_stencil(depth_stencil_state);
@@ -573,31 +678,65 @@ fn prepare_pipelines(device: &VkDevice, model: &VkglTFModel, render_pass: vk::Re
         pipeline
     };
 
+    let wireframe_mode = WireframeMode::select(device, line_mode);
+
+    // the line width of barycentric wireframe is set by specialization constant(constant_id = 0).
+    let line_width_entry = vk::SpecializationMapEntry {
+        constant_id: 0,
+        offset: 0,
+        size: ::std::mem::size_of::<vkfloat>(),
+    };
+    let line_width_specialization = vk::SpecializationInfo {
+        map_entry_count: 1,
+        p_map_entries  : &line_width_entry,
+        data_size: ::std::mem::size_of::<vkfloat>(),
+        p_data: &LINE_WIDTH as *const vkfloat as _,
+    };
+
     let wireframe_pipeline = {
 
         let vert_codes = shader_compiler.compile_from_path(Path::new(WIREFRAME_VERTEX_SHADER_SOURCE_PATH), shaderc::ShaderKind::Vertex, "[Vertex Shader]", "main")?;
-        let frag_codes = shader_compiler.compile_from_path(Path::new(WIREFRAME_FRAGMENT_SHADER_SOURCE_PATH), shaderc::ShaderKind::Fragment, "[Fragment Shader]", "main")?;
-
         let vert_module = ShaderModuleCI::new(vert_codes)
             .build(device)?;
-        let frag_module = ShaderModuleCI::new(frag_codes).build(device)?;
 
-        let shaders = [
-            ShaderStageCI::new(vk::ShaderStageFlags::VERTEX, vert_module),
-            ShaderStageCI::new(vk::ShaderStageFlags::FRAGMENT, frag_module),
-        ];
+        let mut modules = vec![vert_module];
+        let mut shaders = vec![ShaderStageCI::new(vk::ShaderStageFlags::VERTEX, vert_module)];
+
+        match wireframe_mode {
+            | Some(WireframeMode::Barycentric) => {
+
+                let geom_codes = shader_compiler.compile_from_path(Path::new(BARYCENTRIC_GEOMETRY_SHADER_SOURCE_PATH), shaderc::ShaderKind::Geometry, "[Geometry Shader]", "main")?;
+                let frag_codes = shader_compiler.compile_from_path(Path::new(BARYCENTRIC_FRAGMENT_SHADER_SOURCE_PATH), shaderc::ShaderKind::Fragment, "[Fragment Shader]", "main")?;
+                let geom_module = ShaderModuleCI::new(geom_codes).build(device)?;
+                let frag_module = ShaderModuleCI::new(frag_codes).build(device)?;
+
+                modules.extend_from_slice(&[geom_module, frag_module]);
+                shaders.push(ShaderStageCI::new(vk::ShaderStageFlags::GEOMETRY, geom_module));
+                shaders.push(ShaderStageCI::new(vk::ShaderStageFlags::FRAGMENT, frag_module)
+                    .specialization(line_width_specialization));
+            },
+            | Some(WireframeMode::PolygonLine) | None => {
+
+                let frag_codes = shader_compiler.compile_from_path(Path::new(WIREFRAME_FRAGMENT_SHADER_SOURCE_PATH), shaderc::ShaderKind::Fragment, "[Fragment Shader]", "main")?;
+                let frag_module = ShaderModuleCI::new(frag_codes).build(device)?;
+
+                modules.push(frag_module);
+                shaders.push(ShaderStageCI::new(vk::ShaderStageFlags::FRAGMENT, frag_module));
+            },
+        }
         pipeline_ci.set_shaders(&shaders);
 
         // Non solid rendering is not a mandatory Vulkan feature.
-        if device.phy.features_enabled().fill_mode_non_solid == vk::TRUE {
+        if wireframe_mode == Some(WireframeMode::PolygonLine) {
             rasterization_state = rasterization_state.polygon(vk::PolygonMode::LINE);
             pipeline_ci.set_rasterization(rasterization_state);
         }
 
         let pipeline = device.build(&pipeline_ci)?;
 
-        device.discard(vert_module);
-        device.discard(frag_module);
+        for module in modules {
+            device.discard(module);
+        }
 
         pipeline
     };
@@ -609,6 +748,7 @@ fn prepare_pipelines(device: &VkDevice, model: &VkglTFModel, render_pass: vk::Re
         wireframe: wireframe_pipeline,
 
         layout: pipeline_layout,
+        wireframe_mode, line_mode,
     };
     Ok(result)
 }
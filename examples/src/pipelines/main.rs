@@ -3,6 +3,8 @@
 //!
 //! Using different pipelines in one single renderpass.
 //!
+//! Press Tab to cycle between drawing all pipelines side by side and drawing each of them in full screen.
+//!
 
 mod example;
 
@@ -16,6 +18,8 @@ fn main() {
     use vkbase::context::{PhysicalDevConfig, VulkanContext};
     use vkbase::ProcPipeline;
 
+    vkbase::utils::path::set_asset_root(vkexamples::ASSET_ROOT);
+
     let mut win_config = WindowConfig::default();
     win_config.dimension.width  = WINDOW_WIDTH;
     win_config.dimension.height = WINDOW_HEIGHT;
@@ -29,6 +33,8 @@ fn main() {
     let mut phy_config = PhysicalDevConfig::default();
     phy_config.request_features.fill_mode_non_solid = ash::vk::TRUE;
     phy_config.request_features.wide_lines = ash::vk::TRUE;
+    // used to render wireframe when fill_mode_non_solid is unsupported.
+    phy_config.request_features.geometry_shader = ash::vk::TRUE;
 
     let mut vk_context = VulkanContext::new(&window)
         .with_physical_device_config(phy_config)
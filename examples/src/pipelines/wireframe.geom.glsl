@@ -0,0 +1,34 @@
+
+#version 450
+
+layout (triangles) in;
+layout (triangle_strip, max_vertices = 3) out;
+
+layout (location = 0) in vec3 inColor[];
+
+layout (location = 0) out vec3 outColor;
+layout (location = 1) out vec3 outBarycentric;
+
+in gl_PerVertex {
+	vec4 gl_Position;
+} gl_in[];
+
+out gl_PerVertex {
+	vec4 gl_Position;
+};
+
+// Emit each triangle unchanged, attaching barycentric coordinates to its vertices.
+void main() {
+
+	const vec3 barycentrics[3] = vec3[](vec3(1.0, 0.0, 0.0), vec3(0.0, 1.0, 0.0), vec3(0.0, 0.0, 1.0));
+
+	for (int i = 0; i < 3; i++) {
+
+		outColor = inColor[i];
+		outBarycentric = barycentrics[i];
+		gl_Position = gl_in[i].gl_Position;
+		EmitVertex();
+	}
+
+	EndPrimitive();
+}
@@ -0,0 +1,23 @@
+
+#version 450
+
+layout (location = 0) in vec3 inColor;
+layout (location = 1) in vec3 inBarycentric;
+
+layout (location = 0) out vec4 outFragColor;
+
+// The width of lines in pixels, which is shared by the two triangles of each edge.
+layout (constant_id = 0) const float LINE_WIDTH = 2.0;
+
+void main() {
+
+	// the distance to each edge in pixels, estimated by the screen-space derivatives of barycentric coordinates.
+	vec3 edge_distance = inBarycentric / max(fwidth(inBarycentric), vec3(1e-6));
+	float nearest_edge = min(min(edge_distance.x, edge_distance.y), edge_distance.z);
+
+	if (nearest_edge > LINE_WIDTH * 0.5) {
+		discard;
+	}
+
+	outFragColor.rgb = inColor * 1.5;
+}
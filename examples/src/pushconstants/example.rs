@@ -8,6 +8,7 @@ use std::path::Path;
 
 use vkbase::context::{VkDevice, VkSwapchain};
 use vkbase::ci::VkObjectBuildableCI;
+use vkbase::ci::pipeline::RenderPass;
 use vkbase::ci::buffer::BufferCI;
 use vkbase::ci::vma::{VmaBuffer, VmaAllocationCI};
 use vkbase::gltf::VkglTFModel;
@@ -47,7 +48,7 @@ pub struct VulkanExample {
 /// layout(push_constant) uniform PushConsts {
 ///	    vec4 lightPos[lightCount];
 /// } pushConsts;
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy)]
 #[repr(C)]
 struct PushConstants {
     lights: [[f32; 4]; 6],
@@ -83,7 +84,7 @@ impl VulkanExample {
         let ubo_buffer = prepare_uniform(device)?;
         let descriptors = setup_descriptor(device, &ubo_buffer, &model)?;
 
-        let pipelines = prepare_pipelines(device, &model, backend.render_pass, descriptors.layout)?;
+        let pipelines = prepare_pipelines(device, &model, backend.render_pass.handle, descriptors.layout)?;
 
         let target = VulkanExample {
             backend, model, ubo_buffer, ubo_data, descriptors, pipelines, camera,
@@ -128,7 +129,7 @@ impl vkbase::RenderWorkflow for VulkanExample {
 
         let render_pass = setup_renderpass(device, new_chain)?;
         self.backend.swapchain_reload(device, new_chain, render_pass)?;
-        self.pipelines = prepare_pipelines(device, &self.model, self.backend.render_pass, self.descriptors.layout)?;
+        self.pipelines = prepare_pipelines(device, &self.model, self.backend.render_pass.handle, self.descriptors.layout)?;
 
         for command_index in 0..self.backend.commands.len() {
             self.record_command(device, command_index, self.backend.dimension)?;
@@ -162,7 +163,7 @@ impl vkbase::RenderWorkflow for VulkanExample {
         device.discard(self.descriptors.pool);
 
         device.discard(self.pipelines.pipeline);
-        device.discard(self.pipelines.layout);
+        self.pipelines.layout.discard_by(device);
 
         device.vma_discard(self.ubo_buffer)?;
         device.vma_discard(self.model)?;
@@ -225,40 +226,39 @@ impl VulkanExample {
         };
 
         let push_data = self.generate_push_data();
-        let push_data_ptr = unsafe {
-            vkbase::utils::memory::any_as_u8_slice(&push_data)
-        };
 
         let mut recorder: VkCmdRecorder<IGraphics> = VkCmdRecorder::new(&device.logic, command);
         recorder.set_usage(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT);
 
-        let render_pass_bi = RenderPassBI::new(self.backend.render_pass, self.backend.framebuffers[command_index])
+        let render_pass_bi = RenderPassBI::with_pass(self.backend.render_pass, self.backend.framebuffers[command_index])
             .render_extent(dimension)
-            .set_clear_values(vkexamples::DEFAULT_CLEAR_VALUES.clone());
-
-        recorder.begin_record()?
-            .begin_render_pass(render_pass_bi)
-            .set_viewport(0, &[viewport])
-            .set_scissor(0, &[scissor])
-            .bind_pipeline(self.pipelines.pipeline)
-            // Update light positions.
-            .push_constants(self.pipelines.layout, vk::ShaderStageFlags::VERTEX, 0, push_data_ptr);
-
-        let render_params = vkbase::gltf::ModelRenderParams {
-            descriptor_set : self.descriptors.set,
-            pipeline_layout: self.pipelines.layout,
-            material_stage : None,
-        };
+            .set_clear_values(self.backend.clear_values());
 
-        self.model.record_command(&recorder, &render_params);
+        // the recording is ended even if any command returns error.
+        recorder.record(|recorder| {
 
-        self.backend.ui_renderer.record_command(&recorder);
+            recorder
+                .begin_render_pass(render_pass_bi)
+                .set_viewport(0, &[viewport])
+                .set_scissor(0, &[scissor])
+                .bind_pipeline(self.pipelines.pipeline)
+                // Update light positions.
+                .push_constant_data(&self.pipelines.layout, vk::ShaderStageFlags::VERTEX, 0, &push_data);
 
-        recorder
-            .end_render_pass()
-            .end_record()?;
+            let render_params = vkbase::gltf::ModelRenderParams {
+                descriptor_set : self.descriptors.set,
+                pipeline_layout: self.pipelines.layout.handle,
+                material_stage : None,
+            };
 
-        Ok(())
+            self.model.record_command(recorder, &render_params);
+
+            self.backend.record_ui_command(recorder, command_index);
+
+            recorder.end_render_pass();
+
+            Ok(())
+        })
     }
 
     fn update(&mut self, delta_time: f32) {
@@ -282,7 +282,7 @@ impl VulkanExample {
 pub fn prepare_model(device: &mut VkDevice) -> VkResult<VkglTFModel> {
 
     use vkbase::gltf::{GltfModelInfo, load_gltf};
-    use vkbase::gltf::{AttributeFlags, NodeAttachmentFlags};
+    use vkbase::gltf::{AttributeFlags, NodeAttachmentFlags, NormalGeneration};
 
     let model_info = GltfModelInfo {
         path: Path::new(MODEL_PATH),
@@ -298,6 +298,7 @@ pub fn prepare_model(device: &mut VkDevice) -> VkResult<VkglTFModel> {
         // } dyn_node;
         node: NodeAttachmentFlags::TRANSFORM_MATRIX,
         transform: None,
+        normal_generation: NormalGeneration::Smooth,
     };
 
     let model = load_gltf(device, model_info)?;
@@ -416,7 +417,7 @@ fn setup_descriptor(device: &VkDevice, uniform_buffer: &VmaBuffer, model: &VkglT
 }
 
 
-fn setup_renderpass(device: &VkDevice, swapchain: &VkSwapchain) -> VkResult<vk::RenderPass> {
+fn setup_renderpass(device: &VkDevice, swapchain: &VkSwapchain) -> VkResult<RenderPass> {
 
     use vkbase::ci::pipeline::RenderPassCI;
     use vkbase::ci::pipeline::{AttachmentDescCI, SubpassDescCI, SubpassDependencyCI};
@@ -449,7 +450,7 @@ fn setup_renderpass(device: &VkDevice, swapchain: &VkSwapchain) -> VkResult<vk::
         .add_subpass(subpass_description)
         .add_dependency(dependency0)
         .add_dependency(dependency1)
-        .build(device)?;
+        .build_with_attachments(device)?;
 
     Ok(render_pass)
 }
@@ -457,7 +458,7 @@ fn setup_renderpass(device: &VkDevice, swapchain: &VkSwapchain) -> VkResult<vk::
 
 struct PipelineStaff {
     pipeline: vk::Pipeline,
-    layout: vk::PipelineLayout,
+    layout: vkbase::ci::pipeline::PipelineLayout,
 }
 
 fn prepare_pipelines(device: &VkDevice, model: &VkglTFModel, render_pass: vk::RenderPass, set_layout: vk::DescriptorSetLayout) -> VkResult<PipelineStaff> {
@@ -505,7 +506,7 @@ fn prepare_pipelines(device: &VkDevice, model: &VkglTFModel, render_pass: vk::Re
         .add_set_layout(set_layout)
         // Push constant ranges are part of the pipeline layout.
         .add_push_constants(push_constant_range)
-        .build(device)?;
+        .build_with_ranges(device)?;
     // ---------------------------------------------------------------------------------------
 
     // shaders
@@ -520,7 +521,7 @@ fn prepare_pipelines(device: &VkDevice, model: &VkglTFModel, render_pass: vk::Re
     let frag_module = ShaderModuleCI::new(frag_codes).build(device)?;
 
     // Pipeline.
-    let mut pipeline_ci = GraphicsPipelineCI::new(render_pass, layout);
+    let mut pipeline_ci = GraphicsPipelineCI::new(render_pass, layout.handle);
 
     let shaders = [
         ShaderStageCI::new(vk::ShaderStageFlags::VERTEX, vert_module),
@@ -529,6 +530,7 @@ fn prepare_pipelines(device: &VkDevice, model: &VkglTFModel, render_pass: vk::Re
     pipeline_ci.set_shaders(&shaders);
 
     pipeline_ci.set_vertex_input(model.meshes.vertex_input.clone());
+    model.meshes.check_pipeline(&pipeline_ci)?;
     pipeline_ci.set_viewport(viewport_state);
     pipeline_ci.set_depth_stencil(depth_stencil_state);
     pipeline_ci.set_rasterization(rasterization_state);
@@ -7,6 +7,7 @@ use std::path::Path;
 
 use vkbase::context::{VkDevice, VkSwapchain};
 use vkbase::ci::VkObjectBuildableCI;
+use vkbase::ci::pipeline::RenderPass;
 use vkbase::ci::buffer::BufferCI;
 use vkbase::ci::vma::{VmaBuffer, VmaAllocationCI};
 use vkbase::ci::shader::{ShaderModuleCI, ShaderStageCI};
@@ -79,7 +80,7 @@ impl VulkanExample {
         let ubo_buffer = prepare_uniform(device)?;
         let descriptors = setup_descriptor(device, &ubo_buffer, &model, &color_map)?;
 
-        let pipelines = prepare_pipelines(device, &model, backend.render_pass, descriptors.layout)?;
+        let pipelines = prepare_pipelines(device, &model, backend.render_pass.handle, descriptors.layout)?;
 
         let target = VulkanExample {
             backend, model, color_map, ubo_buffer, descriptors, pipelines, camera, ubo_data,
@@ -125,7 +126,7 @@ impl vkbase::RenderWorkflow for VulkanExample {
 
         let render_pass = setup_renderpass(device, new_chain)?;
         self.backend.swapchain_reload(device, new_chain, render_pass)?;
-        self.pipelines = prepare_pipelines(device, &self.model, self.backend.render_pass, self.descriptors.layout)?;
+        self.pipelines = prepare_pipelines(device, &self.model, self.backend.render_pass.handle, self.descriptors.layout)?;
 
         self.record_commands(device, self.backend.dimension)?;
 
@@ -197,9 +198,9 @@ impl VulkanExample {
 
             let recorder: VkCmdRecorder<IGraphics> = VkCmdRecorder::new(&device.logic, command);
 
-            let render_pass_bi = RenderPassBI::new(self.backend.render_pass, self.backend.framebuffers[i])
+            let render_pass_bi = RenderPassBI::with_pass(self.backend.render_pass, self.backend.framebuffers[i])
                 .render_extent(dimension)
-                .set_clear_values(vkexamples::DEFAULT_CLEAR_VALUES.clone());
+                .set_clear_values(self.backend.clear_values());
 
             recorder.begin_record()?
                 .begin_render_pass(render_pass_bi)
@@ -230,7 +231,7 @@ impl VulkanExample {
                 self.model.record_command(&recorder, &render_params);
             }
 
-            self.backend.ui_renderer.record_command(&recorder);
+            self.backend.record_ui_command(&recorder, i);
 
             recorder
                 .end_render_pass()
@@ -260,7 +261,7 @@ impl VulkanExample {
 pub fn prepare_model(device: &mut VkDevice) -> VkResult<VkglTFModel> {
 
     use vkbase::gltf::{GltfModelInfo, load_gltf};
-    use vkbase::gltf::{AttributeFlags, NodeAttachmentFlags};
+    use vkbase::gltf::{AttributeFlags, NodeAttachmentFlags, NormalGeneration};
 
     let model_info = GltfModelInfo {
         path: Path::new(MODEL_PATH),
@@ -269,6 +270,7 @@ pub fn prepare_model(device: &mut VkDevice) -> VkResult<VkglTFModel> {
         // specify model's node attachment layout.
         node: NodeAttachmentFlags::TRANSFORM_MATRIX,
         transform: None,
+        normal_generation: NormalGeneration::Smooth,
     };
 
     let model = load_gltf(device, model_info)?;
@@ -401,7 +403,7 @@ fn setup_descriptor(device: &VkDevice, ubo_buffer: &VmaBuffer, model: &VkglTFMod
     Ok(descriptors)
 }
 
-fn setup_renderpass(device: &VkDevice, swapchain: &VkSwapchain) -> VkResult<vk::RenderPass> {
+fn setup_renderpass(device: &VkDevice, swapchain: &VkSwapchain) -> VkResult<RenderPass> {
 
     use vkbase::ci::pipeline::RenderPassCI;
     use vkbase::ci::pipeline::{AttachmentDescCI, SubpassDescCI, SubpassDependencyCI};
@@ -434,7 +436,7 @@ fn setup_renderpass(device: &VkDevice, swapchain: &VkSwapchain) -> VkResult<vk::
         .add_subpass(subpass_description)
         .add_dependency(dependency0)
         .add_dependency(dependency1)
-        .build(device)?;
+        .build_with_attachments(device)?;
 
     Ok(render_pass)
 }
@@ -479,6 +481,7 @@ fn prepare_pipelines(device: &VkDevice, model: &VkglTFModel, render_pass: vk::Re
     let mut pipeline_ci = GraphicsPipelineCI::new(render_pass, pipeline_layout);
 
     pipeline_ci.set_vertex_input(model.meshes.vertex_input.clone());
+    model.meshes.check_pipeline(&pipeline_ci)?;
     pipeline_ci.set_viewport(viewport_state);
     pipeline_ci.set_rasterization(rasterization_state.clone());
     pipeline_ci.set_depth_stencil(depth_stencil_state);
@@ -0,0 +1,9 @@
+
+#version 450
+
+layout (location = 0) out vec4 outFragColor;
+
+void main() {
+
+	outFragColor = vec4(1.0, 1.0, 1.0, 1.0);
+}
@@ -0,0 +1,28 @@
+
+#version 450
+
+layout (location = 0) in vec3 inPos;
+layout (location = 1) in vec3 inNormal;
+
+layout (set = 0, binding = 0) uniform UBO {
+	mat4 projection;
+	mat4 view;
+	mat4 model;
+	vec4 lightPos;
+	float outlineWidth;
+} ubo;
+
+layout (set = 0, binding = 1) uniform NodeAttachments {
+	mat4 transform;
+} node_attachments;
+
+out gl_PerVertex {
+	vec4 gl_Position;
+};
+
+void main() {
+
+	// extrude the vertices along their normals to get a larger silhouette of model.
+	vec4 pos = vec4(inPos.xyz + inNormal * ubo.outlineWidth, 1.0);
+	gl_Position = ubo.projection * ubo.view * ubo.model * node_attachments.transform * pos;
+}
@@ -5,6 +5,7 @@ use std::path::Path;
 
 use vkbase::context::{VkDevice, VkSwapchain};
 use vkbase::ci::VkObjectBuildableCI;
+use vkbase::ci::pipeline::RenderPass;
 use vkbase::ci::shader::{ShaderModuleCI, ShaderStageCI};
 use vkbase::context::VulkanContext;
 use vkbase::utils::color::VkColor;
@@ -50,7 +51,7 @@ impl VulkanExample {
         let text_pool = TextPool::new(device, swapchain.dimension, hidpi_factor)?;
         let descriptors = setup_descriptor(device, &text_glyphs)?;
 
-        let pipelines = prepare_pipelines(device, swapchain.dimension, backend_res.render_pass, descriptors.layout)?;
+        let pipelines = prepare_pipelines(device, swapchain.dimension, backend_res.render_pass.handle, descriptors.layout)?;
 
         let target = VulkanExample {
             backend_res, descriptors, pipelines,
@@ -99,7 +100,7 @@ impl vkbase::RenderWorkflow for VulkanExample {
 
         let render_pass = setup_renderpass(device, new_chain)?;
         self.backend_res.swapchain_reload(device, new_chain, render_pass)?;
-        self.pipelines = prepare_pipelines(device, self.backend_res.dimension, self.backend_res.render_pass, self.descriptors.layout)?;
+        self.pipelines = prepare_pipelines(device, self.backend_res.dimension, self.backend_res.render_pass.handle, self.descriptors.layout)?;
 
         self.record_commands(device, self.backend_res.dimension)?;
 
@@ -143,9 +144,9 @@ impl VulkanExample {
 
             let recorder: VkCmdRecorder<IGraphics> = VkCmdRecorder::new(&device.logic, command);
 
-            let render_pass_bi = RenderPassBI::new(self.backend_res.render_pass, self.backend_res.framebuffers[i])
+            let render_pass_bi = RenderPassBI::with_pass(self.backend_res.render_pass, self.backend_res.framebuffers[i])
                 .render_extent(dimension)
-                .set_clear_values(vec![vkexamples::DEFAULT_CLEAR_VALUES[0].clone()]);
+                .set_clear_values(vec![self.backend_res.clear_color_value()]);
 
             recorder.begin_record()?
                 .begin_render_pass(render_pass_bi)
@@ -221,7 +222,7 @@ fn setup_descriptor(device: &VkDevice, glyphs: &GlyphImages) -> VkResult<Descrip
     Ok(descriptors)
 }
 
-fn setup_renderpass(device: &VkDevice, swapchain: &VkSwapchain) -> VkResult<vk::RenderPass> {
+fn setup_renderpass(device: &VkDevice, swapchain: &VkSwapchain) -> VkResult<RenderPass> {
 
     use vkbase::ci::pipeline::RenderPassCI;
     use vkbase::ci::pipeline::{AttachmentDescCI, SubpassDescCI, SubpassDependencyCI};
@@ -249,7 +250,7 @@ fn setup_renderpass(device: &VkDevice, swapchain: &VkSwapchain) -> VkResult<vk::
         .add_subpass(subpass_description)
         .add_dependency(dependency0)
         .add_dependency(dependency1)
-        .build(device)?;
+        .build_with_attachments(device)?;
 
     Ok(render_pass)
 }
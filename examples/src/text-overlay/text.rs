@@ -376,9 +376,10 @@ fn allocate_image(device: &VkDevice, image_bytes: Vec<u8>, image_dimension: vk::
     )).build(device)?;
     device.bind_memory(staging_buffer, staging_memory, 0)?;
 
-    let data_ptr = device.map_memory(staging_memory, 0, vk::WHOLE_SIZE)? as vkptr<u8>;
-    unsafe { data_ptr.copy_from_nonoverlapping(image_bytes.as_ptr(), image_bytes.len()); }
-    device.unmap_memory(staging_memory);
+    {
+        let mapped = device.map_scoped(staging_memory, 0, vk::WHOLE_SIZE)?;
+        unsafe { mapped.copy_from(&image_bytes); }
+    }
 
     // transfer image data from staging buffer to destination image.
     let command_pool = CommandPoolCI::new(device.logic.queues.transfer.family_index)
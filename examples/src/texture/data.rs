@@ -199,7 +199,7 @@ impl Texture {
         // Texture data contains 4 channels (RGBA) with unnormalized 8-bit values, this is the most commonly supported format.
         let format = vk::Format::R8G8B8A8_UNORM;
 
-        let tex_2d: gli::Texture2D = gli::load_ktx(texture_path)
+        let tex_2d: gli::Texture2D = gli::load_ktx(vkbase::utils::asset::local_asset_path(texture_path)?)
             .map_err(VkErrorKind::Gli)?;
 
         debug_assert!(!tex_2d.empty());
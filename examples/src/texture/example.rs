@@ -1,14 +1,15 @@
 
 use ash::vk;
 
-use std::ptr;
 use std::path::Path;
 
 use vkbase::context::{VulkanContext, VkDevice, VkSwapchain};
 use vkbase::ci::VkObjectBuildableCI;
+use vkbase::ci::pipeline::RenderPass;
 use vkbase::ci::vma::VmaBuffer;
 use vkbase::utils::color::VkColor;
-use vkbase::ui::{TextInfo, TextType, TextHAlign};
+use vkbase::utils::reflect::ShaderReflection;
+use vkbase::ui::{TextInfo, TextID, TextType, TextHAlign};
 use vkbase::{FlightCamera, FrameAction};
 use vkbase::{vkuint, vkptr, Vec3F, Vec4F};
 use vkbase::VkResult;
@@ -36,7 +37,7 @@ pub struct VulkanExample {
     pipelines: PipelineStaff,
     descriptors: DescriptorStaff,
 
-    lod_text_id: usize,
+    lod_text_id: Option<TextID>,
     is_toggle_event: bool,
 }
 
@@ -48,28 +49,29 @@ impl VulkanExample {
         let swapchain = &context.swapchain;
         let dimension = swapchain.dimension;
 
+        let render_pass = setup_renderpass(device, &context.swapchain)?;
+        let backend = VkExampleBackend::new(device, swapchain, render_pass)?;
+
         let mut camera = FlightCamera::new()
             .place_at(Vec3F::new(0.0, 0.0, 2.5))
             .screen_aspect_ratio(dimension.width as f32 / dimension.height as f32)
+            .depth_projection(backend.depth_config().projection)
             .build();
         camera.set_move_speed(5.0);
 
-        let render_pass = setup_renderpass(device, &context.swapchain)?;
-        let backend = VkExampleBackend::new(device, swapchain, render_pass)?;
-
         let (vertices, indices) = super::data::generate_quad(device)?;
         let (ubo_buffer, ubo_data) = UboVS::prepare_buffer(device, &camera)?;
         let texture = Texture::load_ktx(device, Path::new(TEXTURE_PATH))?;
 
         let descriptors = setup_descriptor(device, &ubo_buffer, &texture)?;
 
-        let pipelines = prepare_pipelines(device, backend.render_pass, descriptors.layout)?;
+        let pipelines = prepare_pipelines(device, &backend, descriptors.layout)?;
 
         let target = VulkanExample {
             backend, descriptors, pipelines, camera,
             vertices, indices, texture,
             ubo_buffer, ubo_data,
-            lod_text_id: 0,
+            lod_text_id: None,
             is_toggle_event: true,
         };
         Ok(target)
@@ -84,13 +86,15 @@ impl vkbase::RenderWorkflow for VulkanExample {
 
         let lod_text = TextInfo {
             content: format!("Lod bias: {:1.2} (numpad +/- to change)", self.ubo_data.lod_bias),
-            scale: 14.0,
+            scale: 23.0,
             align: TextHAlign::Left,
             color: VkColor::WHITE,
             location: vk::Offset2D { x: 5, y: 140 },
+            pivot: (0.0, 0.0),
+            shadow: None,
             r#type: TextType::Dynamic { capacity: 40 },
         };
-        self.lod_text_id = self.backend.ui_renderer.add_text(lod_text)?;
+        self.lod_text_id = self.backend.add_text(lod_text)?;
 
         self.update_uniforms()?;
         self.record_commands(device, self.backend.dimension)?;
@@ -118,7 +122,7 @@ impl vkbase::RenderWorkflow for VulkanExample {
 
         let render_pass = setup_renderpass(device, new_chain)?;
         self.backend.swapchain_reload(device, new_chain, render_pass)?;
-        self.pipelines = prepare_pipelines(device, self.backend.render_pass, self.descriptors.layout)?;
+        self.pipelines = prepare_pipelines(device, &self.backend, self.descriptors.layout)?;
 
         self.record_commands(device, self.backend.dimension)?;
 
@@ -139,13 +143,13 @@ impl vkbase::RenderWorkflow for VulkanExample {
             if inputer.key.is_key_pressed(winit::VirtualKeyCode::Equals) && self.ubo_data.lod_bias < self.texture.mip_levels as f32 {
 
                 self.ubo_data.lod_bias += 0.05;
-                self.backend.ui_renderer.change_text(
+                self.backend.change_text(
                     format!("Lod bias: {:1.2} (numpad +/- to change)", self.ubo_data.lod_bias),
                     self.lod_text_id);
             } else if inputer.key.is_key_pressed(winit::VirtualKeyCode::Minus) && self.ubo_data.lod_bias > 0.0 {
 
                 self.ubo_data.lod_bias -= 0.05;
-                self.backend.ui_renderer.change_text(
+                self.backend.change_text(
                     format!("Lod bias: {:1.2} (numpad +/- to change)", self.ubo_data.lod_bias),
                     self.lod_text_id);
             }
@@ -155,7 +159,14 @@ impl vkbase::RenderWorkflow for VulkanExample {
 
         self.backend.update_fps_text(inputer);
 
-        FrameAction::Rendering
+        // the lod text may use new characters, which are added to font atlas before commands are recorded again.
+        self.backend.ui_action()
+    }
+
+    fn rerecord_commands(&mut self, device: &mut VkDevice) -> VkResult<()> {
+
+        self.backend.update_glyph_atlas(device)?;
+        self.record_commands(device, self.backend.dimension)
     }
 
     fn deinit(self, device: &mut VkDevice) -> VkResult<()> {
@@ -197,9 +208,9 @@ impl VulkanExample {
 
             let recorder: VkCmdRecorder<IGraphics> = VkCmdRecorder::new(&device.logic, command);
 
-            let render_pass_bi = RenderPassBI::new(self.backend.render_pass, self.backend.framebuffers[i])
+            let render_pass_bi = RenderPassBI::with_pass(self.backend.render_pass, self.backend.framebuffers[i])
                 .render_extent(dimension)
-                .set_clear_values(vkexamples::DEFAULT_CLEAR_VALUES.clone());
+                .set_clear_values(self.backend.clear_values());
 
             recorder.begin_record()?
                 .begin_render_pass(render_pass_bi)
@@ -211,7 +222,7 @@ impl VulkanExample {
                 .bind_index_buffer(self.indices.handle, vk::IndexType::UINT32, 0)
                 .draw_indexed(INDEX_DATA.len() as vkuint, 1, 0, 0, 0);
 
-            self.backend.ui_renderer.record_command(&recorder);
+            self.backend.record_ui_command(&recorder, i);
 
             recorder.end_render_pass()
                 .end_record()?;
@@ -257,34 +268,11 @@ fn setup_descriptor(device: &VkDevice, ubo_buffer: &VmaBuffer, texture: &Texture
         .add_descriptor(vk::DescriptorType::COMBINED_IMAGE_SAMPLER, 1)
         .build(device)?;
 
-    // in texture.vert.glsl:
-    // layout (set = 0, binding = 0) uniform UBO {
-    //     mat4 projection;
-    //     mat4 model;
-    //     vec4 viewPos;
-    //     float lodBias;
-    // } ubo;
-    let ubo_descriptor = vk::DescriptorSetLayoutBinding {
-        binding: 0,
-        descriptor_type: vk::DescriptorType::UNIFORM_BUFFER,
-        descriptor_count: 1,
-        stage_flags: vk::ShaderStageFlags::VERTEX,
-        p_immutable_samplers: ptr::null(),
-    };
-
-    // in texture.frag.glsl:
-    // layout (binding = 1) uniform sampler2D samplerColor;
-    let sampler_descriptor = vk::DescriptorSetLayoutBinding {
-        binding: 1,
-        descriptor_type: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
-        descriptor_count: 1,
-        stage_flags: vk::ShaderStageFlags::FRAGMENT,
-        p_immutable_samplers: ptr::null(),
-    };
-
-    let set_layout = DescriptorSetLayoutCI::new()
-        .add_binding(ubo_descriptor)
-        .add_binding(sampler_descriptor)
+    // the bindings are reflected from texture.vert.glsl and texture.frag.glsl:
+    // layout (set = 0, binding = 0) uniform UBO { ... } ubo;
+    // layout (set = 0, binding = 1) uniform sampler2D samplerColor;
+    let reflection = reflect_shaders()?;
+    let set_layout = DescriptorSetLayoutCI::from_reflection(&reflection, 0)
         .build(device)?;
 
     // Descriptor set.
@@ -324,7 +312,20 @@ fn setup_descriptor(device: &VkDevice, ubo_buffer: &VmaBuffer, texture: &Texture
     Ok(result)
 }
 
-fn setup_renderpass(device: &VkDevice, swapchain: &VkSwapchain) -> VkResult<vk::RenderPass> {
+/// Reflect the descriptor bindings used by both shaders of the pipeline.
+fn reflect_shaders() -> VkResult<ShaderReflection> {
+
+    use vkbase::ci::shader::ShaderModuleCI;
+
+    let mut shader_compiler = vkbase::utils::shaderc::VkShaderCompiler::new()?;
+    let vert_codes = shader_compiler.compile_from_path(Path::new(SHADER_VERTEX_PATH), shaderc::ShaderKind::Vertex, "[Vertex Shader]", "main")?;
+    let frag_codes = shader_compiler.compile_from_path(Path::new(SHADER_FRAGMENT_PATH), shaderc::ShaderKind::Fragment, "[Fragment Shader]", "main")?;
+
+    ShaderModuleCI::new(vert_codes).reflect()?
+        .merge(ShaderModuleCI::new(frag_codes).reflect()?)
+}
+
+fn setup_renderpass(device: &VkDevice, swapchain: &VkSwapchain) -> VkResult<RenderPass> {
 
     use vkbase::ci::pipeline::RenderPassCI;
     use vkbase::ci::pipeline::{AttachmentDescCI, SubpassDescCI, SubpassDependencyCI};
@@ -357,7 +358,7 @@ fn setup_renderpass(device: &VkDevice, swapchain: &VkSwapchain) -> VkResult<vk::
         .add_subpass(subpass_description)
         .add_dependency(dependency0)
         .add_dependency(dependency1)
-        .build(device)?;
+        .build_with_attachments(device)?;
 
     Ok(render_pass)
 }
@@ -368,7 +369,7 @@ struct PipelineStaff {
     layout: vk::PipelineLayout,
 }
 
-fn prepare_pipelines(device: &VkDevice, render_pass: vk::RenderPass, set_layout: vk::DescriptorSetLayout) -> VkResult<PipelineStaff> {
+fn prepare_pipelines(device: &VkDevice, backend: &VkExampleBackend, set_layout: vk::DescriptorSetLayout) -> VkResult<PipelineStaff> {
 
     use vkbase::ci::pipeline::*;
 
@@ -384,8 +385,8 @@ fn prepare_pipelines(device: &VkDevice, render_pass: vk::RenderPass, set_layout:
     let blend_state = ColorBlendSCI::new()
         .add_attachment(blend_attachment);
 
-    let depth_stencil_state = DepthStencilSCI::new()
-        .depth_test(true, true, vk::CompareOp::LESS_OR_EQUAL);
+    // follow the depth configuration of backend, so that the depth test matches the cleared depth value.
+    let depth_stencil_state = backend.depth_stencil_state();
 
     let dynamic_state = DynamicSCI::new()
         .add_dynamic(vk::DynamicState::VIEWPORT)
@@ -408,7 +409,7 @@ fn prepare_pipelines(device: &VkDevice, render_pass: vk::RenderPass, set_layout:
         .build(device)?;
 
     // Pipeline.
-    let mut pipeline_ci = GraphicsPipelineCI::new(render_pass, layout);
+    let mut pipeline_ci = GraphicsPipelineCI::new(backend.render_pass.handle, layout);
 
     let shaders = [
         ShaderStageCI::new(vk::ShaderStageFlags::VERTEX, vert_module),
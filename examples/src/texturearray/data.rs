@@ -239,7 +239,7 @@ impl TextureArray {
 
         use gli::GliTexture;
 
-        let tex_2d_array: gli::Texture2DArray = gli::load_ktx(texture_path)
+        let tex_2d_array: gli::Texture2DArray = gli::load_ktx(vkbase::utils::asset::local_asset_path(texture_path)?)
             .map_err(VkErrorKind::Gli)?;
 
         debug_assert!(!tex_2d_array.empty());
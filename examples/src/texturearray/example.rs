@@ -6,6 +6,7 @@ use std::path::Path;
 
 use vkbase::context::{VulkanContext, VkDevice, VkSwapchain};
 use vkbase::ci::VkObjectBuildableCI;
+use vkbase::ci::pipeline::RenderPass;
 use vkbase::ci::vma::VmaBuffer;
 use vkbase::{FlightCamera, FrameAction};
 use vkbase::{vkuint, vkptr, Vec3F};
@@ -60,7 +61,7 @@ impl VulkanExample {
 
         let descriptors = setup_descriptor(device, &ubo_buffer, &texture)?;
 
-        let pipelines = prepare_pipelines(device, backend.render_pass, descriptors.layout)?;
+        let pipelines = prepare_pipelines(device, backend.render_pass.handle, descriptors.layout)?;
 
         let target = VulkanExample {
             backend, descriptors, pipelines, camera,
@@ -104,7 +105,7 @@ impl vkbase::RenderWorkflow for VulkanExample {
 
         let render_pass = setup_renderpass(device, new_chain)?;
         self.backend.swapchain_reload(device, new_chain, render_pass)?;
-        self.pipelines = prepare_pipelines(device, self.backend.render_pass, self.descriptors.layout)?;
+        self.pipelines = prepare_pipelines(device, self.backend.render_pass.handle, self.descriptors.layout)?;
 
         self.record_commands(device, self.backend.dimension)?;
 
@@ -169,9 +170,9 @@ impl VulkanExample {
 
             let recorder: VkCmdRecorder<IGraphics> = VkCmdRecorder::new(&device.logic, command);
 
-            let render_pass_bi = RenderPassBI::new(self.backend.render_pass, self.backend.framebuffers[i])
+            let render_pass_bi = RenderPassBI::with_pass(self.backend.render_pass, self.backend.framebuffers[i])
                 .render_extent(dimension)
-                .set_clear_values(vkexamples::DEFAULT_CLEAR_VALUES.clone());
+                .set_clear_values(self.backend.clear_values());
 
             recorder.begin_record()?
                 .begin_render_pass(render_pass_bi)
@@ -183,7 +184,7 @@ impl VulkanExample {
                 .bind_index_buffer(self.indices.handle, vk::IndexType::UINT32, 0)
                 .draw_indexed(INDEX_DATA.len() as vkuint, self.texture.layer_count, 0, 0, 0);
 
-            self.backend.ui_renderer.record_command(&recorder);
+            self.backend.record_ui_command(&recorder, i);
 
             recorder.end_render_pass()
                 .end_record()?;
@@ -292,7 +293,7 @@ fn setup_descriptor(device: &VkDevice, ubo_buffer: &VmaBuffer, texture: &Texture
     Ok(result)
 }
 
-fn setup_renderpass(device: &VkDevice, swapchain: &VkSwapchain) -> VkResult<vk::RenderPass> {
+fn setup_renderpass(device: &VkDevice, swapchain: &VkSwapchain) -> VkResult<RenderPass> {
 
     use vkbase::ci::pipeline::RenderPassCI;
     use vkbase::ci::pipeline::{AttachmentDescCI, SubpassDescCI, SubpassDependencyCI};
@@ -325,7 +326,7 @@ fn setup_renderpass(device: &VkDevice, swapchain: &VkSwapchain) -> VkResult<vk::
         .add_subpass(subpass_description)
         .add_dependency(dependency0)
         .add_dependency(dependency1)
-        .build(device)?;
+        .build_with_attachments(device)?;
 
     Ok(render_pass)
 }
@@ -7,6 +7,7 @@ use std::path::Path;
 
 use vkbase::context::{VkDevice, VkSwapchain};
 use vkbase::ci::VkObjectBuildableCI;
+use vkbase::ci::pipeline::RenderPass;
 use vkbase::context::VulkanContext;
 use vkbase::{FlightCamera, FrameAction};
 use vkbase::{vkbytes, vkptr, Vec3F, Mat4F};
@@ -58,7 +59,7 @@ impl VulkanExample {
 
         let descriptors = setup_descriptor(device, &mut skybox)?;
 
-        let pipelines = prepare_pipelines(device, &skybox, backend.render_pass, descriptors.layout)?;
+        let pipelines = prepare_pipelines(device, &skybox, backend.render_pass.handle, descriptors.layout)?;
 
         let target = VulkanExample {
             backend, skybox, descriptors, pipelines, camera,
@@ -100,7 +101,7 @@ impl vkbase::RenderWorkflow for VulkanExample {
 
         let render_pass = setup_renderpass(device, new_chain)?;
         self.backend.swapchain_reload(device, new_chain, render_pass)?;
-        self.pipelines = prepare_pipelines(device, &self.skybox, self.backend.render_pass, self.descriptors.layout)?;
+        self.pipelines = prepare_pipelines(device, &self.skybox, self.backend.render_pass.handle, self.descriptors.layout)?;
 
         self.record_commands(device, self.backend.dimension)?;
 
@@ -161,9 +162,9 @@ impl VulkanExample {
 
             let recorder: VkCmdRecorder<IGraphics> = VkCmdRecorder::new(&device.logic, command);
 
-            let render_pass_bi = RenderPassBI::new(self.backend.render_pass, self.backend.framebuffers[i])
+            let render_pass_bi = RenderPassBI::with_pass(self.backend.render_pass, self.backend.framebuffers[i])
                 .render_extent(dimension)
-                .set_clear_values(vkexamples::DEFAULT_CLEAR_VALUES.clone());
+                .set_clear_values(self.backend.clear_values());
 
             recorder.begin_record()?
                 .begin_render_pass(render_pass_bi)
@@ -183,7 +184,7 @@ impl VulkanExample {
                 self.skybox.model.record_command(&recorder, &render_params);
             }
 
-            self.backend.ui_renderer.record_command(&recorder);
+            self.backend.record_ui_command(&recorder, i);
 
             recorder
                 .end_render_pass()
@@ -306,7 +307,7 @@ fn setup_descriptor(device: &VkDevice, skybox: &mut Skybox) -> VkResult<Descript
         .add_buffer(skybox.model.nodes.node_descriptor());
     // Binding 2: Object texture.
     let sampler_write_info = DescriptorImageSetWI::new(skybox.descriptor_set, 2, vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
-        .add_image(skybox.texture.descriptor());
+        .add_image(skybox.texture.descriptor);
 
     DescriptorSetsUpdateCI::new()
         .add_write(&ubo_write_info)
@@ -321,7 +322,7 @@ fn setup_descriptor(device: &VkDevice, skybox: &mut Skybox) -> VkResult<Descript
     Ok(descriptors)
 }
 
-fn setup_renderpass(device: &VkDevice, swapchain: &VkSwapchain) -> VkResult<vk::RenderPass> {
+fn setup_renderpass(device: &VkDevice, swapchain: &VkSwapchain) -> VkResult<RenderPass> {
 
     use vkbase::ci::pipeline::RenderPassCI;
     use vkbase::ci::pipeline::{AttachmentDescCI, SubpassDescCI, SubpassDependencyCI};
@@ -354,7 +355,7 @@ fn setup_renderpass(device: &VkDevice, swapchain: &VkSwapchain) -> VkResult<vk::
         .add_subpass(subpass_description)
         .add_dependency(dependency0)
         .add_dependency(dependency1)
-        .build(device)?;
+        .build_with_attachments(device)?;
 
     Ok(render_pass)
 }
@@ -416,6 +417,7 @@ fn prepare_pipelines(device: &VkDevice, skybox: &Skybox, render_pass: vk::Render
     pipeline_ci.set_shaders(&shaders);
 
     pipeline_ci.set_vertex_input(skybox.model.meshes.vertex_input.clone());
+    skybox.model.meshes.check_pipeline(&pipeline_ci)?;
     pipeline_ci.set_viewport(viewport_state);
     pipeline_ci.set_depth_stencil(depth_stencil_state);
     pipeline_ci.set_rasterization(rasterization_state);
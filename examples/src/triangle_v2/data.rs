@@ -5,8 +5,9 @@ use vkbase::context::VkDevice;
 use vkbase::ci::VkObjectBuildableCI;
 use vkbase::ci::buffer::BufferCI;
 use vkbase::ci::memory::MemoryAI;
+use vkbase::utils::owned::{OwnedBuffer, OwnedMemory};
 use vkbase::VkResult;
-use vkbase::{vkuint, vkptr, vkbytes, Mat4F, Vec3F};
+use vkbase::{vkuint, vkbytes, Mat4F, Vec3F};
 
 use std::mem;
 
@@ -69,10 +70,14 @@ pub struct IndexBuffer {
 }
 
 /// Uniform buffer block object.
+///
+/// The buffer and memory are destroyed automatically when this object is dropped.
 pub struct UniformBuffer {
-    pub memory: vk::DeviceMemory,
-    pub buffer: vk::Buffer,
+    pub buffer: OwnedBuffer,
+    pub memory: OwnedMemory,
     pub descriptor: vk::DescriptorBufferInfo,
+    /// false if the memory may be non-coherent, which requires explicit flush after each update.
+    pub is_coherent: bool,
 }
 
 pub struct DescriptorStaff {
@@ -196,9 +201,10 @@ fn allocate_buffer<D: Copy>(device: &VkDevice, data: &[D], buffer_usage: vk::Buf
     let staging_memory = MemoryAI::new(staging_requirement.size, staging_memory_index)
         .build(device)?;
 
-    let data_ptr = device.map_memory(staging_memory, 0, staging_requirement.size)? as vkptr<D>;
-    unsafe { data_ptr.copy_from_nonoverlapping(data.as_ptr(), data.len()); }
-    device.unmap_memory(staging_memory);
+    {
+        let mapped = device.map_scoped(staging_memory, 0, staging_requirement.size)?;
+        unsafe { mapped.as_ptr::<D>().copy_from_nonoverlapping(data.as_ptr(), data.len()); }
+    }
 
     device.bind_memory(staging_buffer, staging_memory, 0)?;
 
@@ -216,20 +222,17 @@ fn allocate_buffer<D: Copy>(device: &VkDevice, data: &[D], buffer_usage: vk::Buf
     Ok(result)
 }
 
-pub fn prepare_uniform(device: &VkDevice, dimension: vk::Extent2D) -> VkResult<UniformBuffer> {
+pub fn prepare_uniform(device: &VkDevice, dimension: vk::Extent2D, is_coherent: bool) -> VkResult<UniformBuffer> {
 
-    let (uniform_buffer, memory_requirement) = BufferCI::new(mem::size_of::<UboVS>() as vkbytes)
-        .usage(vk::BufferUsageFlags::UNIFORM_BUFFER)
-        .build(device)?;
+    let uniform_ci = BufferCI::new(mem::size_of::<UboVS>() as vkbytes)
+        .usage(vk::BufferUsageFlags::UNIFORM_BUFFER);
+    let uniform_buffer = OwnedBuffer::new(device, &uniform_ci)?;
 
-    let memory_index = device.get_memory_type(memory_requirement.memory_type_bits,
-        vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT);
-    let uniform_memory = MemoryAI::new(memory_requirement.size, memory_index)
-        .build(device)?;
-    device.bind_memory(uniform_buffer, uniform_memory, 0)?;
+    let uniform_memory = OwnedMemory::new(device, &MemoryAI::host_visible(device, uniform_buffer.requirement(), is_coherent))?;
+    uniform_buffer.bind(&uniform_memory, 0)?;
 
     let descriptor_info = vk::DescriptorBufferInfo {
-        buffer: uniform_buffer,
+        buffer: uniform_buffer.handle(),
         offset: 0,
         range: mem::size_of::<UboVS>() as vkbytes,
     };
@@ -238,6 +241,7 @@ pub fn prepare_uniform(device: &VkDevice, dimension: vk::Extent2D) -> VkResult<U
         buffer: uniform_buffer,
         memory: uniform_memory,
         descriptor: descriptor_info,
+        is_coherent,
     };
 
     update_uniform_buffers(device, dimension, &result)?;
@@ -256,10 +260,13 @@ fn update_uniform_buffers(device: &VkDevice, dimension: vk::Extent2D, uniforms:
     };
 
     // Map uniform buffer and update it.
-    let data_ptr = device.map_memory(uniforms.memory, 0, mem::size_of::<UboVS>() as _)? as vkptr<UboVS>;
-    unsafe { data_ptr.copy_from_nonoverlapping(&ubo_data, 1) }
+    let mapped = device.map_scoped(uniforms.memory.handle(), 0, mem::size_of::<UboVS>() as _)?;
+    unsafe { mapped.as_ptr::<UboVS>().copy_from_nonoverlapping(&ubo_data, 1) }
 
-    device.unmap_memory(uniforms.memory);
+    // the writes to non-coherent memory must be flushed before unmapping.
+    if !uniforms.is_coherent {
+        mapped.flush()?;
+    }
 
     Ok(())
 }
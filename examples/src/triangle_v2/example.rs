@@ -6,6 +6,7 @@ use std::path::Path;
 
 use vkbase::context::{VkDevice, VkSwapchain};
 use vkbase::ci::VkObjectBuildableCI;
+use vkbase::ci::pipeline::RenderPass;
 use vkbase::VkResult;
 use vkbase::FrameAction;
 
@@ -40,11 +41,12 @@ impl VulkanExample {
         let backend_res = VkExampleBackend::new(device, swapchain, render_pass)?;
 
         let (vertex_buffer, index_buffer) = super::data::prepare_vertices(device)?;
-        let uniform_buffer = super::data::prepare_uniform(device, dimension)?;
+        // use coherent memory for uniform buffer. Set it to false to test explicit flush of non-coherent memory.
+        let uniform_buffer = super::data::prepare_uniform(device, dimension, true)?;
 
         let descriptors = setup_descriptor(device, &uniform_buffer)?;
 
-        let pipeline = prepare_pipelines(device, backend_res.render_pass, descriptors.pipeline_layout)?;
+        let pipeline = prepare_pipelines(device, backend_res.render_pass.handle, descriptors.pipeline_layout)?;
 
         let target = VulkanExample {
             backend: backend_res, descriptors, pipeline,
@@ -82,7 +84,7 @@ impl vkbase::RenderWorkflow for VulkanExample {
 
         let render_pass = setup_renderpass(device, new_chain)?;
         self.backend.swapchain_reload(device, new_chain, render_pass)?;
-        self.pipeline = prepare_pipelines(device, self.backend.render_pass, self.descriptors.pipeline_layout)?;
+        self.pipeline = prepare_pipelines(device, self.backend.render_pass.handle, self.descriptors.pipeline_layout)?;
 
         self.record_commands(device, self.backend.dimension)?;
 
@@ -112,8 +114,7 @@ impl vkbase::RenderWorkflow for VulkanExample {
         device.discard(self.index_buffer.buffer);
         device.discard(self.index_buffer.memory);
 
-        device.discard(self.uniform_buffer.buffer);
-        device.discard(self.uniform_buffer.memory);
+        // the uniform buffer is destroyed when this example is dropped.
 
         self.backend.discard_by(device)
     }
@@ -146,7 +147,7 @@ impl VulkanExample {
 
             let recorder: VkCmdRecorder<IGraphics> = VkCmdRecorder::new(&device.logic, command);
 
-            let render_pass_bi = RenderPassBI::new(self.backend.render_pass, self.backend.framebuffers[i])
+            let render_pass_bi = RenderPassBI::with_pass(self.backend.render_pass, self.backend.framebuffers[i])
                 .render_extent(dimension)
                 .set_clear_values(clear_values.clone());
 
@@ -213,7 +214,7 @@ fn setup_descriptor(device: &VkDevice, uniforms: &UniformBuffer) -> VkResult<Des
     Ok(result)
 }
 
-fn setup_renderpass(device: &VkDevice, swapchain: &VkSwapchain) -> VkResult<vk::RenderPass> {
+fn setup_renderpass(device: &VkDevice, swapchain: &VkSwapchain) -> VkResult<RenderPass> {
 
     use vkbase::ci::pipeline::RenderPassCI;
     use vkbase::ci::pipeline::{AttachmentDescCI, SubpassDescCI, SubpassDependencyCI};
@@ -246,7 +247,7 @@ fn setup_renderpass(device: &VkDevice, swapchain: &VkSwapchain) -> VkResult<vk::
         .add_subpass(subpass_description)
         .add_dependency(dependency0)
         .add_dependency(dependency1)
-        .build(device)?;
+        .build_with_attachments(device)?;
 
     Ok(render_pass)
 }